@@ -17,11 +17,14 @@ pub fn update(ws_dir: &Path, metadata: &Metadata) -> Result<()> {
     let agents_path = ws_dir.join("AGENTS.md");
     let section = build_marked_section(metadata);
 
-    let content = if agents_path.exists() {
-        let existing = fs::read_to_string(&agents_path).context("reading existing AGENTS.md")?;
-        replace_marked_section(&existing, &section)
+    let previous = if agents_path.exists() {
+        Some(fs::read_to_string(&agents_path).context("reading existing AGENTS.md")?)
     } else {
-        build_initial_file(metadata, &section)
+        None
+    };
+    let content = match &previous {
+        Some(existing) => replace_marked_section(existing, &section),
+        None => build_initial_file(metadata, &section),
     };
 
     // Atomic write via tempfile + rename
@@ -32,7 +35,7 @@ pub fn update(ws_dir: &Path, metadata: &Metadata) -> Result<()> {
     tmp.persist(&agents_path)
         .context("renaming temp file to AGENTS.md")?;
 
-    ensure_symlink(ws_dir)?;
+    ensure_claude_md(ws_dir, previous.as_deref(), &content)?;
     install_skill(ws_dir)?;
 
     Ok(())
@@ -206,39 +209,73 @@ fn replace_marked_section(existing: &str, new_section: &str) -> String {
     }
 }
 
-fn ensure_symlink(ws_dir: &Path) -> Result<()> {
+/// Points CLAUDE.md at AGENTS.md: a symlink, or where one can't be made
+/// (Windows without Developer Mode) a copy kept in step with AGENTS.md. A
+/// CLAUDE.md of the user's own, a regular file that isn't wsp's copy, is
+/// left alone. `previous` is AGENTS.md before this update.
+fn ensure_claude_md(ws_dir: &Path, previous: Option<&str>, agents: &str) -> Result<()> {
     let link_path = ws_dir.join("CLAUDE.md");
 
     match fs::symlink_metadata(&link_path) {
-        Ok(meta) => {
-            if meta.file_type().is_symlink() {
-                // Skip if already pointing to AGENTS.md
-                if fs::read_link(&link_path).ok().as_deref() == Some(Path::new("AGENTS.md")) {
-                    return Ok(());
-                }
-                fs::remove_file(&link_path).context("removing stale CLAUDE.md symlink")?;
-                symlink_file("AGENTS.md", &link_path).context("creating CLAUDE.md symlink")?;
+        Ok(meta) if meta.file_type().is_symlink() => {
+            // Skip if already pointing to AGENTS.md
+            if fs::read_link(&link_path).ok().as_deref() == Some(Path::new("AGENTS.md")) {
+                return Ok(());
             }
-            // Regular file — leave it alone
+            fs::remove_file(&link_path).context("removing stale CLAUDE.md symlink")?;
+            link_claude_md(&link_path, agents)
         }
-        Err(_) => {
-            // Path doesn't exist. (Broken symlinks are handled above since
-            // symlink_metadata succeeds for broken symlinks and reports is_symlink=true.)
-            symlink_file("AGENTS.md", &link_path).context("creating CLAUDE.md symlink")?;
+        Ok(_) => {
+            // A copy of the previous AGENTS.md is wsp's: refresh it.
+            let is_copy =
+                previous.is_some_and(|p| fs::read_to_string(&link_path).is_ok_and(|c| c == p));
+            if is_copy {
+                fs::write(&link_path, agents).context("updating CLAUDE.md")?;
+            }
+            Ok(())
         }
+        // Path doesn't exist. (Broken symlinks are handled above since
+        // symlink_metadata succeeds for broken symlinks and reports is_symlink=true.)
+        Err(_) => link_claude_md(&link_path, agents),
     }
+}
 
-    Ok(())
+fn link_claude_md(link_path: &Path, agents: &str) -> Result<()> {
+    if crate::util::symlink(Path::new("AGENTS.md"), link_path).is_ok() {
+        return Ok(());
+    }
+    fs::write(link_path, agents).context("writing CLAUDE.md as a copy of AGENTS.md")
 }
 
-#[cfg(unix)]
-fn symlink_file<P: AsRef<Path>, Q: AsRef<Path>>(original: P, link: Q) -> std::io::Result<()> {
-    std::os::unix::fs::symlink(original, link)
+/// Whether CLAUDE.md is wsp's: a symlink to AGENTS.md, or a copy of it
+/// where symlinks aren't available.
+pub fn is_claude_md_managed(ws_dir: &Path) -> bool {
+    let path = ws_dir.join("CLAUDE.md");
+    match fs::symlink_metadata(&path) {
+        Ok(meta) if meta.file_type().is_symlink() => {
+            fs::read_link(&path).is_ok_and(|t| t == Path::new("AGENTS.md"))
+        }
+        Ok(_) => match (
+            fs::read_to_string(&path),
+            fs::read_to_string(ws_dir.join("AGENTS.md")),
+        ) {
+            (Ok(claude), Ok(agents)) => claude == agents,
+            _ => false,
+        },
+        Err(_) => false,
+    }
 }
 
-#[cfg(windows)]
-fn symlink_file<P: AsRef<Path>, Q: AsRef<Path>>(original: P, link: Q) -> std::io::Result<()> {
-    std::os::windows::fs::symlink_file(original, link)
+/// Replaces CLAUDE.md with wsp's link to (or copy of) AGENTS.md.
+pub fn relink_claude_md(ws_dir: &Path) -> Result<()> {
+    let link_path = ws_dir.join("CLAUDE.md");
+    let agents = fs::read_to_string(ws_dir.join("AGENTS.md")).context("reading AGENTS.md")?;
+    match fs::remove_file(&link_path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e).context("removing CLAUDE.md"),
+    }
+    link_claude_md(&link_path, &agents)
 }
 
 fn install_skill(ws_dir: &Path) -> Result<()> {
@@ -261,7 +298,6 @@ fn install_skill(ws_dir: &Path) -> Result<()> {
 
 #[cfg(test)]
 mod tests {
-    use super::symlink_file;
     use super::*;
     use std::collections::BTreeMap;
 
@@ -297,7 +333,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: BTreeMap::new(),
         }
     }
 
@@ -595,7 +635,7 @@ mod tests {
         let ws_dir = tmp.path();
 
         // Create a broken symlink
-        symlink_file("nonexistent-target", ws_dir.join("CLAUDE.md")).unwrap();
+        crate::util::symlink(Path::new("nonexistent-target"), &ws_dir.join("CLAUDE.md")).unwrap();
 
         let meta = make_metadata("ws", "ws", &[]);
         update(ws_dir, &meta).unwrap();
@@ -625,6 +665,42 @@ mod tests {
         assert_eq!(content, "# My custom CLAUDE.md\n");
     }
 
+    #[test]
+    fn test_claude_md_copy_kept_in_step() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws_dir = tmp.path();
+        let meta = make_metadata("ws", "ws", &[("github.com/acme/api", None)]);
+        update(ws_dir, &meta).unwrap();
+
+        // Where symlinks aren't available, CLAUDE.md is a copy.
+        let claude_path = ws_dir.join("CLAUDE.md");
+        fs::remove_file(&claude_path).unwrap();
+        fs::copy(ws_dir.join("AGENTS.md"), &claude_path).unwrap();
+        assert!(is_claude_md_managed(ws_dir));
+
+        let meta2 = make_metadata(
+            "ws",
+            "ws",
+            &[("github.com/acme/api", None), ("github.com/acme/web", None)],
+        );
+        update(ws_dir, &meta2).unwrap();
+
+        let claude = fs::read_to_string(&claude_path).unwrap();
+        assert!(claude.contains("| github.com/acme/web | web |"));
+        assert!(
+            !fs::symlink_metadata(&claude_path)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert!(is_claude_md_managed(ws_dir));
+
+        fs::write(&claude_path, "# Mine\n").unwrap();
+        assert!(!is_claude_md_managed(ws_dir));
+        relink_claude_md(ws_dir).unwrap();
+        assert!(is_claude_md_managed(ws_dir));
+    }
+
     #[test]
     fn test_empty_repos_generates_valid_table() {
         let tmp = tempfile::tempdir().unwrap();
@@ -0,0 +1,114 @@
+//! API tokens for GitHub and GitLab, kept in the OS keyring (macOS
+//! Keychain, Windows Credential Manager, the Secret Service on Linux) under
+//! service "wsp", one entry per host. `wsp setup auth login <host>` stores
+//! them; [`crate::forge::Forge::command`] hands the right one to gh or glab.
+//!
+//! Built without the `keyring` feature, there is nowhere to keep them:
+//! storing fails and no host has a token.
+
+#[cfg(feature = "keyring")]
+use anyhow::anyhow;
+use anyhow::{Result, bail};
+
+#[cfg(feature = "keyring")]
+const SERVICE: &str = "wsp";
+
+#[cfg(feature = "keyring")]
+fn entry(host: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(SERVICE, host)
+        .map_err(|e| anyhow!("opening keyring entry for {}: {}", host, e))
+}
+
+#[cfg(not(feature = "keyring"))]
+const NO_KEYRING: &str = "this wsp was built without keyring support (the `keyring` feature)";
+
+/// Saves `token` for `host`, replacing any token already there.
+#[cfg(feature = "keyring")]
+pub fn store(host: &str, token: &str) -> Result<()> {
+    validate_host(host)?;
+    entry(host)?
+        .set_password(token)
+        .map_err(|e| anyhow!("saving token for {} to the keyring: {}", host, e))
+}
+
+#[cfg(not(feature = "keyring"))]
+pub fn store(host: &str, _token: &str) -> Result<()> {
+    validate_host(host)?;
+    bail!("{}", NO_KEYRING)
+}
+
+/// The token stored for `host`. A missing entry or an unavailable keyring
+/// both mean no token: the forge CLI then falls back to its own login.
+#[cfg(feature = "keyring")]
+pub fn token(host: &str) -> Option<String> {
+    match entry(host).ok()?.get_password() {
+        Ok(token) => Some(token),
+        Err(keyring::Error::NoEntry) => None,
+        Err(e) => {
+            tracing::debug!("reading keyring token for {}: {}", host, e);
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "keyring"))]
+pub fn token(_host: &str) -> Option<String> {
+    None
+}
+
+/// Removes the token for `host`. Returns whether there was one.
+#[cfg(feature = "keyring")]
+pub fn delete(host: &str) -> Result<bool> {
+    validate_host(host)?;
+    match entry(host)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => bail!("removing token for {} from the keyring: {}", host, e),
+    }
+}
+
+#[cfg(not(feature = "keyring"))]
+pub fn delete(host: &str) -> Result<bool> {
+    validate_host(host)?;
+    bail!("{}", NO_KEYRING)
+}
+
+/// A bare host name, optionally with a port: what repo identities start
+/// with. Catches `https://github.com` and `github.com/acme` early.
+pub fn validate_host(host: &str) -> Result<()> {
+    if host.is_empty() {
+        bail!("host cannot be empty");
+    }
+    if host.contains("://") || host.contains('/') {
+        bail!(
+            "invalid host {:?}: give the host name alone, like github.com",
+            host
+        );
+    }
+    if host.starts_with('-') || host.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        bail!("invalid host {:?}", host);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_host() {
+        let cases = vec![
+            ("github.com", true),
+            ("git.acme.com:8443", true),
+            ("gitlab.example.org", true),
+            ("", false),
+            ("https://github.com", false),
+            ("github.com/acme", false),
+            ("-github.com", false),
+            ("git hub.com", false),
+        ];
+        for (host, ok) in cases {
+            assert_eq!(validate_host(host).is_ok(), ok, "{:?}", host);
+        }
+    }
+}
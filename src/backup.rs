@@ -0,0 +1,495 @@
+//! `wsp setup backup` and `wsp setup restore`: wsp's own state —
+//! config.yaml, templates, and each workspace's `.wsp.yaml` — in one
+//! `.tar.gz`, without any git data. Restoring writes the files back and
+//! recreates the workspaces, cloning their repos now or, with `--no-clone`,
+//! leaving them pending for `wsp repair`.
+//!
+//! Archive layout:
+//!
+//! ```text
+//! wsp-backup.yaml                 manifest: version, date, workspace dirs
+//! config.yaml
+//! templates/<name>.yaml
+//! workspaces/<name>/.wsp.yaml
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Config, Paths};
+use crate::error::{self, ErrorKind};
+use crate::filelock;
+use crate::git;
+use crate::workspace::{self, METADATA_FILE};
+
+pub const MANIFEST_FILE: &str = "wsp-backup.yaml";
+
+/// Bumped when the archive layout changes; older wsp refuses newer archives.
+pub const CURRENT_BACKUP_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    pub created: DateTime<Utc>,
+    /// Workspace name → the directory it lived in.
+    #[serde(default)]
+    pub workspaces: BTreeMap<String, String>,
+}
+
+/// Writes a backup of `paths`' state to `out`.
+pub fn create(paths: &Paths, out: &Path) -> Result<Manifest> {
+    if !paths.config_path.exists() {
+        bail!(
+            "nothing to back up: {} doesn't exist",
+            paths.config_path.display()
+        );
+    }
+    let stage = tempfile::tempdir().context("creating staging dir")?;
+    fs::copy(&paths.config_path, stage.path().join("config.yaml"))
+        .context("copying config.yaml")?;
+
+    if paths.templates_dir.is_dir() {
+        let dest = stage.path().join("templates");
+        fs::create_dir_all(&dest)?;
+        for entry in fs::read_dir(&paths.templates_dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "yaml")
+                && let Some(name) = path.file_name()
+            {
+                fs::copy(&path, dest.join(name))
+                    .with_context(|| format!("copying {}", path.display()))?;
+            }
+        }
+    }
+
+    let mut workspaces = BTreeMap::new();
+    for name in workspace::list_all(paths)? {
+        let ws_dir = paths.workspace_dir(&name);
+        let dest = stage.path().join("workspaces").join(&name);
+        fs::create_dir_all(&dest)?;
+        fs::copy(ws_dir.join(METADATA_FILE), dest.join(METADATA_FILE))
+            .with_context(|| format!("copying metadata of workspace {:?}", name))?;
+        let root = ws_dir.parent().unwrap_or(&paths.workspaces_dir);
+        workspaces.insert(name, root.display().to_string());
+    }
+
+    let manifest = Manifest {
+        version: CURRENT_BACKUP_VERSION,
+        created: Utc::now(),
+        workspaces,
+    };
+    fs::write(
+        stage.path().join(MANIFEST_FILE),
+        serde_yaml_ng::to_string(&manifest)?,
+    )?;
+
+    let out_str = out.to_str().context("backup path is not valid UTF-8")?;
+    let stage_str = stage
+        .path()
+        .to_str()
+        .context("temp path is not valid UTF-8")?;
+    run_tar(&["-czf", out_str, "-C", stage_str, "."])?;
+    Ok(manifest)
+}
+
+/// An archive extracted to a temp dir, for [`restore_files`] and
+/// [`restore_workspace`].
+pub struct Unpacked {
+    dir: tempfile::TempDir,
+    pub manifest: Manifest,
+}
+
+/// Extracts a backup and checks it is one this wsp can read.
+pub fn unpack(archive: &Path) -> Result<Unpacked> {
+    let archive_str = archive.to_str().context("backup path is not valid UTF-8")?;
+    let dir = tempfile::tempdir().context("creating staging dir")?;
+    let dir_str = dir
+        .path()
+        .to_str()
+        .context("temp path is not valid UTF-8")?;
+    run_tar(&["-xzf", archive_str, "-C", dir_str])?;
+
+    let data = fs::read_to_string(dir.path().join(MANIFEST_FILE))
+        .with_context(|| format!("{} is not a wsp backup", archive.display()))?;
+    let manifest: Manifest = serde_yaml_ng::from_str(&data)
+        .with_context(|| format!("reading {} in {}", MANIFEST_FILE, archive.display()))?;
+    if manifest.version > CURRENT_BACKUP_VERSION {
+        bail!(
+            "{} has version {}, but this wsp only understands up to version {}; upgrade wsp to restore it",
+            archive.display(),
+            manifest.version,
+            CURRENT_BACKUP_VERSION
+        );
+    }
+    Ok(Unpacked { dir, manifest })
+}
+
+/// Writes the backed-up config.yaml and templates into `paths`, replacing
+/// what's there. An existing config.yaml is first copied to
+/// `config.yaml.bak` next to it. Returns the number of templates and the
+/// copy, if one was made.
+pub fn restore_files(paths: &Paths, unpacked: &Unpacked) -> Result<(usize, Option<PathBuf>)> {
+    let restored = Config::load_from(&unpacked.dir.path().join("config.yaml"))
+        .context("reading the backed-up config.yaml")?;
+    let mut saved = None;
+    if paths.config_path.exists() {
+        let bak = paths.config_path.with_extension("yaml.bak");
+        fs::copy(&paths.config_path, &bak)
+            .with_context(|| format!("saving the current config to {}", bak.display()))?;
+        saved = Some(bak);
+    }
+    filelock::with_config(&paths.config_path, |cfg| {
+        *cfg = restored;
+        Ok(())
+    })
+    .context("restoring config.yaml")?;
+
+    let templates = unpacked.dir.path().join("templates");
+    let mut count = 0;
+    if templates.is_dir() {
+        fs::create_dir_all(&paths.templates_dir)?;
+        for entry in fs::read_dir(&templates)? {
+            let path = entry?.path();
+            if let Some(name) = path.file_name() {
+                fs::copy(&path, paths.templates_dir.join(name))
+                    .with_context(|| format!("restoring template {}", path.display()))?;
+                count += 1;
+            }
+        }
+    }
+    Ok((count, saved))
+}
+
+/// Where a backed-up workspace is restored: the directory it lived in if
+/// the restored config still puts workspaces there, else the default one.
+pub fn restore_root(cfg: &Config, default_dir: &Path, was: &Path) -> PathBuf {
+    let default_dir = cfg
+        .workspaces_dir
+        .as_deref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| default_dir.to_path_buf());
+    let known = cfg
+        .template_workspaces_dirs
+        .iter()
+        .flat_map(|m| m.values())
+        .any(|d| Path::new(d) == was);
+    if was == default_dir || known {
+        was.to_path_buf()
+    } else {
+        default_dir
+    }
+}
+
+/// Upstream URL of each repo in backed-up workspace `name`: its own, else
+/// the one registered in `cfg`. Repos with neither are left out.
+pub fn upstream_urls(
+    cfg: &Config,
+    unpacked: &Unpacked,
+    name: &str,
+) -> Result<BTreeMap<String, String>> {
+    workspace::validate_name(name)?;
+    let meta = workspace::load_metadata(&unpacked.dir.path().join("workspaces").join(name))?;
+    Ok(meta
+        .repos
+        .into_iter()
+        .filter_map(|(identity, repo_ref)| {
+            let url = repo_ref
+                .and_then(|r| r.url)
+                .or_else(|| cfg.upstream_url(&identity).map(String::from))?;
+            Some((identity, url))
+        })
+        .collect())
+}
+
+/// Why a restored repo is pending until it's cloned.
+pub const NOT_CLONED: &str = "not cloned since the restore";
+
+/// Recreates workspace `name` in `root` from its backed-up metadata. Every
+/// repo is recorded as pending first, keeping its directory name, so one
+/// not cloned now can be added back later with `wsp repair`. With `clone`,
+/// each repo is then cloned from its mirror; a branch already pushed to
+/// origin is checked out tracking it, so work pushed before the backup picks
+/// up where it left off. A repo that fails is reported, not fatal, and stays
+/// pending. Returns the repos left pending with why.
+pub fn restore_workspace(
+    paths: &Paths,
+    cfg: &Config,
+    unpacked: &Unpacked,
+    name: &str,
+    root: &Path,
+    clone: bool,
+) -> Result<BTreeMap<String, String>> {
+    let urls = upstream_urls(cfg, unpacked, name)?;
+    let mut meta = workspace::load_metadata(&unpacked.dir.path().join("workspaces").join(name))?;
+    let repos = std::mem::take(&mut meta.repos);
+    meta.pending = repos
+        .into_keys()
+        .map(|identity| (identity, NOT_CLONED.to_string()))
+        .collect();
+
+    let ws_dir = workspace::dir(root, name);
+    if ws_dir.exists() {
+        return Err(error::tag(
+            ErrorKind::Conflict,
+            anyhow::anyhow!("{} already exists", ws_dir.display()),
+        ));
+    }
+    fs::create_dir_all(&ws_dir)?;
+    workspace::save_metadata(&ws_dir, &meta)?;
+    if !clone {
+        return Ok(meta.pending);
+    }
+
+    let mut failed = BTreeMap::new();
+    for identity in meta.pending.into_keys() {
+        let added = match urls.get(&identity) {
+            Some(url) => {
+                let refs = BTreeMap::from([(identity.clone(), String::new())]);
+                let urls = BTreeMap::from([(identity.clone(), url.clone())]);
+                workspace::add_repos(&paths.mirrors_dir, &ws_dir, &refs, &urls, &meta.branches)
+                    .and_then(|()| track_pushed_branch(&ws_dir, &identity))
+            }
+            None => Err(anyhow::anyhow!("not registered")),
+        };
+        if let Err(e) = added {
+            let why = format!("{:#}", e);
+            filelock::with_metadata(&ws_dir, |m| {
+                if m.pending.contains_key(&identity) {
+                    m.pending.insert(identity.clone(), why.clone());
+                }
+                Ok(())
+            })?;
+            failed.insert(identity, why);
+        }
+    }
+    Ok(failed)
+}
+
+/// Points a freshly restored clone's branch at `origin/<branch>`, tracking
+/// it, if the branch was pushed. The clone has no work of its own yet.
+fn track_pushed_branch(ws_dir: &Path, identity: &str) -> Result<()> {
+    let meta = workspace::load_metadata(ws_dir)?;
+    let branch = meta.branch_for(identity);
+    let dir = ws_dir.join(meta.dir_name(identity)?);
+    if git::remote_branch_exists(&dir, branch) {
+        let upstream = format!("origin/{}", branch);
+        git::run(
+            Some(&dir),
+            &["checkout", "-B", branch, "--track", &upstream],
+        )?;
+    }
+    Ok(())
+}
+
+fn run_tar(args: &[&str]) -> Result<()> {
+    let out = std::process::Command::new("tar")
+        .args(args)
+        .output()
+        .context("running tar")?;
+    if !out.status.success() {
+        bail!(
+            "tar {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore_root() {
+        let cfg: Config = serde_yaml_ng::from_str(
+            "workspaces_dir: /ws\ntemplate_workspaces_dirs:\n  oss: /oss\n",
+        )
+        .unwrap();
+        let cases = vec![
+            ("/ws", "/ws"),
+            ("/oss", "/oss"),
+            ("/home/old/dev/workspaces", "/ws"),
+        ];
+        for (was, want) in cases {
+            let got = restore_root(&cfg, Path::new("/default"), Path::new(was));
+            assert_eq!(got, PathBuf::from(want), "{}", was);
+        }
+        let got = restore_root(&Config::default(), Path::new("/default"), Path::new("/ws"));
+        assert_eq!(got, PathBuf::from("/default"));
+    }
+
+    #[test]
+    fn test_backup_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let paths = Paths::from_dirs(&tmp.path().join("data"), &tmp.path().join("ws"));
+        fs::create_dir_all(&paths.templates_dir).unwrap();
+        fs::write(&paths.config_path, "branch_prefix: me\n").unwrap();
+        fs::write(paths.templates_dir.join("backend.yaml"), "repos: []\n").unwrap();
+        let ws_dir = workspace::dir(&paths.workspaces_dir, "demo");
+        fs::create_dir_all(&ws_dir).unwrap();
+        fs::write(
+            ws_dir.join(METADATA_FILE),
+            "name: demo\nbranch: me/demo\nrepos: {}\ncreated: 2026-01-01T00:00:00Z\ndescription: billing\n",
+        )
+        .unwrap();
+
+        let archive = tmp.path().join("wsp.tar.gz");
+        let manifest = create(&paths, &archive).unwrap();
+        assert_eq!(
+            manifest.workspaces.get("demo").map(String::as_str),
+            paths.workspaces_dir.to_str()
+        );
+
+        let other = Paths::from_dirs(&tmp.path().join("new"), &tmp.path().join("new-ws"));
+        let unpacked = unpack(&archive).unwrap();
+        assert_eq!(restore_files(&other, &unpacked).unwrap(), (1, None));
+        let cfg = Config::load_from(&other.config_path).unwrap();
+        assert_eq!(cfg.branch_prefix.as_deref(), Some("me"));
+        assert!(other.templates_dir.join("backend.yaml").exists());
+
+        let root = restore_root(&cfg, &other.workspaces_dir, Path::new("/elsewhere"));
+        let failed = restore_workspace(&other, &cfg, &unpacked, "demo", &root, true).unwrap();
+        assert!(failed.is_empty(), "{:?}", failed);
+        let meta = workspace::load_metadata(&workspace::dir(&root, "demo")).unwrap();
+        assert_eq!(meta.branch, "me/demo");
+        assert_eq!(meta.description.as_deref(), Some("billing"));
+        assert!(restore_workspace(&other, &cfg, &unpacked, "demo", &root, true).is_err());
+    }
+
+    #[test]
+    fn test_restore_files_keeps_previous_config() {
+        let tmp = tempfile::tempdir().unwrap();
+        let paths = Paths::from_dirs(&tmp.path().join("data"), &tmp.path().join("ws"));
+        fs::create_dir_all(paths.config_dir()).unwrap();
+        fs::write(&paths.config_path, "branch_prefix: me\n").unwrap();
+        let archive = tmp.path().join("wsp.tar.gz");
+        create(&paths, &archive).unwrap();
+        fs::write(&paths.config_path, "branch_prefix: mine\n").unwrap();
+
+        let unpacked = unpack(&archive).unwrap();
+        let (_, saved) = restore_files(&paths, &unpacked).unwrap();
+        let bak = paths.config_dir().join("config.yaml.bak");
+        assert_eq!(saved.as_deref(), Some(bak.as_path()));
+        assert_eq!(fs::read_to_string(&bak).unwrap(), "branch_prefix: mine\n");
+        let cfg = Config::load_from(&paths.config_path).unwrap();
+        assert_eq!(cfg.branch_prefix.as_deref(), Some("me"));
+    }
+
+    #[test]
+    fn test_restore_tracks_pushed_branch() {
+        let (_clone, source, _clone_tmp, _source_tmp) = crate::testutil::setup_clone_repo();
+        git::run(Some(&source), &["branch", "me/demo"]).unwrap();
+        let url = source.to_str().unwrap().to_string();
+        let identity = "test.local/user/test-repo".to_string();
+
+        let tmp = tempfile::tempdir().unwrap();
+        let paths = Paths::from_dirs(&tmp.path().join("data"), &tmp.path().join("ws"));
+        let parsed = crate::giturl::Parsed::from_identity(&identity).unwrap();
+        crate::mirror::clone(&paths.mirrors_dir, &parsed, &url).unwrap();
+        crate::mirror::fetch(&paths.mirrors_dir, &parsed).unwrap();
+        fs::create_dir_all(&paths.templates_dir).unwrap();
+        fs::write(
+            &paths.config_path,
+            format!(
+                "repos:\n  {}:\n    url: {}\n    added: 2026-01-01T00:00:00Z\n",
+                identity, url
+            ),
+        )
+        .unwrap();
+        let ws_dir = workspace::dir(&paths.workspaces_dir, "demo");
+        fs::create_dir_all(&ws_dir).unwrap();
+        fs::write(
+            ws_dir.join(METADATA_FILE),
+            format!(
+                "name: demo\nbranch: me/demo\nrepos:\n  {0}:\ndirs:\n  {0}: mine\ncreated: 2026-01-01T00:00:00Z\n",
+                identity
+            ),
+        )
+        .unwrap();
+        let archive = tmp.path().join("wsp.tar.gz");
+        create(&paths, &archive).unwrap();
+        fs::remove_dir_all(&ws_dir).unwrap();
+
+        let unpacked = unpack(&archive).unwrap();
+        let cfg = Config::load_from(&paths.config_path).unwrap();
+        let urls = upstream_urls(&cfg, &unpacked, "demo").unwrap();
+        assert_eq!(urls.get(&identity), Some(&url));
+        let failed =
+            restore_workspace(&paths, &cfg, &unpacked, "demo", &paths.workspaces_dir, true)
+                .unwrap();
+        assert!(failed.is_empty(), "{:?}", failed);
+        let meta = workspace::load_metadata(&ws_dir).unwrap();
+        assert!(meta.pending.is_empty(), "{:?}", meta.pending);
+        assert_eq!(meta.dir_name(&identity).unwrap(), "mine");
+        let upstream = git::run(
+            Some(&ws_dir.join("mine")),
+            &["rev-parse", "--abbrev-ref", "@{upstream}"],
+        )
+        .unwrap();
+        assert_eq!(upstream.trim(), "origin/me/demo");
+
+        // Without cloning, the repo waits in pending, and cloning it later
+        // (as `wsp repair` does) puts it back in its own directory.
+        let lazy = tmp.path().join("lazy");
+        let left = restore_workspace(&paths, &cfg, &unpacked, "demo", &lazy, false).unwrap();
+        assert_eq!(left.get(&identity).map(String::as_str), Some(NOT_CLONED));
+        let lazy_dir = workspace::dir(&lazy, "demo");
+        let meta = workspace::load_metadata(&lazy_dir).unwrap();
+        assert!(meta.repos.is_empty());
+        assert!(meta.pending.contains_key(&identity));
+        assert!(!lazy_dir.join("mine").exists());
+        workspace::add_repos(
+            &paths.mirrors_dir,
+            &lazy_dir,
+            &BTreeMap::from([(identity.clone(), String::new())]),
+            &urls,
+            &meta.branches,
+        )
+        .unwrap();
+        let meta = workspace::load_metadata(&lazy_dir).unwrap();
+        assert!(meta.pending.is_empty());
+        assert_eq!(meta.dir_name(&identity).unwrap(), "mine");
+        assert!(lazy_dir.join("mine").join(".git").exists());
+    }
+
+    #[test]
+    fn test_unpack_rejects_other_archives() {
+        let tmp = tempfile::tempdir().unwrap();
+        let stage = tmp.path().join("stage");
+        fs::create_dir_all(&stage).unwrap();
+        fs::write(stage.join("x"), "x").unwrap();
+        let archive = tmp.path().join("x.tar.gz");
+        run_tar(&[
+            "-czf",
+            archive.to_str().unwrap(),
+            "-C",
+            stage.to_str().unwrap(),
+            ".",
+        ])
+        .unwrap();
+        let err = unpack(&archive).err().unwrap();
+        assert!(err.to_string().contains("not a wsp backup"), "{}", err);
+
+        fs::write(
+            stage.join(MANIFEST_FILE),
+            "version: 99\ncreated: 2026-01-01T00:00:00Z\n",
+        )
+        .unwrap();
+        run_tar(&[
+            "-czf",
+            archive.to_str().unwrap(),
+            "-C",
+            stage.to_str().unwrap(),
+            ".",
+        ])
+        .unwrap();
+        let err = unpack(&archive).err().unwrap();
+        assert!(err.to_string().contains("upgrade wsp"), "{}", err);
+    }
+}
@@ -0,0 +1,189 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use anyhow::{Result, bail};
+
+use crate::config::Config;
+use crate::workspace::Metadata;
+
+/// Returns the repo part of a `setup.<repo>` key. Only the prefix is
+/// normalized — repo names may legitimately contain underscores.
+pub fn key_repo(key: &str) -> Option<&str> {
+    let (prefix, repo) = key.split_once('.')?;
+    (prefix == "setup").then_some(repo)
+}
+
+/// Lines of output shown for a failed setup command.
+const FAILURE_TAIL_LINES: usize = 10;
+
+/// Run each repo's registered `setup` command (see `RepoEntry::setup`) in its
+/// new clone, in parallel, printing one progress line per repo to stderr.
+/// Failures are reported but never fail the caller: the workspace is usable
+/// and setup can be re-run by hand. Returns the number of failed repos.
+pub fn run(ws_dir: &Path, meta: &Metadata, cfg: &Config, identities: &[String]) -> usize {
+    let jobs: Vec<(String, std::path::PathBuf, &str)> = identities
+        .iter()
+        .filter_map(|id| {
+            let cmd = cfg.repos.get(id)?.setup.as_deref()?;
+            if cmd.trim().is_empty() {
+                return None;
+            }
+            let dir_name = meta.dir_name(id).ok()?;
+            Some((dir_name.clone(), ws_dir.join(dir_name), cmd))
+        })
+        .collect();
+    if jobs.is_empty() {
+        return 0;
+    }
+
+    eprintln!("Running setup in {} repo(s)...", jobs.len());
+    let progress = Mutex::new(());
+    let failed: usize = std::thread::scope(|s| {
+        let handles: Vec<_> = jobs
+            .iter()
+            .map(|(name, dir, cmd)| {
+                let progress = &progress;
+                s.spawn(move || {
+                    let start = Instant::now();
+                    let result = run_one(dir, cmd).map_err(|e| e.to_string());
+                    let secs = start.elapsed().as_secs_f64();
+                    let _lock = progress.lock().unwrap_or_else(|e| e.into_inner());
+                    match &result {
+                        Ok(()) => eprintln!("  ok    {} ({:.1}s)", name, secs),
+                        Err(e) => {
+                            eprintln!("  FAIL  {} ({})", name, e.lines().next().unwrap_or(""));
+                            for line in e.lines().skip(1) {
+                                eprintln!("        {}", line);
+                            }
+                        }
+                    }
+                    result.is_err()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap_or(true))
+            .filter(|failed| *failed)
+            .count()
+    });
+    if failed > 0 {
+        eprintln!(
+            "warning: setup failed in {} repo(s); fix and re-run the command in the repo",
+            failed
+        );
+    }
+    failed
+}
+
+/// Run `cmd` through the shell in `dir`. On failure the error holds the exit
+/// status followed by the tail of the command's output.
+fn run_one(dir: &Path, cmd: &str) -> Result<()> {
+    let argv = crate::task::shell_argv(cmd);
+    let output = Command::new(&argv[0])
+        .args(&argv[1..])
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output()?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let status = match output.status.code() {
+        Some(code) => format!("exit status {}", code),
+        None => "terminated by signal".to_string(),
+    };
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let text = if stderr.trim().is_empty() {
+        stdout
+    } else {
+        stderr
+    };
+    let lines: Vec<&str> = text.lines().collect();
+    let tail = &lines[lines.len().saturating_sub(FAILURE_TAIL_LINES)..];
+    if tail.is_empty() {
+        bail!("{}", status);
+    }
+    bail!("{}\n{}", status, tail.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_key_repo() {
+        let cases = vec![
+            ("setup.api", Some("api")),
+            ("setup.my_api", Some("my_api")),
+            ("setup", None),
+            ("task.api", None),
+        ];
+        for (key, want) in cases {
+            assert_eq!(key_repo(key), want, "{}", key);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ids = ["api", "web", "docs"].map(|r| format!("github.com/acme/{}", r));
+        let mut meta_repos = BTreeMap::new();
+        let mut cfg = Config::default();
+        let setups = [
+            Some("echo built > out.txt"),
+            Some("echo boom >&2; exit 2"),
+            None,
+        ];
+        for (id, setup) in ids.iter().zip(setups) {
+            meta_repos.insert(id.clone(), None);
+            std::fs::create_dir(tmp.path().join(id.rsplit('/').next().unwrap())).unwrap();
+            cfg.repos.insert(
+                id.clone(),
+                crate::config::RepoEntry {
+                    url: format!("https://{}.git", id),
+                    added: chrono::Utc::now(),
+                    setup: setup.map(String::from),
+                    exec_dir: None,
+                },
+            );
+        }
+        let meta = Metadata {
+            version: 0,
+            name: "ws".into(),
+            branch: "ws".into(),
+            repos: meta_repos,
+            created: chrono::Utc::now(),
+            description: None,
+            last_used: None,
+            created_from: None,
+            dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
+            config: None,
+            pending: BTreeMap::new(),
+        };
+
+        assert_eq!(run(tmp.path(), &meta, &cfg, &ids), 1);
+        let out = std::fs::read_to_string(tmp.path().join("api/out.txt")).unwrap();
+        assert_eq!(out.trim(), "built");
+
+        // Only the requested repos run
+        assert_eq!(run(tmp.path(), &meta, &cfg, &ids[..1]), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_one_error_tail() {
+        let tmp = tempfile::tempdir().unwrap();
+        let err = run_one(tmp.path(), "echo one; echo two >&2; exit 3")
+            .unwrap_err()
+            .to_string();
+        assert_eq!(err, "exit status 3\ntwo");
+    }
+}
@@ -1,18 +1,25 @@
 use std::collections::BTreeMap;
+use std::path::Path;
 
 use anyhow::{Result, bail};
 use chrono::Utc;
 use clap::{Arg, ArgMatches, Command};
 use clap_complete::engine::ArgValueCandidates;
 
+use crate::bootstrap;
 use crate::config::{self, Paths, RepoEntry};
 use crate::discovery;
+use crate::error;
 use crate::filelock;
 use crate::gc;
 use crate::giturl;
+use crate::hooks;
 use crate::mirror;
+use crate::offline::FetchPolicy;
 use crate::output::{MutationOutput, Output};
+use crate::pushguard;
 use crate::template;
+use crate::trailer;
 use crate::workspace;
 
 use super::completers;
@@ -44,6 +51,12 @@ pub fn cmd() -> Command {
                 .action(clap::ArgAction::SetTrue)
                 .help("Skip template discovery in added repos"),
         )
+        .arg(
+            Arg::new("no-setup")
+                .long("no-setup")
+                .action(clap::ArgAction::SetTrue)
+                .help("Skip the added repos' setup commands"),
+        )
 }
 
 pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
@@ -56,9 +69,10 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     let cwd = std::env::current_dir()?;
     let ws_dir = workspace::detect(&cwd)?;
     gc::check_workspace(&ws_dir, /* read_only */ false)?;
+    let _lock = filelock::lock_workspace(&ws_dir)?;
 
     let mut cfg = config::Config::load_from(&paths.config_path)
-        .map_err(|e| anyhow::anyhow!("loading config: {}", e))?;
+        .map_err(|e| error::prefixed(e, "loading config"))?;
 
     let identities: Vec<String> = cfg.repos.keys().cloned().collect();
 
@@ -77,11 +91,12 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     // Track URLs that need global registration (not yet in config.yaml)
     let mut to_register: Vec<(String, String)> = Vec::new(); // (identity, url)
 
+    let index = giturl::Index::new(&identities);
     for rn in &repo_args {
         let name = giturl::parse_repo_ref(rn);
 
         // Try resolving as a registered shortname first
-        match giturl::resolve(name, &identities) {
+        match index.resolve(name) {
             Ok(id) => {
                 repo_refs.insert(id, String::new());
             }
@@ -131,6 +146,8 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
                 RepoEntry {
                     url: url.clone(),
                     added: Utc::now(),
+                    setup: None,
+                    exec_dir: None,
                 },
             );
             Ok(())
@@ -153,26 +170,49 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
         }
     }
 
+    // Repos matched by a branch-prefix.<pattern> rule get their own branch,
+    // as do all of them if the workspace sets its own branch-prefix.
+    let ws_meta = workspace::load_metadata(&ws_dir)?;
+    let effective = ws_meta.apply_workspace_config(&cfg);
+    let plan = workspace::Branches::plan(&effective, &ws_meta.name, repo_refs.keys())?;
+    let mut branches = plan.per_repo;
+    if ws_meta
+        .config
+        .as_ref()
+        .is_some_and(|c| c.branch_prefix.is_some())
+        && plan.default != ws_meta.branch
+    {
+        for identity in repo_refs.keys() {
+            branches
+                .entry(identity.clone())
+                .or_insert_with(|| plan.default.clone());
+        }
+    }
+
+    // Registered repos whose mirror is missing get it cloned first, in parallel.
+    super::new::prepare_mirrors(
+        paths,
+        repo_refs.keys(),
+        &upstream_urls,
+        FetchPolicy::from_flag(/* no_fetch */ true),
+        &super::fetch::Limits::resolve(matches, &cfg),
+    );
+
     eprintln!("Adding {} repos to workspace...", repo_refs.len());
     let new_ids: Vec<String> = repo_refs.keys().cloned().collect();
-    workspace::add_repos(&paths.mirrors_dir, &ws_dir, &repo_refs, &upstream_urls)?;
+    workspace::add_repos(
+        &paths.mirrors_dir,
+        &ws_dir,
+        &repo_refs,
+        &upstream_urls,
+        &branches,
+    )?;
 
     let meta_result = workspace::load_metadata(&ws_dir);
 
-    // Apply git config defaults to newly added clones only
-    if let Ok(ref meta) = meta_result {
-        let git_config = cfg.effective_git_config();
-        workspace::apply_git_config(&ws_dir, meta, &git_config, Some(&new_ids));
-    }
     match &meta_result {
-        Ok(meta) => crate::lang::run_integrations(&ws_dir, meta, &cfg),
-        Err(e) => eprintln!("warning: skipping language integrations: {}", e),
-    }
-    if cfg.agent_md.unwrap_or(true)
-        && let Ok(meta) = &meta_result
-        && let Err(e) = crate::agentmd::update(&ws_dir, meta)
-    {
-        eprintln!("warning: AGENTS.md generation failed: {}", e);
+        Ok(meta) => set_up_added(&ws_dir, meta, &cfg, &new_ids, matches.get_flag("no-setup"))?,
+        Err(e) => tracing::warn!("skipping setup of added repos: {}", e),
     }
 
     // Template discovery: scan newly added repos for .wsp.yaml files
@@ -193,9 +233,47 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
             }
         }
         if let Err(e) = discovery::prompt_and_import(&all_discovered, &paths.templates_dir) {
-            eprintln!("warning: template discovery failed: {}", e);
+            tracing::warn!("template discovery failed: {}", e);
         }
     }
 
     Ok(Output::Mutation(MutationOutput::new("Done.")))
 }
+
+/// Sets up repos just cloned into a workspace: git config, commit authors
+/// and hooks, setup commands (unless `no_setup`), then the workspace-wide
+/// language integrations and AGENTS.md, and finally the post-add hooks.
+pub(crate) fn set_up_added(
+    ws_dir: &Path,
+    meta: &workspace::Metadata,
+    cfg: &config::Config,
+    new_ids: &[String],
+    no_setup: bool,
+) -> Result<()> {
+    let effective = meta.apply_workspace_config(cfg);
+    workspace::apply_git_config(ws_dir, meta, &cfg.effective_git_config(), Some(new_ids));
+    workspace::add_git_config(ws_dir, meta, &cfg.url_rewrite_git_config(), Some(new_ids));
+    workspace::apply_committers(ws_dir, meta, cfg, Some(new_ids));
+    if cfg.commit_trailer == Some(true) {
+        trailer::HOOK.install_all(ws_dir, meta, Some(new_ids));
+    }
+    if cfg.push_guard == Some(true) {
+        pushguard::HOOK.install_all(ws_dir, meta, Some(new_ids));
+    }
+    if !no_setup {
+        bootstrap::run(ws_dir, meta, cfg, new_ids);
+    }
+    crate::lang::run_integrations(ws_dir, meta, cfg);
+    if effective.agent_md.unwrap_or(true)
+        && let Err(e) = crate::agentmd::update(ws_dir, meta)
+    {
+        tracing::warn!("AGENTS.md generation failed: {}", e);
+    }
+    hooks::run(
+        hooks::Event::PostAdd,
+        cfg.hooks.as_ref(),
+        ws_dir,
+        meta,
+        new_ids,
+    )
+}
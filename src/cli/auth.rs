@@ -0,0 +1,175 @@
+use std::collections::BTreeSet;
+use std::io::{IsTerminal, Read};
+
+use anyhow::{Context, Result, bail};
+use clap::{Arg, ArgMatches, Command};
+use clap_complete::engine::ArgValueCandidates;
+
+use crate::auth;
+use crate::config::{Config, Paths};
+use crate::forge::Forge;
+use crate::output::{AuthHostStatus, AuthStatusOutput, MutationOutput, Output};
+
+use super::completers;
+
+pub fn cmd() -> Command {
+    Command::new("auth")
+        .about("Store GitHub and GitLab API tokens in the OS keyring")
+        .long_about(
+            "Store GitHub and GitLab API tokens in the OS keyring.\n\n\
+             wsp talks to forges through gh and glab (`wsp pr`, `wsp new --issue`, \
+             `wsp registry add --from`). `login` saves a token for a host in the \
+             keyring — Keychain on macOS, Credential Manager on Windows, the Secret \
+             Service on Linux — and wsp hands it to gh or glab whenever it runs them \
+             against that host, so the token never sits in a plaintext env var or \
+             file. The token is read from stdin, without echo when stdin is a \
+             terminal.\n\n\
+             A token already in the environment (GH_TOKEN, GH_ENTERPRISE_TOKEN, \
+             GITLAB_TOKEN) wins over the keyring. With neither, gh and glab use their \
+             own login. `status` shows which applies for each host wsp knows about.",
+        )
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("login")
+                .about("Save a token for a host, read from stdin")
+                .arg(host_arg().help("Forge host, e.g. github.com or git.acme.com")),
+        )
+        .subcommand(
+            Command::new("logout")
+                .about("Remove the token saved for a host")
+                .arg(host_arg().help("Forge host the token was saved for")),
+        )
+        .subcommand(
+            Command::new("status")
+                .about("Show where each forge host's token comes from [read-only]"),
+        )
+}
+
+fn host_arg() -> Arg {
+    Arg::new("host")
+        .required(true)
+        .add(ArgValueCandidates::new(completers::complete_hosts))
+}
+
+pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    match matches.subcommand() {
+        Some(("login", m)) => login(m.get_one::<String>("host").unwrap(), paths),
+        Some(("logout", m)) => logout(m.get_one::<String>("host").unwrap()),
+        Some(("status", _)) => status(paths),
+        _ => unreachable!(),
+    }
+}
+
+fn login(host: &str, paths: &Paths) -> Result<Output> {
+    auth::validate_host(host)?;
+    let cfg = Config::load_from(&paths.config_path)?;
+    let forge = Forge::detect(host, cfg.forges.as_ref());
+    let token = read_token(host)?;
+    auth::store(host, &token)?;
+
+    let mut out = MutationOutput::new(format!(
+        "saved {} token for {} in the keyring",
+        forge.name(),
+        host
+    ));
+    if let Some(var) = forge.token_env_set(host) {
+        out = out.with_hint(format!("{} is set and takes precedence over it", var));
+    }
+    Ok(Output::Mutation(out))
+}
+
+fn logout(host: &str) -> Result<Output> {
+    if !auth::delete(host)? {
+        bail!("no token saved for {}", host);
+    }
+    Ok(Output::Mutation(MutationOutput::new(format!(
+        "removed the token for {} from the keyring",
+        host
+    ))))
+}
+
+fn status(paths: &Paths) -> Result<Output> {
+    let cfg = Config::load_from(&paths.config_path)?;
+    let hosts = known_hosts(&cfg)
+        .into_iter()
+        .map(|host| {
+            let forge = Forge::detect(&host, cfg.forges.as_ref());
+            let (source, variable) = match forge.token_env_set(&host) {
+                Some(var) => ("env", Some(var.to_string())),
+                None if auth::token(&host).is_some() => ("keyring", None),
+                None => ("none", None),
+            };
+            AuthHostStatus {
+                host,
+                forge: forge.name().into(),
+                cli: forge.cli().into(),
+                source: source.into(),
+                variable,
+            }
+        })
+        .collect();
+    Ok(Output::AuthStatus(AuthStatusOutput { hosts }))
+}
+
+/// Hosts wsp may call a forge for: those of registered repos and those
+/// with a `forge.<host>` override.
+pub fn known_hosts(cfg: &Config) -> Vec<String> {
+    let mut hosts: BTreeSet<String> = cfg
+        .repos
+        .keys()
+        .filter_map(|id| id.split('/').next())
+        .map(String::from)
+        .collect();
+    hosts.extend(cfg.forges.iter().flat_map(|m| m.keys().cloned()));
+    hosts.into_iter().collect()
+}
+
+/// Reads the token from stdin: one line, echo off when it's a terminal.
+fn read_token(host: &str) -> Result<String> {
+    let stdin = std::io::stdin();
+    let token = if stdin.is_terminal() {
+        rpassword::prompt_password(format!("Token for {}: ", host))
+            .context("reading token from the terminal")?
+    } else {
+        let mut buf = String::new();
+        stdin
+            .lock()
+            .read_to_string(&mut buf)
+            .context("reading token from stdin")?;
+        buf
+    };
+    let token = token.trim();
+    if token.is_empty() {
+        bail!("no token given");
+    }
+    if token.contains(char::is_whitespace) {
+        bail!("token contains whitespace; paste the token alone");
+    }
+    Ok(token.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_hosts() {
+        let mut yaml = String::from("forge:\n  git.acme.com: gitlab\nrepos:\n");
+        for id in [
+            "github.com/acme/api",
+            "gitlab.com/acme/web",
+            "github.com/acme/docs",
+        ] {
+            yaml.push_str(&format!(
+                "  {}:\n    url: https://{}.git\n    added: 2026-01-01T00:00:00Z\n",
+                id, id
+            ));
+        }
+        let cfg: Config = serde_yaml_ng::from_str(&yaml).unwrap();
+        assert_eq!(
+            known_hosts(&cfg),
+            vec!["git.acme.com", "github.com", "gitlab.com"]
+        );
+        assert!(known_hosts(&Config::default()).is_empty());
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+
+use crate::backup;
+use crate::config::{Config, Paths};
+use crate::error::{self, ErrorKind};
+use crate::offline::FetchPolicy;
+use crate::output::{MutationOutput, Output};
+
+pub fn backup_cmd() -> Command {
+    Command::new("backup")
+        .about("Save config, templates and workspace metadata to a .tar.gz")
+        .long_about(
+            "Save config, templates and workspace metadata to a .tar.gz.\n\n\
+             The archive holds config.yaml, every template, and each workspace's \
+             .wsp.yaml — no git data, so it stays small. Restore it on another machine \
+             with `wsp setup restore`. Commit and push work you want to keep: only \
+             branches on origin come back.",
+        )
+        .arg(
+            Arg::new("file")
+                .help("Archive to write [default: wsp-backup-<date>.tar.gz]")
+                .value_hint(clap::ValueHint::FilePath),
+        )
+}
+
+pub fn restore_cmd() -> Command {
+    Command::new("restore")
+        .about("Restore config, templates and workspaces from a backup")
+        .long_about(
+            "Restore config, templates and workspaces from a backup.\n\n\
+             Writes back config.yaml and the templates from a `wsp setup backup` \
+             archive, clones the mirrors the workspaces use, then recreates each \
+             workspace: its clones are made again on the workspace branch (picked up from \
+             origin, tracking it, if it was pushed), in the directories they had. Mirrors \
+             of registered repos no workspace uses are cloned when a workspace first needs \
+             them.\n\n\
+             With --no-clone, nothing is cloned: the workspaces come back with their repos \
+             pending, and `wsp repair <workspace>` clones them when you need them.\n\n\
+             Refuses to replace an existing config.yaml without --force; with it, the \
+             current one is kept as config.yaml.bak. Workspaces that already exist are \
+             skipped.",
+        )
+        .arg(
+            Arg::new("file")
+                .required(true)
+                .help("Archive written by `wsp setup backup`")
+                .value_hint(clap::ValueHint::FilePath),
+        )
+        .arg(
+            Arg::new("force")
+                .short('f')
+                .long("force")
+                .action(clap::ArgAction::SetTrue)
+                .help("Replace an existing config.yaml (kept as config.yaml.bak)"),
+        )
+        .arg(
+            Arg::new("no-clone")
+                .long("no-clone")
+                .action(clap::ArgAction::SetTrue)
+                .help("Leave the repos pending; `wsp repair` clones them later"),
+        )
+        .arg(
+            Arg::new("no-setup")
+                .long("no-setup")
+                .action(clap::ArgAction::SetTrue)
+                .help("Skip the repos' setup commands"),
+        )
+}
+
+pub fn run_backup(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    let out = match matches.get_one::<String>("file") {
+        Some(f) => PathBuf::from(f),
+        None => PathBuf::from(format!(
+            "wsp-backup-{}.tar.gz",
+            chrono::Local::now().format("%Y-%m-%d")
+        )),
+    };
+    let manifest = backup::create(paths, &out)?;
+    Ok(Output::Mutation(
+        MutationOutput::new(format!(
+            "backed up config, templates and {} workspace(s) to {}",
+            manifest.workspaces.len(),
+            out.display()
+        ))
+        .with_hint("restore it with `wsp setup restore <file>`"),
+    ))
+}
+
+pub fn run_restore(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    let archive = Path::new(matches.get_one::<String>("file").unwrap());
+    let force = matches.get_flag("force");
+    let no_setup = matches.get_flag("no-setup");
+    let clone = !matches.get_flag("no-clone");
+
+    let unpacked = backup::unpack(archive)?;
+    if paths.config_path.exists() && !force {
+        return Err(error::tag(
+            ErrorKind::Conflict,
+            anyhow::anyhow!(
+                "{} already exists; restore is for setting up a new machine (--force replaces it)",
+                paths.config_path.display()
+            ),
+        ));
+    }
+    let (templates, saved) = backup::restore_files(paths, &unpacked)?;
+    let cfg = Config::load_from(&paths.config_path)?;
+    if let Some(bak) = &saved {
+        eprintln!("Saved the previous config.yaml to {}", bak.display());
+    }
+    eprintln!("Restored config.yaml and {} template(s)", templates);
+
+    if clone {
+        let mut urls = BTreeMap::new();
+        for name in unpacked.manifest.workspaces.keys() {
+            if let Ok(ws_urls) = backup::upstream_urls(&cfg, &unpacked, name) {
+                urls.extend(ws_urls);
+            }
+        }
+        super::new::prepare_mirrors(
+            paths,
+            urls.keys(),
+            &urls,
+            FetchPolicy::Fetch,
+            &super::fetch::Limits::resolve(matches, &cfg),
+        );
+    }
+
+    let mut restored = 0;
+    let mut pending = 0;
+    for (name, was) in &unpacked.manifest.workspaces {
+        let root = backup::restore_root(&cfg, &paths.workspaces_dir, Path::new(was));
+        let ws_dir = crate::workspace::dir(&root, name);
+        if ws_dir.exists() || paths.workspace_dir(name).exists() {
+            eprintln!("  {}: already exists, skipping", name);
+            continue;
+        }
+        eprintln!("Restoring workspace {:?}...", name);
+        match backup::restore_workspace(paths, &cfg, &unpacked, name, &root, clone) {
+            Ok(left) => {
+                if clone {
+                    for (identity, why) in &left {
+                        eprintln!("  FAIL  {} ({}), left pending", identity, why);
+                    }
+                }
+                pending += left.len();
+                super::new::finish(paths, &cfg, &ws_dir, None, no_setup, true)?;
+                restored += 1;
+            }
+            Err(e) => eprintln!("  FAIL  {} ({:#})", name, e),
+        }
+    }
+
+    let mut out = MutationOutput::new(format!(
+        "restored {} of {} workspace(s)",
+        restored,
+        unpacked.manifest.workspaces.len()
+    ));
+    if pending > 0 {
+        let why = if clone {
+            "could not be cloned"
+        } else {
+            "not cloned"
+        };
+        out = out.with_hint(format!(
+            "{} repo(s) {} and are pending; clone them with `wsp repair <workspace>`",
+            pending, why
+        ));
+    }
+
+    Ok(Output::Mutation(out))
+}
@@ -1,9 +1,16 @@
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+
 use anyhow::{Result, bail};
 use clap::{Arg, ArgMatches, Command};
 use clap_complete::engine::ArgValueCandidates;
+use serde::{Deserialize, Serialize};
 
 use crate::config::Paths;
+use crate::error::{self, ErrorKind};
 use crate::output::{Output, PathOutput};
+use crate::picker;
 use crate::workspace;
 
 use super::completers;
@@ -15,21 +22,94 @@ pub fn cmd() -> Command {
             "Change directory into a workspace.\n\n\
              Requires shell integration to be active (see `wsp completion`). Without it, \
              prints the workspace path instead. Also propagates mirror refs to clones so \
-             remote tracking branches stay current.",
+             remote tracking branches stay current.\n\n\
+             Without a workspace name, picks one from a fuzzy list of workspaces, most \
+             recently used first; without a terminal, a name is required.\n\n\
+             `wsp cd -` returns to the workspace you were in before the last `wsp cd`, \
+             like `cd -`.",
         )
         .arg(
             Arg::new("workspace")
-                .required(true)
+                .help("Workspace to enter; `-` for the previous one (omit to pick)")
                 .add(ArgValueCandidates::new(completers::complete_workspaces)),
         )
 }
 
+const STATE_FILE: &str = "cd-state.yaml";
+
+/// The workspaces `wsp cd` last moved between, kept in the data dir so
+/// `wsp cd -` works across shells.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct CdState {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    current: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    previous: Option<String>,
+}
+
+impl CdState {
+    fn load(data_dir: &Path) -> CdState {
+        fs::read_to_string(data_dir.join(STATE_FILE))
+            .ok()
+            .and_then(|s| serde_yaml_ng::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    /// Best effort: losing the state only breaks the next `wsp cd -`.
+    /// Written whole and renamed, so two shells never leave it half written.
+    fn save(&self, data_dir: &Path) {
+        let Ok(text) = serde_yaml_ng::to_string(self) else {
+            return;
+        };
+        let _ = fs::create_dir_all(data_dir);
+        if let Ok(mut tmp) = tempfile::NamedTempFile::new_in(data_dir)
+            && tmp.write_all(text.as_bytes()).is_ok()
+        {
+            let _ = tmp.persist(data_dir.join(STATE_FILE));
+        }
+    }
+
+    /// Record entering `name` from workspace `from` (the one the shell is
+    /// in, if any; otherwise the one last entered).
+    fn enter(&mut self, name: &str, from: Option<String>) {
+        let from = from.or_else(|| self.current.clone());
+        if let Some(from) = from
+            && from != name
+        {
+            self.previous = Some(from);
+        }
+        self.current = Some(name.to_string());
+    }
+}
+
 pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
-    let name = matches.get_one::<String>("workspace").unwrap();
-    let ws_dir = workspace::dir(&paths.workspaces_dir, name);
+    let mut state = CdState::load(paths.data_dir());
+    let name = match matches.get_one::<String>("workspace").map(String::as_str) {
+        Some("-") => match &state.previous {
+            Some(prev) => prev.clone(),
+            None => bail!("no previous workspace; `wsp cd -` works after a `wsp cd`"),
+        },
+        Some(name) => name.to_string(),
+        None if !std::io::stdin().is_terminal() => {
+            bail!("no workspace given and no terminal to pick one; run `wsp cd <workspace>`")
+        }
+        None => pick_workspace(paths)?,
+    };
+    let ws_dir = paths.workspace_dir(&name);
     if !ws_dir.join(workspace::METADATA_FILE).exists() {
-        bail!("workspace '{}' not found", name);
+        return Err(error::tag(
+            ErrorKind::NotFound,
+            anyhow::anyhow!("workspace '{}' not found", name),
+        ));
     }
+    workspace::touch_last_used(&ws_dir);
+
+    let from = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| workspace::detect(&cwd).ok())
+        .and_then(|dir| dir.file_name().map(|n| n.to_string_lossy().into_owned()));
+    state.enter(&name, from);
+    state.save(paths.data_dir());
 
     // Propagate mirror refs to clones
     if let Ok(meta) = workspace::load_metadata(&ws_dir) {
@@ -46,3 +126,156 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
         path: ws_dir.display().to_string(),
     }))
 }
+
+/// Interactive picker over all workspaces, most recently used first.
+fn pick_workspace(paths: &Paths) -> Result<String> {
+    let mut entries = workspaces_by_recent_use(paths)?;
+    let width = entries.iter().map(|(n, _)| n.len()).max().unwrap_or(0);
+    let items = entries
+        .iter()
+        .map(|(name, meta)| match meta {
+            Some(m) => format!(
+                "{:width$}  {}{}",
+                name,
+                m.branch,
+                m.description
+                    .as_deref()
+                    .map(|d| format!("  {}", d))
+                    .unwrap_or_default(),
+                width = width
+            ),
+            None => name.clone(),
+        })
+        .collect();
+    let i = picker::pick_one("Workspace:", items)?;
+    Ok(entries.swap_remove(i).0)
+}
+
+/// All workspaces with their metadata, most recently used first. Errors
+/// when there are none.
+fn workspaces_by_recent_use(paths: &Paths) -> Result<Vec<(String, Option<workspace::Metadata>)>> {
+    let mut entries: Vec<(String, Option<workspace::Metadata>)> = workspace::list_all(paths)?
+        .into_iter()
+        .map(|name| {
+            let meta = workspace::load_metadata(&paths.workspace_dir(&name)).ok();
+            (name, meta)
+        })
+        .collect();
+    if entries.is_empty() {
+        bail!("no workspaces (create one with `wsp new`)");
+    }
+    sort_by_recent_use(&mut entries);
+    Ok(entries)
+}
+
+/// Newest `last_used` (falling back to `created`) first; unreadable
+/// workspaces last.
+fn sort_by_recent_use(entries: &mut [(String, Option<workspace::Metadata>)]) {
+    entries.sort_by_key(|(_, meta)| {
+        std::cmp::Reverse(meta.as_ref().map(|m| m.last_used.unwrap_or(m.created)))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use chrono::{TimeZone, Utc};
+
+    fn meta(created_day: u32, last_used_day: Option<u32>) -> Option<workspace::Metadata> {
+        let day = |d| Utc.with_ymd_and_hms(2026, 1, d, 0, 0, 0).unwrap();
+        Some(workspace::Metadata {
+            version: 0,
+            name: String::new(),
+            branch: String::new(),
+            repos: BTreeMap::new(),
+            created: day(created_day),
+            description: None,
+            last_used: last_used_day.map(day),
+            created_from: None,
+            dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
+            config: None,
+            pending: BTreeMap::new(),
+        })
+    }
+
+    #[test]
+    fn test_sort_by_recent_use() {
+        let mut entries = vec![
+            ("old".to_string(), meta(1, None)),
+            ("broken".to_string(), None),
+            ("used".to_string(), meta(2, Some(20))),
+            ("new".to_string(), meta(10, None)),
+        ];
+        sort_by_recent_use(&mut entries);
+        let names: Vec<&str> = entries.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["used", "new", "old", "broken"]);
+    }
+
+    #[test]
+    fn test_cd_state_enter() {
+        let state = |current: Option<&str>, previous: Option<&str>| CdState {
+            current: current.map(String::from),
+            previous: previous.map(String::from),
+        };
+        let cases = vec![
+            // (name, before, entering, from shell, after)
+            (
+                "first cd",
+                state(None, None),
+                "a",
+                None,
+                state(Some("a"), None),
+            ),
+            (
+                "from the last entered",
+                state(Some("a"), None),
+                "b",
+                None,
+                state(Some("b"), Some("a")),
+            ),
+            (
+                "shell is in another workspace",
+                state(Some("a"), None),
+                "b",
+                Some("c"),
+                state(Some("b"), Some("c")),
+            ),
+            (
+                "re-entering keeps previous",
+                state(Some("a"), Some("b")),
+                "a",
+                None,
+                state(Some("a"), Some("b")),
+            ),
+            (
+                "cd - swaps",
+                state(Some("b"), Some("a")),
+                "a",
+                Some("b"),
+                state(Some("a"), Some("b")),
+            ),
+        ];
+        for (name, mut before, entering, from, want) in cases {
+            before.enter(entering, from.map(String::from));
+            assert_eq!(before, want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_cd_state_roundtrip() {
+        let tmp = tempfile::tempdir().unwrap();
+        // The data dir may not exist yet.
+        let dir = tmp.path().join("wsp");
+        assert_eq!(CdState::load(&dir), CdState::default());
+        let mut st = CdState::default();
+        st.enter("a", None);
+        st.enter("b", None);
+        st.save(&dir);
+        assert_eq!(CdState::load(&dir), st);
+    }
+}
@@ -1,14 +1,24 @@
 use std::collections::BTreeMap;
+use std::io::{IsTerminal, Write};
 use std::path::Path;
 
 use anyhow::{Result, bail};
 use clap::{Arg, ArgAction, ArgMatches, Command};
 use clap_complete::engine::ArgValueCandidates;
 
+use crate::bootstrap;
 use crate::cli::completers;
+use crate::cli::fetch;
 use crate::config::{self, Paths};
+use crate::deps;
+use crate::error::{self, ErrorKind};
 use crate::filelock;
+use crate::files;
+use crate::forge;
+use crate::hooks;
+use crate::issue;
 use crate::output::{ConfigGetOutput, ConfigListEntry, ConfigListOutput, MutationOutput, Output};
+use crate::task;
 use crate::template;
 use crate::workspace;
 
@@ -17,17 +27,26 @@ pub fn cmd() -> Command {
         .about("Manage wsp settings")
         .long_about(
             "Manage wsp settings.\n\n\
-             Settings are stored in ~/.local/share/wsp/config.yaml (global) or per-workspace \
+             Settings are stored in ~/.config/wsp/config.yaml (global) or per-workspace \
              in .wsp.yaml (workspace-scoped). When run inside a workspace, set/get/unset/ls \
              operate on workspace config by default. Use --global to target global config \
-             instead. Workspace config overrides global for: sync-strategy, git.*, \
-             lang.*. Keys like branch-prefix, workspaces-dir, gc.retention-days, \
-             agent-md, shell.tmux, and shell.prompt are global-only.",
+             instead. Workspace config overrides global for: branch-prefix, sync-strategy, \
+             agent-md, run.jobs, git.*, lang.*, task.*, depends-on.*, exec-dir.<repo>. \
+             Keys like \
+             branch-prefix.<pattern>, branch-template, committer.<pattern>.<field>, \
+             workspaces-dir, workspaces-dir.<template>, gc.retention-days, fetch.jobs, fetch.retries, fetch.timeout, fetch.max-age, commit-trailer, push-guard, shell.tmux, shell.prompt, editor, \
+             issue.name-template, jira.url, hook.<event>, \
+             setup.<repo>, file.<path>, forge.<host>, url-rewrite.<prefix> and host.<host>.* \
+             are global-only.\n\n\
+             WSP_* variables override global settings without changing config.yaml, \
+             e.g. WSP_BRANCH_PREFIX, WSP_WORKSPACES_DIR, WSP_JOBS (see `wsp help config`).",
         )
         .subcommand(list_cmd())
         .subcommand(get_cmd())
         .subcommand(set_cmd())
         .subcommand(unset_cmd())
+        .subcommand(doctor_cmd())
+        .subcommand(edit_cmd())
 }
 
 pub fn dispatch(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
@@ -53,6 +72,8 @@ pub fn dispatch(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     };
 
     match (sub_name, ws_dir) {
+        ("doctor", _) => run_doctor(paths),
+        ("edit", _) => run_edit(paths),
         ("ls", Some(ws)) => run_list_workspace(sub_matches, &ws, paths),
         ("ls", None) => run_list(sub_matches, paths),
         ("get", Some(ws)) => run_get_workspace(sub_matches, &ws, paths),
@@ -67,20 +88,47 @@ pub fn dispatch(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
 
 /// Keys that are global-only and cannot be set at workspace level.
 const GLOBAL_ONLY_KEYS: &[&str] = &[
-    "branch-prefix",
+    "branch-template",
     "workspaces-dir",
     "gc.retention-days",
-    "agent-md",
+    "fetch.jobs",
+    "fetch.retries",
+    "fetch.timeout",
+    "fetch.max-age",
+    "commit-trailer",
+    "push-guard",
     "shell.tmux",
     "shell.prompt",
+    "editor",
+    "issue.name-template",
+    "jira.url",
     "experimental",
 ];
 
+/// Keys a workspace can override that templates can't set.
+const WORKSPACE_SETTING_KEYS: &[&str] = &["branch-prefix", "agent-md", "run.jobs"];
+
+/// Returns the repo part of an `exec-dir.<repo>` key. Only the prefix is
+/// normalized — repo names may legitimately contain underscores.
+fn exec_dir_repo(key: &str) -> Option<&str> {
+    let (prefix, repo) = key.split_once('.')?;
+    (prefix.replace('_', "-") == "exec-dir").then_some(repo)
+}
+
 fn is_global_only_key(key: &str) -> bool {
     let normalized = template::normalize_key(key);
     GLOBAL_ONLY_KEYS.contains(&normalized.as_str())
         || normalized.starts_with("shell.")
         || normalized.starts_with("experimental.")
+        || bootstrap::key_repo(key).is_some()
+        || files::key_dest(key).is_some()
+        || hooks::key_event(&normalized).is_some()
+        || forge::key_host(key).is_some()
+        || workspace::key_prefix_pattern(key).is_some()
+        || config::key_committer(key).is_some()
+        || config::key_template_root(key).is_some()
+        || config::key_url_rewrite(key).is_some()
+        || config::key_host_setting(key).is_some()
 }
 
 fn global_arg() -> Arg {
@@ -124,6 +172,151 @@ pub fn set_cmd() -> Command {
         .arg(global_arg())
 }
 
+pub fn doctor_cmd() -> Command {
+    Command::new("doctor")
+        .about("Check config.yaml for unknown keys and invalid values [read-only]")
+        .long_about(
+            "Check config.yaml for unknown keys and invalid values [read-only].\n\n\
+             Reports every problem at once, each with its line: keys wsp doesn't know \
+             (usually typos, silently ignored otherwise), values of the wrong type, \
+             invalid repo URLs, depends_on entries naming repos that aren't registered, \
+             and missing shared file sources. Exits non-zero if anything is found.",
+        )
+}
+
+/// `wsp config doctor`: the config checks of `wsp doctor` on their own.
+fn run_doctor(paths: &Paths) -> Result<Output> {
+    eprintln!("Checking {}...", paths.config_path.display());
+    let mut checks = Vec::new();
+    super::doctor::check_config_problems(paths, &mut checks);
+    Ok(Output::Doctor(super::doctor::build_output(checks, 0)))
+}
+
+pub fn edit_cmd() -> Command {
+    Command::new("edit")
+        .about("Edit config.yaml in your editor, saving it only if it's valid")
+        .long_about(
+            "Edit config.yaml in your editor, saving it only if it's valid.\n\n\
+             Opens a copy of config.yaml in the configured editor ($VISUAL, then $EDITOR), \
+             then runs the checks of `wsp config doctor` on the result. Warnings are \
+             shown and the edit is saved. Errors are shown with a diff of what changed, \
+             and config.yaml is left untouched: in a terminal you're asked to edit \
+             again, otherwise the edit is kept as config.yaml.edit and the next \
+             `wsp config edit` picks it up where you left off.\n\n\
+             The editor must wait for the file to be closed (e.g. `code --wait`).",
+        )
+}
+
+/// `wsp config edit`: hand-edit config.yaml through a scratch copy that only
+/// replaces the real file once it passes the config checks.
+fn run_edit(paths: &Paths) -> Result<Output> {
+    let config_path = &paths.config_path;
+    let original = match std::fs::read_to_string(config_path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let edit_path = edit_path(config_path);
+    if edit_path.exists() {
+        eprintln!("Resuming the unsaved edit in {}", edit_path.display());
+    } else {
+        if let Some(dir) = edit_path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&edit_path, &original)?;
+    }
+
+    // A config.yaml that doesn't load has no editor setting to offer.
+    let configured = config::Config::load_from(config_path)
+        .ok()
+        .and_then(|c| c.editor);
+    let (visual, env_editor) = (std::env::var("VISUAL").ok(), std::env::var("EDITOR").ok());
+    let editor = super::open::resolve_editor(
+        None,
+        configured.as_deref(),
+        visual.as_deref(),
+        env_editor.as_deref(),
+    )?;
+
+    let base = paths.data_dir();
+    loop {
+        super::open::run_editor(editor, std::slice::from_ref(&edit_path))?;
+        let edited = std::fs::read_to_string(&edit_path)?;
+        if edited == original {
+            std::fs::remove_file(&edit_path)?;
+            return Ok(Output::Mutation(MutationOutput::new(
+                "config.yaml unchanged",
+            )));
+        }
+
+        let problems = crate::configcheck::check(&edited, base);
+        for p in &problems {
+            let mark = match p.severity {
+                crate::configcheck::Severity::Error => "✗",
+                crate::configcheck::Severity::Warning => "⚠",
+            };
+            eprintln!("  {} {}", mark, p);
+        }
+        if !problems
+            .iter()
+            .any(|p| p.severity == crate::configcheck::Severity::Error)
+        {
+            filelock::replace_config(config_path, &original, &edited)?;
+            std::fs::remove_file(&edit_path)?;
+            return Ok(Output::Mutation(MutationOutput::new("config.yaml saved")));
+        }
+
+        eprintln!();
+        print_edit_diff(config_path, &original, &edit_path);
+        if !std::io::stdin().is_terminal() {
+            bail!(
+                "config.yaml has errors and was not saved; the edit is kept in {} \
+                 and `wsp config edit` resumes it",
+                edit_path.display()
+            );
+        }
+        eprint!("Edit again? [Y/n]: ");
+        let answer = crate::util::read_stdin_line();
+        if matches!(answer.trim().to_lowercase().as_str(), "n" | "no") {
+            bail!(
+                "config.yaml was not saved; the edit is kept in {}",
+                edit_path.display()
+            );
+        }
+    }
+}
+
+/// The scratch copy `wsp config edit` works on, next to config.yaml.
+fn edit_path(config_path: &Path) -> std::path::PathBuf {
+    let mut p = config_path.as_os_str().to_os_string();
+    p.push(".edit");
+    std::path::PathBuf::from(p)
+}
+
+/// Shows what the edit changed, via `git diff --no-index`. Best-effort: a
+/// missing git only loses the diff.
+fn print_edit_diff(config_path: &Path, original: &str, edit_path: &Path) {
+    let Ok(mut before) = tempfile::NamedTempFile::new() else {
+        return;
+    };
+    if before.write_all(original.as_bytes()).is_err() {
+        return;
+    }
+    let out = std::process::Command::new("git")
+        .args(["diff", "--no-index", "--no-color", "--"])
+        .arg(before.path())
+        .arg(edit_path)
+        .output();
+    if let Ok(out) = out {
+        // The temp file's name means nothing to the reader.
+        let diff = String::from_utf8_lossy(&out.stdout).replace(
+            &before.path().display().to_string(),
+            &config_path.display().to_string(),
+        );
+        eprint!("{}", diff);
+    }
+}
+
 pub fn unset_cmd() -> Command {
     Command::new("unset")
         .about("Unset a config value")
@@ -139,17 +332,23 @@ pub fn unset_cmd() -> Command {
 // Workspace-scoped config operations
 // ---------------------------------------------------------------------------
 
-fn run_set_workspace(matches: &ArgMatches, ws_dir: &Path, _paths: &Paths) -> Result<Output> {
+fn run_set_workspace(matches: &ArgMatches, ws_dir: &Path, paths: &Paths) -> Result<Output> {
     let key = matches.get_one::<String>("key").unwrap();
     let value = matches.get_one::<String>("value").unwrap();
 
+    if let Some(repo) = exec_dir_repo(key) {
+        return run_set_exec_dir(ws_dir, repo, value);
+    }
+
     if is_global_only_key(key) {
         bail!("{} is a global-only key; use --global to set it", key);
     }
 
-    template::validate_template_config_key(key)?;
-
     let normalized = template::normalize_key(key);
+    if !WORKSPACE_SETTING_KEYS.contains(&normalized.as_str()) {
+        template::validate_template_config_key(key)?;
+    }
+    let cfg = config::Config::load_from(&paths.config_path)?;
 
     let meta = filelock::with_metadata(ws_dir, |meta| {
         let config = meta
@@ -162,6 +361,15 @@ fn run_set_workspace(matches: &ArgMatches, ws_dir: &Path, _paths: &Paths) -> Res
                 _ => bail!("sync-strategy must be 'rebase' or 'merge'"),
             }
             config.sync_strategy = Some(value.to_string());
+        } else if normalized == "branch-prefix" {
+            config.branch_prefix = Some(value.to_string());
+        } else if normalized == "agent-md" {
+            let enabled: bool = value
+                .parse()
+                .map_err(|_| anyhow::anyhow!("value must be true or false"))?;
+            config.agent_md = Some(enabled);
+        } else if normalized == "run.jobs" {
+            config.run_jobs = Some(parse_jobs(value)?);
         } else if let Some(lang) = normalized.strip_prefix("lang.") {
             let known = crate::lang::integration_names();
             if !known.iter().any(|n| n == lang) {
@@ -180,6 +388,22 @@ fn run_set_workspace(matches: &ArgMatches, ws_dir: &Path, _paths: &Paths) -> Res
             }
             let gc = config.git_config.get_or_insert_with(BTreeMap::new);
             gc.insert(git_key.to_string(), value.to_string());
+        } else if let Some(name) = task::key_name(key) {
+            task::validate_name(name)?;
+            let tasks = config.tasks.get_or_insert_with(BTreeMap::new);
+            tasks.entry(name.to_string()).or_default().run = value.to_string();
+        } else if let Some(repo) = deps::key_repo(key) {
+            let identities: Vec<String> = meta.repos.keys().cloned().collect();
+            let (identity, on) = deps::resolve_entry(repo, value, &identities)?;
+            // Validate the merged graph now rather than failing on the next exec
+            let mut depends_on = cfg.depends_on.clone().unwrap_or_default();
+            depends_on.extend(config.depends_on.clone().unwrap_or_default());
+            depends_on.insert(identity.clone(), on.clone());
+            deps::order(Some(&depends_on), &identities)?;
+            config
+                .depends_on
+                .get_or_insert_with(BTreeMap::new)
+                .insert(identity, on);
         }
         Ok(())
     })?;
@@ -198,7 +422,15 @@ fn run_set_workspace(matches: &ArgMatches, ws_dir: &Path, _paths: &Paths) -> Res
 fn run_get_workspace(matches: &ArgMatches, ws_dir: &Path, paths: &Paths) -> Result<Output> {
     let key = matches.get_one::<String>("key").unwrap();
     let meta = workspace::load_metadata(ws_dir)?;
+
     let cfg = config::Config::load_from(&paths.config_path)?;
+    if let Some(repo) = exec_dir_repo(key) {
+        let identity = resolve_workspace_repo(&meta, repo)?;
+        return Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: meta.exec_dir(&cfg, &identity).map(|s| s.to_string()),
+        }));
+    }
     let effective = meta.apply_workspace_config(&cfg);
 
     // For workspace-scoped keys, return effective value; for global-only, delegate.
@@ -216,6 +448,18 @@ fn run_get_workspace(matches: &ArgMatches, ws_dir: &Path, paths: &Paths) -> Resu
                     .to_string(),
             ),
         })),
+        "branch-prefix" => Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: effective.branch_prefix,
+        })),
+        "agent-md" => Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: Some(effective.agent_md.unwrap_or(true).to_string()),
+        })),
+        "run.jobs" => Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: effective.run_jobs.map(|n| n.to_string()),
+        })),
         k if k.starts_with("lang.") => {
             let lang = &k["lang.".len()..];
             let enabled = effective
@@ -237,6 +481,25 @@ fn run_get_workspace(matches: &ArgMatches, ws_dir: &Path, paths: &Paths) -> Resu
                 value: effective_gc.get(git_key).cloned(),
             }))
         }
+        _ if let Some(name) = task::key_name(key) => Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: effective
+                .tasks
+                .as_ref()
+                .and_then(|m| m.get(name))
+                .map(|t| t.run.clone()),
+        })),
+        _ if let Some(repo) = deps::key_repo(key) => {
+            let identity = resolve_workspace_repo(&meta, repo)?;
+            Ok(Output::ConfigGet(ConfigGetOutput {
+                key: key.clone(),
+                value: effective
+                    .depends_on
+                    .as_ref()
+                    .and_then(|m| m.get(&identity))
+                    .map(|d| deps::format_value(d)),
+            }))
+        }
         // Global-only keys: delegate to global get
         _ => run_get(matches, paths),
     }
@@ -245,18 +508,26 @@ fn run_get_workspace(matches: &ArgMatches, ws_dir: &Path, paths: &Paths) -> Resu
 fn run_unset_workspace(matches: &ArgMatches, ws_dir: &Path, paths: &Paths) -> Result<Output> {
     let key = matches.get_one::<String>("key").unwrap();
 
+    if let Some(repo) = exec_dir_repo(key) {
+        return run_unset_exec_dir(ws_dir, repo, key);
+    }
+
     if is_global_only_key(key) {
         bail!("{} is a global-only key; use --global to unset it", key);
     }
 
-    template::validate_template_config_key(key)?;
-
     let normalized = template::normalize_key(key);
+    if !WORKSPACE_SETTING_KEYS.contains(&normalized.as_str()) {
+        template::validate_template_config_key(key)?;
+    }
     let cfg = config::Config::load_from(&paths.config_path)?;
 
     warn_if_deprecated(key, &normalized);
 
     filelock::with_metadata(ws_dir, |meta| {
+        let dep_identity = deps::key_repo(key)
+            .map(|repo| resolve_workspace_repo(meta, repo))
+            .transpose()?;
         let config = match &mut meta.config {
             Some(c) => c,
             None => return Ok(()),
@@ -264,6 +535,12 @@ fn run_unset_workspace(matches: &ArgMatches, ws_dir: &Path, paths: &Paths) -> Re
 
         if normalized == "sync-strategy" {
             config.sync_strategy = None;
+        } else if normalized == "branch-prefix" {
+            config.branch_prefix = None;
+        } else if normalized == "agent-md" {
+            config.agent_md = None;
+        } else if normalized == "run.jobs" {
+            config.run_jobs = None;
         } else if let Some(lang) = normalized.strip_prefix("lang.") {
             if let Some(ref mut m) = config.language_integrations {
                 m.remove(lang);
@@ -278,6 +555,20 @@ fn run_unset_workspace(matches: &ArgMatches, ws_dir: &Path, paths: &Paths) -> Re
             if m.is_empty() {
                 config.git_config = None;
             }
+        } else if let Some(name) = task::key_name(key)
+            && let Some(ref mut m) = config.tasks
+        {
+            m.remove(name);
+            if m.is_empty() {
+                config.tasks = None;
+            }
+        } else if let Some(identity) = dep_identity
+            && let Some(ref mut m) = config.depends_on
+        {
+            m.remove(&identity);
+            if m.is_empty() {
+                config.depends_on = None;
+            }
         }
 
         // Clean up empty config
@@ -295,6 +586,15 @@ fn run_unset_workspace(matches: &ArgMatches, ws_dir: &Path, paths: &Paths) -> Re
             let global = cfg.sync_strategy.as_deref().unwrap_or("rebase");
             format!(" (using global: {})", global)
         }
+        "branch-prefix" => match cfg.branch_prefix {
+            Some(ref prefix) => format!(" (using global: {})", prefix),
+            None => String::new(),
+        },
+        "agent-md" => format!(" (using global: {})", cfg.agent_md.unwrap_or(true)),
+        "run.jobs" => match cfg.run_jobs {
+            Some(jobs) => format!(" (using global: {})", jobs),
+            None => String::new(),
+        },
         k if k.starts_with("lang.") => {
             let lang = &k["lang.".len()..];
             let global = cfg
@@ -321,6 +621,22 @@ fn run_unset_workspace(matches: &ArgMatches, ws_dir: &Path, paths: &Paths) -> Re
                 format!(" (using global: {})", global)
             }
         }
+        _ if let Some(name) = task::key_name(key) => {
+            match cfg.tasks.as_ref().and_then(|m| m.get(name)) {
+                Some(t) => format!(" (using global: {})", t.run),
+                None => String::new(),
+            }
+        }
+        _ if let Some(repo) = deps::key_repo(key) => {
+            let identities: Vec<String> = cfg.repos.keys().cloned().collect();
+            let global = crate::giturl::resolve(repo, &identities)
+                .ok()
+                .and_then(|id| cfg.depends_on.as_ref()?.get(&id));
+            match global {
+                Some(d) => format!(" (using global: {})", deps::format_value(d)),
+                None => String::new(),
+            }
+        }
         _ => String::new(),
     };
 
@@ -334,9 +650,23 @@ fn run_list_workspace(_matches: &ArgMatches, ws_dir: &Path, paths: &Paths) -> Re
     let ws_config = meta.config.as_ref();
 
     let mut entries = vec![
+        ConfigListEntry {
+            key: "branch-prefix".into(),
+            value: ws_config
+                .and_then(|c| c.branch_prefix.as_deref())
+                .or(cfg.branch_prefix.as_deref())
+                .unwrap_or("(not set)")
+                .to_string(),
+            source: ws_config
+                .and_then(|c| c.branch_prefix.as_ref())
+                .map(|_| "workspace".to_string()),
+            experimental: false,
+        },
         entry(
-            "branch-prefix",
-            cfg.branch_prefix.as_deref().unwrap_or("(not set)"),
+            "branch-template",
+            cfg.branch_template
+                .as_deref()
+                .unwrap_or(workspace::DEFAULT_BRANCH_TEMPLATE),
         ),
         entry(
             "workspaces-dir",
@@ -354,11 +684,58 @@ fn run_list_workspace(_matches: &ArgMatches, ws_dir: &Path, paths: &Paths) -> Re
                 .map(|_| "workspace".to_string()),
             experimental: false,
         },
-        entry("agent-md", &cfg.agent_md.unwrap_or(true).to_string()),
+        ConfigListEntry {
+            key: "agent-md".into(),
+            value: ws_config
+                .and_then(|c| c.agent_md)
+                .or(cfg.agent_md)
+                .unwrap_or(true)
+                .to_string(),
+            source: ws_config
+                .and_then(|c| c.agent_md)
+                .map(|_| "workspace".to_string()),
+            experimental: false,
+        },
+        entry(
+            "commit-trailer",
+            &cfg.commit_trailer.unwrap_or(false).to_string(),
+        ),
+        entry("push-guard", &cfg.push_guard.unwrap_or(false).to_string()),
         entry(
             "gc.retention-days",
             &cfg.gc_retention_days.unwrap_or(7).to_string(),
         ),
+        ConfigListEntry {
+            key: "run.jobs".into(),
+            value: ws_config
+                .and_then(|c| c.run_jobs)
+                .or(cfg.run_jobs)
+                .map_or("(not set)".into(), |n| n.to_string()),
+            source: ws_config
+                .and_then(|c| c.run_jobs)
+                .map(|_| "workspace".to_string()),
+            experimental: false,
+        },
+        entry(
+            "fetch.jobs",
+            &cfg.fetch_jobs.unwrap_or(fetch::DEFAULT_JOBS).to_string(),
+        ),
+        entry(
+            "fetch.retries",
+            &cfg.fetch_retries
+                .unwrap_or(fetch::DEFAULT_RETRIES)
+                .to_string(),
+        ),
+        entry("fetch.timeout", &cfg.fetch_timeout.unwrap_or(0).to_string()),
+        entry("fetch.max-age", cfg.fetch_max_age.as_deref().unwrap_or("0")),
+        entry("editor", cfg.editor.as_deref().unwrap_or("(not set)")),
+        entry(
+            "issue.name-template",
+            cfg.issue_name_template
+                .as_deref()
+                .unwrap_or(issue::DEFAULT_NAME_TEMPLATE),
+        ),
+        entry("jira.url", cfg.jira_url.as_deref().unwrap_or("(not set)")),
     ];
 
     // shell features (global-only, experimental)
@@ -396,6 +773,71 @@ fn run_list_workspace(_matches: &ArgMatches, ws_dir: &Path, paths: &Paths) -> Re
         });
     }
 
+    // tasks: merge workspace overrides over global
+    let ws_tasks = ws_config.and_then(|c| c.tasks.as_ref());
+    let mut task_names: Vec<&String> = cfg.tasks.iter().flat_map(|m| m.keys()).collect();
+    task_names.extend(ws_tasks.iter().flat_map(|m| m.keys()));
+    task_names.sort();
+    task_names.dedup();
+    for name in task_names {
+        let (def, from_ws) = match ws_tasks.and_then(|m| m.get(name)) {
+            Some(def) => (def, true),
+            None => (&cfg.tasks.as_ref().unwrap()[name], false),
+        };
+        entries.push(ConfigListEntry {
+            key: format!("task.{}", name),
+            value: def.run.clone(),
+            source: from_ws.then(|| "workspace".to_string()),
+            experimental: false,
+        });
+    }
+
+    // depends-on: merge workspace overrides over global, by identity
+    let ws_deps = ws_config.and_then(|c| c.depends_on.as_ref());
+    let mut dep_repos: Vec<&String> = cfg.depends_on.iter().flat_map(|m| m.keys()).collect();
+    dep_repos.extend(ws_deps.iter().flat_map(|m| m.keys()));
+    dep_repos.sort();
+    dep_repos.dedup();
+    for repo in dep_repos {
+        let (on, from_ws) = match ws_deps.and_then(|m| m.get(repo)) {
+            Some(on) => (on, true),
+            None => (&cfg.depends_on.as_ref().unwrap()[repo], false),
+        };
+        entries.push(ConfigListEntry {
+            key: format!("depends-on.{}", repo),
+            value: deps::format_value(on),
+            source: from_ws.then(|| "workspace".to_string()),
+            experimental: false,
+        });
+    }
+
+    // hooks: global-only, so a workspace can't add commands that run on
+    // their own
+    for (event, def) in cfg.hooks.iter().flatten() {
+        entries.push(ConfigListEntry {
+            key: format!("hook.{}", event),
+            value: def.run.clone(),
+            source: None,
+            experimental: false,
+        });
+    }
+
+    // exec-dir.<repo>: the workspace's own, else the global one, by identity
+    for identity in meta.repos.keys() {
+        let Some(subdir) = meta.exec_dir(&cfg, identity) else {
+            continue;
+        };
+        let from_ws = ws_config
+            .and_then(|c| c.exec_dirs.as_ref())
+            .is_some_and(|m| m.contains_key(identity));
+        entries.push(ConfigListEntry {
+            key: format!("exec-dir.{}", identity),
+            value: subdir.to_string(),
+            source: from_ws.then(|| "workspace".to_string()),
+            experimental: false,
+        });
+    }
+
     // language integrations: merge workspace overrides
     for name in crate::lang::integration_names() {
         let from_ws = ws_config
@@ -426,9 +868,82 @@ fn run_list_workspace(_matches: &ArgMatches, ws_dir: &Path, paths: &Paths) -> Re
         });
     }
 
+    mark_env_entries(&mut entries, &cfg);
     Ok(Output::ConfigList(ConfigListOutput { entries }))
 }
 
+/// Annotates the entries a `WSP_*` variable set, unless the workspace
+/// overrides them too.
+fn mark_env_entries(entries: &mut [ConfigListEntry], cfg: &config::Config) {
+    for e in entries.iter_mut().filter(|e| e.source.is_none()) {
+        if cfg.from_env.contains(&e.key.as_str()) {
+            e.source = Some("env".to_string());
+        }
+    }
+}
+
+/// Parses a `run.jobs` value: how many repos `wsp run` runs at once.
+fn parse_jobs(value: &str) -> Result<usize> {
+    match value.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => bail!("run.jobs must be a positive integer"),
+    }
+}
+
+fn resolve_workspace_repo(meta: &workspace::Metadata, repo: &str) -> Result<String> {
+    let identities: Vec<String> = meta.repos.keys().cloned().collect();
+    crate::giturl::resolve(repo, &identities)
+}
+
+fn run_set_exec_dir(ws_dir: &Path, repo: &str, value: &str) -> Result<Output> {
+    workspace::validate_exec_dir(value)?;
+
+    let mut identity = String::new();
+    let meta = filelock::with_metadata(ws_dir, |meta| {
+        identity = resolve_workspace_repo(meta, repo)?;
+        let config = meta
+            .config
+            .get_or_insert_with(template::TemplateConfig::default);
+        config
+            .exec_dirs
+            .get_or_insert_with(BTreeMap::new)
+            .insert(identity.clone(), value.to_string());
+        Ok(())
+    })?;
+
+    let message = format!(
+        "exec-dir.{} = {} (workspace: {})",
+        identity, value, meta.name
+    );
+    Ok(Output::Mutation(MutationOutput::new(message)))
+}
+
+fn run_unset_exec_dir(ws_dir: &Path, repo: &str, key: &str) -> Result<Output> {
+    filelock::with_metadata(ws_dir, |meta| {
+        let identity = resolve_workspace_repo(meta, repo)?;
+        let config = match &mut meta.config {
+            Some(c) => c,
+            None => return Ok(()),
+        };
+        if let Some(ref mut m) = config.exec_dirs {
+            m.remove(&identity);
+            if m.is_empty() {
+                config.exec_dirs = None;
+            }
+        }
+        if *config == template::TemplateConfig::default() {
+            meta.config = None;
+        }
+        Ok(())
+    })?;
+
+    let message = format!(
+        "{} unset in workspace (exec falls back to the global exec-dir, else the repo root)",
+        key
+    );
+    Ok(Output::Mutation(MutationOutput::new(message)))
+}
+
 /// Helper to create a simple config list entry.
 fn entry(key: &str, value: &str) -> ConfigListEntry {
     ConfigListEntry {
@@ -460,6 +975,12 @@ pub fn run_list(_matches: &ArgMatches, paths: &Paths) -> Result<Output> {
             "branch-prefix",
             cfg.branch_prefix.as_deref().unwrap_or("(not set)"),
         ),
+        entry(
+            "branch-template",
+            cfg.branch_template
+                .as_deref()
+                .unwrap_or(workspace::DEFAULT_BRANCH_TEMPLATE),
+        ),
         entry(
             "workspaces-dir",
             &paths.workspaces_dir.display().to_string(),
@@ -469,10 +990,39 @@ pub fn run_list(_matches: &ArgMatches, paths: &Paths) -> Result<Output> {
             cfg.sync_strategy.as_deref().unwrap_or("rebase"),
         ),
         entry("agent-md", &cfg.agent_md.unwrap_or(true).to_string()),
+        entry(
+            "commit-trailer",
+            &cfg.commit_trailer.unwrap_or(false).to_string(),
+        ),
+        entry("push-guard", &cfg.push_guard.unwrap_or(false).to_string()),
         entry(
             "gc.retention-days",
             &cfg.gc_retention_days.unwrap_or(7).to_string(),
         ),
+        entry(
+            "run.jobs",
+            &cfg.run_jobs.map_or("(not set)".into(), |n| n.to_string()),
+        ),
+        entry(
+            "fetch.jobs",
+            &cfg.fetch_jobs.unwrap_or(fetch::DEFAULT_JOBS).to_string(),
+        ),
+        entry(
+            "fetch.retries",
+            &cfg.fetch_retries
+                .unwrap_or(fetch::DEFAULT_RETRIES)
+                .to_string(),
+        ),
+        entry("fetch.timeout", &cfg.fetch_timeout.unwrap_or(0).to_string()),
+        entry("fetch.max-age", cfg.fetch_max_age.as_deref().unwrap_or("0")),
+        entry("editor", cfg.editor.as_deref().unwrap_or("(not set)")),
+        entry(
+            "issue.name-template",
+            cfg.issue_name_template
+                .as_deref()
+                .unwrap_or(issue::DEFAULT_NAME_TEMPLATE),
+        ),
+        entry("jira.url", cfg.jira_url.as_deref().unwrap_or("(not set)")),
     ];
 
     // shell features (always shown, no gate)
@@ -502,6 +1052,77 @@ pub fn run_list(_matches: &ArgMatches, paths: &Paths) -> Result<Output> {
         entries.push(entry(&format!("lang.{}", name), &enabled.to_string()));
     }
 
+    for (name, def) in cfg.tasks.iter().flatten() {
+        entries.push(entry(&format!("task.{}", name), &def.run));
+    }
+
+    for (repo, on) in cfg.depends_on.iter().flatten() {
+        entries.push(entry(
+            &format!("depends-on.{}", repo),
+            &deps::format_value(on),
+        ));
+    }
+
+    for (event, def) in cfg.hooks.iter().flatten() {
+        entries.push(entry(&format!("hook.{}", event), &def.run));
+    }
+
+    for (identity, repo) in &cfg.repos {
+        if let Some(ref setup) = repo.setup {
+            entries.push(entry(&format!("setup.{}", identity), setup));
+        }
+        if let Some(ref subdir) = repo.exec_dir {
+            entries.push(entry(&format!("exec-dir.{}", identity), subdir));
+        }
+    }
+
+    for (dest, source) in cfg.files.iter().flatten() {
+        entries.push(entry(&format!("file.{}", dest), source));
+    }
+
+    for (host, kind) in cfg.forges.iter().flatten() {
+        entries.push(entry(&format!("forge.{}", host), kind));
+    }
+
+    for (prefix, base) in cfg.url_rewrites.iter().flatten() {
+        entries.push(entry(&format!("url-rewrite.{}", prefix), base));
+    }
+
+    for (host, settings) in cfg.hosts.iter().flatten() {
+        if let Some(ref proxy) = settings.proxy {
+            entries.push(entry(&format!("host.{}.proxy", host), proxy));
+        }
+        if let Some(secs) = settings.timeout {
+            entries.push(entry(&format!("host.{}.timeout", host), &secs.to_string()));
+        }
+        for (name, value) in settings.http.iter().flatten() {
+            entries.push(entry(&format!("host.{}.http.{}", host, name), value));
+        }
+    }
+
+    for (pattern, prefix) in cfg.branch_prefixes.iter().flatten() {
+        entries.push(entry(&format!("branch-prefix.{}", pattern), prefix));
+    }
+
+    for (name, dir) in cfg.template_workspaces_dirs.iter().flatten() {
+        entries.push(entry(&format!("workspaces-dir.{}", name), dir));
+    }
+
+    for (pattern, committer) in cfg.committers.iter().flatten() {
+        let fields = [
+            ("name", &committer.name),
+            ("email", &committer.email),
+            ("signing-key", &committer.signing_key),
+            ("signing-format", &committer.signing_format),
+        ];
+        for (field, value) in fields {
+            if let Some(value) = value {
+                entries.push(entry(&format!("committer.{}.{}", pattern, field), value));
+            }
+        }
+    }
+
+    mark_env_entries(&mut entries, &cfg);
     Ok(Output::ConfigList(ConfigListOutput { entries }))
 }
 
@@ -516,6 +1137,13 @@ pub fn run_get(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
             key: key.clone(),
             value: cfg.branch_prefix,
         })),
+        "branch-template" => Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: Some(
+                cfg.branch_template
+                    .unwrap_or_else(|| workspace::DEFAULT_BRANCH_TEMPLATE.to_string()),
+            ),
+        })),
         "workspaces-dir" => Ok(Output::ConfigGet(ConfigGetOutput {
             key: key.clone(),
             value: Some(paths.workspaces_dir.display().to_string()),
@@ -528,10 +1156,42 @@ pub fn run_get(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
             key: key.clone(),
             value: Some(cfg.agent_md.unwrap_or(true).to_string()),
         })),
+        "commit-trailer" => Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: Some(cfg.commit_trailer.unwrap_or(false).to_string()),
+        })),
+        "push-guard" => Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: Some(cfg.push_guard.unwrap_or(false).to_string()),
+        })),
         "gc.retention-days" => Ok(Output::ConfigGet(ConfigGetOutput {
             key: key.clone(),
             value: Some(cfg.gc_retention_days.unwrap_or(7).to_string()),
         })),
+        "run.jobs" => Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: cfg.run_jobs.map(|n| n.to_string()),
+        })),
+        "fetch.jobs" => Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: Some(cfg.fetch_jobs.unwrap_or(fetch::DEFAULT_JOBS).to_string()),
+        })),
+        "fetch.retries" => Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: Some(
+                cfg.fetch_retries
+                    .unwrap_or(fetch::DEFAULT_RETRIES)
+                    .to_string(),
+            ),
+        })),
+        "fetch.timeout" => Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: Some(cfg.fetch_timeout.unwrap_or(0).to_string()),
+        })),
+        "fetch.max-age" => Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: Some(cfg.fetch_max_age.clone().unwrap_or_else(|| "0".into())),
+        })),
         "shell.tmux" => {
             let mode = cfg.shell_tmux_mode().unwrap_or("false");
             Ok(Output::ConfigGet(ConfigGetOutput {
@@ -543,6 +1203,21 @@ pub fn run_get(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
             key: key.clone(),
             value: Some(cfg.shell_prompt_enabled().to_string()),
         })),
+        "editor" => Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: cfg.editor,
+        })),
+        "issue.name-template" => Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: Some(
+                cfg.issue_name_template
+                    .unwrap_or_else(|| issue::DEFAULT_NAME_TEMPLATE.to_string()),
+            ),
+        })),
+        "jira.url" => Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: cfg.jira_url,
+        })),
         k if k.starts_with("lang.") => {
             let lang = &k["lang.".len()..];
             let enabled = cfg
@@ -564,15 +1239,124 @@ pub fn run_get(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
                 value: effective.get(git_key).cloned(),
             }))
         }
-        // Legacy: still accept "experimental" and "experimental.*" for backward compat
-        "experimental" => {
-            let enabled = cfg.experimental.as_ref().is_some_and(|e| e.enabled);
+        _ if let Some(name) = task::key_name(key) => Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: cfg
+                .tasks
+                .as_ref()
+                .and_then(|m| m.get(name))
+                .map(|t| t.run.clone()),
+        })),
+        _ if let Some(repo) = deps::key_repo(key) => {
+            let identities: Vec<String> = cfg.repos.keys().cloned().collect();
+            let identity = crate::giturl::resolve(repo, &identities)?;
+            Ok(Output::ConfigGet(ConfigGetOutput {
+                key: key.clone(),
+                value: cfg
+                    .depends_on
+                    .as_ref()
+                    .and_then(|m| m.get(&identity))
+                    .map(|d| deps::format_value(d)),
+            }))
+        }
+        _ if let Some(dest) = files::key_dest(key) => Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: cfg.files.as_ref().and_then(|m| m.get(dest)).cloned(),
+        })),
+        _ if let Some(host) = forge::key_host(key) => Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: cfg.forges.as_ref().and_then(|m| m.get(host)).cloned(),
+        })),
+        _ if let Some(prefix) = config::key_url_rewrite(key) => {
+            Ok(Output::ConfigGet(ConfigGetOutput {
+                key: key.clone(),
+                value: cfg
+                    .url_rewrites
+                    .as_ref()
+                    .and_then(|m| m.get(prefix))
+                    .cloned(),
+            }))
+        }
+        _ if let Some((host, setting)) = config::key_host_setting(key) => {
+            let settings = cfg.hosts.as_ref().and_then(|m| m.get(host));
+            let value = settings.and_then(|s| match setting {
+                config::HostSetting::Proxy => s.proxy.clone(),
+                config::HostSetting::Timeout => s.timeout.map(|n| n.to_string()),
+                config::HostSetting::Http(name) => s.http.as_ref()?.get(name).cloned(),
+            });
+            Ok(Output::ConfigGet(ConfigGetOutput {
+                key: key.clone(),
+                value,
+            }))
+        }
+        _ if let Some((pattern, field)) = config::key_committer(key) => {
+            let mut committer = cfg
+                .committers
+                .as_ref()
+                .and_then(|m| m.get(pattern))
+                .cloned()
+                .unwrap_or_default();
+            Ok(Output::ConfigGet(ConfigGetOutput {
+                key: key.clone(),
+                value: committer.field_mut(field)?.take(),
+            }))
+        }
+        _ if let Some(pattern) = workspace::key_prefix_pattern(key) => {
+            Ok(Output::ConfigGet(ConfigGetOutput {
+                key: key.clone(),
+                value: cfg
+                    .branch_prefixes
+                    .as_ref()
+                    .and_then(|m| m.get(pattern))
+                    .cloned(),
+            }))
+        }
+        _ if let Some(name) = config::key_template_root(key) => {
+            Ok(Output::ConfigGet(ConfigGetOutput {
+                key: key.clone(),
+                value: cfg
+                    .template_workspaces_dirs
+                    .as_ref()
+                    .and_then(|m| m.get(name))
+                    .cloned(),
+            }))
+        }
+        _ if let Some(repo) = bootstrap::key_repo(key) => {
+            let identities: Vec<String> = cfg.repos.keys().cloned().collect();
+            let identity = crate::giturl::resolve(repo, &identities)?;
+            Ok(Output::ConfigGet(ConfigGetOutput {
+                key: key.clone(),
+                value: cfg.repos[&identity].setup.clone(),
+            }))
+        }
+        _ if let Some(repo) = exec_dir_repo(key) => {
+            let identities: Vec<String> = cfg.repos.keys().cloned().collect();
+            let identity = crate::giturl::resolve(repo, &identities)?;
+            Ok(Output::ConfigGet(ConfigGetOutput {
+                key: key.clone(),
+                value: cfg.repos[&identity].exec_dir.clone(),
+            }))
+        }
+        k if let Some(event) = hooks::key_event(k) => Ok(Output::ConfigGet(ConfigGetOutput {
+            key: key.clone(),
+            value: cfg
+                .hooks
+                .as_ref()
+                .and_then(|m| m.get(event))
+                .map(|h| h.run.clone()),
+        })),
+        // Legacy: still accept "experimental" and "experimental.*" for backward compat
+        "experimental" => {
+            let enabled = cfg.experimental.as_ref().is_some_and(|e| e.enabled);
             Ok(Output::ConfigGet(ConfigGetOutput {
                 key: key.clone(),
                 value: Some(enabled.to_string()),
             }))
         }
-        _ => bail!("unknown config key: {}", key),
+        _ => Err(error::tag(
+            ErrorKind::Config,
+            anyhow::anyhow!("unknown config key: {}", key),
+        )),
     }
 }
 
@@ -585,11 +1369,7 @@ pub fn run_set(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     // Validate inputs before acquiring lock
     let (message, hint) = match normalized.as_str() {
         "branch-prefix" => {
-            let v = value.clone();
-            filelock::with_config(&paths.config_path, |cfg| {
-                cfg.branch_prefix = Some(v);
-                Ok(())
-            })?;
+            set_scalar(paths, &normalized, value)?;
             (
                 format!("branch-prefix = {}", value),
                 Some(
@@ -597,16 +1377,18 @@ pub fn run_set(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
                 ),
             )
         }
+        "branch-template" => {
+            set_scalar(paths, &normalized, value)?;
+            (
+                format!("branch-template = {}", value),
+                Some(
+                    "new workspaces will use this template; existing workspaces are unchanged"
+                        .into(),
+                ),
+            )
+        }
         "workspaces-dir" => {
-            let path = std::path::Path::new(value.as_str());
-            if !path.is_absolute() {
-                bail!("workspaces-dir must be an absolute path");
-            }
-            let v = value.clone();
-            filelock::with_config(&paths.config_path, |cfg| {
-                cfg.workspaces_dir = Some(v);
-                Ok(())
-            })?;
+            set_scalar(paths, &normalized, value)?;
             (
                 format!("workspaces-dir = {}", value),
                 Some(
@@ -615,66 +1397,119 @@ pub fn run_set(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
             )
         }
         "sync-strategy" => {
-            match value.as_str() {
-                "rebase" | "merge" => {}
-                _ => bail!("sync-strategy must be 'rebase' or 'merge'"),
-            }
-            let v = value.clone();
-            filelock::with_config(&paths.config_path, |cfg| {
-                cfg.sync_strategy = Some(v);
-                Ok(())
-            })?;
+            set_scalar(paths, &normalized, value)?;
             (
                 format!("sync-strategy = {}", value),
                 Some(format!("wsp sync will use {} for all workspaces", value)),
             )
         }
         "agent-md" => {
-            let enabled: bool = value
-                .parse()
-                .map_err(|_| anyhow::anyhow!("value must be true or false"))?;
+            set_scalar(paths, &normalized, value)?;
+            (
+                format!("agent-md = {}", value),
+                Some("takes effect on next wsp new or wsp sync".into()),
+            )
+        }
+        "commit-trailer" | "push-guard" => {
+            set_scalar(paths, &normalized, value)?;
+            (
+                format!("{} = {}", normalized, value),
+                Some(
+                    "applied to new clones; run wsp doctor --fix to update existing workspaces"
+                        .into(),
+                ),
+            )
+        }
+        "gc.retention-days" => {
+            let cfg = set_scalar(paths, &normalized, value)?;
+            let days = cfg.gc_retention_days.unwrap_or_default();
+            let hint = if days == 0 {
+                "gc disabled: deleted workspaces kept indefinitely until manually purged".into()
+            } else {
+                format!(
+                    "deleted workspaces recoverable via wsp recover for {} days",
+                    days
+                )
+            };
+            (format!("gc.retention-days = {}", days), Some(hint))
+        }
+        "run.jobs" => {
+            let cfg = set_scalar(paths, &normalized, value)?;
+            (
+                format!("run.jobs = {}", cfg.run_jobs.unwrap_or_default()),
+                Some(
+                    "wsp run will run up to this many repos at once unless --jobs is given".into(),
+                ),
+            )
+        }
+        "fetch.jobs" => {
+            let jobs = match value.parse::<usize>() {
+                Ok(n) if n > 0 => n,
+                _ => bail!("fetch.jobs must be a positive integer"),
+            };
             filelock::with_config(&paths.config_path, |cfg| {
-                cfg.agent_md = Some(enabled);
+                cfg.fetch_jobs = Some(jobs);
                 Ok(())
             })?;
             (
-                format!("agent-md = {}", enabled),
-                Some("takes effect on next wsp new or wsp sync".into()),
+                format!("fetch.jobs = {}", jobs),
+                Some("wsp repo fetch will fetch up to this many repos at once".into()),
             )
         }
-        "gc.retention-days" => {
-            let days: u32 = value
+        "fetch.retries" => {
+            let retries: u32 = value
                 .parse()
                 .map_err(|_| anyhow::anyhow!("value must be a non-negative integer"))?;
             filelock::with_config(&paths.config_path, |cfg| {
-                cfg.gc_retention_days = Some(days);
+                cfg.fetch_retries = Some(retries);
                 Ok(())
             })?;
-            let hint = if days == 0 {
-                "gc disabled: deleted workspaces kept indefinitely until manually purged".into()
+            let hint = if retries == 0 {
+                "fetches are not retried".into()
             } else {
                 format!(
-                    "deleted workspaces recoverable via wsp recover for {} days",
-                    days
+                    "a fetch that hits a network error is tried up to {} more times",
+                    retries
                 )
             };
-            (format!("gc.retention-days = {}", days), Some(hint))
+            (format!("fetch.retries = {}", retries), Some(hint))
         }
-        "shell.tmux" => {
-            if !config::SHELL_TMUX_VALUES.contains(&value.as_str()) {
-                bail!(
-                    "shell.tmux must be one of: {}",
-                    config::SHELL_TMUX_VALUES.join(", ")
-                );
-            }
-            let v = value.clone();
-            let is_enabled = value != "false";
+        "fetch.timeout" => {
+            let secs: u64 = value
+                .parse()
+                .map_err(|_| anyhow::anyhow!("value must be a non-negative integer (seconds)"))?;
             filelock::with_config(&paths.config_path, |cfg| {
-                cfg.shell_tmux = Some(v);
+                cfg.fetch_timeout = Some(secs);
                 Ok(())
             })?;
+            let hint = if secs == 0 {
+                "fetches run until git finishes".into()
+            } else {
+                format!("each fetch attempt gives up after {}s", secs)
+            };
+            (format!("fetch.timeout = {}", secs), Some(hint))
+        }
+        "fetch.max-age" => {
+            let age = crate::util::parse_duration(value)?;
+            let value = value.trim().to_string();
+            filelock::with_config(&paths.config_path, |cfg| {
+                cfg.fetch_max_age = Some(value.clone());
+                Ok(())
+            })?;
+            let hint = if age.is_zero() {
+                "mirrors are fetched every time".into()
+            } else {
+                format!(
+                    "commands that fetch mirrors first skip those fetched within {}",
+                    value
+                )
+            };
+            (format!("fetch.max-age = {}", value), Some(hint))
+        }
+        "shell.tmux" => {
+            set_scalar(paths, &normalized, value)?;
             let msg = format!("shell.tmux = {}", value);
-            let hint = if is_enabled {
+            let hint = if value != "false" {
                 note_if_experimental("shell.tmux");
                 Some("re-source your shell to activate: eval \"$(wsp completion zsh)\"".into())
             } else {
@@ -683,15 +1518,9 @@ pub fn run_set(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
             (msg, hint)
         }
         "shell.prompt" => {
-            let enabled: bool = value
-                .parse()
-                .map_err(|_| anyhow::anyhow!("value must be true or false"))?;
-            filelock::with_config(&paths.config_path, |cfg| {
-                cfg.shell_prompt = Some(enabled);
-                Ok(())
-            })?;
-            let msg = format!("shell.prompt = {}", enabled);
-            let hint = if enabled {
+            set_scalar(paths, &normalized, value)?;
+            let msg = format!("shell.prompt = {}", value);
+            let hint = if value == "true" {
                 note_if_experimental("shell.prompt");
                 Some("re-source your shell to activate: eval \"$(wsp completion zsh)\"".into())
             } else {
@@ -699,6 +1528,27 @@ pub fn run_set(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
             };
             (msg, hint)
         }
+        "editor" => {
+            set_scalar(paths, &normalized, value)?;
+            (
+                format!("editor = {}", value),
+                Some("`wsp open` will use this instead of $VISUAL/$EDITOR".into()),
+            )
+        }
+        "issue.name-template" => {
+            set_scalar(paths, &normalized, value)?;
+            (
+                format!("issue.name-template = {}", value),
+                Some("names workspaces created with `wsp new --issue`".into()),
+            )
+        }
+        "jira.url" => {
+            let cfg = set_scalar(paths, &normalized, value)?;
+            (
+                format!("jira.url = {}", cfg.jira_url.unwrap_or_default()),
+                Some("`wsp new --issue KEY-123` looks up Jira keys here".into()),
+            )
+        }
         k if k.starts_with("lang.") => {
             let lang = &k["lang.".len()..];
             let known = crate::lang::integration_names();
@@ -735,14 +1585,268 @@ pub fn run_set(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
                 Some("applied to new clones; run wsp doctor --fix to update existing repos".into()),
             )
         }
+        _ if let Some(name) = task::key_name(key) => {
+            task::validate_name(name)?;
+            let name = name.to_string();
+            let v = value.clone();
+            filelock::with_config(&paths.config_path, |cfg| {
+                let tasks = cfg.tasks.get_or_insert_with(BTreeMap::new);
+                tasks.entry(name.clone()).or_default().run = v;
+                Ok(())
+            })?;
+            (
+                format!("task.{} = {}", name, value),
+                Some(format!("run it with: wsp run {}", name)),
+            )
+        }
+        _ if let Some(repo) = deps::key_repo(key) => {
+            let repo = repo.to_string();
+            let v = value.clone();
+            let mut identity = String::new();
+            filelock::with_config(&paths.config_path, |cfg| {
+                let identities: Vec<String> = cfg.repos.keys().cloned().collect();
+                let (id, on) = deps::resolve_entry(&repo, &v, &identities)?;
+                let depends_on = cfg.depends_on.get_or_insert_with(BTreeMap::new);
+                depends_on.insert(id.clone(), on);
+                deps::order(Some(depends_on), &identities)?;
+                identity = id;
+                Ok(())
+            })?;
+            (
+                format!("depends-on.{} = {}", identity, value),
+                Some("exec, sync, and run process repos in dependency order".into()),
+            )
+        }
+        _ if let Some(dest) = files::key_dest(key) => {
+            files::validate_dest(dest)?;
+            let dest = dest.to_string();
+            let v = value.clone();
+            filelock::with_config(&paths.config_path, |cfg| {
+                let files = cfg.files.get_or_insert_with(BTreeMap::new);
+                files.insert(dest.clone(), v);
+                Ok(())
+            })?;
+            (
+                format!("file.{} = {}", dest, value),
+                Some("rendered into the root of new workspaces by wsp new".into()),
+            )
+        }
+        _ if let Some(host) = forge::key_host(key) => {
+            if host.is_empty() {
+                bail!("forge host cannot be empty");
+            }
+            let forge = forge::Forge::parse(value)?;
+            let host = host.to_string();
+            filelock::with_config(&paths.config_path, |cfg| {
+                let forges = cfg.forges.get_or_insert_with(BTreeMap::new);
+                forges.insert(host.clone(), forge.name().to_string());
+                Ok(())
+            })?;
+            (
+                format!("forge.{} = {}", host, forge.name()),
+                Some(format!("wsp pr uses {} for repos on {}", forge.cli(), host)),
+            )
+        }
+        _ if let Some(prefix) = config::key_url_rewrite(key) => {
+            if prefix.is_empty() {
+                bail!("url-rewrite prefix cannot be empty");
+            }
+            if value.is_empty() {
+                bail!("url-rewrite value cannot be empty");
+            }
+            let (prefix, v) = (prefix.to_string(), value.clone());
+            filelock::with_config(&paths.config_path, |cfg| {
+                let rewrites = cfg.url_rewrites.get_or_insert_with(BTreeMap::new);
+                rewrites.insert(prefix.clone(), v);
+                Ok(())
+            })?;
+            (
+                format!("url-rewrite.{} = {}", prefix, value),
+                Some(
+                    "mirrors fetch through it now; run wsp doctor --fix to apply it in existing clones"
+                        .into(),
+                ),
+            )
+        }
+        _ if let Some((host, setting)) = config::key_host_setting(key) => {
+            if host.is_empty() {
+                bail!("host cannot be empty");
+            }
+            let timeout = match setting {
+                config::HostSetting::Timeout => Some(
+                    value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|n| *n > 0)
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("timeout must be a positive number of seconds")
+                        })?,
+                ),
+                config::HostSetting::Http("") => bail!("http option name cannot be empty"),
+                _ => None,
+            };
+            let (h, v) = (host.to_string(), value.clone());
+            filelock::with_config(&paths.config_path, |cfg| {
+                let settings = cfg
+                    .hosts
+                    .get_or_insert_with(BTreeMap::new)
+                    .entry(h)
+                    .or_default();
+                match setting {
+                    config::HostSetting::Proxy => settings.proxy = Some(v),
+                    config::HostSetting::Timeout => settings.timeout = timeout,
+                    config::HostSetting::Http(name) => {
+                        settings
+                            .http
+                            .get_or_insert_with(BTreeMap::new)
+                            .insert(name.to_string(), v);
+                    }
+                }
+                Ok(())
+            })?;
+            (
+                format!("{} = {}", key, value),
+                Some(format!(
+                    "applies to git over https://{}; run wsp doctor --fix to apply it in existing clones",
+                    host
+                )),
+            )
+        }
+        _ if let Some((pattern, field)) = config::key_committer(key) => {
+            if pattern.is_empty() {
+                bail!("committer pattern cannot be empty");
+            }
+            config::Committer::default().field_mut(field)?;
+            if field == "email" && !value.contains('@') {
+                bail!("committer email {:?} is not an email address", value);
+            }
+            if field == "signing-format" && !config::SIGNING_FORMATS.contains(&value.as_str()) {
+                bail!(
+                    "unknown signing-format {:?}: must be one of {}",
+                    value,
+                    config::SIGNING_FORMATS.join(", ")
+                );
+            }
+            let (pattern, field) = (pattern.to_string(), field.to_string());
+            let v = value.clone();
+            filelock::with_config(&paths.config_path, |cfg| {
+                let committer = cfg
+                    .committers
+                    .get_or_insert_with(BTreeMap::new)
+                    .entry(pattern.clone())
+                    .or_default();
+                *committer.field_mut(&field)? = Some(v);
+                Ok(())
+            })?;
+            (
+                format!("committer.{}.{} = {}", pattern, field, value),
+                Some(format!(
+                    "applied to new clones of repos matching {}; run wsp doctor --fix to update existing repos",
+                    pattern
+                )),
+            )
+        }
+        _ if let Some(pattern) = workspace::key_prefix_pattern(key) => {
+            if pattern.is_empty() {
+                bail!("branch-prefix pattern cannot be empty");
+            }
+            let pattern = pattern.to_string();
+            let v = value.clone();
+            filelock::with_config(&paths.config_path, |cfg| {
+                let prefixes = cfg.branch_prefixes.get_or_insert_with(BTreeMap::new);
+                prefixes.insert(pattern.clone(), v);
+                Ok(())
+            })?;
+            (
+                format!("branch-prefix.{} = {}", pattern, value),
+                Some(format!(
+                    "new workspace branches for repos matching {} use this prefix",
+                    pattern
+                )),
+            )
+        }
+        _ if let Some(name) = config::key_template_root(key) => {
+            template::validate_name(name)?;
+            if !std::path::Path::new(value.as_str()).is_absolute() {
+                bail!("workspaces-dir must be an absolute path");
+            }
+            let name = name.to_string();
+            let v = value.clone();
+            filelock::with_config(&paths.config_path, |cfg| {
+                let dirs = cfg
+                    .template_workspaces_dirs
+                    .get_or_insert_with(BTreeMap::new);
+                dirs.insert(name.clone(), v);
+                Ok(())
+            })?;
+            (
+                format!("workspaces-dir.{} = {}", name, value),
+                Some(format!(
+                    "`wsp new -t {}` will create workspaces here; existing workspaces are not moved",
+                    name
+                )),
+            )
+        }
+        _ if let Some(repo) = bootstrap::key_repo(key) => {
+            let repo = repo.to_string();
+            let v = value.clone();
+            let mut identity = String::new();
+            filelock::with_config(&paths.config_path, |cfg| {
+                let identities: Vec<String> = cfg.repos.keys().cloned().collect();
+                identity = crate::giturl::resolve(&repo, &identities)?;
+                if let Some(entry) = cfg.repos.get_mut(&identity) {
+                    entry.setup = Some(v);
+                }
+                Ok(())
+            })?;
+            (
+                format!("setup.{} = {}", identity, value),
+                Some("runs in new clones during wsp new and wsp repo add".into()),
+            )
+        }
+        _ if let Some(repo) = exec_dir_repo(key) => {
+            workspace::validate_exec_dir(value)?;
+            let repo = repo.to_string();
+            let v = value.clone();
+            let mut identity = String::new();
+            filelock::with_config(&paths.config_path, |cfg| {
+                let identities: Vec<String> = cfg.repos.keys().cloned().collect();
+                identity = crate::giturl::resolve(&repo, &identities)?;
+                if let Some(entry) = cfg.repos.get_mut(&identity) {
+                    entry.exec_dir = Some(v);
+                }
+                Ok(())
+            })?;
+            (
+                format!("exec-dir.{} = {}", identity, value),
+                Some("wsp exec, run and watch work there unless a workspace sets its own".into()),
+            )
+        }
+        k if let Some(event) = hooks::key_event(k) => {
+            hooks::validate_event(event)?;
+            let event = event.to_string();
+            let v = value.clone();
+            filelock::with_config(&paths.config_path, |cfg| {
+                let hooks = cfg.hooks.get_or_insert_with(BTreeMap::new);
+                hooks.entry(event.clone()).or_default().run = v;
+                Ok(())
+            })?;
+            (format!("hook.{} = {}", event, value), None)
+        }
         // Legacy key — no longer functional, guide users to new keys
         "experimental" => {
             bail!(
                 "'experimental' is no longer supported. Use 'shell.tmux' and 'shell.prompt' directly instead."
             );
         }
-        _ => bail!("unknown config key: {}", key),
+        _ => {
+            return Err(error::tag(
+                ErrorKind::Config,
+                anyhow::anyhow!("unknown config key: {}", key),
+            ));
+        }
     };
+    note_if_env_override(&normalized);
 
     let mut out = MutationOutput::new(message);
     if let Some(h) = hint {
@@ -751,6 +1855,21 @@ pub fn run_set(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     Ok(Output::Mutation(out))
 }
 
+/// Saves one of the settings a `WSP_*` variable can override, validated by
+/// the same [`config::Config::set_scalar`] as the variable.
+fn set_scalar(paths: &Paths, key: &str, value: &str) -> Result<config::Config> {
+    filelock::with_config(&paths.config_path, |cfg| cfg.set_scalar(key, value))
+}
+
+/// Print a note on stderr when a `WSP_*` variable overrides the key just set.
+fn note_if_env_override(key: &str) {
+    if let Some(var) = config::env_var_for(key)
+        && std::env::var_os(var).is_some_and(|v| !v.is_empty())
+    {
+        eprintln!("note: ${} is set and overrides '{}' for now", var, key);
+    }
+}
+
 /// Extracts the hint from an Output::Mutation, if present.
 #[cfg(test)]
 fn extract_hint(output: &Output) -> Option<&str> {
@@ -790,94 +1909,378 @@ pub fn run_unset(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     let (message, hint): (String, Option<String>) = match normalized.as_str() {
         "branch-prefix" => {
             filelock::with_config(&paths.config_path, |cfg| {
-                cfg.branch_prefix = None;
+                cfg.branch_prefix = None;
+                Ok(())
+            })?;
+            ("branch-prefix unset".into(), None)
+        }
+        "branch-template" => {
+            filelock::with_config(&paths.config_path, |cfg| {
+                cfg.branch_template = None;
+                Ok(())
+            })?;
+            ("branch-template unset".into(), None)
+        }
+        "workspaces-dir" => {
+            filelock::with_config(&paths.config_path, |cfg| {
+                cfg.workspaces_dir = None;
+                Ok(())
+            })?;
+            (
+                "workspaces-dir unset (default: ~/dev/workspaces)".into(),
+                None,
+            )
+        }
+        "sync-strategy" => {
+            filelock::with_config(&paths.config_path, |cfg| {
+                cfg.sync_strategy = None;
+                Ok(())
+            })?;
+            ("sync-strategy unset (default: rebase)".into(), None)
+        }
+        "agent-md" => {
+            filelock::with_config(&paths.config_path, |cfg| {
+                cfg.agent_md = None;
+                Ok(())
+            })?;
+            ("agent-md unset (default: true)".into(), None)
+        }
+        "commit-trailer" => {
+            filelock::with_config(&paths.config_path, |cfg| {
+                cfg.commit_trailer = None;
+                Ok(())
+            })?;
+            ("commit-trailer unset (default: false)".into(), None)
+        }
+        "push-guard" => {
+            filelock::with_config(&paths.config_path, |cfg| {
+                cfg.push_guard = None;
+                Ok(())
+            })?;
+            ("push-guard unset (default: false)".into(), None)
+        }
+        "gc.retention-days" => {
+            filelock::with_config(&paths.config_path, |cfg| {
+                cfg.gc_retention_days = None;
+                Ok(())
+            })?;
+            ("gc.retention-days unset (default: 7)".into(), None)
+        }
+        "run.jobs" => {
+            filelock::with_config(&paths.config_path, |cfg| {
+                cfg.run_jobs = None;
+                Ok(())
+            })?;
+            ("run.jobs unset (default: number of CPUs)".into(), None)
+        }
+        "fetch.jobs" => {
+            filelock::with_config(&paths.config_path, |cfg| {
+                cfg.fetch_jobs = None;
+                Ok(())
+            })?;
+            (
+                format!("fetch.jobs unset (default: {})", fetch::DEFAULT_JOBS),
+                None,
+            )
+        }
+        "fetch.retries" => {
+            filelock::with_config(&paths.config_path, |cfg| {
+                cfg.fetch_retries = None;
+                Ok(())
+            })?;
+            (
+                format!("fetch.retries unset (default: {})", fetch::DEFAULT_RETRIES),
+                None,
+            )
+        }
+        "fetch.timeout" => {
+            filelock::with_config(&paths.config_path, |cfg| {
+                cfg.fetch_timeout = None;
+                Ok(())
+            })?;
+            ("fetch.timeout unset (default: 0, no limit)".into(), None)
+        }
+        "fetch.max-age" => {
+            filelock::with_config(&paths.config_path, |cfg| {
+                cfg.fetch_max_age = None;
+                Ok(())
+            })?;
+            (
+                "fetch.max-age unset (default: 0, always fetch)".into(),
+                None,
+            )
+        }
+        "shell.tmux" => {
+            filelock::with_config(&paths.config_path, |cfg| {
+                cfg.shell_tmux = None;
+                Ok(())
+            })?;
+            ("shell.tmux unset (default: false)".into(), None)
+        }
+        "shell.prompt" => {
+            filelock::with_config(&paths.config_path, |cfg| {
+                cfg.shell_prompt = None;
+                Ok(())
+            })?;
+            ("shell.prompt unset (default: false)".into(), None)
+        }
+        "editor" => {
+            filelock::with_config(&paths.config_path, |cfg| {
+                cfg.editor = None;
+                Ok(())
+            })?;
+            ("editor unset (default: $VISUAL, then $EDITOR)".into(), None)
+        }
+        "issue.name-template" => {
+            filelock::with_config(&paths.config_path, |cfg| {
+                cfg.issue_name_template = None;
+                Ok(())
+            })?;
+            (
+                format!(
+                    "issue.name-template unset (default: {})",
+                    issue::DEFAULT_NAME_TEMPLATE
+                ),
+                None,
+            )
+        }
+        "jira.url" => {
+            filelock::with_config(&paths.config_path, |cfg| {
+                cfg.jira_url = None;
+                Ok(())
+            })?;
+            ("jira.url unset".into(), None)
+        }
+        k if k.starts_with("lang.") => {
+            let lang = &k["lang.".len()..];
+            let known = crate::lang::integration_names();
+            if !known.iter().any(|n| n == lang) {
+                bail!("unknown language integration: {}", lang);
+            }
+            let lang = lang.to_string();
+            filelock::with_config(&paths.config_path, |cfg| {
+                if let Some(ref mut m) = cfg.language_integrations {
+                    m.remove(&lang);
+                    if m.is_empty() {
+                        cfg.language_integrations = None;
+                    }
+                }
+                Ok(())
+            })?;
+            (format!("lang.{} unset (default: false)", lang), None)
+        }
+        k if k.starts_with("git.") => {
+            let git_key = k["git.".len()..].to_string();
+            if git_key.is_empty() {
+                bail!("git key cannot be empty");
+            }
+            let default_val = config::Config::default_git_config().get(&git_key).cloned();
+            filelock::with_config(&paths.config_path, |cfg| {
+                if let Some(ref mut m) = cfg.git_config {
+                    m.remove(&git_key);
+                    if m.is_empty() {
+                        cfg.git_config = None;
+                    }
+                }
+                Ok(())
+            })?;
+            let msg = match default_val {
+                Some(v) => format!("git.{} unset (default: {})", git_key, v),
+                None => format!("git.{} unset", git_key),
+            };
+            (msg, None)
+        }
+        _ if let Some(name) = task::key_name(key) => {
+            let name = name.to_string();
+            filelock::with_config(&paths.config_path, |cfg| {
+                if let Some(ref mut m) = cfg.tasks {
+                    m.remove(&name);
+                    if m.is_empty() {
+                        cfg.tasks = None;
+                    }
+                }
+                Ok(())
+            })?;
+            (format!("task.{} unset", name), None)
+        }
+        _ if let Some(repo) = deps::key_repo(key) => {
+            let repo = repo.to_string();
+            filelock::with_config(&paths.config_path, |cfg| {
+                let identities: Vec<String> = cfg.repos.keys().cloned().collect();
+                let identity = crate::giturl::resolve(&repo, &identities)?;
+                if let Some(ref mut m) = cfg.depends_on {
+                    m.remove(&identity);
+                    if m.is_empty() {
+                        cfg.depends_on = None;
+                    }
+                }
+                Ok(())
+            })?;
+            (format!("depends-on.{} unset", repo), None)
+        }
+        _ if let Some(dest) = files::key_dest(key) => {
+            let dest = dest.to_string();
+            filelock::with_config(&paths.config_path, |cfg| {
+                if let Some(ref mut m) = cfg.files {
+                    m.remove(&dest);
+                    if m.is_empty() {
+                        cfg.files = None;
+                    }
+                }
                 Ok(())
             })?;
-            ("branch-prefix unset".into(), None)
+            (format!("file.{} unset", dest), None)
         }
-        "workspaces-dir" => {
+        _ if let Some(host) = forge::key_host(key) => {
+            let host = host.to_string();
             filelock::with_config(&paths.config_path, |cfg| {
-                cfg.workspaces_dir = None;
+                if let Some(ref mut m) = cfg.forges {
+                    m.remove(&host);
+                    if m.is_empty() {
+                        cfg.forges = None;
+                    }
+                }
                 Ok(())
             })?;
             (
-                "workspaces-dir unset (default: ~/dev/workspaces)".into(),
+                format!("forge.{} unset (detected from the host name)", host),
                 None,
             )
         }
-        "sync-strategy" => {
+        _ if let Some(prefix) = config::key_url_rewrite(key) => {
+            let prefix = prefix.to_string();
             filelock::with_config(&paths.config_path, |cfg| {
-                cfg.sync_strategy = None;
+                if let Some(ref mut m) = cfg.url_rewrites {
+                    m.remove(&prefix);
+                    if m.is_empty() {
+                        cfg.url_rewrites = None;
+                    }
+                }
                 Ok(())
             })?;
-            ("sync-strategy unset (default: rebase)".into(), None)
+            (format!("url-rewrite.{} unset", prefix), None)
         }
-        "agent-md" => {
+        _ if let Some((host, setting)) = config::key_host_setting(key) => {
+            let h = host.to_string();
             filelock::with_config(&paths.config_path, |cfg| {
-                cfg.agent_md = None;
+                if let Some(ref mut m) = cfg.hosts {
+                    if let Some(settings) = m.get_mut(&h) {
+                        match setting {
+                            config::HostSetting::Proxy => settings.proxy = None,
+                            config::HostSetting::Timeout => settings.timeout = None,
+                            config::HostSetting::Http(name) => {
+                                if let Some(ref mut http) = settings.http {
+                                    http.remove(name);
+                                    if http.is_empty() {
+                                        settings.http = None;
+                                    }
+                                }
+                            }
+                        }
+                        if *settings == config::HostSettings::default() {
+                            m.remove(&h);
+                        }
+                    }
+                    if m.is_empty() {
+                        cfg.hosts = None;
+                    }
+                }
                 Ok(())
             })?;
-            ("agent-md unset (default: true)".into(), None)
+            (format!("{} unset", key), None)
         }
-        "gc.retention-days" => {
+        _ if let Some((pattern, field)) = config::key_committer(key) => {
+            config::Committer::default().field_mut(field)?;
+            let (pattern, field) = (pattern.to_string(), field.to_string());
             filelock::with_config(&paths.config_path, |cfg| {
-                cfg.gc_retention_days = None;
+                if let Some(ref mut m) = cfg.committers {
+                    if let Some(committer) = m.get_mut(&pattern) {
+                        *committer.field_mut(&field)? = None;
+                        if *committer == config::Committer::default() {
+                            m.remove(&pattern);
+                        }
+                    }
+                    if m.is_empty() {
+                        cfg.committers = None;
+                    }
+                }
                 Ok(())
             })?;
-            ("gc.retention-days unset (default: 7)".into(), None)
+            (format!("committer.{}.{} unset", pattern, field), None)
         }
-        "shell.tmux" => {
+        _ if let Some(pattern) = workspace::key_prefix_pattern(key) => {
+            let pattern = pattern.to_string();
             filelock::with_config(&paths.config_path, |cfg| {
-                cfg.shell_tmux = None;
+                if let Some(ref mut m) = cfg.branch_prefixes {
+                    m.remove(&pattern);
+                    if m.is_empty() {
+                        cfg.branch_prefixes = None;
+                    }
+                }
                 Ok(())
             })?;
-            ("shell.tmux unset (default: false)".into(), None)
+            (format!("branch-prefix.{} unset", pattern), None)
         }
-        "shell.prompt" => {
+        _ if let Some(name) = config::key_template_root(key) => {
+            let name = name.to_string();
             filelock::with_config(&paths.config_path, |cfg| {
-                cfg.shell_prompt = None;
+                if let Some(ref mut m) = cfg.template_workspaces_dirs {
+                    m.remove(&name);
+                    if m.is_empty() {
+                        cfg.template_workspaces_dirs = None;
+                    }
+                }
                 Ok(())
             })?;
-            ("shell.prompt unset (default: false)".into(), None)
+            (
+                format!("workspaces-dir.{} unset", name),
+                Some(
+                    "workspaces already in that dir stay there, but wsp no longer finds them"
+                        .into(),
+                ),
+            )
         }
-        k if k.starts_with("lang.") => {
-            let lang = &k["lang.".len()..];
-            let known = crate::lang::integration_names();
-            if !known.iter().any(|n| n == lang) {
-                bail!("unknown language integration: {}", lang);
-            }
-            let lang = lang.to_string();
+        _ if let Some(repo) = bootstrap::key_repo(key) => {
+            let repo = repo.to_string();
+            let mut identity = String::new();
             filelock::with_config(&paths.config_path, |cfg| {
-                if let Some(ref mut m) = cfg.language_integrations {
-                    m.remove(&lang);
-                    if m.is_empty() {
-                        cfg.language_integrations = None;
-                    }
+                let identities: Vec<String> = cfg.repos.keys().cloned().collect();
+                identity = crate::giturl::resolve(&repo, &identities)?;
+                if let Some(entry) = cfg.repos.get_mut(&identity) {
+                    entry.setup = None;
                 }
                 Ok(())
             })?;
-            (format!("lang.{} unset (default: false)", lang), None)
+            (format!("setup.{} unset", identity), None)
         }
-        k if k.starts_with("git.") => {
-            let git_key = k["git.".len()..].to_string();
-            if git_key.is_empty() {
-                bail!("git key cannot be empty");
-            }
-            let default_val = config::Config::default_git_config().get(&git_key).cloned();
+        _ if let Some(repo) = exec_dir_repo(key) => {
+            let repo = repo.to_string();
+            let mut identity = String::new();
             filelock::with_config(&paths.config_path, |cfg| {
-                if let Some(ref mut m) = cfg.git_config {
-                    m.remove(&git_key);
+                let identities: Vec<String> = cfg.repos.keys().cloned().collect();
+                identity = crate::giturl::resolve(&repo, &identities)?;
+                if let Some(entry) = cfg.repos.get_mut(&identity) {
+                    entry.exec_dir = None;
+                }
+                Ok(())
+            })?;
+            (
+                format!("exec-dir.{} unset", identity),
+                Some("wsp exec runs at the repo root".into()),
+            )
+        }
+        k if let Some(event) = hooks::key_event(k) => {
+            let event = event.to_string();
+            filelock::with_config(&paths.config_path, |cfg| {
+                if let Some(ref mut m) = cfg.hooks {
+                    m.remove(&event);
                     if m.is_empty() {
-                        cfg.git_config = None;
+                        cfg.hooks = None;
                     }
                 }
                 Ok(())
             })?;
-            let msg = match default_val {
-                Some(v) => format!("git.{} unset (default: {})", git_key, v),
-                None => format!("git.{} unset", git_key),
-            };
-            (msg, None)
+            (format!("hook.{} unset", event), None)
         }
         // Legacy: still accept "experimental" for backward compat
         "experimental" => {
@@ -892,7 +2295,12 @@ pub fn run_unset(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
                 Some("use shell.tmux / shell.prompt directly instead".into()),
             )
         }
-        _ => bail!("unknown config key: {}", key),
+        _ => {
+            return Err(error::tag(
+                ErrorKind::Config,
+                anyhow::anyhow!("unknown config key: {}", key),
+            ));
+        }
     };
 
     let mut out = MutationOutput::new(message);
@@ -913,6 +2321,7 @@ mod tests {
             mirrors_dir: tmp.join("mirrors"),
             gc_dir: tmp.join("gc"),
             templates_dir: tmp.join("templates"),
+            extra_workspaces_dirs: Vec::new(),
             workspaces_dir: tmp.join("workspaces"),
         }
     }
@@ -941,14 +2350,37 @@ mod tests {
 
         let cases = vec![
             ("branch-prefix", "jg"),
+            ("branch-template", "{prefix}/{user}/{workspace}"),
             ("workspaces-dir", "/tmp/ws"),
             ("sync-strategy", "merge"),
             ("agent-md", "true"),
+            ("commit-trailer", "true"),
+            ("push-guard", "true"),
             ("gc.retention-days", "14"),
+            ("run.jobs", "4"),
+            ("fetch.jobs", "4"),
+            ("fetch.retries", "0"),
+            ("fetch.timeout", "120"),
+            ("fetch.max-age", "10m"),
             ("lang.go", "true"),
             ("git.push.default", "current"),
             ("shell.tmux", "window-title"),
             ("shell.prompt", "true"),
+            ("editor", "code --new-window"),
+            ("task.test", "make test"),
+            ("forge.git.acme.com", "gitlab"),
+            (
+                "url-rewrite.https://github.com/",
+                "https://gh-proxy.acme.com/",
+            ),
+            ("host.ghe.acme.com.proxy", "http://proxy.acme.com:3128"),
+            ("host.ghe.acme.com.timeout", "30"),
+            ("host.ghe.acme.com.http.sslCAInfo", "/etc/ssl/acme-ca.pem"),
+            ("branch-prefix.github.com/oss-org/*", "feature"),
+            ("workspaces-dir.oss", "/tmp/oss"),
+            ("committer.github.com/acme/*.email", "jg@acme.com"),
+            ("issue.name-template", "{key}"),
+            ("jira.url", "https://acme.atlassian.net"),
         ];
 
         for (key, value) in cases {
@@ -981,6 +2413,92 @@ mod tests {
         );
     }
 
+    #[test]
+    fn committer_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let paths = test_paths(tmp.path());
+        config::Config::default()
+            .save_to(&paths.config_path)
+            .unwrap();
+
+        do_set(&paths, "committer.github.com/acme/*.name", "J Ganoff");
+        do_set(&paths, "committer.github.com/acme/*.email", "jg@acme.com");
+        let cfg = config::Config::load_from(&paths.config_path).unwrap();
+        let committer = cfg.committer_for("github.com/acme/api").unwrap();
+        assert_eq!(committer.name.as_deref(), Some("J Ganoff"));
+        assert_eq!(committer.email.as_deref(), Some("jg@acme.com"));
+
+        do_set(
+            &paths,
+            "committer.github.com/acme/*.signing-key",
+            "ABCD1234",
+        );
+        do_set(&paths, "committer.github.com/acme/*.signing-format", "ssh");
+        let cfg = config::Config::load_from(&paths.config_path).unwrap();
+        let committer = cfg.committer_for("github.com/acme/api").unwrap();
+        assert_eq!(committer.signing_key.as_deref(), Some("ABCD1234"));
+        assert_eq!(committer.signing_format.as_deref(), Some("ssh"));
+        do_unset(&paths, "committer.github.com/acme/*.signing-key");
+        do_unset(&paths, "committer.github.com/acme/*.signing-format");
+
+        for (key, value) in [
+            ("committer.github.com/acme/*.signing-format", "pgp"),
+            ("committer.github.com/acme/*.email", "not-an-email"),
+            ("committer.github.com/acme/*.login", "jg"),
+            ("committer..name", "J"),
+        ] {
+            let m = set_cmd().get_matches_from(["set", key, value]);
+            assert!(run_set(&m, &paths).is_err(), "{}", key);
+        }
+
+        do_unset(&paths, "committer.github.com/acme/*.name");
+        let cfg = config::Config::load_from(&paths.config_path).unwrap();
+        let committer = cfg.committer_for("github.com/acme/api").unwrap();
+        assert_eq!(committer.name, None);
+
+        do_unset(&paths, "committer.github.com/acme/*.email");
+        let cfg = config::Config::load_from(&paths.config_path).unwrap();
+        assert!(cfg.committers.is_none());
+    }
+
+    #[test]
+    fn host_settings_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let paths = test_paths(tmp.path());
+        config::Config::default()
+            .save_to(&paths.config_path)
+            .unwrap();
+
+        do_set(
+            &paths,
+            "host.ghe.acme.com.proxy",
+            "http://proxy.acme.com:3128",
+        );
+        do_set(&paths, "host.ghe.acme.com.http.sslVerify", "false");
+        let cfg = config::Config::load_from(&paths.config_path).unwrap();
+        let settings = &cfg.hosts.as_ref().unwrap()["ghe.acme.com"];
+        assert_eq!(
+            settings.proxy.as_deref(),
+            Some("http://proxy.acme.com:3128")
+        );
+        assert_eq!(settings.http.as_ref().unwrap()["sslVerify"], "false");
+
+        for (key, value) in [
+            ("host.ghe.acme.com.timeout", "0"),
+            ("host.ghe.acme.com.timeout", "soon"),
+            ("host..proxy", "http://proxy"),
+            ("host.ghe.acme.com.http.", "x"),
+        ] {
+            let m = set_cmd().get_matches_from(["set", key, value]);
+            assert!(run_set(&m, &paths).is_err(), "{} {}", key, value);
+        }
+
+        do_unset(&paths, "host.ghe.acme.com.proxy");
+        do_unset(&paths, "host.ghe.acme.com.http.sslVerify");
+        let cfg = config::Config::load_from(&paths.config_path).unwrap();
+        assert!(cfg.hosts.is_none());
+    }
+
     #[test]
     fn set_shell_hint_mentions_shell() {
         let tmp = tempfile::tempdir().unwrap();
@@ -1062,7 +2580,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: BTreeMap::new(),
         };
         workspace::save_metadata(&ws_dir, &meta).unwrap();
         ws_dir
@@ -1113,11 +2635,242 @@ mod tests {
     }
 
     #[test]
-    fn workspace_get_falls_back_to_global() {
+    fn workspace_exec_dir_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let paths = test_paths(tmp.path());
+        config::Config::default()
+            .save_to(&paths.config_path)
+            .unwrap();
+        let ws_dir = setup_workspace(tmp.path());
+        filelock::with_metadata(&ws_dir, |meta| {
+            meta.repos.insert("github.com/acme/my_api".into(), None);
+            Ok(())
+        })
+        .unwrap();
+
+        // Underscores in the repo part must survive key normalization
+        let m = set_cmd().get_matches_from(["set", "exec_dir.my_api", "services/api"]);
+        let out = run_set_workspace(&m, &ws_dir, &paths).unwrap();
+        assert!(extract_message(&out).contains("exec-dir.github.com/acme/my_api"));
+
+        let m = get_cmd().get_matches_from(["get", "exec-dir.my_api"]);
+        let out = run_get_workspace(&m, &ws_dir, &paths).unwrap();
+        assert_eq!(extract_config_value(&out), Some("services/api"));
+
+        let m = list_cmd().get_matches_from(["ls"]);
+        let out = run_list_workspace(&m, &ws_dir, &paths).unwrap();
+        let entry = extract_config_entries(&out)
+            .iter()
+            .find(|e| e.key == "exec-dir.github.com/acme/my_api")
+            .expect("exec-dir entry listed");
+        assert_eq!(entry.source.as_deref(), Some("workspace"));
+
+        let m = unset_cmd().get_matches_from(["unset", "exec-dir.my_api"]);
+        run_unset_workspace(&m, &ws_dir, &paths).unwrap();
+        let meta = workspace::load_metadata(&ws_dir).unwrap();
+        assert!(meta.config.is_none(), "empty config should be cleaned up");
+    }
+
+    #[test]
+    fn global_exec_dir_is_workspace_fallback() {
         let tmp = tempfile::tempdir().unwrap();
         let paths = test_paths(tmp.path());
         let mut cfg = config::Config::default();
-        cfg.sync_strategy = Some("merge".into());
+        cfg.repos.insert(
+            "github.com/acme/api".into(),
+            config::RepoEntry {
+                url: "https://github.com/acme/api.git".into(),
+                added: chrono::Utc::now(),
+                setup: None,
+                exec_dir: None,
+            },
+        );
+        cfg.save_to(&paths.config_path).unwrap();
+        let ws_dir = setup_workspace(tmp.path());
+        filelock::with_metadata(&ws_dir, |meta| {
+            meta.repos.insert("github.com/acme/api".into(), None);
+            Ok(())
+        })
+        .unwrap();
+
+        do_set(&paths, "exec-dir.api", "services/api");
+        let cfg = config::Config::load_from(&paths.config_path).unwrap();
+        assert_eq!(
+            cfg.repos["github.com/acme/api"].exec_dir.as_deref(),
+            Some("services/api")
+        );
+
+        let m = get_cmd().get_matches_from(["get", "exec-dir.api"]);
+        let out = run_get_workspace(&m, &ws_dir, &paths).unwrap();
+        assert_eq!(extract_config_value(&out), Some("services/api"));
+        let m = list_cmd().get_matches_from(["ls"]);
+        let out = run_list_workspace(&m, &ws_dir, &paths).unwrap();
+        let entry = extract_config_entries(&out)
+            .iter()
+            .find(|e| e.key == "exec-dir.github.com/acme/api")
+            .expect("global exec-dir listed in the workspace");
+        assert_eq!(entry.source, None);
+
+        do_unset(&paths, "exec-dir.api");
+        let cfg = config::Config::load_from(&paths.config_path).unwrap();
+        assert_eq!(cfg.repos["github.com/acme/api"].exec_dir, None);
+    }
+
+    #[test]
+    fn workspace_depends_on_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let paths = test_paths(tmp.path());
+        config::Config::default()
+            .save_to(&paths.config_path)
+            .unwrap();
+        let ws_dir = setup_workspace(tmp.path());
+        filelock::with_metadata(&ws_dir, |meta| {
+            for repo in ["api", "proto", "web"] {
+                meta.repos.insert(format!("github.com/acme/{}", repo), None);
+            }
+            Ok(())
+        })
+        .unwrap();
+
+        let m = set_cmd().get_matches_from(["set", "depends-on.api", "proto"]);
+        let out = run_set_workspace(&m, &ws_dir, &paths).unwrap();
+        assert!(extract_message(&out).contains("depends-on.api = proto"));
+
+        let m = set_cmd().get_matches_from(["set", "depends-on.web", "api,proto"]);
+        run_set_workspace(&m, &ws_dir, &paths).unwrap();
+
+        let m = get_cmd().get_matches_from(["get", "depends_on.web"]);
+        let out = run_get_workspace(&m, &ws_dir, &paths).unwrap();
+        assert_eq!(
+            extract_config_value(&out),
+            Some("github.com/acme/api,github.com/acme/proto")
+        );
+
+        // Closing a cycle is rejected and leaves the config untouched
+        let m = set_cmd().get_matches_from(["set", "depends-on.proto", "web"]);
+        let err = match run_set_workspace(&m, &ws_dir, &paths) {
+            Ok(_) => panic!("expected cycle error"),
+            Err(e) => e.to_string(),
+        };
+        assert!(err.contains("cycle"), "got {:?}", err);
+
+        let m = list_cmd().get_matches_from(["ls"]);
+        let out = run_list_workspace(&m, &ws_dir, &paths).unwrap();
+        let keys: Vec<&str> = extract_config_entries(&out)
+            .iter()
+            .filter(|e| e.key.starts_with("depends-on."))
+            .map(|e| e.key.as_str())
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                "depends-on.github.com/acme/api",
+                "depends-on.github.com/acme/web"
+            ]
+        );
+
+        for repo in ["api", "web"] {
+            let key = format!("depends-on.{}", repo);
+            let m = unset_cmd().get_matches_from(["unset", key.as_str()]);
+            run_unset_workspace(&m, &ws_dir, &paths).unwrap();
+        }
+        let meta = workspace::load_metadata(&ws_dir).unwrap();
+        assert!(meta.config.is_none(), "empty config should be cleaned up");
+    }
+
+    #[test]
+    fn workspace_hooks_are_global_only() {
+        let tmp = tempfile::tempdir().unwrap();
+        let paths = test_paths(tmp.path());
+        config::Config::default()
+            .save_to(&paths.config_path)
+            .unwrap();
+        let ws_dir = setup_workspace(tmp.path());
+        do_set(&paths, "hook.post_sync", "make deps");
+
+        let m = set_cmd().get_matches_from(["set", "hook.post-sync", "touch PWNED"]);
+        assert!(run_set_workspace(&m, &ws_dir, &paths).is_err());
+
+        let m = get_cmd().get_matches_from(["get", "hook.post-sync"]);
+        let out = run_get_workspace(&m, &ws_dir, &paths).unwrap();
+        assert_eq!(extract_config_value(&out), Some("make deps"));
+        let meta = workspace::load_metadata(&ws_dir).unwrap();
+        assert!(meta.config.is_none());
+    }
+
+    #[test]
+    fn workspace_task_overrides_global() {
+        let tmp = tempfile::tempdir().unwrap();
+        let paths = test_paths(tmp.path());
+        config::Config::default()
+            .save_to(&paths.config_path)
+            .unwrap();
+        let ws_dir = setup_workspace(tmp.path());
+
+        do_set(&paths, "task.test", "make test");
+        do_set(&paths, "task.fmt", "cargo fmt");
+
+        let m = set_cmd().get_matches_from(["set", "task.test", "npm test"]);
+        run_set_workspace(&m, &ws_dir, &paths).unwrap();
+
+        let m = get_cmd().get_matches_from(["get", "task.test"]);
+        let out = run_get_workspace(&m, &ws_dir, &paths).unwrap();
+        assert_eq!(extract_config_value(&out), Some("npm test"));
+
+        let m = list_cmd().get_matches_from(["ls"]);
+        let out = run_list_workspace(&m, &ws_dir, &paths).unwrap();
+        let tasks: Vec<(&str, &str, Option<&str>)> = extract_config_entries(&out)
+            .iter()
+            .filter(|e| e.key.starts_with("task."))
+            .map(|e| (e.key.as_str(), e.value.as_str(), e.source.as_deref()))
+            .collect();
+        assert_eq!(
+            tasks,
+            vec![
+                ("task.fmt", "cargo fmt", None),
+                ("task.test", "npm test", Some("workspace")),
+            ]
+        );
+
+        let m = unset_cmd().get_matches_from(["unset", "task.test"]);
+        let out = run_unset_workspace(&m, &ws_dir, &paths).unwrap();
+        assert!(extract_message(&out).contains("using global: make test"));
+    }
+
+    #[test]
+    fn workspace_exec_dir_rejects_bad_input() {
+        let tmp = tempfile::tempdir().unwrap();
+        let paths = test_paths(tmp.path());
+        let ws_dir = setup_workspace(tmp.path());
+        filelock::with_metadata(&ws_dir, |meta| {
+            meta.repos.insert("github.com/acme/api".into(), None);
+            Ok(())
+        })
+        .unwrap();
+
+        let cases = vec![
+            ("unknown repo", "exec-dir.nope", "src"),
+            ("traversal", "exec-dir.api", "../other"),
+            ("absolute", "exec-dir.api", "/etc"),
+        ];
+        for (name, key, value) in cases {
+            let m = set_cmd().get_matches_from(["set", key, value]);
+            assert!(
+                run_set_workspace(&m, &ws_dir, &paths).is_err(),
+                "{}: expected error",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn workspace_get_falls_back_to_global() {
+        let tmp = tempfile::tempdir().unwrap();
+        let paths = test_paths(tmp.path());
+        let cfg = config::Config {
+            sync_strategy: Some("merge".into()),
+            ..Default::default()
+        };
         cfg.save_to(&paths.config_path).unwrap();
         let ws_dir = setup_workspace(tmp.path());
 
@@ -1137,13 +2890,28 @@ mod tests {
         let ws_dir = setup_workspace(tmp.path());
 
         let cases = vec![
-            "branch-prefix",
+            "branch-template",
             "workspaces-dir",
             "gc.retention-days",
-            "agent-md",
+            "fetch.jobs",
+            "fetch.retries",
+            "fetch.timeout",
+            "fetch.max-age",
+            "commit-trailer",
+            "push-guard",
             "shell.tmux",
             "shell.prompt",
+            "editor",
             "experimental",
+            "forge.git.acme.com",
+            "url-rewrite.https://github.com/",
+            "host.ghe.acme.com.proxy",
+            "branch-prefix.github.com/acme/*",
+            "workspaces-dir.oss",
+            "committer.github.com/acme/*.name",
+            "issue.name-template",
+            "jira.url",
+            "hook.post-create",
         ];
         for key in cases {
             let cmd = set_cmd();
@@ -1164,8 +2932,10 @@ mod tests {
     fn workspace_unset_falls_back_to_global() {
         let tmp = tempfile::tempdir().unwrap();
         let paths = test_paths(tmp.path());
-        let mut cfg = config::Config::default();
-        cfg.sync_strategy = Some("merge".into());
+        let cfg = config::Config {
+            sync_strategy: Some("merge".into()),
+            ..Default::default()
+        };
         cfg.save_to(&paths.config_path).unwrap();
         let ws_dir = setup_workspace(tmp.path());
 
@@ -1187,6 +2957,48 @@ mod tests {
         assert_eq!(extract_config_value(&out), Some("merge"));
     }
 
+    #[test]
+    fn workspace_settings_override_global() {
+        let tmp = tempfile::tempdir().unwrap();
+        let paths = test_paths(tmp.path());
+        let cfg = config::Config {
+            branch_prefix: Some("jg".into()),
+            agent_md: Some(true),
+            run_jobs: Some(8),
+            ..Default::default()
+        };
+        cfg.save_to(&paths.config_path).unwrap();
+        let ws_dir = setup_workspace(tmp.path());
+
+        // (key, workspace value, global value)
+        let cases = vec![
+            ("branch-prefix", "hotfix", "jg"),
+            ("agent-md", "false", "true"),
+            ("run.jobs", "1", "8"),
+        ];
+        for (key, ws_value, global) in cases {
+            let m = set_cmd().get_matches_from(["set", key, ws_value]);
+            run_set_workspace(&m, &ws_dir, &paths).unwrap();
+            let m = get_cmd().get_matches_from(["get", key]);
+            let out = run_get_workspace(&m, &ws_dir, &paths).unwrap();
+            assert_eq!(extract_config_value(&out), Some(ws_value), "{}", key);
+
+            let m = unset_cmd().get_matches_from(["unset", key]);
+            let out = run_unset_workspace(&m, &ws_dir, &paths).unwrap();
+            let msg = extract_message(&out);
+            assert!(msg.contains(global), "{}: {}", key, msg);
+            let m = get_cmd().get_matches_from(["get", key]);
+            let out = run_get_workspace(&m, &ws_dir, &paths).unwrap();
+            assert_eq!(extract_config_value(&out), Some(global), "{}", key);
+        }
+        assert!(workspace::load_metadata(&ws_dir).unwrap().config.is_none());
+
+        for (key, value) in [("run.jobs", "0"), ("agent-md", "maybe")] {
+            let m = set_cmd().get_matches_from(["set", key, value]);
+            assert!(run_set_workspace(&m, &ws_dir, &paths).is_err(), "{}", key);
+        }
+    }
+
     #[test]
     fn workspace_list_shows_source_annotation() {
         let tmp = tempfile::tempdir().unwrap();
@@ -1252,8 +3064,10 @@ mod tests {
 
     #[test]
     fn apply_workspace_config_hierarchy() {
-        let mut global = config::Config::default();
-        global.sync_strategy = Some("rebase".into());
+        let mut global = config::Config {
+            sync_strategy: Some("rebase".into()),
+            ..Default::default()
+        };
         global.git_config = Some({
             let mut m = BTreeMap::new();
             m.insert("push.default".into(), "current".into());
@@ -1271,6 +3085,9 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
             config: Some(template::TemplateConfig {
                 sync_strategy: Some("merge".into()),
                 git_config: Some({
@@ -1279,7 +3096,15 @@ mod tests {
                     m
                 }),
                 language_integrations: None,
+                exec_dirs: None,
+                tasks: None,
+                depends_on: None,
+                files: None,
+                branch_prefix: None,
+                agent_md: None,
+                run_jobs: None,
             }),
+            pending: BTreeMap::new(),
         };
 
         let effective = meta.apply_workspace_config(&global);
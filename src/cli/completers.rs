@@ -4,12 +4,50 @@
 use clap_complete::engine::CompletionCandidate;
 
 use crate::config::{Config, Paths};
+use crate::gc;
 use crate::giturl;
 use crate::template;
 use crate::workspace;
 
+/// Paths for the profile of the command line being completed, so
+/// `wsp --profile work cd <TAB>` offers that profile's workspaces.
+fn paths() -> anyhow::Result<Paths> {
+    let index = std::env::var("_CLAP_COMPLETE_INDEX")
+        .ok()
+        .and_then(|i| i.parse().ok());
+    let profile = completed_profile(std::env::args(), index);
+    Paths::resolve_profile(profile.as_deref(), false, false)
+}
+
+/// The `--profile` in the words of a completion request (`wsp -- <words...>`),
+/// looking only before the word at `index`, the one being completed.
+fn completed_profile(args: impl Iterator<Item = String>, index: Option<usize>) -> Option<String> {
+    let words: Vec<String> = args.skip_while(|a| a != "--").skip(1).collect();
+    let end = index.unwrap_or(words.len()).min(words.len());
+    let mut words = words[..end].iter().skip(1);
+    while let Some(word) = words.next() {
+        if word == "--" {
+            break;
+        }
+        if word == "--profile" {
+            return words.next().cloned();
+        }
+        if let Some(p) = word.strip_prefix("--profile=") {
+            return Some(p.to_string());
+        }
+    }
+    None
+}
+
+pub fn complete_profiles() -> Vec<CompletionCandidate> {
+    crate::config::profile_names()
+        .into_iter()
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
 pub fn complete_templates() -> Vec<CompletionCandidate> {
-    let Ok(paths) = Paths::resolve() else {
+    let Ok(paths) = paths() else {
         return Vec::new();
     };
     let Ok(names) = template::list(&paths.templates_dir) else {
@@ -19,7 +57,7 @@ pub fn complete_templates() -> Vec<CompletionCandidate> {
 }
 
 pub fn complete_repos() -> Vec<CompletionCandidate> {
-    let Ok(paths) = Paths::resolve() else {
+    let Ok(paths) = paths() else {
         return Vec::new();
     };
     let Ok(cfg) = Config::load_from(&paths.config_path) else {
@@ -28,6 +66,20 @@ pub fn complete_repos() -> Vec<CompletionCandidate> {
     repos_to_candidates(cfg.repos.keys().cloned().collect())
 }
 
+/// Forge hosts of registered repos and `forge.<host>` overrides.
+pub fn complete_hosts() -> Vec<CompletionCandidate> {
+    let Ok(paths) = paths() else {
+        return Vec::new();
+    };
+    let Ok(cfg) = Config::load_from(&paths.config_path) else {
+        return Vec::new();
+    };
+    super::auth::known_hosts(&cfg)
+        .into_iter()
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
 /// Complete only repos in the current workspace (for `ws repo rm`).
 pub fn complete_workspace_repos() -> Vec<CompletionCandidate> {
     let Ok(cwd) = std::env::current_dir() else {
@@ -47,7 +99,7 @@ pub fn complete_template_repos() -> Vec<CompletionCandidate> {
     let Some(name) = template_name_from_args() else {
         return Vec::new();
     };
-    let Ok(paths) = Paths::resolve() else {
+    let Ok(paths) = paths() else {
         return Vec::new();
     };
     let Ok(tmpl) = template::load(&paths.templates_dir, &name) else {
@@ -74,12 +126,23 @@ pub fn complete_template_config_keys() -> Vec<CompletionCandidate> {
 pub fn complete_config_keys() -> Vec<CompletionCandidate> {
     let mut keys: Vec<CompletionCandidate> = vec![
         CompletionCandidate::new("branch-prefix"),
+        CompletionCandidate::new("branch-template"),
         CompletionCandidate::new("workspaces-dir"),
         CompletionCandidate::new("sync-strategy"),
         CompletionCandidate::new("agent-md"),
+        CompletionCandidate::new("commit-trailer"),
+        CompletionCandidate::new("push-guard"),
         CompletionCandidate::new("gc.retention-days"),
+        CompletionCandidate::new("run.jobs"),
+        CompletionCandidate::new("fetch.jobs"),
+        CompletionCandidate::new("fetch.retries"),
+        CompletionCandidate::new("fetch.timeout"),
+        CompletionCandidate::new("fetch.max-age"),
         CompletionCandidate::new("shell.tmux"),
         CompletionCandidate::new("shell.prompt"),
+        CompletionCandidate::new("editor"),
+        CompletionCandidate::new("issue.name-template"),
+        CompletionCandidate::new("jira.url"),
     ];
 
     // lang.<name> keys
@@ -92,9 +155,98 @@ pub fn complete_config_keys() -> Vec<CompletionCandidate> {
         keys.push(CompletionCandidate::new(format!("git.{}", key)));
     }
 
+    // task.<name> — existing tasks; setup.<repo> (global-only) — registered repos
+    if let Ok(paths) = paths()
+        && let Ok(cfg) = Config::load_from(&paths.config_path)
+    {
+        let ids: Vec<String> = cfg.repos.keys().cloned().collect();
+        for shortname in crate::giturl::shortnames(&ids).values() {
+            keys.push(CompletionCandidate::new(format!("setup.{}", shortname)));
+        }
+        for name in cfg.tasks.unwrap_or_default().keys() {
+            keys.push(CompletionCandidate::new(format!("task.{}", name)));
+        }
+        // forge.<host>, url-rewrite.<prefix> and host.<host>.* (global-only)
+        // — hosts of registered repos
+        let hosts: std::collections::BTreeSet<&str> =
+            ids.iter().filter_map(|id| id.split('/').next()).collect();
+        for host in hosts {
+            keys.push(CompletionCandidate::new(format!("forge.{}", host)));
+            keys.push(CompletionCandidate::new(format!(
+                "url-rewrite.https://{}/",
+                host
+            )));
+            for setting in ["proxy", "timeout"] {
+                keys.push(CompletionCandidate::new(format!(
+                    "host.{}.{}",
+                    host, setting
+                )));
+            }
+        }
+        // workspaces-dir.<template> (global-only) — saved templates
+        for name in crate::template::list(&paths.templates_dir).unwrap_or_default() {
+            keys.push(CompletionCandidate::new(format!("workspaces-dir.{}", name)));
+        }
+    }
+
+    // hook.<event>
+    for event in crate::hooks::EVENTS {
+        keys.push(CompletionCandidate::new(format!("hook.{}", event)));
+    }
+
+    // exec-dir.<repo> and depends-on.<repo>: workspace repos
+    // inside a workspace, registered repos outside
+    if let Some(meta) = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| workspace::detect(&cwd).ok())
+        .and_then(|ws_dir| workspace::load_metadata(&ws_dir).ok())
+    {
+        for identity in meta.repos.keys() {
+            if let Ok(dir_name) = meta.dir_name(identity) {
+                keys.push(CompletionCandidate::new(format!("exec-dir.{}", dir_name)));
+                keys.push(CompletionCandidate::new(format!("depends-on.{}", dir_name)));
+            }
+        }
+    } else if let Ok(paths) = paths()
+        && let Ok(cfg) = Config::load_from(&paths.config_path)
+    {
+        let ids: Vec<String> = cfg.repos.keys().cloned().collect();
+        for shortname in crate::giturl::shortnames(&ids).values() {
+            keys.push(CompletionCandidate::new(format!("exec-dir.{}", shortname)));
+            keys.push(CompletionCandidate::new(format!(
+                "depends-on.{}",
+                shortname
+            )));
+        }
+    }
+
     keys
 }
 
+/// Complete task names for `wsp run`: global tasks plus the current workspace's.
+pub fn complete_tasks() -> Vec<CompletionCandidate> {
+    let Ok(paths) = paths() else {
+        return Vec::new();
+    };
+    let Ok(cfg) = Config::load_from(&paths.config_path) else {
+        return Vec::new();
+    };
+    let effective = match std::env::current_dir()
+        .ok()
+        .and_then(|cwd| workspace::detect(&cwd).ok())
+        .and_then(|ws_dir| workspace::load_metadata(&ws_dir).ok())
+    {
+        Some(meta) => meta.apply_workspace_config(&cfg),
+        None => cfg,
+    };
+    effective
+        .tasks
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, def)| CompletionCandidate::new(name).help(Some(def.run.into())))
+        .collect()
+}
+
 /// Complete config values for `wsp config set` based on the key being set.
 pub fn complete_config_values() -> Vec<CompletionCandidate> {
     // Inspect prior args to find the key
@@ -111,7 +263,7 @@ pub fn complete_config_values() -> Vec<CompletionCandidate> {
             CompletionCandidate::new("rebase"),
             CompletionCandidate::new("merge"),
         ],
-        Some("agent-md" | "shell.prompt") => bool_candidates(),
+        Some("agent-md" | "commit-trailer" | "push-guard" | "shell.prompt") => bool_candidates(),
         Some("shell.tmux") => crate::config::SHELL_TMUX_VALUES
             .iter()
             .map(|v| CompletionCandidate::new(*v))
@@ -129,15 +281,32 @@ fn bool_candidates() -> Vec<CompletionCandidate> {
 }
 
 pub fn complete_workspaces() -> Vec<CompletionCandidate> {
-    let Ok(paths) = Paths::resolve() else {
+    let Ok(paths) = paths() else {
         return Vec::new();
     };
-    let Ok(names) = workspace::list_all(&paths.workspaces_dir) else {
+    let Ok(names) = workspace::list_all(&paths) else {
         return Vec::new();
     };
     names.into_iter().map(CompletionCandidate::new).collect()
 }
 
+/// Complete workspaces held in gc (for `wsp recover`).
+pub fn complete_removed_workspaces() -> Vec<CompletionCandidate> {
+    let Ok(paths) = paths() else {
+        return Vec::new();
+    };
+    let Ok(entries) = gc::list(&paths.gc_dir) else {
+        return Vec::new();
+    };
+    entries
+        .into_iter()
+        .map(|e| {
+            let help = format!("removed {}", e.trashed_at.format("%Y-%m-%d %H:%M"));
+            CompletionCandidate::new(e.name).help(Some(help.into()))
+        })
+        .collect()
+}
+
 fn repos_to_candidates(identities: Vec<String>) -> Vec<CompletionCandidate> {
     let shortnames = giturl::shortnames(&identities);
     shortnames
@@ -153,3 +322,56 @@ fn template_name_from_args() -> Option<String> {
     // template <sub-noun> <verb> <name>
     args.get(pos + 3).filter(|a| !a.starts_with('-')).cloned()
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Command;
+    use clap_complete::engine::ArgValueCandidates;
+
+    use super::completed_profile;
+
+    #[test]
+    fn test_completed_profile() {
+        let cases = vec![
+            ("wsp -- wsp cd ", None, None),
+            ("wsp -- wsp --profile work cd ", None, Some("work")),
+            ("wsp -- wsp cd --profile=work ", None, Some("work")),
+            ("wsp -- wsp --profile work cd x", Some(4), Some("work")),
+            ("wsp -- wsp --profile=wo", Some(1), None),
+            ("wsp -- wsp exec x -- cmd --profile work", None, None),
+            ("wsp cd --profile work", None, None),
+        ];
+        for (line, index, want) in cases {
+            let args = line.split(' ').map(String::from);
+            assert_eq!(
+                completed_profile(args, index).as_deref(),
+                want,
+                "{} at {:?}",
+                line,
+                index
+            );
+        }
+    }
+
+    /// Every argument naming an existing workspace completes workspace
+    /// names rather than falling back to files. `wsp new` names a new one.
+    #[test]
+    fn test_workspace_args_complete() {
+        fn walk(cmd: &Command, path: &str, missing: &mut Vec<String>) {
+            for arg in cmd.get_arguments() {
+                let names_workspace = matches!(arg.get_id().as_str(), "workspace" | "old");
+                if names_workspace && path != "wsp new" && arg.get::<ArgValueCandidates>().is_none()
+                {
+                    missing.push(format!("{} <{}>", path, arg.get_id()));
+                }
+            }
+            for sub in cmd.get_subcommands() {
+                walk(sub, &format!("{} {}", path, sub.get_name()), missing);
+            }
+        }
+
+        let mut missing = Vec::new();
+        walk(&crate::cli::build_cli(), "wsp", &mut missing);
+        assert!(missing.is_empty(), "no workspace completer: {:?}", missing);
+    }
+}
@@ -41,13 +41,16 @@ pub fn cmd() -> Command {
         .long_about(
             "Output shell integration (completions + wrapper function) [read-only].\n\n\
              Prints a shell script that provides tab completion and the `wsp cd` wrapper \
-             function. Add `eval \"$(wsp completion zsh)\"` to your shell rc file.",
-        )
-        .arg(
-            Arg::new("shell")
-                .required(true)
-                .value_parser(["zsh", "bash", "fish"]),
+             function. Add `eval \"$(wsp completion zsh)\"` (or `bash`) to your shell rc \
+             file; for fish, `wsp completion fish | source`.",
         )
+        .arg(Arg::new("shell").required(true).value_parser([
+            "zsh",
+            "bash",
+            "fish",
+            "nushell",
+            "powershell",
+        ]))
 }
 
 pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
@@ -69,7 +72,7 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
             }
         }
         Err(e) => {
-            eprintln!("wsp: warning: failed to load config, shell hooks disabled: {e}");
+            tracing::warn!("failed to load wsp config, shell hooks disabled: {e}");
             ShellHookOpts::default()
         }
     };
@@ -86,12 +89,32 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
             generate_fish(&mut std::io::stdout(), paths, hooks)?;
             Ok(Output::None)
         }
-        _ => bail!("unsupported shell: {} (supported: zsh, bash, fish)", shell),
+        "nushell" => {
+            warn_hooks_unsupported(shell, &hooks);
+            generate_nushell(&mut std::io::stdout())?;
+            Ok(Output::None)
+        }
+        "powershell" => {
+            warn_hooks_unsupported(shell, &hooks);
+            generate_powershell(&mut std::io::stdout())?;
+            Ok(Output::None)
+        }
+        _ => bail!(
+            "unsupported shell: {} (supported: zsh, bash, fish, nushell, powershell)",
+            shell
+        ),
     }
 }
 
 // ---------- shared helpers ----------
 
+/// Shells without a hook implementation only get completions.
+fn warn_hooks_unsupported(shell: &str, hooks: &ShellHookOpts) {
+    if hooks.any_enabled() {
+        tracing::warn!("shell hooks are not supported in {shell}");
+    }
+}
+
 fn bin_path() -> Result<String> {
     let bin = std::env::current_exe()
         .map_err(|e| anyhow::anyhow!("cannot determine executable path: {}", e))?;
@@ -111,6 +134,22 @@ fn fish_escape(s: &str) -> String {
     s.replace('\'', "\\'")
 }
 
+/// Escape a string for embedding inside PowerShell single quotes, where a
+/// doubled quote stands for one: `'` → `''`
+fn powershell_escape(s: &str) -> String {
+    s.replace('\'', "''")
+}
+
+/// Quote a string as a nushell raw string (`r#'...'#`), which has no escape
+/// sequences. Adds `#`s until the closing delimiter can't occur inside.
+fn nu_quote(s: &str) -> String {
+    let mut hashes = "#".to_string();
+    while s.contains(&format!("'{hashes}")) {
+        hashes.push('#');
+    }
+    format!("r{hashes}'{s}'{hashes}")
+}
+
 // ---------- zsh / bash (POSIX-like) ----------
 
 fn generate_posix(
@@ -131,9 +170,8 @@ fn write_posix(
     shell: &str,
     hooks: ShellHookOpts,
 ) -> Result<()> {
-    let cases = build_posix_cases();
+    let cases = build_cases();
     let bin_esc = posix_escape(bin_str);
-    let root_esc = posix_escape(wsp_root);
 
     write!(
         w,
@@ -141,19 +179,21 @@ fn write_posix(
          \n\
          wsp() {{\n\
          \x20 local wsp_bin='{bin_esc}'\n\
-         \x20 local wsp_root='{root_esc}'\n\
          \n\
          \x20 case \"$1\" in\n",
     )?;
 
     for case in &cases {
-        write!(
-            w,
-            "    {})\n\
-             \x20     {}\n\
-             \x20     ;;\n",
-            case.pattern, case.body
-        )?;
+        let body = posix_case_body(case);
+        for name in &case.names {
+            write!(
+                w,
+                "    {})\n\
+                 \x20     {}\n\
+                 \x20     ;;\n",
+                name, body
+            )?;
+        }
     }
 
     write!(
@@ -183,75 +223,103 @@ fn write_posix(
         writeln!(w, "  source <(COMPLETE={shell} '{bin_esc}')")?;
         writeln!(w, "fi")?;
     } else {
-        writeln!(w, "source <(COMPLETE={shell} '{bin_esc}')")?;
+        // bash 3.2 (macOS's /bin/bash) silently sources nothing from a
+        // process substitution, so eval the script instead.
+        writeln!(w, "eval \"$(COMPLETE={shell} '{bin_esc}')\"")?;
     }
 
     // Experimental: shell hooks for workspace detection, tmux title, prompt variable
     if hooks.any_enabled() {
-        write_posix_hooks(w, &root_esc, shell, hooks)?;
+        write_posix_hooks(w, &posix_escape(wsp_root), shell, hooks)?;
     }
 
     Ok(())
 }
 
+/// What a wrapper case does around the real command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CaseAction {
+    /// Run the command with `--print-dir`, then cd into the workspace it
+    /// created. The arguments can't tell where that is: with `--issue` the
+    /// name comes from the issue, and the first argument may be a flag.
+    EnterNew,
+    /// cd to the directory the command prints.
+    GoTo,
+    /// Leave the workspace before the command removes it.
+    Leave,
+}
+
+/// One arm of the wrapper's `case`: the subcommand name plus its visible
+/// aliases, and what to do for them. Each shell renders these its own way.
 struct ShellCase {
-    pattern: String,
-    body: String,
+    command: String,
+    names: Vec<String>,
+    action: CaseAction,
 }
 
-fn build_posix_cases() -> Vec<ShellCase> {
-    vec![
-        ShellCase {
-            pattern: "new".to_string(),
-            body: build_posix_cd_into("new"),
-        },
-        ShellCase {
-            pattern: "cd".to_string(),
-            body: "shift\n\
-                 \x20     local dir\n\
-                 \x20     dir=$(WSP_SHELL=1 command \"$wsp_bin\" cd \"$@\") || return\n\
-                 \x20     cd \"$dir\""
-                .to_string(),
-        },
-        ShellCase {
-            pattern: "rm".to_string(),
-            body: build_posix_cd_out("rm"),
-        },
+impl ShellCase {
+    fn from_cmd(cmd: &Command, action: CaseAction) -> Self {
         ShellCase {
-            pattern: "remove".to_string(),
-            body: build_posix_cd_out("rm"),
-        },
-    ]
+            command: cmd.get_name().to_string(),
+            names: std::iter::once(cmd.get_name())
+                .chain(cmd.get_visible_aliases())
+                .map(str::to_string)
+                .collect(),
+            action,
+        }
+    }
 }
 
-fn build_posix_cd_into(cmd_name: &str) -> String {
-    format!(
-        "shift\n\
-         \x20     command \"$wsp_bin\" {cmd_name} \"$@\" || return\n\
-         \x20     local wsp_dir=\"$wsp_root/$1\"\n\
-         \x20     cd \"$wsp_dir\"",
-    )
+/// Wrapper cases for the commands that change directory. Names come from the
+/// subcommands themselves, so a new alias for `rm` is wrapped too.
+fn build_cases() -> Vec<ShellCase> {
+    vec![
+        ShellCase::from_cmd(&super::new::cmd(), CaseAction::EnterNew),
+        ShellCase::from_cmd(&super::cd::cmd(), CaseAction::GoTo),
+        ShellCase::from_cmd(&super::delete::cmd(), CaseAction::Leave),
+    ]
 }
 
-fn build_posix_cd_out(cmd_name: &str) -> String {
-    format!(
-        "shift\n\
-         \x20     local _wsp_name\n\
-         \x20     for _wsp_name in \"$@\"; do\n\
-         \x20       [[ \"$_wsp_name\" != -* ]] && break\n\
-         \x20       _wsp_name=\n\
-         \x20     done\n\
-         \x20     if [[ -n \"$_wsp_name\" ]]; then\n\
-         \x20       local wsp_dir=\"$wsp_root/$_wsp_name\"\n\
-         \x20       if [[ \"$PWD\" = \"$wsp_dir\"* ]]; then\n\
-         \x20         cd \"$wsp_root\" || cd \"$HOME\"\n\
-         \x20       fi\n\
-         \x20     fi\n\
-         \x20     command \"$wsp_bin\" {cmd_name} \"$@\"\n\
-         \x20     if [[ ! -d \"$PWD\" ]]; then\n\
-         \x20       cd \"$wsp_root\" || cd \"$HOME\"\n\
-         \x20     fi",
-    )
+fn posix_case_body(case: &ShellCase) -> String {
+    let cmd_name = &case.command;
+    match case.action {
+        CaseAction::EnterNew => format!(
+            "shift\n\
+             \x20     local wsp_dir wsp_rc\n\
+             \x20     wsp_dir=$(command \"$wsp_bin\" {cmd_name} --print-dir \"$@\")\n\
+             \x20     wsp_rc=$?\n\
+             \x20     if [[ -d \"$wsp_dir\" ]]; then\n\
+             \x20       cd \"$wsp_dir\"\n\
+             \x20     elif [[ -n \"$wsp_dir\" ]]; then\n\
+             \x20       printf '%s\\n' \"$wsp_dir\"\n\
+             \x20     fi\n\
+             \x20     return $wsp_rc",
+        ),
+        CaseAction::GoTo => format!(
+            "shift\n\
+             \x20     local dir\n\
+             \x20     dir=$(WSP_SHELL=1 command \"$wsp_bin\" {cmd_name} \"$@\") || return\n\
+             \x20     cd \"$dir\"",
+        ),
+        CaseAction::Leave => format!(
+            "shift\n\
+             \x20     local _wsp_name wsp_root wsp_dir\n\
+             \x20     for _wsp_name in \"$@\"; do\n\
+             \x20       [[ \"$_wsp_name\" != -* ]] && break\n\
+             \x20       _wsp_name=\n\
+             \x20     done\n\
+             \x20     wsp_root=$(command \"$wsp_bin\" dir 2>/dev/null) || wsp_root=\"$HOME\"\n\
+             \x20     if [[ -n \"$_wsp_name\" ]] && wsp_dir=$(command \"$wsp_bin\" dir \"$_wsp_name\" 2>/dev/null); then\n\
+             \x20       if [[ \"$PWD\" = \"$wsp_dir\" || \"$PWD\" = \"$wsp_dir\"/* ]]; then\n\
+             \x20         cd \"$wsp_root\" || cd \"$HOME\"\n\
+             \x20       fi\n\
+             \x20     fi\n\
+             \x20     command \"$wsp_bin\" {cmd_name} \"$@\"\n\
+             \x20     if [[ ! -d \"$PWD\" ]]; then\n\
+             \x20       cd \"$wsp_root\" || cd \"$HOME\"\n\
+             \x20     fi",
+        ),
+    }
 }
 
 fn write_posix_hooks(
@@ -361,64 +429,87 @@ fn write_fish(
     hooks: ShellHookOpts,
 ) -> Result<()> {
     let bin_esc = fish_escape(bin_str);
-    let root_esc = fish_escape(wsp_root);
 
     write!(
         w,
-        "\
-# wsp shell integration \u{2014} source with: wsp completion fish | source\n\
-\n\
-function wsp\n\
-    set -l wsp_bin '{bin_esc}'\n\
-    set -l wsp_root '{root_esc}'\n\
-\n\
-    switch $argv[1]\n\
-        case new\n\
-            set -l args $argv[2..]\n\
-            command $wsp_bin new $args; or return\n\
-            set -l wsp_dir \"$wsp_root/$args[1]\"\n\
-            cd $wsp_dir\n\
-\n\
-        case cd\n\
-            set -l args $argv[2..]\n\
-            set -l dir (WSP_SHELL=1 command $wsp_bin cd $args); or return\n\
-            cd $dir\n\
-\n\
-        case rm remove\n\
-            set -l args $argv[2..]\n\
-            set -l _wsp_name\n\
-            for _a in $args\n\
-                if not string match -q -- '-*' $_a\n\
-                    set _wsp_name $_a\n\
-                    break\n\
-                end\n\
-            end\n\
-            if test -n \"$_wsp_name\"\n\
-                set -l wsp_dir \"$wsp_root/$_wsp_name\"\n\
-                if string match -q \"$wsp_dir*\" $PWD\n\
-                    cd \"$wsp_root\"; or cd $HOME\n\
-                end\n\
-            end\n\
-            command $wsp_bin rm $args\n\
-            if not test -d $PWD\n\
-                cd \"$wsp_root\"; or cd $HOME\n\
-            end\n\
-\n\
-        case '*'\n\
-            command $wsp_bin $argv\n\
-    end\n\
-end\n\
-\n\
-COMPLETE=fish '{bin_esc}' | source\n"
+        "# wsp shell integration \u{2014} source with: wsp completion fish | source\n\
+         \n\
+         function wsp\n\
+         \x20   set -l wsp_bin '{bin_esc}'\n\
+         \n\
+         \x20   switch $argv[1]\n"
+    )?;
+
+    for case in &build_cases() {
+        write!(
+            w,
+            "        case {}\n{}\n",
+            case.names.join(" "),
+            fish_case_body(case)
+        )?;
+    }
+
+    write!(
+        w,
+        "        case '*'\n\
+         \x20           command $wsp_bin $argv\n\
+         \x20   end\n\
+         end\n\
+         \n\
+         COMPLETE=fish '{bin_esc}' | source\n"
     )?;
 
     if hooks.any_enabled() {
-        write_fish_hooks(w, &root_esc, hooks)?;
+        write_fish_hooks(w, &fish_escape(wsp_root), hooks)?;
     }
 
     Ok(())
 }
 
+fn fish_case_body(case: &ShellCase) -> String {
+    let cmd_name = &case.command;
+    let lines: Vec<String> = match case.action {
+        CaseAction::EnterNew => vec![
+            "set -l args $argv[2..]".into(),
+            format!("set -l wsp_dir (command $wsp_bin {cmd_name} --print-dir $args)"),
+            "set -l wsp_rc $status".into(),
+            "if test -d \"$wsp_dir\"".into(),
+            "    cd $wsp_dir".into(),
+            "else if test -n \"$wsp_dir\"".into(),
+            "    printf '%s\\n' $wsp_dir".into(),
+            "end".into(),
+            "return $wsp_rc".into(),
+        ],
+        CaseAction::GoTo => vec![
+            "set -l args $argv[2..]".into(),
+            format!("set -l dir (WSP_SHELL=1 command $wsp_bin {cmd_name} $args); or return"),
+            "cd $dir".into(),
+        ],
+        CaseAction::Leave => vec![
+            "set -l args $argv[2..]".into(),
+            "set -l wsp_root (command $wsp_bin dir 2>/dev/null); or set wsp_root $HOME".into(),
+            "set -l _wsp_name".into(),
+            "for _a in $args".into(),
+            "    if not string match -q -- '-*' $_a".into(),
+            "        set _wsp_name $_a".into(),
+            "        break".into(),
+            "    end".into(),
+            "end".into(),
+            "if test -n \"$_wsp_name\"; and set -l wsp_dir (command $wsp_bin dir $_wsp_name 2>/dev/null)"
+                .into(),
+            "    if test \"$PWD\" = \"$wsp_dir\"; or string match -q -- \"$wsp_dir/*\" $PWD".into(),
+            "        cd \"$wsp_root\"; or cd $HOME".into(),
+            "    end".into(),
+            "end".into(),
+            format!("command $wsp_bin {cmd_name} $args"),
+            "if not test -d $PWD".into(),
+            "    cd \"$wsp_root\"; or cd $HOME".into(),
+            "end".into(),
+        ],
+    };
+    lines.iter().map(|l| format!("            {l}\n")).collect()
+}
+
 fn write_fish_hooks(w: &mut dyn Write, root_esc: &str, hooks: ShellHookOpts) -> Result<()> {
     writeln!(w)?;
     writeln!(
@@ -497,6 +588,197 @@ fn write_fish_hooks(w: &mut dyn Write, root_esc: &str, hooks: ShellHookOpts) ->
     Ok(())
 }
 
+// ---------- nushell ----------
+
+fn generate_nushell(w: &mut dyn Write) -> Result<()> {
+    write_nushell(w, &bin_path()?)
+}
+
+/// nushell can't source generated code at runtime, so the script is a module
+/// saved to a file and imported from config.nu. Candidates come from clap's fish
+/// protocol (`value\tdescription` lines), which nushell's completer parses.
+fn write_nushell(w: &mut dyn Write, bin_str: &str) -> Result<()> {
+    let bin_q = nu_quote(bin_str);
+
+    write!(
+        w,
+        "# wsp shell integration \u{2014} a module; save it and use it from config.nu:\n\
+         #   wsp completion nushell | save -f ($nu.default-config-dir | path join wsp.nu)\n\
+         #   use wsp.nu *\n\
+         \n\
+         def \"nu-complete wsp\" [context: string] {{\n\
+         \x20   let words = ($context | str trim --left | split row -r '\\s+')\n\
+         \x20   with-env {{ COMPLETE: fish }} {{ ^{bin_q} -- ...$words }}\n\
+         \x20   | lines\n\
+         \x20   | each {{|line|\n\
+         \x20       let parts = ($line | split row \"\\t\")\n\
+         \x20       {{ value: $parts.0, description: ($parts.1? | default '') }}\n\
+         \x20   }}\n\
+         }}\n\
+         \n\
+         export def --env --wrapped wsp [...args: string@\"nu-complete wsp\"] {{\n\
+         \x20   let wsp_bin = {bin_q}\n\
+         \n\
+         \x20   match ($args.0? | default '') {{\n"
+    )?;
+
+    for case in &build_cases() {
+        let pattern = case
+            .names
+            .iter()
+            .map(|n| format!("\"{n}\""))
+            .collect::<Vec<_>>()
+            .join(" | ");
+        write!(
+            w,
+            "        {pattern} => {{\n{}        }}\n",
+            nu_case_body(case)
+        )?;
+    }
+
+    write!(
+        w,
+        "        _ => {{\n\
+         \x20           ^$wsp_bin ...$args\n\
+         \x20       }}\n\
+         \x20   }}\n\
+         }}\n"
+    )?;
+    Ok(())
+}
+
+fn nu_case_body(case: &ShellCase) -> String {
+    let cmd_name = &case.command;
+    let lines: Vec<String> = match case.action {
+        CaseAction::EnterNew => vec![
+            format!("let dir = (^$wsp_bin {cmd_name} --print-dir ...($args | skip 1))"),
+            "let dir = ($dir | str trim)".into(),
+            "if $dir != '' and ($dir | path exists) {".into(),
+            "    cd $dir".into(),
+            "} else if $dir != '' {".into(),
+            "    print $dir".into(),
+            "}".into(),
+        ],
+        CaseAction::GoTo => vec![
+            format!(
+                "let dir = (with-env {{ WSP_SHELL: '1' }} {{ ^$wsp_bin {cmd_name} ...($args | skip 1) }})"
+            ),
+            "if $env.LAST_EXIT_CODE != 0 { return }".into(),
+            "cd ($dir | str trim)".into(),
+        ],
+        CaseAction::Leave => vec![
+            "let rest = ($args | skip 1)".into(),
+            "let root = (^$wsp_bin dir | complete)".into(),
+            "let wsp_root = if $root.exit_code == 0 { $root.stdout | str trim } else { $env.HOME }"
+                .into(),
+            "let names = ($rest | where {|a| not ($a | str starts-with '-') })".into(),
+            "let found = if ($names | length) > 0 { ^$wsp_bin dir $names.0 | complete } else { null }"
+                .into(),
+            "if $found != null and $found.exit_code == 0 {".into(),
+            "    let wsp_dir = ($found.stdout | str trim)".into(),
+            "    if $env.PWD == $wsp_dir or ($env.PWD | str starts-with $\"($wsp_dir)/\") {".into(),
+            "        cd $wsp_root".into(),
+            "    }".into(),
+            "}".into(),
+            format!("^$wsp_bin {cmd_name} ...$rest"),
+            "if not ($env.PWD | path exists) {".into(),
+            "    cd $wsp_root".into(),
+            "}".into(),
+        ],
+    };
+    lines.iter().map(|l| format!("            {l}\n")).collect()
+}
+
+// ---------- powershell ----------
+
+fn generate_powershell(w: &mut dyn Write) -> Result<()> {
+    write_powershell(w, &bin_path()?)
+}
+
+fn write_powershell(w: &mut dyn Write, bin_str: &str) -> Result<()> {
+    let bin_esc = powershell_escape(bin_str);
+
+    write!(
+        w,
+        "# wsp shell integration \u{2014} add to $PROFILE:\n\
+         #   Invoke-Expression (& wsp completion powershell | Out-String)\n\
+         \n\
+         function wsp {{\n\
+         \x20   $wsp_bin = '{bin_esc}'\n\
+         \x20   $wsp_args = @($args)\n\
+         \x20   $rest = @($wsp_args | Select-Object -Skip 1)\n\
+         \n\
+         \x20   switch ($wsp_args[0]) {{\n"
+    )?;
+
+    for case in &build_cases() {
+        let names = case
+            .names
+            .iter()
+            .map(|n| format!("'{n}'"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(
+            w,
+            "        {{ $_ -cin {names} }} {{\n{}        }}\n",
+            powershell_case_body(case)
+        )?;
+    }
+
+    write!(
+        w,
+        "        default {{\n\
+         \x20           & $wsp_bin @wsp_args\n\
+         \x20       }}\n\
+         \x20   }}\n\
+         }}\n\
+         \n\
+         $env:COMPLETE = 'powershell'\n\
+         try {{ & '{bin_esc}' | Out-String | Invoke-Expression }} finally {{ Remove-Item Env:\\COMPLETE }}\n"
+    )?;
+    Ok(())
+}
+
+fn powershell_case_body(case: &ShellCase) -> String {
+    let cmd_name = &case.command;
+    let lines: Vec<String> = match case.action {
+        CaseAction::EnterNew => vec![
+            format!("$dir = & $wsp_bin {cmd_name} --print-dir @rest"),
+            "if ($dir -is [string] -and (Test-Path -LiteralPath $dir -PathType Container)) {"
+                .into(),
+            "    Set-Location -LiteralPath $dir".into(),
+            "} elseif ($dir) {".into(),
+            "    $dir".into(),
+            "}".into(),
+        ],
+        CaseAction::GoTo => vec![
+            "$env:WSP_SHELL = '1'".into(),
+            format!("try {{ $dir = & $wsp_bin {cmd_name} @rest }} finally {{ Remove-Item Env:\\WSP_SHELL }}"),
+            "if ($LASTEXITCODE -ne 0) { return }".into(),
+            "Set-Location -LiteralPath $dir".into(),
+        ],
+        CaseAction::Leave => vec![
+            "$wsp_root = & $wsp_bin dir 2>$null".into(),
+            "if ($LASTEXITCODE -ne 0) { $wsp_root = $HOME }".into(),
+            "$name = $rest | Where-Object { -not $_.StartsWith('-') } | Select-Object -First 1"
+                .into(),
+            "if ($name) { $wsp_dir = & $wsp_bin dir $name 2>$null }".into(),
+            "if ($name -and $LASTEXITCODE -eq 0) {".into(),
+            "    $here = (Get-Location).Path".into(),
+            "    if ($here -eq $wsp_dir -or $here.StartsWith($wsp_dir + [IO.Path]::DirectorySeparatorChar)) {"
+                .into(),
+            "        Set-Location -LiteralPath $wsp_root".into(),
+            "    }".into(),
+            "}".into(),
+            format!("& $wsp_bin {cmd_name} @rest"),
+            "if (-not (Test-Path -LiteralPath (Get-Location).Path)) {".into(),
+            "    Set-Location -LiteralPath $wsp_root".into(),
+            "}".into(),
+        ],
+    };
+    lines.iter().map(|l| format!("            {l}\n")).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -540,27 +822,20 @@ mod tests {
                 "case {}: wsp_bin should be single-quoted",
                 tc.name
             );
+            // Workspace paths are resolved at run time, so a later
+            // workspaces_dir change doesn't need a new script.
             assert!(
-                out.contains("local wsp_root='/home/user/dev'"),
-                "case {}: wsp_root should be single-quoted",
+                !out.contains("/home/user/dev"),
+                "case {}: wsp_root should not be baked into the wrapper",
                 tc.name
             );
-            // wsp_root should be referenced as $wsp_root, not interpolated
             assert!(
-                out.contains("$wsp_root/"),
-                "case {}: wsp_root should be referenced as variable",
+                out.contains("command \"$wsp_bin\" dir \"$_wsp_name\""),
+                "case {}: workspace paths should come from `wsp dir`",
                 tc.name
             );
             assert!(
-                !out.contains("\"/home/user/dev/"),
-                "case {}: wsp_root should not be interpolated directly into commands",
-                tc.name
-            );
-            assert!(
-                out.contains(&format!(
-                    "source <(COMPLETE={} '/opt/my tools/ws')",
-                    tc.shell
-                )),
+                out.contains(&format!("COMPLETE={} '/opt/my tools/ws')", tc.shell)),
                 "case {}: COMPLETE line should be single-quoted",
                 tc.name
             );
@@ -608,6 +883,102 @@ mod tests {
         assert!(zsh.contains("eval \"$(wsp completion zsh)\""));
     }
 
+    #[test]
+    fn test_bash_evals_completions() {
+        let out = output(|w| {
+            write_posix(
+                w,
+                "/usr/bin/wsp",
+                "/home/user/dev",
+                "bash",
+                ShellHookOpts::default(),
+            )
+        });
+        assert!(
+            out.contains("eval \"$(COMPLETE=bash '/usr/bin/wsp')\""),
+            "bash should eval completions: {}",
+            out
+        );
+        assert!(
+            !out.contains("source <("),
+            "bash 3.2 can't source a process substitution"
+        );
+    }
+
+    #[test]
+    fn test_bash_script_parses() {
+        let opts = ShellHookOpts {
+            prompt: true,
+            tmux: TmuxMode::WindowTitle,
+        };
+        let out = output(|w| write_posix(w, "/usr/bin/wsp", "/home/o'brien/dev", "bash", opts));
+        let check = match std::process::Command::new("bash")
+            .args(["-n", "-c", &out])
+            .output()
+        {
+            Ok(check) => check,
+            Err(_) => return, // no bash to check with
+        };
+        assert!(
+            check.status.success(),
+            "bash -n: {}",
+            String::from_utf8_lossy(&check.stderr)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bash_new_enters_printed_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let ws_dir = tmp.path().join("proj-42-fix-login");
+        std::fs::create_dir(&ws_dir).unwrap();
+        // Stands in for `wsp new` leaving a repo pending: the path on
+        // stdout, progress on stderr, exit code 1.
+        let bin = tmp.path().join("wsp");
+        std::fs::write(
+            &bin,
+            format!(
+                "#!/bin/sh\n\
+                 [ \"$1 $2\" = \"new --print-dir\" ] || exit 0\n\
+                 echo cloning >&2\n\
+                 echo '{}'\n\
+                 exit 1\n",
+                ws_dir.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&bin, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let script = output(|w| {
+            write_posix(
+                w,
+                bin.to_str().unwrap(),
+                "/home/user/dev",
+                "bash",
+                ShellHookOpts::default(),
+            )
+        });
+        let run = match std::process::Command::new("bash")
+            .arg("-c")
+            .arg(format!(
+                "{}\nwsp new --issue PROJ-42 api; echo \"$? $PWD\"",
+                script
+            ))
+            .output()
+        {
+            Ok(run) => run,
+            Err(_) => return, // no bash to run it with
+        };
+        assert_eq!(
+            String::from_utf8_lossy(&run.stdout).trim(),
+            format!("1 {}", ws_dir.display()),
+            "{}",
+            String::from_utf8_lossy(&run.stderr)
+        );
+    }
+
     #[test]
     fn test_fish_quotes_bin_path_and_wsp_root() {
         let out = output(|w| {
@@ -623,16 +994,12 @@ mod tests {
             "wsp_bin should be single-quoted"
         );
         assert!(
-            out.contains("set -l wsp_root '/home/user/dev'"),
-            "wsp_root should be single-quoted"
-        );
-        assert!(
-            out.contains("$wsp_root/"),
-            "wsp_root should be referenced as variable"
+            !out.contains("/home/user/dev"),
+            "wsp_root should not be baked into the wrapper"
         );
         assert!(
-            !out.contains("\"/home/user/dev/"),
-            "wsp_root should not be interpolated directly"
+            out.contains("command $wsp_bin dir $_wsp_name"),
+            "workspace paths should come from `wsp dir`"
         );
         assert!(
             out.contains("COMPLETE=fish '/opt/my tools/ws' | source"),
@@ -649,6 +1016,112 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_build_cases_include_aliases() {
+        let cases = build_cases();
+        let names: Vec<(&str, Vec<&str>)> = cases
+            .iter()
+            .map(|c| {
+                (
+                    c.command.as_str(),
+                    c.names.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("new", vec!["new"]),
+                ("cd", vec!["cd"]),
+                ("rm", vec!["rm", "remove"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cd_out_matches_whole_workspace_dir() {
+        // Removing `foo` must not cd out of a sibling like `foobar`.
+        let posix = output(|w| {
+            write_posix(
+                w,
+                "/usr/bin/wsp",
+                "/home/user/dev",
+                "bash",
+                ShellHookOpts::default(),
+            )
+        });
+        assert!(posix.contains(r#"[[ "$PWD" = "$wsp_dir" || "$PWD" = "$wsp_dir"/* ]]"#));
+        let fish = output(|w| {
+            write_fish(
+                w,
+                "/usr/bin/wsp",
+                "/home/user/dev",
+                ShellHookOpts::default(),
+            )
+        });
+        assert!(fish.contains(r#"string match -q -- "$wsp_dir/*" $PWD"#));
+        assert!(!fish.contains(r#""$wsp_dir*""#));
+    }
+
+    #[test]
+    fn test_nu_quote() {
+        let cases = vec![
+            ("/usr/bin/wsp", "r#'/usr/bin/wsp'#"),
+            ("/home/o'brien", "r#'/home/o'brien'#"),
+            ("/odd/'#dir", "r##'/odd/'#dir'##"),
+        ];
+        for (input, want) in cases {
+            assert_eq!(nu_quote(input), want, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_nushell_wrapper() {
+        let out = output(|w| write_nushell(w, "/opt/it's here/wsp"));
+        assert!(
+            out.contains("let wsp_bin = r#'/opt/it's here/wsp'#"),
+            "{}",
+            out
+        );
+        assert!(
+            out.contains("export def --env --wrapped wsp [...args: string@\"nu-complete wsp\"]"),
+            "wrapper must be --env to change the caller's directory"
+        );
+        assert!(out.contains("with-env { COMPLETE: fish }"));
+        for pattern in &["\"new\" =>", "\"cd\" =>", "\"rm\" | \"remove\" =>", "_ =>"] {
+            assert!(out.contains(pattern), "missing match arm: {}", pattern);
+        }
+        assert!(
+            out.contains("^$wsp_bin dir $names.0"),
+            "workspace paths should come from `wsp dir`"
+        );
+    }
+
+    #[test]
+    fn test_powershell_wrapper() {
+        let out = output(|w| write_powershell(w, "C:\\Program Files\\o'brien\\wsp.exe"));
+        assert!(
+            out.contains("$wsp_bin = 'C:\\Program Files\\o''brien\\wsp.exe'"),
+            "single quote must be doubled: {}",
+            out
+        );
+        assert!(out.contains(
+            "try { & 'C:\\Program Files\\o''brien\\wsp.exe' | Out-String | Invoke-Expression }"
+        ));
+        for pattern in &[
+            "{ $_ -cin 'new' }",
+            "{ $_ -cin 'cd' }",
+            "{ $_ -cin 'rm', 'remove' }",
+            "default {",
+        ] {
+            assert!(out.contains(pattern), "missing switch clause: {}", pattern);
+        }
+        assert!(
+            out.contains("& $wsp_bin dir $name"),
+            "workspace paths should come from `wsp dir`"
+        );
+    }
+
     #[test]
     fn test_fish_header() {
         let out =
@@ -680,10 +1153,13 @@ mod tests {
                 "/usr/bin/wsp",
                 "/home/o'brien/dev",
                 "bash",
-                ShellHookOpts::default(),
+                ShellHookOpts {
+                    prompt: true,
+                    tmux: TmuxMode::Off,
+                },
             )
         });
-        // Single quote in wsp_root must be escaped as '\''
+        // Single quote in the hook's wsp_root must be escaped as '\''
         assert!(
             out.contains(r"local wsp_root='/home/o'\''brien/dev'"),
             "wsp_root single quote must be escaped: {}",
@@ -721,7 +1197,10 @@ mod tests {
                 w,
                 "/usr/bin/wsp",
                 "/home/o'brien/dev",
-                ShellHookOpts::default(),
+                ShellHookOpts {
+                    prompt: true,
+                    tmux: TmuxMode::Off,
+                },
             )
         });
         assert!(
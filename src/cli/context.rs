@@ -0,0 +1,117 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use clap_complete::engine::ArgValueCandidates;
+
+use crate::config::Paths;
+use crate::error;
+use crate::gc;
+use crate::git;
+use crate::output::{ContextOutput, ContextRepoEntry, Output};
+use crate::workspace;
+
+use super::completers;
+
+pub fn cmd() -> Command {
+    Command::new("context")
+        .about("Print a workspace briefing for LLM prompts [read-only]")
+        .long_about(
+            "Print a workspace briefing for LLM prompts [read-only].\n\n\
+             Writes a compact Markdown document describing the current (or named) \
+             workspace to stdout: each repo's identity, path, role, ref, branch with \
+             ahead/behind counts, uncommitted files, and most recent commits. Meant to be \
+             piped into an agent or pasted into a prompt; unlike AGENTS.md it is built on \
+             demand and touches no files. Use --json for the same data as JSON.",
+        )
+        .arg(Arg::new("workspace").add(ArgValueCandidates::new(completers::complete_workspaces)))
+        .arg(
+            Arg::new("commits")
+                .short('n')
+                .long("commits")
+                .value_name("N")
+                .help("Recent commits to include per repo")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("5"),
+        )
+}
+
+pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    let ws_dir: PathBuf = match matches.get_one::<String>("workspace") {
+        Some(name) => paths.workspace_dir(name),
+        None => workspace::detect(&std::env::current_dir()?)?,
+    };
+    let commits = *matches.get_one::<usize>("commits").unwrap();
+
+    gc::check_workspace(&ws_dir, /* read_only */ true)?;
+
+    let meta =
+        workspace::load_metadata(&ws_dir).map_err(|e| error::prefixed(e, "reading workspace"))?;
+
+    let repos = meta
+        .repos
+        .iter()
+        .map(|(identity, repo_ref)| {
+            let mut entry = ContextRepoEntry {
+                identity: identity.clone(),
+                dir_name: String::new(),
+                path: String::new(),
+                role: "active".into(),
+                r#ref: repo_ref
+                    .as_ref()
+                    .map(|r| r.r#ref.clone())
+                    .filter(|r| !r.is_empty()),
+                branch: None,
+                upstream: None,
+                ahead: 0,
+                behind: 0,
+                changed_files: Vec::new(),
+                recent_commits: Vec::new(),
+                error: None,
+            };
+            let dir_name = match meta.dir_name(identity) {
+                Ok(d) => d,
+                Err(e) => {
+                    entry.error = Some(e.to_string());
+                    return entry;
+                }
+            };
+            let clone_dir = ws_dir.join(&dir_name);
+            entry.dir_name = dir_name;
+            entry.path = clone_dir.display().to_string();
+            if !clone_dir.exists() {
+                entry.error = Some("clone directory is missing".into());
+                return entry;
+            }
+
+            entry.branch = git::branch_current(&clone_dir).ok();
+            let upstream = git::resolve_upstream_ref(&clone_dir);
+            entry.upstream = match &upstream {
+                git::UpstreamRef::Tracking => git::upstream_name(&clone_dir).ok(),
+                git::UpstreamRef::DefaultBranch(b) => Some(format!("origin/{}", b)),
+                git::UpstreamRef::Head => None,
+            };
+            entry.ahead = git::ahead_count_from(&clone_dir, &upstream).unwrap_or(0);
+            entry.behind = git::behind_count_from(&clone_dir, &upstream).unwrap_or(0);
+            match git::changed_files(&clone_dir) {
+                Ok(files) => entry.changed_files = files,
+                Err(e) => entry.error = Some(e.to_string()),
+            }
+            if commits > 0 {
+                let n = commits.to_string();
+                entry.recent_commits =
+                    super::log::fetch_commits(&clone_dir, &["-n", &n, "HEAD"]).unwrap_or_default();
+            }
+            entry
+        })
+        .collect();
+
+    Ok(Output::Context(ContextOutput {
+        workspace: meta.name,
+        branch: meta.branch,
+        workspace_dir: ws_dir,
+        description: meta.description,
+        issue: meta.issue,
+        repos,
+    }))
+}
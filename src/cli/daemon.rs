@@ -0,0 +1,261 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+
+use crate::config::{self, Paths};
+use crate::filelock::FileLock;
+use crate::giturl;
+use crate::mirror;
+use crate::output::{FetchOutput, FetchRepoResult, Output};
+use crate::workspace;
+
+pub fn cmd() -> Command {
+    Command::new("daemon")
+        .about("Keep mirrors fresh by fetching them in the background")
+        .long_about(
+            "Keep mirrors fresh by fetching them in the background.\n\n\
+             Runs until interrupted, fetching every registered repo's mirror from \
+             upstream each --interval and propagating the new refs into the clones of \
+             every workspace, so `wsp st`, `wsp diff`, and friends see current remote \
+             state without fetching themselves. A repo fetched within --ttl (by the \
+             daemon or by any other wsp command) is skipped, and at most --jobs fetches \
+             run at once.\n\n\
+             Only one daemon runs at a time. `wsp st` shows how long ago a workspace's \
+             repos were fetched. For a scheduler-driven alternative, run \
+             `wsp daemon --once` from cron or a systemd timer.",
+        )
+        .arg(
+            Arg::new("interval")
+                .long("interval")
+                .value_name("SECS")
+                .help("Seconds between fetch rounds")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .default_value("300"),
+        )
+        .arg(
+            Arg::new("ttl")
+                .long("ttl")
+                .value_name("SECS")
+                .help("Skip repos fetched less than this many seconds ago")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("300"),
+        )
+        .arg(
+            Arg::new("jobs")
+                .short('j')
+                .long("jobs")
+                .value_name("N")
+                .help("Maximum concurrent fetches")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .default_value("4"),
+        )
+        .arg(
+            Arg::new("prune")
+                .long("prune")
+                .help("Prune deleted remote branches")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("once")
+                .long("once")
+                .help("Run a single round and exit")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    crate::offline::ensure_online("wsp daemon")?;
+    let interval = Duration::from_secs(*matches.get_one::<u64>("interval").unwrap());
+    let ttl = chrono::Duration::seconds(*matches.get_one::<u64>("ttl").unwrap() as i64);
+    let jobs = *matches.get_one::<u64>("jobs").unwrap() as usize;
+    let prune = matches.get_flag("prune");
+
+    let lock_target = paths.data_dir().join("daemon");
+    let _lock = FileLock::acquire(&lock_target, Duration::ZERO).map_err(|_| {
+        let pid =
+            std::fs::read_to_string(FileLock::lock_path_for(&lock_target)).unwrap_or_default();
+        anyhow::anyhow!("wsp daemon is already running (PID {})", pid.trim())
+    })?;
+
+    loop {
+        let repos = round(paths, ttl, jobs, prune)?;
+        if matches.get_flag("once") {
+            return Ok(Output::Fetch(FetchOutput {
+                workspace: String::new(),
+                repos,
+            }));
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+/// Fetch every registered mirror that is due, then propagate to the
+/// workspaces using them. The config is reread each round so newly
+/// registered repos are picked up.
+fn round(
+    paths: &Paths,
+    ttl: chrono::Duration,
+    jobs: usize,
+    prune: bool,
+) -> Result<Vec<FetchRepoResult>> {
+    let cfg = config::Config::load_from(&paths.config_path)?;
+    let now = Utc::now();
+    let mut due = Vec::new();
+    for identity in cfg.repos.keys() {
+        let Ok(parsed) = giturl::Parsed::from_identity(identity) else {
+            continue;
+        };
+        if !mirror::exists(&paths.mirrors_dir, &parsed) {
+            continue;
+        }
+        if is_due(mirror::last_fetched(&paths.mirrors_dir, &parsed), now, ttl) {
+            due.push((identity.clone(), mirror::dir(&paths.mirrors_dir, &parsed)));
+        }
+    }
+    if due.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let ids: Vec<String> = due.iter().map(|(id, _)| id.clone()).collect();
+    let shortnames = giturl::shortnames(&ids);
+    eprintln!(
+        "{} fetching {} repo(s)",
+        Utc::now().format("%Y-%m-%d %H:%M:%S"),
+        due.len()
+    );
+
+    let results = fetch_bounded(&due, jobs, prune);
+    let mut out = Vec::new();
+    for ((id, _), result) in due.iter().zip(results) {
+        let name = shortnames.get(id).cloned().unwrap_or_else(|| id.clone());
+        match &result {
+            Ok(()) => eprintln!("  ok    {}", name),
+            Err(e) => eprintln!("  FAIL  {} ({})", name, e),
+        }
+        out.push(FetchRepoResult {
+            identity: id.clone(),
+            shortname: name,
+            ok: result.is_ok(),
+            error: result.err().map(|e| e.to_string()),
+            retries: 0,
+            objects: None,
+            bytes: None,
+            duration_ms: None,
+            skipped: false,
+            cloned: false,
+        });
+    }
+
+    let fetched: Vec<&str> = out
+        .iter()
+        .filter(|r| r.ok)
+        .map(|r| r.identity.as_str())
+        .collect();
+    for name in workspace::list_all(paths).unwrap_or_default() {
+        let ws_dir = paths.workspace_dir(&name);
+        if let Ok(meta) = workspace::load_metadata(&ws_dir)
+            && meta.repos.keys().any(|id| fetched.contains(&id.as_str()))
+        {
+            workspace::propagate_mirror_to_clones(&paths.mirrors_dir, &ws_dir, &meta, prune);
+        }
+    }
+    Ok(out)
+}
+
+/// A mirror is due when it has never been fetched or its last fetch is at
+/// least `ttl` old.
+fn is_due(last: Option<DateTime<Utc>>, now: DateTime<Utc>, ttl: chrono::Duration) -> bool {
+    last.is_none_or(|t| now - t >= ttl)
+}
+
+/// Fetch each mirror with at most `jobs` fetches in flight. Results are in
+/// the order of `mirrors`.
+fn fetch_bounded(mirrors: &[(String, PathBuf)], jobs: usize, prune: bool) -> Vec<Result<()>> {
+    let next = Mutex::new(0usize);
+    let results: Mutex<Vec<Option<Result<()>>>> =
+        Mutex::new((0..mirrors.len()).map(|_| None).collect());
+    std::thread::scope(|s| {
+        for _ in 0..jobs.min(mirrors.len()) {
+            s.spawn(|| {
+                loop {
+                    let i = {
+                        let mut next = next.lock().unwrap_or_else(|e| e.into_inner());
+                        let i = *next;
+                        *next += 1;
+                        i
+                    };
+                    let Some((_, dir)) = mirrors.get(i) else {
+                        break;
+                    };
+                    let result = mirror::fetch_dir(dir, prune);
+                    results.lock().unwrap_or_else(|e| e.into_inner())[i] = Some(result);
+                }
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner())
+        .into_iter()
+        .map(|r| r.unwrap_or_else(|| Err(anyhow::anyhow!("fetch did not run"))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_due() {
+        let now = Utc::now();
+        let ttl = chrono::Duration::minutes(5);
+        let cases = vec![
+            ("never fetched", None, true),
+            (
+                "just fetched",
+                Some(now - chrono::Duration::seconds(10)),
+                false,
+            ),
+            ("at the ttl", Some(now - ttl), true),
+            ("stale", Some(now - chrono::Duration::hours(1)), true),
+        ];
+        for (name, last, want) in cases {
+            assert_eq!(is_due(last, now, ttl), want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_fetch_bounded_keeps_order() {
+        let (good, _source, _ct, _st) = crate::testutil::setup_clone_repo();
+        let missing = good.with_file_name("missing");
+        let mirrors = vec![
+            ("good".to_string(), good.clone()),
+            ("missing".to_string(), missing),
+            ("good-again".to_string(), good),
+        ];
+        for jobs in [1, 2, 8] {
+            let ok: Vec<bool> = fetch_bounded(&mirrors, jobs, false)
+                .iter()
+                .map(|r| r.is_ok())
+                .collect();
+            assert_eq!(ok, vec![true, false, true], "jobs={}", jobs);
+        }
+    }
+
+    #[test]
+    fn test_daemon_args() {
+        let cases: Vec<(Vec<&str>, bool)> = vec![
+            (vec!["daemon"], true),
+            (vec!["daemon", "--once", "-j", "2", "--ttl", "0"], true),
+            (vec!["daemon", "--interval", "0"], false),
+            (vec!["daemon", "--jobs", "0"], false),
+        ];
+        for (args, ok) in cases {
+            assert_eq!(cmd().try_get_matches_from(&args).is_ok(), ok, "{:?}", args);
+        }
+    }
+}
@@ -1,9 +1,14 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
 use clap::{Arg, ArgMatches, Command};
 use clap_complete::engine::ArgValueCandidates;
 
 use crate::config::Paths;
+use crate::confirm;
+use crate::error;
+use crate::offline::FetchPolicy;
 use crate::output::{MutationOutput, Output};
+use crate::picker;
 use crate::workspace;
 
 use super::completers;
@@ -16,11 +21,22 @@ pub fn cmd() -> Command {
             "Remove a workspace.\n\n\
              Fetches from upstream, checks whether the workspace branch has been merged \
              (regular, squash, or rebase merge), and removes the workspace if safe. \
-             Unmerged or pushed-but-unmerged branches block removal unless --force is used.\n\n\
+             Unmerged or pushed-but-unmerged branches block removal unless --force is used. \
+             With --no-fetch, merge status is checked against the last fetch.\n\n\
              By default, workspaces are moved to a gc directory and can be recovered with \
-             `wsp recover`. Use --permanent to skip gc and delete immediately.",
+             `wsp recover`. Use --permanent to skip gc and delete immediately.\n\n\
+             With -i, pick any number of workspaces from a list annotated with their \
+             state (merged, dirty, stale) and remove each one with the same checks.",
         )
         .arg(Arg::new("workspace").add(ArgValueCandidates::new(completers::complete_workspaces)))
+        .arg(
+            Arg::new("interactive")
+                .short('i')
+                .long("interactive")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("workspace")
+                .help("Pick workspaces to remove from a list"),
+        )
         .arg(
             Arg::new("force")
                 .short('f')
@@ -34,11 +50,19 @@ pub fn cmd() -> Command {
                 .action(clap::ArgAction::SetTrue)
                 .help("Permanently delete instead of deferring for gc"),
         )
+        .arg(super::no_fetch_arg())
 }
 
 pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     let force = matches.get_flag("force");
     let permanent = matches.get_flag("permanent");
+    let yes = matches.get_flag("yes");
+    let fetch = FetchPolicy::from_flag(matches.get_flag("no-fetch"));
+    fetch.note_skipped();
+
+    if matches.get_flag("interactive") {
+        return run_interactive(paths, force, permanent, yes, fetch);
+    }
 
     let name = if let Some(n) = matches.get_one::<String>("workspace") {
         n.clone()
@@ -46,29 +70,207 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
         let cwd = std::env::current_dir()?;
         let ws_dir = workspace::detect(&cwd)?;
         let meta = workspace::load_metadata(&ws_dir)
-            .map_err(|e| anyhow::anyhow!("reading workspace: {}", e))?;
+            .map_err(|e| error::prefixed(e, "reading workspace"))?;
         meta.name
     };
 
-    eprintln!("Removing workspace {:?}...", name);
-    workspace::remove(paths, &name, force, permanent)?;
+    remove_one(paths, &name, force, permanent, yes, fetch)?;
 
     let mut out = MutationOutput::new(format!("Workspace {:?} removed.", name));
+    if !force {
+        out = out.with_fetch_skipped(fetch);
+    }
+    if !permanent {
+        out = out.with_hint(recover_hint(paths));
+    }
+    Ok(Output::Mutation(out))
+}
+
+/// Remove with the usual safety checks, which run the pre-remove hook once
+/// they pass. With --force, first confirm if local work would be lost.
+fn remove_one(
+    paths: &Paths,
+    name: &str,
+    force: bool,
+    permanent: bool,
+    yes: bool,
+    fetch: FetchPolicy,
+) -> Result<()> {
+    let ws_dir = paths.workspace_dir(name);
+    if force && let Ok(meta) = workspace::load_metadata(&ws_dir) {
+        let identities: Vec<String> = meta.repos.keys().cloned().collect();
+        let lost: Vec<String> = workspace::repo_removal_states(&ws_dir, &meta, &identities)
+            .iter()
+            .filter_map(|r| {
+                r.at_risk()
+                    .map(|risk| format!("  - {}: {}", r.identity, risk))
+            })
+            .collect();
+        if !lost.is_empty() {
+            confirm::confirm(
+                yes,
+                &format!(
+                    "Force-removing workspace {:?} ({}){} discards:\n{}",
+                    name,
+                    meta.branch,
+                    if permanent { " permanently" } else { "" },
+                    lost.join("\n")
+                ),
+                "Remove anyway?",
+            )?;
+        }
+    }
+
+    eprintln!("Removing workspace {:?}...", name);
+    workspace::remove(paths, name, force, permanent, fetch)
+}
+
+fn recover_hint(paths: &Paths) -> String {
+    let cfg = crate::config::Config::load_from(&paths.config_path).unwrap_or_default();
+    let days = cfg
+        .gc_retention_days
+        .unwrap_or(crate::gc::DEFAULT_RETENTION_DAYS);
+    if days == 0 {
+        "recoverable via `wsp recover` (gc disabled, kept indefinitely)".into()
+    } else {
+        format!(
+            "recoverable via `wsp recover` for {} day{}",
+            days,
+            if days == 1 { "" } else { "s" }
+        )
+    }
+}
+
+/// Workspaces not used (or created) for this many days are marked stale.
+const STALE_DAYS: i64 = 30;
+
+fn run_interactive(
+    paths: &Paths,
+    force: bool,
+    permanent: bool,
+    yes: bool,
+    fetch: FetchPolicy,
+) -> Result<Output> {
+    let names = workspace::list_all(paths)?;
+    if names.is_empty() {
+        bail!("no workspaces to remove");
+    }
+
+    eprintln!("Checking {} workspaces...", names.len());
+    let labels: Vec<Vec<String>> = std::thread::scope(|s| {
+        let handles: Vec<_> = names
+            .iter()
+            .map(|name| s.spawn(move || state_labels(paths, name, Utc::now())))
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap_or_default())
+            .collect()
+    });
+
+    let width = names.iter().map(|n| n.len()).max().unwrap_or(0);
+    let items = names
+        .iter()
+        .zip(&labels)
+        .map(|(name, labels)| format!("{:width$}  {}", name, labels.join(", "), width = width))
+        .collect();
+    let chosen = picker::pick_many("Select workspaces to remove:", items)?;
+    if chosen.is_empty() {
+        bail!("no workspaces selected");
+    }
+
+    let mut removed = Vec::new();
+    let mut failed = Vec::new();
+    for i in chosen {
+        let name = &names[i];
+        match remove_one(paths, name, force, permanent, yes, fetch) {
+            Ok(()) => removed.push(name.clone()),
+            Err(e) => {
+                eprintln!("  FAIL  {}: {}", name, e);
+                failed.push(name.clone());
+            }
+        }
+    }
+    if !failed.is_empty() {
+        bail!(
+            "{} of {} workspaces not removed: {}",
+            failed.len(),
+            failed.len() + removed.len(),
+            failed.join(", ")
+        );
+    }
+
+    let mut out = MutationOutput::new(format!(
+        "Removed {} workspace{}: {}",
+        removed.len(),
+        if removed.len() == 1 { "" } else { "s" },
+        removed.join(", ")
+    ));
+    if !force {
+        out = out.with_fetch_skipped(fetch);
+    }
     if !permanent {
-        let cfg = crate::config::Config::load_from(&paths.config_path).unwrap_or_default();
-        let days = cfg
-            .gc_retention_days
-            .unwrap_or(crate::gc::DEFAULT_RETENTION_DAYS);
-        let hint = if days == 0 {
-            "recoverable via `wsp recover` (gc disabled, kept indefinitely)".into()
-        } else {
-            format!(
-                "recoverable via `wsp recover` for {} day{}",
-                days,
-                if days == 1 { "" } else { "s" }
-            )
-        };
-        out = out.with_hint(hint);
+        out = out.with_hint(recover_hint(paths));
     }
     Ok(Output::Mutation(out))
 }
+
+/// Annotations shown next to a workspace in the picker.
+fn state_labels(paths: &Paths, name: &str, now: DateTime<Utc>) -> Vec<String> {
+    let ws_dir = paths.workspace_dir(name);
+    let Ok(meta) = workspace::load_metadata(&ws_dir) else {
+        return vec!["unreadable".into()];
+    };
+    let state = workspace::removal_state(&ws_dir, &meta);
+    labels(&state, meta.last_used.unwrap_or(meta.created), now)
+}
+
+fn labels(
+    state: &workspace::RemovalState,
+    last_used: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    let mut out = Vec::new();
+    if state.merged {
+        out.push("merged".to_string());
+    }
+    if state.dirty {
+        out.push("dirty".to_string());
+    }
+    let idle = (now - last_used).num_days();
+    if idle >= STALE_DAYS {
+        out.push(format!("stale {}d", idle));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_labels() {
+        let now = Utc::now();
+        let state = |merged, dirty| workspace::RemovalState { dirty, merged };
+        let cases = vec![
+            ("fresh", state(false, false), 0, vec![]),
+            ("merged", state(true, false), 2, vec!["merged"]),
+            (
+                "dirty and stale",
+                state(false, true),
+                45,
+                vec!["dirty", "stale 45d"],
+            ),
+            (
+                "merged and stale",
+                state(true, false),
+                30,
+                vec!["merged", "stale 30d"],
+            ),
+        ];
+        for (name, state, idle_days, want) in cases {
+            let got = labels(&state, now - chrono::Duration::days(idle_days), now);
+            assert_eq!(got, want, "{}", name);
+        }
+    }
+}
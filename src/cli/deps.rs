@@ -0,0 +1,144 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use clap_complete::engine::ArgValueCandidates;
+
+use crate::config::{self, Paths};
+use crate::deps;
+use crate::error;
+use crate::giturl;
+use crate::output::{DepsGraphOutput, DepsGraphRepo, Output};
+use crate::workspace;
+
+use super::completers;
+
+pub fn cmd() -> Command {
+    Command::new("deps")
+        .about("Inspect declared repo dependencies")
+        .long_about(
+            "Inspect declared repo dependencies.\n\n\
+             Dependencies are declared with `wsp config set depends-on.<repo> <repos>`, \
+             globally or per workspace. They govern the order of exec, sync, and run.",
+        )
+        .subcommand(graph_cmd())
+}
+
+pub fn graph_cmd() -> Command {
+    Command::new("graph")
+        .about("Print the repo dependency graph as DOT or Mermaid [read-only]")
+        .long_about(
+            "Print the repo dependency graph as DOT or Mermaid.\n\n\
+             Shows every repo that declares or is the target of a dependency. Inside a \
+             workspace (or with a workspace name), workspace config overrides are applied \
+             and the workspace's repos are highlighted. Pipe DOT output to graphviz \
+             (`wsp deps graph | dot -Tsvg > deps.svg`) or paste Mermaid into docs.",
+        )
+        .arg(
+            Arg::new("workspace")
+                .required(false)
+                .add(ArgValueCandidates::new(completers::complete_workspaces)),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_parser(["dot", "mermaid", "table", "json", "yaml", "csv"])
+                .default_value("dot")
+                .help("Graph syntax to print, or a structured output format"),
+        )
+}
+
+pub fn dispatch(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    match matches.subcommand() {
+        Some(("graph", m)) => run_graph(m, paths),
+        None => run_graph(&graph_cmd().get_matches_from(["graph"]), paths),
+        _ => unreachable!(),
+    }
+}
+
+pub fn run_graph(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    // Structured formats are picked up by the renderer; anything else is a
+    // text graph.
+    let format = match matches.get_one::<String>("format").map(|s| s.as_str()) {
+        Some("mermaid") => "mermaid".to_string(),
+        _ => "dot".to_string(),
+    };
+    let cfg = config::Config::load_from(&paths.config_path)?;
+
+    let meta = match matches.get_one::<String>("workspace") {
+        Some(name) => Some(
+            workspace::load_metadata(&paths.workspace_dir(name))
+                .map_err(|e| error::prefixed(e, "reading workspace"))?,
+        ),
+        None => std::env::current_dir()
+            .ok()
+            .and_then(|cwd| workspace::detect(&cwd).ok())
+            .and_then(|ws_dir| workspace::load_metadata(&ws_dir).ok()),
+    };
+    let effective = match &meta {
+        Some(m) => m.apply_workspace_config(&cfg),
+        None => cfg.clone(),
+    };
+
+    let ws_ids: BTreeSet<String> = meta
+        .as_ref()
+        .map(|m| m.repos.keys().cloned().collect())
+        .unwrap_or_default();
+    let mut known: BTreeSet<String> = cfg.repos.keys().cloned().collect();
+    known.extend(ws_ids.iter().cloned());
+    let known: Vec<String> = known.into_iter().collect();
+
+    let dag = deps::graph(effective.depends_on.as_ref(), &known);
+    let targets: BTreeSet<&String> = known.iter().flat_map(|id| dag.deps(id)).collect();
+    let shortnames = giturl::shortnames(&known);
+
+    let repos = known
+        .iter()
+        .filter(|id| ws_ids.contains(*id) || targets.contains(id) || dag.deps(id).next().is_some())
+        .map(|id| DepsGraphRepo {
+            identity: id.clone(),
+            shortname: shortnames.get(id).cloned().unwrap_or_else(|| id.clone()),
+            in_workspace: ws_ids.contains(id),
+            depends_on: dag.deps(id).cloned().collect(),
+        })
+        .collect();
+
+    Ok(Output::DepsGraph(DepsGraphOutput {
+        workspace: meta.map(|m| m.name),
+        repos,
+        format,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_args() {
+        let cases = vec![
+            ("defaults", vec!["graph"], None, "dot"),
+            (
+                "workspace and mermaid",
+                vec!["graph", "my-ws", "--format", "mermaid"],
+                Some("my-ws"),
+                "mermaid",
+            ),
+        ];
+        for (name, args, want_ws, want_format) in cases {
+            let m = graph_cmd().get_matches_from(args);
+            assert_eq!(
+                m.get_one::<String>("workspace").map(|s| s.as_str()),
+                want_ws,
+                "{}",
+                name
+            );
+            assert_eq!(
+                m.get_one::<String>("format").map(|s| s.as_str()),
+                Some(want_format),
+                "{}",
+                name
+            );
+        }
+    }
+}
@@ -1,9 +1,11 @@
-use anyhow::{Result, bail};
+use anyhow::Result;
 use clap::{Arg, ArgMatches, Command};
 use clap_complete::engine::ArgValueCandidates;
 
-use crate::config::Paths;
+use crate::config::{self, Paths};
+use crate::error::{self, ErrorKind};
 use crate::filelock;
+use crate::issue;
 use crate::output::{MutationOutput, Output};
 use crate::workspace;
 
@@ -15,37 +17,64 @@ pub fn cmd() -> Command {
         .long_about(
             "Set or update a workspace description.\n\n\
              Stores a short purpose string in the workspace metadata. The description \
-             appears in `wsp ls` output to help identify workspaces at a glance.",
+             appears in `wsp ls` output to help identify workspaces at a glance.\n\n\
+             --issue links the workspace to a GitHub issue URL or a Jira issue URL or \
+             key (made a URL with jira.url); `wsp ls`, `wsp info`, and `wsp context` \
+             show it. Pass an empty string to clear either.",
         )
         .arg(
             Arg::new("workspace")
                 .required(true)
                 .add(ArgValueCandidates::new(completers::complete_workspaces)),
         )
-        .arg(Arg::new("text").required(true))
+        .arg(Arg::new("text").required_unless_present("issue"))
+        .arg(
+            Arg::new("issue")
+                .long("issue")
+                .value_name("ISSUE")
+                .help("Link a GitHub issue URL or Jira key/URL"),
+        )
 }
 
 pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     let ws_name = matches.get_one::<String>("workspace").unwrap();
-    let text = matches.get_one::<String>("text").unwrap();
+    let text = matches.get_one::<String>("text");
 
     workspace::validate_name(ws_name)?;
-    let ws_dir = workspace::dir(&paths.workspaces_dir, ws_name);
+    let ws_dir = paths.workspace_dir(ws_name);
     if !ws_dir.join(workspace::METADATA_FILE).exists() {
-        bail!("workspace '{}' not found", ws_name);
+        return Err(error::tag(
+            ErrorKind::NotFound,
+            anyhow::anyhow!("workspace '{}' not found", ws_name),
+        ));
     }
 
-    let desc = if text.is_empty() {
-        None
-    } else {
-        Some(text.clone())
+    // None: leave alone; Some(None): clear.
+    let issue = match matches.get_one::<String>("issue") {
+        Some(raw) if raw.is_empty() => Some(None),
+        Some(raw) => {
+            let cfg = config::Config::load_from(&paths.config_path)?;
+            Some(Some(issue::parse(raw)?.canonical(cfg.jira_url.as_deref())))
+        }
+        None => None,
     };
 
     filelock::with_metadata(&ws_dir, |meta| {
-        meta.description = desc;
+        if let Some(text) = text {
+            meta.description = Some(text.clone()).filter(|t| !t.is_empty());
+        }
+        if let Some(ref issue) = issue {
+            meta.issue = issue.clone();
+        }
         Ok(())
     })?;
 
+    let Some(text) = text else {
+        return Ok(Output::Mutation(MutationOutput::new(match issue {
+            Some(Some(url)) => format!("Issue set for {}: {}", ws_name, url),
+            _ => format!("Issue cleared for {}", ws_name),
+        })));
+    };
     if text.is_empty() {
         Ok(Output::Mutation(MutationOutput::new(format!(
             "Description cleared for {}",
@@ -1,4 +1,3 @@
-use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
@@ -6,9 +5,10 @@ use clap::{Arg, ArgMatches, Command};
 use clap_complete::engine::ArgValueCandidates;
 
 use crate::config::Paths;
+use crate::error;
 use crate::gc;
 use crate::git;
-use crate::output::{DiffOutput, Output, RepoDiffEntry};
+use crate::output::{self, DiffOutput, DiffStat, Format, Output, RepoDiffEntry};
 use crate::workspace;
 
 use super::completers;
@@ -25,7 +25,15 @@ pub fn cmd() -> Command {
              wsp diff -- --staged          # staged changes only\n  \
              wsp diff -- --name-only       # list changed filenames\n  \
              wsp diff -- --stat            # diffstat summary\n  \
-             wsp diff -- -- path/to/file   # diff a specific file",
+             wsp diff -- -- path/to/file   # diff a specific file\n\n\
+             With --json, each repo also carries a per-file diffstat (`stat`). \
+             --no-patch drops the diff text and prints only the diffstat.",
+        )
+        .arg(
+            Arg::new("no-patch")
+                .long("no-patch")
+                .action(clap::ArgAction::SetTrue)
+                .help("Only show per-repo diffstat, not the diff text"),
         )
         .arg(Arg::new("workspace").add(ArgValueCandidates::new(completers::complete_workspaces)))
         .arg(
@@ -39,7 +47,7 @@ pub fn cmd() -> Command {
 
 pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     let ws_dir: PathBuf = if let Some(name) = matches.get_one::<String>("workspace") {
-        workspace::dir(&paths.workspaces_dir, name)
+        paths.workspace_dir(name)
     } else {
         let cwd = std::env::current_dir()?;
         workspace::detect(&cwd)?
@@ -47,16 +55,17 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
 
     gc::check_workspace(&ws_dir, /* read_only */ true)?;
 
-    let meta = workspace::load_metadata(&ws_dir)
-        .map_err(|e| anyhow::anyhow!("reading workspace: {}", e))?;
+    let meta =
+        workspace::load_metadata(&ws_dir).map_err(|e| error::prefixed(e, "reading workspace"))?;
 
     let extra_args: Vec<&str> = matches
         .get_many::<String>("args")
         .map(|vals| vals.map(|s| s.as_str()).collect())
         .unwrap_or_default();
 
-    let is_json = matches.get_flag("json");
-    let use_color = !is_json && std::io::stdout().is_terminal();
+    let is_json = Format::from_matches(matches).is_structured();
+    let no_patch = matches.get_flag("no-patch");
+    let use_color = !is_json && output::use_color(matches);
 
     let mut repos = Vec::new();
     for identity in meta.repos.keys() {
@@ -67,7 +76,8 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
                     identity: identity.clone(),
                     shortname: identity.rsplit('/').next().unwrap_or(identity).to_string(),
                     path: String::new(),
-                    diff: String::new(),
+                    diff: None,
+                    stat: None,
                     error: Some(e.to_string()),
                 });
                 continue;
@@ -76,41 +86,49 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
 
         let repo_dir = ws_dir.join(&dir_name);
 
-        let mut args = vec!["diff"];
-        if use_color {
-            args.push("--color=always");
-        }
         let diff_base = if extra_args.is_empty() {
             Some(resolve_diff_base(&repo_dir))
         } else {
             None
         };
+        let mut rev_args: Vec<&str> = Vec::new();
         if let Some(ref base) = diff_base {
-            args.push(base);
+            rev_args.push(base);
         }
-        args.extend(&extra_args);
-
-        let diff = match git::run(Some(&repo_dir), &args) {
-            Ok(o) => o,
-            Err(e) => {
-                repos.push(RepoDiffEntry {
-                    identity: identity.clone(),
-                    shortname: dir_name,
-                    path: repo_dir.to_string_lossy().to_string(),
-                    diff: String::new(),
-                    error: Some(e.to_string()),
-                });
-                continue;
-            }
-        };
+        rev_args.extend(&extra_args);
 
-        repos.push(RepoDiffEntry {
+        let mut entry = RepoDiffEntry {
             identity: identity.clone(),
             shortname: dir_name,
             path: repo_dir.to_string_lossy().to_string(),
-            diff,
+            diff: None,
+            stat: None,
             error: None,
-        });
+        };
+
+        if !no_patch {
+            let mut args = vec!["diff", output::git_color_arg(use_color)];
+            args.extend(&rev_args);
+            match git::run(Some(&repo_dir), &args) {
+                Ok(o) => entry.diff = Some(o),
+                Err(e) => {
+                    entry.error = Some(e.to_string());
+                    repos.push(entry);
+                    continue;
+                }
+            }
+        }
+
+        if is_json || no_patch {
+            let mut args = vec!["diff", "--numstat"];
+            args.extend(&rev_args);
+            match git::run(Some(&repo_dir), &args) {
+                Ok(o) => entry.stat = Some(DiffStat::from_numstat(git::parse_numstat(&o))),
+                Err(e) => entry.error = Some(e.to_string()),
+            }
+        }
+
+        repos.push(entry);
     }
 
     Ok(Output::Diff(DiffOutput {
@@ -0,0 +1,42 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use clap_complete::engine::ArgValueCandidates;
+
+use crate::config::Paths;
+use crate::error::{self, ErrorKind};
+use crate::output::{Output, PathOutput};
+use crate::workspace;
+
+use super::completers;
+
+pub fn cmd() -> Command {
+    Command::new("dir")
+        .about("Print the path of a workspace [read-only]")
+        .long_about(
+            "Print the path of a workspace [read-only].\n\n\
+             With a workspace name, prints that workspace's directory; without one, \
+             prints the directory workspaces are created in. The shell integration \
+             uses this to find workspaces, so it follows `workspaces_dir` changes \
+             without regenerating the script. Unlike `wsp cd`, it has no side effects.",
+        )
+        .arg(Arg::new("workspace").add(ArgValueCandidates::new(completers::complete_workspaces)))
+}
+
+pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    let path = match matches.get_one::<String>("workspace") {
+        Some(name) => {
+            let ws_dir = paths.workspace_dir(name);
+            if !ws_dir.join(workspace::METADATA_FILE).exists() {
+                return Err(error::tag(
+                    ErrorKind::NotFound,
+                    anyhow::anyhow!("workspace '{}' not found", name),
+                ));
+            }
+            ws_dir
+        }
+        None => paths.workspaces_dir.clone(),
+    };
+    Ok(Output::Path(PathOutput {
+        path: path.display().to_string(),
+    }))
+}
@@ -2,32 +2,38 @@ use std::fs;
 
 use anyhow::Result;
 use clap::{ArgMatches, Command};
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use crate::agentmd;
 use crate::config::{self, Paths};
+use crate::configcheck::{self, Severity};
+use crate::du::dir_size;
 use crate::filelock;
 use crate::gc;
 use crate::git;
 use crate::giturl;
 use crate::lang;
+use crate::managedhook::ManagedHook;
 use crate::mirror;
 use crate::output::Output;
+use crate::pushguard;
 use crate::template;
+use crate::trailer;
 use crate::workspace;
 
 // ---------------------------------------------------------------------------
 // Output types
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct DoctorOutput {
     pub ok: bool,
     pub checks: Vec<DoctorCheck>,
     pub summary: DoctorSummary,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct DoctorCheck {
     pub scope: String,
     pub check: String,
@@ -39,7 +45,7 @@ pub struct DoctorCheck {
     pub details: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum CheckStatus {
     Ok,
@@ -47,7 +53,7 @@ pub enum CheckStatus {
     Error,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct DoctorSummary {
     pub total: usize,
     pub ok: usize,
@@ -146,6 +152,7 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
                 details: None,
             });
             eprintln!("  ✗ config failed to load: {}", e);
+            check_config_problems(paths, &mut checks);
             // Can't proceed without config
             return Ok(Output::Doctor(build_output(checks, fixed)));
         }
@@ -155,6 +162,9 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     // G2. Config version skew
     check_config_version(&cfg, &mut checks);
 
+    // G12. Config keys and values: unknown keys, bad values, dangling refs
+    check_config_problems(paths, &mut checks);
+
     // 2. Mirrors exist for registered repos
     let mut missing_mirrors = Vec::new();
     for (identity, entry) in &cfg.repos {
@@ -246,6 +256,9 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     // G11. Deprecated config keys — old-format keys that should be migrated
     check_deprecated_config_keys(paths, &cfg, fix, &mut checks, &mut fixed);
 
+    // G13. Git backend — which one runs clone/fetch, and whether git is there
+    check_git_backend(&mut checks);
+
     // --- Workspace checks (if inside one) ---
     let cwd = std::env::current_dir()?;
     if let Ok(ws_dir) = workspace::detect(&cwd) {
@@ -285,11 +298,10 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
 
         // W14. Git config drift — clone's local config differs from effective config
         let effective_cfg = meta.apply_workspace_config(&cfg);
-        let effective_gc = effective_cfg.effective_git_config();
         check_git_config_drift(
             &ws_dir,
             &meta,
-            &effective_gc,
+            &effective_cfg,
             &ws_scope,
             fix,
             &mut checks,
@@ -342,6 +354,20 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
             // W7. In-progress git operation
             check_in_progress_op(&info.clone_dir, &info.dir_name, &scope, &mut checks);
 
+            // wsp's git hooks match commit-trailer and push-guard
+            for hook in &WSP_HOOKS {
+                check_git_hook(
+                    &info.clone_dir,
+                    &info.dir_name,
+                    &scope,
+                    hook,
+                    (hook.enabled)(&cfg),
+                    fix,
+                    &mut checks,
+                    &mut fixed,
+                );
+            }
+
             // Origin URL matches registered URL
             let clone_url = git::remote_get_url(&info.clone_dir, "origin")
                 .unwrap_or_default()
@@ -914,7 +940,7 @@ fn check_legacy_wsp_mirror(
     }
 }
 
-/// W7. In-progress git operation — interrupted rebase or merge.
+/// W7. In-progress git operation — interrupted rebase, merge, cherry-pick, revert, or bisect.
 fn check_in_progress_op(
     clone_dir: &std::path::Path,
     dir_name: &str,
@@ -922,15 +948,7 @@ fn check_in_progress_op(
     checks: &mut Vec<DoctorCheck>,
 ) {
     if let Some(op) = git::in_progress_op(clone_dir) {
-        let (op_name, hint) = match op {
-            git::InProgressOp::Rebase => (
-                "rebase",
-                "run `git rebase --continue` or `git rebase --abort`",
-            ),
-            git::InProgressOp::Merge => {
-                ("merge", "run `git merge --continue` or `git merge --abort`")
-            }
-        };
+        let (op_name, hint) = (op.name(), op.hint());
         checks.push(DoctorCheck {
             scope: scope.into(),
             check: "in-progress-git-op".into(),
@@ -947,6 +965,147 @@ fn check_in_progress_op(
     }
 }
 
+/// A git hook wsp installs in clones, as doctor checks it.
+struct WspHook {
+    /// The config key that turns it on; the check is named `<key>-hook`.
+    key: &'static str,
+    enabled: fn(&config::Config) -> bool,
+    managed: &'static ManagedHook,
+}
+
+const WSP_HOOKS: [WspHook; 2] = [
+    WspHook {
+        key: "commit-trailer",
+        enabled: |cfg| cfg.commit_trailer == Some(true),
+        managed: &trailer::HOOK,
+    },
+    WspHook {
+        key: "push-guard",
+        enabled: |cfg| cfg.push_guard == Some(true),
+        managed: &pushguard::HOOK,
+    },
+];
+
+/// wsp's git hook is installed exactly when its config key is on. A repo's
+/// own hook of the same name is never replaced.
+#[allow(clippy::too_many_arguments)]
+fn check_git_hook(
+    clone_dir: &std::path::Path,
+    dir_name: &str,
+    scope: &str,
+    hook: &WspHook,
+    enabled: bool,
+    fix: bool,
+    checks: &mut Vec<DoctorCheck>,
+    fixed: &mut usize,
+) {
+    let check = format!("{}-hook", hook.key);
+    if enabled && let Some(hooks_path) = hooks_path_override(clone_dir) {
+        let message = format!(
+            "{}: core.hooksPath is {}, so git never runs the {}",
+            dir_name, hooks_path, hook.managed.label
+        );
+        eprintln!("  ✗ {}", message);
+        eprintln!(
+            "      unset git.core.hooksPath, or turn off {} and call it from the shared hooks",
+            hook.key
+        );
+        checks.push(DoctorCheck {
+            scope: scope.into(),
+            check,
+            status: CheckStatus::Error,
+            message,
+            fixable: false,
+            details: Some(serde_json::json!({ "hooks_path": hooks_path })),
+        });
+        return;
+    }
+    if enabled == hook.managed.is_installed(clone_dir) {
+        return;
+    }
+    if enabled && hook.managed.has_other_hook(clone_dir) {
+        let message = format!(
+            "{}: has its own {} hook, {} is not applied",
+            dir_name, hook.managed.name, hook.key
+        );
+        eprintln!("  ⚠ {}", message);
+        checks.push(DoctorCheck {
+            scope: scope.into(),
+            check,
+            status: CheckStatus::Warn,
+            message,
+            fixable: false,
+            details: None,
+        });
+        return;
+    }
+
+    let (problem, done) = if enabled {
+        (
+            format!("{} missing", hook.managed.label),
+            format!("installed {}", hook.managed.label),
+        )
+    } else {
+        (
+            format!("{} installed but {} is off", hook.managed.label, hook.key),
+            format!("removed {}", hook.managed.label),
+        )
+    };
+    let fixable = true;
+    if fix {
+        let result = if enabled {
+            hook.managed.install(clone_dir)
+        } else {
+            hook.managed.uninstall(clone_dir)
+        };
+        match result {
+            Ok(()) => {
+                checks.push(DoctorCheck {
+                    scope: scope.into(),
+                    check,
+                    status: CheckStatus::Ok,
+                    message: format!("{}: {}", dir_name, done),
+                    fixable,
+                    details: None,
+                });
+                eprintln!("  ✓ {}: {}", dir_name, done);
+                *fixed += 1;
+            }
+            Err(e) => {
+                checks.push(DoctorCheck {
+                    scope: scope.into(),
+                    check,
+                    status: CheckStatus::Warn,
+                    message: format!("{}: {}, fix failed: {}", dir_name, problem, e),
+                    fixable,
+                    details: None,
+                });
+                eprintln!("  ⚠ {}: {}, fix failed: {}", dir_name, problem, e);
+            }
+        }
+    } else {
+        checks.push(DoctorCheck {
+            scope: scope.into(),
+            check,
+            status: CheckStatus::Warn,
+            message: format!("{}: {}", dir_name, problem),
+            fixable,
+            details: None,
+        });
+        eprintln!("  ⚠ {}: {}", dir_name, problem);
+    }
+}
+
+/// The hooks directory git uses in a clone, when `core.hooksPath` (from any
+/// config scope) moves it away from `.git/hooks`, where wsp installs its own.
+fn hooks_path_override(clone_dir: &std::path::Path) -> Option<String> {
+    let out = git::run(Some(clone_dir), &["rev-parse", "--git-path", "hooks"]).ok()?;
+    let resolve = |p: std::path::PathBuf| p.canonicalize().unwrap_or(p);
+    let effective = resolve(clone_dir.join(out.trim()));
+    let default = resolve(clone_dir.join(".git").join("hooks"));
+    (effective != default).then(|| out.trim().to_string())
+}
+
 /// W3. Legacy ref field — stale @ref values in metadata.
 fn check_legacy_ref_field(
     ws_dir: &std::path::Path,
@@ -1040,7 +1199,9 @@ fn check_stale_dirs_map(
     let stale_entries: Vec<String> = meta
         .dirs
         .keys()
-        .filter(|identity| !meta.repos.contains_key(*identity))
+        .filter(|identity| {
+            !meta.repos.contains_key(*identity) && !meta.pending.contains_key(*identity)
+        })
         .cloned()
         .collect();
 
@@ -1051,7 +1212,9 @@ fn check_stale_dirs_map(
     let fixable = true;
     if fix {
         match crate::filelock::with_metadata(ws_dir, |m| {
-            m.dirs.retain(|identity, _| m.repos.contains_key(identity));
+            m.dirs.retain(|identity, _| {
+                m.repos.contains_key(identity) || m.pending.contains_key(identity)
+            });
             Ok(())
         }) {
             Ok(_) => {
@@ -1194,6 +1357,8 @@ fn check_unregistered_repos(
                                 config::RepoEntry {
                                     url: url.clone(),
                                     added: chrono::Utc::now(),
+                                    setup: None,
+                                    exec_dir: None,
                                 },
                             );
                         }
@@ -1292,7 +1457,7 @@ fn check_agents_md_valid(
                     }
                     _ => {} // correct symlink
                 }
-            } else {
+            } else if !agentmd::is_claude_md_managed(ws_dir) {
                 problems.push("CLAUDE.md is not a symlink to AGENTS.md");
             }
         }
@@ -1320,14 +1485,7 @@ fn check_agents_md_valid(
             match agentmd::update(ws_dir, meta) {
                 Ok(()) => {
                     // Also ensure CLAUDE.md symlink
-                    let _ = fs::remove_file(&claude_path);
-                    #[cfg(unix)]
-                    let link_result: std::io::Result<()> =
-                        std::os::unix::fs::symlink("AGENTS.md", &claude_path);
-                    #[cfg(windows)]
-                    let link_result: std::io::Result<()> =
-                        std::os::windows::fs::symlink_file("AGENTS.md", &claude_path);
-                    match link_result {
+                    match agentmd::relink_claude_md(ws_dir) {
                         Ok(()) => {
                             checks.push(DoctorCheck {
                                 scope: ws_scope.into(),
@@ -1387,6 +1545,39 @@ fn check_agents_md_valid(
     }
 }
 
+/// G13. Git backend — which one runs clone and fetch, and whether the git
+/// CLI is on PATH.
+fn check_git_backend(checks: &mut Vec<DoctorCheck>) {
+    let backend = git::backend();
+    let (status, message) = match backend {
+        git::Backend::Cli if !git::git_on_path() => {
+            (CheckStatus::Error, "git not found on PATH".to_string())
+        }
+        git::Backend::Cli => (CheckStatus::Ok, "using the git CLI".to_string()),
+        #[cfg(feature = "libgit2")]
+        git::Backend::Libgit2 => (
+            CheckStatus::Warn,
+            "git not found on PATH; using libgit2 for clone, fetch and new workspaces \
+             (install git for status, sync, diff and the rest)"
+                .to_string(),
+        ),
+    };
+    let icon = match status {
+        CheckStatus::Ok => "✓",
+        CheckStatus::Warn => "⚠",
+        CheckStatus::Error => "✗",
+    };
+    eprintln!("  {} {}", icon, message);
+    checks.push(DoctorCheck {
+        scope: "global".into(),
+        check: "git-backend".into(),
+        status,
+        message,
+        fixable: false,
+        details: Some(serde_json::json!({ "backend": backend.name() })),
+    });
+}
+
 /// G3. Workspaces dir exists.
 fn check_workspaces_dir_exists(
     paths: &Paths,
@@ -1679,6 +1870,8 @@ fn check_template_repos_registered(
                             config::RepoEntry {
                                 url: url.clone(),
                                 added: chrono::Utc::now(),
+                                setup: None,
+                                exec_dir: None,
                             },
                         );
                     }
@@ -1828,8 +2021,7 @@ fn check_missing_dirs_map(
     checks: &mut Vec<DoctorCheck>,
     fixed: &mut usize,
 ) {
-    let identities: Vec<&str> = meta.repos.keys().map(|s| s.as_str()).collect();
-    let expected = match workspace::compute_dir_names(&identities) {
+    let expected = match workspace::compute_dir_names(&meta.dir_identities()) {
         Ok(d) => d,
         Err(_) => return,
     };
@@ -1850,7 +2042,9 @@ fn check_missing_dirs_map(
     // Also check for entries in meta.dirs that shouldn't be there (expected is empty but dirs has entries)
     let mut extra: Vec<String> = Vec::new();
     for identity in meta.dirs.keys() {
-        if !expected.contains_key(identity) && meta.repos.contains_key(identity) {
+        if !expected.contains_key(identity)
+            && (meta.repos.contains_key(identity) || meta.pending.contains_key(identity))
+        {
             extra.push(identity.clone());
         }
     }
@@ -1924,7 +2118,7 @@ fn check_wspignore_defaults(
     checks: &mut Vec<DoctorCheck>,
     fixed: &mut usize,
 ) {
-    let wspignore_path = paths.data_dir().join("wspignore");
+    let wspignore_path = workspace::global_wspignore_path(paths.config_dir(), paths.data_dir());
     if !wspignore_path.exists() {
         // ensure_global_wspignore will create it on next command; not an issue
         return;
@@ -2208,20 +2402,19 @@ fn check_mirror_refspec(
     }
 }
 
-/// W14. Git config drift — clone's local git config differs from effective config.
+/// W14. Git config drift — clone's local git config differs from effective config
+/// (including the repo's committer).
 fn check_git_config_drift(
     ws_dir: &std::path::Path,
     meta: &workspace::Metadata,
-    effective_gc: &std::collections::BTreeMap<String, String>,
+    effective_cfg: &config::Config,
     ws_scope: &str,
     fix: bool,
     checks: &mut Vec<DoctorCheck>,
     fixed: &mut usize,
 ) {
-    if effective_gc.is_empty() {
-        return;
-    }
-
+    let effective_gc = effective_cfg.effective_git_config();
+    let rewrites = effective_cfg.url_rewrite_git_config();
     let repo_infos = meta.repo_infos(ws_dir);
     let mut all_drifted: Vec<serde_json::Value> = Vec::new();
 
@@ -2230,8 +2423,12 @@ fn check_git_config_drift(
             continue;
         }
 
+        let mut expected_gc = effective_gc.clone();
+        if let Some(committer) = effective_cfg.committer_for(&info.identity) {
+            expected_gc.extend(committer.git_config());
+        }
         let mut drifted_keys: Vec<serde_json::Value> = Vec::new();
-        for (key, expected) in effective_gc {
+        for (key, expected) in &expected_gc {
             let actual = git::get_config(&info.clone_dir, key).ok();
             if actual.as_deref() != Some(expected.as_str()) {
                 drifted_keys.push(serde_json::json!({
@@ -2241,6 +2438,16 @@ fn check_git_config_drift(
                 }));
             }
         }
+        for (key, expected) in &rewrites {
+            let actual = git::get_all_config(&info.clone_dir, key);
+            if !actual.contains(expected) {
+                drifted_keys.push(serde_json::json!({
+                    "key": key,
+                    "expected": expected,
+                    "actual": actual,
+                }));
+            }
+        }
 
         if drifted_keys.is_empty() {
             continue;
@@ -2264,7 +2471,9 @@ fn check_git_config_drift(
     let repo_count = all_drifted.len();
 
     if fix {
-        workspace::apply_git_config(ws_dir, meta, effective_gc, None);
+        workspace::apply_git_config(ws_dir, meta, &effective_gc, None);
+        workspace::add_git_config(ws_dir, meta, &rewrites, None);
+        workspace::apply_committers(ws_dir, meta, effective_cfg, None);
         checks.push(DoctorCheck {
             scope: ws_scope.into(),
             check: "git-config-drift".into(),
@@ -2316,24 +2525,6 @@ fn check_git_config_drift(
 // Helpers
 // ---------------------------------------------------------------------------
 
-fn dir_size(path: &std::path::Path) -> u64 {
-    let mut total = 0u64;
-    if let Ok(entries) = fs::read_dir(path) {
-        for entry in entries.flatten() {
-            let ft = match entry.file_type() {
-                Ok(ft) => ft,
-                Err(_) => continue,
-            };
-            if ft.is_dir() {
-                total += dir_size(&entry.path());
-            } else {
-                total += entry.metadata().map(|m| m.len()).unwrap_or(0);
-            }
-        }
-    }
-    total
-}
-
 fn format_bytes(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = 1024 * KB;
@@ -2349,7 +2540,55 @@ fn format_bytes(bytes: u64) -> String {
     }
 }
 
-fn build_output(checks: Vec<DoctorCheck>, fixed: usize) -> DoctorOutput {
+/// Reports every problem [`configcheck`] finds in config.yaml, or one ok
+/// check if there are none.
+pub(crate) fn check_config_problems(paths: &Paths, checks: &mut Vec<DoctorCheck>) {
+    let problems = match configcheck::check_file(&paths.config_path, paths.data_dir()) {
+        Ok(problems) => problems,
+        Err(e) => {
+            checks.push(DoctorCheck {
+                scope: "global".into(),
+                check: "config-valid".into(),
+                status: CheckStatus::Error,
+                message: format!("config could not be read: {}", e),
+                fixable: false,
+                details: None,
+            });
+            eprintln!("  ✗ config could not be read: {}", e);
+            return;
+        }
+    };
+    if problems.is_empty() {
+        checks.push(DoctorCheck {
+            scope: "global".into(),
+            check: "config-valid".into(),
+            status: CheckStatus::Ok,
+            message: "config keys and values are valid".into(),
+            fixable: false,
+            details: None,
+        });
+        eprintln!("  ✓ config keys and values are valid");
+        return;
+    }
+    for p in problems {
+        let (status, mark) = match p.severity {
+            Severity::Error => (CheckStatus::Error, "✗"),
+            Severity::Warning => (CheckStatus::Warn, "⚠"),
+        };
+        let message = format!("config.yaml {}", p);
+        eprintln!("  {} {}", mark, message);
+        checks.push(DoctorCheck {
+            scope: "global".into(),
+            check: "config-valid".into(),
+            status,
+            message,
+            fixable: false,
+            details: Some(serde_json::json!({ "line": p.line, "key": p.key })),
+        });
+    }
+}
+
+pub(crate) fn build_output(checks: Vec<DoctorCheck>, fixed: usize) -> DoctorOutput {
     let total = checks.len();
     let ok_count = checks
         .iter()
@@ -2460,7 +2699,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: std::collections::BTreeMap::new(),
+            branches: std::collections::BTreeMap::new(),
+            prs: std::collections::BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: std::collections::BTreeMap::new(),
         }
     }
 
@@ -2472,6 +2715,7 @@ mod tests {
             mirrors_dir: tmp.join("mirrors"),
             gc_dir: tmp.join("gc"),
             templates_dir: tmp.join("templates"),
+            extra_workspaces_dirs: Vec::new(),
             workspaces_dir: tmp.join("workspaces"),
         }
     }
@@ -2655,6 +2899,8 @@ mod tests {
                 config::RepoEntry {
                     url: "git@github.com:acme/kept.git".into(),
                     added: chrono::Utc::now(),
+                    setup: None,
+                    exec_dir: None,
                 },
             )]),
             ..Default::default()
@@ -2673,6 +2919,7 @@ mod tests {
             mirrors_dir,
             gc_dir: tmp.path().join("gc"),
             templates_dir: tmp.path().join("templates"),
+            extra_workspaces_dirs: Vec::new(),
             workspaces_dir: tmp.path().join("workspaces"),
         };
 
@@ -2696,6 +2943,8 @@ mod tests {
                 config::RepoEntry {
                     url: "git@github.com:acme/repo.git".into(),
                     added: chrono::Utc::now(),
+                    setup: None,
+                    exec_dir: None,
                 },
             )]),
             ..Default::default()
@@ -2710,6 +2959,7 @@ mod tests {
             mirrors_dir,
             gc_dir: tmp.path().join("gc"),
             templates_dir: tmp.path().join("templates"),
+            extra_workspaces_dirs: Vec::new(),
             workspaces_dir: tmp.path().join("workspaces"),
         };
 
@@ -2736,6 +2986,7 @@ mod tests {
             mirrors_dir: mirrors_dir.clone(),
             gc_dir: tmp.path().join("gc"),
             templates_dir: tmp.path().join("templates"),
+            extra_workspaces_dirs: Vec::new(),
             workspaces_dir: tmp.path().join("workspaces"),
         };
 
@@ -2755,6 +3006,7 @@ mod tests {
             mirrors_dir: tmp.path().join("mirrors"),
             gc_dir: tmp.path().join("gc"),
             templates_dir: tmp.path().join("templates"),
+            extra_workspaces_dirs: Vec::new(),
             workspaces_dir: tmp.path().join("workspaces"),
         };
         let cfg = config::Config::default();
@@ -2787,7 +3039,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: std::collections::BTreeMap::new(),
+            branches: std::collections::BTreeMap::new(),
+            prs: std::collections::BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: std::collections::BTreeMap::new(),
         };
         let cfg = config::Config {
             repos: std::collections::BTreeMap::from([(
@@ -2795,6 +3051,8 @@ mod tests {
                 config::RepoEntry {
                     url: "git@github.com:acme/known.git".into(),
                     added: chrono::Utc::now(),
+                    setup: None,
+                    exec_dir: None,
                 },
             )]),
             ..Default::default()
@@ -2837,7 +3095,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: std::collections::BTreeMap::new(),
+            branches: std::collections::BTreeMap::new(),
+            prs: std::collections::BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: std::collections::BTreeMap::new(),
         };
 
         let mut checks = Vec::new();
@@ -2878,7 +3140,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: std::collections::BTreeMap::new(),
+            branches: std::collections::BTreeMap::new(),
+            prs: std::collections::BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: std::collections::BTreeMap::new(),
         };
 
         let mut checks = Vec::new();
@@ -2911,7 +3177,11 @@ mod tests {
                 ("github.com/acme/repo".into(), "repo".into()),
                 ("github.com/acme/removed".into(), "removed".into()),
             ]),
+            branches: std::collections::BTreeMap::new(),
+            prs: std::collections::BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: std::collections::BTreeMap::new(),
         };
 
         let mut checks = Vec::new();
@@ -2945,7 +3215,11 @@ mod tests {
                 "github.com/acme/repo".into(),
                 "repo".into(),
             )]),
+            branches: std::collections::BTreeMap::new(),
+            prs: std::collections::BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: std::collections::BTreeMap::new(),
         };
 
         let mut checks = Vec::new();
@@ -3153,6 +3427,146 @@ mod tests {
         assert!(!git::has_remote(&clone_dir, "wsp-mirror"));
     }
 
+    #[test]
+    fn git_hook_follows_config() {
+        let cases = vec![
+            // (enabled, fix, want checks, want installed after)
+            (false, false, 0, false),
+            (true, false, 1, false),
+            (true, true, 1, true),
+            (true, false, 0, true),
+            (false, false, 1, true),
+            (false, true, 1, false),
+        ];
+        for hook in &WSP_HOOKS {
+            let tmp = tempfile::tempdir().unwrap();
+            let clone_dir = tmp.path().join("repo");
+            fs::create_dir_all(&clone_dir).unwrap();
+            init_git_repo(&clone_dir);
+
+            for &(enabled, fix, want_checks, want_installed) in &cases {
+                let mut checks = Vec::new();
+                let mut fixed = 0;
+                check_git_hook(
+                    &clone_dir,
+                    "repo",
+                    "workspace/test/repo",
+                    hook,
+                    enabled,
+                    fix,
+                    &mut checks,
+                    &mut fixed,
+                );
+                let case = format!("{} enabled={} fix={}", hook.key, enabled, fix);
+                assert_eq!(checks.len(), want_checks, "{}", case);
+                assert_eq!(fixed, usize::from(fix), "{}", case);
+                assert_eq!(
+                    hook.managed.is_installed(&clone_dir),
+                    want_installed,
+                    "{}",
+                    case
+                );
+                if let Some(check) = checks.first() {
+                    assert_eq!(check.check, format!("{}-hook", hook.key), "{}", case);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn git_hook_fails_under_hooks_path() {
+        for hook in &WSP_HOOKS {
+            let tmp = tempfile::tempdir().unwrap();
+            let clone_dir = tmp.path().join("repo");
+            fs::create_dir_all(&clone_dir).unwrap();
+            init_git_repo(&clone_dir);
+            git::run(Some(&clone_dir), &["config", "core.hooksPath", ".githooks"]).unwrap();
+
+            let cases = vec![
+                // (enabled, fix, want status)
+                (false, false, None),
+                (true, false, Some(CheckStatus::Error)),
+                (true, true, Some(CheckStatus::Error)),
+            ];
+            for (enabled, fix, want) in cases {
+                let mut checks = Vec::new();
+                let mut fixed = 0;
+                check_git_hook(
+                    &clone_dir,
+                    "repo",
+                    "workspace/test/repo",
+                    hook,
+                    enabled,
+                    fix,
+                    &mut checks,
+                    &mut fixed,
+                );
+                let case = format!("{} enabled={} fix={}", hook.key, enabled, fix);
+                assert_eq!(checks.first().map(|c| c.status), want, "{}", case);
+                assert_eq!(fixed, 0, "{}", case);
+                assert!(!hook.managed.is_installed(&clone_dir), "{}", case);
+            }
+        }
+    }
+
+    #[test]
+    fn hooks_path_override_detects_shared_hooks() {
+        let tmp = tempfile::tempdir().unwrap();
+        let clone_dir = tmp.path().join("repo");
+        fs::create_dir_all(&clone_dir).unwrap();
+        init_git_repo(&clone_dir);
+
+        let cases = vec![
+            (None, None),
+            (Some(".git/hooks"), None),
+            (Some(".githooks"), Some(".githooks")),
+            (Some("/opt/acme/githooks"), Some("/opt/acme/githooks")),
+        ];
+        for (hooks_path, want) in cases {
+            match hooks_path {
+                Some(p) => git::run(Some(&clone_dir), &["config", "core.hooksPath", p]),
+                None => Ok(String::new()),
+            }
+            .unwrap();
+            assert_eq!(
+                hooks_path_override(&clone_dir).as_deref(),
+                want,
+                "{:?}",
+                hooks_path
+            );
+        }
+    }
+
+    #[test]
+    fn git_hook_keeps_user_hook() {
+        for hook in &WSP_HOOKS {
+            let tmp = tempfile::tempdir().unwrap();
+            let clone_dir = tmp.path().join("repo");
+            fs::create_dir_all(&clone_dir).unwrap();
+            init_git_repo(&clone_dir);
+            let path = hook.managed.path(&clone_dir);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, "#!/bin/sh\nexit 0\n").unwrap();
+
+            let mut checks = Vec::new();
+            let mut fixed = 0;
+            check_git_hook(
+                &clone_dir,
+                "repo",
+                "workspace/test/repo",
+                hook,
+                true,
+                true,
+                &mut checks,
+                &mut fixed,
+            );
+            assert_eq!(checks.len(), 1, "{}", hook.key);
+            assert!(!checks[0].fixable, "{}", hook.key);
+            assert_eq!(fixed, 0, "{}", hook.key);
+            assert_eq!(fs::read_to_string(&path).unwrap(), "#!/bin/sh\nexit 0\n");
+        }
+    }
+
     #[test]
     fn legacy_wsp_mirror_absent() {
         let tmp = tempfile::tempdir().unwrap();
@@ -3323,7 +3737,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: std::collections::BTreeMap::new(),
+            branches: std::collections::BTreeMap::new(),
+            prs: std::collections::BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: std::collections::BTreeMap::new(),
         };
         create_workspace_on_disk(&ws_dir, &meta);
 
@@ -3345,10 +3763,8 @@ mod tests {
 
         // Verify the fix persisted to disk
         let reloaded = workspace::load_metadata(&ws_dir).unwrap();
-        for (_, ref_opt) in &reloaded.repos {
-            if let Some(repo_ref) = ref_opt {
-                assert!(repo_ref.r#ref.is_empty(), "ref should be cleared");
-            }
+        for repo_ref in reloaded.repos.values().flatten() {
+            assert!(repo_ref.r#ref.is_empty(), "ref should be cleared");
         }
     }
 
@@ -3372,8 +3788,16 @@ mod tests {
             dirs: std::collections::BTreeMap::from([
                 ("github.com/acme/repo".into(), "repo".into()),
                 ("github.com/acme/removed".into(), "removed".into()),
+                ("github.com/acme/pending".into(), "pending".into()),
             ]),
+            branches: std::collections::BTreeMap::new(),
+            prs: std::collections::BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: std::collections::BTreeMap::from([(
+                "github.com/acme/pending".into(),
+                "connection refused".into(),
+            )]),
         };
         create_workspace_on_disk(&ws_dir, &meta);
 
@@ -3394,8 +3818,9 @@ mod tests {
 
         // Verify the fix persisted
         let reloaded = workspace::load_metadata(&ws_dir).unwrap();
-        assert_eq!(reloaded.dirs.len(), 1);
+        assert_eq!(reloaded.dirs.len(), 2);
         assert!(reloaded.dirs.contains_key("github.com/acme/repo"));
+        assert!(reloaded.dirs.contains_key("github.com/acme/pending"));
         assert!(!reloaded.dirs.contains_key("github.com/acme/removed"));
     }
 
@@ -3570,6 +3995,8 @@ mod tests {
                 config::RepoEntry {
                     url: "git@github.com:acme/repo.git".into(),
                     added: chrono::Utc::now(),
+                    setup: None,
+                    exec_dir: None,
                 },
             )]),
             ..Default::default()
@@ -3856,6 +4283,8 @@ mod tests {
                 config::RepoEntry {
                     url: "git@github.com:acme/repo.git".into(),
                     added: chrono::Utc::now(),
+                    setup: None,
+                    exec_dir: None,
                 },
             )]),
             ..Default::default()
@@ -3921,7 +4350,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: std::collections::BTreeMap::new(),
+            branches: std::collections::BTreeMap::new(),
+            prs: std::collections::BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: std::collections::BTreeMap::new(),
         };
         create_workspace_on_disk(&ws_dir, &meta);
 
@@ -3958,7 +4391,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: std::collections::BTreeMap::new(), // Missing collision entries!
+            branches: std::collections::BTreeMap::new(),
+            prs: std::collections::BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: std::collections::BTreeMap::new(),
         };
         create_workspace_on_disk(&ws_dir, &meta);
 
@@ -3996,7 +4433,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: std::collections::BTreeMap::new(),
+            branches: std::collections::BTreeMap::new(),
+            prs: std::collections::BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: std::collections::BTreeMap::new(),
         };
         create_workspace_on_disk(&ws_dir, &meta);
 
@@ -4041,7 +4482,11 @@ mod tests {
                 ("github.com/org1/shared".into(), "wrong-name-1".into()),
                 ("github.com/org2/shared".into(), "wrong-name-2".into()),
             ]),
+            branches: std::collections::BTreeMap::new(),
+            prs: std::collections::BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: std::collections::BTreeMap::new(),
         };
         create_workspace_on_disk(&ws_dir, &meta);
 
@@ -4336,6 +4781,22 @@ mod tests {
         assert!(stdout.contains("+refs/heads/*:refs/remotes/origin/*"));
     }
 
+    // -----------------------------------------------------------------------
+    // G13. git-backend
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn git_backend_reports_cli() {
+        let mut checks = Vec::new();
+        check_git_backend(&mut checks);
+
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].check, "git-backend");
+        // git is on PATH wherever the tests run, so the CLI is used.
+        assert_eq!(checks[0].status, CheckStatus::Ok);
+        assert_eq!(checks[0].details.as_ref().unwrap()["backend"], "git");
+    }
+
     // -----------------------------------------------------------------------
     // G11: Deprecated config keys
     // -----------------------------------------------------------------------
@@ -1,12 +1,19 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::process::{Command as ProcessCommand, Stdio};
 
-use anyhow::Result;
-use clap::{Arg, ArgMatches, Command};
+use anyhow::{Result, bail};
+use clap::{Arg, ArgAction, ArgMatches, Command};
 use clap_complete::engine::ArgValueCandidates;
 
-use crate::config::Paths;
-use crate::output::{ExecOutput, ExecRepoResult, Output};
+use crate::config::{self, Paths};
+use crate::deps;
+use crate::error;
+use crate::git;
+use crate::giturl;
+use crate::output::{ExecOutput, ExecRepoResult, Format, MutationOutput, Output};
+use crate::tmux;
+use crate::util;
 use crate::workspace;
 
 use super::completers;
@@ -16,9 +23,21 @@ pub fn cmd() -> Command {
         .about("Run a command in each repo of a workspace")
         .long_about(
             "Run a command in each repo of a workspace.\n\n\
-             Executes the given command sequentially in each repo directory. The command and \
+             Executes the given command sequentially in each repo directory, in dependency \
+             order when repos declare `depends-on.<repo>` (a cycle is an error). The command and \
              its arguments follow `--` (e.g., `wsp exec my-ws -- make test`). Exit codes \
              are collected per repo and reported in the output.\n\n\
+             Repos with an `exec-dir.<repo>` setting (workspace, else global) run the command in that \
+             subdirectory instead of the repo root (e.g., `services/api` in a monorepo). \
+             Use --dir REPO=SUBDIR to override for a single run.\n\n\
+             With --affected, the command runs only in repos with changes relative to \
+             upstream (commits not on origin/<default>, or uncommitted changes) plus every \
+             repo that depends on them, per `depends-on.<repo>`.\n\n\
+             With --tmux, the command is launched in a tmux pane per repo instead of \
+             running serially: panes are tiled in one window of a session named after the \
+             workspace (created detached if it doesn't exist), and stay open after the \
+             command exits. Useful for watching several long-running dev servers side by \
+             side.\n\n\
              The workspace name is optional when running from inside a workspace directory.",
         )
         .arg(
@@ -26,95 +45,294 @@ pub fn cmd() -> Command {
                 .required(false)
                 .add(ArgValueCandidates::new(completers::complete_workspaces)),
         )
+        .arg(
+            Arg::new("dir")
+                .long("dir")
+                .value_name("REPO=SUBDIR")
+                .action(ArgAction::Append)
+                .help("Run in SUBDIR of REPO instead of the repo root (repeatable)"),
+        )
+        .arg(
+            Arg::new("affected")
+                .long("affected")
+                .action(ArgAction::SetTrue)
+                .help("Only run in changed repos and the repos that depend on them"),
+        )
+        .arg(
+            Arg::new("tmux")
+                .long("tmux")
+                .action(ArgAction::SetTrue)
+                .help("Launch in a tmux pane per repo instead of running serially"),
+        )
         .arg(Arg::new("command").required(true).num_args(1..).last(true))
 }
 
 pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     let command: Vec<&String> = matches.get_many::<String>("command").unwrap().collect();
-    let is_json = matches.get_flag("json");
+    let is_json = Format::from_matches(matches).is_structured();
 
     let ws_dir: PathBuf = if let Some(name) = matches.get_one::<String>("workspace") {
-        workspace::dir(&paths.workspaces_dir, name)
+        paths.workspace_dir(name)
     } else {
         let cwd = std::env::current_dir()?;
         workspace::detect(&cwd)?
     };
-    let meta = workspace::load_metadata(&ws_dir)
-        .map_err(|e| anyhow::anyhow!("reading workspace: {}", e))?;
+    let meta =
+        workspace::load_metadata(&ws_dir).map_err(|e| error::prefixed(e, "reading workspace"))?;
 
-    let mut results = Vec::new();
+    let dir_overrides: Vec<&String> = matches
+        .get_many::<String>("dir")
+        .map(|v| v.collect())
+        .unwrap_or_default();
+    let cfg = config::Config::load_from(&paths.config_path)?;
+    let subdirs = resolve_subdirs(&meta, &cfg, &dir_overrides)?;
+    let effective = meta.apply_workspace_config(&cfg);
+    let identities: Vec<String> = meta.repos.keys().cloned().collect();
+    let mut order = deps::order(effective.depends_on.as_ref(), &identities)?;
 
-    for identity in meta.repos.keys() {
-        let dir_name = match meta.dir_name(identity) {
-            Ok(d) => d,
-            Err(e) => {
-                if !is_json {
-                    eprintln!("[{}] error: {}", identity, e);
-                }
-                results.push(ExecRepoResult {
-                    identity: identity.to_string(),
-                    shortname: identity.rsplit('/').next().unwrap_or(identity).to_string(),
-                    path: String::new(),
-                    directory: String::new(),
-                    exit_code: -1,
-                    ok: false,
-                    stdout: None,
-                    stderr: None,
-                    error: Some(e.to_string()),
-                });
-                continue;
+    if matches.get_flag("affected") {
+        let graph = deps::graph(effective.depends_on.as_ref(), &identities);
+        let mut changed = Vec::new();
+        for id in &identities {
+            let dir = ws_dir.join(meta.dir_name(id)?);
+            let is_changed = repo_changed(&dir)
+                .map_err(|e| anyhow::anyhow!("checking {} for changes: {}", id, e))?;
+            if is_changed {
+                changed.push(id.clone());
             }
-        };
+        }
+        let affected = graph.with_dependents(changed);
+        order.retain(|id| affected.contains(id));
+        if !is_json {
+            let names: Vec<String> = order
+                .iter()
+                .map(|id| meta.dir_name(id).unwrap_or_else(|_| id.clone()))
+                .collect();
+            if names.is_empty() {
+                eprintln!("No affected repos.");
+            } else {
+                eprintln!(
+                    "Affected: {} of {} repos ({})\n",
+                    names.len(),
+                    identities.len(),
+                    names.join(", ")
+                );
+            }
+        }
+    }
 
-        let repo_dir = ws_dir.join(&dir_name);
-        let cmd_str = command
-            .iter()
-            .map(|s| s.as_str())
-            .collect::<Vec<_>>()
-            .join(" ");
+    let command: Vec<String> = command.into_iter().cloned().collect();
+    if matches.get_flag("tmux") {
+        return launch_tmux(&ws_dir, &meta, &order, &command, &subdirs);
+    }
+    let display = command.join(" ");
+    let results = order
+        .iter()
+        .map(|identity| {
+            exec_in_repo(
+                &ws_dir,
+                &meta,
+                identity,
+                &command,
+                &display,
+                subdirs.get(identity).map(|s| s.as_str()),
+                is_json,
+            )
+        })
+        .collect();
 
-        if !is_json {
-            println!("==> [{}] {}", dir_name, cmd_str);
+    Ok(Output::Exec(ExecOutput {
+        workspace: meta.name,
+        repos: results,
+    }))
+}
+
+/// Run `command` in one repo of a workspace (or its `subdir`), printing a
+/// `==> [repo] cmd` header unless capturing. Never fails: errors are reported in
+/// the returned result so callers can keep going with the remaining repos.
+pub(crate) fn exec_in_repo(
+    ws_dir: &Path,
+    meta: &workspace::Metadata,
+    identity: &str,
+    command: &[String],
+    display: &str,
+    subdir: Option<&str>,
+    capture: bool,
+) -> ExecRepoResult {
+    let dir_name = match meta.dir_name(identity) {
+        Ok(d) => d,
+        Err(e) => {
+            if !capture {
+                eprintln!("[{}] error: {}", identity, e);
+            }
+            return ExecRepoResult {
+                identity: identity.to_string(),
+                shortname: identity.rsplit('/').next().unwrap_or(identity).to_string(),
+                path: String::new(),
+                directory: String::new(),
+                exit_code: -1,
+                ok: false,
+                stdout: None,
+                stderr: None,
+                error: Some(e.to_string()),
+            };
         }
+    };
+
+    let (repo_dir, label) = match subdir {
+        Some(sub) => (
+            ws_dir.join(&dir_name).join(sub),
+            format!("{}/{}", dir_name, sub),
+        ),
+        None => (ws_dir.join(&dir_name), dir_name.clone()),
+    };
+
+    if !capture {
+        println!("==> [{}] {}", label, display);
+    }
 
-        match run_command(&command, &repo_dir, is_json, identity, &dir_name) {
-            Ok(result) => {
-                if !is_json && !result.ok {
-                    eprintln!("[{}] error: exit status {}", dir_name, result.exit_code);
-                }
-                results.push(result);
+    let result = if !repo_dir.is_dir() {
+        Err(anyhow::anyhow!(
+            "directory {} does not exist",
+            repo_dir.display()
+        ))
+    } else {
+        run_command(command, &repo_dir, capture, identity, &dir_name)
+    };
+
+    let result = match result {
+        Ok(result) => {
+            if !capture && !result.ok {
+                eprintln!("[{}] error: exit status {}", label, result.exit_code);
             }
-            Err(e) => {
-                if !is_json {
-                    eprintln!("[{}] error: {}", dir_name, e);
-                }
-                results.push(ExecRepoResult {
-                    identity: identity.to_string(),
-                    shortname: dir_name.clone(),
-                    path: repo_dir.to_string_lossy().to_string(),
-                    directory: dir_name,
-                    exit_code: -1,
-                    ok: false,
-                    stdout: None,
-                    stderr: None,
-                    error: Some(e.to_string()),
-                });
+            result
+        }
+        Err(e) => {
+            if !capture {
+                eprintln!("[{}] error: {}", label, e);
+            }
+            ExecRepoResult {
+                identity: identity.to_string(),
+                shortname: dir_name.clone(),
+                path: repo_dir.to_string_lossy().to_string(),
+                directory: dir_name,
+                exit_code: -1,
+                ok: false,
+                stdout: None,
+                stderr: None,
+                error: Some(e.to_string()),
             }
         }
+    };
 
-        if !is_json {
-            println!();
+    if !capture {
+        println!();
+    }
+    result
+}
+
+/// Open one tmux pane per repo running `command`, in a session named after
+/// the workspace.
+fn launch_tmux(
+    ws_dir: &Path,
+    meta: &workspace::Metadata,
+    order: &[String],
+    command: &[String],
+    subdirs: &BTreeMap<String, String>,
+) -> Result<Output> {
+    if !tmux::is_available() {
+        bail!("tmux not found on PATH");
+    }
+    let shell_command: Vec<String> = command.iter().map(|a| util::shell_quote(a)).collect();
+    let shell_command = shell_command.join(" ");
+
+    let mut panes = Vec::new();
+    for identity in order {
+        let dir_name = meta.dir_name(identity)?;
+        let (dir, label) = match subdirs.get(identity) {
+            Some(sub) => (
+                ws_dir.join(&dir_name).join(sub),
+                format!("{}/{}", dir_name, sub),
+            ),
+            None => (ws_dir.join(&dir_name), dir_name),
+        };
+        if !dir.is_dir() {
+            eprintln!(
+                "[{}] skipping: directory {} does not exist",
+                label,
+                dir.display()
+            );
+            continue;
         }
+        panes.push(tmux::Pane {
+            label,
+            dir,
+            command: shell_command.clone(),
+        });
+    }
+    if panes.is_empty() {
+        bail!("no repos to run in");
     }
 
-    Ok(Output::Exec(ExecOutput {
-        workspace: meta.name,
-        repos: results,
-    }))
+    let session = tmux::session_name(&meta.name);
+    let window = Path::new(&command[0])
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| command[0].clone());
+    tmux::launch(&session, &window, &panes)?;
+
+    Ok(Output::Mutation(
+        MutationOutput::new(format!(
+            "Launched {} panes in tmux session {:?}",
+            panes.len(),
+            session
+        ))
+        .with_hint(tmux::attach_hint(&session)),
+    ))
+}
+
+/// A repo is changed if its branch has commits that aren't on
+/// origin/<default>, or its working tree has uncommitted changes. Errors
+/// are returned rather than guessed at, so `--affected` never silently
+/// skips (or includes) a repo it couldn't inspect.
+fn repo_changed(dir: &Path) -> Result<bool> {
+    if git::changed_file_count(dir)? > 0 {
+        return Ok(true);
+    }
+    let branch = git::default_branch(dir)?;
+    Ok(git::commit_count(dir, &format!("origin/{}", branch), "HEAD")? > 0)
+}
+
+/// Merge `exec-dir` config, workspace over global, with `--dir REPO=SUBDIR`
+/// overrides into a map keyed by identity. Flags win over config.
+pub(crate) fn resolve_subdirs(
+    meta: &workspace::Metadata,
+    cfg: &config::Config,
+    overrides: &[&String],
+) -> Result<BTreeMap<String, String>> {
+    let mut subdirs = BTreeMap::new();
+    for identity in meta.repos.keys() {
+        if let Some(subdir) = meta.exec_dir(cfg, identity) {
+            workspace::validate_exec_dir(subdir)
+                .map_err(|e| anyhow::anyhow!("invalid exec-dir for {}: {}", identity, e))?;
+            subdirs.insert(identity.clone(), subdir.to_string());
+        }
+    }
+
+    let identities: Vec<String> = meta.repos.keys().cloned().collect();
+    for raw in overrides {
+        let Some((repo, subdir)) = raw.split_once('=') else {
+            bail!("--dir expects REPO=SUBDIR, got {:?}", raw);
+        };
+        workspace::validate_exec_dir(subdir)?;
+        let identity = giturl::resolve(repo, &identities)?;
+        subdirs.insert(identity, subdir.to_string());
+    }
+    Ok(subdirs)
 }
 
 fn run_command(
-    command: &[&String],
+    command: &[String],
     dir: &Path,
     capture: bool,
     identity: &str,
@@ -124,10 +342,8 @@ fn run_command(
         !command.is_empty(),
         "command must have at least one element"
     );
-    let mut cmd = ProcessCommand::new(command[0].as_str());
-    for arg in &command[1..] {
-        cmd.arg(arg.as_str());
-    }
+    let mut cmd = ProcessCommand::new(&command[0]);
+    cmd.args(&command[1..]);
     cmd.current_dir(dir);
     // In capture mode (--json), use null stdin so subprocesses that read stdin
     // get immediate EOF instead of hanging in automated/agent pipelines.
@@ -204,4 +420,168 @@ mod tests {
             .collect();
         assert_eq!(command, vec!["make", "test"]);
     }
+
+    #[test]
+    fn parse_args_affected() {
+        let m = cmd().get_matches_from(["exec", "--affected", "--", "make"]);
+        assert!(m.get_flag("affected"));
+        let m = cmd().get_matches_from(["exec", "--", "make"]);
+        assert!(!m.get_flag("affected"));
+    }
+
+    #[test]
+    fn parse_args_tmux() {
+        let m = cmd().get_matches_from(["exec", "--tmux", "--", "npm", "run", "dev"]);
+        assert!(m.get_flag("tmux"));
+        let m = cmd().get_matches_from(["exec", "--", "make"]);
+        assert!(!m.get_flag("tmux"));
+    }
+
+    #[test]
+    fn parse_args_with_dirs() {
+        let m = cmd().get_matches_from([
+            "exec",
+            "--dir",
+            "api=services/api",
+            "--dir",
+            "web=app",
+            "--",
+            "npm",
+            "test",
+        ]);
+        let dirs: Vec<&str> = m
+            .get_many::<String>("dir")
+            .unwrap()
+            .map(|s| s.as_str())
+            .collect();
+        assert_eq!(dirs, vec!["api=services/api", "web=app"]);
+    }
+
+    #[test]
+    fn test_repo_changed() {
+        let (clone_dir, _source, _ct, _st) = crate::testutil::setup_clone_repo();
+        assert!(
+            !repo_changed(&clone_dir).unwrap(),
+            "fresh branch is unchanged"
+        );
+
+        std::fs::write(clone_dir.join("wip.txt"), "wip").unwrap();
+        assert!(repo_changed(&clone_dir).unwrap(), "untracked file counts");
+
+        crate::testutil::local_commit(&clone_dir, "wip.txt", "wip");
+        assert!(
+            repo_changed(&clone_dir).unwrap(),
+            "commit not on origin/main counts"
+        );
+
+        let not_a_repo = tempfile::tempdir().unwrap();
+        assert!(repo_changed(not_a_repo.path()).is_err(), "errors propagate");
+    }
+
+    fn meta_with_exec_dirs(exec_dirs: &[(&str, &str)]) -> workspace::Metadata {
+        let mut repos = BTreeMap::new();
+        repos.insert("github.com/acme/api".to_string(), None);
+        repos.insert("github.com/acme/web".to_string(), None);
+        let exec_dirs: BTreeMap<String, String> = exec_dirs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        workspace::Metadata {
+            version: 0,
+            name: "ws".into(),
+            branch: "ws".into(),
+            repos,
+            created: chrono::Utc::now(),
+            description: None,
+            last_used: None,
+            created_from: None,
+            dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
+            config: Some(crate::template::TemplateConfig {
+                exec_dirs: Some(exec_dirs),
+                ..Default::default()
+            }),
+            pending: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_subdirs() {
+        let api = "github.com/acme/api";
+        let web = "github.com/acme/web";
+        type Pairs<'a> = Vec<(&'a str, &'a str)>;
+        let cases: Vec<(&str, Pairs, Vec<&str>, Pairs)> = vec![
+            (
+                "config only",
+                vec![(api, "services/api")],
+                vec![],
+                vec![(api, "services/api")],
+            ),
+            (
+                "flag by shortname",
+                vec![],
+                vec!["web=app"],
+                vec![(web, "app")],
+            ),
+            (
+                "flag overrides config",
+                vec![(api, "services/api")],
+                vec!["api=cmd"],
+                vec![(api, "cmd")],
+            ),
+        ];
+        for (name, config, flags, want) in cases {
+            let meta = meta_with_exec_dirs(&config);
+            let flags: Vec<String> = flags.iter().map(|s| s.to_string()).collect();
+            let flag_refs: Vec<&String> = flags.iter().collect();
+            let got = resolve_subdirs(&meta, &config::Config::default(), &flag_refs).unwrap();
+            let want: BTreeMap<String, String> = want
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            assert_eq!(got, want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_resolve_subdirs_global_fallback() {
+        let api = "github.com/acme/api";
+        let web = "github.com/acme/web";
+        let mut cfg = config::Config::default();
+        for (id, subdir) in [(api, "global/api"), (web, "global/web")] {
+            cfg.repos.insert(
+                id.to_string(),
+                config::RepoEntry {
+                    url: format!("https://{}.git", id),
+                    added: chrono::Utc::now(),
+                    setup: None,
+                    exec_dir: Some(subdir.to_string()),
+                },
+            );
+        }
+        let meta = meta_with_exec_dirs(&[(api, "services/api")]);
+        let got = resolve_subdirs(&meta, &cfg, &[]).unwrap();
+        assert_eq!(got[api], "services/api", "workspace wins");
+        assert_eq!(got[web], "global/web", "global fills in");
+    }
+
+    #[test]
+    fn test_resolve_subdirs_errors() {
+        let cases = vec![
+            ("missing equals", "services/api"),
+            ("unknown repo", "nope=src"),
+            ("traversal", "api=../web"),
+        ];
+        for (name, flag) in cases {
+            let meta = meta_with_exec_dirs(&[]);
+            let flag = flag.to_string();
+            assert!(
+                resolve_subdirs(&meta, &config::Config::default(), &[&flag]).is_err(),
+                "{}",
+                name
+            );
+        }
+    }
 }
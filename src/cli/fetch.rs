@@ -1,14 +1,18 @@
+use std::cell::Cell;
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-use anyhow::{Result, bail};
-use clap::{ArgMatches, Command};
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
 
 use crate::config::{self, Paths};
+use crate::error::{self, ErrorKind};
 use crate::gc;
 use crate::git;
 use crate::giturl;
 use crate::mirror;
-use crate::output::{FetchOutput, FetchRepoResult, Output};
+use crate::output::{self, FetchOutput, FetchRepoResult, Output};
+use crate::util;
 use crate::workspace;
 
 pub fn cmd() -> Command {
@@ -18,7 +22,16 @@ pub fn cmd() -> Command {
             "Fetch updates for workspace repos.\n\n\
              Fetches from upstream into the bare mirror, then propagates to each clone via \
              local path-based fetch. This two-layer fetch means upstream is only contacted \
-             once per repo, regardless of how many workspaces share it.",
+             once per repo, regardless of how many workspaces share it.\n\n\
+             Mirrors are fetched in parallel, up to --jobs at a time. A fetch that fails \
+             with a network error is retried (--retries), waiting 1s, 2s, 4s... between \
+             attempts; --timeout gives up on an attempt that hangs. Defaults come from \
+             fetch.jobs, fetch.retries and fetch.timeout.\n\n\
+             --max-age skips mirrors fetched more recently than the given age (e.g. 10m, \
+             2h; default fetch.max-age), which keeps repeated `fetch --all` runs over a \
+             large registry cheap. \
+             Runs over more than 50 repos report progress every 50 repos instead of \
+             one line per repo; failures are always listed.",
         )
         .arg(
             clap::Arg::new("all")
@@ -32,11 +45,102 @@ pub fn cmd() -> Command {
                 .action(clap::ArgAction::SetTrue)
                 .help("Prune deleted remote branches"),
         )
+        .arg(
+            Arg::new("jobs")
+                .short('j')
+                .long("jobs")
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize))
+                .help("Fetch up to N repos at once [default: fetch.jobs, else 8]"),
+        )
+        .arg(
+            Arg::new("retries")
+                .long("retries")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u32))
+                .help("Retry a repo up to N times after a network error [default: fetch.retries, else 2]"),
+        )
+        .arg(
+            Arg::new("timeout")
+                .long("timeout")
+                .value_name("SECS")
+                .value_parser(clap::value_parser!(u64))
+                .help("Give up on a fetch attempt after SECS seconds, 0 for never [default: fetch.timeout, else 0]"),
+        )
+        .arg(super::max_age_arg())
+}
+
+/// Default `fetch.jobs`: network-bound, so not tied to the CPU count.
+pub const DEFAULT_JOBS: usize = 8;
+/// Default `fetch.retries`.
+pub const DEFAULT_RETRIES: u32 = 2;
+/// How often a fetch still running reports its progress.
+pub(crate) const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+/// Above this many repos, progress is reported per chunk of this many
+/// rather than per repo.
+const CHUNK: usize = 50;
+
+/// How [`fetch_mirrors`] goes about it: how many repos at once, and how
+/// hard to try each one.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    pub jobs: usize,
+    /// Further attempts after a network error.
+    pub retries: u32,
+    /// Per attempt.
+    pub timeout: Option<Duration>,
+    /// Wait before the first retry, doubled for each one after.
+    pub backoff: Duration,
+    /// Skip mirrors fetched more recently than this.
+    pub max_age: Option<Duration>,
+}
+
+impl Limits {
+    /// The `fetch.*` settings, else the defaults.
+    pub fn from_config(cfg: &config::Config) -> Self {
+        Limits {
+            jobs: cfg.fetch_jobs.unwrap_or(DEFAULT_JOBS).max(1),
+            retries: cfg.fetch_retries.unwrap_or(DEFAULT_RETRIES),
+            timeout: cfg
+                .fetch_timeout
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs),
+            backoff: Duration::from_secs(1),
+            max_age: cfg
+                .fetch_max_age
+                .as_deref()
+                .and_then(|s| crate::util::parse_duration(s).ok())
+                .filter(|age| !age.is_zero()),
+        }
+    }
+
+    /// [`Limits::from_config`] with the `--max-age` given, for the commands
+    /// that take it.
+    pub fn resolve(matches: &ArgMatches, cfg: &config::Config) -> Self {
+        let mut limits = Self::from_config(cfg);
+        if let Ok(Some(&age)) = matches.try_get_one::<Duration>("max-age") {
+            limits.max_age = (!age.is_zero()).then_some(age);
+        }
+        limits
+    }
 }
 
 pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    crate::offline::ensure_online("wsp repo fetch")?;
     let all = matches.get_flag("all");
     let prune = matches.get_flag("prune");
+    let cfg = config::Config::load_from(&paths.config_path)
+        .map_err(|e| error::prefixed(e, "loading config"))?;
+    let mut limits = Limits::resolve(matches, &cfg);
+    if let Some(&jobs) = matches.get_one::<usize>("jobs") {
+        limits.jobs = jobs.max(1);
+    }
+    if let Some(&retries) = matches.get_one::<u32>("retries") {
+        limits.retries = retries;
+    }
+    if let Some(&secs) = matches.get_one::<u64>("timeout") {
+        limits.timeout = (secs > 0).then(|| Duration::from_secs(secs));
+    }
 
     // Detect current workspace (if not --all)
     let current_ws: Option<(std::path::PathBuf, workspace::Metadata)> = if !all {
@@ -54,13 +158,16 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     };
 
     let identities: Vec<String> = if all {
-        let cfg = config::Config::load_from(&paths.config_path)
-            .map_err(|e| anyhow::anyhow!("loading config: {}", e))?;
         cfg.repos.keys().cloned().collect()
     } else {
         match &current_ws {
             Some((_, meta)) => meta.repos.keys().cloned().collect(),
-            None => bail!("not in a workspace, use --all to fetch all registered repos"),
+            None => {
+                return Err(error::tag(
+                    ErrorKind::NotFound,
+                    anyhow::anyhow!("not in a workspace, use --all to fetch all registered repos"),
+                ));
+            }
         }
     };
 
@@ -75,75 +182,14 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     }
 
     // Phase 1: Fetch mirrors (network, parallel)
-    let repos: Vec<(String, std::path::PathBuf)> = identities
-        .into_iter()
-        .filter_map(|id| match giturl::Parsed::from_identity(&id) {
-            Ok(parsed) => Some((id, mirror::dir(&paths.mirrors_dir, &parsed))),
-            Err(e) => {
-                eprintln!("  {}: error parsing identity: {}", id, e);
-                None
-            }
-        })
-        .collect();
-
-    let ids: Vec<String> = repos.iter().map(|(id, _)| id.clone()).collect();
-    let shortnames = giturl::shortnames(&ids);
-
-    if repos.len() == 1 {
-        let name = shortnames
-            .get(&repos[0].0)
-            .map(|s| s.as_str())
-            .unwrap_or(&repos[0].0);
-        eprintln!("Fetching {}...", name);
-    } else {
-        eprintln!("Fetching {} repos...", repos.len());
-    }
-
-    let progress = Mutex::new(());
-    let results: Vec<(String, Result<()>)> = std::thread::scope(|s| {
-        let handles: Vec<_> = repos
-            .iter()
-            .map(|(id, mirror_dir)| {
-                let progress = &progress;
-                let shortnames = &shortnames;
-                s.spawn(move || {
-                    let result = git::fetch(mirror_dir, prune);
-                    let _lock = progress.lock().unwrap_or_else(|e| e.into_inner());
-                    let name = shortnames.get(id).map(|s| s.as_str()).unwrap_or(id);
-                    match &result {
-                        Ok(()) => eprintln!("  ok    {}", name),
-                        Err(e) => eprintln!("  FAIL  {} ({})", name, e),
-                    }
-                    result
-                })
-            })
-            .collect();
-
-        repos
-            .iter()
-            .zip(handles)
-            .map(|((id, _), h)| {
-                (
-                    id.clone(),
-                    h.join().unwrap_or_else(|panic_val| {
-                        let msg = panic_val
-                            .downcast_ref::<&str>()
-                            .map(|s| s.to_string())
-                            .or_else(|| panic_val.downcast_ref::<String>().cloned())
-                            .unwrap_or_else(|| "unknown panic".to_string());
-                        Err(anyhow::anyhow!("thread panicked: {}", msg))
-                    }),
-                )
-            })
-            .collect()
-    });
+    let results = fetch_mirrors(paths, identities, prune, &limits);
 
     // Phase 2: Propagate mirror refs to workspace clones
     if all {
         // Propagate to all workspaces
-        if let Ok(ws_names) = workspace::list_all(&paths.workspaces_dir) {
+        if let Ok(ws_names) = workspace::list_all(paths) {
             for ws_name in &ws_names {
-                let ws_dir = workspace::dir(&paths.workspaces_dir, ws_name);
+                let ws_dir = paths.workspace_dir(ws_name);
                 if let Ok(meta) = workspace::load_metadata(&ws_dir) {
                     workspace::propagate_mirror_to_clones(
                         &paths.mirrors_dir,
@@ -163,19 +209,482 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
             .as_ref()
             .map(|(_, m)| m.name.clone())
             .unwrap_or_default(),
-        repos: results
-            .into_iter()
-            .map(|(id, result)| {
-                let name = shortnames.get(&id).cloned().unwrap_or_else(|| id.clone());
-                FetchRepoResult {
-                    identity: id,
-                    shortname: name,
-                    ok: result.is_ok(),
-                    error: result.err().map(|e| e.to_string()),
-                }
-            })
-            .collect(),
+        repos: results,
     };
 
     Ok(Output::Fetch(output))
 }
+
+/// Fetch the mirrors of `identities` from upstream, `limits.jobs` at a
+/// time, printing progress to stderr. Returns each repo's result, in order.
+/// Clones are not touched; follow up with
+/// `workspace::propagate_mirror_to_clones`.
+pub fn fetch_mirrors(
+    paths: &Paths,
+    identities: Vec<String>,
+    prune: bool,
+    limits: &Limits,
+) -> Vec<FetchRepoResult> {
+    let repos = identities.into_iter().map(|id| (id, None)).collect();
+    fetch_or_clone_mirrors(paths, repos, prune, limits)
+}
+
+/// [`fetch_mirrors`] for `(identity, url)` pairs, where a repo with a URL
+/// has no mirror yet: it is cloned from the URL first, then fetched (which
+/// fills in `refs/remotes/origin`, as `registry add` does). Clones get the
+/// same retries and timeout as fetches, and aren't skipped by `max_age`.
+pub fn fetch_or_clone_mirrors(
+    paths: &Paths,
+    repos: Vec<(String, Option<String>)>,
+    prune: bool,
+    limits: &Limits,
+) -> Vec<FetchRepoResult> {
+    let repos: Vec<(String, giturl::Parsed, Option<String>)> = repos
+        .into_iter()
+        .filter_map(|(id, url)| match giturl::Parsed::from_identity(&id) {
+            Ok(parsed) => Some((id, parsed, url)),
+            Err(e) => {
+                eprintln!("  {}: error parsing identity: {}", id, e);
+                None
+            }
+        })
+        .collect();
+
+    let ids: Vec<String> = repos.iter().map(|(id, _, _)| id.clone()).collect();
+    let shortnames = giturl::shortnames(&ids);
+    let name_of = |id: &str| {
+        shortnames
+            .get(id)
+            .cloned()
+            .unwrap_or_else(|| id.to_string())
+    };
+
+    let cloning = repos.iter().filter(|(_, _, url)| url.is_some()).count();
+    match (repos.len() - cloning, cloning) {
+        (1, 0) => eprintln!("Fetching {}...", name_of(&repos[0].0)),
+        (0, 1) => eprintln!("Cloning missing mirror of {}...", name_of(&repos[0].0)),
+        (n, 0) => eprintln!("Fetching {} repos...", n),
+        (0, m) => eprintln!("Cloning {} missing mirrors...", m),
+        (n, m) => eprintln!("Fetching {} repos, cloning {} missing...", n, m),
+    }
+
+    // Large runs report every CHUNK repos; per-repo lines would scroll
+    // the failures out of sight.
+    let chunked = repos.len() > CHUNK;
+    let tally = Mutex::new(Tally::default());
+    let report = |tally: &mut Tally, line: Option<String>| {
+        tally.done += 1;
+        if let Some(line) = line {
+            eprintln!("{}", line);
+        }
+        if chunked && (tally.done.is_multiple_of(CHUNK) || tally.done == repos.len()) {
+            eprintln!(
+                "  [{}/{}] {} ok, {} skipped, {} failed",
+                tally.done,
+                repos.len(),
+                tally.done - tally.skipped - tally.failed,
+                tally.skipped,
+                tally.failed
+            );
+        }
+    };
+
+    let progress = &tally;
+    util::run_bounded(
+        &repos,
+        limits.jobs,
+        |(id, parsed, url)| {
+            let name = name_of(id);
+            if url.is_none()
+                && let Some(at) = recently_fetched(&paths.mirrors_dir, parsed, limits.max_age)
+            {
+                let mut tally = progress.lock().unwrap_or_else(|e| e.into_inner());
+                tally.skipped += 1;
+                let line = (!chunked)
+                    .then(|| format!("  skip  {} (fetched {})", name, output::format_age(&at)));
+                report(&mut tally, line);
+                return FetchRepoResult {
+                    identity: id.clone(),
+                    shortname: name,
+                    ok: true,
+                    error: None,
+                    retries: 0,
+                    objects: None,
+                    bytes: None,
+                    duration_ms: None,
+                    skipped: true,
+                    cloned: false,
+                };
+            }
+            let mirror_dir = mirror::dir(&paths.mirrors_dir, parsed);
+            let start = Instant::now();
+            // Slow transfers say how far along they are, every few seconds.
+            let last_report = Cell::new(start);
+            let on_progress = |t: &git::Transfer| {
+                if last_report.get().elapsed() < PROGRESS_INTERVAL {
+                    return;
+                }
+                last_report.set(Instant::now());
+                let _lock = progress.lock().unwrap_or_else(|e| e.into_inner());
+                eprintln!(
+                    "  ...   {} ({}{}/{} objects, {}, {}s)",
+                    name,
+                    if url.is_some() { "cloning, " } else { "" },
+                    t.objects,
+                    t.total_objects,
+                    output::format_bytes(t.bytes),
+                    start.elapsed().as_secs()
+                );
+            };
+            let on_retry = |attempt: u32, wait: Duration, e: &anyhow::Error| {
+                let _lock = progress.lock().unwrap_or_else(|e| e.into_inner());
+                eprintln!(
+                    "  retry {} in {}s, attempt {} of {} ({})",
+                    name,
+                    wait.as_secs(),
+                    attempt + 1,
+                    limits.retries + 1,
+                    e
+                );
+            };
+            let (cloned, clone_retries) = match url {
+                Some(url) => {
+                    let (result, retries) = fetch_with_retry(
+                        limits,
+                        |timeout| {
+                            mirror::clone_within(
+                                &paths.mirrors_dir,
+                                parsed,
+                                url,
+                                timeout,
+                                &on_progress,
+                            )
+                        },
+                        on_retry,
+                    );
+                    (Some(result), retries)
+                }
+                None => (None, 0),
+            };
+            let (result, retries) = match cloned {
+                Some(Err(e)) => (Err(anyhow::anyhow!("cloning: {}", e)), clone_retries),
+                _ => {
+                    let (result, retries) = fetch_with_retry(
+                        limits,
+                        |timeout| {
+                            mirror::fetch_dir_within(&mirror_dir, prune, timeout, &on_progress)
+                        },
+                        on_retry,
+                    );
+                    (result, clone_retries + retries)
+                }
+            };
+            let duration_ms = start.elapsed().as_millis() as u64;
+            let mut tally = progress.lock().unwrap_or_else(|e| e.into_inner());
+            let line = match &result {
+                _ if chunked && result.is_ok() => None,
+                Ok(_) if url.is_some() => Some(format!(
+                    "  ok    {} (cloned in {:.1}s)",
+                    name,
+                    duration_ms as f64 / 1000.0
+                )),
+                Ok(Some(t)) if t.objects > 0 => Some(format!(
+                    "  ok    {} ({} objects, {} in {:.1}s)",
+                    name,
+                    t.objects,
+                    output::format_bytes(t.bytes),
+                    duration_ms as f64 / 1000.0
+                )),
+                Ok(Some(_)) => Some(format!(
+                    "  ok    {} ({:.1}s)",
+                    name,
+                    duration_ms as f64 / 1000.0
+                )),
+                Ok(None) => Some(format!("  ok    {} (fetched by another command)", name)),
+                Err(e) => {
+                    tally.failed += 1;
+                    Some(format!("  FAIL  {} ({})", name, e))
+                }
+            };
+            report(&mut tally, line);
+            drop(tally);
+            let transfer = result
+                .as_ref()
+                .ok()
+                .copied()
+                .flatten()
+                .filter(|t| t.objects > 0 && url.is_none());
+            FetchRepoResult {
+                identity: id.clone(),
+                shortname: name,
+                ok: result.is_ok(),
+                error: result.err().map(|e| e.to_string()),
+                retries,
+                objects: transfer.map(|t| t.objects),
+                bytes: transfer.map(|t| t.bytes),
+                duration_ms: Some(duration_ms),
+                skipped: false,
+                cloned: url.is_some(),
+            }
+        },
+        None,
+    )
+}
+
+/// Counts behind the chunked progress lines of [`fetch_mirrors`].
+#[derive(Default)]
+struct Tally {
+    done: usize,
+    skipped: usize,
+    failed: usize,
+}
+
+/// When the mirror was last fetched, if that was within `max_age`.
+pub(crate) fn recently_fetched(
+    mirrors_dir: &std::path::Path,
+    parsed: &giturl::Parsed,
+    max_age: Option<Duration>,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    let max_age = max_age?;
+    let fetched = mirror::last_fetched(mirrors_dir, parsed)?;
+    let age = (chrono::Utc::now() - fetched).to_std().unwrap_or_default();
+    (age < max_age).then_some(fetched)
+}
+
+/// Run `fetch` (given the per-attempt timeout) until it succeeds, fails
+/// with something other than a network error, or runs out of retries,
+/// sleeping `backoff`, then twice that, and so on in between. `on_retry`
+/// hears about each retry before its wait. Returns the last result and how
+/// many retries it took.
+fn fetch_with_retry<T>(
+    limits: &Limits,
+    fetch: impl Fn(Option<Duration>) -> Result<T>,
+    on_retry: impl Fn(u32, Duration, &anyhow::Error),
+) -> (Result<T>, u32) {
+    let mut retries = 0;
+    loop {
+        let result =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| fetch(limits.timeout)))
+                .unwrap_or_else(|panic_val| {
+                    let msg = panic_val
+                        .downcast_ref::<&str>()
+                        .map(|s| s.to_string())
+                        .or_else(|| panic_val.downcast_ref::<String>().cloned())
+                        .unwrap_or_else(|| "unknown panic".to_string());
+                    Err(anyhow::anyhow!("thread panicked: {}", msg))
+                });
+        match result {
+            Err(e) if retries < limits.retries && error::classify(&e) == ErrorKind::Network => {
+                let wait = limits.backoff.saturating_mul(1 << retries.min(16));
+                retries += 1;
+                on_retry(retries, wait, &e);
+                std::thread::sleep(wait);
+            }
+            result => return (result, retries),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    fn limits(retries: u32) -> Limits {
+        Limits {
+            jobs: 1,
+            retries,
+            timeout: None,
+            backoff: Duration::ZERO,
+            max_age: None,
+        }
+    }
+
+    #[test]
+    fn test_fetch_with_retry() {
+        let network = || {
+            error::tag(
+                ErrorKind::Network,
+                anyhow::anyhow!("could not resolve host"),
+            )
+        };
+        struct Case {
+            name: &'static str,
+            retries: u32,
+            /// Attempts that fail before one succeeds; `None` never succeeds.
+            failures: Option<u32>,
+            network: bool,
+            want_ok: bool,
+            want_retries: u32,
+        }
+        let cases = vec![
+            Case {
+                name: "first try",
+                retries: 2,
+                failures: Some(0),
+                network: true,
+                want_ok: true,
+                want_retries: 0,
+            },
+            Case {
+                name: "recovers",
+                retries: 2,
+                failures: Some(2),
+                network: true,
+                want_ok: true,
+                want_retries: 2,
+            },
+            Case {
+                name: "gives up",
+                retries: 2,
+                failures: None,
+                network: true,
+                want_ok: false,
+                want_retries: 2,
+            },
+            Case {
+                name: "retries off",
+                retries: 0,
+                failures: None,
+                network: true,
+                want_ok: false,
+                want_retries: 0,
+            },
+            Case {
+                name: "not a network error",
+                retries: 2,
+                failures: None,
+                network: false,
+                want_ok: false,
+                want_retries: 0,
+            },
+        ];
+        for tc in cases {
+            let attempts = RefCell::new(0u32);
+            let heard = RefCell::new(Vec::new());
+            let (result, retries) = fetch_with_retry(
+                &limits(tc.retries),
+                |_| {
+                    let n = *attempts.borrow();
+                    *attempts.borrow_mut() += 1;
+                    match tc.failures {
+                        Some(f) if n >= f => Ok(()),
+                        _ if tc.network => Err(network()),
+                        _ => Err(anyhow::anyhow!("fatal: bad object")),
+                    }
+                },
+                |attempt, _, _| heard.borrow_mut().push(attempt),
+            );
+            assert_eq!(result.is_ok(), tc.want_ok, "{}", tc.name);
+            assert_eq!(retries, tc.want_retries, "{}", tc.name);
+            assert_eq!(*attempts.borrow(), tc.want_retries + 1, "{}", tc.name);
+            assert_eq!(
+                *heard.borrow(),
+                (1..=tc.want_retries).collect::<Vec<_>>(),
+                "{}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_fetch_times_out_hung_remote() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mirror = tmp.path();
+        crate::git::run(Some(mirror), &["init", "--bare"]).unwrap();
+        // A remote whose transport never answers.
+        crate::git::run(Some(mirror), &["remote", "add", "origin", "ext::sleep 30"]).unwrap();
+        crate::git::run(Some(mirror), &["config", "protocol.ext.allow", "always"]).unwrap();
+        let start = std::time::Instant::now();
+        let err =
+            crate::git::fetch(mirror, false, Some(Duration::from_secs(1)), &|_| {}).unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(error::classify(&err), ErrorKind::Network, "{}", err);
+        assert!(err.to_string().contains("timed out after 1s"), "{}", err);
+    }
+
+    #[test]
+    fn test_recently_fetched() {
+        let tmp = tempfile::tempdir().unwrap();
+        let fetched = giturl::Parsed::from_identity("github.com/acme/fetched").unwrap();
+        let never = giturl::Parsed::from_identity("github.com/acme/never").unwrap();
+        let dir = mirror::dir(tmp.path(), &fetched);
+        std::fs::create_dir_all(&dir).unwrap();
+        // A failed fetch still writes FETCH_HEAD; it doesn't count.
+        let never_dir = mirror::dir(tmp.path(), &never);
+        std::fs::create_dir_all(&never_dir).unwrap();
+        std::fs::write(never_dir.join("FETCH_HEAD"), "").unwrap();
+        mirror::mark_fetched(&dir, std::time::SystemTime::now(), false).unwrap();
+
+        let min = |m: u64| Some(Duration::from_secs(m * 60));
+        let cases = [
+            ("no max age", &fetched, None, false),
+            ("within max age", &fetched, min(10), true),
+            ("older than max age", &fetched, Some(Duration::ZERO), false),
+            ("never fetched", &never, min(10), false),
+        ];
+        for (name, parsed, max_age, want) in cases {
+            assert_eq!(
+                recently_fetched(tmp.path(), parsed, max_age).is_some(),
+                want,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_limits_max_age_from_config() {
+        let cases = [
+            ("unset", None, None),
+            ("minutes", Some("10m"), Some(Duration::from_secs(600))),
+            ("zero means always fetch", Some("0"), None),
+            // configcheck reports it
+            ("invalid means always fetch", Some("soon"), None),
+        ];
+        for (name, value, want) in cases {
+            let cfg = config::Config {
+                fetch_max_age: value.map(String::from),
+                ..Default::default()
+            };
+            assert_eq!(Limits::from_config(&cfg).max_age, want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_limits_resolve_max_age() {
+        let cfg = config::Config {
+            fetch_max_age: Some("10m".into()),
+            ..Default::default()
+        };
+        let with_flag = Command::new("t").arg(super::super::max_age_arg());
+        let without_flag = Command::new("t");
+        let cases = [
+            ("no flag on the command", &without_flag, vec![], Some(600)),
+            ("flag not given", &with_flag, vec![], Some(600)),
+            (
+                "flag given",
+                &with_flag,
+                vec!["--max-age", "2h"],
+                Some(7200),
+            ),
+            (
+                "zero fetches everything",
+                &with_flag,
+                vec!["--max-age", "0"],
+                None,
+            ),
+        ];
+        for (name, cmd, args, want) in cases {
+            let matches = cmd
+                .clone()
+                .get_matches_from(std::iter::once("t").chain(args));
+            assert_eq!(
+                Limits::resolve(&matches, &cfg).max_age,
+                want.map(Duration::from_secs),
+                "{}",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use clap::{Arg, ArgMatches, Command};
+use clap_complete::engine::ArgValueCandidates;
+
+use crate::config::Paths;
+use crate::error::{self, ErrorKind};
+use crate::filelock;
+use crate::gc;
+use crate::git;
+use crate::output::{FixOutput, FixRepoResult, Output};
+use crate::workspace;
+
+use super::completers;
+
+pub fn cmd() -> Command {
+    Command::new("fix")
+        .about("Switch drifted repos back to the workspace branch")
+        .long_about(
+            "Switch drifted repos back to the workspace branch.\n\n\
+             A repo has drifted when its HEAD is detached or on a branch other than the \
+             workspace branch, e.g. after a manual `git checkout`. `wsp st` flags these. \
+             This command checks the workspace branch back out in each drifted repo.\n\n\
+             It never discards work: repos with uncommitted changes, an unfinished \
+             rebase/merge/cherry-pick/revert/bisect, or a detached HEAD holding commits \
+             that are on no branch are skipped with the reason. If the workspace branch \
+             only exists on origin, it is recreated from there.",
+        )
+        .arg(Arg::new("workspace").add(ArgValueCandidates::new(completers::complete_workspaces)))
+}
+
+pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    let ws_dir: PathBuf = match matches.get_one::<String>("workspace") {
+        Some(name) => paths.workspace_dir(name),
+        None => workspace::detect(&std::env::current_dir()?)?,
+    };
+
+    gc::check_workspace(&ws_dir, /* read_only */ false)?;
+    let _lock = filelock::lock_workspace(&ws_dir)?;
+
+    let meta =
+        workspace::load_metadata(&ws_dir).map_err(|e| error::prefixed(e, "reading workspace"))?;
+
+    let mut repos = Vec::new();
+    for identity in meta.repos.keys() {
+        let branch = meta.branch_for(identity);
+        let dir_name = match meta.dir_name(identity) {
+            Ok(d) => d,
+            Err(e) => {
+                repos.push(FixRepoResult {
+                    identity: identity.clone(),
+                    shortname: identity.clone(),
+                    from: String::new(),
+                    to: branch.to_string(),
+                    action: "skipped".into(),
+                    ok: false,
+                    error: Some(e.to_string()),
+                });
+                continue;
+            }
+        };
+        let clone_dir = ws_dir.join(&dir_name);
+        if !clone_dir.is_dir() {
+            repos.push(FixRepoResult {
+                identity: identity.clone(),
+                shortname: dir_name,
+                from: String::new(),
+                to: branch.to_string(),
+                action: "skipped".into(),
+                ok: false,
+                error: Some("clone directory is missing".into()),
+            });
+            continue;
+        }
+        let current = match git::symbolic_head(&clone_dir) {
+            Some(b) if b == branch => continue,
+            Some(b) => b,
+            None => "HEAD".to_string(),
+        };
+        let result = fix_repo(&clone_dir, branch, &current);
+        repos.push(FixRepoResult {
+            identity: identity.clone(),
+            shortname: dir_name,
+            from: current,
+            to: branch.to_string(),
+            action: if result.is_ok() {
+                "switched".into()
+            } else {
+                "skipped".into()
+            },
+            ok: result.is_ok(),
+            error: result.err().map(|e| e.to_string()),
+        });
+    }
+
+    Ok(Output::Fix(FixOutput {
+        workspace: meta.name,
+        branch: meta.branch,
+        repos,
+    }))
+}
+
+/// Check out `branch` in a repo currently on `current` ("HEAD" when
+/// detached), refusing whenever that could lose work.
+fn fix_repo(dir: &Path, branch: &str, current: &str) -> Result<()> {
+    if let Some(op) = git::in_progress_op(dir) {
+        bail!("{} in progress; {}", op.name(), op.hint());
+    }
+    if git::changed_file_count(dir)? > 0 {
+        bail!("uncommitted changes; commit or stash them first");
+    }
+    if current == "HEAD" && !git::head_on_any_branch(dir)? {
+        bail!("detached HEAD has commits on no branch; create a branch for them first");
+    }
+    if !git::branch_exists(dir, branch) && !git::remote_branch_exists(dir, branch) {
+        return Err(error::tag(
+            ErrorKind::NotFound,
+            anyhow::anyhow!("branch {:?} not found locally or on origin", branch),
+        ));
+    }
+    // For a branch only on origin, checkout recreates it tracking origin.
+    git::checkout(dir, branch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{local_commit, setup_clone_repo};
+
+    #[test]
+    fn test_fix_repo() {
+        // setup_clone_repo leaves the clone on `feature`; the workspace
+        // branch here is `main`.
+        type Setup = fn(&Path);
+        let cases: Vec<(&str, Setup, Option<&str>)> = vec![
+            ("other branch", |_| {}, None),
+            (
+                "dirty",
+                |dir| std::fs::write(dir.join("wip.txt"), "x").unwrap(),
+                Some("uncommitted changes"),
+            ),
+            (
+                "detached at a branch tip",
+                |dir| git::checkout(dir, "--detach").unwrap(),
+                None,
+            ),
+            (
+                "detached with orphan commits",
+                |dir| {
+                    git::checkout(dir, "--detach").unwrap();
+                    local_commit(dir, "lost.txt", "x");
+                },
+                Some("commits on no branch"),
+            ),
+        ];
+        for (name, setup, want_err) in cases {
+            let (clone, _source, _ct, _st) = setup_clone_repo();
+            setup(&clone);
+            let current = git::branch_current(&clone).unwrap();
+            let result = fix_repo(&clone, "main", &current);
+            match want_err {
+                None => {
+                    assert!(result.is_ok(), "{}: {:?}", name, result);
+                    assert_eq!(git::branch_current(&clone).unwrap(), "main", "{}", name);
+                }
+                Some(msg) => {
+                    let err = result.unwrap_err().to_string();
+                    assert!(err.contains(msg), "{}: {}", name, err);
+                    assert_eq!(git::branch_current(&clone).unwrap(), current, "{}", name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fix_repo_missing_branch() {
+        let (clone, _source, _ct, _st) = setup_clone_repo();
+        let err = fix_repo(&clone, "nope", "feature").unwrap_err();
+        assert!(err.to_string().contains("not found"), "{}", err);
+    }
+}
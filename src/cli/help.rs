@@ -2,7 +2,7 @@ use clap::{Arg, Command};
 use clap_complete::engine::{ArgValueCandidates, CompletionCandidate};
 use serde::Serialize;
 
-use crate::output::Output;
+use crate::output::{Format, Output, print_structured};
 
 /// Built-in help topics. Each is (name, short description, full text).
 const TOPICS: &[(&str, &str, &str)] = &[
@@ -83,7 +83,7 @@ IGNORE FILES
 
   There are two wspignore files, checked in order:
 
-  1. Global:        ~/.local/share/wsp/wspignore
+  1. Global:        ~/.config/wsp/wspignore
                     Created automatically on first use with sensible defaults
                     (.DS_Store, Thumbs.db, etc.). Edit to add patterns that
                     apply to all workspaces.
@@ -114,28 +114,62 @@ config — configuration keys and their effects
 
 Settings are stored at two levels:
 
-  Global:     ~/.local/share/wsp/config.yaml
-  Workspace:  .wsp.yaml `config` field (per-workspace overrides)
+  Global:     ~/.config/wsp/config.yaml
+  Workspace:  .wsp.yaml `config` field (per-workspace overrides; `settings`
+              is accepted as an alias)
 
 When run inside a workspace, `wsp config set/get/unset/ls` operate on
 workspace config by default. Use --global to target global config instead.
 Outside a workspace, commands always use global config.
 
-Workspace-scoped keys: sync-strategy, git.*, lang.*
-Global-only keys: branch-prefix, workspaces-dir, gc.retention-days, agent-md,
-                  shell.tmux, shell.prompt
-
-Config hierarchy (top wins): workspace → global → built-in defaults.
+Workspace-scoped keys: branch-prefix, sync-strategy, agent-md, run.jobs,
+                       git.*, lang.*, task.*, exec-dir.*
+Global-only keys: branch-prefix.*, branch-template,
+                  workspaces-dir, workspaces-dir.*, gc.retention-days, fetch.*,
+                  commit-trailer, push-guard, shell.tmux, shell.prompt, editor,
+                  issue.name-template, jira.url, forge.*, committer.*,
+                  url-rewrite.*, host.*
+
+Config hierarchy (top wins): workspace → environment → global → built-in
+defaults.
+
+Most global settings can be overridden for one command or a CI job with a
+WSP_* variable, without touching config.yaml: WSP_BRANCH_PREFIX,
+WSP_BRANCH_TEMPLATE, WSP_WORKSPACES_DIR, WSP_SYNC_STRATEGY, WSP_AGENT_MD,
+WSP_COMMIT_TRAILER, WSP_PUSH_GUARD, WSP_GC_RETENTION_DAYS, WSP_JOBS
+(run.jobs), WSP_EDITOR, WSP_ISSUE_NAME_TEMPLATE, WSP_JIRA_URL, WSP_SHELL_TMUX
+and WSP_SHELL_PROMPT. Empty variables are ignored; an invalid
+value is an error. `wsp config ls` marks overridden keys (env).
 
 GENERAL
 
   branch-prefix         String. Prefix prepended to workspace branch names.
                         Example: `jganoff` → branch `jganoff/my-feature`.
                         Default: not set (branches are just the workspace name).
+                        Set in a workspace, it applies to repos added later
+                        with `wsp repo add`.
+
+  branch-prefix.<pattern>
+                        String. Branch prefix for repos whose identity matches
+                        the pattern (`*` matches anything), e.g.
+                        `branch-prefix.github.com/oss-org/* feature`. The
+                        longest matching pattern wins over branch-prefix.
+
+  branch-template       String. How workspace branch names are built, from
+                        {prefix} (branch-prefix), {user} ($USER), {workspace},
+                        and {date} (YYYY-MM-DD). Must contain {workspace};
+                        empty parts drop out with their slash.
+                        Example: `{prefix}/{user}/{workspace}`.
+                        Default: {prefix}/{workspace}
 
   workspaces-dir        Absolute path. Where workspaces are created.
                         Default: ~/dev/workspaces
 
+  workspaces-dir.<template>
+                        Absolute path. Where `wsp new -t <template>` creates
+                        workspaces instead, e.g. `workspaces-dir.oss ~/oss`.
+                        Workspaces are found by name in every such dir.
+
   sync-strategy         `rebase` or `merge`. How `wsp sync` integrates upstream.
                         Default: rebase
 
@@ -143,6 +177,43 @@ GENERAL
                         workspace roots. Provides context for AI agents.
                         Default: true
 
+  editor                Command. Editor `wsp open` launches, e.g. `code` or
+                        `nvim`. Arguments are allowed: `code --new-window`.
+                        Default: $VISUAL, then $EDITOR
+
+ISSUES
+
+  issue.name-template   Workspace name for `wsp new --issue`. {key} is the
+                        issue key (ACME-123, or the GitHub issue number),
+                        {title} its title, lowercased and dashed.
+                        Default: {key}-{title}
+
+  jira.url              Jira site that `wsp new --issue ACME-123` looks keys
+                        up on, e.g. https://acme.atlassian.net. Auth comes
+                        from $JIRA_API_TOKEN (with $JIRA_USER on Jira Cloud).
+
+FETCH
+
+  fetch.jobs            Integer (≥1). How many mirrors `wsp repo fetch` fetches
+                        at once when -j isn't given.
+                        Default: 8
+
+  fetch.retries         Integer (≥0). How many times a fetch that fails with a
+                        network error is retried, waiting 1s, 2s, 4s... between
+                        attempts.
+                        Default: 2
+
+  fetch.timeout         Integer (seconds, ≥0). Give up on a fetch attempt that
+                        takes longer; it counts as a network error, so it is
+                        retried. 0 means no limit.
+                        Default: 0
+
+  fetch.max-age         Duration (e.g. 30s, 10m, 2h). Commands that fetch
+                        mirrors first skip those fetched more recently than
+                        this, unless --max-age says otherwise. 0 means always
+                        fetch.
+                        Default: 0
+
 GC (GARBAGE COLLECTION)
 
   gc.retention-days     Integer (≥0). How many days `wsp rm` keeps deleted
@@ -180,10 +251,177 @@ GIT CONFIG
                         Example: `wsp config set git.merge.conflictstyle zdiff3`
                         Unset reverts to the built-in default (if any).
 
+  committer.<pattern>.name, committer.<pattern>.email
+                        String. user.name / user.email for clones of repos
+                        whose identity matches the pattern (`*` matches
+                        anything; the longest match wins). Global-only.
+                        Example:
+                        `wsp config set 'committer.github.com/acme/*.email' jg@acme.com`
+
+  committer.<pattern>.signing-key, committer.<pattern>.signing-format
+                        user.signingkey (turning on commit.gpgsign) and
+                        gpg.format (gpg, ssh, or x509) for clones of repos
+                        matching the pattern. Global-only.
+                        Example:
+                        `wsp config set 'committer.github.com/*.signing-format' ssh`
+
+  commit-trailer        Boolean. Install a prepare-commit-msg hook in each
+                        clone that adds a `Workspace: <name>` trailer to
+                        commit messages. A repo's own prepare-commit-msg
+                        hook is left alone. Global-only.
+                        Default: false
+
+  push-guard            Boolean. Install a pre-push hook in each clone that
+                        rejects pushes to the repo's default branch, so a
+                        stray `git push origin HEAD:main` from a workspace
+                        fails. `git push --no-verify` bypasses it. A repo's
+                        own pre-push hook is left alone. Global-only.
+                        Default: false
+
+  git.core.hooksPath    Shared git hooks directory for every clone, so
+                        organization hooks run without per-clone setup.
+                        Relative paths resolve inside each clone (e.g.
+                        `.githooks`). Git then skips .git/hooks, so
+                        commit-trailer and push-guard can't be used with it;
+                        wsp doctor reports the conflict as an error.
+
+TASKS
+
+  task.<name>           Command string run by `wsp run <name>` in each repo,
+                        through the shell. Workspace tasks override global
+                        tasks of the same name. Per-repo overrides live in
+                        YAML (an empty command skips the repo):
+
+                          tasks:
+                            test:
+                              run: make test
+                              repos:
+                                web: npm test
+                              depends_on:
+                                api: [proto:gen]
+
+                        `depends_on` maps a repo to tasks that must finish
+                        first: `<repo>` (same task) or `<repo>:<task>`.
+                        Independent repos run in parallel (`wsp run -j N`);
+                        a failure skips everything that depends on it.
+
+                        Example: `wsp config set task.test \"make test\"`
+
+  run.jobs              Integer (≥1). How many repos `wsp run` runs at once
+                        when -j isn't given. 1 streams output live.
+                        Default: number of CPUs
+
+EXEC
+
+  exec-dir.<repo>       Relative path. Subdirectory of <repo> where `wsp exec`
+                        runs commands instead of the repo root. Useful for
+                        monorepos (e.g., `services/api`). A workspace's own
+                        setting wins over the global one; override per run
+                        with `wsp exec --dir <repo>=<subdir>`.
+                        Example: `wsp config set exec-dir.api-gateway services/api`
+
+REPO DEPENDENCIES
+
+  depends-on.<repo>     Comma-separated repos that <repo> depends on. `wsp
+                        exec`, `wsp sync`, and `wsp run` process a repo after
+                        the repos it depends on; a cycle is an error. Set
+                        globally (registered repos) or per workspace.
+                        Example: `wsp config set depends-on.api-gateway proto`
+
+HOOKS
+
+  hook.<event>          Command run through the shell in the workspace root at
+                        a lifecycle event: post-create (wsp new), pre-remove
+                        (wsp rm), post-add (wsp repo add), post-sync (wsp sync).
+                        Gets WSP_WORKSPACE, WSP_DIR, WSP_REPO_<NAME>, WSP_HOOK,
+                        and WSP_HOOK_REPOS (added repos for post-add). Timeout
+                        and failure policy live in YAML:
+
+                          hooks:
+                            pre-remove:
+                              run: ./scripts/teardown.sh
+                              timeout: 30        # seconds, default 300
+                              on_failure: abort  # default: warn
+
+                        `abort` fails the command (pre-remove keeps the
+                        workspace); `warn` prints a warning and continues.
+                        Global only: templates and .wsp.yaml can't set hooks.
+                        Example: `wsp config set hook.post-create ./bootstrap.sh`
+
+REPO SETUP
+
+  setup.<repo>          Command run through the shell in each new clone of
+                        <repo> by `wsp new` and `wsp repo add` (e.g. `npm ci`),
+                        so fresh workspaces are ready to build. Repos set up
+                        in parallel; a failure is reported with the tail of its
+                        output but never fails the command. Skip with
+                        --no-setup. Global-only.
+                        Example: `wsp config set setup.web-app \"npm ci\"`
+
+SHARED FILES
+
+  file.<path>           Source file rendered to <path> in the root of each new
+                        workspace (e.g. .editorconfig, a Makefile that fans out
+                        to repos). Sources are absolute, ~/..., or relative to
+                        the wsp data directory. Placeholders: {{workspace}},
+                        {{branch}}, {{dir}}, {{repos}} (repo directories,
+                        space-separated), {{repo.<name>}} (a repo's path).
+                        Global-only; templates can add their own.
+                        Example: `wsp config set file.Makefile ~/dotfiles/ws.mk`
+
+FORGES
+
+  forge.<host>          github or gitlab. Which service hosts repos on <host>,
+                        for `wsp pr`. Without it, hosts with a `gitlab` label
+                        (gitlab.com, gitlab.acme.com) are GitLab and the rest
+                        GitHub. Global-only.
+                        Example: `wsp config set forge.git.acme.com gitlab`
+
+  url-rewrite.<prefix>  URL. Fetch and push repos whose URL starts with
+                        <prefix> through this URL instead, like git's
+                        url.<base>.insteadOf: for company mirrors and proxies.
+                        Applied to every git command wsp runs and set in
+                        clones' git config. Global-only.
+                        Example: `wsp config set url-rewrite.https://github.com/
+                        https://gh-proxy.acme.com/`
+
+  host.<host>.proxy     URL. Proxy for HTTPS remotes on <host>, e.g. a GitHub
+                        Enterprise instance only reachable through one. Empty
+                        for a direct connection. Global-only.
+                        Example: `wsp config set host.ghe.acme.com.proxy
+                        http://proxy.acme.com:3128`
+
+  host.<host>.timeout   Integer. Seconds a transfer from <host> may stall
+                        before git gives up on it. Global-only.
+
+  host.<host>.http.<key>
+                        Any other git http.<key> option for <host>, e.g.
+                        `host.ghe.acme.com.http.sslCAInfo /etc/ssl/acme.pem`.
+                        Global-only.
+
+                        host.* settings reach HTTPS remotes only: repos
+                        registered over SSH get nothing from them, and
+                        `wsp config doctor` warns about them. Set SSH
+                        options in ~/.ssh/config.
+
+  Host settings are git's URL-matched http.https://<host>.* config: passed to
+  every git command wsp runs and set in clones' git config.
+
 LANGUAGE INTEGRATIONS
 
   lang.<name>           Boolean. Enable/disable per-language workspace support.
-                        Available: go (generates go.work for multi-module repos).
+                        Available: go (generates go.work for multi-module repos),
+                        dotenv (merges each repo's .env.workspace or
+                        .env.example into a workspace .env; the first repo to
+                        set a variable wins, duplicates stay as comments),
+                        devcontainer (generates .devcontainer/devcontainer.json
+                        for the whole workspace, merging repos' features),
+                        nix (generates flake.nix and shell.nix combining each
+                        repo's flake.nix or shell.nix dev shell),
+                        jetbrains (generates an .idea/ project with each repo
+                        as a content root and Git mapping),
+                        direnv (generates an .envrc exporting WSP_* variables
+                        and sourcing each repo's .envrc.workspace).
                         Default: false
 
 EXAMPLES
@@ -195,8 +433,15 @@ EXAMPLES
   wsp config set gc.retention-days 30             # keep deleted workspaces 30 days
   wsp config set git.merge.conflictstyle zdiff3         # workspace or global
   wsp config set shell.prompt true                      # enable prompt variable (global)
+  wsp config set exec-dir.api-gateway services/api      # exec in a subdirectory
+  wsp config set task.test \"make test\"                  # define a task for wsp run
+  wsp config set hook.post-sync \"make deps\"             # run after every wsp sync
+  wsp config set setup.web-app \"npm ci\"                # bootstrap new clones (global)
+  wsp config set file..editorconfig ~/.editorconfig     # copy into new workspaces (global)
   wsp config unset sync-strategy                  # unset workspace override
   wsp config unset --global branch-prefix         # revert global to default
+  wsp config doctor                               # report every config.yaml problem
+  wsp config edit                                 # hand-edit config.yaml, saved if valid
 ",
     ),
 ];
@@ -262,9 +507,13 @@ pub fn cmd() -> Command {
         )
 }
 
-pub fn run(matches: &clap::ArgMatches, cli: &mut Command, json: bool) -> anyhow::Result<Output> {
+pub fn run(
+    matches: &clap::ArgMatches,
+    cli: &mut Command,
+    format: Format,
+) -> anyhow::Result<Output> {
     if matches.get_flag("guides") {
-        if json {
+        if format.is_structured() {
             let out = HelpTopicListOutput {
                 topics: TOPICS
                     .iter()
@@ -274,7 +523,7 @@ pub fn run(matches: &clap::ArgMatches, cli: &mut Command, json: bool) -> anyhow:
                     })
                     .collect(),
             };
-            println!("{}", serde_json::to_string_pretty(&out)?);
+            print_structured(&out, format, Some("topics"))?;
         } else {
             println!("Available guides:\n");
             for (name, desc, _) in TOPICS {
@@ -300,13 +549,13 @@ pub fn run(matches: &clap::ArgMatches, cli: &mut Command, json: bool) -> anyhow:
     // Check built-in topics first
     for (name, summary, text) in TOPICS {
         if *name == topic.as_str() {
-            if json {
+            if format.is_structured() {
                 let out = HelpTopicOutput {
                     name: name.to_string(),
                     summary: summary.to_string(),
                     text: text.to_string(),
                 };
-                println!("{}", serde_json::to_string_pretty(&out)?);
+                print_structured(&out, format, None)?;
             } else {
                 print!("{}", text);
             }
@@ -339,6 +588,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_config_env_vars_match_overrides() {
+        let (_, _, text) = TOPICS
+            .iter()
+            .find(|(name, _, _)| *name == "config")
+            .unwrap();
+        let start = text.find("Most global settings").unwrap();
+        let end = start + text[start..].find("Empty variables").unwrap();
+        let listed: Vec<&str> = text[start..end]
+            .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .filter(|w| w.strip_prefix("WSP_").is_some_and(|rest| !rest.is_empty()))
+            .collect();
+        let known: Vec<&str> = crate::config::ENV_OVERRIDES
+            .iter()
+            .map(|(_, var)| *var)
+            .collect();
+        assert_eq!(listed, known);
+    }
+
     #[test]
     fn test_topic_lookup() {
         let found = TOPICS.iter().find(|(name, _, _)| *name == "wspignore");
@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use clap_complete::engine::ArgValueCandidates;
+
+use crate::config::Paths;
+use crate::error;
+use crate::gc;
+use crate::git;
+use crate::giturl;
+use crate::mirror;
+use crate::output::{InfoOutput, InfoRepoEntry, Output};
+use crate::workspace;
+
+use super::completers;
+
+pub fn cmd() -> Command {
+    Command::new("info")
+        .about("Show everything about a workspace [read-only]")
+        .long_about(
+            "Show everything about a workspace [read-only].\n\n\
+             Prints the workspace metadata and, for each repo, its identity, directory, \
+             role, configured ref, checked-out branch and commit, upstream with \
+             ahead/behind counts, origin URL, and mirror path. Defaults to the current \
+             workspace.",
+        )
+        .arg(Arg::new("workspace").add(ArgValueCandidates::new(completers::complete_workspaces)))
+}
+
+pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    let ws_dir: PathBuf = match matches.get_one::<String>("workspace") {
+        Some(name) => paths.workspace_dir(name),
+        None => workspace::detect(&std::env::current_dir()?)?,
+    };
+
+    gc::check_workspace(&ws_dir, /* read_only */ true)?;
+
+    let meta =
+        workspace::load_metadata(&ws_dir).map_err(|e| error::prefixed(e, "reading workspace"))?;
+
+    let repos = meta
+        .repos
+        .iter()
+        .map(|(identity, repo_ref)| {
+            let mut entry = InfoRepoEntry {
+                identity: identity.clone(),
+                dir_name: String::new(),
+                path: String::new(),
+                role: "active".into(),
+                r#ref: repo_ref
+                    .as_ref()
+                    .map(|r| r.r#ref.clone())
+                    .filter(|r| !r.is_empty()),
+                branch: None,
+                sha: None,
+                upstream: None,
+                has_upstream: false,
+                ahead: 0,
+                behind: 0,
+                origin_url: None,
+                mirror: None,
+                mirror_exists: false,
+                error: None,
+            };
+            if let Ok(parsed) = giturl::Parsed::from_identity(identity) {
+                let mirror_dir = mirror::dir(&paths.mirrors_dir, &parsed);
+                entry.mirror_exists = mirror_dir.exists();
+                entry.mirror = Some(mirror_dir.display().to_string());
+            }
+            let dir_name = match meta.dir_name(identity) {
+                Ok(d) => d,
+                Err(e) => {
+                    entry.error = Some(e.to_string());
+                    return entry;
+                }
+            };
+            let clone_dir = ws_dir.join(&dir_name);
+            entry.dir_name = dir_name;
+            entry.path = clone_dir.display().to_string();
+            if !clone_dir.exists() {
+                entry.error = Some("clone directory is missing".into());
+                return entry;
+            }
+
+            entry.branch = git::branch_current(&clone_dir).ok();
+            entry.sha = git::head_sha(&clone_dir).ok();
+            entry.origin_url = git::remote_get_url(&clone_dir, "origin").ok();
+            let upstream = git::resolve_upstream_ref(&clone_dir);
+            entry.has_upstream = matches!(upstream, git::UpstreamRef::Tracking);
+            entry.upstream = match &upstream {
+                git::UpstreamRef::Tracking => git::upstream_name(&clone_dir).ok(),
+                git::UpstreamRef::DefaultBranch(b) => Some(format!("origin/{}", b)),
+                git::UpstreamRef::Head => None,
+            };
+            entry.ahead = git::ahead_count_from(&clone_dir, &upstream).unwrap_or(0);
+            entry.behind = git::behind_count_from(&clone_dir, &upstream).unwrap_or(0);
+            entry
+        })
+        .collect();
+
+    Ok(Output::Info(InfoOutput {
+        workspace: meta.name,
+        branch: meta.branch,
+        workspace_dir: ws_dir,
+        description: meta.description,
+        issue: meta.issue,
+        created: meta.created,
+        last_used: meta.last_used,
+        created_from: meta.created_from,
+        repos,
+        pending: meta.pending,
+    }))
+}
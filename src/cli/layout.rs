@@ -0,0 +1,104 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use clap_complete::engine::ArgValueCandidates;
+
+use crate::error;
+use crate::output::{LayoutOutput, LayoutPane, Output};
+use crate::workspace;
+
+use super::completers;
+
+pub fn cmd() -> Command {
+    Command::new("layout")
+        .about("Print a tmuxinator or zellij layout for a workspace [read-only]")
+        .long_about(
+            "Print a tmuxinator or zellij layout for a workspace.\n\n\
+             The layout opens one window (tab) named after the workspace with a pane per \
+             repo, each started in that repo's directory, using the same directory names \
+             as the workspace (e.g. `owner-repo` when two repos share a name). Save the \
+             output where your tool looks for it, e.g. `wsp layout tmux > ~/.config/tmuxinator/my-ws.yml` then \
+             `tmuxinator start my-ws`, or `wsp layout zellij > my-ws.kdl` then \
+             `zellij --layout my-ws.kdl`.\n\n\
+             The workspace name is optional when running from inside a workspace directory.",
+        )
+        .arg(
+            Arg::new("kind")
+                .required(true)
+                .value_parser(["tmux", "zellij"])
+                .help("Layout format: tmux (tmuxinator YAML) or zellij (KDL)"),
+        )
+        .arg(
+            Arg::new("workspace")
+                .required(false)
+                .add(ArgValueCandidates::new(completers::complete_workspaces)),
+        )
+}
+
+pub fn run(matches: &ArgMatches, paths: &crate::config::Paths) -> Result<Output> {
+    let kind = matches.get_one::<String>("kind").unwrap().clone();
+    let ws_dir: PathBuf = if let Some(name) = matches.get_one::<String>("workspace") {
+        paths.workspace_dir(name)
+    } else {
+        let cwd = std::env::current_dir()?;
+        workspace::detect(&cwd)?
+    };
+    let meta =
+        workspace::load_metadata(&ws_dir).map_err(|e| error::prefixed(e, "reading workspace"))?;
+
+    let mut panes = Vec::new();
+    for identity in meta.repos.keys() {
+        let directory = meta.dir_name(identity)?;
+        panes.push(LayoutPane {
+            identity: identity.clone(),
+            path: ws_dir.join(&directory).to_string_lossy().into_owned(),
+            directory,
+        });
+    }
+
+    Ok(Output::Layout(LayoutOutput {
+        workspace: meta.name,
+        path: ws_dir.to_string_lossy().into_owned(),
+        panes,
+        kind,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_args() {
+        let cases = vec![
+            ("tmux", vec!["layout", "tmux"], "tmux", None),
+            (
+                "zellij with workspace",
+                vec!["layout", "zellij", "my-ws"],
+                "zellij",
+                Some("my-ws"),
+            ),
+        ];
+        for (name, args, want_kind, want_ws) in cases {
+            let m = cmd().get_matches_from(args);
+            assert_eq!(
+                m.get_one::<String>("kind").map(|s| s.as_str()),
+                Some(want_kind),
+                "{}",
+                name
+            );
+            assert_eq!(
+                m.get_one::<String>("workspace").map(|s| s.as_str()),
+                want_ws,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn parse_args_rejects_unknown_kind() {
+        assert!(cmd().try_get_matches_from(["layout", "screen"]).is_err());
+    }
+}
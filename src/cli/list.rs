@@ -1,10 +1,21 @@
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+
 use anyhow::Result;
 use clap::{Arg, ArgMatches, Command};
+use clap_complete::engine::ArgValueCandidates;
 
 use crate::config::Paths;
-use crate::output::{Output, WorkspaceListEntry, WorkspaceListOutput};
+use crate::du;
+use crate::output::{
+    self, Format, Output, WorkspaceListEntry, WorkspaceListOutput, WorkspaceStatusSummary,
+};
+use crate::statuscache;
+use crate::util;
 use crate::workspace;
 
+use super::completers;
+
 pub fn cmd() -> Command {
     Command::new("ls")
         .visible_alias("list")
@@ -13,19 +24,52 @@ pub fn cmd() -> Command {
             "List active workspaces [read-only].\n\n\
              Shows all workspaces under the workspaces directory, with their branch, repo \
              count, and description. Supports sorting by name (default), last-used time, \
-             or creation date.",
+             creation date, or repo count, and narrowing the list by name or by a repo \
+             the workspace contains:\n\n  \
+             wsp ls --sort recent              # most recently entered first\n  \
+             wsp ls --filter billing           # name, branch, description, or issue matches\n  \
+             wsp ls --repo user-service        # workspaces containing user-service\n\n\
+             With --status, also checks every repo (in parallel across workspaces) and \
+             summarizes unfinished work per workspace, e.g. \"2 dirty, 5 ahead\": repos \
+             with uncommitted changes and commits not yet pushed. Clones unchanged \
+             since their last check reuse the cached counts.\n\n\
+             With --du, also measures each workspace's size on disk and prints a total. \
+             Sizes are cached for a few minutes, so repeated listings are cheap.\n\n\
+             Workspace metadata is read in parallel, and with --status each row prints \
+             as soon as its workspace is checked. --limit and --page split a long list \
+             into pages, with the unpaged count in the JSON output's total.",
+        )
+        .arg(
+            Arg::new("status")
+                .short('s')
+                .long("status")
+                .action(clap::ArgAction::SetTrue)
+                .help("Show dirty/ahead counts per workspace"),
+        )
+        .arg(
+            Arg::new("du")
+                .long("du")
+                .action(clap::ArgAction::SetTrue)
+                .help("Show disk usage per workspace and in total"),
+        )
+        .arg(
+            Arg::new("sort-by")
+                .long("sort")
+                .value_name("KEY")
+                .value_parser(["name", "created", "recent", "repos"])
+                .help("Sort by name, created (newest first), recent (last used), or repos (most first)"),
         )
         .arg(
             Arg::new("time")
                 .short('t')
                 .action(clap::ArgAction::SetTrue)
-                .help("Sort by last used, newest first (falls back to created)"),
+                .help("Sort by last used, newest first (falls back to created); same as --sort recent"),
         )
         .arg(
             Arg::new("creation")
                 .short('U')
                 .action(clap::ArgAction::SetTrue)
-                .help("Sort by creation date, newest first"),
+                .help("Sort by creation date, newest first; same as --sort created"),
         )
         .arg(
             Arg::new("reverse")
@@ -33,113 +77,531 @@ pub fn cmd() -> Command {
                 .action(clap::ArgAction::SetTrue)
                 .help("Reverse sort order"),
         )
+        .arg(
+            Arg::new("filter")
+                .long("filter")
+                .value_name("TEXT")
+                .help("Only workspaces whose name, branch, description, or issue contains TEXT"),
+        )
+        .arg(
+            Arg::new("repo")
+                .long("repo")
+                .value_name("REPO")
+                .help("Only workspaces containing this repo (identity, shortname, or dir name)")
+                .add(ArgValueCandidates::new(completers::complete_repos)),
+        )
+        .arg(
+            Arg::new("limit")
+                .long("limit")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .help("Show at most N workspaces"),
+        )
+        .arg(
+            Arg::new("page")
+                .long("page")
+                .value_name("P")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .requires("limit")
+                .help("Show the Pth page of --limit workspaces [default: 1]"),
+        )
         .group(
             clap::ArgGroup::new("sort")
-                .args(["time", "creation"])
+                .args(["sort-by", "time", "creation"])
                 .required(false),
         )
 }
 
+/// Order of `wsp ls`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SortKey {
+    Name,
+    Created,
+    Recent,
+    Repos,
+}
+
+fn sort_key(matches: &ArgMatches) -> SortKey {
+    let flag = |id| {
+        matches
+            .try_get_one::<bool>(id)
+            .ok()
+            .flatten()
+            .copied()
+            .unwrap_or(false)
+    };
+    if flag("time") {
+        return SortKey::Recent;
+    }
+    if flag("creation") {
+        return SortKey::Created;
+    }
+    match matches
+        .try_get_one::<String>("sort-by")
+        .ok()
+        .flatten()
+        .map(|s| s.as_str())
+    {
+        Some("created") => SortKey::Created,
+        Some("recent") => SortKey::Recent,
+        Some("repos") => SortKey::Repos,
+        _ => SortKey::Name,
+    }
+}
+
 pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
-    let sort_time = matches
-        .try_get_one::<bool>("time")
+    let sort = sort_key(matches);
+    let reverse = matches
+        .try_get_one::<bool>("reverse")
         .ok()
         .flatten()
         .copied()
         .unwrap_or(false);
-    let sort_created = matches
-        .try_get_one::<bool>("creation")
+
+    let with_status = matches
+        .try_get_one::<bool>("status")
         .ok()
         .flatten()
         .copied()
         .unwrap_or(false);
-    let reverse = matches
-        .try_get_one::<bool>("reverse")
+
+    let with_du = matches
+        .try_get_one::<bool>("du")
         .ok()
         .flatten()
         .copied()
         .unwrap_or(false);
 
-    let names = workspace::list_all(&paths.workspaces_dir)?;
-
-    let mut workspaces = Vec::new();
-    for name in &names {
-        let ws_dir = workspace::dir(&paths.workspaces_dir, name);
-        let meta = match workspace::load_metadata(&ws_dir) {
-            Ok(m) => m,
-            Err(_) => {
-                workspaces.push(WorkspaceListEntry {
-                    name: name.clone(),
-                    branch: "ERROR".to_string(),
-                    repo_count: 0,
-                    path: ws_dir.display().to_string(),
-                    description: None,
-                    created: String::new(),
-                    last_used: None,
-                    created_from: None,
-                });
-                continue;
-            }
-        };
-        workspaces.push(WorkspaceListEntry {
-            name: name.clone(),
-            branch: meta.branch,
-            repo_count: meta.repos.len(),
-            path: ws_dir.display().to_string(),
-            description: meta.description,
-            created: meta.created.to_rfc3339(),
-            last_used: None,
-            created_from: meta.created_from,
-        });
-    }
-
-    // Sort by requested criteria
-    if sort_time || sort_created {
-        // Both -t and -U sort by timestamp. -t uses last_used with created fallback;
-        // -U uses created directly. Since last_used is not yet tracked, both
-        // currently sort by created.
-        workspaces.sort_by(|a, b| {
-            let ts_a = if sort_time {
-                a.last_used.as_deref().unwrap_or(&a.created)
-            } else {
-                &a.created
-            };
-            let ts_b = if sort_time {
-                b.last_used.as_deref().unwrap_or(&b.created)
-            } else {
-                &b.created
-            };
-            // Newest first (reverse chronological)
-            ts_b.cmp(ts_a)
-        });
+    let filter = matches
+        .try_get_one::<String>("filter")
+        .ok()
+        .flatten()
+        .map(|s| s.to_lowercase());
+    let repo = matches.try_get_one::<String>("repo").ok().flatten();
+
+    let limit = matches.try_get_one::<u64>("limit").ok().flatten();
+    let page = matches
+        .try_get_one::<u64>("page")
+        .ok()
+        .flatten()
+        .copied()
+        .unwrap_or(1);
+
+    let names = workspace::list_all(paths)?;
+    let jobs = std::thread::available_parallelism().map_or(1, |n| n.get());
+
+    // With hundreds of workspaces, reading their metadata one at a time is
+    // most of what `wsp ls` spends.
+    let mut workspaces: Vec<WorkspaceListEntry> = util::run_bounded(
+        &names,
+        jobs,
+        |name| list_entry(paths, name, repo.map(String::as_str)),
+        None,
+    )
+    .into_iter()
+    .flatten()
+    .collect();
+
+    if let Some(filter) = &filter {
+        workspaces.retain(|ws| matches_filter(ws, filter));
     }
 
+    sort_entries(&mut workspaces, sort);
+
     if reverse {
         workspaces.reverse();
     }
 
+    let total = limit.map(|_| workspaces.len());
+    if let Some(&limit) = limit {
+        workspaces = paginate(workspaces, limit, page);
+    }
+
+    let mut total_disk_bytes = None;
+    if with_du {
+        let dirs: Vec<(PathBuf, String)> = workspaces
+            .iter()
+            .map(|ws| (PathBuf::from(&ws.path), ws.created.clone()))
+            .collect();
+        let sizes = du::workspace_sizes(paths.data_dir(), &dirs);
+        for (ws, bytes) in workspaces.iter_mut().zip(&sizes) {
+            ws.disk_bytes = Some(*bytes);
+        }
+        total_disk_bytes = Some(sizes.iter().sum());
+    }
+
+    // Checking repos is the slow part: table rows print as their
+    // workspace is done, in order.
+    let stream =
+        with_status && !workspaces.is_empty() && Format::from_matches(matches) == Format::Table;
+    if with_status {
+        let cache = statuscache::load(paths.data_dir());
+        let indexes: Vec<usize> = (0..workspaces.len()).collect();
+        let now = chrono::Utc::now().timestamp();
+        let mut rows = None;
+        // A closed stdout (`wsp ls --status | head -1`) stops the checks.
+        let mut closed = stream
+            && output::keep_streaming(
+                stream_rows(&workspaces, with_du, now).map(|r| rows = Some(r)),
+            )
+            .is_break();
+        let checked = if closed {
+            Vec::new()
+        } else {
+            let mut emit = |(i, checked): &(usize, Option<CheckedStatus>)| {
+                let Some(rows) = &rows else {
+                    return ControlFlow::Continue(());
+                };
+                let mut row = output::workspace_list_row(&workspaces[*i], true, with_du, now);
+                row[3] = checked
+                    .as_ref()
+                    .map(|(summary, _)| summary.summary())
+                    .unwrap_or_default();
+                let flow = output::keep_streaming(rows.row(&row));
+                closed |= flow.is_break();
+                flow
+            };
+            util::run_bounded(
+                &indexes,
+                jobs,
+                |&i| (i, status_summary(Path::new(&workspaces[i].path), &cache)),
+                Some(&mut emit as util::Emit<'_, (usize, Option<CheckedStatus>)>),
+            )
+        };
+        let mut updates = Vec::new();
+        for (i, checked) in checked {
+            if let Some((summary, fresh)) = checked {
+                workspaces[i].status = Some(summary);
+                updates.extend(fresh);
+            }
+        }
+        statuscache::save(paths.data_dir(), cache, updates);
+        if closed {
+            return Ok(Output::None);
+        }
+    }
+
     Ok(Output::WorkspaceList(WorkspaceListOutput {
         hint: None,
         workspaces,
+        total_disk_bytes,
+        total,
+        streamed: stream,
     }))
 }
 
+/// A workspace's row, or `None` when it doesn't contain `repo`.
+fn list_entry(paths: &Paths, name: &str, repo: Option<&str>) -> Option<WorkspaceListEntry> {
+    let ws_dir = paths.workspace_dir(name);
+    let meta = match workspace::load_metadata(&ws_dir) {
+        Ok(m) => m,
+        // Unreadable workspaces can't be matched against a repo.
+        Err(_) if repo.is_some() => return None,
+        Err(_) => {
+            return Some(WorkspaceListEntry {
+                name: name.to_string(),
+                branch: "ERROR".to_string(),
+                repo_count: 0,
+                path: ws_dir.display().to_string(),
+                description: None,
+                issue: None,
+                created: String::new(),
+                last_used: None,
+                created_from: None,
+                status: None,
+                disk_bytes: None,
+            });
+        }
+    };
+    if let Some(repo) = repo
+        && meta.resolve_repo(repo).is_err()
+    {
+        return None;
+    }
+    Some(WorkspaceListEntry {
+        name: name.to_string(),
+        branch: meta.branch,
+        repo_count: meta.repos.len(),
+        path: ws_dir.display().to_string(),
+        description: meta.description,
+        issue: meta.issue,
+        created: meta.created.to_rfc3339(),
+        last_used: meta.last_used.map(|t| t.to_rfc3339()),
+        created_from: meta.created_from,
+        status: None,
+        disk_bytes: None,
+    })
+}
+
+/// Room kept for the status column when rows print before every summary
+/// is known. Fits "1 dirty, 12 ahead, 1 unchecked" and most others.
+const STREAMED_STATUS_WIDTH: usize = 20;
+
+/// Prints the header of a `wsp ls --status` table whose rows come one at
+/// a time. Every column but the status is as wide as its widest cell.
+fn stream_rows(
+    workspaces: &[WorkspaceListEntry],
+    with_du: bool,
+    now: i64,
+) -> std::io::Result<output::RowStream> {
+    let headers = output::workspace_list_headers(true, with_du);
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for ws in workspaces {
+        for (w, cell) in widths
+            .iter_mut()
+            .zip(output::workspace_list_row(ws, true, with_du, now))
+        {
+            *w = (*w).max(cell.chars().count());
+        }
+    }
+    widths[3] = widths[3].max(STREAMED_STATUS_WIDTH);
+    output::RowStream::new(&headers, widths)
+}
+
+/// The `page`th run of `limit` entries, counting from 1; empty past the end.
+fn paginate(entries: Vec<WorkspaceListEntry>, limit: u64, page: u64) -> Vec<WorkspaceListEntry> {
+    let skip = page.saturating_sub(1).saturating_mul(limit);
+    entries
+        .into_iter()
+        .skip(usize::try_from(skip).unwrap_or(usize::MAX))
+        .take(usize::try_from(limit).unwrap_or(usize::MAX))
+        .collect()
+}
+
+/// Case-insensitive substring match on name, branch, description, and
+/// issue. `filter` must already be lowercase.
+fn matches_filter(ws: &WorkspaceListEntry, filter: &str) -> bool {
+    [
+        Some(&ws.name),
+        Some(&ws.branch),
+        ws.description.as_ref(),
+        ws.issue.as_ref(),
+    ]
+    .into_iter()
+    .flatten()
+    .any(|field| field.to_lowercase().contains(filter))
+}
+
+/// Names arrive sorted from `list_all`; the sorts are stable, so ties keep
+/// name order. Timestamps are RFC 3339, so they compare as strings, and an
+/// empty `created` (unreadable metadata) sorts last.
+fn sort_entries(entries: &mut [WorkspaceListEntry], key: SortKey) {
+    match key {
+        SortKey::Name => entries.sort_by(|a, b| a.name.cmp(&b.name)),
+        SortKey::Created => entries.sort_by(|a, b| b.created.cmp(&a.created)),
+        // last_used is set by `wsp cd`; fall back to created.
+        SortKey::Recent => entries.sort_by(|a, b| {
+            let ts = |e: &WorkspaceListEntry| e.last_used.clone().unwrap_or(e.created.clone());
+            ts(b).cmp(&ts(a))
+        }),
+        SortKey::Repos => entries.sort_by_key(|e| std::cmp::Reverse(e.repo_count)),
+    }
+}
+
+/// A workspace's summary and the status cache entries to store.
+type CheckedStatus = (WorkspaceStatusSummary, Vec<(PathBuf, statuscache::Entry)>);
+
+/// Check every repo in the workspace, reusing cached counts for clones
+/// that haven't changed. Returns the summary and the cache entries to
+/// store; `None` when the metadata can't be read.
+fn status_summary(ws_dir: &Path, cache: &statuscache::Cache) -> Option<CheckedStatus> {
+    let meta = workspace::load_metadata(ws_dir).ok()?;
+    let mut fresh = Vec::new();
+    let repos: Vec<Option<(u32, u32)>> = meta
+        .repos
+        .keys()
+        .map(|identity| {
+            let clone_dir = ws_dir.join(meta.dir_name(identity).ok()?);
+            let (counts, entry) = statuscache::counts(&clone_dir, cache.entry(&clone_dir))?;
+            fresh.extend(entry.map(|e| (clone_dir, e)));
+            Some(counts)
+        })
+        .collect();
+    Some((summarize(&repos), fresh))
+}
+
+/// Fold per-repo `(changed files, commits ahead)` into a summary; `None`
+/// entries are repos that couldn't be checked.
+pub fn summarize(repos: &[Option<(u32, u32)>]) -> WorkspaceStatusSummary {
+    let mut summary = WorkspaceStatusSummary::default();
+    for repo in repos {
+        match repo {
+            Some((changed, ahead)) => {
+                if *changed > 0 {
+                    summary.dirty += 1;
+                }
+                summary.ahead += ahead;
+            }
+            None => summary.errors += 1,
+        }
+    }
+    summary
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_summarize() {
+        let summary = |dirty, ahead, errors| WorkspaceStatusSummary {
+            dirty,
+            ahead,
+            errors,
+        };
+        let cases = vec![
+            ("no repos", vec![], summary(0, 0, 0), "clean"),
+            (
+                "clean",
+                vec![Some((0, 0)), Some((0, 0))],
+                summary(0, 0, 0),
+                "clean",
+            ),
+            (
+                "dirty and ahead",
+                vec![Some((3, 2)), Some((1, 0)), Some((0, 3))],
+                summary(2, 5, 0),
+                "2 dirty, 5 ahead",
+            ),
+            (
+                "unchecked",
+                vec![Some((0, 1)), None],
+                summary(0, 1, 1),
+                "1 ahead, 1 unchecked",
+            ),
+        ];
+        for (name, repos, want, text) in cases {
+            let got = summarize(&repos);
+            assert_eq!(got, want, "{}", name);
+            assert_eq!(got.summary(), text, "{}", name);
+        }
+    }
+
+    fn entry(
+        name: &str,
+        repos: usize,
+        created: &str,
+        last_used: Option<&str>,
+    ) -> WorkspaceListEntry {
+        WorkspaceListEntry {
+            name: name.into(),
+            branch: name.into(),
+            repo_count: repos,
+            path: format!("/ws/{}", name),
+            description: None,
+            issue: None,
+            created: created.into(),
+            last_used: last_used.map(String::from),
+            created_from: None,
+            status: None,
+            disk_bytes: None,
+        }
+    }
+
+    #[test]
+    fn test_paginate() {
+        let entries = || {
+            ["a", "b", "c", "d", "e"]
+                .into_iter()
+                .map(|n| entry(n, 1, "2026-01-01T00:00:00+00:00", None))
+                .collect::<Vec<_>>()
+        };
+        let cases = vec![
+            (2, 1, vec!["a", "b"]),
+            (2, 3, vec!["e"]),
+            (2, 4, vec![]),
+            (10, 1, vec!["a", "b", "c", "d", "e"]),
+            (1, u64::MAX, vec![]),
+        ];
+        for (limit, page, want) in cases {
+            let got: Vec<String> = paginate(entries(), limit, page)
+                .into_iter()
+                .map(|e| e.name)
+                .collect();
+            assert_eq!(got, want, "limit {} page {}", limit, page);
+        }
+    }
+
+    #[test]
+    fn test_sort_entries() {
+        let entries = || {
+            vec![
+                entry(
+                    "alpha",
+                    1,
+                    "2026-01-01T00:00:00+00:00",
+                    Some("2026-04-01T00:00:00+00:00"),
+                ),
+                entry("broken", 0, "", None),
+                entry("gamma", 3, "2026-03-01T00:00:00+00:00", None),
+                entry("beta", 3, "2026-02-01T00:00:00+00:00", None),
+            ]
+        };
+        let cases = vec![
+            (SortKey::Name, vec!["alpha", "beta", "broken", "gamma"]),
+            (SortKey::Created, vec!["gamma", "beta", "alpha", "broken"]),
+            (SortKey::Recent, vec!["alpha", "gamma", "beta", "broken"]),
+            (SortKey::Repos, vec!["gamma", "beta", "alpha", "broken"]),
+        ];
+        for (key, want) in cases {
+            let mut got = entries();
+            sort_entries(&mut got, key);
+            let names: Vec<&str> = got.iter().map(|e| e.name.as_str()).collect();
+            assert_eq!(names, want, "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn test_sort_key() {
+        let cases = vec![
+            (vec!["ls"], SortKey::Name),
+            (vec!["ls", "--sort", "repos"], SortKey::Repos),
+            (vec!["ls", "--sort", "recent"], SortKey::Recent),
+            (vec!["ls", "-t"], SortKey::Recent),
+            (vec!["ls", "-U"], SortKey::Created),
+        ];
+        for (args, want) in cases {
+            let m = cmd().try_get_matches_from(&args).unwrap();
+            assert_eq!(sort_key(&m), want, "{:?}", args);
+        }
+        assert!(
+            cmd()
+                .try_get_matches_from(["ls", "-t", "--sort", "name"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_matches_filter() {
+        let mut ws = entry("add-billing", 2, "", None);
+        ws.branch = "me/add-billing".into();
+        ws.description = Some("Stripe v3 migration".into());
+        let cases = vec![
+            ("bill", true),
+            ("me/", true),
+            ("stripe", true),
+            ("auth", false),
+        ];
+        for (filter, want) in cases {
+            assert_eq!(matches_filter(&ws, filter), want, "{}", filter);
+        }
+    }
+
     #[test]
     fn test_sort_by_created() {
-        let mut entries = vec![
+        let mut entries = [
             WorkspaceListEntry {
                 name: "old".into(),
                 branch: "old".into(),
                 repo_count: 1,
                 path: "/ws/old".into(),
                 description: None,
+                issue: None,
                 created: "2026-01-01T00:00:00+00:00".into(),
                 last_used: None,
                 created_from: None,
+                status: None,
+                disk_bytes: None,
             },
             WorkspaceListEntry {
                 name: "new".into(),
@@ -147,9 +609,12 @@ mod tests {
                 repo_count: 1,
                 path: "/ws/new".into(),
                 description: None,
+                issue: None,
                 created: "2026-03-01T00:00:00+00:00".into(),
                 last_used: None,
                 created_from: None,
+                status: None,
+                disk_bytes: None,
             },
             WorkspaceListEntry {
                 name: "mid".into(),
@@ -157,9 +622,12 @@ mod tests {
                 repo_count: 1,
                 path: "/ws/mid".into(),
                 description: None,
+                issue: None,
                 created: "2026-02-01T00:00:00+00:00".into(),
                 last_used: None,
                 created_from: None,
+                status: None,
+                disk_bytes: None,
             },
         ];
 
@@ -176,16 +644,19 @@ mod tests {
 
     #[test]
     fn test_sort_empty_created_sorts_last() {
-        let mut entries = vec![
+        let mut entries = [
             WorkspaceListEntry {
                 name: "error-ws".into(),
                 branch: "ERROR".into(),
                 repo_count: 0,
                 path: "/ws/error".into(),
                 description: None,
+                issue: None,
                 created: String::new(),
                 last_used: None,
                 created_from: None,
+                status: None,
+                disk_bytes: None,
             },
             WorkspaceListEntry {
                 name: "good".into(),
@@ -193,9 +664,12 @@ mod tests {
                 repo_count: 1,
                 path: "/ws/good".into(),
                 description: None,
+                issue: None,
                 created: "2026-03-01T00:00:00+00:00".into(),
                 last_used: None,
                 created_from: None,
+                status: None,
+                disk_bytes: None,
             },
         ];
 
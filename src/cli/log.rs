@@ -1,4 +1,3 @@
-use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
@@ -7,9 +6,10 @@ use clap_complete::engine::ArgValueCandidates;
 
 use super::completers;
 use crate::config::Paths;
+use crate::error;
 use crate::gc;
 use crate::git;
-use crate::output::{LogCommit, LogOutput, Output, RepoLogEntry};
+use crate::output::{self, Format, LogCommit, LogOutput, Output, RepoLogEntry};
 use crate::workspace;
 
 pub fn cmd() -> Command {
@@ -38,7 +38,7 @@ pub fn cmd() -> Command {
 
 pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     let ws_dir: PathBuf = if let Some(name) = matches.get_one::<String>("workspace") {
-        workspace::dir(&paths.workspaces_dir, name)
+        paths.workspace_dir(name)
     } else {
         let cwd = std::env::current_dir()?;
         workspace::detect(&cwd)?
@@ -46,17 +46,17 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
 
     gc::check_workspace(&ws_dir, /* read_only */ true)?;
 
-    let meta = workspace::load_metadata(&ws_dir)
-        .map_err(|e| anyhow::anyhow!("reading workspace: {}", e))?;
+    let meta =
+        workspace::load_metadata(&ws_dir).map_err(|e| error::prefixed(e, "reading workspace"))?;
 
     let extra_args: Vec<&str> = matches
         .get_many::<String>("args")
         .map(|vals| vals.map(|s| s.as_str()).collect())
         .unwrap_or_default();
 
-    let is_json = matches.get_flag("json");
+    let is_json = Format::from_matches(matches).is_structured();
     let is_oneline = matches.get_flag("oneline");
-    let use_color = !is_json && !is_oneline && std::io::stdout().is_terminal();
+    let use_color = !is_json && !is_oneline && output::use_color(matches);
 
     let mut repos = Vec::new();
     for identity in meta.repos.keys() {
@@ -79,10 +79,7 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
 
         if !extra_args.is_empty() {
             // Pass-through mode: run git log with user-supplied args verbatim
-            let mut args = vec!["log"];
-            if use_color {
-                args.push("--color=always");
-            }
+            let mut args = vec!["log", output::git_color_arg(use_color)];
             args.extend(&extra_args);
 
             match git::run(Some(&repo_dir), &args) {
@@ -110,7 +107,7 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
         } else {
             // Structured mode: parse commits from upstream..HEAD
             match resolve_log_range(&repo_dir) {
-                Some(range) => match fetch_commits(&repo_dir, &range) {
+                Some(range) => match fetch_commits(&repo_dir, &[&range]) {
                     Ok(commits) => {
                         repos.push(RepoLogEntry {
                             identity: identity.clone(),
@@ -166,11 +163,20 @@ fn resolve_log_range(repo_dir: &Path) -> Option<String> {
     }
 }
 
-/// Run `git log --format=...` and parse each line into a LogCommit.
+/// Run `git log --format=... <revs>` and parse each line into a LogCommit.
 /// Uses NUL byte (%x00) as field separator to handle subjects with spaces
 /// or empty subjects without silent data loss.
-fn fetch_commits(repo_dir: &Path, range: &str) -> Result<Vec<LogCommit>> {
-    let output = git::run(Some(repo_dir), &["log", "--format=%H%x00%ct%x00%s", range])?;
+pub(crate) fn fetch_commits(repo_dir: &Path, revs: &[&str]) -> Result<Vec<LogCommit>> {
+    #[cfg(feature = "gitoxide")]
+    if let Some(entries) = crate::gitoxide::log(repo_dir, revs) {
+        return Ok(entries
+            .into_iter()
+            .map(|e| log_commit(e.hash, e.timestamp, e.subject))
+            .collect());
+    }
+    let mut args = vec!["log", "--format=%H%x00%ct%x00%s"];
+    args.extend(revs);
+    let output = git::run(Some(repo_dir), &args)?;
     if output.is_empty() {
         return Ok(vec![]);
     }
@@ -182,19 +188,27 @@ fn fetch_commits(repo_dir: &Path, range: &str) -> Result<Vec<LogCommit>> {
             continue;
         }
         let timestamp = parts[1].parse::<i64>().unwrap_or(0);
-        let authored_at = chrono::DateTime::from_timestamp(timestamp, 0)
-            .map(|dt| dt.to_rfc3339())
-            .unwrap_or_default();
-        commits.push(LogCommit {
-            hash: parts[0].to_string(),
-            authored_at,
+        commits.push(log_commit(
+            parts[0].to_string(),
             timestamp,
-            subject: parts[2].to_string(),
-        });
+            parts[2].to_string(),
+        ));
     }
     Ok(commits)
 }
 
+fn log_commit(hash: String, timestamp: i64, subject: String) -> LogCommit {
+    let authored_at = chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|dt| dt.to_rfc3339())
+        .unwrap_or_default();
+    LogCommit {
+        hash,
+        authored_at,
+        timestamp,
+        subject,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -255,7 +269,7 @@ mod tests {
         let (dir, _tmp) = setup_repo(3);
 
         // Range: HEAD~2..HEAD should give 2 commits
-        let commits = fetch_commits(&dir, "HEAD~2..HEAD").unwrap();
+        let commits = fetch_commits(&dir, &["HEAD~2..HEAD"]).unwrap();
         assert_eq!(commits.len(), 2, "expected 2 commits");
 
         // Verify structure
@@ -274,7 +288,7 @@ mod tests {
     fn test_fetch_commits_empty_range() {
         let (dir, _tmp) = setup_repo(1);
         // HEAD..HEAD is an empty range
-        let commits = fetch_commits(&dir, "HEAD..HEAD").unwrap();
+        let commits = fetch_commits(&dir, &["HEAD..HEAD"]).unwrap();
         assert!(commits.is_empty());
     }
 
@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+
+use crate::config::{self, Paths};
+use crate::error;
+use crate::giturl::{self, Parsed};
+use crate::mirror;
+use crate::output::{self, MutationOutput, Output};
+
+pub fn cmd() -> Command {
+    Command::new("mirror")
+        .about("Manage the bare mirrors behind registered repos")
+        .long_about(
+            "Manage the bare mirrors behind registered repos.\n\n\
+             `dedupe` makes forks share objects. Mirrors of repos with the same name \
+             on the same host are taken to be forks; once they are confirmed to share \
+             history, each one borrows the objects it has in common with one of them \
+             (through git alternates) instead of keeping its own copy. New forks are \
+             cloned that way already; `dedupe` retrofits mirrors registered before \
+             their upstream was, or before wsp did this.\n\n\
+             A mirror others borrow from keeps unreachable objects rather than pruning \
+             them. Removing it with `wsp registry rm` first copies the borrowed objects \
+             back into the forks and their workspace clones.",
+        )
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("dedupe")
+                .about("Share objects between mirrors of forks")
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Show which mirrors would borrow from which, without changing them"),
+                ),
+        )
+}
+
+pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    match matches.subcommand() {
+        Some(("dedupe", m)) => dedupe(paths, m.get_flag("dry-run")),
+        _ => unreachable!(),
+    }
+}
+
+fn dedupe(paths: &Paths, dry_run: bool) -> Result<Output> {
+    let cfg = config::Config::load_from(&paths.config_path)
+        .map_err(|e| error::prefixed(e, "loading config"))?;
+    let identities: Vec<String> = cfg.repos.keys().cloned().collect();
+    let shortnames = giturl::shortnames(&identities);
+    let name_of = |id: &str| {
+        shortnames
+            .get(id)
+            .cloned()
+            .unwrap_or_else(|| id.to_string())
+    };
+
+    // Forks: same host, same repo name.
+    let mut groups: BTreeMap<(String, String), Vec<(String, PathBuf)>> = BTreeMap::new();
+    for id in &identities {
+        let Ok(parsed) = Parsed::from_identity(id) else {
+            continue;
+        };
+        if !mirror::exists(&paths.mirrors_dir, &parsed) {
+            continue;
+        }
+        let dir = mirror::dir(&paths.mirrors_dir, &parsed);
+        groups
+            .entry((parsed.host, parsed.repo))
+            .or_default()
+            .push((id.clone(), dir));
+    }
+
+    let mut deduped = 0;
+    let mut freed = 0;
+    for members in groups.into_values().filter(|m| m.len() > 1) {
+        let Some((base_id, base)) = lender(&members) else {
+            continue;
+        };
+        for (id, dir) in &members {
+            if dir == base || !mirror::borrows_from(dir).is_empty() {
+                continue;
+            }
+            if !mirror::shares_history(dir, base) {
+                eprintln!(
+                    "  skip  {} (no history in common with {})",
+                    name_of(id),
+                    name_of(base_id)
+                );
+                continue;
+            }
+            if dry_run {
+                eprintln!(
+                    "  plan  {} would borrow from {}",
+                    name_of(id),
+                    name_of(base_id)
+                );
+                deduped += 1;
+                continue;
+            }
+            match mirror::borrow(dir, base) {
+                Ok(bytes) => {
+                    eprintln!(
+                        "  ok    {} borrows from {} ({} freed)",
+                        name_of(id),
+                        name_of(base_id),
+                        output::format_bytes(bytes)
+                    );
+                    deduped += 1;
+                    freed += bytes;
+                }
+                Err(e) => eprintln!("  FAIL  {} ({})", name_of(id), e),
+            }
+        }
+    }
+
+    let message = match (dry_run, deduped) {
+        (_, 0) => "no mirrors to dedupe".to_string(),
+        (true, n) => format!("{} mirror(s) would borrow objects from a fork", n),
+        (false, n) => format!(
+            "{} mirror(s) now borrow objects from a fork, {} freed",
+            n,
+            output::format_bytes(freed)
+        ),
+    };
+    Ok(Output::Mutation(MutationOutput::new(message)))
+}
+
+/// The mirror of a group of forks the others borrow from: the one some
+/// already do, else the largest of those that borrow nothing themselves,
+/// which most likely holds the most of what the others need.
+fn lender(members: &[(String, PathBuf)]) -> Option<&(String, PathBuf)> {
+    let roots = || {
+        members
+            .iter()
+            .filter(|(_, d)| mirror::borrows_from(d).is_empty())
+    };
+    roots()
+        .find(|(_, d)| !mirror::borrowers(d, members.iter().map(|(_, m)| m.clone())).is_empty())
+        .or_else(|| roots().max_by_key(|(_, d)| crate::du::dir_size(&d.join("objects"))))
+}
@@ -1,31 +1,54 @@
 pub mod add;
+pub mod auth;
+pub mod backup;
 pub mod cd;
 pub mod cfg;
 pub mod completers;
 pub mod completion;
+pub mod context;
+pub mod daemon;
 pub mod delete;
+pub mod deps;
 pub mod describe;
 pub mod diff;
+pub mod dir;
 pub mod doctor;
 pub mod exec;
 pub mod fetch;
+pub mod fix;
 pub mod help;
+pub mod info;
+pub mod layout;
 pub mod list;
 pub mod log;
+pub mod mirrors;
 pub mod new;
+pub mod open;
+pub mod pr;
+pub mod prompt;
 pub mod recover;
 pub mod registry;
 pub mod remove;
 pub mod rename;
+pub mod repair;
 pub mod repo;
 pub mod repo_list;
+pub mod review;
+pub mod run;
+pub mod schedule;
+pub mod schema;
+pub mod serve;
 pub mod setup;
+pub mod shell;
 pub mod skill;
 pub mod status;
 pub mod sync;
 pub mod template;
+pub mod watch;
+pub mod which;
 
 use clap::{Arg, ArgMatches, Command};
+use clap_complete::engine::ArgValueCandidates;
 
 use crate::config::{self, Paths};
 use crate::output::Output;
@@ -37,10 +60,17 @@ const HELP_CATEGORIES: &[(&str, &[&str])] = &[
     (
         "Workspace",
         &[
-            "new", "repo", "cd", "ls", "rename", "describe", "rm", "recover",
+            "new", "repo", "cd", "open", "dir", "which", "prompt", "ls", "info", "context",
+            "rename", "describe", "shell", "rm", "recover",
+        ],
+    ),
+    (
+        "Workflow",
+        &[
+            "st", "diff", "log", "sync", "fix", "repair", "pr", "review", "exec", "run", "watch",
+            "layout",
         ],
     ),
-    ("Workflow", &["st", "diff", "log", "sync", "exec"]),
     (
         "Admin",
         &[
@@ -48,7 +78,11 @@ const HELP_CATEGORIES: &[(&str, &[&str])] = &[
             "registry",
             "template",
             "config",
+            "deps",
             "doctor",
+            "daemon",
+            "serve",
+            "schema",
             "completion",
             "help",
         ],
@@ -87,18 +121,100 @@ pub fn build_cli() -> Command {
                 .long("json")
                 .global(true)
                 .action(clap::ArgAction::SetTrue)
-                .help("Output as JSON"),
+                .conflicts_with("format")
+                .help("Output as JSON (same as --format json)"),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .global(true)
+                .value_name("FORMAT")
+                .value_parser(clap::builder::PossibleValuesParser::new(
+                    crate::output::FORMAT_NAMES,
+                ))
+                .help("Output format: table (default), json, yaml, or csv"),
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .global(true)
+                .action(clap::ArgAction::Count)
+                .help("Show git commands and timings (-v), and their output (-vv)"),
+        )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .global(true)
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("verbose")
+                .help("Don't print warnings (progress and errors still print)"),
+        )
+        .arg(
+            Arg::new("yes")
+                .short('y')
+                .long("yes")
+                .global(true)
+                .action(clap::ArgAction::SetTrue)
+                .help("Skip confirmation prompts for destructive operations"),
+        )
+        .arg(
+            Arg::new("timings")
+                .long("timings")
+                .global(true)
+                .action(clap::ArgAction::SetTrue)
+                .help("Print where time went (per repo and git operation) on stderr"),
+        )
+        .arg(
+            Arg::new("color")
+                .long("color")
+                .global(true)
+                .value_name("WHEN")
+                .value_parser(clap::builder::PossibleValuesParser::new(
+                    crate::output::COLOR_NAMES,
+                ))
+                .help("Color git output: auto (default, honors NO_COLOR), always, never"),
+        )
+        .arg(
+            Arg::new("offline")
+                .long("offline")
+                .global(true)
+                .action(clap::ArgAction::SetTrue)
+                .help("Skip fetching and fail network-only operations; results may be stale [env: WSP_OFFLINE]"),
+        )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .global(true)
+                .value_name("NAME")
+                .add(ArgValueCandidates::new(completers::complete_profiles))
+                .help(
+                    "Use a profile: its own repo registry, config, and workspaces [env: WSP_PROFILE]",
+                ),
         )
         // Workspace commands
         .subcommand(new::cmd())
         .subcommand(delete::cmd())
         .subcommand(list::cmd())
         .subcommand(status::cmd())
+        .subcommand(info::cmd())
+        .subcommand(context::cmd())
         .subcommand(diff::cmd())
         .subcommand(log::cmd())
         .subcommand(sync::cmd())
+        .subcommand(fix::cmd())
+        .subcommand(repair::cmd())
+        .subcommand(pr::cmd())
+        .subcommand(review::cmd())
         .subcommand(exec::cmd())
+        .subcommand(run::cmd())
+        .subcommand(watch::cmd())
         .subcommand(cd::cmd())
+        .subcommand(open::cmd())
+        .subcommand(dir::cmd())
+        .subcommand(which::cmd())
+        .subcommand(prompt::cmd())
         .subcommand(recover::cmd())
         .subcommand(rename::cmd())
         .subcommand(describe::cmd())
@@ -109,7 +225,13 @@ pub fn build_cli() -> Command {
         .subcommand(registry::cmd())
         .subcommand(template::cmd())
         .subcommand(cfg::cmd())
+        .subcommand(deps::cmd())
+        .subcommand(layout::cmd())
+        .subcommand(shell::cmd())
         .subcommand(doctor::cmd())
+        .subcommand(daemon::cmd())
+        .subcommand(serve::cmd())
+        .subcommand(schema::cmd())
         .subcommand(completion::cmd())
         // Help with topic support
         .subcommand(help::cmd());
@@ -157,6 +279,52 @@ fn build_categorized_help(cli: &Command) -> String {
     out
 }
 
+/// `--no-fetch`, shared by the commands that fetch from upstream before
+/// they work. Read with [`crate::offline::FetchPolicy::from_flag`].
+pub(crate) fn no_fetch_arg() -> Arg {
+    Arg::new("no-fetch")
+        .long("no-fetch")
+        .action(clap::ArgAction::SetTrue)
+        .help("Don't fetch from upstream first; use refs from the last fetch")
+}
+
+/// `--max-age`, shared by the commands that fetch mirrors: leave alone those
+/// fetched more recently. Read with [`fetch::Limits::resolve`].
+pub(crate) fn max_age_arg() -> Arg {
+    Arg::new("max-age")
+        .long("max-age")
+        .value_name("AGE")
+        .value_parser(crate::util::parse_duration)
+        .help("Skip repos whose mirror was fetched within AGE (e.g. 10m, 2h), 0 for none [default: fetch.max-age, else 0]")
+}
+
+/// Whether the command diagnoses, repairs or replaces config.yaml, and so must start
+/// even when it doesn't load.
+pub fn checks_config(matches: &ArgMatches) -> bool {
+    match matches.subcommand() {
+        Some(("doctor", _)) => true,
+        Some(("config", m)) => matches!(m.subcommand_name(), Some("doctor" | "edit")),
+        Some(("setup", m)) => m.subcommand_name() == Some("restore"),
+        _ => false,
+    }
+}
+
+/// Whether the command writes config.yaml or templates, and so may move them
+/// from the data dir to the config dir first. Commands that only read them
+/// leave them where they are.
+pub fn changes_config(matches: &ArgMatches) -> bool {
+    match matches.subcommand() {
+        Some(("doctor", m)) => m.get_flag("fix"),
+        Some(("registry", m)) => matches!(m.subcommand_name(), Some("add" | "rm")),
+        Some(("template", m)) => {
+            !matches!(m.subcommand_name(), None | Some("ls" | "show" | "export"))
+        }
+        Some(("config", m)) => matches!(m.subcommand_name(), Some("set" | "unset" | "edit")),
+        Some(("setup", m)) => matches!(m.subcommand_name(), None | Some("restore")),
+        _ => false,
+    }
+}
+
 pub fn dispatch(matches: &ArgMatches, paths: &Paths) -> anyhow::Result<Output> {
     match matches.subcommand() {
         // --- Workspace-scoped repo commands ---
@@ -173,21 +341,39 @@ pub fn dispatch(matches: &ArgMatches, paths: &Paths) -> anyhow::Result<Output> {
         Some(("new", m)) => new::run(m, paths),
         Some(("rm", m)) => delete::run(m, paths),
         Some(("cd", m)) => cd::run(m, paths),
+        Some(("open", m)) => open::run(m, paths),
         Some(("ls", m)) => list::run(m, paths),
         Some(("st", m)) => status::run(m, paths),
         Some(("diff", m)) => diff::run(m, paths),
         Some(("log", m)) => log::run(m, paths),
         Some(("sync", m)) => sync::run(m, paths),
+        Some(("fix", m)) => fix::run(m, paths),
+        Some(("repair", m)) => repair::run(m, paths),
+        Some(("pr", sub)) => pr::dispatch(sub, paths),
+        Some(("review", m)) => review::run(m, paths),
         Some(("exec", m)) => exec::run(m, paths),
+        Some(("run", m)) => run::run(m, paths),
+        Some(("watch", m)) => watch::run(m, paths),
         Some(("recover", m)) => recover::run(m, paths),
         Some(("rename", m)) => rename::run(m, paths),
         Some(("describe", m)) => describe::run(m, paths),
+        Some(("dir", m)) => dir::run(m, paths),
+        Some(("which", m)) => which::run(m, paths),
+        Some(("prompt", m)) => prompt::run(m, paths),
+        Some(("info", m)) => info::run(m, paths),
+        Some(("context", m)) => context::run(m, paths),
 
         // --- Admin commands (promoted from setup) ---
         Some(("registry", sub)) => registry::dispatch(sub, paths),
         Some(("template", sub)) => template::dispatch(sub, paths),
         Some(("config", sub)) => cfg::dispatch(sub, paths),
+        Some(("deps", sub)) => deps::dispatch(sub, paths),
+        Some(("layout", sub)) => layout::run(sub, paths),
+        Some(("shell", sub)) => shell::run(sub, paths),
         Some(("doctor", m)) => doctor::run(m, paths),
+        Some(("daemon", m)) => daemon::run(m, paths),
+        Some(("serve", m)) => serve::run(m, paths),
+        Some(("schema", m)) => schema::run(m, paths),
         Some(("completion", m)) => completion::run(m, paths),
         Some(("setup", m)) => setup::run(m, paths),
 
@@ -1,18 +1,27 @@
 use std::collections::BTreeMap;
-use std::sync::Mutex;
+use std::io::IsTerminal;
 use std::time::Instant;
 
 use anyhow::{Result, bail};
 use clap::{Arg, ArgMatches, Command};
 use clap_complete::engine::ArgValueCandidates;
 
+use crate::bootstrap;
 use crate::config::{self, Paths};
 use crate::discovery;
-use crate::git;
+use crate::error::{self, ErrorKind};
+use crate::filelock;
+use crate::files;
 use crate::giturl;
+use crate::hooks;
+use crate::issue;
 use crate::mirror;
+use crate::offline::FetchPolicy;
 use crate::output::{MutationOutput, Output};
+use crate::picker;
+use crate::pushguard;
 use crate::template;
+use crate::trailer;
 use crate::workspace;
 
 use super::completers;
@@ -24,12 +33,26 @@ pub fn cmd() -> Command {
             "Create a new workspace.\n\n\
              Sets up a directory with local clones of the specified repos, all sharing a \
              single feature branch. Clones are bootstrapped from local bare mirrors, so \
-             creation is fast and works offline once mirrors exist.\n\n\
+             creation is fast and works offline once mirrors exist. The mirrors are fetched \
+             first, except with --no-fetch, or those fetched within --max-age (default \
+             fetch.max-age), which makes creating workspace after workspace cheap.\n\n\
              When run inside an existing workspace with no repos specified, automatically \
              copies the repo list from the current workspace. This makes it easy to spin up \
-             parallel workspaces for related features.",
+             parallel workspaces for related features.\n\n\
+             With -i (or when no repos are given outside a workspace, in a terminal), \
+             pick repos and templates from an interactive fuzzy list.\n\n\
+             With --issue, the workspace is named after a GitHub issue (URL) or Jira issue \
+             (URL or key, looked up on jira.url) using issue.name-template, every \
+             positional argument is a repo, the issue title becomes the description unless \
+             -d is given, and the issue is recorded in the workspace metadata.\n\n\
+             A repo that fails to clone (a bad branch, no access) fails the whole \
+             workspace. With --continue-on-error the workspace is created with the repos \
+             that cloned, and the others are recorded as pending for `wsp repair`.\n\n\
+             With --print-dir, the workspace path is the only thing printed on stdout \
+             (the rest goes to stderr), so scripts can capture it; the shell integration \
+             uses it to cd into the new workspace.",
         )
-        .arg(Arg::new("workspace").required(true))
+        .arg(Arg::new("workspace").required_unless_present("issue"))
         .arg(
             Arg::new("repos")
                 .num_args(0..)
@@ -62,44 +85,101 @@ pub fn cmd() -> Command {
                 .required(false),
         )
         .arg(
-            Arg::new("no-fetch")
-                .long("no-fetch")
+            Arg::new("interactive")
+                .short('i')
+                .long("interactive")
                 .action(clap::ArgAction::SetTrue)
-                .help("Skip fetching mirrors before cloning"),
+                .conflicts_with_all(["repos", "source"])
+                .help("Pick repos and templates interactively"),
         )
+        .arg(super::no_fetch_arg())
+        .arg(super::max_age_arg().conflicts_with("no-fetch"))
         .arg(
             Arg::new("description")
                 .short('d')
                 .long("description")
                 .help("Purpose of the workspace"),
         )
+        .arg(
+            Arg::new("issue")
+                .long("issue")
+                .value_name("ISSUE")
+                .help("Name the workspace after a GitHub issue URL or Jira key/URL"),
+        )
         .arg(
             Arg::new("no-discover")
                 .long("no-discover")
                 .action(clap::ArgAction::SetTrue)
                 .help("Skip template discovery in cloned repos"),
         )
+        .arg(
+            Arg::new("no-setup")
+                .long("no-setup")
+                .action(clap::ArgAction::SetTrue)
+                .help("Skip the repos' setup commands"),
+        )
+        .arg(
+            Arg::new("continue-on-error")
+                .long("continue-on-error")
+                .action(clap::ArgAction::SetTrue)
+                .help("Create the workspace with the repos that clone; retry the rest with `wsp repair`"),
+        )
+        .arg(
+            Arg::new("print-dir")
+                .long("print-dir")
+                .action(clap::ArgAction::SetTrue)
+                // The shell integration adds it too.
+                .overrides_with("print-dir")
+                .help("Print only the workspace path on stdout, for scripts"),
+        )
 }
 
 pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
-    let ws_name = matches.get_one::<String>("workspace").unwrap();
-    let repo_args: Vec<&String> = matches
+    let mut repo_args: Vec<&String> = matches
         .get_many::<String>("repos")
         .map(|v| v.collect())
         .unwrap_or_default();
     let template_source = matches.get_one::<String>("template");
     let from_workspace = matches.get_one::<String>("from-workspace");
     let from_file = matches.get_one::<String>("file");
-    let no_fetch = matches.get_flag("no-fetch");
-    let description = matches.get_one::<String>("description");
+    let fetch = FetchPolicy::from_flag(matches.get_flag("no-fetch"));
+    let mut description = matches.get_one::<String>("description").cloned();
 
     let mut cfg = config::Config::load_from(&paths.config_path)
-        .map_err(|e| anyhow::anyhow!("loading config: {}", e))?;
+        .map_err(|e| error::prefixed(e, "loading config"))?;
+
+    // With --issue the name comes from the issue, so every positional
+    // argument is a repo.
+    let (ws_name, issue_url) = match matches.get_one::<String>("issue") {
+        Some(raw) => {
+            if let Some(first) = matches.get_one::<String>("workspace") {
+                repo_args.insert(0, first);
+            }
+            let found = issue::fetch(&issue::parse(raw)?, cfg.jira_url.as_deref())?;
+            let template = cfg
+                .issue_name_template
+                .as_deref()
+                .unwrap_or(issue::DEFAULT_NAME_TEMPLATE);
+            let name = issue::workspace_name(template, &found.key, &found.title)?;
+            eprintln!("Issue {}: {}", found.key, found.title);
+            description.get_or_insert(found.title);
+            (name, Some(found.url))
+        }
+        None => (
+            matches.get_one::<String>("workspace").unwrap().clone(),
+            None,
+        ),
+    };
+    let ws_name = ws_name.as_str();
 
     let mut repo_refs: BTreeMap<String, String> = BTreeMap::new();
     let mut created_from: Option<String> = None;
     let mut loaded_template: Option<template::Template> = None;
 
+    if matches.get_flag("interactive") {
+        pick_repos(&mut cfg, paths, &mut repo_refs)?;
+    }
+
     // Add repos from template name
     if let Some(source) = template_source {
         let tmpl = template::load(&paths.templates_dir, source)?;
@@ -148,9 +228,10 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
 
     // Add individual repos
     let identities: Vec<String> = cfg.repos.keys().cloned().collect();
+    let index = giturl::Index::new(&identities);
     for rn in &repo_args {
         let name = giturl::parse_repo_ref(rn);
-        let id = giturl::resolve(name, &identities)?;
+        let id = index.resolve(name)?;
         repo_refs.insert(id, String::new());
     }
 
@@ -182,16 +263,26 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
             );
             created_from = Some(format!("workspace:{}", source_name));
             loaded_template = Some(tmpl);
+        } else if std::io::stdin().is_terminal() {
+            pick_repos(&mut cfg, paths, &mut repo_refs)?;
         } else {
-            bail!("no repos specified (use repo args, -t, -w, or -f)");
+            bail!("no repos specified (use repo args, -t, -w, -f, or -i)");
         }
     }
 
+    // A template with its own workspaces dir puts the workspace there.
+    let template_paths = template_source
+        .and_then(|t| cfg.template_workspaces_dirs.as_ref()?.get(t))
+        .map(|root| paths.with_workspaces_dir(std::path::Path::new(root)));
+    let paths = template_paths.as_ref().unwrap_or(paths);
+
     // Validate early before expensive I/O
     workspace::validate_name(ws_name)?;
-    let ws_dir = workspace::dir(&paths.workspaces_dir, ws_name);
-    if ws_dir.exists() {
-        bail!("workspace {:?} already exists", ws_name);
+    if paths.workspace_dir(ws_name).exists() {
+        return Err(error::tag(
+            ErrorKind::Conflict,
+            anyhow::anyhow!("workspace {:?} already exists", ws_name),
+        ));
     }
 
     // Build upstream URL map from config
@@ -202,110 +293,230 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
         }
     }
 
+    let branches = workspace::Branches::plan(&cfg, ws_name, repo_refs.keys())?;
+
     let start = Instant::now();
 
-    // Pre-fetch mirrors (parallel) unless --no-fetch
-    if !no_fetch {
-        let mirrors: Vec<(String, std::path::PathBuf)> = repo_refs
-            .keys()
-            .filter_map(|id| {
-                giturl::Parsed::from_identity(id)
-                    .ok()
-                    .map(|p| (id.clone(), mirror::dir(&paths.mirrors_dir, &p)))
-            })
-            .collect();
-
-        if !mirrors.is_empty() {
-            eprintln!("Fetching {} mirrors...", mirrors.len());
-            let progress = Mutex::new(());
-            std::thread::scope(|s| {
-                let handles: Vec<_> = mirrors
-                    .iter()
-                    .map(|(id, mirror_dir)| {
-                        let progress = &progress;
-                        s.spawn(move || {
-                            let result = git::fetch(mirror_dir, true);
-                            let _lock = progress.lock().unwrap_or_else(|e| e.into_inner());
-                            match &result {
-                                Ok(()) => eprintln!("  ok    {}", id),
-                                Err(e) => eprintln!("  FAIL  {} ({})", id, e),
-                            }
-                        })
-                    })
-                    .collect();
-                for h in handles {
-                    let _ = h.join();
-                }
-            });
-        }
+    // Pre-fetch mirrors (parallel) unless --no-fetch, cloning missing ones
+    if !fetch.fetches() {
+        fetch.note_skipped();
     }
-
-    let branch_prefix = cfg.branch_prefix.as_deref();
-    let branch = match branch_prefix.filter(|p| !p.is_empty()) {
-        Some(prefix) => format!("{}/{}", prefix, ws_name),
-        None => ws_name.to_string(),
-    };
+    prepare_mirrors(
+        paths,
+        repo_refs.keys(),
+        &upstream_urls,
+        fetch,
+        &super::fetch::Limits::resolve(matches, &cfg),
+    );
 
     eprintln!(
         "Creating workspace {:?} (branch: {}) with {} repos...",
         ws_name,
-        branch,
+        branches.default,
         repo_refs.len()
     );
-    workspace::create(
+    for (identity, branch) in &branches.per_repo {
+        eprintln!("  {} on branch {}", identity, branch);
+    }
+    let pending = workspace::create(
         paths,
         ws_name,
         &repo_refs,
-        branch_prefix,
+        &branches,
         &upstream_urls,
-        description.map(|s| s.as_str()),
-        created_from.as_deref(),
+        &workspace::CreateOpts {
+            description: description.as_deref(),
+            created_from: created_from.as_deref(),
+            continue_on_error: matches.get_flag("continue-on-error"),
+        },
     )?;
 
     let ws_dir = workspace::dir(&paths.workspaces_dir, ws_name);
-    let meta_result = workspace::load_metadata(&ws_dir);
+    if let Some(url) = issue_url {
+        filelock::with_metadata(&ws_dir, |meta| {
+            meta.issue = Some(url);
+            Ok(())
+        })?;
+    }
+    finish(
+        paths,
+        &cfg,
+        &ws_dir,
+        loaded_template.as_ref(),
+        matches.get_flag("no-setup"),
+        matches.get_flag("no-discover"),
+    )?;
+
+    let duration_ms = start.elapsed().as_millis() as u64;
+
+    let mut output = MutationOutput::new(format!("Workspace created: {}", ws_dir.display()))
+        .with_duration(duration_ms)
+        .with_workspace(ws_name, ws_dir.display().to_string(), &branches.default)
+        .with_fetch_skipped(fetch)
+        .with_print_dir(matches.get_flag("print-dir"));
+    if !pending.is_empty() {
+        output = output.with_hint(format!(
+            "{} repo(s) failed to clone and are pending: {}; retry with `wsp repair {}`",
+            pending.len(),
+            pending.keys().cloned().collect::<Vec<_>>().join(", "),
+            ws_name
+        ));
+    }
+    Ok(Output::Mutation(output.with_pending(&pending)))
+}
+
+/// Interactive selection for `wsp new -i`: registered repos followed by
+/// templates, whose repos are added as a group.
+fn pick_repos(
+    cfg: &mut config::Config,
+    paths: &Paths,
+    repo_refs: &mut BTreeMap<String, String>,
+) -> Result<()> {
+    let identities: Vec<String> = cfg.repos.keys().cloned().collect();
+    let templates = template::list(&paths.templates_dir)?;
+    if identities.is_empty() && templates.is_empty() {
+        bail!("no repos registered (use `wsp registry add <url>`)");
+    }
+
+    let shortnames = giturl::shortnames(&identities);
+    let width = shortnames.values().map(|s| s.len()).max().unwrap_or(0);
+    let mut items: Vec<String> = identities
+        .iter()
+        .map(|id| format!("{:width$}  {}", shortnames[id], id, width = width))
+        .collect();
+    items.extend(templates.iter().map(|t| format!("template: {}", t)));
+
+    let chosen = picker::pick_many("Select repos for the new workspace:", items)?;
+    for i in chosen {
+        if let Some(id) = identities.get(i) {
+            repo_refs.insert(id.clone(), String::new());
+            continue;
+        }
+        let tmpl = template::load(&paths.templates_dir, &templates[i - identities.len()])?;
+        template::auto_register(&tmpl, cfg, paths)?;
+        for id in tmpl.identities()? {
+            repo_refs.insert(id, String::new());
+        }
+    }
+    if repo_refs.is_empty() {
+        bail!("no repos selected");
+    }
+    Ok(())
+}
+
+/// Get the mirrors of `identities` ready to clone from, `limits.jobs` at a
+/// time, reporting each: fetch the existing ones when `fetch` allows, except
+/// those fetched within `limits.max_age`, and clone the missing ones
+/// (registered, but never cloned on this machine or since deleted) from
+/// `upstream_urls` unless offline. Failures are reported but not fatal: a
+/// clone falls back to what its mirror has, and a repo whose mirror is still
+/// missing fails to clone.
+pub(crate) fn prepare_mirrors<'a>(
+    paths: &Paths,
+    identities: impl Iterator<Item = &'a String>,
+    upstream_urls: &BTreeMap<String, String>,
+    fetch: FetchPolicy,
+    limits: &super::fetch::Limits,
+) {
+    let repos: Vec<(String, Option<String>)> = identities
+        .filter_map(|id| {
+            let parsed = giturl::Parsed::from_identity(id).ok()?;
+            if mirror::exists(&paths.mirrors_dir, &parsed) {
+                fetch.fetches().then(|| (id.clone(), None))
+            } else if fetch != FetchPolicy::Offline {
+                Some((id.clone(), Some(upstream_urls.get(id)?.clone())))
+            } else {
+                None
+            }
+        })
+        .collect();
+    if !repos.is_empty() {
+        super::fetch::fetch_or_clone_mirrors(paths, repos, true, limits);
+    }
+}
+
+/// Everything `wsp new` does once the clones exist: git config, shared
+/// files, setup commands, language integrations, AGENTS.md, post-create
+/// hooks, and template discovery.
+pub(crate) fn finish(
+    paths: &Paths,
+    cfg: &config::Config,
+    ws_dir: &std::path::Path,
+    template: Option<&template::Template>,
+    no_setup: bool,
+    no_discover: bool,
+) -> Result<()> {
+    let meta_result = workspace::load_metadata(ws_dir);
 
     // Apply template settings over global config for integrations
-    let effective_cfg = match &loaded_template {
-        Some(tmpl) => tmpl.apply_config(&cfg),
+    let effective_cfg = match template {
+        Some(tmpl) => tmpl.apply_config(cfg),
         None => cfg.clone(),
     };
 
-    // Apply git config defaults to all clones
+    // Apply git config defaults and commit authors to all clones
     if let Ok(ref meta) = meta_result {
         let git_config = effective_cfg.effective_git_config();
-        workspace::apply_git_config(&ws_dir, meta, &git_config, None);
+        workspace::apply_git_config(ws_dir, meta, &git_config, None);
+        workspace::add_git_config(ws_dir, meta, &effective_cfg.url_rewrite_git_config(), None);
+        workspace::apply_committers(ws_dir, meta, &effective_cfg, None);
+        if effective_cfg.commit_trailer == Some(true) {
+            trailer::HOOK.install_all(ws_dir, meta, None);
+        }
+        if effective_cfg.push_guard == Some(true) {
+            pushguard::HOOK.install_all(ws_dir, meta, None);
+        }
+    }
+
+    // Render shared files into the workspace root
+    if let Ok(ref meta) = meta_result {
+        files::write_all(ws_dir, meta, effective_cfg.files.as_ref(), paths.data_dir());
+    }
+
+    if !no_setup && let Ok(ref meta) = meta_result {
+        let identities: Vec<String> = meta.repos.keys().cloned().collect();
+        bootstrap::run(ws_dir, meta, cfg, &identities);
     }
 
     match &meta_result {
-        Ok(meta) => crate::lang::run_integrations(&ws_dir, meta, &effective_cfg),
-        Err(e) => eprintln!("warning: skipping language integrations: {}", e),
+        Ok(meta) => crate::lang::run_integrations(ws_dir, meta, &effective_cfg),
+        Err(e) => tracing::warn!("skipping language integrations: {}", e),
     }
     // Seed AGENTS.md with template's agent_md content before auto-generation.
     // agentmd::update() will append the marked section, preserving this content.
     // Only seed if agent_md generation is enabled — otherwise we'd create a
     // half-baked AGENTS.md with no markers, no symlink, and no skills.
     if cfg.agent_md.unwrap_or(true)
-        && let Some(ref tmpl) = loaded_template
+        && let Some(tmpl) = template
         && let Some(ref content) = tmpl.agent_md
     {
         let agents_path = ws_dir.join("AGENTS.md");
         if let Err(e) = std::fs::write(&agents_path, format!("{}\n\n", content)) {
-            eprintln!("warning: could not write template agent content: {}", e);
+            tracing::warn!("could not write template agent content: {}", e);
         }
     }
 
     if cfg.agent_md.unwrap_or(true)
         && let Ok(meta) = &meta_result
-        && let Err(e) = crate::agentmd::update(&ws_dir, meta)
+        && let Err(e) = crate::agentmd::update(ws_dir, meta)
     {
-        eprintln!("warning: AGENTS.md generation failed: {}", e);
+        tracing::warn!("AGENTS.md generation failed: {}", e);
+    }
+
+    if let Ok(ref meta) = meta_result {
+        let identities: Vec<String> = meta.repos.keys().cloned().collect();
+        hooks::run(
+            hooks::Event::PostCreate,
+            cfg.hooks.as_ref(),
+            ws_dir,
+            meta,
+            &identities,
+        )?;
     }
 
     // Template discovery: scan cloned repos for .wsp.yaml files
-    let no_discover = matches.get_flag("no-discover");
     if !no_discover && let Ok(ref meta) = meta_result {
-        let repo_infos = meta.repo_infos(&ws_dir);
+        let repo_infos = meta.repo_infos(ws_dir);
         let mut all_discovered = Vec::new();
         for info in &repo_infos {
             if info.error.is_some() {
@@ -316,15 +527,83 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
             all_discovered.extend(discovered);
         }
         if let Err(e) = discovery::prompt_and_import(&all_discovered, &paths.templates_dir) {
-            eprintln!("warning: template discovery failed: {}", e);
+            tracing::warn!("template discovery failed: {}", e);
         }
     }
 
-    let duration_ms = start.elapsed().as_millis() as u64;
+    Ok(())
+}
 
-    Ok(Output::Mutation(
-        MutationOutput::new(format!("Workspace created: {}", ws_dir.display()))
-            .with_duration(duration_ms)
-            .with_workspace(ws_name, ws_dir.display().to_string(), &branch),
-    ))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::fetch::Limits;
+    use crate::git;
+    use std::time::Duration;
+
+    fn limits(max_age: Option<Duration>) -> Limits {
+        Limits {
+            jobs: 4,
+            retries: 0,
+            timeout: None,
+            backoff: Duration::ZERO,
+            max_age,
+        }
+    }
+
+    #[test]
+    fn test_prepare_mirrors_clones_missing() {
+        let (_clone, source, _clone_tmp, _source_tmp) = crate::testutil::setup_clone_repo();
+        let tmp = tempfile::tempdir().unwrap();
+        let paths = Paths::from_dirs(&tmp.path().join("data"), &tmp.path().join("ws"));
+        let id = "test.local/user/test-repo".to_string();
+        let parsed = giturl::Parsed::from_identity(&id).unwrap();
+        let urls = BTreeMap::from([(id.clone(), source.to_str().unwrap().to_string())]);
+
+        // Offline, nothing can be cloned.
+        prepare_mirrors(
+            &paths,
+            [&id].into_iter(),
+            &urls,
+            FetchPolicy::Offline,
+            &limits(None),
+        );
+        assert!(!mirror::exists(&paths.mirrors_dir, &parsed));
+
+        prepare_mirrors(
+            &paths,
+            [&id].into_iter(),
+            &urls,
+            FetchPolicy::NoFetch,
+            &limits(None),
+        );
+        let dir = mirror::dir(&paths.mirrors_dir, &parsed);
+        assert!(git::ref_exists(&dir, "refs/heads/main"));
+        assert!(git::ref_exists(&dir, "refs/remotes/origin/main"));
+
+        // Fetched ten minutes ago: left alone within an hour's --max-age,
+        // fetched again within a minute's.
+        let ten_min_ago = std::time::SystemTime::now() - Duration::from_secs(600);
+        mirror::mark_fetched(&dir, ten_min_ago, true).unwrap();
+        let fetched = || mirror::last_fetched(&paths.mirrors_dir, &parsed).unwrap();
+        let before = fetched();
+        let hour = Some(Duration::from_secs(3600));
+        prepare_mirrors(
+            &paths,
+            [&id].into_iter(),
+            &urls,
+            FetchPolicy::Fetch,
+            &limits(hour),
+        );
+        assert_eq!(fetched(), before);
+        let minute = Some(Duration::from_secs(60));
+        prepare_mirrors(
+            &paths,
+            [&id].into_iter(),
+            &urls,
+            FetchPolicy::Fetch,
+            &limits(minute),
+        );
+        assert!(fetched() > before);
+    }
 }
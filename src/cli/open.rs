@@ -0,0 +1,227 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use clap::{Arg, ArgMatches, Command};
+use clap_complete::engine::ArgValueCandidates;
+
+use crate::config::{self, Paths};
+use crate::error;
+use crate::output::Output;
+use crate::workspace;
+
+use super::completers;
+
+/// Editors that take several directories and open them as one multi-root
+/// window, each with its own source control view.
+const MULTI_ROOT_EDITORS: &[&str] = &[
+    "code",
+    "code-insiders",
+    "codium",
+    "cursor",
+    "windsurf",
+    "zed",
+    "subl",
+];
+
+pub fn cmd() -> Command {
+    Command::new("open")
+        .about("Open a workspace in your editor")
+        .long_about(
+            "Open a workspace in your editor.\n\n\
+             Opens the current (or named) workspace, or one of its repos with --repo. \
+             The editor is --editor, else the `editor` config key, else $VISUAL, else \
+             $EDITOR. The command may include arguments (`code --new-window`).\n\n\
+             Editors with multi-root support (VS Code and its forks, Zed, Sublime Text) \
+             get the workspace root plus every repo as separate folders, so each repo \
+             has its own git view. Other editors get the workspace root.",
+        )
+        .arg(Arg::new("workspace").add(ArgValueCandidates::new(completers::complete_workspaces)))
+        .arg(
+            Arg::new("repo")
+                .short('r')
+                .long("repo")
+                .help("Open only this repo's clone")
+                .add(ArgValueCandidates::new(
+                    completers::complete_workspace_repos,
+                )),
+        )
+        .arg(
+            Arg::new("editor")
+                .short('e')
+                .long("editor")
+                .value_name("COMMAND")
+                .help("Editor command to use instead of the configured one"),
+        )
+}
+
+pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    let ws_dir: PathBuf = match matches.get_one::<String>("workspace") {
+        Some(name) => paths.workspace_dir(name),
+        None => workspace::detect(&std::env::current_dir()?)?,
+    };
+    let meta =
+        workspace::load_metadata(&ws_dir).map_err(|e| error::prefixed(e, "reading workspace"))?;
+
+    let cfg = config::Config::load_from(&paths.config_path)?;
+    let (visual, env_editor) = (std::env::var("VISUAL").ok(), std::env::var("EDITOR").ok());
+    let editor = resolve_editor(
+        matches.get_one::<String>("editor").map(String::as_str),
+        cfg.editor.as_deref(),
+        visual.as_deref(),
+        env_editor.as_deref(),
+    )?;
+
+    let targets = match matches.get_one::<String>("repo") {
+        Some(repo) => {
+            let identity = meta.resolve_repo(repo)?;
+            vec![ws_dir.join(meta.dir_name(&identity)?)]
+        }
+        None => {
+            let mut clones = Vec::new();
+            for identity in meta.repos.keys() {
+                let clone_dir = ws_dir.join(meta.dir_name(identity)?);
+                if clone_dir.is_dir() {
+                    clones.push(clone_dir);
+                }
+            }
+            workspace_targets(editor, &ws_dir, clones)
+        }
+    };
+
+    run_editor(editor, &targets)?;
+    Ok(Output::None)
+}
+
+/// Runs the editor command on `targets` and waits for it to exit.
+pub(crate) fn run_editor(editor: &str, targets: &[PathBuf]) -> Result<()> {
+    let argv = editor_argv(editor, targets);
+    let status = std::process::Command::new(&argv[0])
+        .args(&argv[1..])
+        .status()
+        .map_err(|e| anyhow::anyhow!("running editor {:?}: {}", editor, e))?;
+    if !status.success() {
+        bail!("editor {:?} exited with {}", editor, status);
+    }
+    Ok(())
+}
+
+/// First non-empty of the flag, the config key, $VISUAL, then $EDITOR.
+pub(crate) fn resolve_editor<'a>(
+    flag: Option<&'a str>,
+    configured: Option<&'a str>,
+    visual: Option<&'a str>,
+    editor: Option<&'a str>,
+) -> Result<&'a str> {
+    [flag, configured, visual, editor]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .find(|e| !e.is_empty())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "no editor configured; pass --editor, run `wsp config set editor <command>`, \
+                 or set $VISUAL"
+            )
+        })
+}
+
+/// Whether the editor command's program opens several folders as one window.
+fn is_multi_root(editor: &str) -> bool {
+    let program = editor.split_whitespace().next().unwrap_or_default();
+    Path::new(program)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .is_some_and(|s| MULTI_ROOT_EDITORS.contains(&s.as_str()))
+}
+
+/// What to open for a whole workspace: the root, plus each clone for
+/// multi-root editors.
+fn workspace_targets(editor: &str, ws_dir: &Path, clones: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut targets = vec![ws_dir.to_path_buf()];
+    if is_multi_root(editor) {
+        targets.extend(clones);
+    }
+    targets
+}
+
+/// Run the editor command through the shell so it can carry arguments,
+/// passing the targets as separate arguments rather than interpolating them.
+#[cfg(unix)]
+fn editor_argv(editor: &str, targets: &[PathBuf]) -> Vec<String> {
+    let mut argv = vec![
+        "sh".into(),
+        "-c".into(),
+        format!("{} \"$@\"", editor),
+        "wsp-open".into(),
+    ];
+    argv.extend(targets.iter().map(|t| t.display().to_string()));
+    argv
+}
+
+#[cfg(windows)]
+fn editor_argv(editor: &str, targets: &[PathBuf]) -> Vec<String> {
+    let mut command = editor.to_string();
+    for t in targets {
+        command.push_str(&format!(" \"{}\"", t.display()));
+    }
+    crate::task::shell_argv(&command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_editor() {
+        let cases = vec![
+            (Some("zed"), Some("code"), Some("vim"), None, Some("zed")),
+            (None, Some("code -n"), Some("vim"), None, Some("code -n")),
+            (None, None, Some("vim"), Some("nano"), Some("vim")),
+            (None, None, Some(" "), Some("nano"), Some("nano")),
+            (None, None, None, None, None),
+        ];
+        for (flag, configured, visual, editor, want) in cases {
+            let got = resolve_editor(flag, configured, visual, editor).ok();
+            assert_eq!(got, want, "{:?}", (flag, configured, visual, editor));
+        }
+    }
+
+    #[test]
+    fn test_workspace_targets() {
+        let ws = Path::new("/ws/feat");
+        let clones = || vec![ws.join("api"), ws.join("web")];
+        let cases = vec![
+            ("code", 3),
+            ("/usr/local/bin/code --new-window", 3),
+            ("cursor", 3),
+            ("zed", 3),
+            ("vim", 1),
+            ("nvim -O", 1),
+            ("emacsclient -c", 1),
+        ];
+        for (editor, want) in cases {
+            let targets = workspace_targets(editor, ws, clones());
+            assert_eq!(targets.len(), want, "{}", editor);
+            assert_eq!(targets[0], ws, "{}", editor);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_editor_argv_passes_paths_as_arguments() {
+        let tmp = tempfile::tempdir().unwrap();
+        let out = tmp.path().join("args");
+        let editor = format!("printf '%s\\n' >{}", out.display());
+        let targets = vec![PathBuf::from("/ws/it's here"), PathBuf::from("/ws/$HOME")];
+        let argv = editor_argv(&editor, &targets);
+        let status = std::process::Command::new(&argv[0])
+            .args(&argv[1..])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap(),
+            "/ws/it's here\n/ws/$HOME\n"
+        );
+    }
+}
@@ -0,0 +1,902 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use clap_complete::engine::ArgValueCandidates;
+
+use crate::config::{self, Paths};
+use crate::error;
+use crate::filelock;
+use crate::forge::{Forge, Forges};
+use crate::gc;
+use crate::git;
+use crate::giturl;
+use crate::output::{Output, PrOutput, PrRepoResult, PrStatusEntry, PrStatusOutput};
+use crate::workspace;
+
+use super::completers;
+
+/// Markers around the sibling-PR list wsp keeps in each PR description.
+/// Text outside them is never touched.
+const LINKS_BEGIN: &str = "<!-- wsp:prs -->";
+const LINKS_END: &str = "<!-- /wsp:prs -->";
+
+pub fn cmd() -> Command {
+    Command::new("pr")
+        .about("Open and track pull requests across a workspace's repos")
+        .long_about(
+            "Open and track pull requests across a workspace's repos.\n\n\
+             Uses the GitHub CLI (`gh`), or `glab` for repos on GitLab, which must be \
+             installed and logged in to the repo's host. The forge is detected from the \
+             host in each repo's identity; set forge.<host> for self-hosted instances \
+             whose name doesn't say (wsp config set forge.git.acme.com gitlab).",
+        )
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("create")
+                .about("Push and open a PR in every repo with commits ahead")
+                .long_about(
+                    "Push and open a PR in every repo with commits ahead.\n\n\
+                     For each repo on the workspace branch with commits ahead of \
+                     origin's default branch, pushes the branch and opens a pull request \
+                     against that default branch (a merge request on GitLab). Every PR's description then gets a list \
+                     linking its sibling PRs, kept between wsp markers and refreshed on \
+                     each run. PR URLs are recorded in the workspace metadata, so running \
+                     again pushes new commits and links PRs for newly ahead repos without \
+                     opening duplicates.\n\n\
+                     The title defaults to the workspace description, else the branch \
+                     name; the body defaults to the description. `{repo}` in either is \
+                     replaced with each repo's directory name.",
+                )
+                .arg(
+                    Arg::new("workspace")
+                        .add(ArgValueCandidates::new(completers::complete_workspaces)),
+                )
+                .arg(
+                    Arg::new("title")
+                        .short('t')
+                        .long("title")
+                        .value_name("TITLE")
+                        .help("PR title ({repo} is replaced per repo)"),
+                )
+                .arg(
+                    Arg::new("body")
+                        .short('b')
+                        .long("body")
+                        .value_name("BODY")
+                        .help("PR description ({repo} is replaced per repo)"),
+                )
+                .arg(
+                    Arg::new("draft")
+                        .long("draft")
+                        .help("Open the PRs as drafts")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Show what would be pushed and opened")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("status")
+                .about("Show state, reviews, and checks of the workspace's PRs [read-only]")
+                .long_about(
+                    "Show state, reviews, and checks of the workspace's PRs [read-only].\n\n\
+                     For each repo, looks up the PR recorded by `wsp pr create`, else the \
+                     PR whose head is the workspace branch, and shows its state, review \
+                     decision, CI check results, and whether it conflicts with its base. \
+                     The change is ready when every PR is merged or open, non-draft, not \
+                     blocked on review, conflict-free, and green; anything in the way is \
+                     listed per repo.",
+                )
+                .arg(
+                    Arg::new("workspace")
+                        .add(ArgValueCandidates::new(completers::complete_workspaces)),
+                ),
+        )
+}
+
+pub fn dispatch(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    match matches.subcommand() {
+        Some(("create", m)) => run_create(m, paths),
+        Some(("status", m)) => run_status(m, paths),
+        _ => unreachable!(),
+    }
+}
+
+/// A repo with commits to propose.
+struct Candidate {
+    result: usize,
+    branch: String,
+    base: String,
+    existing: Option<String>,
+}
+
+fn run_create(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    crate::offline::ensure_online("pushing branches and opening pull requests")?;
+    let ws_dir: PathBuf = match matches.get_one::<String>("workspace") {
+        Some(name) => paths.workspace_dir(name),
+        None => workspace::detect(&std::env::current_dir()?)?,
+    };
+    gc::check_workspace(&ws_dir, /* read_only */ false)?;
+    let _lock = filelock::lock_workspace(&ws_dir)?;
+    let meta =
+        filelock::read_metadata(&ws_dir).map_err(|e| error::prefixed(e, "reading workspace"))?;
+    let dry_run = matches.get_flag("dry-run");
+    let cfg = config::Config::load_from(&paths.config_path)?;
+
+    let title = matches
+        .get_one::<String>("title")
+        .cloned()
+        .or_else(|| meta.description.clone())
+        .unwrap_or_else(|| meta.branch.clone());
+    let body = matches
+        .get_one::<String>("body")
+        .cloned()
+        .or_else(|| meta.description.clone())
+        .unwrap_or_default();
+
+    let mut results = Vec::new();
+    let mut remotes = Vec::new();
+    let mut candidates = Vec::new();
+    for identity in meta.repos.keys() {
+        let dir_name = meta.dir_name(identity)?;
+        let clone_dir = ws_dir.join(&dir_name);
+        let mut result = PrRepoResult {
+            identity: identity.clone(),
+            shortname: dir_name,
+            action: "skipped".into(),
+            ok: true,
+            url: meta.prs.get(identity).cloned(),
+            detail: None,
+            error: None,
+        };
+        let remote = Remote::new(identity, clone_dir, cfg.forges.as_ref())?;
+        let branch = meta.branch_for(identity);
+        match classify(&remote.dir, branch) {
+            Ok(Ok(base)) => candidates.push(Candidate {
+                result: results.len(),
+                branch: branch.to_string(),
+                base,
+                existing: result.url.clone(),
+            }),
+            Ok(Err(reason)) => result.detail = Some(reason),
+            Err(e) => {
+                result.ok = false;
+                result.error = Some(e.to_string());
+            }
+        }
+        results.push(result);
+        remotes.push(remote);
+    }
+    if candidates.is_empty() && results.iter().all(|r| r.ok) {
+        bail!("no repo has commits ahead of its default branch; nothing to propose");
+    }
+
+    for c in &candidates {
+        let remote = &remotes[c.result];
+        let r = &mut results[c.result];
+        r.action = match (&c.existing, dry_run) {
+            (Some(_), true) => "would push",
+            (Some(_), false) => "pushed",
+            (None, true) => "would create",
+            (None, false) => "created",
+        }
+        .into();
+        r.detail = Some(format!("against {}", c.base));
+        if dry_run {
+            continue;
+        }
+        if let Err(e) = git::run(Some(&remote.dir), &["push", "-u", "origin", &c.branch]) {
+            r.ok = false;
+            r.error = Some(format!("push failed: {}", e));
+            continue;
+        }
+        if c.existing.is_some() {
+            continue;
+        }
+        let created = remote.create(
+            &c.branch,
+            &c.base,
+            &expand(&title, &r.shortname),
+            &expand(&body, &r.shortname),
+            matches.get_flag("draft"),
+        );
+        match created {
+            Ok(url) => r.url = Some(url),
+            Err(e) => {
+                r.ok = false;
+                r.error = Some(e.to_string());
+            }
+        }
+    }
+
+    if !dry_run {
+        let created: Vec<(String, String)> = candidates
+            .iter()
+            .filter(|c| c.existing.is_none())
+            .filter_map(|c| {
+                let r = &results[c.result];
+                Some((r.identity.clone(), r.url.clone()?))
+            })
+            .collect();
+        if !created.is_empty() {
+            filelock::with_metadata(&ws_dir, |m| {
+                for (id, url) in &created {
+                    m.prs.insert(id.clone(), url.clone());
+                }
+                Ok(())
+            })?;
+        }
+        link_siblings(&remotes, &mut results);
+    }
+
+    Ok(Output::Pr(PrOutput {
+        workspace: meta.name,
+        branch: meta.branch,
+        dry_run,
+        repos: results,
+    }))
+}
+
+/// `Ok(Ok(base))` when the clone has commits on the workspace branch ahead
+/// of `origin/<base>`, `Ok(Err(reason))` when there is nothing to propose.
+fn classify(clone_dir: &Path, branch: &str) -> Result<std::result::Result<String, String>> {
+    if !clone_dir.exists() {
+        bail!("clone directory is missing");
+    }
+    let current = git::branch_current(clone_dir).unwrap_or_default();
+    if current != branch {
+        return Ok(Err(format!("on {} instead of {}", current, branch)));
+    }
+    let base = git::default_branch_for_remote(clone_dir, "origin")?;
+    let ahead = git::commit_count(clone_dir, &format!("origin/{}", base), "HEAD")?;
+    if ahead == 0 {
+        return Ok(Err(format!("no commits ahead of origin/{}", base)));
+    }
+    Ok(Ok(base))
+}
+
+/// Refresh the sibling list in every recorded PR's description. Failures
+/// are reported on the repo without failing it, since its PR exists.
+fn link_siblings(remotes: &[Remote], results: &mut [PrRepoResult]) {
+    let links: Vec<(String, String)> = results
+        .iter()
+        .filter_map(|r| Some((r.shortname.clone(), r.url.clone()?)))
+        .collect();
+    if links.len() < 2 {
+        return;
+    }
+    for (r, remote) in results.iter_mut().zip(remotes) {
+        let Some(url) = r.url.clone() else { continue };
+        let updated = remote.body(&url).and_then(|current| {
+            let body = with_links(&current, &links_section(&url, &links));
+            if body != current {
+                remote.set_body(&url, &body)?;
+            }
+            Ok(())
+        });
+        if let Err(e) = updated {
+            r.detail = Some(format!("linking sibling PRs failed: {}", e));
+        }
+    }
+}
+
+fn run_status(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    crate::offline::ensure_online("checking pull requests")?;
+    let ws_dir: PathBuf = match matches.get_one::<String>("workspace") {
+        Some(name) => paths.workspace_dir(name),
+        None => workspace::detect(&std::env::current_dir()?)?,
+    };
+    gc::check_workspace(&ws_dir, /* read_only */ true)?;
+    let meta =
+        workspace::load_metadata(&ws_dir).map_err(|e| error::prefixed(e, "reading workspace"))?;
+    let cfg = config::Config::load_from(&paths.config_path)?;
+
+    let mut repos = Vec::new();
+    for identity in meta.repos.keys() {
+        let dir_name = meta.dir_name(identity)?;
+        let remote = Remote::new(identity, ws_dir.join(&dir_name), cfg.forges.as_ref())?;
+        // Without a recorded URL, the CLI resolves the branch name against
+        // the clone's remote.
+        let selector = meta
+            .prs
+            .get(identity)
+            .cloned()
+            .unwrap_or_else(|| meta.branch_for(identity).to_string());
+        repos.push((identity.clone(), dir_name, remote, selector));
+    }
+
+    let entries: Vec<PrStatusEntry> = std::thread::scope(|s| {
+        let handles: Vec<_> = repos
+            .iter()
+            .map(|(identity, dir_name, remote, selector)| {
+                s.spawn(move || {
+                    let mut entry = PrStatusEntry {
+                        identity: identity.clone(),
+                        shortname: dir_name.clone(),
+                        forge: remote.forge.name().into(),
+                        ..Default::default()
+                    };
+                    if !remote.dir.exists() {
+                        entry.error = Some("clone directory is missing".into());
+                        return entry;
+                    }
+                    match remote.view(selector) {
+                        Ok(Some(v)) => {
+                            match remote.forge {
+                                Forge::Github => apply_github_view(&mut entry, &v),
+                                Forge::Gitlab => apply_gitlab_view(&mut entry, &v),
+                            }
+                            add_blockers(&mut entry);
+                        }
+                        Ok(None) => {}
+                        Err(e) => entry.error = Some(e.to_string()),
+                    }
+                    entry
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap_or_default())
+            .collect()
+    });
+
+    let with_pr: Vec<&PrStatusEntry> = entries.iter().filter(|e| e.url.is_some()).collect();
+    let ready = !with_pr.is_empty()
+        && with_pr.iter().all(|e| e.blockers.is_empty())
+        && entries.iter().all(|e| e.error.is_none());
+    Ok(Output::PrStatus(PrStatusOutput {
+        workspace: meta.name,
+        branch: meta.branch,
+        ready,
+        repos: entries,
+    }))
+}
+
+/// Fill `entry` from `gh pr view --json` output.
+fn apply_github_view(entry: &mut PrStatusEntry, v: &serde_json::Value) {
+    let text = |key: &str| v[key].as_str().unwrap_or_default().to_lowercase();
+    entry.url = v["url"].as_str().map(String::from);
+    entry.number = v["number"].as_u64();
+    entry.state = text("state");
+    entry.draft = v["isDraft"].as_bool().unwrap_or(false);
+    entry.review = text("reviewDecision");
+    entry.mergeable = text("mergeable");
+
+    for check in v["statusCheckRollup"].as_array().into_iter().flatten() {
+        // Check runs report `status` then `conclusion`; commit statuses a
+        // single `state`.
+        let outcome = check["conclusion"]
+            .as_str()
+            .filter(|c| !c.is_empty())
+            .or_else(|| check["state"].as_str())
+            .unwrap_or_default();
+        match outcome {
+            "SUCCESS" | "NEUTRAL" | "SKIPPED" => entry.checks_passed += 1,
+            "FAILURE" | "ERROR" | "CANCELLED" | "TIMED_OUT" | "ACTION_REQUIRED"
+            | "STARTUP_FAILURE" => entry.checks_failed += 1,
+            _ => entry.checks_pending += 1,
+        }
+    }
+}
+
+/// Fill `entry` from `glab mr view --output json`, in GitHub's terms. The
+/// head pipeline counts as the MR's one check.
+fn apply_gitlab_view(entry: &mut PrStatusEntry, v: &serde_json::Value) {
+    entry.url = v["web_url"].as_str().map(String::from);
+    entry.number = v["iid"].as_u64();
+    entry.state = match v["state"].as_str().unwrap_or_default() {
+        "opened" => "open".into(),
+        "locked" => "closed".into(),
+        other => other.to_string(),
+    };
+    entry.draft =
+        v["draft"].as_bool().unwrap_or(false) || v["work_in_progress"].as_bool().unwrap_or(false);
+
+    let merge_status = v["detailed_merge_status"].as_str().unwrap_or_default();
+    entry.review = match merge_status {
+        "not_approved" => "review_required".into(),
+        "requested_changes" => "changes_requested".into(),
+        _ => String::new(),
+    };
+    entry.mergeable = if v["has_conflicts"].as_bool().unwrap_or(false) || merge_status == "conflict"
+    {
+        "conflicting".into()
+    } else if merge_status == "mergeable" {
+        "mergeable".into()
+    } else {
+        "unknown".into()
+    };
+
+    let pipeline = if v["head_pipeline"].is_object() {
+        &v["head_pipeline"]
+    } else {
+        &v["pipeline"]
+    };
+    match pipeline["status"].as_str() {
+        None => {}
+        Some("success" | "skipped") => entry.checks_passed += 1,
+        Some("failed" | "canceled") => entry.checks_failed += 1,
+        Some(_) => entry.checks_pending += 1,
+    }
+}
+
+/// Work out what keeps the PR in `entry` from merging.
+fn add_blockers(entry: &mut PrStatusEntry) {
+    match entry.state.as_str() {
+        "merged" => return,
+        "open" => {}
+        other => {
+            entry.blockers.push(other.to_string());
+            return;
+        }
+    }
+    if entry.draft {
+        entry.blockers.push("draft".into());
+    }
+    match entry.review.as_str() {
+        "changes_requested" => entry.blockers.push("changes requested".into()),
+        "review_required" => entry.blockers.push("review required".into()),
+        _ => {}
+    }
+    if entry.checks_failed > 0 {
+        entry.blockers.push("checks failing".into());
+    } else if entry.checks_pending > 0 {
+        entry.blockers.push("checks pending".into());
+    }
+    if entry.mergeable == "conflicting" {
+        entry.blockers.push("conflicts".into());
+    }
+}
+
+/// The marked list of the other PRs, for the PR at `own`.
+fn links_section(own: &str, links: &[(String, String)]) -> String {
+    let mut s = format!("{}\n**Related pull requests:**\n", LINKS_BEGIN);
+    for (name, url) in links {
+        if url != own {
+            s.push_str(&format!("- {}: {}\n", name, url));
+        }
+    }
+    s.push_str(LINKS_END);
+    s
+}
+
+/// Replace the marked section in `body`, or append it.
+fn with_links(body: &str, section: &str) -> String {
+    if let Some(start) = body.find(LINKS_BEGIN)
+        && let Some(end) = body[start..].find(LINKS_END)
+    {
+        let end = start + end + LINKS_END.len();
+        return format!("{}{}{}", &body[..start], section, &body[end..]);
+    }
+    let body = body.trim_end();
+    if body.is_empty() {
+        section.to_string()
+    } else {
+        format!("{}\n\n{}", body, section)
+    }
+}
+
+fn expand(template: &str, repo: &str) -> String {
+    template.replace("{repo}", repo)
+}
+
+/// The GitLab MR iid in a merge request URL (`.../-/merge_requests/12`).
+fn mr_iid(url: &str) -> Option<&str> {
+    let (_, rest) = url.split_once("/-/merge_requests/")?;
+    let iid = rest.split(['/', '?', '#']).next()?;
+    (!iid.is_empty() && iid.bytes().all(|b| b.is_ascii_digit())).then_some(iid)
+}
+
+/// Where a repo's PRs live: its forge and host, and the clone the forge's
+/// CLI runs in.
+struct Remote {
+    forge: Forge,
+    host: String,
+    dir: PathBuf,
+}
+
+impl Remote {
+    fn new(identity: &str, dir: PathBuf, overrides: Option<&Forges>) -> Result<Remote> {
+        let host = giturl::Parsed::from_identity(identity)?.host;
+        Ok(Remote {
+            forge: Forge::detect(&host, overrides),
+            host,
+            dir,
+        })
+    }
+
+    /// Open a PR from `branch` into `base` and return its URL.
+    fn create(
+        &self,
+        branch: &str,
+        base: &str,
+        title: &str,
+        body: &str,
+        draft: bool,
+    ) -> Result<String> {
+        let mut args = match self.forge {
+            Forge::Github => vec![
+                "pr", "create", "--head", branch, "--base", base, "--title", title, "--body", body,
+            ],
+            Forge::Gitlab => vec![
+                "mr",
+                "create",
+                "--source-branch",
+                branch,
+                "--target-branch",
+                base,
+                "--title",
+                title,
+                "--description",
+                body,
+                "--yes",
+            ],
+        };
+        if draft {
+            args.push("--draft");
+        }
+        let out = self.run(&args)?;
+        match out.lines().rev().find(|l| l.trim().starts_with("http")) {
+            Some(url) => Ok(url.trim().to_string()),
+            None => bail!("{} printed no PR URL: {}", self.forge.cli(), out),
+        }
+    }
+
+    /// The PR's description.
+    fn body(&self, url: &str) -> Result<String> {
+        match self.forge {
+            Forge::Github => self.run(&["pr", "view", url, "--json", "body", "--jq", ".body"]),
+            Forge::Gitlab => {
+                let v = self.gitlab_view(url)?;
+                Ok(v["description"].as_str().unwrap_or_default().to_string())
+            }
+        }
+    }
+
+    fn set_body(&self, url: &str, body: &str) -> Result<()> {
+        match self.forge {
+            Forge::Github => self.run(&["pr", "edit", url, "--body", body])?,
+            Forge::Gitlab => self.run(&[
+                "mr",
+                "update",
+                mr_iid(url).unwrap_or(url),
+                "--description",
+                body,
+            ])?,
+        };
+        Ok(())
+    }
+
+    /// The PR `selector` (a URL or head branch) names, as the forge CLI
+    /// reports it; `None` when there is none.
+    fn view(&self, selector: &str) -> Result<Option<serde_json::Value>> {
+        let viewed = match self.forge {
+            Forge::Github => self
+                .run(&["pr", "view", selector, "--json", GITHUB_VIEW_FIELDS])
+                .and_then(|out| Ok(serde_json::from_str(&out)?)),
+            Forge::Gitlab => self.gitlab_view(selector),
+        };
+        match viewed {
+            Ok(v) => Ok(Some(v)),
+            Err(e) => {
+                let msg = e.to_string();
+                if msg.contains("no pull requests found") || msg.contains("no open merge request") {
+                    Ok(None)
+                } else {
+                    Err(e)
+                }
+            }
+        }
+    }
+
+    fn gitlab_view(&self, selector: &str) -> Result<serde_json::Value> {
+        let out = self.run(&[
+            "mr",
+            "view",
+            mr_iid(selector).unwrap_or(selector),
+            "--output",
+            "json",
+        ])?;
+        serde_json::from_str(&out).map_err(|e| anyhow::anyhow!("parsing glab output: {}", e))
+    }
+
+    /// Run the forge CLI in the clone, pointed at the repo's host.
+    fn run(&self, args: &[&str]) -> Result<String> {
+        let cli = self.forge.cli();
+        let output = self
+            .forge
+            .command(&self.host)
+            .args(args)
+            .current_dir(&self.dir)
+            .output()
+            .map_err(|e| anyhow::anyhow!("failed to run {}: {} (is {} installed?)", cli, e, cli))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            bail!("{} failed: {}", cli, stderr);
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+/// Fields requested from `gh pr view`.
+const GITHUB_VIEW_FIELDS: &str =
+    "url,number,state,isDraft,reviewDecision,mergeable,statusCheckRollup";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_links_section_omits_own_pr() {
+        let links = vec![
+            (
+                "api".to_string(),
+                "https://github.com/acme/api/pull/1".to_string(),
+            ),
+            (
+                "web".to_string(),
+                "https://github.com/acme/web/pull/7".to_string(),
+            ),
+        ];
+        assert_eq!(
+            links_section("https://github.com/acme/api/pull/1", &links),
+            "<!-- wsp:prs -->\n**Related pull requests:**\n\
+             - web: https://github.com/acme/web/pull/7\n<!-- /wsp:prs -->"
+        );
+    }
+
+    #[test]
+    fn test_with_links() {
+        let section = "<!-- wsp:prs -->\nnew\n<!-- /wsp:prs -->";
+        let cases = vec![
+            ("empty body", "", section.to_string()),
+            (
+                "appended",
+                "Fixes login.\n",
+                format!("Fixes login.\n\n{}", section),
+            ),
+            (
+                "replaced in place",
+                "Intro\n\n<!-- wsp:prs -->\nold\n<!-- /wsp:prs -->\n\nOutro",
+                format!("Intro\n\n{}\n\nOutro", section),
+            ),
+            (
+                "unterminated marker appends",
+                "<!-- wsp:prs -->\nold",
+                format!("<!-- wsp:prs -->\nold\n\n{}", section),
+            ),
+        ];
+        for (name, body, want) in cases {
+            assert_eq!(with_links(body, section), want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_apply_github_view() {
+        let view = |state: &str, draft: bool, review: &str, mergeable: &str, checks: &str| {
+            serde_json::from_str::<serde_json::Value>(&format!(
+                r#"{{"url": "https://github.com/acme/api/pull/1", "number": 1,
+                    "state": "{state}", "isDraft": {draft}, "reviewDecision": "{review}",
+                    "mergeable": "{mergeable}", "statusCheckRollup": {checks}}}"#
+            ))
+            .unwrap()
+        };
+        let green = r#"[{"status": "COMPLETED", "conclusion": "SUCCESS"}, {"state": "SUCCESS"}]"#;
+        let cases = vec![
+            (
+                "ready",
+                view("OPEN", false, "APPROVED", "MERGEABLE", green),
+                vec![],
+                (2, 0, 0),
+            ),
+            (
+                "no review policy",
+                view("OPEN", false, "", "UNKNOWN", "[]"),
+                vec![],
+                (0, 0, 0),
+            ),
+            (
+                "merged",
+                view("MERGED", false, "APPROVED", "UNKNOWN", green),
+                vec![],
+                (2, 0, 0),
+            ),
+            (
+                "closed",
+                view("CLOSED", false, "", "UNKNOWN", "[]"),
+                vec!["closed"],
+                (0, 0, 0),
+            ),
+            (
+                "everything wrong",
+                view(
+                    "OPEN",
+                    true,
+                    "CHANGES_REQUESTED",
+                    "CONFLICTING",
+                    r#"[{"status": "COMPLETED", "conclusion": "FAILURE"},
+                        {"status": "IN_PROGRESS", "conclusion": ""}]"#,
+                ),
+                vec!["draft", "changes requested", "checks failing", "conflicts"],
+                (0, 1, 1),
+            ),
+            (
+                "pending checks",
+                view(
+                    "OPEN",
+                    false,
+                    "REVIEW_REQUIRED",
+                    "MERGEABLE",
+                    r#"[{"state": "PENDING"}]"#,
+                ),
+                vec!["review required", "checks pending"],
+                (0, 0, 1),
+            ),
+        ];
+        for (name, v, blockers, checks) in cases {
+            let mut entry = PrStatusEntry::default();
+            apply_github_view(&mut entry, &v);
+            add_blockers(&mut entry);
+            assert_eq!(entry.number, Some(1), "{}", name);
+            assert_eq!(entry.blockers, blockers, "{}", name);
+            assert_eq!(
+                (
+                    entry.checks_passed,
+                    entry.checks_failed,
+                    entry.checks_pending
+                ),
+                checks,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_apply_gitlab_view() {
+        let view =
+            |state: &str, draft: bool, merge_status: &str, conflicts: bool, pipeline: &str| {
+                serde_json::from_str::<serde_json::Value>(&format!(
+                    r#"{{"web_url": "https://gitlab.com/acme/api/-/merge_requests/3", "iid": 3,
+                    "state": "{state}", "draft": {draft},
+                    "detailed_merge_status": "{merge_status}",
+                    "has_conflicts": {conflicts}, "head_pipeline": {pipeline}}}"#
+                ))
+                .unwrap()
+            };
+        let cases = vec![
+            (
+                "ready",
+                view(
+                    "opened",
+                    false,
+                    "mergeable",
+                    false,
+                    r#"{"status": "success"}"#,
+                ),
+                "open",
+                vec![],
+                (1, 0, 0),
+            ),
+            (
+                "no pipeline",
+                view("opened", false, "mergeable", false, "null"),
+                "open",
+                vec![],
+                (0, 0, 0),
+            ),
+            (
+                "merged",
+                view(
+                    "merged",
+                    false,
+                    "not_open",
+                    false,
+                    r#"{"status": "success"}"#,
+                ),
+                "merged",
+                vec![],
+                (1, 0, 0),
+            ),
+            (
+                "locked",
+                view("locked", false, "not_open", false, "null"),
+                "closed",
+                vec!["closed"],
+                (0, 0, 0),
+            ),
+            (
+                "everything wrong",
+                view(
+                    "opened",
+                    true,
+                    "requested_changes",
+                    true,
+                    r#"{"status": "failed"}"#,
+                ),
+                "open",
+                vec!["draft", "changes requested", "checks failing", "conflicts"],
+                (0, 1, 0),
+            ),
+            (
+                "awaiting approval",
+                view(
+                    "opened",
+                    false,
+                    "not_approved",
+                    false,
+                    r#"{"status": "running"}"#,
+                ),
+                "open",
+                vec!["review required", "checks pending"],
+                (0, 0, 1),
+            ),
+        ];
+        for (name, v, state, blockers, checks) in cases {
+            let mut entry = PrStatusEntry::default();
+            apply_gitlab_view(&mut entry, &v);
+            add_blockers(&mut entry);
+            assert_eq!(entry.number, Some(3), "{}", name);
+            assert_eq!(entry.state, state, "{}", name);
+            assert_eq!(entry.blockers, blockers, "{}", name);
+            assert_eq!(
+                (
+                    entry.checks_passed,
+                    entry.checks_failed,
+                    entry.checks_pending
+                ),
+                checks,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_mr_iid() {
+        let cases = vec![
+            (
+                "https://gitlab.com/acme/api/-/merge_requests/12",
+                Some("12"),
+            ),
+            (
+                "https://git.acme.com/a/b/c/-/merge_requests/7/diffs?x=1",
+                Some("7"),
+            ),
+            ("https://gitlab.com/acme/api/-/merge_requests/", None),
+            ("https://github.com/acme/api/pull/1", None),
+            ("my-branch", None),
+        ];
+        for (url, want) in cases {
+            assert_eq!(mr_iid(url), want, "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_classify() {
+        let (clone, _source, _ct, _st) = crate::testutil::setup_clone_repo();
+        let run = |args: &[&str]| git::run(Some(&clone), args).unwrap();
+        run(&["checkout", "-q", "-b", "feat"]);
+
+        let got = classify(&clone, "other").unwrap();
+        assert_eq!(got, Err("on feat instead of other".to_string()));
+        let got = classify(&clone, "feat").unwrap();
+        assert!(
+            got.as_ref()
+                .is_err_and(|r| r.starts_with("no commits ahead")),
+            "{:?}",
+            got
+        );
+
+        run(&["commit", "-q", "--allow-empty", "-m", "change"]);
+        let base = git::default_branch_for_remote(&clone, "origin").unwrap();
+        assert_eq!(classify(&clone, "feat").unwrap(), Ok(base));
+
+        assert!(classify(&clone.with_file_name("missing"), "feat").is_err());
+    }
+}
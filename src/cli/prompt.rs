@@ -0,0 +1,185 @@
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+
+use crate::config::Paths;
+use crate::output::{Output, PromptOutput};
+use crate::statuscache;
+use crate::workspace;
+
+use super::list::summarize;
+
+pub fn cmd() -> Command {
+    Command::new("prompt")
+        .about("Print the current workspace for shell prompts [read-only]")
+        .long_about(
+            "Print the current workspace for shell prompts [read-only].\n\n\
+             Prints the name of the workspace containing the current directory, and \
+             nothing (exit 0) outside a workspace, so it can go straight into PS1 or a \
+             prompt framework segment:\n\n  \
+             PS1='$(wsp prompt) \\$ '\n\n\
+             --status appends `*N` for N repos with uncommitted changes and `↑N` for N \
+             commits ahead of upstream. Repos are checked in parallel within a time \
+             budget (--timeout, default 200ms); if any repo isn't checked in time, `?` \
+             is appended instead of waiting.\n\n\
+             For starship, `wsp prompt starship` prints a custom-module snippet for \
+             starship.toml that runs `wsp prompt --starship --status`.",
+        )
+        .args_conflicts_with_subcommands(true)
+        .arg(
+            Arg::new("status")
+                .short('s')
+                .long("status")
+                .help("Append dirty and ahead counts")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("timeout")
+                .long("timeout")
+                .value_name("MS")
+                .help("Time budget for --status in milliseconds")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("200")
+                .requires("status"),
+        )
+        .arg(
+            Arg::new("starship")
+                .long("starship")
+                .help("Print in starship custom-module format (no trailing newline)")
+                .action(ArgAction::SetTrue),
+        )
+        .subcommand(
+            Command::new("starship")
+                .about("Print a starship.toml snippet for the wsp prompt module [read-only]"),
+        )
+}
+
+/// Custom module for starship.toml. The conditional group hides the module,
+/// symbol included, when `wsp prompt` prints nothing (outside a workspace);
+/// `when = true` because custom modules are off unless told otherwise.
+const STARSHIP_SNIPPET: &str = r#"# Add to ~/.config/starship.toml, then put ${custom.wsp} in `format`
+# (or leave `format` unset to show it with the other custom modules).
+[custom.wsp]
+description = "Current wsp workspace"
+command = "wsp prompt --starship --status"
+when = true
+style = "bold purple"
+format = "([ws:$output]($style) )"
+"#;
+
+pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    if let Some(("starship", _)) = matches.subcommand() {
+        print!("{}", STARSHIP_SNIPPET);
+        return Ok(Output::None);
+    }
+    let Ok(cwd) = std::env::current_dir() else {
+        return Ok(Output::None);
+    };
+    let Ok(ws_dir) = workspace::detect(&cwd) else {
+        return Ok(Output::None);
+    };
+    let Some(name) = ws_dir.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+        return Ok(Output::None);
+    };
+
+    let status = if matches.get_flag("status") {
+        let timeout = Duration::from_millis(*matches.get_one::<u64>("timeout").unwrap());
+        Some(summarize(&check_repos(paths.data_dir(), &ws_dir, timeout)))
+    } else {
+        None
+    };
+
+    Ok(Output::Prompt(PromptOutput {
+        workspace: name,
+        status,
+        starship: matches.get_flag("starship"),
+    }))
+}
+
+/// `(changed files, commits ahead)` per repo, checked in parallel, from the
+/// status cache for clones unchanged since their last check. Repos that
+/// fail or don't answer within `timeout` are `None`; their threads are
+/// left behind and end with the process.
+fn check_repos(data_dir: &Path, ws_dir: &Path, timeout: Duration) -> Vec<Option<(u32, u32)>> {
+    let Ok(meta) = workspace::load_metadata(ws_dir) else {
+        return vec![None];
+    };
+    let cache = statuscache::load(data_dir);
+    let (tx, rx) = mpsc::channel();
+    let mut count = 0;
+    for identity in meta.repos.keys() {
+        let Ok(dir_name) = meta.dir_name(identity) else {
+            continue;
+        };
+        let clone_dir = ws_dir.join(dir_name);
+        let cached = cache.entry(&clone_dir).cloned();
+        let tx = tx.clone();
+        count += 1;
+        std::thread::spawn(move || {
+            let result = statuscache::counts(&clone_dir, cached.as_ref());
+            let _ = tx.send((clone_dir, result));
+        });
+    }
+    drop(tx);
+
+    let deadline = Instant::now() + timeout;
+    let mut results = Vec::with_capacity(count);
+    let mut updates = Vec::new();
+    while results.len() < count {
+        match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok((clone_dir, result)) => {
+                let counts = result.map(|(counts, entry)| {
+                    updates.extend(entry.map(|e| (clone_dir, e)));
+                    counts
+                });
+                results.push(counts);
+            }
+            Err(_) => break,
+        }
+    }
+    statuscache::save(data_dir, cache, updates);
+    results.resize(meta.repos.len(), None);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_args() {
+        let cases: Vec<(Vec<&str>, bool)> = vec![
+            (vec!["prompt"], true),
+            (vec!["prompt", "--status"], true),
+            (vec!["prompt", "-s", "--timeout", "50"], true),
+            (vec!["prompt", "--timeout", "50"], false), // requires --status
+            (vec!["prompt", "-s", "--timeout", "soon"], false),
+            (vec!["prompt", "--starship", "-s"], true),
+            (vec!["prompt", "starship"], true),
+            (vec!["prompt", "-s", "starship"], false),
+        ];
+        for (args, ok) in cases {
+            assert_eq!(cmd().try_get_matches_from(&args).is_ok(), ok, "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn test_starship_snippet_runs_starship_mode() {
+        assert!(STARSHIP_SNIPPET.contains("[custom.wsp]"));
+        assert!(STARSHIP_SNIPPET.contains(r#"command = "wsp prompt --starship --status""#));
+        // Every table line is a comment or a key = value pair.
+        for line in STARSHIP_SNIPPET.lines() {
+            assert!(
+                line.is_empty()
+                    || line.starts_with('#')
+                    || line.starts_with('[')
+                    || line.contains(" = "),
+                "{}",
+                line
+            );
+        }
+    }
+}
@@ -1,10 +1,13 @@
 use anyhow::Result;
 use clap::{Arg, ArgMatches, Command};
+use clap_complete::engine::ArgValueCandidates;
 
 use crate::config::Paths;
 use crate::gc;
 use crate::output::{MutationOutput, Output, RecoverListOutput, RecoverShowOutput};
 
+use super::completers;
+
 pub fn cmd() -> Command {
     Command::new("recover")
         .about("List, inspect, or restore recently removed workspaces [read-only without args]")
@@ -27,10 +30,19 @@ pub fn cmd() -> Command {
                 .arg(
                     Arg::new("workspace")
                         .required(true)
-                        .help("Name of workspace to inspect"),
+                        .help("Name of workspace to inspect")
+                        .add(ArgValueCandidates::new(
+                            completers::complete_removed_workspaces,
+                        )),
                 ),
         )
-        .arg(Arg::new("workspace").help("Name of workspace to restore"))
+        .arg(
+            Arg::new("workspace")
+                .help("Name of workspace to restore")
+                .add(ArgValueCandidates::new(
+                    completers::complete_removed_workspaces,
+                )),
+        )
 }
 
 fn retention_days(paths: &Paths) -> u32 {
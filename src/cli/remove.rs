@@ -1,10 +1,14 @@
-use anyhow::{Result, bail};
+use anyhow::Result;
 use clap::{Arg, ArgMatches, Command};
 use clap_complete::engine::ArgValueCandidates;
 
 use crate::config::{self, Paths};
+use crate::confirm;
+use crate::error::{self, ErrorKind};
+use crate::filelock;
 use crate::gc;
 use crate::giturl;
+use crate::offline::FetchPolicy;
 use crate::output::{MutationOutput, Output};
 use crate::workspace;
 
@@ -18,7 +22,8 @@ pub fn cmd() -> Command {
             "Remove repo(s) from the current workspace.\n\n\
              Runs the same safety checks as `wsp rm` (pending changes, branch merge status) \
              on each repo before removal. The repo's directory is deleted but the mirror is \
-             kept. Use --force to skip safety checks.",
+             kept. Use --force to skip safety checks, --no-fetch to check merge status \
+             against the last fetch.",
         )
         .arg(
             Arg::new("repos")
@@ -35,25 +40,28 @@ pub fn cmd() -> Command {
                 .action(clap::ArgAction::SetTrue)
                 .help("Remove even if repos have pending changes or unmerged branches"),
         )
+        .arg(super::no_fetch_arg())
 }
 
 pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     let repo_args: Vec<&String> = matches.get_many::<String>("repos").unwrap().collect();
     let force = matches.get_flag("force");
+    let fetch = FetchPolicy::from_flag(matches.get_flag("no-fetch"));
 
     let cwd = std::env::current_dir()?;
     let ws_dir = workspace::detect(&cwd)?;
     gc::check_workspace(&ws_dir, /* read_only */ false)?;
+    let _lock = filelock::lock_workspace(&ws_dir)?;
 
-    let meta = workspace::load_metadata(&ws_dir)
-        .map_err(|e| anyhow::anyhow!("reading workspace: {}", e))?;
+    let meta =
+        workspace::load_metadata(&ws_dir).map_err(|e| error::prefixed(e, "reading workspace"))?;
 
     // Resolve repo args to full identities using workspace repos
     let ws_identities: Vec<String> = meta.repos.keys().cloned().collect();
 
     // Also load config to resolve against registered repos
     let cfg = config::Config::load_from(&paths.config_path)
-        .map_err(|e| anyhow::anyhow!("loading config: {}", e))?;
+        .map_err(|e| error::prefixed(e, "loading config"))?;
     let cfg_identities: Vec<String> = cfg.repos.keys().cloned().collect();
 
     let mut resolved = Vec::new();
@@ -62,25 +70,61 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
         let id = giturl::resolve(rn, &ws_identities)
             .or_else(|_| giturl::resolve(rn, &cfg_identities))?;
         if !meta.repos.contains_key(&id) {
-            bail!("repo {} is not in this workspace", id);
+            return Err(error::tag(
+                ErrorKind::NotFound,
+                anyhow::anyhow!("repo {} is not in this workspace", id),
+            ));
         }
         resolved.push(id);
     }
 
+    if force {
+        let lost: Vec<String> = workspace::repo_removal_states(&ws_dir, &meta, &resolved)
+            .iter()
+            .filter_map(|r| {
+                r.at_risk()
+                    .map(|risk| format!("  - {}: {}", r.identity, risk))
+            })
+            .collect();
+        if !lost.is_empty() {
+            confirm::confirm(
+                matches.get_flag("yes"),
+                &format!(
+                    "Force-removing these clones deletes their branch {} and discards:\n{}",
+                    meta.branch,
+                    lost.join("\n")
+                ),
+                "Remove anyway?",
+            )?;
+        }
+    }
+
     eprintln!("Removing {} repo(s) from workspace...", resolved.len());
-    workspace::remove_repos(&paths.mirrors_dir, &ws_dir, &resolved, force)?;
+    fetch.note_skipped();
+    workspace::remove_repos(
+        &paths.mirrors_dir,
+        &ws_dir,
+        &resolved,
+        force,
+        fetch,
+        super::fetch::Limits::resolve(matches, &cfg).jobs,
+    )?;
 
     let meta_result = workspace::load_metadata(&ws_dir);
     match &meta_result {
         Ok(meta) => crate::lang::run_integrations(&ws_dir, meta, &cfg),
-        Err(e) => eprintln!("warning: skipping language integrations: {}", e),
+        Err(e) => tracing::warn!("skipping language integrations: {}", e),
     }
-    if cfg.agent_md.unwrap_or(true)
-        && let Ok(meta) = &meta_result
+    if let Ok(meta) = &meta_result
+        && meta.apply_workspace_config(&cfg).agent_md.unwrap_or(true)
         && let Err(e) = crate::agentmd::update(&ws_dir, meta)
     {
-        eprintln!("warning: AGENTS.md generation failed: {}", e);
+        tracing::warn!("AGENTS.md generation failed: {}", e);
     }
 
-    Ok(Output::Mutation(MutationOutput::new("Done.")))
+    let mut out = MutationOutput::new("Done.");
+    if !force {
+        out = out.with_fetch_skipped(fetch);
+    }
+    Ok(Output::Mutation(out))
 }
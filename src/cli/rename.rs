@@ -42,7 +42,7 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
         ));
     }
 
-    let new_dir = workspace::dir(&paths.workspaces_dir, new_name);
+    let new_dir = paths.workspace_dir(new_name);
     let new_branch = results
         .first()
         .map(|r| r.new_branch.as_str())
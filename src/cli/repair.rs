@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+use clap::{Arg, ArgMatches, Command};
+use clap_complete::engine::ArgValueCandidates;
+
+use crate::config::{self, Paths};
+use crate::error;
+use crate::filelock;
+use crate::gc;
+use crate::offline::FetchPolicy;
+use crate::output::{MutationOutput, Output};
+use crate::workspace;
+
+use super::completers;
+
+pub fn cmd() -> Command {
+    Command::new("repair")
+        .about("Clone the repos a workspace is missing")
+        .long_about(
+            "Clone the repos a workspace is missing.\n\n\
+             `wsp new --continue-on-error` creates a workspace even when some of its repos \
+             fail to clone, recording those as pending. This command retries each pending \
+             repo: it clones the mirror if it is missing, clones the repo on its branch, \
+             and sets it up the way `wsp repo add` would. Repos that fail again stay \
+             pending with the new error.",
+        )
+        .arg(Arg::new("workspace").add(ArgValueCandidates::new(completers::complete_workspaces)))
+        .arg(super::no_fetch_arg())
+        .arg(
+            Arg::new("no-setup")
+                .long("no-setup")
+                .action(clap::ArgAction::SetTrue)
+                .help("Skip the repaired repos' setup commands"),
+        )
+}
+
+pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    let ws_dir: PathBuf = match matches.get_one::<String>("workspace") {
+        Some(name) => paths.workspace_dir(name),
+        None => workspace::detect(&std::env::current_dir()?)?,
+    };
+    let fetch = FetchPolicy::from_flag(matches.get_flag("no-fetch"));
+
+    gc::check_workspace(&ws_dir, /* read_only */ false)?;
+    let _lock = filelock::lock_workspace(&ws_dir)?;
+
+    let meta =
+        workspace::load_metadata(&ws_dir).map_err(|e| error::prefixed(e, "reading workspace"))?;
+    if meta.pending.is_empty() {
+        return Ok(Output::Mutation(
+            MutationOutput::new(format!("Workspace {:?} has no pending repos", meta.name))
+                .with_workspace(&meta.name, ws_dir.display().to_string(), &meta.branch),
+        ));
+    }
+
+    let cfg = config::Config::load_from(&paths.config_path)
+        .map_err(|e| error::prefixed(e, "loading config"))?;
+    let mut upstream_urls: BTreeMap<String, String> = BTreeMap::new();
+    for identity in meta.pending.keys() {
+        if let Some(url) = cfg.upstream_url(identity) {
+            upstream_urls.insert(identity.clone(), url.to_string());
+        }
+    }
+
+    super::new::prepare_mirrors(
+        paths,
+        meta.pending.keys(),
+        &upstream_urls,
+        fetch,
+        &super::fetch::Limits::resolve(matches, &cfg),
+    );
+
+    eprintln!("Repairing {} pending repos...", meta.pending.len());
+    let mut repaired = Vec::new();
+    let mut failed = BTreeMap::new();
+    for identity in meta.pending.keys() {
+        let one = BTreeMap::from([(identity.clone(), String::new())]);
+        match workspace::add_repos(
+            &paths.mirrors_dir,
+            &ws_dir,
+            &one,
+            &upstream_urls,
+            &meta.branches,
+        ) {
+            Ok(()) => repaired.push(identity.clone()),
+            Err(e) => {
+                eprintln!("  FAIL  {} ({})", identity, e);
+                let err = e.to_string();
+                filelock::with_metadata(&ws_dir, |m| {
+                    m.pending.insert(identity.clone(), err.clone());
+                    Ok(())
+                })?;
+                failed.insert(identity.clone(), err);
+            }
+        }
+    }
+
+    if repaired.is_empty() {
+        bail!(
+            "no pending repo could be cloned: {}; see the errors above",
+            failed.keys().cloned().collect::<Vec<_>>().join(", ")
+        );
+    }
+
+    match workspace::load_metadata(&ws_dir) {
+        Ok(meta) => super::add::set_up_added(
+            &ws_dir,
+            &meta,
+            &cfg,
+            &repaired,
+            matches.get_flag("no-setup"),
+        )?,
+        Err(e) => tracing::warn!("skipping setup of repaired repos: {}", e),
+    }
+
+    let mut out = MutationOutput::new(format!(
+        "Repaired {} repo(s) in workspace {:?}",
+        repaired.len(),
+        meta.name
+    ))
+    .with_workspace(&meta.name, ws_dir.display().to_string(), &meta.branch)
+    .with_fetch_skipped(fetch);
+    if !failed.is_empty() {
+        out = out.with_hint(format!(
+            "{} repo(s) still pending: {}",
+            failed.len(),
+            failed.keys().cloned().collect::<Vec<_>>().join(", ")
+        ));
+    }
+    Ok(Output::Mutation(out.with_pending(&failed)))
+}
@@ -4,13 +4,17 @@ use clap::{Arg, ArgMatches, Command};
 use clap_complete::engine::ArgValueCandidates;
 
 use crate::config::{self, Paths, RepoEntry};
+use crate::confirm;
 use crate::discovery;
+use crate::error::{self, ErrorKind};
 use crate::filelock;
 use crate::giturl;
 use crate::mirror;
 use crate::output::{
     ImportFailure, ImportOutput, MutationOutput, Output, RepoListEntry, RepoListOutput,
 };
+use crate::util;
+use crate::workspace;
 
 use super::completers;
 
@@ -87,10 +91,16 @@ pub fn run_add(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     // Phase 1: pre-check under lock (fast, read-only)
     let snapshot = filelock::read_config(&paths.config_path)?;
     if snapshot.repos.contains_key(&identity) {
-        bail!("repo {} already registered", identity);
+        return Err(error::tag(
+            ErrorKind::Conflict,
+            anyhow::anyhow!("repo {} already registered", identity),
+        ));
     }
     if mirror::exists(&paths.mirrors_dir, &parsed) {
-        bail!("mirror already exists for {}", identity);
+        return Err(error::tag(
+            ErrorKind::Conflict,
+            anyhow::anyhow!("mirror already exists for {}", identity),
+        ));
     }
 
     // Phase 2: clone mirror + initial fetch (slow, no lock held)
@@ -113,6 +123,8 @@ pub fn run_add(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
             RepoEntry {
                 url: raw_url.clone(),
                 added: Utc::now(),
+                setup: None,
+                exec_dir: None,
             },
         );
         Ok(())
@@ -130,7 +142,7 @@ pub fn run_add(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
         let mirror_dir = mirror::dir(&paths.mirrors_dir, &parsed);
         let discovered = discovery::scan_bare_mirror(&mirror_dir, &identity, &paths.templates_dir);
         if let Err(e) = discovery::prompt_and_import(&discovered, &paths.templates_dir) {
-            eprintln!("warning: template discovery failed: {}", e);
+            tracing::warn!("template discovery failed: {}", e);
         }
     }
 
@@ -165,7 +177,7 @@ fn run_add_from(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     } else {
         repos
             .into_iter()
-            .filter(|(name, _url)| patterns.iter().any(|p| glob_match(p, name)))
+            .filter(|(name, _url)| patterns.iter().any(|p| util::glob_match(p, name)))
             .collect()
     };
 
@@ -258,6 +270,8 @@ fn import_repos(
                     RepoEntry {
                         url: cr.url.clone(),
                         added: Utc::now(),
+                        setup: None,
+                        exec_dir: None,
                     },
                 );
                 registered.push(cr.identity.clone());
@@ -278,7 +292,7 @@ fn import_repos(
             }
         }
         if let Err(e) = discovery::prompt_and_import(&all_discovered, &paths.templates_dir) {
-            eprintln!("warning: template discovery failed: {}", e);
+            tracing::warn!("template discovery failed: {}", e);
         }
     }
 
@@ -324,8 +338,10 @@ fn parse_from_arg(from: &str) -> Result<(String, String)> {
 }
 
 fn gh_list_repos(owner: &str, use_https: bool) -> Result<Vec<(String, String)>> {
+    crate::offline::ensure_online("listing the org's repos")?;
     let limit = 1000;
-    let output = std::process::Command::new("gh")
+    let output = crate::forge::Forge::Github
+        .command("github.com")
         .args([
             "repo",
             "list",
@@ -370,36 +386,9 @@ fn gh_list_repos(owner: &str, use_https: bool) -> Result<Vec<(String, String)>>
     Ok(repos)
 }
 
-fn glob_match(pattern: &str, name: &str) -> bool {
-    let parts: Vec<&str> = pattern.split('*').collect();
-    if parts.len() == 1 {
-        return pattern == name;
-    }
-    let mut pos = 0;
-    for (i, part) in parts.iter().enumerate() {
-        if part.is_empty() {
-            continue;
-        }
-        match name[pos..].find(part) {
-            Some(idx) => {
-                if i == 0 && idx != 0 {
-                    return false;
-                }
-                pos += idx + part.len();
-            }
-            None => return false,
-        }
-    }
-    if !pattern.ends_with('*') {
-        pos == name.len()
-    } else {
-        true
-    }
-}
-
 pub fn run_list(_matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     let cfg = config::Config::load_from(&paths.config_path)
-        .map_err(|e| anyhow::anyhow!("loading config: {}", e))?;
+        .map_err(|e| error::prefixed(e, "loading config"))?;
 
     let mut identities: Vec<String> = cfg.repos.keys().cloned().collect();
     identities.sort();
@@ -432,6 +421,28 @@ pub fn run_remove(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     let entry = &snapshot.repos[&identity];
     let parsed = giturl::parse(&entry.url)?;
 
+    let mirror_dir = mirror::dir(&paths.mirrors_dir, &parsed);
+    if mirror_dir.exists() {
+        confirm::confirm(
+            matches.get_flag("yes"),
+            &format!(
+                "Unregistering {} deletes its mirror:\n  {}\n\
+                 Workspaces keep their clones, but can no longer fetch through the mirror.",
+                identity,
+                mirror_dir.display()
+            ),
+            "Remove it?",
+        )?;
+    }
+
+    // Workspace clones of its forks may borrow objects from the mirror;
+    // give them their own copies while there's still time to back out.
+    for clone in borrowing_clones(paths, &mirror_dir) {
+        eprintln!("Copying borrowed objects into {}...", clone.display());
+        mirror::dissociate(&clone)
+            .map_err(|e| anyhow::anyhow!("copying objects into {}: {}", clone.display(), e))?;
+    }
+
     // Phase 2: unregister under lock (fast) — before mirror deletion so that
     // a crash between phases leaves config clean rather than orphaned.
     filelock::with_config(&paths.config_path, |cfg| {
@@ -450,44 +461,28 @@ pub fn run_remove(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     ))))
 }
 
+/// The workspace clones that borrow objects from the mirror at `mirror_dir`.
+fn borrowing_clones(paths: &Paths, mirror_dir: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let mut clones = Vec::new();
+    for name in workspace::list_all(paths).unwrap_or_default() {
+        let ws_dir = paths.workspace_dir(&name);
+        let Ok(meta) = workspace::load_metadata(&ws_dir) else {
+            continue;
+        };
+        clones.extend(
+            meta.repos
+                .keys()
+                .filter_map(|id| meta.dir_name(id).ok())
+                .map(|dn| ws_dir.join(dn)),
+        );
+    }
+    mirror::borrowers(mirror_dir, clones)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_glob_match() {
-        let cases = vec![
-            ("prefix wildcard", "api-*", "api-gateway", true),
-            ("prefix wildcard 2", "api-*", "api-v2", true),
-            ("prefix no match", "api-*", "user-api", false),
-            ("suffix wildcard", "*-service", "user-service", true),
-            ("suffix no match", "*-service", "service-mesh", false),
-            ("contains wildcard", "*core*", "core", true),
-            ("contains wildcard 2", "*core*", "core-lib", true),
-            ("contains wildcard 3", "*core*", "my-core-service", true),
-            ("exact match", "exact", "exact", true),
-            ("exact no match", "exact", "exactly", false),
-            ("match all", "*", "anything", true),
-            ("match all empty", "*", "", true),
-            ("empty pattern", "", "", true),
-            ("empty pattern no match", "", "x", false),
-            ("multi wildcard", "a*b*c", "aXbYc", true),
-            ("multi wildcard no match", "a*b*c", "aXYc", false),
-            ("anchored start", "api-*", "xapi-foo", false),
-            ("anchored end", "*-api", "api-x", false),
-        ];
-        for (name, pattern, input, want) in cases {
-            assert_eq!(
-                glob_match(pattern, input),
-                want,
-                "{}: glob_match({:?}, {:?})",
-                name,
-                pattern,
-                input
-            );
-        }
-    }
-
     #[test]
     fn test_parse_from_arg() {
         let cases = vec![
@@ -2,6 +2,7 @@ use anyhow::Result;
 use clap::{ArgMatches, Command};
 
 use crate::config::Paths;
+use crate::error;
 use crate::gc;
 use crate::giturl;
 use crate::output::{Output, WorkspaceRepoListEntry, WorkspaceRepoListOutput};
@@ -23,8 +24,8 @@ pub fn run(_matches: &ArgMatches, _paths: &Paths) -> Result<Output> {
 
     gc::check_workspace(&ws_dir, /* read_only */ true)?;
 
-    let meta = workspace::load_metadata(&ws_dir)
-        .map_err(|e| anyhow::anyhow!("reading workspace: {}", e))?;
+    let meta =
+        workspace::load_metadata(&ws_dir).map_err(|e| error::prefixed(e, "reading workspace"))?;
 
     let identities: Vec<String> = meta.repos.keys().cloned().collect();
     let shortnames = giturl::shortnames(&identities);
@@ -36,7 +37,7 @@ pub fn run(_matches: &ArgMatches, _paths: &Paths) -> Result<Output> {
             let dir_name = match meta.dir_name(id) {
                 Ok(d) => d,
                 Err(e) => {
-                    eprintln!("  warning: cannot resolve dir for {}: {}", id, e);
+                    tracing::warn!("  cannot resolve dir for {}: {}", id, e);
                     String::new()
                 }
             };
@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use anyhow::{Result, bail};
+use clap::{Arg, ArgMatches, Command};
+
+use crate::config::{self, Paths};
+use crate::error::{self, ErrorKind};
+use crate::forge::PrUrl;
+use crate::git;
+use crate::giturl;
+use crate::offline::FetchPolicy;
+use crate::output::{MutationOutput, Output};
+use crate::template;
+use crate::workspace;
+
+pub fn cmd() -> Command {
+    Command::new("review")
+        .about("Create a workspace for reviewing pull requests")
+        .long_about(
+            "Create a workspace for reviewing pull requests.\n\n\
+             Takes GitHub PR or GitLab MR URLs, one per repo of a cross-repo change. Each \
+             repo is registered if needed and cloned into a new workspace whose branch \
+             starts at the PR's head commit, fetched from the forge's PR ref so PRs from \
+             forks work too. `wsp diff` and `wsp log` then show exactly what each PR \
+             changes against its default branch.\n\n\
+             The workspace is named review-<repo>-<number> after the first URL unless \
+             --name is given; its description lists the URLs.",
+        )
+        .arg(
+            Arg::new("urls")
+                .value_name("PR_URL")
+                .required(true)
+                .num_args(1..),
+        )
+        .arg(
+            Arg::new("name")
+                .short('n')
+                .long("name")
+                .help("Workspace name [default: review-<repo>-<number>]"),
+        )
+        .arg(super::no_fetch_arg())
+        .arg(
+            Arg::new("no-setup")
+                .long("no-setup")
+                .action(clap::ArgAction::SetTrue)
+                .help("Skip the repos' setup commands"),
+        )
+}
+
+/// A PR resolved to the registered repo it targets.
+struct Target {
+    url: String,
+    pr: PrUrl,
+    identity: String,
+}
+
+pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    crate::offline::ensure_online("fetching pull requests")?;
+    let urls: Vec<&str> = matches
+        .get_many::<String>("urls")
+        .unwrap()
+        .map(|s| s.as_str())
+        .collect();
+
+    let mut targets: Vec<Target> = Vec::new();
+    for url in &urls {
+        let pr = PrUrl::parse(url)?;
+        let identity = giturl::parse(&pr.repo_url)?.identity();
+        if let Some(other) = targets.iter().find(|t| t.identity == identity) {
+            bail!(
+                "{} and {} are both in {}; a workspace holds one branch per repo",
+                other.url,
+                url,
+                identity
+            );
+        }
+        targets.push(Target {
+            url: url.to_string(),
+            pr,
+            identity,
+        });
+    }
+
+    let ws_name = match matches.get_one::<String>("name") {
+        Some(name) => name.clone(),
+        None => {
+            let first = &targets[0];
+            let repo = giturl::Parsed::from_identity(&first.identity)?.repo;
+            format!("review-{}-{}", repo, first.pr.number)
+        }
+    };
+    workspace::validate_name(&ws_name)?;
+    let ws_dir = paths.workspace_dir(&ws_name);
+    if ws_dir.exists() {
+        return Err(error::tag(
+            ErrorKind::Conflict,
+            anyhow::anyhow!("workspace {:?} already exists", ws_name),
+        ));
+    }
+
+    let mut cfg = config::Config::load_from(&paths.config_path)
+        .map_err(|e| error::prefixed(e, "loading config"))?;
+    let repo_urls: Vec<&str> = targets.iter().map(|t| t.pr.repo_url.as_str()).collect();
+    template::register_urls(&repo_urls, "from PR URLs", &mut cfg, paths)?;
+
+    let repo_refs: BTreeMap<String, String> = targets
+        .iter()
+        .map(|t| (t.identity.clone(), String::new()))
+        .collect();
+    let upstream_urls: BTreeMap<String, String> = repo_refs
+        .keys()
+        .filter_map(|id| Some((id.clone(), cfg.upstream_url(id)?.to_string())))
+        .collect();
+
+    let branches = workspace::Branches::plan(&cfg, &ws_name, repo_refs.keys())?;
+
+    let start = Instant::now();
+    let fetch = FetchPolicy::from_flag(matches.get_flag("no-fetch"));
+    if !fetch.fetches() {
+        fetch.note_skipped();
+    }
+    super::new::prepare_mirrors(
+        paths,
+        repo_refs.keys(),
+        &upstream_urls,
+        fetch,
+        &super::fetch::Limits::resolve(matches, &cfg),
+    );
+
+    let description = format!("Review {}", urls.join(" "));
+    eprintln!(
+        "Creating workspace {:?} to review {} PR{}...",
+        ws_name,
+        targets.len(),
+        if targets.len() == 1 { "" } else { "s" }
+    );
+    workspace::create(
+        paths,
+        &ws_name,
+        &repo_refs,
+        &branches,
+        &upstream_urls,
+        &workspace::CreateOpts {
+            description: Some(&description),
+            created_from: Some("review"),
+            ..Default::default()
+        },
+    )?;
+
+    // Nothing has been set up in the clones yet, so a failed checkout
+    // leaves nothing worth keeping.
+    if let Err(e) = checkout_heads(&ws_dir, &targets) {
+        let _ = std::fs::remove_dir_all(&ws_dir);
+        return Err(e);
+    }
+
+    super::new::finish(
+        paths,
+        &cfg,
+        &ws_dir,
+        None,
+        matches.get_flag("no-setup"),
+        /* no_discover */ true,
+    )?;
+
+    let meta = workspace::load_metadata(&ws_dir)?;
+    Ok(Output::Mutation(
+        MutationOutput::new(format!("Workspace created: {}", ws_dir.display()))
+            .with_duration(start.elapsed().as_millis() as u64)
+            .with_workspace(&ws_name, ws_dir.display().to_string(), &meta.branch),
+    ))
+}
+
+/// Move each clone's workspace branch to its PR's head commit.
+fn checkout_heads(ws_dir: &std::path::Path, targets: &[Target]) -> Result<()> {
+    let meta = workspace::load_metadata(ws_dir)?;
+    for t in targets {
+        let clone_dir = ws_dir.join(meta.dir_name(&t.identity)?);
+        let head_ref = t.pr.head_ref();
+        git::run(Some(&clone_dir), &["fetch", "--quiet", "origin", &head_ref])
+            .map_err(|e| anyhow::anyhow!("fetching {} for {}: {}", head_ref, t.url, e))?;
+        git::run(
+            Some(&clone_dir),
+            &["reset", "--quiet", "--hard", "FETCH_HEAD"],
+        )?;
+        let sha = git::run(Some(&clone_dir), &["rev-parse", "--short", "HEAD"])?;
+        eprintln!("  {}  {} at {}", t.identity, t.url, sha.trim());
+    }
+    Ok(())
+}
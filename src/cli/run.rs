@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Instant;
+
+use anyhow::{Result, bail};
+use clap::{Arg, ArgMatches, Command};
+use clap_complete::engine::ArgValueCandidates;
+
+use crate::config::{self, Paths};
+use crate::deps;
+use crate::error;
+use crate::output::{ExecRepoResult, Format, Output, RunOutput, RunRepoResult};
+use crate::task::{self, TaskNode};
+use crate::workspace;
+
+use super::completers;
+use super::exec;
+
+pub fn cmd() -> Command {
+    Command::new("run")
+        .about("Run a named task in each repo of a workspace")
+        .long_about(
+            "Run a named task in each repo of a workspace.\n\n\
+             Tasks are named command presets defined under `tasks` in config.yaml (global) \
+             or in the workspace's .wsp.yaml config (overrides global by name). Define one \
+             with `wsp config set task.test \"make test\"`. A task can override its command \
+             per repo in YAML (`repos: {web: npm test}`); an empty override skips that repo.\n\n\
+             A task can depend on tasks in other repos (`depends_on: {api: [proto:gen]}`), \
+             and repo-level `depends-on.<repo>` settings make each repo's task wait for the \
+             same task in the repos it depends on. \
+             Dependencies run first; independent repos run in parallel (up to --jobs). When a \
+             dependency fails, everything downstream of it is skipped. A summary of each \
+             task, its status, and its duration is printed at the end.\n\n\
+             Commands run through the shell, in each repo's exec-dir if one is configured. \
+             The workspace name is optional when running from inside a workspace directory.",
+        )
+        .arg(
+            Arg::new("task")
+                .required(true)
+                .add(ArgValueCandidates::new(completers::complete_tasks)),
+        )
+        .arg(
+            Arg::new("workspace")
+                .required(false)
+                .add(ArgValueCandidates::new(completers::complete_workspaces)),
+        )
+        .arg(
+            Arg::new("jobs")
+                .short('j')
+                .long("jobs")
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize))
+                .help("Run up to N repos at once [default: run.jobs, else number of CPUs; 1 streams output live]"),
+        )
+}
+
+pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    let task_name = matches.get_one::<String>("task").unwrap();
+    let is_json = Format::from_matches(matches).is_structured();
+
+    let ws_dir: PathBuf = if let Some(name) = matches.get_one::<String>("workspace") {
+        paths.workspace_dir(name)
+    } else {
+        let cwd = std::env::current_dir()?;
+        workspace::detect(&cwd)?
+    };
+    let meta =
+        workspace::load_metadata(&ws_dir).map_err(|e| error::prefixed(e, "reading workspace"))?;
+    let cfg = config::Config::load_from(&paths.config_path)?;
+    let effective = meta.apply_workspace_config(&cfg);
+
+    let tasks = effective.tasks.unwrap_or_default();
+    if !tasks.contains_key(task_name) {
+        if tasks.is_empty() {
+            bail!(
+                "unknown task {:?}; no tasks defined (add one with: wsp config set task.<name> <command>)",
+                task_name
+            );
+        }
+        let names: Vec<&str> = tasks.keys().map(|s| s.as_str()).collect();
+        bail!(
+            "unknown task {:?}; available: {}",
+            task_name,
+            names.join(", ")
+        );
+    }
+
+    let identities: Vec<String> = meta.repos.keys().cloned().collect();
+    let repo_deps = effective.depends_on.as_ref();
+    deps::order(repo_deps, &identities)?;
+    let plan = task::plan(&tasks, task_name, &identities, repo_deps)?;
+    let subdirs = exec::resolve_subdirs(&meta, &cfg, &[])?;
+    let jobs = matches
+        .get_one::<usize>("jobs")
+        .copied()
+        .or(effective.run_jobs)
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()))
+        .max(1);
+
+    let runner = Runner {
+        ws_dir: &ws_dir,
+        meta: &meta,
+        plan: &plan,
+        subdirs: &subdirs,
+        capture: is_json || jobs > 1,
+        quiet: is_json,
+    };
+    let results = runner.run(jobs);
+
+    Ok(Output::Run(RunOutput {
+        workspace: meta.name,
+        task: task_name.clone(),
+        repos: results,
+    }))
+}
+
+struct Runner<'a> {
+    ws_dir: &'a Path,
+    meta: &'a workspace::Metadata,
+    plan: &'a task::Plan,
+    subdirs: &'a BTreeMap<String, String>,
+    /// Capture each command's output instead of streaming it.
+    capture: bool,
+    /// Don't print captured output (JSON mode).
+    quiet: bool,
+}
+
+enum NodeState {
+    Pending,
+    Running,
+    Done(bool),
+}
+
+impl Runner<'_> {
+    /// Execute the plan, starting each node once all of its dependencies have
+    /// succeeded and at most `jobs` at a time. Results come back in plan order.
+    fn run(&self, jobs: usize) -> Vec<RunRepoResult> {
+        let mut state: BTreeMap<&TaskNode, NodeState> = self
+            .plan
+            .order
+            .iter()
+            .map(|n| (n, NodeState::Pending))
+            .collect();
+        let mut results: BTreeMap<&TaskNode, RunRepoResult> = BTreeMap::new();
+
+        std::thread::scope(|s| {
+            let (tx, rx) = mpsc::channel();
+            let mut running = 0;
+            loop {
+                for node in &self.plan.order {
+                    if !matches!(state[node], NodeState::Pending) {
+                        continue;
+                    }
+                    let mut blocked = false;
+                    let mut failed = false;
+                    for dep in self.plan.dag.deps(node) {
+                        match state[dep] {
+                            NodeState::Done(true) => {}
+                            NodeState::Done(false) => failed = true,
+                            _ => blocked = true,
+                        }
+                    }
+                    if failed {
+                        state.insert(node, NodeState::Done(false));
+                        results.insert(node, self.skipped(node));
+                    } else if !blocked && running < jobs {
+                        state.insert(node, NodeState::Running);
+                        running += 1;
+                        if jobs == 1 {
+                            let r = self.exec(node);
+                            tx.send((node, r)).expect("receiver outlives sender");
+                        } else {
+                            let tx = tx.clone();
+                            s.spawn(move || {
+                                let r = self.exec(node);
+                                let _ = tx.send((node, r));
+                            });
+                        }
+                    }
+                }
+                if running == 0 {
+                    break;
+                }
+                let (node, r) = rx.recv().expect("a running task reports back");
+                running -= 1;
+                if self.capture && !self.quiet {
+                    self.print_captured(node, &r);
+                }
+                state.insert(node, NodeState::Done(r.status == "ok"));
+                results.insert(node, r);
+            }
+        });
+
+        self.plan
+            .order
+            .iter()
+            .filter_map(|n| results.remove(n))
+            .collect()
+    }
+
+    fn exec(&self, node: &TaskNode) -> RunRepoResult {
+        let command = &self.plan.commands[node];
+        let start = Instant::now();
+        let result = exec::exec_in_repo(
+            self.ws_dir,
+            self.meta,
+            &node.identity,
+            &task::shell_argv(command),
+            command,
+            self.subdirs.get(&node.identity).map(|s| s.as_str()),
+            self.capture,
+        );
+        RunRepoResult {
+            task: node.task.clone(),
+            status: if result.ok { "ok" } else { "failed" }.into(),
+            duration_ms: start.elapsed().as_millis() as u64,
+            depends_on: self.deps(node),
+            result,
+        }
+    }
+
+    fn skipped(&self, node: &TaskNode) -> RunRepoResult {
+        RunRepoResult {
+            task: node.task.clone(),
+            status: "skipped".into(),
+            duration_ms: 0,
+            depends_on: self.deps(node),
+            result: ExecRepoResult {
+                identity: node.identity.clone(),
+                shortname: self
+                    .meta
+                    .dir_name(&node.identity)
+                    .unwrap_or_else(|_| node.identity.clone()),
+                path: String::new(),
+                directory: String::new(),
+                exit_code: -1,
+                ok: false,
+                stdout: None,
+                stderr: None,
+                error: Some("skipped: a dependency failed".into()),
+            },
+        }
+    }
+
+    fn deps(&self, node: &TaskNode) -> Vec<String> {
+        self.plan.dag.deps(node).map(|d| d.to_string()).collect()
+    }
+
+    /// Print one node's captured output as a block so parallel runs don't
+    /// interleave.
+    fn print_captured(&self, node: &TaskNode, r: &RunRepoResult) {
+        let label = match self.subdirs.get(&node.identity) {
+            Some(sub) => format!("{}/{}", r.result.shortname, sub),
+            None => r.result.shortname.clone(),
+        };
+        println!("==> [{}] {}", label, self.plan.commands[node]);
+        if let Some(out) = &r.result.stdout {
+            print!("{}", out);
+        }
+        if let Some(err) = &r.result.stderr {
+            eprint!("{}", err);
+        }
+        if let Some(e) = &r.result.error {
+            eprintln!("[{}] error: {}", label, e);
+        } else if !r.result.ok {
+            eprintln!("[{}] error: exit status {}", label, r.result.exit_code);
+        }
+        println!();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_args() {
+        let cases = vec![
+            ("task only", vec!["run", "test"], "test", None),
+            (
+                "task and workspace",
+                vec!["run", "fmt", "my-ws"],
+                "fmt",
+                Some("my-ws"),
+            ),
+            ("jobs flag", vec!["run", "-j", "4", "build"], "build", None),
+        ];
+        for (name, args, want_task, want_ws) in cases {
+            let m = cmd().get_matches_from(args);
+            assert_eq!(
+                m.get_one::<String>("task").map(|s| s.as_str()),
+                Some(want_task),
+                "{}",
+                name
+            );
+            assert_eq!(
+                m.get_one::<String>("workspace").map(|s| s.as_str()),
+                want_ws,
+                "{}",
+                name
+            );
+        }
+    }
+}
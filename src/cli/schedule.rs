@@ -0,0 +1,404 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use clap::{Arg, ArgMatches, Command};
+
+use crate::config::Paths;
+use crate::output::{MutationOutput, Output, ScheduleStatusOutput};
+use crate::util::xml_escape;
+
+/// Name of the systemd units and the launchd job label's last part.
+const UNIT: &str = "wsp-fetch";
+const LAUNCHD_LABEL: &str = "dev.wsp.fetch";
+
+/// Environment a scheduled fetch needs from the installing shell: `PATH` to
+/// find git (service managers start with a minimal one), and the variables
+/// that move wsp's config and data directories.
+const PASSED_ENV: &[&str] = &["PATH", "XDG_CONFIG_HOME", "XDG_DATA_HOME", "WSP_PROFILE"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scheduler {
+    Systemd,
+    Launchd,
+}
+
+impl Scheduler {
+    fn current() -> Result<Self> {
+        if cfg!(target_os = "macos") {
+            Ok(Scheduler::Launchd)
+        } else if cfg!(target_os = "linux") {
+            Ok(Scheduler::Systemd)
+        } else {
+            bail!("scheduled fetches are supported with systemd (Linux) and launchd (macOS)")
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Scheduler::Systemd => "systemd",
+            Scheduler::Launchd => "launchd",
+        }
+    }
+
+    /// The file that defines the schedule: the timer unit or the plist.
+    fn path(self) -> Result<PathBuf> {
+        match self {
+            Scheduler::Systemd => {
+                let config = dirs::config_dir().context("cannot determine config directory")?;
+                Ok(config.join("systemd/user").join(format!("{}.timer", UNIT)))
+            }
+            Scheduler::Launchd => {
+                let home = dirs::home_dir().context("cannot determine home directory")?;
+                Ok(home
+                    .join("Library/LaunchAgents")
+                    .join(format!("{}.plist", LAUNCHD_LABEL)))
+            }
+        }
+    }
+}
+
+pub fn cmd() -> Command {
+    Command::new("schedule")
+        .about("Fetch mirrors on a schedule with systemd or launchd")
+        .long_about(
+            "Fetch mirrors on a schedule with systemd or launchd.\n\n\
+             `install` writes a systemd user timer (Linux) or a launchd agent (macOS) \
+             that runs `wsp repo fetch --all --prune` every --interval minutes, and \
+             enables it. `uninstall` disables and removes it; `status` shows whether \
+             it is installed and running.\n\n\
+             The job runs this wsp binary with the PATH, XDG_CONFIG_HOME, XDG_DATA_HOME and WSP_PROFILE \
+             of the shell that installed it. Fetching over SSH needs a key usable without an agent \
+             prompt; HTTPS remotes with a credential helper work as-is.",
+        )
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("install")
+                .about("Install and enable the scheduled fetch")
+                .arg(
+                    Arg::new("interval")
+                        .long("interval")
+                        .value_name("MINUTES")
+                        .help("Minutes between fetches")
+                        .value_parser(clap::value_parser!(u64).range(1..))
+                        .default_value("30"),
+                ),
+        )
+        .subcommand(Command::new("uninstall").about("Disable and remove the scheduled fetch"))
+        .subcommand(
+            Command::new("status")
+                .about("Show whether the scheduled fetch is installed [read-only]"),
+        )
+}
+
+pub fn run(matches: &ArgMatches, _paths: &Paths) -> Result<Output> {
+    let scheduler = Scheduler::current()?;
+    match matches.subcommand() {
+        Some(("install", m)) => install(scheduler, *m.get_one::<u64>("interval").unwrap()),
+        Some(("uninstall", _)) => uninstall(scheduler),
+        Some(("status", _)) => status(scheduler),
+        _ => unreachable!(),
+    }
+}
+
+fn install(scheduler: Scheduler, interval: u64) -> Result<Output> {
+    let bin = std::env::current_exe().context("locating the wsp binary")?;
+    let env: Vec<(String, String)> = PASSED_ENV
+        .iter()
+        .filter_map(|k| std::env::var(k).ok().map(|v| (k.to_string(), v)))
+        .collect();
+    let path = scheduler.path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
+    }
+
+    match scheduler {
+        Scheduler::Systemd => {
+            let (service, timer) = systemd_units(&bin, interval, &env);
+            fs::write(path.with_extension("service"), service)?;
+            fs::write(&path, timer)?;
+            run_tool("systemctl", &["--user", "daemon-reload"])?;
+            run_tool(
+                "systemctl",
+                &["--user", "enable", "--now", &format!("{}.timer", UNIT)],
+            )?;
+        }
+        Scheduler::Launchd => {
+            // Reloading picks up a changed interval on reinstall.
+            let plist = path.to_string_lossy().into_owned();
+            if path.exists() {
+                let _ = run_tool("launchctl", &["unload", &plist]);
+            }
+            fs::write(&path, launchd_plist(&bin, interval, &env))?;
+            run_tool("launchctl", &["load", "-w", &plist])?;
+        }
+    }
+
+    Ok(Output::Mutation(
+        MutationOutput::new(format!(
+            "scheduled `wsp repo fetch --all --prune` every {} min ({})",
+            interval,
+            scheduler.name()
+        ))
+        .with_hint("check on it with `wsp setup schedule status`"),
+    ))
+}
+
+fn uninstall(scheduler: Scheduler) -> Result<Output> {
+    let path = scheduler.path()?;
+    if !path.exists() {
+        return Ok(Output::Mutation(MutationOutput::new(
+            "no scheduled fetch installed",
+        )));
+    }
+    match scheduler {
+        Scheduler::Systemd => {
+            let _ = run_tool(
+                "systemctl",
+                &["--user", "disable", "--now", &format!("{}.timer", UNIT)],
+            );
+            fs::remove_file(&path)?;
+            let service = path.with_extension("service");
+            if service.exists() {
+                fs::remove_file(service)?;
+            }
+            let _ = run_tool("systemctl", &["--user", "daemon-reload"]);
+        }
+        Scheduler::Launchd => {
+            let _ = run_tool("launchctl", &["unload", "-w", &path.to_string_lossy()]);
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(Output::Mutation(MutationOutput::new(format!(
+        "removed scheduled fetch ({})",
+        scheduler.name()
+    ))))
+}
+
+fn status(scheduler: Scheduler) -> Result<Output> {
+    let path = scheduler.path()?;
+    let content = fs::read_to_string(&path).ok();
+    let active = match (scheduler, &content) {
+        (_, None) => None,
+        (Scheduler::Systemd, Some(_)) => std::process::Command::new("systemctl")
+            .args(["--user", "is-active", "--quiet", &format!("{}.timer", UNIT)])
+            .stderr(std::process::Stdio::null())
+            .status()
+            .ok()
+            .map(|s| s.success()),
+        (Scheduler::Launchd, Some(_)) => std::process::Command::new("launchctl")
+            .args(["list", LAUNCHD_LABEL])
+            .output()
+            .ok()
+            .map(|o| o.status.success()),
+    };
+    Ok(Output::ScheduleStatus(ScheduleStatusOutput {
+        scheduler: scheduler.name().into(),
+        installed: content.is_some(),
+        path: path.display().to_string(),
+        interval_minutes: content
+            .as_deref()
+            .and_then(|c| parse_interval(scheduler, c)),
+        active,
+    }))
+}
+
+fn run_tool(program: &str, args: &[&str]) -> Result<()> {
+    let out = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("running {}", program))?;
+    if !out.status.success() {
+        bail!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// The fetch `wsp` runs on each tick.
+fn fetch_args(bin: &Path) -> Vec<String> {
+    vec![
+        bin.display().to_string(),
+        "repo".into(),
+        "fetch".into(),
+        "--all".into(),
+        "--prune".into(),
+    ]
+}
+
+/// `(service, timer)` unit files. The timer also fires shortly after login
+/// and catches up on runs missed while the machine was asleep.
+fn systemd_units(bin: &Path, interval: u64, env: &[(String, String)]) -> (String, String) {
+    let mut service = String::from(
+        "# Generated by `wsp setup schedule install`.\n\
+         [Unit]\n\
+         Description=Fetch wsp mirrors\n\n\
+         [Service]\n\
+         Type=oneshot\n",
+    );
+    for (k, v) in env {
+        service.push_str(&format!("Environment=\"{}={}\"\n", k, systemd_quoted(v)));
+    }
+    let exec: Vec<String> = fetch_args(bin).iter().map(|a| systemd_escape(a)).collect();
+    service.push_str(&format!("ExecStart={}\n", exec.join(" ")));
+
+    let timer = format!(
+        "# Generated by `wsp setup schedule install`.\n\
+         [Unit]\n\
+         Description=Fetch wsp mirrors every {interval} min\n\n\
+         [Timer]\n\
+         OnStartupSec=2min\n\
+         OnUnitActiveSec={interval}min\n\
+         Persistent=true\n\n\
+         [Install]\n\
+         WantedBy=timers.target\n"
+    );
+    (service, timer)
+}
+
+/// Quote a value for a systemd unit: `%` is a specifier prefix, and
+/// whitespace or quotes need the word double-quoted.
+fn systemd_escape(s: &str) -> String {
+    let s = s.replace('%', "%%");
+    if s.chars()
+        .any(|c| c.is_whitespace() || c == '"' || c == '\\')
+    {
+        format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        s
+    }
+}
+
+/// Escape text going inside a double-quoted systemd word, like the
+/// `"K=v"` of `Environment=`.
+fn systemd_quoted(s: &str) -> String {
+    s.replace('%', "%%")
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn launchd_plist(bin: &Path, interval: u64, env: &[(String, String)]) -> String {
+    let mut out = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+         <!-- Generated by `wsp setup schedule install`. -->\n\
+         <plist version=\"1.0\">\n\
+         <dict>\n\
+         \x20 <key>Label</key>\n\
+         \x20 <string>{LAUNCHD_LABEL}</string>\n\
+         \x20 <key>ProgramArguments</key>\n\
+         \x20 <array>\n"
+    );
+    for arg in fetch_args(bin) {
+        out.push_str(&format!("    <string>{}</string>\n", xml_escape(&arg)));
+    }
+    out.push_str("  </array>\n");
+    if !env.is_empty() {
+        out.push_str("  <key>EnvironmentVariables</key>\n  <dict>\n");
+        for (k, v) in env {
+            out.push_str(&format!(
+                "    <key>{}</key>\n    <string>{}</string>\n",
+                xml_escape(k),
+                xml_escape(v)
+            ));
+        }
+        out.push_str("  </dict>\n");
+    }
+    out.push_str(&format!(
+        "  <key>StartInterval</key>\n  <integer>{}</integer>\n\
+         \x20 <key>RunAtLoad</key>\n  <true/>\n\
+         </dict>\n</plist>\n",
+        interval * 60
+    ));
+    out
+}
+
+/// Minutes between runs, read back from an installed timer or plist.
+fn parse_interval(scheduler: Scheduler, content: &str) -> Option<u64> {
+    match scheduler {
+        Scheduler::Systemd => content
+            .lines()
+            .find_map(|l| l.strip_prefix("OnUnitActiveSec="))
+            .and_then(|v| v.trim().strip_suffix("min"))
+            .and_then(|v| v.parse().ok()),
+        Scheduler::Launchd => {
+            let (_, rest) = content.split_once("<key>StartInterval</key>")?;
+            let (_, rest) = rest.split_once("<integer>")?;
+            let (secs, _) = rest.split_once("</integer>")?;
+            secs.trim().parse::<u64>().ok().map(|s| s / 60)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env() -> Vec<(String, String)> {
+        vec![("PATH".into(), "/opt/my tools/bin:/usr/bin".into())]
+    }
+
+    #[test]
+    fn test_systemd_units() {
+        let (service, timer) = systemd_units(Path::new("/home/me/.cargo/bin/wsp"), 15, &env());
+        assert!(service.contains("Environment=\"PATH=/opt/my tools/bin:/usr/bin\"\n"));
+        assert!(service.contains("ExecStart=/home/me/.cargo/bin/wsp repo fetch --all --prune\n"));
+        assert!(timer.contains("OnUnitActiveSec=15min\n"));
+        assert_eq!(parse_interval(Scheduler::Systemd, &timer), Some(15));
+    }
+
+    #[test]
+    fn test_systemd_quoted() {
+        let cases = vec![
+            ("plain", "/usr/bin", "/usr/bin"),
+            ("space", "/opt/my tools", "/opt/my tools"),
+            ("quote", r#"a"b"#, r#"a\"b"#),
+            ("backslash", r"a\b", r"a\\b"),
+            ("specifier", "50%", "50%%"),
+        ];
+        for (name, input, want) in cases {
+            assert_eq!(systemd_quoted(input), want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_launchd_plist() {
+        let plist = launchd_plist(Path::new("/Users/me/bin/wsp"), 30, &env());
+        assert!(plist.contains("<string>dev.wsp.fetch</string>"));
+        assert!(
+            plist.contains("    <string>/Users/me/bin/wsp</string>\n    <string>repo</string>\n")
+        );
+        assert!(plist.contains("<key>PATH</key>\n    <string>/opt/my tools/bin:/usr/bin</string>"));
+        assert!(plist.contains("<integer>1800</integer>"));
+        assert_eq!(parse_interval(Scheduler::Launchd, &plist), Some(30));
+    }
+
+    #[test]
+    fn test_systemd_escape() {
+        let cases = vec![
+            ("/usr/bin/wsp", "/usr/bin/wsp"),
+            ("/opt/my tools/wsp", "\"/opt/my tools/wsp\""),
+            ("100%", "100%%"),
+            ("say \"hi\"", "\"say \\\"hi\\\"\""),
+        ];
+        for (input, want) in cases {
+            assert_eq!(systemd_escape(input), want, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_interval_rejects_foreign_files() {
+        let cases = vec![
+            (Scheduler::Systemd, "[Timer]\nOnCalendar=daily\n"),
+            (Scheduler::Launchd, "<plist><dict></dict></plist>"),
+        ];
+        for (scheduler, content) in cases {
+            assert_eq!(parse_interval(scheduler, content), None, "{:?}", scheduler);
+        }
+    }
+}
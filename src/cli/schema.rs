@@ -0,0 +1,201 @@
+use anyhow::{Result, anyhow};
+use clap::{Arg, ArgMatches, Command};
+use clap_complete::engine::{ArgValueCandidates, CompletionCandidate};
+use schemars::generate::SchemaSettings;
+use schemars::{JsonSchema, Schema};
+
+use crate::config::Paths;
+use crate::error::{self, ErrorKind};
+use crate::output::{
+    AuthStatusOutput, ConfigGetOutput, ConfigListOutput, ContextOutput, DepsGraphOutput,
+    DiffOutput, ErrorOutput, ExecOutput, FetchOutput, FixOutput, ImportOutput, InfoOutput,
+    LayoutOutput, LogOutput, MutationOutput, Output, PrOutput, PrStatusOutput, PromptOutput,
+    RecoverListOutput, RecoverShowOutput, RepoListOutput, RunOutput, ScheduleStatusOutput,
+    SchemaListEntry, SchemaListOutput, SchemaOutput, ShellCheckOutput, StatusOutput,
+    SyncAbortOutput, SyncOutput, TemplateListOutput, TemplateShowOutput, TimingsOutput,
+    WorkspaceListOutput, WorkspaceRepoListOutput,
+};
+
+/// (name, command whose `--json` output it describes, schema).
+type SchemaEntry = (&'static str, &'static str, fn() -> Schema);
+
+const SCHEMAS: &[SchemaEntry] = &[
+    ("ls", "wsp ls", schema::<WorkspaceListOutput>),
+    ("st", "wsp st", schema::<StatusOutput>),
+    ("info", "wsp info", schema::<InfoOutput>),
+    ("context", "wsp context", schema::<ContextOutput>),
+    ("diff", "wsp diff", schema::<DiffOutput>),
+    ("log", "wsp log", schema::<LogOutput>),
+    ("sync", "wsp sync", schema::<SyncOutput>),
+    ("sync-abort", "wsp sync --abort", schema::<SyncAbortOutput>),
+    ("fix", "wsp fix", schema::<FixOutput>),
+    ("pr-create", "wsp pr create", schema::<PrOutput>),
+    ("pr-status", "wsp pr status", schema::<PrStatusOutput>),
+    ("prompt", "wsp prompt", schema::<PromptOutput>),
+    ("exec", "wsp exec", schema::<ExecOutput>),
+    ("run", "wsp run", schema::<RunOutput>),
+    ("layout", "wsp layout", schema::<LayoutOutput>),
+    (
+        "shell-check",
+        "wsp shell --check",
+        schema::<ShellCheckOutput>,
+    ),
+    ("recover", "wsp recover", schema::<RecoverListOutput>),
+    (
+        "recover-show",
+        "wsp recover show",
+        schema::<RecoverShowOutput>,
+    ),
+    ("repo-ls", "wsp repo ls", schema::<WorkspaceRepoListOutput>),
+    ("repo-fetch", "wsp repo fetch", schema::<FetchOutput>),
+    (
+        "auth-status",
+        "wsp setup auth status",
+        schema::<AuthStatusOutput>,
+    ),
+    (
+        "schedule-status",
+        "wsp setup schedule status",
+        schema::<ScheduleStatusOutput>,
+    ),
+    ("registry-ls", "wsp registry ls", schema::<RepoListOutput>),
+    (
+        "registry-import",
+        "wsp registry add --from <org>",
+        schema::<ImportOutput>,
+    ),
+    (
+        "template-ls",
+        "wsp template ls",
+        schema::<TemplateListOutput>,
+    ),
+    (
+        "template-show",
+        "wsp template show",
+        schema::<TemplateShowOutput>,
+    ),
+    ("config-ls", "wsp config ls", schema::<ConfigListOutput>),
+    ("config-get", "wsp config get", schema::<ConfigGetOutput>),
+    ("deps-graph", "wsp deps graph", schema::<DepsGraphOutput>),
+    (
+        "doctor",
+        "wsp doctor",
+        schema::<super::doctor::DoctorOutput>,
+    ),
+    (
+        "mutation",
+        "mutating commands (new, rm, repo add, config set, ...)",
+        schema::<MutationOutput>,
+    ),
+    ("error", "any command that fails", schema::<ErrorOutput>),
+    ("timings", "--timings (on stderr)", schema::<TimingsDoc>),
+];
+
+/// The `{"timings": ...}` document `--timings` prints with structured formats.
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct TimingsDoc {
+    timings: TimingsOutput,
+}
+
+/// Schema for what wsp emits: fields skipped when empty are optional.
+fn schema<T: JsonSchema>() -> Schema {
+    SchemaSettings::draft2020_12()
+        .for_serialize()
+        .into_generator()
+        .into_root_schema_for::<T>()
+}
+
+fn complete_schema_names() -> Vec<CompletionCandidate> {
+    SCHEMAS
+        .iter()
+        .map(|(name, command, _)| CompletionCandidate::new(*name).help(Some((*command).into())))
+        .collect()
+}
+
+pub fn cmd() -> Command {
+    Command::new("schema")
+        .about("Print JSON Schemas for --json output [read-only]")
+        .long_about(
+            "Print JSON Schemas for --json output [read-only].\n\n\
+             Without a name, lists the available schemas and the command each one \
+             describes. With a name, prints that schema (JSON Schema 2020-12) so tools \
+             can validate or generate types for wsp's JSON output:\n\n  \
+             wsp schema                 # list schemas\n  \
+             wsp schema st              # schema for `wsp st --json`\n  \
+             wsp schema error           # schema for error documents",
+        )
+        .arg(
+            Arg::new("name")
+                .help("Schema to print (see `wsp schema` for the list)")
+                .add(ArgValueCandidates::new(complete_schema_names)),
+        )
+}
+
+pub fn run(matches: &ArgMatches, _paths: &Paths) -> Result<Output> {
+    let Some(name) = matches.get_one::<String>("name") else {
+        return Ok(Output::SchemaList(SchemaListOutput {
+            schemas: SCHEMAS
+                .iter()
+                .map(|(name, command, _)| SchemaListEntry {
+                    name: name.to_string(),
+                    command: command.to_string(),
+                })
+                .collect(),
+        }));
+    };
+    match SCHEMAS.iter().find(|(n, _, _)| n == name) {
+        Some((_, _, build)) => Ok(Output::Schema(SchemaOutput(build().to_value()))),
+        None => Err(error::tag(
+            ErrorKind::NotFound,
+            anyhow!("no schema named {:?}. Run `wsp schema` to list them", name),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schemas_are_objects_with_properties() {
+        for (name, _, build) in SCHEMAS {
+            let value = build().to_value();
+            assert_eq!(value["type"], "object", "{}", name);
+            assert!(value["properties"].is_object(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_schema_reflects_serde_attributes() {
+        let st = schema::<StatusOutput>().to_value();
+        let required: Vec<&str> = st["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap())
+            .collect();
+        // skip_serializing_if fields are optional, #[serde(skip)] fields absent
+        assert!(required.contains(&"repos"));
+        assert!(!required.contains(&"root"));
+        assert!(st["properties"].get("verbose").is_none());
+
+        let cfg = schema::<ConfigListOutput>().to_value();
+        assert!(cfg["properties"].get("settings").is_some());
+    }
+
+    #[test]
+    fn test_run_unknown_name() {
+        let m = cmd().try_get_matches_from(["schema", "nope"]).unwrap();
+        let paths = Paths {
+            config_path: "/nonexistent/config.yaml".into(),
+            mirrors_dir: "/nonexistent/mirrors".into(),
+            workspaces_dir: "/nonexistent/workspaces".into(),
+            gc_dir: "/nonexistent/gc".into(),
+            templates_dir: "/nonexistent/templates".into(),
+            extra_workspaces_dirs: Vec::new(),
+        };
+        let err = run(&m, &paths).err().unwrap();
+        assert_eq!(error::classify(&err), ErrorKind::NotFound);
+    }
+}
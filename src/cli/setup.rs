@@ -29,11 +29,28 @@ pub fn cmd() -> Command {
             "Interactive first-time setup.\n\n\
              Walks through configuring wsp for first use: checks dependencies, sets \
              branch prefix, and configures shell integration. Idempotent — skips steps \
-             that are already configured. Re-run anytime to fill in missing pieces.",
+             that are already configured. Re-run anytime to fill in missing pieces.\n\n\
+             `auth` keeps GitHub and GitLab API tokens in the OS keyring. \
+             `mirror dedupe` makes mirrors of forks share objects. \
+             `backup` and `restore` move wsp's state (config, templates, workspace \
+             metadata) to another machine.",
         )
+        .subcommand(super::schedule::cmd())
+        .subcommand(super::auth::cmd())
+        .subcommand(super::mirrors::cmd())
+        .subcommand(super::backup::backup_cmd())
+        .subcommand(super::backup::restore_cmd())
 }
 
-pub fn run(_matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    match matches.subcommand() {
+        Some(("schedule", m)) => return super::schedule::run(m, paths),
+        Some(("auth", m)) => return super::auth::run(m, paths),
+        Some(("mirror", m)) => return super::mirrors::run(m, paths),
+        Some(("backup", m)) => return super::backup::run_backup(m, paths),
+        Some(("restore", m)) => return super::backup::run_restore(m, paths),
+        _ => {}
+    }
     if !std::io::stdin().is_terminal() {
         print_non_interactive_guide(paths)?;
         return Ok(Output::None);
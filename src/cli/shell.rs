@@ -0,0 +1,228 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command as ProcessCommand;
+
+use anyhow::{Context, Result, bail};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use clap_complete::engine::ArgValueCandidates;
+
+use crate::config::Paths;
+use crate::error;
+use crate::output::{Output, ShellCheckOutput};
+use crate::workspace;
+
+use super::completers;
+
+/// Set to the workspace root inside `wsp shell`; its presence is how
+/// `wsp shell --check` detects the subshell.
+pub const DIR_VAR: &str = "WSP_DIR";
+pub const WORKSPACE_VAR: &str = "WSP_WORKSPACE";
+
+pub fn cmd() -> Command {
+    Command::new("shell")
+        .about("Start a subshell with the workspace environment")
+        .long_about(
+            "Start a subshell with the workspace environment.\n\n\
+             Spawns $SHELL in the workspace directory with these variables set:\n\n  \
+             WSP_WORKSPACE     workspace name\n  \
+             WSP_DIR           workspace root directory\n  \
+             WSP_REPO_<NAME>   path of each repo, named after its directory \
+             (api-gateway -> WSP_REPO_API_GATEWAY)\n\n\
+             <workspace>/bin is prepended to PATH, so workspace-local scripts placed there \
+             are found by name. Exit the shell to leave the workspace.\n\n\
+             Scripts can call `wsp shell --check` to detect the subshell: it prints the \
+             workspace and exits 0 inside, exits 1 outside.\n\n\
+             The workspace name is optional when running from inside a workspace directory.",
+        )
+        .arg(
+            Arg::new("workspace")
+                .required(false)
+                .add(ArgValueCandidates::new(completers::complete_workspaces)),
+        )
+        .arg(
+            Arg::new("check")
+                .long("check")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("workspace")
+                .help("Report whether this is running inside a wsp shell [read-only]"),
+        )
+}
+
+pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    if matches.get_flag("check") {
+        return Ok(Output::ShellCheck(check()));
+    }
+    let current = check();
+    if current.inside {
+        bail!(
+            "already inside a wsp shell for workspace {:?} (exit it first)",
+            current.workspace.unwrap_or_default()
+        );
+    }
+
+    let ws_dir: PathBuf = if let Some(name) = matches.get_one::<String>("workspace") {
+        paths.workspace_dir(name)
+    } else {
+        let cwd = std::env::current_dir()?;
+        workspace::detect(&cwd)?
+    };
+    let meta =
+        workspace::load_metadata(&ws_dir).map_err(|e| error::prefixed(e, "reading workspace"))?;
+    let env = workspace_env(&ws_dir, &meta)?;
+
+    let shell = user_shell();
+    eprintln!(
+        "Entering workspace {:?} ({}). Exit the shell to leave.",
+        meta.name,
+        shell.to_string_lossy()
+    );
+    ProcessCommand::new(&shell)
+        .current_dir(&ws_dir)
+        .envs(env)
+        .env("PATH", prepend_path(&ws_dir.join("bin")))
+        .status()
+        .with_context(|| format!("starting {}", shell.to_string_lossy()))?;
+    eprintln!("Left workspace {:?}.", meta.name);
+    Ok(Output::None)
+}
+
+/// Environment describing a workspace: `WSP_WORKSPACE`, `WSP_DIR`, and a
+/// `WSP_REPO_<NAME>` path per repo.
+pub(crate) fn workspace_env(
+    ws_dir: &Path,
+    meta: &workspace::Metadata,
+) -> Result<Vec<(String, String)>> {
+    let mut env = vec![
+        (WORKSPACE_VAR.to_string(), meta.name.clone()),
+        (DIR_VAR.to_string(), ws_dir.to_string_lossy().into_owned()),
+    ];
+    for identity in meta.repos.keys() {
+        let dir_name = meta.dir_name(identity)?;
+        env.push((
+            repo_var(&dir_name),
+            ws_dir.join(&dir_name).to_string_lossy().into_owned(),
+        ));
+    }
+    Ok(env)
+}
+
+/// Environment variable name for a repo directory: `WSP_REPO_` followed by
+/// the name uppercased, with anything but letters and digits mapped to `_`.
+pub(crate) fn repo_var(dir_name: &str) -> String {
+    let name: String = dir_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("WSP_REPO_{}", name)
+}
+
+/// Inside a wsp shell when `WSP_DIR` points at a workspace.
+fn check() -> ShellCheckOutput {
+    let dir = std::env::var_os(DIR_VAR)
+        .map(PathBuf::from)
+        .filter(|d| workspace::load_metadata(d).is_ok());
+    ShellCheckOutput {
+        inside: dir.is_some(),
+        workspace: dir.as_ref().and_then(|_| std::env::var(WORKSPACE_VAR).ok()),
+        path: dir.map(|d| d.to_string_lossy().into_owned()),
+    }
+}
+
+fn prepend_path(dir: &Path) -> OsString {
+    let mut dirs = vec![dir.to_path_buf()];
+    if let Some(path) = std::env::var_os("PATH") {
+        dirs.extend(std::env::split_paths(&path));
+    }
+    std::env::join_paths(dirs).unwrap_or_else(|_| std::env::var_os("PATH").unwrap_or_default())
+}
+
+#[cfg(unix)]
+fn user_shell() -> OsString {
+    std::env::var_os("SHELL")
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "sh".into())
+}
+
+#[cfg(windows)]
+fn user_shell() -> OsString {
+    std::env::var_os("COMSPEC")
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "cmd".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_args() {
+        let m = cmd().get_matches_from(["shell", "my-ws"]);
+        assert_eq!(
+            m.get_one::<String>("workspace").map(|s| s.as_str()),
+            Some("my-ws")
+        );
+        assert!(!m.get_flag("check"));
+
+        let m = cmd().get_matches_from(["shell", "--check"]);
+        assert!(m.get_flag("check"));
+
+        assert!(
+            cmd()
+                .try_get_matches_from(["shell", "my-ws", "--check"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_repo_var() {
+        let cases = vec![
+            ("api-gateway", "WSP_REPO_API_GATEWAY"),
+            ("acme-web", "WSP_REPO_ACME_WEB"),
+            ("proto.v2", "WSP_REPO_PROTO_V2"),
+            ("web2", "WSP_REPO_WEB2"),
+        ];
+        for (dir, want) in cases {
+            assert_eq!(repo_var(dir), want, "{}", dir);
+        }
+    }
+
+    #[test]
+    fn test_workspace_env() {
+        let mut repos = std::collections::BTreeMap::new();
+        repos.insert("github.com/acme/api".to_string(), None);
+        repos.insert("github.com/other/api".to_string(), None);
+        let mut dirs = std::collections::BTreeMap::new();
+        dirs.insert("github.com/other/api".to_string(), "other-api".to_string());
+        let meta = workspace::Metadata {
+            version: 0,
+            name: "fix".into(),
+            branch: "fix".into(),
+            repos,
+            created: chrono::Utc::now(),
+            description: None,
+            last_used: None,
+            created_from: None,
+            dirs,
+            branches: std::collections::BTreeMap::new(),
+            prs: std::collections::BTreeMap::new(),
+            issue: None,
+            config: None,
+            pending: std::collections::BTreeMap::new(),
+        };
+        let env = workspace_env(Path::new("/ws/fix"), &meta).unwrap();
+        let want = vec![
+            ("WSP_WORKSPACE", "fix"),
+            ("WSP_DIR", "/ws/fix"),
+            ("WSP_REPO_API", "/ws/fix/api"),
+            ("WSP_REPO_OTHER_API", "/ws/fix/other-api"),
+        ];
+        let got: Vec<(&str, &str)> = env.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        assert_eq!(got, want);
+    }
+}
@@ -22,10 +22,12 @@ pub fn generate_cmd() -> Command {
 #[cfg(feature = "codegen")]
 pub fn run_generate(_matches: &ArgMatches, _paths: &Paths) -> Result<Output> {
     use crate::output::{
-        ConfigGetOutput, ConfigListOutput, DiffOutput, ErrorOutput, ExecOutput, FetchOutput,
-        ImportOutput, LogOutput, MutationOutput, RecoverListOutput, RecoverShowOutput,
-        RepoListOutput, StatusOutput, SyncAbortOutput, SyncOutput, TemplateListOutput,
-        TemplateShowOutput, WorkspaceListOutput, WorkspaceRepoListOutput,
+        AuthStatusOutput, ConfigGetOutput, ConfigListOutput, ContextOutput, DepsGraphOutput,
+        DiffOutput, ErrorOutput, ExecOutput, FetchOutput, FixOutput, ImportOutput, InfoOutput,
+        LayoutOutput, LogOutput, MutationOutput, PrOutput, PrStatusOutput, PromptOutput,
+        RecoverListOutput, RecoverShowOutput, RepoListOutput, RunOutput, ScheduleStatusOutput,
+        SchemaListOutput, ShellCheckOutput, StatusOutput, SyncAbortOutput, SyncOutput,
+        TemplateListOutput, TemplateShowOutput, WorkspaceListOutput, WorkspaceRepoListOutput,
     };
 
     let cli = super::build_cli();
@@ -50,7 +52,8 @@ pub fn run_generate(_matches: &ArgMatches, _paths: &Paths) -> Result<Output> {
     // Workspaces — top-level workspace commands + `repo` subcommands
     out.push_str("### Workspaces\n\n```bash\n");
     let ws_cmds = [
-        "new", "ls", "st", "diff", "log", "sync", "exec", "cd", "rm", "recover", "rename",
+        "new", "ls", "st", "diff", "log", "sync", "exec", "run", "watch", "layout", "shell", "cd",
+        "rm", "recover", "rename",
     ];
     for name in &ws_cmds {
         if let Some(sub) = cli.find_subcommand(name) {
@@ -70,6 +73,11 @@ pub fn run_generate(_matches: &ArgMatches, _paths: &Paths) -> Result<Output> {
     write_subcommand_section(&cli, &mut out, "config", &["wsp", "config"]);
     out.push_str("```\n\n");
 
+    // Deps — top-level
+    out.push_str("### Repo dependencies\n\n```bash\n");
+    write_subcommand_section(&cli, &mut out, "deps", &["wsp", "deps"]);
+    out.push_str("```\n\n");
+
     // Doctor — top-level (no subcommands, just write the command itself)
     out.push_str("### Diagnostics\n\n```bash\n");
     if let Some(sub) = cli.find_subcommand("doctor") {
@@ -83,13 +91,24 @@ pub fn run_generate(_matches: &ArgMatches, _paths: &Paths) -> Result<Output> {
     write_schema::<RepoListOutput>(&mut out, "wsp registry ls --json");
     write_schema::<WorkspaceListOutput>(&mut out, "wsp ls --json");
     write_schema::<StatusOutput>(&mut out, "wsp st --json");
+    write_schema::<InfoOutput>(&mut out, "wsp info --json");
+    write_schema::<ContextOutput>(&mut out, "wsp context --json");
     write_schema::<DiffOutput>(&mut out, "wsp diff --json");
     write_schema::<LogOutput>(&mut out, "wsp log --json");
     write_schema::<SyncOutput>(&mut out, "wsp sync --json");
     write_schema::<SyncAbortOutput>(&mut out, "wsp sync --abort --json");
+    write_schema::<FixOutput>(&mut out, "wsp fix --json");
+    write_schema::<PrOutput>(&mut out, "wsp pr create --json");
+    write_schema::<PrStatusOutput>(&mut out, "wsp pr status --json");
+    write_schema::<PromptOutput>(&mut out, "wsp prompt --status --json");
     write_schema::<WorkspaceRepoListOutput>(&mut out, "wsp repo ls --json");
     write_schema::<ExecOutput>(&mut out, "wsp exec <workspace> --json -- <command>");
+    write_schema::<RunOutput>(&mut out, "wsp run <task> [<workspace>] --json");
+    write_schema::<DepsGraphOutput>(&mut out, "wsp deps graph [<workspace>] --json");
+    write_schema::<LayoutOutput>(&mut out, "wsp layout <tmux|zellij> [<workspace>] --json");
     write_schema::<FetchOutput>(&mut out, "wsp repo fetch --json");
+    write_schema::<ScheduleStatusOutput>(&mut out, "wsp setup schedule status --json");
+    write_schema::<AuthStatusOutput>(&mut out, "wsp setup auth status --json");
     write_schema::<TemplateListOutput>(&mut out, "wsp template ls --json");
     write_schema::<TemplateShowOutput>(&mut out, "wsp template show <name> --json");
     write_schema::<ConfigListOutput>(&mut out, "wsp config ls --json");
@@ -99,9 +118,11 @@ pub fn run_generate(_matches: &ArgMatches, _paths: &Paths) -> Result<Output> {
         "Mutation commands (new, rm, add, remove, set, etc.)",
     );
     write_schema::<ImportOutput>(&mut out, "wsp registry add --from <org> --all --json");
+    write_schema::<ShellCheckOutput>(&mut out, "wsp shell --check --json");
     write_schema::<RecoverListOutput>(&mut out, "wsp recover --json");
     write_schema::<RecoverShowOutput>(&mut out, "wsp recover show <name> --json");
     write_schema::<super::doctor::DoctorOutput>(&mut out, "wsp doctor --json");
+    write_schema::<SchemaListOutput>(&mut out, "wsp schema --json");
     write_schema::<ErrorOutput>(&mut out, "Errors");
 
     // --- Static reference sections ---
@@ -132,20 +153,33 @@ impl_sample!(
     crate::output::TemplateShowOutput,
     crate::output::WorkspaceListOutput,
     crate::output::StatusOutput,
+    crate::output::InfoOutput,
+    crate::output::ContextOutput,
     crate::output::DiffOutput,
     crate::output::LogOutput,
     crate::output::SyncOutput,
     crate::output::SyncAbortOutput,
+    crate::output::FixOutput,
+    crate::output::PrOutput,
+    crate::output::PrStatusOutput,
+    crate::output::PromptOutput,
     crate::output::ConfigListOutput,
     crate::output::ConfigGetOutput,
     crate::output::WorkspaceRepoListOutput,
     crate::output::ExecOutput,
+    crate::output::RunOutput,
+    crate::output::DepsGraphOutput,
+    crate::output::LayoutOutput,
+    crate::output::ShellCheckOutput,
     crate::output::FetchOutput,
+    crate::output::ScheduleStatusOutput,
+    crate::output::AuthStatusOutput,
     crate::output::MutationOutput,
     crate::output::ImportOutput,
     crate::output::RecoverListOutput,
     crate::output::RecoverShowOutput,
     crate::cli::doctor::DoctorOutput,
+    crate::output::SchemaListOutput,
     crate::output::ErrorOutput,
 );
 
@@ -170,7 +204,7 @@ fn write_cmd_line(out: &mut String, prefix: &[&str], cmd: &Command) {
 
     for arg in cmd.get_arguments() {
         let id = arg.get_id().as_str();
-        if id == "json" || id == "help" || id == "version" {
+        if arg.is_global_set() || id == "help" || id == "version" {
             continue;
         }
         if arg.is_positional() {
@@ -301,6 +335,10 @@ wsp exec my-feature -- make test
 ### Clean up when done
 ```bash
 wsp rm my-feature      # Removes clones + branch (if merged)
-wsp rm my-feature -f   # Force remove even if unmerged
+wsp rm my-feature -f -y  # Force remove even if unmerged (-y: no prompt)
 ```
+
+Destructive operations that would lose work (`rm -f`, `repo rm -f`,
+`registry rm`) ask for confirmation and fail without a terminal; pass `--yes`
+only after checking `wsp st --json` shows nothing worth keeping.
 "#;
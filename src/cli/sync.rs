@@ -1,6 +1,5 @@
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
 
 use anyhow::{Result, bail};
 use clap::{Arg, ArgAction, ArgMatches, Command};
@@ -8,13 +7,20 @@ use clap_complete::engine::ArgValueCandidates;
 
 use super::completers;
 use crate::config::{self, Paths};
+use crate::deps;
 use crate::discovery;
+use crate::error;
+use crate::filelock;
 use crate::gc;
 use crate::git::{self, SyncAction};
 use crate::giturl;
+use crate::hooks;
 use crate::mirror;
-use crate::output::{Output, SyncAbortOutput, SyncAbortRepoResult, SyncOutput, SyncRepoResult};
-use crate::workspace::{self, RepoInfo};
+use crate::offline::FetchPolicy;
+use crate::output::{
+    self, Output, SyncAbortOutput, SyncAbortRepoResult, SyncOutput, SyncRepoResult,
+};
+use crate::workspace;
 
 pub fn cmd() -> Command {
     Command::new("sync")
@@ -22,10 +28,13 @@ pub fn cmd() -> Command {
         .long_about(
             "Fetch and rebase/merge all workspace repos.\n\n\
              Fetches upstream changes through the mirror layer, then rebases (default) or \
-             merges each repo's workspace branch onto its upstream tracking branch. If a \
+             merges each repo's workspace branch onto its upstream tracking branch. Repos are \
+             synced in dependency order when they declare `depends-on.<repo>`. If a \
              conflict occurs, the operation pauses — resolve it with git, then re-run sync \
              to continue with the remaining repos. Use --abort to cancel in-progress \
-             operations across all repos.",
+             operations across all repos. With --no-fetch, repos are synced against \
+             the refs of the last fetch; --max-age (default fetch.max-age) does the same \
+             for repos whose mirror was fetched within the given age.",
         )
         .arg(Arg::new("workspace").add(ArgValueCandidates::new(completers::complete_workspaces)))
         .arg(
@@ -54,34 +63,34 @@ pub fn cmd() -> Command {
                 .action(ArgAction::SetTrue)
                 .help("Skip template discovery after sync"),
         )
+        .arg(super::no_fetch_arg().conflicts_with("abort"))
+        .arg(super::max_age_arg().conflicts_with_all(["abort", "no-fetch"]))
 }
 
 pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     let ws_dir: PathBuf = if let Some(name) = matches.get_one::<String>("workspace") {
-        workspace::dir(&paths.workspaces_dir, name)
+        paths.workspace_dir(name)
     } else {
         let cwd = std::env::current_dir()?;
         workspace::detect(&cwd)?
     };
 
     gc::check_workspace(&ws_dir, /* read_only */ false)?;
+    let _lock = filelock::lock_workspace(&ws_dir)?;
 
-    let meta = workspace::load_metadata(&ws_dir)
-        .map_err(|e| anyhow::anyhow!("reading workspace: {}", e))?;
+    let meta =
+        workspace::load_metadata(&ws_dir).map_err(|e| error::prefixed(e, "reading workspace"))?;
 
     if matches.get_flag("abort") {
         return run_abort(&ws_dir, &meta);
     }
 
     let cfg = config::Config::load_from(&paths.config_path)?;
+    let effective = meta.apply_workspace_config(&cfg);
     let strategy = matches
         .get_one::<String>("strategy")
         .map(|s| s.as_str())
-        .or(meta
-            .config
-            .as_ref()
-            .and_then(|c| c.sync_strategy.as_deref()))
-        .or(cfg.sync_strategy.as_deref())
+        .or(effective.sync_strategy.as_deref())
         .unwrap_or("rebase");
 
     // Validate strategy (config file values bypass clap's value_parser)
@@ -95,47 +104,35 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
 
     let dry_run = matches.get_flag("dry-run");
 
-    let repo_infos = meta.repo_infos(&ws_dir);
+    // Sync in dependency order so a repo is updated after the repos it builds on
+    let identities: Vec<String> = meta.repos.keys().cloned().collect();
+    let order = deps::order(effective.depends_on.as_ref(), &identities)?;
+    let mut repo_infos = meta.repo_infos(&ws_dir);
+    repo_infos.sort_by_key(|r| order.iter().position(|id| *id == r.identity));
 
     // Phase 1a: Fetch mirrors from upstream (network, parallel, skip if dry-run)
-    let fetch_failures: HashSet<String> = if !dry_run {
-        let mirrors: Vec<(&RepoInfo, PathBuf)> = repo_infos
+    let fetch = FetchPolicy::from_flag(matches.get_flag("no-fetch"));
+    let fetch_failures: HashSet<String> = if !dry_run && !fetch.fetches() {
+        // Still bring the clones up to whatever the mirrors last fetched.
+        fetch.note_skipped();
+        workspace::propagate_mirror_to_clones(&paths.mirrors_dir, &ws_dir, &meta, true);
+        HashSet::new()
+    } else if !dry_run {
+        let identities: Vec<String> = repo_infos
             .iter()
             .filter(|r| r.error.is_none())
-            .filter_map(|info| {
-                giturl::Parsed::from_identity(&info.identity)
-                    .ok()
-                    .map(|parsed| (info, mirror::dir(&paths.mirrors_dir, &parsed)))
-            })
+            .map(|r| r.identity.clone())
             .collect();
-
-        if !mirrors.is_empty() {
-            eprintln!("Fetching {} repo(s)...", mirrors.len());
-        }
-
-        let progress = Mutex::new(());
-        let results: Vec<(String, bool)> = std::thread::scope(|s| {
-            let handles: Vec<_> = mirrors
-                .iter()
-                .map(|(info, mirror_path)| {
-                    let progress = &progress;
-                    s.spawn(move || {
-                        let result = git::fetch(mirror_path, true);
-                        let _lock = progress.lock().unwrap_or_else(|e| e.into_inner());
-                        match &result {
-                            Ok(()) => eprintln!("  ok    {}", info.dir_name),
-                            Err(e) => eprintln!("  FAIL  {} ({})", info.dir_name, e),
-                        }
-                        (info.dir_name.clone(), result.is_err())
-                    })
-                })
-                .collect();
-
-            handles
-                .into_iter()
-                .map(|h| h.join().unwrap_or_else(|_| (String::new(), true)))
-                .collect()
-        });
+        let results = if identities.is_empty() {
+            Vec::new()
+        } else {
+            super::fetch::fetch_mirrors(
+                paths,
+                identities,
+                true,
+                &super::fetch::Limits::resolve(matches, &cfg),
+            )
+        };
 
         // Phase 1b: Propagate mirror refs to clones (runs for all repos, including
         // those whose mirror fetch failed — stale mirror data is still useful and
@@ -144,8 +141,8 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
 
         results
             .into_iter()
-            .filter(|(_, failed)| *failed)
-            .map(|(name, _)| name)
+            .filter(|r| !r.ok)
+            .map(|r| r.identity)
             .collect()
     } else {
         HashSet::new()
@@ -170,7 +167,7 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
             continue;
         }
 
-        let fetch_failed = fetch_failures.contains(&info.dir_name);
+        let fetch_failed = fetch_failures.contains(&info.identity);
 
         // Resolve default branch first (used in all paths)
         let default_branch = match git::default_branch(&info.clone_dir) {
@@ -234,7 +231,19 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
                 Ok(sync_action) => {
                     let mut detail = format_sync_action(&sync_action);
                     if fetch_failed {
-                        detail.push_str(" (fetch failed, data may be stale)");
+                        let last = giturl::Parsed::from_identity(&info.identity)
+                            .ok()
+                            .and_then(|p| mirror::last_fetched(&paths.mirrors_dir, &p));
+                        match last {
+                            Some(t) => detail.push_str(&format!(
+                                " (fetch failed; last fetched {})",
+                                output::format_relative_time(
+                                    t.timestamp(),
+                                    chrono::Utc::now().timestamp()
+                                )
+                            )),
+                            None => detail.push_str(" (fetch failed, data may be stale)"),
+                        }
                     }
                     results.push(SyncRepoResult {
                         identity: info.identity.clone(),
@@ -279,14 +288,25 @@ pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
             all_discovered.extend(discovered);
         }
         if let Err(e) = discovery::prompt_and_import(&all_discovered, &paths.templates_dir) {
-            eprintln!("warning: template discovery failed: {}", e);
+            tracing::warn!("template discovery failed: {}", e);
         }
     }
 
+    if !dry_run {
+        hooks::run(
+            hooks::Event::PostSync,
+            cfg.hooks.as_ref(),
+            &ws_dir,
+            &meta,
+            &identities,
+        )?;
+    }
+
     Ok(Output::Sync(SyncOutput {
         workspace: meta.name,
         branch: meta.branch,
         dry_run,
+        fetch_skipped: fetch.skip_reason().filter(|_| !dry_run).map(String::from),
         repos: results,
     }))
 }
@@ -308,18 +328,19 @@ fn run_abort(ws_dir: &Path, meta: &workspace::Metadata) -> Result<Output> {
             continue;
         }
 
-        match git::in_progress_op(&info.clone_dir) {
+        // Only rebases and merges can come from `wsp sync`; cherry-picks,
+        // reverts, and bisects are someone's manual work and are left alone.
+        match git::in_progress_op(&info.clone_dir)
+            .filter(|op| matches!(op, git::InProgressOp::Rebase | git::InProgressOp::Merge))
+        {
             Some(op) => {
-                let action = match op {
-                    git::InProgressOp::Rebase => "rebase aborted",
-                    git::InProgressOp::Merge => "merge aborted",
-                };
+                let action = format!("{} aborted", op.name());
                 match git::abort_in_progress(&info.clone_dir, &op) {
                     Ok(()) => results.push(SyncAbortRepoResult {
                         identity: info.identity.clone(),
                         shortname: info.dir_name.clone(),
                         path: info.clone_dir.to_string_lossy().to_string(),
-                        action: action.into(),
+                        action,
                         ok: true,
                         error: None,
                     }),
@@ -327,7 +348,7 @@ fn run_abort(ws_dir: &Path, meta: &workspace::Metadata) -> Result<Output> {
                         identity: info.identity.clone(),
                         shortname: info.dir_name.clone(),
                         path: info.clone_dir.to_string_lossy().to_string(),
-                        action: action.into(),
+                        action,
                         ok: false,
                         error: Some(e.to_string()),
                     }),
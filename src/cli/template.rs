@@ -6,6 +6,7 @@ use clap::{Arg, ArgMatches, Command};
 use clap_complete::engine::ArgValueCandidates;
 
 use crate::config::Paths;
+use crate::error::{self, ErrorKind};
 use crate::filelock;
 use crate::giturl;
 use crate::output::{
@@ -148,10 +149,13 @@ fn run_import(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
 
     // Check for conflicts
     if tmpl::exists(&paths.templates_dir, &name) && !update && !force {
-        anyhow::bail!(
-            "template {:?} already exists (use --update to replace, or --name for a different name)",
-            name
-        );
+        return Err(error::tag(
+            ErrorKind::Conflict,
+            anyhow::anyhow!(
+                "template {:?} already exists (use --update to replace, or --name for a different name)",
+                name
+            ),
+        ));
     }
 
     if update
@@ -263,7 +267,10 @@ fn run_new(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     let description = matches.get_one::<String>("description").cloned();
 
     if tmpl::exists(&paths.templates_dir, name) {
-        anyhow::bail!("template {:?} already exists", name);
+        return Err(error::tag(
+            ErrorKind::Conflict,
+            anyhow::anyhow!("template {:?} already exists", name),
+        ));
     }
 
     let mut template = if let Some(ws_name) = from_workspace {
@@ -322,7 +329,7 @@ fn run_list(_matches: &ArgMatches, paths: &Paths) -> Result<Output> {
                 repo_count: t.repos.len(),
             }),
             Err(e) => {
-                eprintln!("warning: skipping template {:?}: {}", name, e);
+                tracing::warn!("skipping template {:?}: {}", name, e);
             }
         }
     }
@@ -392,7 +399,10 @@ fn run_export(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
         let filename = format!("{}.wsp.yaml", name);
         let dest = std::env::current_dir()?.join(&filename);
         if dest.exists() {
-            anyhow::bail!("{:?} already exists", filename);
+            return Err(error::tag(
+                ErrorKind::Conflict,
+                anyhow::anyhow!("{:?} already exists", filename),
+            ));
         }
         let mut f = fs::File::create(&dest)?;
         f.write_all(yaml.as_bytes())?;
@@ -471,7 +481,7 @@ fn run_repo_add(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
     let template = filelock::with_template(&paths.templates_dir, name, |tmpl| {
         let skipped = tmpl::add_repos(tmpl, repos)?;
         for url in &skipped {
-            eprintln!("warning: repo {:?} already in template, skipping", url);
+            tracing::warn!("repo {:?} already in template, skipping", url);
         }
         Ok(())
     })?;
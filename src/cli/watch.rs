@@ -0,0 +1,264 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use clap_complete::engine::ArgValueCandidates;
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use crate::config::{self, Paths};
+use crate::deps;
+use crate::error;
+use crate::git;
+use crate::output::Output;
+use crate::workspace;
+
+use super::completers;
+use super::exec;
+
+/// Default quiet period, in milliseconds.
+const DEFAULT_DEBOUNCE: &str = "300";
+
+pub fn cmd() -> Command {
+    Command::new("watch")
+        .about("Re-run a command in workspace repos when files change")
+        .long_about(
+            "Re-run a command in workspace repos when files change.\n\n\
+             Runs the command once in every repo, then watches all repo directories and \
+             runs it again whenever files change. Changes are debounced: a burst of saves \
+             triggers one run after things go quiet for --debounce milliseconds. Files \
+             under .git/ and files ignored by git (build output, node_modules) never \
+             trigger a run.\n\n\
+             By default every repo re-runs on any change. With --changed, only the repos \
+             where files changed (and repos that depend on them, per `depends-on.<repo>`) \
+             re-run. Repos run in dependency order and in their exec-dir if one is set.\n\n\
+             Stop with Ctrl-C. The workspace name is optional when running from inside a \
+             workspace directory.",
+        )
+        .arg(
+            Arg::new("workspace")
+                .required(false)
+                .add(ArgValueCandidates::new(completers::complete_workspaces)),
+        )
+        .arg(
+            Arg::new("changed")
+                .long("changed")
+                .action(ArgAction::SetTrue)
+                .help("Only re-run in repos with changes and their dependents"),
+        )
+        .arg(
+            Arg::new("debounce")
+                .long("debounce")
+                .value_name("MS")
+                .value_parser(clap::value_parser!(u64))
+                .default_value(DEFAULT_DEBOUNCE)
+                .help("Quiet period before re-running, in milliseconds"),
+        )
+        .arg(Arg::new("command").required(true).num_args(1..).last(true))
+}
+
+pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    let command: Vec<String> = matches
+        .get_many::<String>("command")
+        .unwrap()
+        .cloned()
+        .collect();
+    let display = command.join(" ");
+    let only_changed = matches.get_flag("changed");
+    let debounce = Duration::from_millis(*matches.get_one::<u64>("debounce").unwrap());
+
+    let ws_dir: PathBuf = if let Some(name) = matches.get_one::<String>("workspace") {
+        paths.workspace_dir(name)
+    } else {
+        let cwd = std::env::current_dir()?;
+        workspace::detect(&cwd)?
+    };
+    let meta =
+        workspace::load_metadata(&ws_dir).map_err(|e| error::prefixed(e, "reading workspace"))?;
+    let cfg = config::Config::load_from(&paths.config_path)?;
+    let effective = meta.apply_workspace_config(&cfg);
+    let identities: Vec<String> = meta.repos.keys().cloned().collect();
+    let order = deps::order(effective.depends_on.as_ref(), &identities)?;
+    let graph = deps::graph(effective.depends_on.as_ref(), &identities);
+    let subdirs = exec::resolve_subdirs(&meta, &cfg, &[])?;
+
+    // (identity, repo dir) for mapping changed paths back to repos
+    let repo_dirs: Vec<(String, PathBuf)> = identities
+        .iter()
+        .filter_map(|id| Some((id.clone(), ws_dir.join(meta.dir_name(id).ok()?))))
+        .collect();
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).context("starting file watcher")?;
+    for (_, dir) in &repo_dirs {
+        if dir.is_dir() {
+            watcher
+                .watch(dir, RecursiveMode::Recursive)
+                .with_context(|| format!("watching {}", dir.display()))?;
+        }
+    }
+
+    let run_in = |targets: &BTreeSet<String>| {
+        for identity in order.iter().filter(|id| targets.contains(*id)) {
+            exec::exec_in_repo(
+                &ws_dir,
+                &meta,
+                identity,
+                &command,
+                &display,
+                subdirs.get(identity).map(|s| s.as_str()),
+                false,
+            );
+        }
+    };
+
+    let all: BTreeSet<String> = identities.iter().cloned().collect();
+    run_in(&all);
+
+    let announce = || {
+        eprintln!(
+            "Watching {} repos for changes (Ctrl-C to stop)...",
+            repo_dirs.len()
+        )
+    };
+    announce();
+
+    loop {
+        let mut changed_paths: Vec<PathBuf> = Vec::new();
+
+        // Block for the first event, then keep collecting until the debounce
+        // window passes with no new events.
+        let Ok(first) = rx.recv() else {
+            return Ok(Output::None);
+        };
+        collect_paths(first, &mut changed_paths);
+        while let Ok(event) = rx.recv_timeout(debounce) {
+            collect_paths(event, &mut changed_paths);
+        }
+
+        let changed = changed_repos(&repo_dirs, &changed_paths);
+        if changed.is_empty() {
+            continue;
+        }
+        let targets = if only_changed {
+            graph.with_dependents(changed)
+        } else {
+            all.clone()
+        };
+        println!();
+        run_in(&targets);
+        announce();
+    }
+}
+
+/// Append the paths of a content-changing event. Access events (reads) and
+/// watcher errors are ignored.
+pub fn collect_paths(event: notify::Result<notify::Event>, out: &mut Vec<PathBuf>) {
+    if let Ok(event) = event
+        && !matches!(event.kind, EventKind::Access(_))
+    {
+        out.extend(event.paths);
+    }
+}
+
+/// Map changed paths to the repos that contain them, skipping anything under
+/// `.git/` and anything git ignores. A repo whose ignore check fails counts as
+/// changed, with a warning.
+pub fn changed_repos(repo_dirs: &[(String, PathBuf)], paths: &[PathBuf]) -> BTreeSet<String> {
+    let mut changed = BTreeSet::new();
+    for (identity, dir) in repo_dirs {
+        let rel: Vec<String> = paths.iter().filter_map(|p| relevant_path(dir, p)).collect();
+        if rel.is_empty() {
+            continue;
+        }
+        let refs: Vec<&str> = rel.iter().map(|s| s.as_str()).collect();
+        // If git can't say what's ignored, count the repo as changed rather
+        // than drop a real edit.
+        let ignored = match git::ignored_paths(dir, &refs) {
+            Ok(ignored) => ignored,
+            Err(e) => {
+                tracing::warn!("checking ignored paths in {}: {}", identity, e);
+                vec![]
+            }
+        };
+        if rel.iter().any(|p| !ignored.contains(p)) {
+            changed.insert(identity.clone());
+        }
+    }
+    changed
+}
+
+/// Returns `path` relative to `repo_dir`, or None if it is outside the repo or
+/// inside its `.git` directory.
+fn relevant_path(repo_dir: &Path, path: &Path) -> Option<String> {
+    let rel = path.strip_prefix(repo_dir).ok()?;
+    if rel.as_os_str().is_empty() || rel.starts_with(".git") {
+        return None;
+    }
+    Some(rel.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_args() {
+        let m = cmd().get_matches_from(["watch", "--changed", "--", "cargo", "test"]);
+        assert!(m.get_flag("changed"));
+        assert_eq!(*m.get_one::<u64>("debounce").unwrap(), 300);
+        let command: Vec<&str> = m
+            .get_many::<String>("command")
+            .unwrap()
+            .map(|s| s.as_str())
+            .collect();
+        assert_eq!(command, vec!["cargo", "test"]);
+
+        let m = cmd().get_matches_from(["watch", "my-ws", "--debounce", "50", "--", "make"]);
+        assert_eq!(
+            m.get_one::<String>("workspace").map(|s| s.as_str()),
+            Some("my-ws")
+        );
+        assert_eq!(*m.get_one::<u64>("debounce").unwrap(), 50);
+    }
+
+    #[test]
+    fn test_relevant_path() {
+        let repo = Path::new("/ws/api");
+        let cases = vec![
+            ("file in repo", "/ws/api/src/main.rs", Some("src/main.rs")),
+            ("git dir", "/ws/api/.git/index", None),
+            ("dotfile is fine", "/ws/api/.gitignore", Some(".gitignore")),
+            ("repo root itself", "/ws/api", None),
+            ("other repo", "/ws/web/index.js", None),
+        ];
+        for (name, path, want) in cases {
+            assert_eq!(
+                relevant_path(repo, Path::new(path)).as_deref(),
+                want,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_changed_repos_skips_ignored() {
+        let (clone_dir, _source, _ct, _st) = crate::testutil::setup_clone_repo();
+        std::fs::write(clone_dir.join(".gitignore"), "target/\n").unwrap();
+        let repo_dirs = vec![("github.com/acme/api".to_string(), clone_dir.clone())];
+
+        let ignored_only = vec![clone_dir.join("target/debug/out")];
+        assert!(changed_repos(&repo_dirs, &ignored_only).is_empty());
+
+        let source = vec![clone_dir.join("target/x"), clone_dir.join("lib.rs")];
+        assert_eq!(
+            changed_repos(&repo_dirs, &source)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["github.com/acme/api"]
+        );
+    }
+}
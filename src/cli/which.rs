@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use clap_complete::engine::ArgValueCandidates;
+
+use crate::config::Paths;
+use crate::error;
+use crate::output::{Output, PathOutput};
+use crate::workspace;
+
+use super::completers;
+
+pub fn cmd() -> Command {
+    Command::new("which")
+        .about("Print the path of a repo in a workspace [read-only]")
+        .long_about(
+            "Print the path of a repo in a workspace [read-only].\n\n\
+             Resolves a clone directory name, shortname, or full identity to the \
+             absolute path of its clone in the current (or named) workspace, for use \
+             in scripts:\n\n  \
+             cd \"$(wsp which api-gateway)\"",
+        )
+        .arg(Arg::new("repo").required(true).add(ArgValueCandidates::new(
+            completers::complete_workspace_repos,
+        )))
+        .arg(Arg::new("workspace").add(ArgValueCandidates::new(completers::complete_workspaces)))
+}
+
+pub fn run(matches: &ArgMatches, paths: &Paths) -> Result<Output> {
+    let repo = matches.get_one::<String>("repo").unwrap();
+    let ws_dir: PathBuf = match matches.get_one::<String>("workspace") {
+        Some(name) => paths.workspace_dir(name),
+        None => workspace::detect(&std::env::current_dir()?)?,
+    };
+
+    let meta =
+        workspace::load_metadata(&ws_dir).map_err(|e| error::prefixed(e, "reading workspace"))?;
+    let identity = meta.resolve_repo(repo)?;
+    let path = ws_dir.join(meta.dir_name(&identity)?);
+
+    Ok(Output::Path(PathOutput {
+        path: path.display().to_string(),
+    }))
+}
@@ -7,6 +7,8 @@ use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::error::{self, ErrorKind};
+
 pub const CURRENT_CONFIG_VERSION: u32 = 0;
 
 fn default_version() -> u32 {
@@ -21,6 +23,157 @@ fn is_current_version(v: &u32) -> bool {
 pub struct RepoEntry {
     pub url: String,
     pub added: DateTime<Utc>,
+    /// Bootstrap command (e.g. `npm ci`) run in each new clone of this repo by
+    /// `wsp new` and `wsp repo add`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub setup: Option<String>,
+    /// Subdirectory `wsp exec`, `wsp run` and `wsp watch` work in for this
+    /// repo, unless a workspace sets its own `exec-dir.<repo>`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exec_dir: Option<String>,
+}
+
+/// Commit author and signing setup for repos whose identity matches a
+/// pattern, written into each clone's local git config.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Committer {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// `user.signingkey`: a GPG key ID, or an SSH key path with the ssh format.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_key: Option<String>,
+    /// `gpg`, `ssh`, or `x509`; git's default (gpg) when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_format: Option<String>,
+}
+
+/// Values accepted for a committer's signing-format.
+pub const SIGNING_FORMATS: &[&str] = &["gpg", "ssh", "x509"];
+
+impl Committer {
+    /// The git config values this committer sets. A signing key turns on
+    /// `commit.gpgsign`.
+    pub fn git_config(&self) -> BTreeMap<String, String> {
+        let mut gc = BTreeMap::new();
+        if let Some(ref name) = self.name {
+            gc.insert("user.name".to_string(), name.clone());
+        }
+        if let Some(ref email) = self.email {
+            gc.insert("user.email".to_string(), email.clone());
+        }
+        if let Some(ref key) = self.signing_key {
+            gc.insert("user.signingkey".to_string(), key.clone());
+            gc.insert("commit.gpgsign".to_string(), "true".to_string());
+        }
+        if let Some(ref format) = self.signing_format {
+            // git calls the gpg format "openpgp".
+            let format = if format == "gpg" { "openpgp" } else { format };
+            gc.insert("gpg.format".to_string(), format.to_string());
+        }
+        gc
+    }
+
+    /// A field by its key name: `name`, `email`, `signing-key`, or
+    /// `signing-format`.
+    pub fn field_mut(&mut self, field: &str) -> Result<&mut Option<String>> {
+        match field {
+            "name" => Ok(&mut self.name),
+            "email" => Ok(&mut self.email),
+            "signing-key" => Ok(&mut self.signing_key),
+            "signing-format" => Ok(&mut self.signing_format),
+            _ => anyhow::bail!(
+                "unknown committer field {:?}: must be name, email, signing-key, or signing-format",
+                field
+            ),
+        }
+    }
+}
+
+/// Network settings for one host, applied through git's URL-matched
+/// `http.<url>.*` config to every HTTPS clone, fetch and push against it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HostSettings {
+    /// Proxy for the host, e.g. `http://proxy.acme.com:3128`; empty for a
+    /// direct connection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Seconds a transfer may stall before git gives up on it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u32>,
+    /// Other `http.*` options for the host, without the `http.` prefix.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http: Option<BTreeMap<String, String>>,
+}
+
+impl HostSettings {
+    /// The git config these settings set for `host`, scoped to
+    /// `https://<host>`.
+    pub fn git_config(&self, host: &str) -> Vec<(String, String)> {
+        let key = |name: &str| format!("http.https://{}.{}", host, name);
+        let mut gc = Vec::new();
+        if let Some(ref proxy) = self.proxy {
+            gc.push((key("proxy"), proxy.clone()));
+        }
+        if let Some(secs) = self.timeout {
+            gc.push((key("lowSpeedLimit"), "1".to_string()));
+            gc.push((key("lowSpeedTime"), secs.to_string()));
+        }
+        for (name, value) in self.http.iter().flatten() {
+            gc.push((key(name), value.clone()));
+        }
+        gc
+    }
+}
+
+/// The part of a host's [`HostSettings`] a `host.<host>.*` key names.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HostSetting<'a> {
+    Proxy,
+    Timeout,
+    /// `http.<key>`
+    Http(&'a str),
+}
+
+/// Returns the host and setting of a `host.<host>.proxy`,
+/// `host.<host>.timeout` or `host.<host>.http.<key>` key.
+pub fn key_host_setting(key: &str) -> Option<(&str, HostSetting<'_>)> {
+    let rest = key.strip_prefix("host.")?;
+    if let Some((host, http_key)) = rest.split_once(".http.") {
+        return Some((host, HostSetting::Http(http_key)));
+    }
+    if let Some(host) = rest.strip_suffix(".proxy") {
+        return Some((host, HostSetting::Proxy));
+    }
+    rest.strip_suffix(".timeout")
+        .map(|host| (host, HostSetting::Timeout))
+}
+
+/// Returns the pattern and field (e.g. `email`) of a
+/// `committer.<pattern>.<field>` key.
+pub fn key_committer(key: &str) -> Option<(&str, &str)> {
+    key.strip_prefix("committer.")?.rsplit_once('.')
+}
+
+/// Returns the URL prefix of a `url-rewrite.<prefix>` key.
+pub fn key_url_rewrite(key: &str) -> Option<&str> {
+    key.strip_prefix("url-rewrite.")
+}
+
+/// Returns the template part of a `workspaces-dir.<template>` key.
+pub fn key_template_root(key: &str) -> Option<&str> {
+    key.strip_prefix("workspaces-dir.")
+}
+
+/// The value under the longest pattern in `patterns` matching `identity`.
+fn best_match<'a, V>(patterns: Option<&'a BTreeMap<String, V>>, identity: &str) -> Option<&'a V> {
+    patterns
+        .into_iter()
+        .flatten()
+        .filter(|(pattern, _)| crate::util::glob_match(pattern, identity))
+        .max_by_key(|(pattern, _)| pattern.len())
+        .map(|(_, v)| v)
 }
 
 /// Value for an experimental feature: either a boolean toggle or a string mode.
@@ -101,6 +254,33 @@ pub const EXPERIMENTAL_KEYS: &[&str] = &["shell.tmux", "shell.prompt"];
 /// Valid values for `shell.tmux` (and legacy `experimental.shell-tmux`).
 pub const SHELL_TMUX_VALUES: &[&str] = &["window-title", "false"];
 
+/// Settings that can be overridden from the environment, above config.yaml:
+/// `(key, variable)`. Per-workspace settings still win over them.
+pub const ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("branch-prefix", "WSP_BRANCH_PREFIX"),
+    ("branch-template", "WSP_BRANCH_TEMPLATE"),
+    ("workspaces-dir", "WSP_WORKSPACES_DIR"),
+    ("sync-strategy", "WSP_SYNC_STRATEGY"),
+    ("agent-md", "WSP_AGENT_MD"),
+    ("commit-trailer", "WSP_COMMIT_TRAILER"),
+    ("push-guard", "WSP_PUSH_GUARD"),
+    ("gc.retention-days", "WSP_GC_RETENTION_DAYS"),
+    ("run.jobs", "WSP_JOBS"),
+    ("editor", "WSP_EDITOR"),
+    ("issue.name-template", "WSP_ISSUE_NAME_TEMPLATE"),
+    ("jira.url", "WSP_JIRA_URL"),
+    ("shell.tmux", "WSP_SHELL_TMUX"),
+    ("shell.prompt", "WSP_SHELL_PROMPT"),
+];
+
+/// The environment variable that overrides `key`, if there is one.
+pub fn env_var_for(key: &str) -> Option<&'static str> {
+    ENV_OVERRIDES
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, var)| *var)
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
     #[serde(
@@ -110,6 +290,12 @@ pub struct Config {
     pub version: u32,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub branch_prefix: Option<String>,
+    /// Branch prefixes for repos whose identity matches a pattern, e.g.
+    /// `github.com/oss-org/*: feature`, overriding `branch_prefix`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch_prefixes: Option<BTreeMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch_template: Option<String>,
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     pub repos: BTreeMap<String, RepoEntry>,
     #[serde(
@@ -121,12 +307,42 @@ pub struct Config {
     pub language_integrations: Option<BTreeMap<String, bool>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub workspaces_dir: Option<String>,
+    /// Workspaces dirs by template name, e.g. `oss: /home/jg/oss`:
+    /// `wsp new --template oss` creates its workspace there instead of in
+    /// `workspaces_dir`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template_workspaces_dirs: Option<BTreeMap<String, String>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub sync_strategy: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub agent_md: Option<bool>,
+    /// Install a prepare-commit-msg hook in clones that adds a
+    /// `Workspace: <name>` trailer to commit messages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_trailer: Option<bool>,
+    /// Install a pre-push hook in clones that rejects pushes to the
+    /// default branch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub push_guard: Option<bool>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub gc_retention_days: Option<u32>,
+    /// Default `--jobs` for `wsp run`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_jobs: Option<usize>,
+    /// Default `--jobs` for `wsp repo fetch`: mirrors fetched at once.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetch_jobs: Option<usize>,
+    /// Default `--retries` for `wsp repo fetch` after a network error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetch_retries: Option<u32>,
+    /// Default `--timeout` for `wsp repo fetch`, in seconds; 0 for none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetch_timeout: Option<u64>,
+    /// Default `--max-age` for `wsp repo fetch`, `wsp new` and `wsp sync`,
+    /// and the max age for the other commands that fetch mirrors, as written
+    /// (e.g. `10m`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetch_max_age: Option<String>,
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -138,24 +354,67 @@ pub struct Config {
     pub shell_tmux: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub shell_prompt: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue_name_template: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jira_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tasks: Option<BTreeMap<String, crate::task::TaskDef>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depends_on: Option<crate::deps::DependsOn>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<crate::hooks::Hooks>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub files: Option<crate::files::Files>,
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "forge")]
+    pub forges: Option<crate::forge::Forges>,
+    /// URL prefix rewrites, like git's `url.<base>.insteadOf`: repos whose
+    /// URL starts with a key are fetched and pushed through the value.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        rename = "url_rewrite"
+    )]
+    pub url_rewrites: Option<BTreeMap<String, String>>,
+    /// Network settings per host, e.g. the proxy a GitHub Enterprise host
+    /// is only reachable through.
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "host")]
+    pub hosts: Option<BTreeMap<String, HostSettings>>,
+    /// Commit author per repo identity pattern, e.g. `github.com/acme/*`.
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "committer")]
+    pub committers: Option<BTreeMap<String, Committer>>,
     #[serde(default, skip_serializing)]
     pub experimental: Option<ExperimentalConfig>,
+    /// Keys whose value came from a `WSP_*` variable, not config.yaml.
+    #[serde(skip)]
+    pub from_env: Vec<&'static str>,
 }
 
 impl Config {
+    /// Loads config.yaml with the [`ENV_OVERRIDES`] applied: the settings a
+    /// command runs with.
     pub fn load_from(path: &Path) -> Result<Config> {
+        let mut cfg = Self::load_file(path)?;
+        cfg.apply_env(|var| std::env::var(var).ok())?;
+        Ok(cfg)
+    }
+
+    /// Loads config.yaml as written, without environment overrides, for
+    /// commands that save it back.
+    pub fn load_file(path: &Path) -> Result<Config> {
         if !path.exists() {
             return Ok(Config::default());
         }
 
-        let data = crate::util::read_yaml_file(path)?;
+        let data = crate::migrate::load(
+            path,
+            CURRENT_CONFIG_VERSION,
+            crate::migrate::CONFIG,
+            "config.yaml",
+        )?;
         let mut cfg: Config = serde_yaml_ng::from_str(&data)?;
-        if cfg.version > CURRENT_CONFIG_VERSION {
-            eprintln!(
-                "warning: config.yaml has version {}, but this wsp only supports version {}. Some fields may be ignored.",
-                cfg.version, CURRENT_CONFIG_VERSION
-            );
-        }
 
         // Migrate experimental shell values to top-level fields
         if let Some(ref exp) = cfg.experimental {
@@ -172,6 +431,102 @@ impl Config {
         Ok(cfg)
     }
 
+    /// Overrides settings with the non-empty [`ENV_OVERRIDES`] variables
+    /// `lookup` finds. A value `wsp config set` would reject is an error.
+    fn apply_env(&mut self, lookup: impl Fn(&str) -> Option<String>) -> Result<()> {
+        for &(key, var) in ENV_OVERRIDES {
+            let Some(value) = lookup(var).filter(|v| !v.is_empty()) else {
+                continue;
+            };
+            self.set_scalar(key, &value).map_err(|e| {
+                error::tag(
+                    ErrorKind::Config,
+                    anyhow::anyhow!("{}={:?}: {}", var, value, e),
+                )
+            })?;
+            self.from_env.push(key);
+        }
+        Ok(())
+    }
+
+    /// Sets one of the [`ENV_OVERRIDES`] settings from its string form,
+    /// rejecting what `wsp config set` rejects: both go through here.
+    pub fn set_scalar(&mut self, key: &str, value: &str) -> Result<()> {
+        let flag = || -> Result<bool> {
+            value
+                .parse()
+                .map_err(|_| anyhow::anyhow!("value must be true or false"))
+        };
+        match key {
+            "branch-prefix" => self.branch_prefix = Some(value.to_string()),
+            "branch-template" => {
+                crate::workspace::validate_branch_template(value)?;
+                self.branch_template = Some(value.to_string());
+            }
+            "workspaces-dir" => {
+                if !Path::new(value).is_absolute() {
+                    anyhow::bail!("workspaces-dir must be an absolute path");
+                }
+                self.workspaces_dir = Some(value.to_string());
+            }
+            "sync-strategy" => {
+                if !matches!(value, "rebase" | "merge") {
+                    anyhow::bail!("sync-strategy must be 'rebase' or 'merge'");
+                }
+                self.sync_strategy = Some(value.to_string());
+            }
+            "agent-md" => self.agent_md = Some(flag()?),
+            "commit-trailer" => self.commit_trailer = Some(flag()?),
+            "push-guard" => self.push_guard = Some(flag()?),
+            "gc.retention-days" => {
+                let days = value
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("value must be a non-negative integer"))?;
+                self.gc_retention_days = Some(days);
+            }
+            "run.jobs" => match value.parse::<usize>() {
+                Ok(n) if n > 0 => self.run_jobs = Some(n),
+                _ => anyhow::bail!("run.jobs must be a positive integer"),
+            },
+            "editor" => {
+                if value.trim().is_empty() {
+                    anyhow::bail!("editor must not be empty");
+                }
+                self.editor = Some(value.to_string());
+            }
+            "issue.name-template" => {
+                crate::issue::validate_template(value)?;
+                self.issue_name_template = Some(value.to_string());
+            }
+            "jira.url" => {
+                let u: url::Url = value
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("invalid jira.url {:?}: {}", value, e))?;
+                if !matches!(u.scheme(), "http" | "https") {
+                    anyhow::bail!("jira.url must be an http(s) URL");
+                }
+                self.jira_url = Some(value.trim_end_matches('/').to_string());
+            }
+            "shell.tmux" => {
+                if !SHELL_TMUX_VALUES.contains(&value) {
+                    anyhow::bail!(
+                        "shell.tmux must be one of: {}",
+                        SHELL_TMUX_VALUES.join(", ")
+                    );
+                }
+                self.shell_tmux = Some(value.to_string());
+            }
+            "shell.prompt" => self.shell_prompt = Some(flag()?),
+            _ => {
+                return Err(error::tag(
+                    ErrorKind::Config,
+                    anyhow::anyhow!("unknown config key: {}", key),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     /// Hardcoded defaults for git config applied to each clone.
     pub fn default_git_config() -> BTreeMap<String, String> {
         BTreeMap::from([
@@ -182,10 +537,36 @@ impl Config {
         ])
     }
 
+    /// URL rewrites as `url.<base>.insteadOf` entries. Prefixes sharing a
+    /// base repeat its key, so they are added to a clone's config with
+    /// `git config --add` rather than set.
+    pub fn url_rewrite_git_config(&self) -> Vec<(String, String)> {
+        self.url_rewrites
+            .iter()
+            .flatten()
+            .map(|(prefix, base)| (format!("url.{}.insteadOf", base), prefix.clone()))
+            .collect()
+    }
+
+    /// Git config for reaching remotes — URL rewrites and each host's
+    /// settings — for every git command wsp runs. Keys can repeat.
+    pub fn network_git_config(&self) -> Vec<(String, String)> {
+        let mut gc = self.url_rewrite_git_config();
+        for (host, settings) in self.hosts.iter().flatten() {
+            gc.extend(settings.git_config(host));
+        }
+        gc
+    }
+
     /// Effective git config: hardcoded defaults merged with user overrides.
-    /// User values win over defaults.
+    /// User values win over defaults. Host settings are included, so git run
+    /// by hand in a clone reaches remotes the same way; URL rewrites, whose
+    /// keys repeat, come from [`Self::url_rewrite_git_config`].
     pub fn effective_git_config(&self) -> BTreeMap<String, String> {
         let mut result = Self::default_git_config();
+        for (host, settings) in self.hosts.iter().flatten() {
+            result.extend(settings.git_config(host));
+        }
         if let Some(ref overrides) = self.git_config {
             for (k, v) in overrides {
                 result.insert(k.clone(), v.clone());
@@ -217,6 +598,18 @@ impl Config {
             .is_some_and(|e| e.is_feature_enabled("shell-prompt"))
     }
 
+    /// The branch prefix from the most specific (longest) `branch_prefixes`
+    /// pattern matching `identity`, if any.
+    pub fn branch_prefix_rule(&self, identity: &str) -> Option<&str> {
+        best_match(self.branch_prefixes.as_ref(), identity).map(|p| p.as_str())
+    }
+
+    /// The committer from the most specific (longest) `committers` pattern
+    /// matching `identity`, if any.
+    pub fn committer_for(&self, identity: &str) -> Option<&Committer> {
+        best_match(self.committers.as_ref(), identity)
+    }
+
     pub fn upstream_url(&self, identity: &str) -> Option<&str> {
         self.repos.get(identity).map(|e| e.url.as_str())
     }
@@ -230,6 +623,7 @@ impl Config {
             tempfile::NamedTempFile::new_in(dir).context("creating temp file for atomic save")?;
         tmp.write_all(data.as_bytes())
             .context("writing config to temp file")?;
+        crate::migrate::back_up_older(path, CURRENT_CONFIG_VERSION, "config.yaml")?;
         tmp.persist(path).context("renaming temp file to config")?;
         Ok(())
     }
@@ -239,6 +633,9 @@ pub struct Paths {
     pub config_path: PathBuf,
     pub mirrors_dir: PathBuf,
     pub workspaces_dir: PathBuf,
+    /// Per-template workspaces dirs other than `workspaces_dir`. Workspaces
+    /// are looked up in all of them.
+    pub extra_workspaces_dirs: Vec<PathBuf>,
     pub gc_dir: PathBuf,
     pub templates_dir: PathBuf,
 }
@@ -246,25 +643,116 @@ pub struct Paths {
 impl Paths {
     /// Resolve paths from environment (XDG_DATA_HOME / HOME). Called once at startup.
     /// Loads config to check for a `workspaces_dir` override before falling back to default.
-    pub fn resolve() -> Result<Paths> {
-        let data = data_dir()?;
-        let config_path = data.join("config.yaml");
-        let cfg = Config::load_from(&config_path)?;
+    ///
+    /// The profile is `profile` (from `--profile`), else `$WSP_PROFILE`,
+    /// else the profile whose workspaces dir holds the current directory.
+    /// Without one, the default data directory is used.
+    ///
+    /// With `tolerate_bad_config`, a config.yaml that fails to load leaves
+    /// the default workspaces dir in place instead of failing, for the
+    /// commands that report what's wrong with it.
+    ///
+    /// config.yaml and templates still in the data dir are read there; with
+    /// `settle_config`, for the commands that change them, they are first
+    /// moved to the config dir.
+    pub fn resolve_profile(
+        profile: Option<&str>,
+        tolerate_bad_config: bool,
+        settle_config: bool,
+    ) -> Result<Paths> {
+        let base = data_dir()?;
+        let config_base = config_dir()?;
+        let profile = match profile {
+            Some(p) => Some(p.to_string()),
+            None => match std::env::var(PROFILE_ENV) {
+                Ok(p) if !p.is_empty() => Some(p),
+                _ => std::env::current_dir()
+                    .ok()
+                    .and_then(|cwd| detect_profile(&config_base, &base, &cwd)),
+            },
+        };
+        let (data, config) = match profile {
+            Some(ref name) => {
+                validate_profile_name(name)?;
+                (
+                    profiles_dir(&base).join(name),
+                    profiles_dir(&config_base).join(name),
+                )
+            }
+            None => (base, config_base),
+        };
+        let config = settle_config_dir(&config, &data, settle_config);
+        let config_path = config.join("config.yaml");
+        let cfg = match Config::load_from(&config_path) {
+            Err(_) if tolerate_bad_config => Config::default(),
+            result => result?,
+        };
         let workspaces_dir = match cfg.workspaces_dir {
             Some(ref dir) => PathBuf::from(dir),
-            None => default_workspaces_dir()?,
+            None => default_workspaces_dir_for(profile.as_deref())?,
         };
+        let mut extra_workspaces_dirs: Vec<PathBuf> = Vec::new();
+        for dir in cfg.template_workspaces_dirs.iter().flat_map(|m| m.values()) {
+            let dir = PathBuf::from(dir);
+            if dir != workspaces_dir && !extra_workspaces_dirs.contains(&dir) {
+                extra_workspaces_dirs.push(dir);
+            }
+        }
         Ok(Paths {
             config_path,
             mirrors_dir: data.join("mirrors"),
             gc_dir: data.join("gc"),
-            templates_dir: data.join("templates"),
+            templates_dir: config.join("templates"),
             workspaces_dir,
+            extra_workspaces_dirs,
         })
     }
 
-    /// The data directory (parent of config.yaml).
+    /// Every directory workspaces live in: `workspaces_dir` first, then the
+    /// per-template ones.
+    pub fn workspace_roots(&self) -> impl Iterator<Item = &Path> {
+        std::iter::once(self.workspaces_dir.as_path())
+            .chain(self.extra_workspaces_dirs.iter().map(PathBuf::as_path))
+    }
+
+    /// The directory of workspace `name`: the first root that has it, else
+    /// where a new one goes by default.
+    pub fn workspace_dir(&self, name: &str) -> PathBuf {
+        self.workspace_roots()
+            .map(|root| crate::workspace::dir(root, name))
+            .find(|dir| dir.exists())
+            .unwrap_or_else(|| crate::workspace::dir(&self.workspaces_dir, name))
+    }
+
+    /// These paths with `root` as the directory new workspaces go in, for a
+    /// template that has its own.
+    pub fn with_workspaces_dir(&self, root: &Path) -> Paths {
+        let mut extra_workspaces_dirs = vec![self.workspaces_dir.clone()];
+        extra_workspaces_dirs.extend(
+            self.extra_workspaces_dirs
+                .iter()
+                .filter(|d| d.as_path() != root)
+                .cloned(),
+        );
+        Paths {
+            config_path: self.config_path.clone(),
+            mirrors_dir: self.mirrors_dir.clone(),
+            workspaces_dir: root.to_path_buf(),
+            extra_workspaces_dirs,
+            gc_dir: self.gc_dir.clone(),
+            templates_dir: self.templates_dir.clone(),
+        }
+    }
+
+    /// The data directory (parent of the mirrors dir). config.yaml and
+    /// templates may live elsewhere, in the config directory.
     pub fn data_dir(&self) -> &Path {
+        self.mirrors_dir.parent().unwrap_or(Path::new(""))
+    }
+
+    /// The config directory (parent of config.yaml), home of the global
+    /// wspignore too.
+    pub fn config_dir(&self) -> &Path {
         self.config_path.parent().unwrap_or(Path::new(""))
     }
 
@@ -277,6 +765,7 @@ impl Paths {
             gc_dir: data_dir.join("gc"),
             templates_dir: data_dir.join("templates"),
             workspaces_dir: workspaces_dir.to_path_buf(),
+            extra_workspaces_dirs: Vec::new(),
         }
     }
 }
@@ -297,14 +786,193 @@ fn data_dir() -> Result<PathBuf> {
     )
 }
 
+/// Resolves the wsp config directory, home of config.yaml and templates.
+/// Accepts injectable overrides for testing.
+pub fn config_dir_with(xdg_config_home: Option<&str>, home: Option<&Path>) -> Result<PathBuf> {
+    if let Some(xdg) = xdg_config_home.filter(|s| !s.is_empty()) {
+        return Ok(PathBuf::from(xdg).join("wsp"));
+    }
+    let home = home.context("cannot determine home directory")?;
+    Ok(home.join(".config").join("wsp"))
+}
+
+fn config_dir() -> Result<PathBuf> {
+    config_dir_with(
+        std::env::var("XDG_CONFIG_HOME").ok().as_deref(),
+        dirs::home_dir().as_deref(),
+    )
+}
+
+/// Where config.yaml and templates are read from: `config_dir`, unless they
+/// are still in `data_dir`, where versions before the split kept them. With
+/// `settle`, they are moved to `config_dir` first, under the config lock; if
+/// they can't be, they're used where they are.
+fn settle_config_dir(config_dir: &Path, data_dir: &Path, settle: bool) -> PathBuf {
+    if find_config(config_dir, data_dir).parent() != Some(data_dir) {
+        return config_dir.to_path_buf();
+    }
+    if !settle {
+        return data_dir.to_path_buf();
+    }
+    let moved = crate::filelock::lock_config(&data_dir.join("config.yaml")).and_then(|_lock| {
+        // Another command may have moved them while this one waited.
+        if find_config(config_dir, data_dir).parent() != Some(data_dir) {
+            return Ok(Vec::new());
+        }
+        move_config(data_dir, config_dir)
+    });
+    match moved {
+        Ok(moved) if moved.is_empty() => config_dir.to_path_buf(),
+        Ok(moved) => {
+            eprintln!(
+                "Moved {} from {} to {}",
+                moved.join(" and "),
+                data_dir.display(),
+                config_dir.display()
+            );
+            config_dir.to_path_buf()
+        }
+        Err(e) => {
+            tracing::warn!(
+                "could not move config.yaml to {}, using it in {}: {:#}",
+                config_dir.display(),
+                data_dir.display(),
+                e
+            );
+            data_dir.to_path_buf()
+        }
+    }
+}
+
+/// Moves config.yaml and templates from `from` to `to`, returning what was
+/// moved. Templates go first: config.yaml in `to` marks the move as done.
+fn move_config(from: &Path, to: &Path) -> Result<Vec<&'static str>> {
+    fs::create_dir_all(to).with_context(|| format!("creating {}", to.display()))?;
+    let mut moved = Vec::new();
+    let templates = from.join("templates");
+    if templates.is_dir() && !to.join("templates").exists() {
+        crate::gc::move_dir(&templates, &to.join("templates")).context("moving templates")?;
+        moved.push("templates");
+    }
+    let (src, dest) = (from.join("config.yaml"), to.join("config.yaml"));
+    if fs::rename(&src, &dest).is_err() {
+        // Across filesystems: copy next to the destination and rename it
+        // into place, so config.yaml never appears half written.
+        let mut tmp = tempfile::NamedTempFile::new_in(to).context("creating temp file")?;
+        tmp.write_all(&fs::read(&src).context("reading config.yaml")?)
+            .context("copying config.yaml")?;
+        tmp.as_file().sync_all()?;
+        tmp.persist(&dest)
+            .context("renaming config.yaml into place")?;
+        fs::remove_file(&src).context("removing the old config.yaml")?;
+    }
+    moved.insert(0, "config.yaml");
+    Ok(moved)
+}
+
+/// config.yaml in `config_dir`, or in `data_dir` if it hasn't been moved
+/// yet. Reads only; [`settle_config_dir`] does the move.
+fn find_config(config_dir: &Path, data_dir: &Path) -> PathBuf {
+    let path = config_dir.join("config.yaml");
+    let legacy = data_dir.join("config.yaml");
+    if !path.exists() && legacy.exists() {
+        legacy
+    } else {
+        path
+    }
+}
+
 /// Resolves the default workspaces directory. Accepts injectable home for testing.
 pub fn default_workspaces_dir_with(home: Option<&Path>) -> Result<PathBuf> {
     let home = home.context("cannot determine home directory")?;
     Ok(home.join("dev").join("workspaces"))
 }
 
-fn default_workspaces_dir() -> Result<PathBuf> {
-    default_workspaces_dir_with(dirs::home_dir().as_deref())
+/// The default workspaces directory of a profile: `~/dev/workspaces-<name>`,
+/// or `~/dev/workspaces` without one.
+fn default_workspaces_dir_for(profile: Option<&str>) -> Result<PathBuf> {
+    let dir = default_workspaces_dir_with(dirs::home_dir().as_deref())?;
+    Ok(match profile {
+        Some(name) => dir.with_file_name(format!("workspaces-{}", name)),
+        None => dir,
+    })
+}
+
+/// Environment variable selecting a profile when `--profile` isn't given.
+pub const PROFILE_ENV: &str = "WSP_PROFILE";
+
+/// Where profiles keep their data directories, each laid out like the
+/// default one.
+fn profiles_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("profiles")
+}
+
+pub fn validate_profile_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || name.starts_with(['-', '.'])
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        anyhow::bail!(
+            "invalid profile name {:?} (allowed: a-z, A-Z, 0-9, dash, underscore, dot)",
+            name
+        );
+    }
+    if crate::util::is_windows_reserved_name(name) {
+        anyhow::bail!("profile name {:?} is reserved on Windows", name);
+    }
+    Ok(())
+}
+
+/// Names of the profiles under `config_dir` or `data_dir`, sorted.
+fn list_profiles(config_dir: &Path, data_dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = [config_dir, data_dir]
+        .iter()
+        .filter_map(|dir| fs::read_dir(profiles_dir(dir)).ok())
+        .flat_map(|entries| entries.flatten())
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().into_string().ok())
+        .filter(|n| validate_profile_name(n).is_ok())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// The profile whose workspaces dir contains `cwd`, so commands run inside
+/// a profile's workspace use that profile without being told.
+fn detect_profile(config_dir: &Path, data_dir: &Path, cwd: &Path) -> Option<String> {
+    list_profiles(config_dir, data_dir)
+        .into_iter()
+        .find(|name| {
+            let cfg = Config::load_from(&find_config(
+                &profiles_dir(config_dir).join(name),
+                &profiles_dir(data_dir).join(name),
+            ))
+            .unwrap_or_default();
+            let dir = match cfg.workspaces_dir {
+                Some(dir) => PathBuf::from(dir),
+                None => match default_workspaces_dir_for(Some(name)) {
+                    Ok(dir) => dir,
+                    Err(_) => return false,
+                },
+            };
+            cwd.starts_with(dir)
+                || cfg
+                    .template_workspaces_dirs
+                    .iter()
+                    .flat_map(|m| m.values())
+                    .any(|dir| cwd.starts_with(dir))
+        })
+}
+
+/// Names of the existing profiles, sorted.
+pub fn profile_names() -> Vec<String> {
+    match (config_dir(), data_dir()) {
+        (Ok(config), Ok(data)) => list_profiles(&config, &data),
+        _ => Vec::new(),
+    }
 }
 
 #[cfg(test)]
@@ -330,6 +998,161 @@ mod tests {
         assert_eq!(dir, PathBuf::from("/home/user/.local/share/wsp"));
     }
 
+    #[test]
+    fn test_validate_profile_name() {
+        let cases = vec![
+            ("work", true),
+            ("acme-consulting", true),
+            ("client_2.eu", true),
+            ("", false),
+            ("-x", false),
+            (".hidden", false),
+            ("a/b", false),
+            ("a b", false),
+            ("nul", false),
+        ];
+        for (name, ok) in cases {
+            assert_eq!(validate_profile_name(name).is_ok(), ok, "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn test_detect_profile() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = tmp.path().join("config");
+        let data = tmp.path().join("data");
+        // oss's config.yaml hasn't been moved out of its data dir yet.
+        for (name, dir) in [("acme", &config), ("oss", &data)] {
+            let cfg = Config {
+                workspaces_dir: Some(tmp.path().join(name).to_string_lossy().into_owned()),
+                ..Default::default()
+            };
+            cfg.save_to(&dir.join("profiles").join(name).join("config.yaml"))
+                .unwrap();
+        }
+        fs::create_dir_all(data.join("profiles").join("acme")).unwrap();
+        fs::create_dir_all(data.join("profiles").join(".junk")).unwrap();
+
+        assert_eq!(list_profiles(&config, &data), vec!["acme", "oss"]);
+        let cases = vec![
+            (tmp.path().join("acme/add-billing/api"), Some("acme")),
+            (tmp.path().join("oss"), Some("oss")),
+            (tmp.path().join("acme-other"), None),
+            (tmp.path().to_path_buf(), None),
+        ];
+        for (cwd, want) in cases {
+            assert_eq!(
+                detect_profile(&config, &data, &cwd).as_deref(),
+                want,
+                "{}",
+                cwd.display()
+            );
+        }
+    }
+
+    #[test]
+    fn test_apply_env() {
+        let file = Config {
+            branch_prefix: Some("jg".into()),
+            run_jobs: Some(8),
+            ..Default::default()
+        };
+        let env = BTreeMap::from([
+            ("WSP_BRANCH_PREFIX", "ci"),
+            ("WSP_JOBS", "2"),
+            ("WSP_PUSH_GUARD", "true"),
+            ("WSP_WORKSPACES_DIR", "/tmp/ws"),
+            ("WSP_JIRA_URL", "https://acme.atlassian.net/"),
+            ("WSP_EDITOR", ""),
+        ]);
+        let mut cfg = file.clone();
+        cfg.apply_env(|var| env.get(var).map(|v| v.to_string()))
+            .unwrap();
+        assert_eq!(cfg.branch_prefix.as_deref(), Some("ci"));
+        assert_eq!(cfg.run_jobs, Some(2));
+        assert_eq!(cfg.push_guard, Some(true));
+        assert_eq!(cfg.workspaces_dir.as_deref(), Some("/tmp/ws"));
+        assert_eq!(cfg.jira_url.as_deref(), Some("https://acme.atlassian.net"));
+        assert_eq!(cfg.editor, None, "empty variables are ignored");
+        assert_eq!(
+            cfg.from_env,
+            vec![
+                "branch-prefix",
+                "workspaces-dir",
+                "push-guard",
+                "run.jobs",
+                "jira.url"
+            ]
+        );
+
+        let cases = vec![
+            ("WSP_JOBS", "0", "run.jobs must be a positive integer"),
+            ("WSP_AGENT_MD", "yes", "true or false"),
+            ("WSP_SYNC_STRATEGY", "squash", "'rebase' or 'merge'"),
+            ("WSP_WORKSPACES_DIR", "ws", "absolute path"),
+            ("WSP_SHELL_TMUX", "on", "shell.tmux must be one of"),
+            ("WSP_GC_RETENTION_DAYS", "-1", "non-negative integer"),
+            ("WSP_EDITOR", " ", "editor must not be empty"),
+            ("WSP_JIRA_URL", "jira.acme.com", "invalid jira.url"),
+            ("WSP_JIRA_URL", "ftp://jira.acme.com", "http(s) URL"),
+        ];
+        for (var, value, want) in cases {
+            let mut cfg = file.clone();
+            let err = cfg
+                .apply_env(|v| (v == var).then(|| value.to_string()))
+                .unwrap_err();
+            let msg = format!("{:#}", err);
+            assert!(msg.contains(var), "{}: {}", var, msg);
+            assert!(msg.contains(want), "{}: {}", var, msg);
+        }
+    }
+
+    #[test]
+    fn test_config_dir() {
+        let cases = vec![
+            (Some("/custom/config"), "/custom/config/wsp"),
+            (Some(""), "/home/user/.config/wsp"),
+            (None, "/home/user/.config/wsp"),
+        ];
+        for (xdg, want) in cases {
+            let dir = config_dir_with(xdg, Some(Path::new("/home/user"))).unwrap();
+            assert_eq!(dir, PathBuf::from(want), "{:?}", xdg);
+        }
+        assert!(config_dir_with(None, None).is_err());
+    }
+
+    #[test]
+    fn test_settle_config_dir_moves_legacy_config() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = tmp.path().join("config");
+        let data = tmp.path().join("data");
+        fs::create_dir_all(data.join("templates")).unwrap();
+        fs::write(data.join("config.yaml"), "branch_prefix: jg\n").unwrap();
+        fs::write(data.join("templates/backend.yaml"), "repos: []\n").unwrap();
+
+        // Read where it is until a command that changes it runs.
+        assert_eq!(settle_config_dir(&config, &data, false), data);
+        assert!(!config.join("config.yaml").exists());
+
+        assert_eq!(settle_config_dir(&config, &data, true), config);
+        assert_eq!(
+            fs::read_to_string(config.join("config.yaml")).unwrap(),
+            "branch_prefix: jg\n"
+        );
+        assert!(config.join("templates/backend.yaml").exists());
+        assert!(!data.join("config.yaml").exists());
+        assert!(!data.join("templates").exists());
+
+        // Already moved, or a fresh install: nothing to do.
+        assert_eq!(settle_config_dir(&config, &data, false), config);
+        assert_eq!(settle_config_dir(&config, &data, true), config);
+        let fresh = tmp.path().join("fresh");
+        assert_eq!(
+            settle_config_dir(&fresh, &tmp.path().join("none"), true),
+            fresh
+        );
+    }
+
     #[test]
     fn test_data_dir_no_home_errors() {
         assert!(data_dir_with(None, None).is_err());
@@ -374,6 +1197,8 @@ mod tests {
             RepoEntry {
                 url: "git@github.com:user/repo-a.git".into(),
                 added: now,
+                setup: None,
+                exec_dir: None,
             },
         );
         cfg.repos.insert(
@@ -381,6 +1206,8 @@ mod tests {
             RepoEntry {
                 url: "git@github.com:user/repo-b.git".into(),
                 added: now,
+                setup: None,
+                exec_dir: None,
             },
         );
 
@@ -414,8 +1241,8 @@ mod tests {
         let cfg2 = Config::load_from(&cfg_path).unwrap();
 
         let li2 = cfg2.language_integrations.unwrap();
-        assert_eq!(li2["go"], true);
-        assert_eq!(li2["npm"], false);
+        assert!(li2["go"]);
+        assert!(!li2["npm"]);
     }
 
     #[test]
@@ -455,8 +1282,10 @@ mod tests {
         let tmp = tempfile::tempdir().unwrap();
         let cfg_path = tmp.path().join("config.yaml");
 
-        let mut cfg = Config::default();
-        cfg.workspaces_dir = Some("/home/user/projects".into());
+        let cfg = Config {
+            workspaces_dir: Some("/home/user/projects".into()),
+            ..Default::default()
+        };
         cfg.save_to(&cfg_path).unwrap();
 
         let cfg2 = Config::load_from(&cfg_path).unwrap();
@@ -470,11 +1299,13 @@ mod tests {
         std::fs::create_dir_all(&data_dir).unwrap();
         let cfg_path = data_dir.join("config.yaml");
 
-        let mut cfg = Config::default();
-        cfg.workspaces_dir = Some("/custom/workspaces".into());
+        let cfg = Config {
+            workspaces_dir: Some("/custom/workspaces".into()),
+            ..Default::default()
+        };
         cfg.save_to(&cfg_path).unwrap();
 
-        // Simulate what Paths::resolve does: load config, use override
+        // Simulate what Paths::resolve_profile does: load config, use override
         let loaded = Config::load_from(&cfg_path).unwrap();
         let ws_dir = match loaded.workspaces_dir {
             Some(ref dir) => PathBuf::from(dir),
@@ -500,6 +1331,31 @@ mod tests {
         assert_eq!(ws_dir, PathBuf::from("/home/user/dev/workspaces"));
     }
 
+    #[test]
+    fn test_workspace_dir_searches_every_root() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (work, oss) = (tmp.path().join("work"), tmp.path().join("oss"));
+        let mut paths = Paths::from_dirs(&tmp.path().join("data"), &work);
+        paths.extra_workspaces_dirs.push(oss.clone());
+        std::fs::create_dir_all(work.join("billing")).unwrap();
+        std::fs::create_dir_all(oss.join("fix-typo")).unwrap();
+
+        let cases = vec![
+            ("billing", work.join("billing")),
+            ("fix-typo", oss.join("fix-typo")),
+            ("new-one", work.join("new-one")),
+        ];
+        for (name, want) in cases {
+            assert_eq!(paths.workspace_dir(name), want, "{}", name);
+        }
+
+        let oss_paths = paths.with_workspaces_dir(&oss);
+        assert_eq!(oss_paths.workspaces_dir, oss);
+        assert_eq!(oss_paths.extra_workspaces_dirs, vec![work.clone()]);
+        assert_eq!(oss_paths.workspace_dir("new-one"), oss.join("new-one"));
+        assert_eq!(oss_paths.workspace_dir("billing"), work.join("billing"));
+    }
+
     #[test]
     fn test_version_round_trip() {
         let tmp = tempfile::tempdir().unwrap();
@@ -532,15 +1388,221 @@ mod tests {
     }
 
     #[test]
-    fn test_unknown_version_loads() {
+    fn test_future_version_refused() {
         let tmp = tempfile::tempdir().unwrap();
         let cfg_path = tmp.path().join("config.yaml");
 
         std::fs::write(&cfg_path, "version: 99\nbranch_prefix: test\n").unwrap();
 
-        let cfg = Config::load_from(&cfg_path).unwrap();
-        assert_eq!(cfg.version, 99);
-        assert_eq!(cfg.branch_prefix.as_deref(), Some("test"));
+        let err = Config::load_from(&cfg_path).unwrap_err().to_string();
+        assert!(err.contains("config.yaml has version 99"), "{}", err);
+    }
+
+    #[test]
+    fn test_branch_prefix_rule() {
+        let cfg = Config {
+            branch_prefix: Some("jg".into()),
+            branch_prefixes: Some(BTreeMap::from([
+                ("github.com/acme/*".to_string(), "acme".to_string()),
+                ("github.com/acme/api".to_string(), "api".to_string()),
+                ("github.com/oss-org/*".to_string(), "feature".to_string()),
+                ("gitlab.com/*".to_string(), "gl".to_string()),
+            ])),
+            ..Default::default()
+        };
+        let cases = vec![
+            ("github.com/acme/web", Some("acme")),
+            ("github.com/acme/api", Some("api")),
+            ("github.com/oss-org/lib", Some("feature")),
+            ("gitlab.com/team/sub/proj", Some("gl")),
+            ("github.com/acme-labs/web", None),
+            ("github.com/other/repo", None),
+        ];
+        for (identity, want) in cases {
+            assert_eq!(cfg.branch_prefix_rule(identity), want, "{}", identity);
+        }
+    }
+
+    #[test]
+    fn test_committer_for() {
+        let acme = Committer {
+            name: Some("J Ganoff".into()),
+            email: Some("jg@acme.com".into()),
+            ..Default::default()
+        };
+        let oss = Committer {
+            email: Some("jg@example.org".into()),
+            ..Default::default()
+        };
+        let cfg = Config {
+            committers: Some(BTreeMap::from([
+                ("github.com/acme/*".to_string(), acme.clone()),
+                ("github.com/*".to_string(), oss.clone()),
+            ])),
+            ..Default::default()
+        };
+        let cases = vec![
+            ("github.com/acme/api", Some(&acme)),
+            ("github.com/oss-org/lib", Some(&oss)),
+            ("gitlab.com/acme/api", None),
+        ];
+        for (identity, want) in cases {
+            assert_eq!(cfg.committer_for(identity), want, "{}", identity);
+        }
+
+        assert_eq!(
+            acme.git_config(),
+            BTreeMap::from([
+                ("user.email".to_string(), "jg@acme.com".to_string()),
+                ("user.name".to_string(), "J Ganoff".to_string()),
+            ])
+        );
+        assert_eq!(oss.git_config().len(), 1);
+    }
+
+    #[test]
+    fn test_committer_signing_git_config() {
+        let cases = vec![
+            (
+                "gpg key",
+                Some("ABCD1234"),
+                None,
+                vec![("commit.gpgsign", "true"), ("user.signingkey", "ABCD1234")],
+            ),
+            (
+                "ssh key",
+                Some("~/.ssh/id_ed25519.pub"),
+                Some("ssh"),
+                vec![
+                    ("commit.gpgsign", "true"),
+                    ("gpg.format", "ssh"),
+                    ("user.signingkey", "~/.ssh/id_ed25519.pub"),
+                ],
+            ),
+            (
+                "gpg format",
+                Some("ABCD1234"),
+                Some("gpg"),
+                vec![
+                    ("commit.gpgsign", "true"),
+                    ("gpg.format", "openpgp"),
+                    ("user.signingkey", "ABCD1234"),
+                ],
+            ),
+            (
+                "format only",
+                None,
+                Some("ssh"),
+                vec![("gpg.format", "ssh")],
+            ),
+        ];
+        for (name, key, format, want) in cases {
+            let c = Committer {
+                signing_key: key.map(String::from),
+                signing_format: format.map(String::from),
+                ..Default::default()
+            };
+            let want: BTreeMap<String, String> = want
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            assert_eq!(c.git_config(), want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_key_committer() {
+        let cases = vec![
+            (
+                "committer.github.com/acme/*.email",
+                Some(("github.com/acme/*", "email")),
+            ),
+            ("committer.gitlab.com.name", Some(("gitlab.com", "name"))),
+            ("committer.x", None),
+            ("committers.x.name", None),
+        ];
+        for (key, want) in cases {
+            assert_eq!(key_committer(key), want, "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_key_host_setting() {
+        let cases = vec![
+            (
+                "host.ghe.acme.com.proxy",
+                Some(("ghe.acme.com", HostSetting::Proxy)),
+            ),
+            (
+                "host.ghe.acme.com.timeout",
+                Some(("ghe.acme.com", HostSetting::Timeout)),
+            ),
+            (
+                "host.ghe.acme.com.http.sslCAInfo",
+                Some(("ghe.acme.com", HostSetting::Http("sslCAInfo"))),
+            ),
+            (
+                "host.http.acme.com.proxy",
+                Some(("http.acme.com", HostSetting::Proxy)),
+            ),
+            ("host.ghe.acme.com.user", None),
+            ("hosts.ghe.acme.com.proxy", None),
+        ];
+        for (key, want) in cases {
+            assert_eq!(key_host_setting(key), want, "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_network_git_config() {
+        let cfg: Config = serde_yaml_ng::from_str(
+            "url_rewrite:\n  https://github.com/: https://gh-proxy.acme.com/\n\
+             host:\n  ghe.acme.com:\n    proxy: http://proxy.acme.com:3128\n    timeout: 30\n\
+             \x20   http:\n      sslCAInfo: /etc/ssl/acme-ca.pem\n",
+        )
+        .unwrap();
+        let want = vec![
+            (
+                "url.https://gh-proxy.acme.com/.insteadOf",
+                "https://github.com/",
+            ),
+            (
+                "http.https://ghe.acme.com.proxy",
+                "http://proxy.acme.com:3128",
+            ),
+            ("http.https://ghe.acme.com.lowSpeedLimit", "1"),
+            ("http.https://ghe.acme.com.lowSpeedTime", "30"),
+            (
+                "http.https://ghe.acme.com.sslCAInfo",
+                "/etc/ssl/acme-ca.pem",
+            ),
+        ];
+        let got = cfg.network_git_config();
+        let got: Vec<(&str, &str)> = got.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        assert_eq!(got, want);
+    }
+
+    #[test]
+    fn test_url_rewrites_sharing_a_base() {
+        let cfg: Config = serde_yaml_ng::from_str(
+            "url_rewrite:\n  https://github.com/: https://proxy.acme.com/\n  \
+             git@github.com:: https://proxy.acme.com/\n",
+        )
+        .unwrap();
+        let want = vec![
+            ("url.https://proxy.acme.com/.insteadOf", "git@github.com:"),
+            (
+                "url.https://proxy.acme.com/.insteadOf",
+                "https://github.com/",
+            ),
+        ];
+        let got = cfg.url_rewrite_git_config();
+        let got: Vec<(&str, &str)> = got.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        assert_eq!(got, want);
+        assert!(
+            !cfg.effective_git_config()
+                .contains_key("url.https://proxy.acme.com/.insteadOf")
+        );
     }
 
     #[test]
@@ -632,8 +1694,10 @@ mod tests {
 
     #[test]
     fn test_experimental_string_value() {
-        let mut exp = ExperimentalConfig::default();
-        exp.enabled = true;
+        let mut exp = ExperimentalConfig {
+            enabled: true,
+            ..Default::default()
+        };
         exp.features.insert(
             "shell-tmux".into(),
             ExperimentalValue::String("window-title".into()),
@@ -644,8 +1708,10 @@ mod tests {
 
     #[test]
     fn test_shell_tmux_mode_new_key() {
-        let mut exp = ExperimentalConfig::default();
-        exp.enabled = true;
+        let mut exp = ExperimentalConfig {
+            enabled: true,
+            ..Default::default()
+        };
         exp.features.insert(
             "shell-tmux".into(),
             ExperimentalValue::String("window-title".into()),
@@ -655,8 +1721,10 @@ mod tests {
 
     #[test]
     fn test_shell_tmux_mode_deprecated_key() {
-        let mut exp = ExperimentalConfig::default();
-        exp.enabled = true;
+        let mut exp = ExperimentalConfig {
+            enabled: true,
+            ..Default::default()
+        };
         exp.features
             .insert("shell-tmux-title".into(), ExperimentalValue::Bool(true));
         assert_eq!(exp.shell_tmux_mode(), Some("window-title"));
@@ -664,8 +1732,10 @@ mod tests {
 
     #[test]
     fn test_shell_tmux_mode_new_key_overrides_deprecated() {
-        let mut exp = ExperimentalConfig::default();
-        exp.enabled = true;
+        let mut exp = ExperimentalConfig {
+            enabled: true,
+            ..Default::default()
+        };
         exp.features.insert(
             "shell-tmux".into(),
             ExperimentalValue::String("false".into()),
@@ -702,9 +1772,11 @@ mod tests {
         let tmp = tempfile::tempdir().unwrap();
         let cfg_path = tmp.path().join("config.yaml");
 
-        let mut cfg = Config::default();
-        cfg.shell_tmux = Some("window-title".into());
-        cfg.shell_prompt = Some(true);
+        let cfg = Config {
+            shell_tmux: Some("window-title".into()),
+            shell_prompt: Some(true),
+            ..Default::default()
+        };
         cfg.save_to(&cfg_path).unwrap();
 
         let yaml = fs::read_to_string(&cfg_path).unwrap();
@@ -0,0 +1,601 @@
+//! Strict checking of `config.yaml`.
+//!
+//! [`Config::load_from`] stops at the first value it can't deserialize and
+//! silently ignores keys it doesn't know. [`check`] instead reads the file
+//! key by key and reports every problem at once — unknown keys, values of the
+//! wrong type, invalid repo URLs, and references to repos that aren't
+//! registered — each with the line it's on.
+
+use std::fmt;
+use std::path::Path;
+
+use serde_yaml_ng::{Mapping, Value};
+
+use crate::config::{self, Config};
+use crate::{files, forge, giturl, hooks, issue, task, workspace};
+
+/// Keys a repo entry may have.
+const REPO_FIELDS: &[&str] = &["url", "added", "setup"];
+
+/// `fetch.retries` above this wait long enough to look like a hang.
+const MAX_QUIET_RETRIES: u32 = 8;
+
+/// `fetch.timeout` below this (in seconds) gives up on fetches that are
+/// just slow.
+const MIN_QUIET_TIMEOUT: u64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// wsp can't load the file, or will misbehave because of it.
+    Error,
+    /// The file loads, but part of it is ignored or points nowhere.
+    Warning,
+}
+
+/// A problem found in config.yaml.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub severity: Severity,
+    /// 1-based line of the offending key, when it can be found.
+    pub line: Option<usize>,
+    /// The offending key, dotted, e.g. `repos.github.com/acme/api`.
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        if !self.key.is_empty() {
+            write!(f, "{}: ", self.key)?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+/// Checks the config file at `path`, with relative `files` sources under
+/// `base`. A missing file has no problems.
+pub fn check_file(path: &Path, base: &Path) -> anyhow::Result<Vec<Problem>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let data = crate::util::read_yaml_file(path)?;
+    Ok(check(&data, base))
+}
+
+/// Checks config.yaml contents. `base` is the directory relative `files`
+/// sources are resolved against.
+pub fn check(data: &str, base: &Path) -> Vec<Problem> {
+    let mut c = Checker {
+        data,
+        problems: Vec::new(),
+    };
+    let doc: Value = match serde_yaml_ng::from_str(data) {
+        Ok(doc) => doc,
+        Err(e) => {
+            c.problems.push(Problem {
+                severity: Severity::Error,
+                line: e.location().map(|l| l.line()),
+                key: String::new(),
+                message: format!("not valid YAML: {}", e),
+            });
+            return c.problems;
+        }
+    };
+    let map = match doc {
+        Value::Null => return c.problems,
+        Value::Mapping(map) => map,
+        _ => {
+            c.error(&[], "config must be a mapping of keys to values".into());
+            return c.problems;
+        }
+    };
+
+    // A newer wsp's layout can't be judged by this one's rules.
+    if !c.check_version(&map) {
+        return c.problems;
+    }
+    let valid = c.check_types(&map);
+    let cfg: Config = serde_yaml_ng::from_value(Value::Mapping(valid)).unwrap_or_default();
+    c.check_repos(&map, &cfg);
+    c.check_values(&cfg, base);
+    c.problems.sort_by_key(|p| p.line);
+    c.problems
+}
+
+struct Checker<'a> {
+    data: &'a str,
+    problems: Vec<Problem>,
+}
+
+impl Checker<'_> {
+    fn push(&mut self, severity: Severity, path: &[&str], message: String) {
+        self.problems.push(Problem {
+            severity,
+            line: key_line(self.data, path),
+            key: path.join("."),
+            message,
+        });
+    }
+
+    fn error(&mut self, path: &[&str], message: String) {
+        self.push(Severity::Error, path, message);
+    }
+
+    fn warn(&mut self, path: &[&str], message: String) {
+        self.push(Severity::Warning, path, message);
+    }
+
+    /// The file's `version` is one this wsp reads. False if it isn't, and
+    /// the rest of the file shouldn't be checked.
+    fn check_version(&mut self, map: &Mapping) -> bool {
+        let Some(v) = map.get("version") else {
+            return true;
+        };
+        let current = crate::config::CURRENT_CONFIG_VERSION;
+        match v.as_u64().and_then(|v| u32::try_from(v).ok()) {
+            Some(version) if version <= current => true,
+            Some(version) => {
+                self.error(
+                    &["version"],
+                    format!(
+                        "version {} is newer than this wsp understands (up to {}); upgrade wsp",
+                        version, current
+                    ),
+                );
+                false
+            }
+            None => {
+                self.error(&["version"], format!("invalid version {:?}", v));
+                false
+            }
+        }
+    }
+
+    /// Deserializes each top-level key on its own (each entry, for keys
+    /// holding a map) so one bad value doesn't hide the rest. Returns the
+    /// keys and entries that deserialized.
+    fn check_types(&mut self, map: &Mapping) -> Mapping {
+        let mut valid = Mapping::new();
+        for (k, v) in map {
+            let Some(key) = k.as_str() else {
+                self.error(&[], format!("key {:?} is not a string", k));
+                continue;
+            };
+            // Checked by check_version.
+            if key == "version" {
+                valid.insert(k.clone(), v.clone());
+                continue;
+            }
+
+            let value = match v {
+                Value::Mapping(entries) => {
+                    let mut ok = Mapping::new();
+                    for (ek, ev) in entries {
+                        let entry_key = ek.as_str().unwrap_or_default();
+                        match probe(key, &Value::Mapping(single(ek, ev))) {
+                            Ok(_) => {
+                                ok.insert(ek.clone(), ev.clone());
+                            }
+                            Err(e) => self.error(&[key, entry_key], e.to_string()),
+                        }
+                    }
+                    Value::Mapping(ok)
+                }
+                _ => match probe(key, v) {
+                    Ok(_) => v.clone(),
+                    Err(e) => {
+                        self.error(&[key], e.to_string());
+                        continue;
+                    }
+                },
+            };
+
+            if is_ignored(key, &value) {
+                let alt = key.replace('-', "_");
+                let message = if alt != key && !is_ignored(&alt, &value) {
+                    format!("unknown key (did you mean {}?)", alt)
+                } else {
+                    "unknown key".to_string()
+                };
+                self.warn(&[key], message);
+                continue;
+            }
+            valid.insert(k.clone(), value);
+        }
+        valid
+    }
+
+    /// Repo entries: known fields, parseable URLs, and URLs that match the
+    /// identity they're registered under.
+    fn check_repos(&mut self, map: &Mapping, cfg: &Config) {
+        if let Some(Value::Mapping(repos)) = map.get("repos") {
+            for (id, entry) in repos {
+                let (Some(id), Value::Mapping(fields)) = (id.as_str(), entry) else {
+                    continue;
+                };
+                for field in fields.keys().filter_map(|f| f.as_str()) {
+                    if !REPO_FIELDS.contains(&field) {
+                        self.warn(&["repos", id, field], "unknown key".into());
+                    }
+                }
+            }
+        }
+
+        for (identity, entry) in &cfg.repos {
+            match giturl::parse(&entry.url) {
+                Ok(parsed) if parsed.identity() != *identity => self.warn(
+                    &["repos", identity, "url"],
+                    format!(
+                        "URL {} is for {}, not {}",
+                        entry.url,
+                        parsed.identity(),
+                        identity
+                    ),
+                ),
+                Ok(_) => {}
+                Err(e) => self.error(
+                    &["repos", identity, "url"],
+                    format!("{:?}: {}", entry.url, e),
+                ),
+            }
+        }
+    }
+
+    /// Values that deserialize but that wsp would reject or can't use.
+    fn check_values(&mut self, cfg: &Config, base: &Path) {
+        if let Some(ref s) = cfg.sync_strategy
+            && s != "rebase"
+            && s != "merge"
+        {
+            self.error(
+                &["sync_strategy"],
+                format!("{:?} must be rebase or merge", s),
+            );
+        }
+        if let Some(ref t) = cfg.branch_template
+            && let Err(e) = workspace::validate_branch_template(t)
+        {
+            self.error(&["branch_template"], e.to_string());
+        }
+        if let Some(ref dir) = cfg.workspaces_dir
+            && !Path::new(dir).is_absolute()
+        {
+            self.error(
+                &["workspaces_dir"],
+                format!("{:?} must be an absolute path", dir),
+            );
+        }
+        for (name, dir) in cfg.template_workspaces_dirs.iter().flatten() {
+            if !Path::new(dir).is_absolute() {
+                self.error(
+                    &["template_workspaces_dirs", name],
+                    format!("{:?} must be an absolute path", dir),
+                );
+            }
+        }
+        if let Some(ref mode) = cfg.shell_tmux
+            && !config::SHELL_TMUX_VALUES.contains(&mode.as_str())
+        {
+            self.error(
+                &["shell_tmux"],
+                format!(
+                    "{:?} must be one of: {}",
+                    mode,
+                    config::SHELL_TMUX_VALUES.join(", ")
+                ),
+            );
+        }
+        if let Some(ref t) = cfg.issue_name_template
+            && let Err(e) = issue::validate_template(t)
+        {
+            self.error(&["issue_name_template"], e.to_string());
+        }
+        if cfg.run_jobs == Some(0) {
+            self.error(&["run_jobs"], "must be at least 1".into());
+        }
+        if cfg.fetch_jobs == Some(0) {
+            self.error(&["fetch_jobs"], "must be at least 1".into());
+        }
+        if let Some(retries) = cfg.fetch_retries
+            && retries > MAX_QUIET_RETRIES
+        {
+            // The wait doubles after each retry, starting at a second.
+            let waits: u64 = (0..retries).map(|i| 1u64 << i.min(16)).sum();
+            self.warn(
+                &["fetch_retries"],
+                format!(
+                    "{} retries wait {} minutes between them before giving up on an unreachable repo",
+                    retries,
+                    waits / 60
+                ),
+            );
+        }
+        if let Some(secs) = cfg.fetch_timeout
+            && secs > 0
+            && secs < MIN_QUIET_TIMEOUT
+        {
+            self.warn(
+                &["fetch_timeout"],
+                format!(
+                    "{}s cuts off fetches of large repos before they finish; 0 means no timeout",
+                    secs
+                ),
+            );
+        }
+        if let Some(ref age) = cfg.fetch_max_age
+            && let Err(e) = crate::util::parse_duration(age)
+        {
+            self.warn(
+                &["fetch_max_age"],
+                format!("{}; every mirror is fetched", e),
+            );
+        }
+
+        for (host, kind) in cfg.forges.iter().flatten() {
+            if let Err(e) = forge::Forge::parse(kind) {
+                self.error(&["forge", host], e.to_string());
+            }
+        }
+        for (prefix, base) in cfg.url_rewrites.iter().flatten() {
+            if base.is_empty() {
+                self.error(&["url_rewrite", prefix], "must not be empty".into());
+            }
+        }
+        for (host, settings) in cfg.hosts.iter().flatten() {
+            if settings.timeout == Some(0) {
+                self.error(&["host", host, "timeout"], "must be at least 1".into());
+            }
+            // git applies them through `http.<url>.*`, which SSH never reads.
+            let over_ssh = cfg
+                .repos
+                .values()
+                .filter(|e| !e.url.starts_with("https://"))
+                .filter(|e| giturl::parse(&e.url).is_ok_and(|p| p.host == *host))
+                .count();
+            if over_ssh > 0 {
+                self.warn(
+                    &["host", host],
+                    format!(
+                        "{} repo(s) on {} are registered over SSH, which these settings don't apply to; set SSH options in ~/.ssh/config",
+                        over_ssh, host
+                    ),
+                );
+            }
+        }
+        for (pattern, committer) in cfg.committers.iter().flatten() {
+            if let Some(ref format) = committer.signing_format
+                && !config::SIGNING_FORMATS.contains(&format.as_str())
+            {
+                self.error(
+                    &["committer", pattern, "signing_format"],
+                    format!(
+                        "{:?} must be one of: {}",
+                        format,
+                        config::SIGNING_FORMATS.join(", ")
+                    ),
+                );
+            }
+        }
+        for name in cfg.tasks.iter().flat_map(|m| m.keys()) {
+            if let Err(e) = task::validate_name(name) {
+                self.error(&["tasks", name], e.to_string());
+            }
+        }
+        for event in cfg.hooks.iter().flat_map(|m| m.keys()) {
+            if let Err(e) = hooks::validate_event(event) {
+                self.error(&["hooks", event], e.to_string());
+            }
+        }
+        for (dest, source) in cfg.files.iter().flatten() {
+            if let Err(e) = files::validate_dest(dest) {
+                self.error(&["files", dest], e.to_string());
+            } else if !files::source_path(source, base).exists() {
+                self.warn(
+                    &["files", dest],
+                    format!("source {} does not exist", source),
+                );
+            }
+        }
+
+        // depends_on entries must name registered repos.
+        let identities: Vec<String> = cfg.repos.keys().cloned().collect();
+        for (repo, on) in cfg.depends_on.iter().flatten() {
+            for name in std::iter::once(repo).chain(on) {
+                if let Err(e) = giturl::resolve(name, &identities) {
+                    self.warn(&["depends_on", repo], e.to_string());
+                }
+            }
+        }
+    }
+}
+
+fn single(k: &Value, v: &Value) -> Mapping {
+    let mut m = Mapping::new();
+    m.insert(k.clone(), v.clone());
+    m
+}
+
+/// Deserializes a config holding only `key`.
+fn probe(key: &str, value: &Value) -> Result<Config, serde_yaml_ng::Error> {
+    serde_yaml_ng::from_value(Value::Mapping(single(&key.into(), value)))
+}
+
+/// Whether deserialization drops `key` entirely, i.e. Config has no such
+/// field. Empty values are never reported: they'd be dropped either way.
+fn is_ignored(key: &str, value: &Value) -> bool {
+    let empty = match value {
+        Value::Null => true,
+        Value::Mapping(m) => m.is_empty(),
+        Value::Sequence(s) => s.is_empty(),
+        _ => false,
+    };
+    // experimental is read but never written back.
+    if empty || key == "experimental" {
+        return false;
+    }
+    let Ok(cfg) = probe(key, value) else {
+        return false;
+    };
+    serde_yaml_ng::to_value(&cfg).ok() == serde_yaml_ng::to_value(Config::default()).ok()
+}
+
+/// Finds the 1-based line of a nested key in block-style YAML: each
+/// element of `path` is looked for among the keys of the block under the
+/// previous one.
+fn key_line(data: &str, path: &[&str]) -> Option<usize> {
+    let mut depth = 0;
+    let mut parent_indent: Option<usize> = None;
+    // Indent of the keys in the current block, once seen.
+    let mut block_indent = Some(0);
+    let mut found = None;
+    for (i, line) in data.lines().enumerate() {
+        if depth == path.len() {
+            break;
+        }
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        if parent_indent.is_some_and(|p| indent <= p) {
+            break;
+        }
+        if *block_indent.get_or_insert(indent) != indent {
+            continue;
+        }
+        let Some((key, _)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().trim_matches(|c| c == '"' || c == '\'');
+        if key == path[depth] {
+            parent_indent = Some(indent);
+            block_indent = None;
+            depth += 1;
+            found = Some(i + 1);
+        }
+    }
+    if depth == path.len() { found } else { None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(data: &str) -> Vec<(Option<usize>, String, Severity)> {
+        check(data, Path::new("/nonexistent"))
+            .into_iter()
+            .map(|p| (p.line, p.key, p.severity))
+            .collect()
+    }
+
+    #[test]
+    fn test_check_valid() {
+        let cases = vec![
+            "",
+            "branch_prefix: jg\nsync_strategy: merge\n",
+            "repos:\n  github.com/acme/api:\n    url: git@github.com:acme/api.git\n    added: 2026-01-01T00:00:00Z\n",
+            "language_integrations:\n  go: true\ngit_config:\n  push.default: simple\n",
+            "experimental:\n  shell-prompt: true\n",
+            "repos: {}\ntasks:\n  test: make test\n",
+            "version: 0\nbranch_prefix: jg\n",
+        ];
+        for data in cases {
+            assert_eq!(problems(data), vec![], "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn test_check_problems() {
+        use Severity::{Error, Warning};
+        let cases = vec![
+            (
+                "branch_prefix: jg\nbranch-prefix: x\n",
+                vec![(Some(2), "branch-prefix", Warning)],
+            ),
+            ("colour: blue\n", vec![(Some(1), "colour", Warning)]),
+            (
+                "agent_md: yes please\ngc_retention_days: -1\n",
+                vec![
+                    (Some(1), "agent_md", Error),
+                    (Some(2), "gc_retention_days", Error),
+                ],
+            ),
+            (
+                "repos:\n  github.com/acme/api:\n    url: x\n    added: never\n  github.com/acme/web:\n    url: not a url\n    added: 2026-01-01T00:00:00Z\n    branch: main\n",
+                vec![
+                    (Some(2), "repos.github.com/acme/api", Error),
+                    (Some(6), "repos.github.com/acme/web.url", Error),
+                    (Some(8), "repos.github.com/acme/web.branch", Warning),
+                ],
+            ),
+            (
+                "sync_strategy: squash\nrun_jobs: 0\nforge:\n  git.acme.com: gitea\n",
+                vec![
+                    (Some(1), "sync_strategy", Error),
+                    (Some(2), "run_jobs", Error),
+                    (Some(4), "forge.git.acme.com", Error),
+                ],
+            ),
+            (
+                "fetch_jobs: 0\nfetch_retries: 20\nfetch_timeout: 3\n",
+                vec![
+                    (Some(1), "fetch_jobs", Error),
+                    (Some(2), "fetch_retries", Warning),
+                    (Some(3), "fetch_timeout", Warning),
+                ],
+            ),
+            (
+                "fetch_max_age: soon\n",
+                vec![(Some(1), "fetch_max_age", Warning)],
+            ),
+            (
+                "fetch_retries: 8\nfetch_timeout: 0\nfetch_max_age: 10m\n",
+                vec![],
+            ),
+            (
+                "depends_on:\n  api: [proto]\n",
+                vec![
+                    (Some(2), "depends_on.api", Warning),
+                    (Some(2), "depends_on.api", Warning),
+                ],
+            ),
+            ("repos: [a\n", vec![(Some(2), "", Error)]),
+            (
+                "version: 99\ncolour: blue\n",
+                vec![(Some(1), "version", Error)],
+            ),
+            ("version: -1\n", vec![(Some(1), "version", Error)]),
+            (
+                "host:\n  ghe.acme.com:\n    timeout: 30\nrepos:\n  ghe.acme.com/acme/api:\n    url: git@ghe.acme.com:acme/api.git\n    added: 2026-01-01T00:00:00Z\n  ghe.acme.com/acme/web:\n    url: https://ghe.acme.com/acme/web.git\n    added: 2026-01-01T00:00:00Z\n",
+                vec![(Some(2), "host.ghe.acme.com", Warning)],
+            ),
+            ("version: two\n", vec![(Some(1), "version", Error)]),
+        ];
+        for (data, want) in cases {
+            let want: Vec<_> = want
+                .into_iter()
+                .map(|(line, key, sev)| (line, key.to_string(), sev))
+                .collect();
+            assert_eq!(problems(data), want, "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn test_key_line() {
+        let data = "repos:\n  # comment\n  \"github.com/acme/api\":\n    url: x\nurl: y\n";
+        let cases = vec![
+            (vec!["repos"], Some(1)),
+            (vec!["repos", "github.com/acme/api"], Some(3)),
+            (vec!["repos", "github.com/acme/api", "url"], Some(4)),
+            (vec!["url"], Some(5)),
+            (vec!["repos", "url"], None),
+            (vec!["repos", "github.com/acme/web"], None),
+        ];
+        for (path, want) in cases {
+            assert_eq!(key_line(data, &path), want, "{:?}", path);
+        }
+    }
+}
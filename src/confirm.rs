@@ -0,0 +1,58 @@
+use std::io::IsTerminal;
+
+use anyhow::{Result, bail};
+
+use crate::util::read_stdin_line;
+
+/// Ask before a destructive operation. `summary` describes what will be
+/// destroyed; `question` is the yes/no prompt. `assume_yes` is the global
+/// `--yes`. Without a terminal to ask on, the operation is refused rather
+/// than assumed.
+pub fn confirm(assume_yes: bool, summary: &str, question: &str) -> Result<()> {
+    if assume_yes {
+        return Ok(());
+    }
+    if !std::io::stdin().is_terminal() {
+        bail!(
+            "{}\n\nRefusing without confirmation; rerun with --yes",
+            summary
+        );
+    }
+    eprintln!("{}", summary);
+    eprint!("{} [y/N]: ", question);
+    if is_yes(&read_stdin_line()) {
+        Ok(())
+    } else {
+        bail!("aborted")
+    }
+}
+
+fn is_yes(answer: &str) -> bool {
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_yes() {
+        let cases = vec![
+            ("y\n", true),
+            ("YES\n", true),
+            (" yes ", true),
+            ("\n", false),
+            ("n\n", false),
+            ("", false),
+            ("yep\n", false),
+        ];
+        for (input, want) in cases {
+            assert_eq!(is_yes(input), want, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_confirm_assume_yes() {
+        assert!(confirm(true, "deletes everything", "Continue?").is_ok());
+    }
+}
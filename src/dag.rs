@@ -0,0 +1,207 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+
+use anyhow::{Result, bail};
+
+/// A dependency graph: each node maps to the set of nodes it depends on.
+/// Iteration order is deterministic (by `Ord`) so plans and reports are stable.
+#[derive(Debug, Clone)]
+pub struct Dag<N> {
+    deps: BTreeMap<N, BTreeSet<N>>,
+}
+
+impl<N: Ord + Clone + Display> Dag<N> {
+    pub fn new() -> Self {
+        Dag {
+            deps: BTreeMap::new(),
+        }
+    }
+
+    pub fn add_node(&mut self, node: N) {
+        self.deps.entry(node).or_default();
+    }
+
+    /// Record that `node` depends on `dep` (`dep` must finish first).
+    pub fn add_edge(&mut self, node: N, dep: N) {
+        self.add_node(dep.clone());
+        self.deps.entry(node).or_default().insert(dep);
+    }
+
+    pub fn contains(&self, node: &N) -> bool {
+        self.deps.contains_key(node)
+    }
+
+    pub fn deps(&self, node: &N) -> impl Iterator<Item = &N> {
+        self.deps.get(node).into_iter().flatten()
+    }
+
+    /// Returns `nodes` plus every node that depends on any of them, directly or
+    /// transitively.
+    pub fn with_dependents(&self, nodes: impl IntoIterator<Item = N>) -> BTreeSet<N> {
+        let mut out: BTreeSet<N> = nodes.into_iter().collect();
+        loop {
+            let added: Vec<N> = self
+                .deps
+                .iter()
+                .filter(|(node, deps)| !out.contains(*node) && deps.iter().any(|d| out.contains(d)))
+                .map(|(node, _)| node.clone())
+                .collect();
+            if added.is_empty() {
+                return out;
+            }
+            out.extend(added);
+        }
+    }
+
+    /// Order nodes so every node comes after its dependencies. Ties are broken
+    /// by `Ord`. Errors with the offending path if the graph has a cycle.
+    pub fn toposort(&self) -> Result<Vec<N>> {
+        let mut remaining: BTreeMap<&N, usize> =
+            self.deps.iter().map(|(n, d)| (n, d.len())).collect();
+        let mut dependents: BTreeMap<&N, Vec<&N>> = BTreeMap::new();
+        for (node, deps) in &self.deps {
+            for dep in deps {
+                dependents.entry(dep).or_default().push(node);
+            }
+        }
+
+        let mut ready: BTreeSet<&N> = remaining
+            .iter()
+            .filter(|(_, n)| **n == 0)
+            .map(|(node, _)| *node)
+            .collect();
+        let mut order = Vec::with_capacity(self.deps.len());
+        while let Some(node) = ready.pop_first() {
+            remaining.remove(node);
+            order.push(node.clone());
+            for dependent in dependents.get(node).into_iter().flatten() {
+                let count = remaining.get_mut(dependent).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    ready.insert(dependent);
+                }
+            }
+        }
+
+        if !remaining.is_empty() {
+            bail!("dependency cycle: {}", describe_cycle(self, &remaining));
+        }
+        Ok(order)
+    }
+}
+
+/// Render one cycle among the nodes Kahn's algorithm could not order, as
+/// `a -> b -> a`. Every such node still has an unordered dependency, so
+/// following those from any of them must eventually revisit a node.
+fn describe_cycle<N: Ord + Clone + Display>(dag: &Dag<N>, stuck: &BTreeMap<&N, usize>) -> String {
+    let mut path: Vec<&N> = Vec::new();
+    let mut node = *stuck.keys().next().unwrap();
+    loop {
+        if let Some(pos) = path.iter().position(|n| *n == node) {
+            let mut names: Vec<String> = path[pos..].iter().map(|n| n.to_string()).collect();
+            names.push(node.to_string());
+            return names.join(" -> ");
+        }
+        path.push(node);
+        node = dag
+            .deps(node)
+            .find(|d| stuck.contains_key(d))
+            .expect("stuck node has a stuck dependency");
+    }
+}
+
+impl<N: Ord + Clone + Display> Default for Dag<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dag(edges: &[(&'static str, &'static str)], nodes: &[&'static str]) -> Dag<&'static str> {
+        let mut d = Dag::new();
+        for n in nodes {
+            d.add_node(*n);
+        }
+        for (node, dep) in edges {
+            d.add_edge(*node, *dep);
+        }
+        d
+    }
+
+    #[test]
+    fn test_toposort() {
+        let cases = vec![
+            (
+                "no edges sorted by name",
+                vec![],
+                vec!["b", "a"],
+                vec!["a", "b"],
+            ),
+            (
+                "chain",
+                vec![("api", "proto"), ("web", "api")],
+                vec![],
+                vec!["proto", "api", "web"],
+            ),
+            (
+                "diamond",
+                vec![("d", "b"), ("d", "c"), ("b", "a"), ("c", "a")],
+                vec![],
+                vec!["a", "b", "c", "d"],
+            ),
+            (
+                "independent node interleaves by name",
+                vec![("b", "c")],
+                vec!["a"],
+                vec!["a", "c", "b"],
+            ),
+        ];
+        for (name, edges, nodes, want) in cases {
+            let got = dag(&edges, &nodes).toposort().unwrap();
+            assert_eq!(got, want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_with_dependents() {
+        let d = dag(
+            &[("api", "proto"), ("web", "api"), ("cli", "proto")],
+            &["docs"],
+        );
+        let cases = vec![
+            ("leaf change", vec!["web"], vec!["web"]),
+            ("mid change", vec!["api"], vec!["api", "web"]),
+            (
+                "root change",
+                vec!["proto"],
+                vec!["api", "cli", "proto", "web"],
+            ),
+            ("isolated", vec!["docs"], vec!["docs"]),
+            ("none", vec![], vec![]),
+        ];
+        for (name, changed, want) in cases {
+            let got: Vec<&str> = d.with_dependents(changed).into_iter().collect();
+            assert_eq!(got, want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_toposort_cycle() {
+        let cases = vec![
+            ("self loop", vec![("a", "a")], "a -> a"),
+            ("two node", vec![("a", "b"), ("b", "a")], "a -> b -> a"),
+            (
+                "cycle behind a tail",
+                vec![("x", "a"), ("a", "b"), ("b", "c"), ("c", "a")],
+                "a -> b -> c -> a",
+            ),
+        ];
+        for (name, edges, want) in cases {
+            let err = dag(&edges, &[]).toposort().unwrap_err().to_string();
+            assert!(err.contains(want), "{}: got {:?}", name, err);
+        }
+    }
+}
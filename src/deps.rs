@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail};
+
+use crate::dag::Dag;
+use crate::giturl;
+
+/// Declared repo dependencies: repo → repos it depends on. Stored under
+/// `depends_on` in config.yaml, templates, and workspace `.wsp.yaml`:
+///
+/// ```yaml
+/// depends_on:
+///   github.com/acme/api-gateway: [github.com/acme/proto]
+///   github.com/acme/web: [github.com/acme/api-gateway]
+/// ```
+///
+/// Entries are keyed by identity when written by `wsp config set`, but
+/// hand-edited shortnames are accepted and resolved when used.
+pub type DependsOn = BTreeMap<String, Vec<String>>;
+
+/// Returns the repo part of a `depends-on.<repo>` key. Only the prefix is
+/// normalized — repo names may legitimately contain underscores.
+pub fn key_repo(key: &str) -> Option<&str> {
+    let (prefix, repo) = key.split_once('.')?;
+    (prefix.replace('_', "-") == "depends-on").then_some(repo)
+}
+
+/// Resolve a `depends-on.<repo> <a,b,...>` setting against `identities`,
+/// returning the repo identity and the identities it depends on. An empty
+/// value is allowed (explicitly no dependencies).
+pub fn resolve_entry(
+    repo: &str,
+    value: &str,
+    identities: &[String],
+) -> Result<(String, Vec<String>)> {
+    let identity = giturl::resolve(repo, identities)?;
+    let mut deps = Vec::new();
+    for name in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let dep = giturl::resolve(name, identities)?;
+        if dep == identity {
+            bail!("{} cannot depend on itself", repo);
+        }
+        if !deps.contains(&dep) {
+            deps.push(dep);
+        }
+    }
+    Ok((identity, deps))
+}
+
+/// Merge `overrides` into `target`, an override replacing the entry for the
+/// same repo however either of them spells it. Keys that resolve against
+/// `identities` end up keyed by identity; others are kept as written.
+pub fn merge(target: &mut DependsOn, overrides: &DependsOn, identities: &[String]) {
+    let canonical = |repo: &str| giturl::resolve(repo, identities).unwrap_or_else(|_| repo.into());
+    let base = std::mem::take(target);
+    for (repo, deps) in base.into_iter().chain(overrides.clone()) {
+        target.insert(canonical(&repo), deps);
+    }
+}
+
+/// Render a dependency list as a `depends-on` config value.
+pub fn format_value(deps: &[String]) -> String {
+    deps.join(",")
+}
+
+/// Build the dependency graph over `identities`. Dependencies on repos that
+/// are not among `identities` are ignored.
+pub fn graph(depends_on: Option<&DependsOn>, identities: &[String]) -> Dag<String> {
+    let mut dag = Dag::new();
+    for id in identities {
+        dag.add_node(id.clone());
+    }
+    for (repo, deps) in depends_on.into_iter().flatten() {
+        let Ok(identity) = giturl::resolve(repo, identities) else {
+            continue;
+        };
+        for dep in deps {
+            if let Ok(dep) = giturl::resolve(dep, identities) {
+                dag.add_edge(identity.clone(), dep);
+            }
+        }
+    }
+    dag
+}
+
+/// Order `identities` so every repo comes after the repos it depends on.
+/// Repos without declared dependencies keep their sorted order. Errors if the
+/// declared dependencies form a cycle.
+pub fn order(depends_on: Option<&DependsOn>, identities: &[String]) -> Result<Vec<String>> {
+    graph(depends_on, identities)
+        .toposort()
+        .map_err(|e| anyhow::anyhow!("repo {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids() -> Vec<String> {
+        ["api", "proto", "web"]
+            .iter()
+            .map(|r| format!("github.com/acme/{}", r))
+            .collect()
+    }
+
+    fn deps(pairs: &[(&str, &[&str])]) -> DependsOn {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.iter().map(|s| s.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_key_repo() {
+        let cases = vec![
+            ("depends-on.api", Some("api")),
+            ("depends_on.my_api", Some("my_api")),
+            ("depends-on", None),
+            ("task.api", None),
+        ];
+        for (key, want) in cases {
+            assert_eq!(key_repo(key), want, "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_resolve_entry() {
+        let (id, got) = resolve_entry("web", "api, proto,api", &ids()).unwrap();
+        assert_eq!(id, "github.com/acme/web");
+        assert_eq!(got, vec!["github.com/acme/api", "github.com/acme/proto"]);
+
+        let (_, got) = resolve_entry("web", "", &ids()).unwrap();
+        assert!(got.is_empty());
+
+        let cases = vec![
+            ("self", "api", "api"),
+            ("unknown repo", "nope", "api"),
+            ("unknown dep", "api", "nope"),
+        ];
+        for (name, repo, value) in cases {
+            assert!(resolve_entry(repo, value, &ids()).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_order() {
+        let cases = vec![
+            ("none", deps(&[]), vec!["api", "proto", "web"]),
+            (
+                "chain by identity",
+                deps(&[
+                    ("github.com/acme/api", &["github.com/acme/proto"]),
+                    ("github.com/acme/proto", &["github.com/acme/web"]),
+                ]),
+                vec!["web", "proto", "api"],
+            ),
+            (
+                "shortnames",
+                deps(&[("api", &["proto"])]),
+                vec!["proto", "api", "web"],
+            ),
+            (
+                "outside workspace ignored",
+                deps(&[("api", &["billing"]), ("billing", &["web"])]),
+                vec!["api", "proto", "web"],
+            ),
+        ];
+        for (name, depends_on, want) in cases {
+            let got = order(Some(&depends_on), &ids()).unwrap();
+            let want: Vec<String> = want
+                .iter()
+                .map(|r| format!("github.com/acme/{}", r))
+                .collect();
+            assert_eq!(got, want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_order_cycle() {
+        let depends_on = deps(&[("api", &["web"]), ("web", &["api"])]);
+        let err = order(Some(&depends_on), &ids()).unwrap_err().to_string();
+        assert!(err.contains("repo dependency cycle"), "got {:?}", err);
+        assert!(
+            err.contains("github.com/acme/api -> github.com/acme/web -> github.com/acme/api"),
+            "got {:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let cases = vec![
+            (
+                "shortname overrides identity",
+                deps(&[("github.com/acme/api", &["github.com/acme/proto"])]),
+                deps(&[("api", &["web"])]),
+                deps(&[("github.com/acme/api", &["web"])]),
+            ),
+            (
+                "identity overrides shortname",
+                deps(&[("api", &["proto"])]),
+                deps(&[("github.com/acme/api", &[])]),
+                deps(&[("github.com/acme/api", &[])]),
+            ),
+            (
+                "other repos kept",
+                deps(&[("web", &["api"])]),
+                deps(&[("api", &["proto"])]),
+                deps(&[
+                    ("github.com/acme/api", &["proto"]),
+                    ("github.com/acme/web", &["api"]),
+                ]),
+            ),
+            (
+                "unresolvable kept as written",
+                deps(&[("billing", &["api"])]),
+                DependsOn::new(),
+                deps(&[("billing", &["api"])]),
+            ),
+        ];
+        for (name, base, overrides, want) in cases {
+            let mut got = base;
+            merge(&mut got, &overrides, &ids());
+            assert_eq!(got, want, "{}", name);
+        }
+    }
+}
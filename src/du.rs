@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Sizes younger than this are reused instead of walking the tree again.
+const CACHE_TTL_MINUTES: i64 = 10;
+
+const CACHE_FILE: &str = "du-cache.yaml";
+
+/// Sizes keyed by workspace directory. Each entry also records the
+/// workspace's creation time, so a workspace removed and created again at
+/// the same path is measured afresh.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Cache {
+    #[serde(default)]
+    workspaces: BTreeMap<PathBuf, CacheEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    bytes: u64,
+    measured: DateTime<Utc>,
+    created: String,
+}
+
+/// Total disk usage of a directory, recursively.
+/// Uses `DirEntry::file_type()` which does NOT follow symlinks, so symlinks
+/// are counted by their metadata size only (not their target). This prevents
+/// escaping the directory or looping on circular symlinks.
+pub fn dir_size(path: &Path) -> u64 {
+    let mut total = 0u64;
+    if let Ok(entries) = fs::read_dir(path) {
+        for entry in entries.flatten() {
+            let ft = match entry.file_type() {
+                Ok(ft) => ft,
+                Err(_) => continue,
+            };
+            if ft.is_dir() {
+                total += dir_size(&entry.path());
+            } else if let Ok(meta) = entry.metadata() {
+                total += meta.len();
+            }
+        }
+    }
+    total
+}
+
+/// Like [`dir_size`], walking each top-level entry (each clone, for a
+/// workspace) on its own thread.
+fn dir_size_parallel(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    let entries: Vec<_> = entries.flatten().collect();
+    std::thread::scope(|s| {
+        let handles: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                s.spawn(move || match entry.file_type() {
+                    Ok(ft) if ft.is_dir() => dir_size(&entry.path()),
+                    Ok(_) => entry.metadata().map(|m| m.len()).unwrap_or(0),
+                    Err(_) => 0,
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap_or(0)).sum()
+    })
+}
+
+/// Disk usage of each `(dir, created)` workspace, in order, where `created`
+/// is its creation time as stored in its metadata. Sizes measured in the
+/// last few minutes come from a cache in `data_dir`; the rest are measured
+/// in parallel and written back. Cache problems are ignored: the worst case
+/// is measuring again.
+pub fn workspace_sizes(data_dir: &Path, workspaces: &[(PathBuf, String)]) -> Vec<u64> {
+    let cache_path = data_dir.join(CACHE_FILE);
+    let mut cache: Cache = fs::read_to_string(&cache_path)
+        .ok()
+        .and_then(|s| serde_yaml_ng::from_str(&s).ok())
+        .unwrap_or_default();
+    let now = Utc::now();
+
+    let sizes: Vec<u64> = std::thread::scope(|s| {
+        let handles: Vec<_> = workspaces
+            .iter()
+            .map(|(dir, created)| {
+                let cached = fresh(&cache, dir, created, now);
+                s.spawn(move || cached.unwrap_or_else(|| dir_size_parallel(dir)))
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap_or(0)).collect()
+    });
+
+    // Keep entries for workspaces not listed this time (e.g. filtered out).
+    for ((dir, created), bytes) in workspaces.iter().zip(&sizes) {
+        if fresh(&cache, dir, created, now).is_none() {
+            cache.workspaces.insert(
+                dir.clone(),
+                CacheEntry {
+                    bytes: *bytes,
+                    measured: now,
+                    created: created.clone(),
+                },
+            );
+        }
+    }
+    cache
+        .workspaces
+        .retain(|_, e| now - e.measured < Duration::minutes(CACHE_TTL_MINUTES));
+    let Ok(text) = serde_yaml_ng::to_string(&cache) else {
+        return sizes;
+    };
+    let _ = fs::create_dir_all(data_dir);
+    // Written whole and renamed, so a concurrent `wsp ls --du` never reads
+    // half.
+    if let Ok(mut tmp) = tempfile::NamedTempFile::new_in(data_dir)
+        && tmp.write_all(text.as_bytes()).is_ok()
+    {
+        let _ = tmp.persist(&cache_path);
+    }
+    sizes
+}
+
+fn fresh(cache: &Cache, dir: &Path, created: &str, now: DateTime<Utc>) -> Option<u64> {
+    cache
+        .workspaces
+        .get(dir)
+        .filter(|e| e.created == created && now - e.measured < Duration::minutes(CACHE_TTL_MINUTES))
+        .map(|e| e.bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dir_size() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("measure");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a.txt"), "hello").unwrap(); // 5 bytes
+        fs::write(dir.join("sub/b.txt"), "world!").unwrap(); // 6 bytes
+
+        assert_eq!(dir_size(&dir), 11);
+        assert_eq!(dir_size_parallel(&dir), 11);
+        assert_eq!(dir_size(&tmp.path().join("missing")), 0);
+    }
+
+    #[test]
+    fn test_workspace_sizes_cached() {
+        let data = tempfile::tempdir().unwrap();
+        let ws = tempfile::tempdir().unwrap();
+        fs::write(ws.path().join("f"), "1234").unwrap();
+        let list = vec![(ws.path().to_path_buf(), "2026-01-01T00:00:00+00:00".into())];
+
+        assert_eq!(workspace_sizes(data.path(), &list), vec![4]);
+        // Growth within the TTL is not seen: the cached size is reused.
+        fs::write(ws.path().join("g"), "1234").unwrap();
+        assert_eq!(workspace_sizes(data.path(), &list), vec![4]);
+
+        // Recreated at the same path: measured again.
+        let list = vec![(ws.path().to_path_buf(), "2026-02-01T00:00:00+00:00".into())];
+        assert_eq!(workspace_sizes(data.path(), &list), vec![8]);
+    }
+
+    #[test]
+    fn test_fresh() {
+        let now = Utc::now();
+        let entry = |age_minutes| CacheEntry {
+            bytes: 42,
+            measured: now - Duration::minutes(age_minutes),
+            created: "c1".into(),
+        };
+        let cache = Cache {
+            workspaces: BTreeMap::from([
+                (PathBuf::from("/ws/new"), entry(1)),
+                (PathBuf::from("/ws/old"), entry(CACHE_TTL_MINUTES + 1)),
+            ]),
+        };
+        let cases = vec![
+            ("/ws/new", "c1", Some(42)),
+            ("/ws/new", "c2", None),
+            ("/ws/old", "c1", None),
+            ("/ws/missing", "c1", None),
+        ];
+        for (dir, created, want) in cases {
+            assert_eq!(
+                fresh(&cache, Path::new(dir), created, now),
+                want,
+                "{} {}",
+                dir,
+                created
+            );
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::fmt;
+
+/// Broad failure categories with stable codes, so scripts and agents can
+/// branch on the kind of failure instead of parsing messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Anything not covered below.
+    Other,
+    /// Unreadable or invalid config, metadata, or templates.
+    Config,
+    /// A git command failed.
+    Git,
+    /// A remote could not be reached.
+    Network,
+    /// The target already exists or the operation collides with local state.
+    Conflict,
+    /// A workspace, repo, template, or file doesn't exist.
+    NotFound,
+}
+
+impl ErrorKind {
+    /// Machine-readable code reported as `code` in JSON errors.
+    pub fn code(self) -> &'static str {
+        match self {
+            ErrorKind::Other => "error",
+            ErrorKind::Config => "config",
+            ErrorKind::Git => "git",
+            ErrorKind::Network => "network",
+            ErrorKind::Conflict => "conflict",
+            ErrorKind::NotFound => "not_found",
+        }
+    }
+
+    /// Process exit code. 2 is left to clap for usage errors.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Other => 1,
+            ErrorKind::Config => 3,
+            ErrorKind::Git => 4,
+            ErrorKind::Network => 5,
+            ErrorKind::Conflict => 6,
+            ErrorKind::NotFound => 7,
+        }
+    }
+}
+
+/// An error tagged with its kind. Displays exactly as the wrapped error.
+#[derive(Debug)]
+struct Tagged {
+    kind: ErrorKind,
+    inner: anyhow::Error,
+}
+
+impl fmt::Display for Tagged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl std::error::Error for Tagged {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.inner.source()
+    }
+}
+
+/// Tag `err` with `kind`. The tag survives `.context(...)` and [`prefixed`].
+pub fn tag(kind: ErrorKind, err: impl Into<anyhow::Error>) -> anyhow::Error {
+    anyhow::Error::new(Tagged {
+        kind,
+        inner: err.into(),
+    })
+}
+
+/// `anyhow!("{prefix}: {err}")`, keeping `err`'s kind.
+pub fn prefixed(err: anyhow::Error, prefix: &str) -> anyhow::Error {
+    let kind = classify(&err);
+    tag(kind, anyhow::anyhow!("{}: {}", prefix, err))
+}
+
+/// Classify a git failure from its stderr: unreachable remotes are network
+/// errors, everything else is a git error.
+pub fn git_kind(stderr: &str) -> ErrorKind {
+    const NETWORK: &[&str] = &[
+        "could not resolve host",
+        "unable to access",
+        "could not read from remote repository",
+        "connection refused",
+        "connection timed out",
+        "network is unreachable",
+        "operation timed out",
+    ];
+    let lower = stderr.to_lowercase();
+    if NETWORK.iter().any(|m| lower.contains(m)) {
+        ErrorKind::Network
+    } else {
+        ErrorKind::Git
+    }
+}
+
+/// Work out an error's kind: an explicit tag wins, then well-known error
+/// types in the chain. Anything else is [`ErrorKind::Other`]; tag errors
+/// where they are raised rather than matching on their wording.
+pub fn classify(err: &anyhow::Error) -> ErrorKind {
+    for cause in err.chain() {
+        if let Some(t) = cause.downcast_ref::<Tagged>() {
+            return t.kind;
+        }
+    }
+    for cause in err.chain() {
+        if let Some(e) = cause.downcast_ref::<std::io::Error>()
+            && e.kind() == std::io::ErrorKind::NotFound
+        {
+            return ErrorKind::NotFound;
+        }
+        if cause.downcast_ref::<serde_yaml_ng::Error>().is_some() {
+            return ErrorKind::Config;
+        }
+    }
+    ErrorKind::Other
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_classify() {
+        let io_missing = || std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
+        let yaml_err = serde_yaml_ng::from_str::<u32>("[").unwrap_err();
+        let cases: Vec<(&str, anyhow::Error, ErrorKind)> = vec![
+            (
+                "tagged",
+                tag(ErrorKind::Git, anyhow!("git fetch: exit 1")),
+                ErrorKind::Git,
+            ),
+            (
+                "tag survives context",
+                tag(ErrorKind::Network, anyhow!("x")).context("fetching"),
+                ErrorKind::Network,
+            ),
+            (
+                "tag survives prefixed",
+                prefixed(tag(ErrorKind::Config, anyhow!("bad")), "reading workspace"),
+                ErrorKind::Config,
+            ),
+            (
+                "io not found",
+                anyhow::Error::new(io_missing()).context("opening .wsp.yaml"),
+                ErrorKind::NotFound,
+            ),
+            (
+                "io not found kept by prefixed",
+                prefixed(
+                    anyhow::Error::new(io_missing()).context("opening"),
+                    "reading workspace",
+                ),
+                ErrorKind::NotFound,
+            ),
+            ("yaml", anyhow::Error::new(yaml_err), ErrorKind::Config),
+            (
+                "wording alone doesn't classify",
+                anyhow!("workspace \"x\" already exists"),
+                ErrorKind::Other,
+            ),
+            ("other", anyhow!("something broke"), ErrorKind::Other),
+        ];
+        for (name, err, want) in cases {
+            assert_eq!(classify(&err), want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_tag_keeps_message() {
+        let err = prefixed(tag(ErrorKind::Git, anyhow!("git fetch failed")), "syncing");
+        assert_eq!(err.to_string(), "syncing: git fetch failed");
+    }
+
+    #[test]
+    fn test_git_kind() {
+        let cases = vec![
+            (
+                "fatal: unable to access 'https://x/': Could not resolve host: x",
+                ErrorKind::Network,
+            ),
+            (
+                "fatal: Could not read from remote repository.",
+                ErrorKind::Network,
+            ),
+            ("fatal: not a git repository", ErrorKind::Git),
+        ];
+        for (stderr, want) in cases {
+            assert_eq!(git_kind(stderr), want, "{}", stderr);
+        }
+    }
+
+    #[test]
+    fn test_codes_are_distinct() {
+        let kinds = [
+            ErrorKind::Other,
+            ErrorKind::Config,
+            ErrorKind::Git,
+            ErrorKind::Network,
+            ErrorKind::Conflict,
+            ErrorKind::NotFound,
+        ];
+        for (i, a) in kinds.iter().enumerate() {
+            for b in &kinds[i + 1..] {
+                assert_ne!(a.code(), b.code());
+                assert_ne!(a.exit_code(), b.exit_code());
+            }
+        }
+    }
+}
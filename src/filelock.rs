@@ -8,9 +8,12 @@ use fs2::FileExt;
 
 use crate::config::Config;
 use crate::template::{self, Template};
-use crate::workspace::{Metadata, load_metadata, save_metadata};
+use crate::workspace::{METADATA_FILE, Metadata, load_metadata, save_metadata};
 
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Workspace and mirror locks are held for a whole command (a sync, a
+/// fetch), so waiters get longer before giving up.
+const OPERATION_TIMEOUT: Duration = Duration::from_secs(600);
 const RETRY_INTERVAL: Duration = Duration::from_millis(50);
 
 /// Advisory file lock using `flock` via the `fs2` crate.
@@ -112,12 +115,42 @@ where
     F: FnOnce(&mut Config) -> Result<()>,
 {
     let _lock = FileLock::acquire(config_path, DEFAULT_TIMEOUT)?;
-    let mut cfg = Config::load_from(config_path)?;
+    let mut cfg = Config::load_file(config_path)?;
     f(&mut cfg)?;
     cfg.save_to(config_path)?;
     Ok(cfg)
 }
 
+/// Replace the config file with `data` under its lock, provided it still
+/// holds `expected`: a hand edit started from `expected` must not overwrite
+/// a change another command made meanwhile.
+pub fn replace_config(config_path: &Path, expected: &str, data: &str) -> Result<()> {
+    let _lock = FileLock::acquire(config_path, DEFAULT_TIMEOUT)?;
+    let current = match fs::read_to_string(config_path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).context("reading config"),
+    };
+    if current != expected {
+        bail!("config.yaml changed while it was being edited");
+    }
+    let dir = config_path.parent().context("config path has no parent")?;
+    fs::create_dir_all(dir)?;
+    let mut tmp =
+        tempfile::NamedTempFile::new_in(dir).context("creating temp file for atomic save")?;
+    tmp.write_all(data.as_bytes())
+        .context("writing config to temp file")?;
+    tmp.as_file().sync_all()?;
+    tmp.persist(config_path)
+        .context("renaming temp file to config")?;
+    Ok(())
+}
+
+/// Lock the config file without loading it, to move it.
+pub fn lock_config(config_path: &Path) -> Result<FileLock> {
+    FileLock::acquire(config_path, DEFAULT_TIMEOUT)
+}
+
 /// Acquire an exclusive lock, load the config, and return a snapshot.
 /// Does not write back. Use this when you only need to read the current state
 /// under the lock (e.g., for phase 1 of a 3-phase lock pattern).
@@ -155,6 +188,44 @@ where
     Ok(tmpl)
 }
 
+/// Acquire the workspace lock, held by a mutating command (sync, rm,
+/// rename, repo add/rm, fix, pr create) for as long as it runs, so two such
+/// commands in the same workspace take turns. Separate from the metadata
+/// lock, which those commands still take for each write.
+///
+/// Fails if the workspace was removed or renamed while waiting.
+pub fn lock_workspace(ws_dir: &Path) -> Result<FileLock> {
+    let name = ws_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let lock = acquire_operation(&ws_dir.join(".wsp"), &format!("workspace {:?}", name))?;
+    if !ws_dir.join(METADATA_FILE).exists() {
+        bail!("workspace {:?} was removed by another wsp command", name);
+    }
+    Ok(lock)
+}
+
+/// Acquire a mirror's lock, held while it is cloned, fetched or removed, so
+/// concurrent commands don't race on its refs.
+pub fn lock_mirror(mirror_dir: &Path) -> Result<FileLock> {
+    let name = mirror_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().trim_end_matches(".git").to_string())
+        .unwrap_or_default();
+    acquire_operation(mirror_dir, &format!("mirror of {}", name))
+}
+
+/// Takes the lock right away if it's free; otherwise says what it's waiting
+/// for and waits up to [`OPERATION_TIMEOUT`].
+fn acquire_operation(path: &Path, what: &str) -> Result<FileLock> {
+    if let Ok(lock) = FileLock::acquire(path, Duration::ZERO) {
+        return Ok(lock);
+    }
+    eprintln!("waiting for another wsp command using the {}...", what);
+    FileLock::acquire(path, OPERATION_TIMEOUT)
+}
+
 /// Acquire an exclusive lock, load the metadata, and return a snapshot.
 /// Does not write back. Use this when you only need to read the current state
 /// under the lock (e.g., for phase 1 of a 3-phase lock pattern).
@@ -220,6 +291,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn lock_workspace_is_exclusive() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws_dir = tmp.path().join("demo");
+        fs::create_dir(&ws_dir).unwrap();
+        fs::write(ws_dir.join(METADATA_FILE), "").unwrap();
+
+        let lock = lock_workspace(&ws_dir).unwrap();
+        assert!(ws_dir.join(".wsp.lock").exists());
+        assert!(FileLock::acquire(&ws_dir.join(".wsp"), Duration::from_millis(100)).is_err());
+        // The metadata lock is separate, so writes under the workspace lock work.
+        assert!(FileLock::acquire(&ws_dir.join(METADATA_FILE), Duration::ZERO).is_ok());
+        drop(lock);
+
+        let lock = lock_workspace(&ws_dir).unwrap();
+        drop(lock);
+
+        fs::remove_file(ws_dir.join(METADATA_FILE)).unwrap();
+        let err = lock_workspace(&ws_dir).unwrap_err().to_string();
+        assert!(err.contains("was removed"), "{}", err);
+    }
+
+    #[test]
+    fn lock_mirror_is_exclusive() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mirror_dir = tmp.path().join("github.com/acme/api.git");
+
+        let _lock = lock_mirror(&mirror_dir).unwrap();
+        assert!(tmp.path().join("github.com/acme/api.git.lock").exists());
+        assert!(FileLock::acquire(&mirror_dir, Duration::from_millis(100)).is_err());
+    }
+
     #[test]
     fn with_config_round_trip() {
         let tmp = tempfile::tempdir().unwrap();
@@ -243,6 +346,19 @@ mod tests {
         assert_eq!(loaded.branch_prefix.as_deref(), Some("feat/"));
     }
 
+    #[test]
+    fn replace_config_refuses_concurrent_change() {
+        let tmp = tempfile::tempdir().unwrap();
+        let cfg_path = tmp.path().join("config.yaml");
+
+        replace_config(&cfg_path, "", "editor: vi\n").unwrap();
+        assert_eq!(fs::read_to_string(&cfg_path).unwrap(), "editor: vi\n");
+
+        let err = replace_config(&cfg_path, "", "editor: nano\n").unwrap_err();
+        assert!(err.to_string().contains("changed"), "{}", err);
+        assert_eq!(fs::read_to_string(&cfg_path).unwrap(), "editor: vi\n");
+    }
+
     #[test]
     fn with_metadata_round_trip() {
         let tmp = tempfile::tempdir().unwrap();
@@ -259,7 +375,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: BTreeMap::new(),
         };
         save_metadata(ws_dir, &meta).unwrap();
 
@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+
+use crate::workspace::{self, Metadata};
+
+/// Shared files rendered into the workspace root by `wsp new`, keyed by
+/// destination path relative to the root, under `files` in config.yaml and
+/// templates:
+///
+/// ```yaml
+/// files:
+///   .editorconfig: ~/dotfiles/editorconfig
+///   Makefile: files/Makefile      # relative to the wsp data directory
+/// ```
+///
+/// Sources may use `{{workspace}}`, `{{branch}}`, `{{dir}}`, `{{repos}}`, and
+/// `{{repo.<name>}}` placeholders (see `vars`).
+pub type Files = BTreeMap<String, String>;
+
+/// Returns the destination part of a `file.<dest>` key. Only the prefix is
+/// checked — destinations keep their own spelling (`file.docker-compose.yml`).
+pub fn key_dest(key: &str) -> Option<&str> {
+    let (prefix, dest) = key.split_once('.')?;
+    (prefix == "file" || prefix == "files").then_some(dest)
+}
+
+/// A destination must be a plain relative path that stays inside the
+/// workspace root and doesn't clobber its metadata.
+pub fn validate_dest(dest: &str) -> Result<()> {
+    let path = Path::new(dest);
+    if dest.is_empty() || !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        bail!(
+            "file destination {:?} must be a relative path inside the workspace",
+            dest
+        );
+    }
+    if dest == workspace::METADATA_FILE {
+        bail!("file destination {:?} is reserved for wsp", dest);
+    }
+    Ok(())
+}
+
+/// Resolve a source path: `~/` (or `~\` on Windows) is the home directory,
+/// relative paths are relative to `base` (the wsp data directory).
+pub fn source_path(source: &str, base: &Path) -> PathBuf {
+    if let Some(rest) = source
+        .strip_prefix('~')
+        .and_then(|r| r.strip_prefix(std::path::is_separator))
+        && let Some(home) = dirs::home_dir()
+    {
+        return home.join(rest);
+    }
+    base.join(source)
+}
+
+/// Placeholder values for a workspace: `workspace` (name), `branch`, `dir`
+/// (root path), `repos` (space-separated repo directories), and
+/// `repo.<name>` (path of each repo directory).
+pub fn vars(ws_dir: &Path, meta: &Metadata) -> Result<Vec<(String, String)>> {
+    let mut dir_names = Vec::new();
+    for identity in meta.repos.keys() {
+        dir_names.push(meta.dir_name(identity)?);
+    }
+    let mut vars = vec![
+        ("workspace".to_string(), meta.name.clone()),
+        ("branch".to_string(), meta.branch.clone()),
+        ("dir".to_string(), ws_dir.to_string_lossy().into_owned()),
+        ("repos".to_string(), dir_names.join(" ")),
+    ];
+    for dir_name in dir_names {
+        vars.push((
+            format!("repo.{}", dir_name),
+            ws_dir.join(&dir_name).to_string_lossy().into_owned(),
+        ));
+    }
+    Ok(vars)
+}
+
+/// Replace `{{name}}` (or `{{ name }}`) placeholders. Unknown placeholders are
+/// left alone so files with their own `{{ }}` syntax pass through.
+pub fn render(text: &str, vars: &[(String, String)]) -> String {
+    let mut out = text.to_string();
+    for (name, value) in vars {
+        out = out
+            .replace(&format!("{{{{{}}}}}", name), value)
+            .replace(&format!("{{{{ {} }}}}", name), value);
+    }
+    out
+}
+
+/// Render every configured file into a new workspace. Problems with one file
+/// are printed as warnings and don't stop the others or fail the workspace.
+/// Written files are added to the workspace's `.wspignore` so `wsp rm` doesn't
+/// count them as user content.
+pub fn write_all(ws_dir: &Path, meta: &Metadata, files: Option<&Files>, base: &Path) {
+    let Some(files) = files.filter(|f| !f.is_empty()) else {
+        return;
+    };
+    let vars = match vars(ws_dir, meta) {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::warn!("skipping shared files: {}", e);
+            return;
+        }
+    };
+    let mut written = Vec::new();
+    for (dest, source) in files {
+        match write_one(ws_dir, meta, dest, &source_path(source, base), &vars) {
+            Ok(()) => written.push(dest.as_str()),
+            Err(e) => tracing::warn!("file {}: {:#}", dest, e),
+        }
+    }
+    if written.is_empty() {
+        return;
+    }
+    eprintln!("Wrote shared files: {}", written.join(", "));
+    if let Err(e) = ignore(ws_dir, &written) {
+        tracing::warn!("could not update .wspignore: {}", e);
+    }
+}
+
+/// Append the root entries of `dests` to the workspace `.wspignore`: the file
+/// itself, or its top-level directory (`tools/`) for nested destinations.
+fn ignore(ws_dir: &Path, dests: &[&str]) -> Result<()> {
+    let path = ws_dir.join(".wspignore");
+    let mut content = fs::read_to_string(&path).unwrap_or_default();
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str("# Shared files written by wsp new\n");
+    let mut entries: Vec<String> = dests
+        .iter()
+        .map(|dest| match dest.split_once('/') {
+            Some((dir, _)) => format!("{}/", dir),
+            None => dest.to_string(),
+        })
+        .collect();
+    entries.dedup();
+    for entry in entries {
+        content.push_str(&entry);
+        content.push('\n');
+    }
+    fs::write(&path, content).with_context(|| format!("writing {}", path.display()))
+}
+
+fn write_one(
+    ws_dir: &Path,
+    meta: &Metadata,
+    dest: &str,
+    source: &Path,
+    vars: &[(String, String)],
+) -> Result<()> {
+    validate_dest(dest)?;
+    let first = Path::new(dest).components().next();
+    for identity in meta.repos.keys() {
+        if first == Some(Component::Normal(meta.dir_name(identity)?.as_ref())) {
+            bail!("destination is inside repo directory");
+        }
+    }
+    let text =
+        fs::read_to_string(source).with_context(|| format!("reading {}", source.display()))?;
+    let path = ws_dir.join(dest);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, render(&text, vars)).with_context(|| format!("writing {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta() -> Metadata {
+        let mut repos = BTreeMap::new();
+        repos.insert("github.com/acme/api".to_string(), None);
+        repos.insert("github.com/acme/web".to_string(), None);
+        Metadata {
+            version: 0,
+            name: "fix".into(),
+            branch: "me/fix".into(),
+            repos,
+            created: chrono::Utc::now(),
+            description: None,
+            last_used: None,
+            created_from: None,
+            dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
+            config: None,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_validate_dest() {
+        let cases = vec![
+            (".editorconfig", true),
+            ("docker/compose.override.yml", true),
+            ("", false),
+            ("/etc/passwd", false),
+            ("../outside", false),
+            ("a/../../b", false),
+            (".wsp.yaml", false),
+        ];
+        for (dest, want_ok) in cases {
+            assert_eq!(validate_dest(dest).is_ok(), want_ok, "{:?}", dest);
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let vars = vars(Path::new("/ws/fix"), &meta()).unwrap();
+        let cases = vec![
+            ("name", "# {{workspace}} on {{ branch }}", "# fix on me/fix"),
+            ("repos", "REPOS := {{repos}}", "REPOS := api web"),
+            ("repo path", "cd {{repo.web}}", "cd /ws/fix/web"),
+            ("unknown kept", "${{ github.sha }}", "${{ github.sha }}"),
+        ];
+        for (name, input, want) in cases {
+            assert_eq!(render(input, &vars), want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_write_all() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path().join("data");
+        let ws_dir = tmp.path().join("fix");
+        fs::create_dir_all(base.join("files")).unwrap();
+        fs::create_dir_all(ws_dir.join("api")).unwrap();
+        fs::write(base.join("files/Makefile"), "REPOS := {{repos}}\n").unwrap();
+
+        let files = Files::from([
+            ("Makefile".to_string(), "files/Makefile".to_string()),
+            ("tools/Makefile".to_string(), "files/Makefile".to_string()),
+            ("api/Makefile".to_string(), "files/Makefile".to_string()),
+            ("missing".to_string(), "files/missing".to_string()),
+        ]);
+        write_all(&ws_dir, &meta(), Some(&files), &base);
+
+        for dest in ["Makefile", "tools/Makefile"] {
+            let got = fs::read_to_string(ws_dir.join(dest)).unwrap();
+            assert_eq!(got, "REPOS := api web\n", "{}", dest);
+        }
+        assert!(!ws_dir.join("api/Makefile").exists());
+        assert!(!ws_dir.join("missing").exists());
+
+        // wsp rm treats them as wsp-managed
+        let problems = workspace::check_root_content(&ws_dir, &meta()).unwrap();
+        let patterns = workspace::load_wspignore(&base, &base, &ws_dir);
+        assert!(workspace::filter_ignored(problems, &patterns).is_empty());
+    }
+}
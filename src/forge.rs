@@ -0,0 +1,268 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail};
+
+/// Per-host forge overrides under `forge` in config.yaml, for self-hosted
+/// instances whose name doesn't give them away:
+///
+/// ```yaml
+/// forge:
+///   git.acme.com: gitlab
+/// ```
+pub type Forges = BTreeMap<String, String>;
+
+/// The service hosting a repo's pull (or merge) requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Forge {
+    Github,
+    Gitlab,
+}
+
+impl Forge {
+    pub fn parse(kind: &str) -> Result<Forge> {
+        match kind {
+            "github" => Ok(Forge::Github),
+            "gitlab" => Ok(Forge::Gitlab),
+            _ => bail!("unknown forge {:?}: must be github or gitlab", kind),
+        }
+    }
+
+    /// The forge for `host`: a configured override, else GitLab when the
+    /// host name says so, else GitHub.
+    pub fn detect(host: &str, overrides: Option<&Forges>) -> Forge {
+        if let Some(kind) = overrides.and_then(|m| m.get(host))
+            && let Ok(forge) = Forge::parse(kind)
+        {
+            return forge;
+        }
+        if host.split('.').any(|label| label == "gitlab") {
+            Forge::Gitlab
+        } else {
+            Forge::Github
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Forge::Github => "github",
+            Forge::Gitlab => "gitlab",
+        }
+    }
+
+    /// The forge's CLI, which holds the per-host login wsp relies on.
+    pub fn cli(self) -> &'static str {
+        match self {
+            Forge::Github => "gh",
+            Forge::Gitlab => "glab",
+        }
+    }
+
+    /// Environment variable that points the CLI at a host, so its API URL
+    /// and token are the ones stored for that host.
+    pub fn host_env(self) -> &'static str {
+        match self {
+            Forge::Github => "GH_HOST",
+            Forge::Gitlab => "GITLAB_HOST",
+        }
+    }
+
+    /// Environment variables the CLI reads a token for `host` from, in the
+    /// order it checks them. GitHub keeps github.com and Enterprise apart.
+    pub fn token_envs(self, host: &str) -> &'static [&'static str] {
+        match self {
+            Forge::Github if host == "github.com" => &["GH_TOKEN", "GITHUB_TOKEN"],
+            Forge::Github => &["GH_ENTERPRISE_TOKEN", "GITHUB_ENTERPRISE_TOKEN"],
+            Forge::Gitlab => &["GITLAB_TOKEN", "GITLAB_ACCESS_TOKEN", "OAUTH_TOKEN"],
+        }
+    }
+
+    /// The token variable already set for `host`, if any.
+    pub fn token_env_set(self, host: &str) -> Option<&'static str> {
+        self.token_envs(host)
+            .iter()
+            .copied()
+            .find(|v| std::env::var_os(v).is_some_and(|s| !s.is_empty()))
+    }
+
+    /// A command running the CLI against `host`: the host set, and the
+    /// token from `wsp setup auth login` passed along unless the
+    /// environment already has one.
+    pub fn command(self, host: &str) -> std::process::Command {
+        let mut cmd = std::process::Command::new(self.cli());
+        cmd.env(self.host_env(), host);
+        if self.token_env_set(host).is_none()
+            && let Some(token) = crate::auth::token(host)
+        {
+            cmd.env(self.token_envs(host)[0], token);
+        }
+        cmd
+    }
+}
+
+/// A pull or merge request named by its web URL.
+#[derive(Debug, PartialEq)]
+pub struct PrUrl {
+    pub forge: Forge,
+    /// Clone URL of the repo the PR targets.
+    pub repo_url: String,
+    pub number: u64,
+}
+
+impl PrUrl {
+    /// Parses `https://<host>/<owner>/<repo>/pull/<n>` (GitHub) or
+    /// `https://<host>/<group>/<project>/-/merge_requests/<n>` (GitLab); the
+    /// URL's shape decides the forge.
+    pub fn parse(raw: &str) -> Result<PrUrl> {
+        let mut u: url::Url = raw
+            .parse()
+            .map_err(|e| anyhow::anyhow!("invalid PR URL {:?}: {}", raw, e))?;
+        let segments: Vec<String> = u
+            .path_segments()
+            .into_iter()
+            .flatten()
+            .map(String::from)
+            .collect();
+        let found = segments.windows(2).enumerate().find_map(|(i, w)| {
+            match (w[0].as_str(), w[1].as_str()) {
+                ("pull", n) if i >= 2 => Some((Forge::Github, i, n)),
+                ("merge_requests", n) if i >= 3 && segments[i - 1] == "-" => {
+                    Some((Forge::Gitlab, i - 1, n))
+                }
+                _ => None,
+            }
+        });
+        let Some((forge, repo_end, number)) = found else {
+            bail!(
+                "not a pull or merge request URL: {} (expected .../<repo>/pull/<n> or \
+                 .../<project>/-/merge_requests/<n>)",
+                raw
+            );
+        };
+        let number: u64 = number
+            .parse()
+            .map_err(|_| anyhow::anyhow!("invalid PR number in {}", raw))?;
+        u.set_path(&format!("{}.git", segments[..repo_end].join("/")));
+        u.set_query(None);
+        u.set_fragment(None);
+        Ok(PrUrl {
+            forge,
+            repo_url: u.to_string(),
+            number,
+        })
+    }
+
+    /// The ref the forge keeps at the PR's head commit. The target repo
+    /// serves it even when the PR comes from a fork.
+    pub fn head_ref(&self) -> String {
+        match self.forge {
+            Forge::Github => format!("refs/pull/{}/head", self.number),
+            Forge::Gitlab => format!("refs/merge-requests/{}/head", self.number),
+        }
+    }
+}
+
+/// Returns the host part of a `forge.<host>` key.
+pub fn key_host(key: &str) -> Option<&str> {
+    let (prefix, host) = key.split_once('.')?;
+    (prefix == "forge").then_some(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let overrides = Forges::from([
+            ("git.acme.com".to_string(), "gitlab".to_string()),
+            ("gitlab.legacy.io".to_string(), "github".to_string()),
+            ("bogus.io".to_string(), "gitea".to_string()),
+        ]);
+        let cases = vec![
+            ("github.com", Forge::Github),
+            ("gitlab.com", Forge::Gitlab),
+            ("gitlab.example.org", Forge::Gitlab),
+            ("code.gitlab.net", Forge::Gitlab),
+            ("mygitlab.io", Forge::Github),
+            ("git.acme.com", Forge::Gitlab),
+            ("gitlab.legacy.io", Forge::Github),
+            ("bogus.io", Forge::Github),
+        ];
+        for (host, want) in cases {
+            assert_eq!(Forge::detect(host, Some(&overrides)), want, "{}", host);
+        }
+        assert_eq!(Forge::detect("git.acme.com", None), Forge::Github);
+    }
+
+    #[test]
+    fn test_token_envs() {
+        let cases = vec![
+            (Forge::Github, "github.com", "GH_TOKEN"),
+            (Forge::Github, "ghe.acme.com", "GH_ENTERPRISE_TOKEN"),
+            (Forge::Gitlab, "gitlab.com", "GITLAB_TOKEN"),
+            (Forge::Gitlab, "git.acme.com", "GITLAB_TOKEN"),
+        ];
+        for (forge, host, want) in cases {
+            assert_eq!(forge.token_envs(host)[0], want, "{}", host);
+        }
+    }
+
+    #[test]
+    fn test_pr_url_parse() {
+        let cases = vec![
+            (
+                "https://github.com/acme/api/pull/42",
+                Forge::Github,
+                "https://github.com/acme/api.git",
+                42,
+            ),
+            (
+                "https://github.com/acme/api/pull/42/files#diff-1",
+                Forge::Github,
+                "https://github.com/acme/api.git",
+                42,
+            ),
+            (
+                "https://gitlab.com/acme/platform/api/-/merge_requests/7",
+                Forge::Gitlab,
+                "https://gitlab.com/acme/platform/api.git",
+                7,
+            ),
+            (
+                "https://git.acme.com:8443/team/web/-/merge_requests/3/diffs?view=inline",
+                Forge::Gitlab,
+                "https://git.acme.com:8443/team/web.git",
+                3,
+            ),
+        ];
+        for (raw, forge, repo_url, number) in cases {
+            let got = PrUrl::parse(raw).unwrap();
+            assert_eq!(got.forge, forge, "{}", raw);
+            assert_eq!(got.repo_url, repo_url, "{}", raw);
+            assert_eq!(got.number, number, "{}", raw);
+        }
+
+        for raw in [
+            "https://github.com/acme/api",
+            "https://github.com/acme/pull/1",
+            "https://github.com/acme/api/pull/abc",
+            "https://gitlab.com/api/-/merge_requests/1",
+            "not a url",
+        ] {
+            assert!(PrUrl::parse(raw).is_err(), "{}", raw);
+        }
+    }
+
+    #[test]
+    fn test_key_host() {
+        let cases = vec![
+            ("forge.git.acme.com", Some("git.acme.com")),
+            ("forge.", Some("")),
+            ("forges.x", None),
+            ("forge", None),
+        ];
+        for (key, want) in cases {
+            assert_eq!(key_host(key), want, "{}", key);
+        }
+    }
+}
@@ -4,9 +4,12 @@ use std::time::Duration;
 
 use anyhow::Result;
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::config::Paths;
+use crate::du::dir_size;
+use crate::error::{self, ErrorKind};
 
 // EXDEV: cross-device link (errno 18 on macOS and Linux)
 fn is_cross_device(e: &std::io::Error) -> bool {
@@ -18,7 +21,7 @@ pub const DEFAULT_RETENTION_DAYS: u32 = 7;
 const GC_COOLDOWN_SECS: u64 = 3600; // 1 hour between auto-gc runs
 
 /// Metadata stored inside each gc'd workspace directory.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema, Deserialize)]
 pub struct GcEntry {
     pub name: String,
     pub branch: String,
@@ -27,7 +30,7 @@ pub struct GcEntry {
 }
 
 /// Enriched list entry with repo count.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct GcListEntry {
     #[serde(flatten)]
     pub entry: GcEntry,
@@ -35,7 +38,7 @@ pub struct GcListEntry {
 }
 
 /// Detailed info for `wsp recover show`.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct GcShowEntry {
     #[serde(flatten)]
     pub entry: GcEntry,
@@ -47,7 +50,7 @@ pub struct GcShowEntry {
 /// Move a directory, falling back to recursive copy + delete if rename
 /// fails with EXDEV (cross-filesystem). An incomplete copy is cleaned up
 /// on failure so the gc area doesn't accumulate garbage.
-fn move_dir(src: &Path, dest: &Path) -> Result<()> {
+pub(crate) fn move_dir(src: &Path, dest: &Path) -> Result<()> {
     match fs::rename(src, dest) {
         Ok(()) => Ok(()),
         Err(e) if is_cross_device(&e) => {
@@ -71,10 +74,7 @@ fn copy_dir_recursive(src: &Path, dest: &Path) -> Result<()> {
         let dest_path = dest.join(item.file_name());
         if ft.is_symlink() {
             let target = fs::read_link(&src_path)?;
-            #[cfg(unix)]
-            std::os::unix::fs::symlink(&target, &dest_path)?;
-            #[cfg(windows)]
-            std::os::windows::fs::symlink_file(&target, &dest_path)?;
+            crate::util::symlink(&target, &dest_path)?;
         } else if ft.is_dir() {
             copy_dir_recursive(&src_path, &dest_path)?;
         } else {
@@ -106,11 +106,14 @@ pub fn check_workspace(ws_dir: &Path, read_only: bool) -> Result<()> {
             );
             Ok(())
         } else {
-            anyhow::bail!(
-                "this workspace was removed on {}. Use `wsp recover {}` to restore it.",
-                date,
-                entry.name
-            );
+            Err(error::tag(
+                ErrorKind::NotFound,
+                anyhow::anyhow!(
+                    "this workspace was removed on {}. Use `wsp recover {}` to restore it.",
+                    date,
+                    entry.name
+                ),
+            ))
         }
     } else {
         Ok(())
@@ -123,7 +126,7 @@ pub fn check_workspace(ws_dir: &Path, read_only: bool) -> Result<()> {
 /// directory. Uses rename when possible, falls back to copy+delete for
 /// cross-filesystem moves.
 pub fn move_to_gc(paths: &Paths, name: &str, branch: &str) -> Result<()> {
-    let ws_dir = crate::workspace::dir(&paths.workspaces_dir, name);
+    let ws_dir = paths.workspace_dir(name);
     let timestamp = Utc::now().format("%Y%m%dT%H%M%S%.3f").to_string();
     let gc_name = format!("{}__{}", name, timestamp);
     let dest = paths.gc_dir.join(&gc_name);
@@ -164,7 +167,7 @@ pub fn list(gc_dir: &Path) -> Result<Vec<GcEntry>> {
         }
     }
 
-    entries.sort_by(|a, b| b.trashed_at.cmp(&a.trashed_at));
+    entries.sort_by_key(|e| std::cmp::Reverse(e.trashed_at));
     Ok(entries)
 }
 
@@ -191,7 +194,7 @@ pub fn list_enriched(gc_dir: &Path) -> Result<Vec<GcListEntry>> {
     }
 
     // Oldest first = next to expire at the top
-    entries.sort_by(|a, b| a.entry.trashed_at.cmp(&b.entry.trashed_at));
+    entries.sort_by_key(|a| a.entry.trashed_at);
     Ok(entries)
 }
 
@@ -199,7 +202,10 @@ pub fn list_enriched(gc_dir: &Path) -> Result<Vec<GcListEntry>> {
 pub fn show(gc_dir: &Path, name: &str) -> Result<GcShowEntry> {
     let entries = find_entries(gc_dir, name)?;
     if entries.is_empty() {
-        anyhow::bail!("no recoverable workspace named {:?}", name);
+        return Err(error::tag(
+            ErrorKind::NotFound,
+            anyhow::anyhow!("no recoverable workspace named {:?}", name),
+        ));
     }
 
     let (gc_name, entry) = entries.into_iter().next().unwrap();
@@ -234,33 +240,14 @@ fn read_repo_identities(ws_dir: &Path) -> Vec<String> {
     }
 }
 
-/// Calculate total disk usage of a directory, recursively.
-/// Uses `DirEntry::file_type()` which does NOT follow symlinks, so symlinks
-/// are counted by their metadata size only (not their target). This prevents
-/// escaping the gc directory or looping on circular symlinks.
-fn dir_size(path: &Path) -> u64 {
-    let mut total = 0u64;
-    if let Ok(entries) = fs::read_dir(path) {
-        for entry in entries.flatten() {
-            let ft = match entry.file_type() {
-                Ok(ft) => ft,
-                Err(_) => continue,
-            };
-            if ft.is_dir() {
-                total += dir_size(&entry.path());
-            } else if let Ok(meta) = entry.metadata() {
-                total += meta.len();
-            }
-        }
-    }
-    total
-}
-
 /// Restore a workspace from the gc area back to the workspaces directory.
 pub fn restore(paths: &Paths, name: &str) -> Result<()> {
     let entries = find_entries(&paths.gc_dir, name)?;
     if entries.is_empty() {
-        anyhow::bail!("no recoverable workspace named {:?}", name);
+        return Err(error::tag(
+            ErrorKind::NotFound,
+            anyhow::anyhow!("no recoverable workspace named {:?}", name),
+        ));
     }
 
     // Use the most recent entry
@@ -269,14 +256,24 @@ pub fn restore(paths: &Paths, name: &str) -> Result<()> {
     // Validate the deserialized name to prevent path traversal from tampered metadata
     crate::workspace::validate_name(&entry.name)?;
 
-    let dest = crate::workspace::dir(&paths.workspaces_dir, &entry.name);
+    // Back into the workspaces dir it came from, if that's still one.
+    let mut dest = paths.workspace_dir(&entry.name);
+    if !dest.exists()
+        && let Some(root) = Path::new(&entry.original_path).parent()
+        && paths.workspace_roots().any(|r| r == root)
+    {
+        dest = crate::workspace::dir(root, &entry.name);
+    }
     // fs::rename on Unix fails atomically if dest is a non-empty directory,
     // so this check is a courtesy error message, not a security gate.
     if dest.exists() {
-        anyhow::bail!(
-            "workspace {:?} already exists; remove or rename it first",
-            entry.name
-        );
+        return Err(error::tag(
+            ErrorKind::Conflict,
+            anyhow::anyhow!(
+                "workspace {:?} already exists; remove or rename it first",
+                entry.name
+            ),
+        ));
     }
 
     let src = paths.gc_dir.join(gc_name);
@@ -318,7 +315,7 @@ pub fn purge(gc_dir: &Path, retention_days: u32) -> Result<u32> {
         if entry.trashed_at < cutoff {
             // Best-effort: continue purging others if one fails
             if let Err(e) = fs::remove_dir_all(&path) {
-                eprintln!("  warning: gc purge failed for {}: {}", entry.name, e);
+                tracing::warn!("  gc purge failed for {}: {}", entry.name, e);
             } else {
                 removed += 1;
             }
@@ -351,7 +348,7 @@ pub fn maybe_run(paths: &Paths, retention_days: Option<u32>) {
         return; // never purge
     }
     if let Err(e) = purge(&paths.gc_dir, days) {
-        eprintln!("  warning: gc failed: {}", e);
+        tracing::warn!("  gc failed: {}", e);
     }
 
     // Touch the marker file
@@ -385,7 +382,7 @@ fn find_entries(gc_dir: &Path, name: &str) -> Result<Vec<(String, GcEntry)>> {
         }
     }
 
-    matches.sort_by(|a, b| b.1.trashed_at.cmp(&a.1.trashed_at));
+    matches.sort_by_key(|m| std::cmp::Reverse(m.1.trashed_at));
     Ok(matches)
 }
 
@@ -400,6 +397,7 @@ mod tests {
             mirrors_dir: tmp.join("mirrors"),
             gc_dir: tmp.join("gc"),
             templates_dir: tmp.join("templates"),
+            extra_workspaces_dirs: Vec::new(),
             workspaces_dir: tmp.join("workspaces"),
         }
     }
@@ -417,7 +415,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: std::collections::BTreeMap::new(),
+            branches: std::collections::BTreeMap::new(),
+            prs: std::collections::BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: std::collections::BTreeMap::new(),
         };
         let yaml = serde_yaml_ng::to_string(&meta).unwrap();
         fs::write(ws_dir.join(".wsp.yaml"), yaml).unwrap();
@@ -541,7 +543,14 @@ mod tests {
         create_workspace(&paths, "soft-del");
 
         // remove with permanent=false should move to gc
-        crate::workspace::remove(&paths, "soft-del", true, false).unwrap();
+        crate::workspace::remove(
+            &paths,
+            "soft-del",
+            true,
+            false,
+            crate::offline::FetchPolicy::Fetch,
+        )
+        .unwrap();
         assert!(!paths.workspaces_dir.join("soft-del").exists());
 
         let entries = list(&paths.gc_dir).unwrap();
@@ -692,7 +701,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: std::collections::BTreeMap::new(),
+            branches: std::collections::BTreeMap::new(),
+            prs: std::collections::BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: std::collections::BTreeMap::new(),
         };
         let yaml = serde_yaml_ng::to_string(&meta).unwrap();
         fs::write(ws_dir.join(".wsp.yaml"), yaml).unwrap();
@@ -798,18 +811,6 @@ mod tests {
         assert!(err.to_string().contains("no recoverable workspace"));
     }
 
-    #[test]
-    fn test_dir_size() {
-        let tmp = tempfile::tempdir().unwrap();
-        let dir = tmp.path().join("measure");
-        fs::create_dir_all(dir.join("sub")).unwrap();
-        fs::write(dir.join("a.txt"), "hello").unwrap(); // 5 bytes
-        fs::write(dir.join("sub/b.txt"), "world!").unwrap(); // 6 bytes
-
-        let size = dir_size(&dir);
-        assert_eq!(size, 11);
-    }
-
     /// Backdate all gc entries by the given number of days.
     fn backdate_gc_entries(gc_dir: &Path, days: i64) {
         for item in fs::read_dir(gc_dir).unwrap() {
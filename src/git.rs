@@ -1,7 +1,14 @@
+use std::collections::BTreeSet;
+use std::io::{Read, Write};
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Output, Stdio};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
-use anyhow::{Context, Result, bail};
+use anyhow::{Result, anyhow, bail};
+
+use crate::error;
+use crate::util::path_str;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BranchSafety {
@@ -11,17 +18,312 @@ pub enum BranchSafety {
     Unmerged,
 }
 
-fn path_str(p: &Path) -> Result<&str> {
-    p.to_str().context("path contains non-UTF8 characters")
+/// How wsp runs git.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// The `git` binary on PATH.
+    Cli,
+    /// libgit2, linked in, for clone, fetch and workspace clones when
+    /// there is no `git` binary.
+    #[cfg(feature = "libgit2")]
+    Libgit2,
+}
+
+impl Backend {
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Cli => "git",
+            #[cfg(feature = "libgit2")]
+            Backend::Libgit2 => "libgit2",
+        }
+    }
+}
+
+static BACKEND: OnceLock<Backend> = OnceLock::new();
+
+/// The backend this process uses: the git CLI when `git` runs, else
+/// libgit2 if it was built in. Probed once.
+pub fn backend() -> Backend {
+    *BACKEND.get_or_init(|| {
+        #[cfg(feature = "libgit2")]
+        if !git_on_path() {
+            tracing::debug!("git not found on PATH; using libgit2");
+            return Backend::Libgit2;
+        }
+        Backend::Cli
+    })
+}
+
+/// Whether a `git` binary can be spawned.
+pub fn git_on_path() -> bool {
+    Command::new("git")
+        .arg("--version")
+        .output()
+        .is_ok_and(|o| o.status.success())
+}
+
+#[cfg(feature = "libgit2")]
+fn use_libgit2() -> bool {
+    backend() == Backend::Libgit2
+}
+
+/// Git config every command gets: URL rewrites and per-host network
+/// settings, from [`crate::config::Config::network_git_config`].
+static NETWORK_CONFIG: OnceLock<Vec<(String, String)>> = OnceLock::new();
+
+/// Applies `entries` to every git command from here on, through its
+/// environment. Called once at startup.
+pub fn set_network_config(entries: Vec<(String, String)>) {
+    let _ = NETWORK_CONFIG.set(entries);
+}
+
+/// Passes config entries through `GIT_CONFIG_*`, after any the user set, so
+/// mirrors cloned or fetched before a setting was added use it too.
+fn apply_network_config(cmd: &mut Command, entries: &[(String, String)]) {
+    if entries.is_empty() {
+        return;
+    }
+    let base: usize = std::env::var("GIT_CONFIG_COUNT")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(0);
+    for (i, (key, value)) in entries.iter().enumerate() {
+        cmd.env(format!("GIT_CONFIG_KEY_{}", base + i), key);
+        cmd.env(format!("GIT_CONFIG_VALUE_{}", base + i), value);
+    }
+    cmd.env("GIT_CONFIG_COUNT", (base + entries.len()).to_string());
+}
+
+/// Run a prepared git command. Every git subprocess goes through here so
+/// `-v` can show what ran and how long it took, `-vv` its output, and
+/// `--timings` where the time went.
+fn logged_output(cmd: &mut Command) -> Result<Output> {
+    logged_output_within(cmd, None, None)
+}
+
+/// Keeps git's messages in English: wsp parses them (progress, errors it
+/// classifies). Only messages change; paths and output in the user's
+/// character set stay as they are. LC_ALL would override LC_MESSAGES, so
+/// its value moves to LC_CTYPE.
+fn untranslated(cmd: &mut Command) {
+    let all = match cmd.get_envs().find(|(k, _)| *k == "LC_ALL") {
+        Some((_, v)) => v.map(|v| v.to_os_string()),
+        None => std::env::var_os("LC_ALL"),
+    };
+    if let Some(all) = all.filter(|v| !v.is_empty()) {
+        cmd.env_remove("LC_ALL").env("LC_CTYPE", all);
+    }
+    cmd.env("LC_MESSAGES", "C").env("LANGUAGE", "");
+}
+
+/// [`logged_output`], killing git if it runs longer than `timeout` and
+/// handing each line of stderr to `on_stderr` as git writes it.
+fn logged_output_within(
+    cmd: &mut Command,
+    timeout: Option<Duration>,
+    on_stderr: Option<&mut dyn FnMut(&str)>,
+) -> Result<Output> {
+    untranslated(cmd);
+    if let Some(entries) = NETWORK_CONFIG.get() {
+        apply_network_config(cmd, entries);
+    }
+    let start = Instant::now();
+    let result = match (timeout, on_stderr) {
+        (None, None) => cmd.output(),
+        (timeout, on_stderr) => output_streaming(cmd, timeout, on_stderr.unwrap_or(&mut |_| {})),
+    };
+    if crate::timings::enabled() {
+        let args: Vec<String> = cmd
+            .get_args()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect();
+        crate::timings::record_git(cmd.get_current_dir(), &args, start.elapsed());
+    }
+    if tracing::enabled!(tracing::Level::DEBUG) {
+        let args: Vec<String> = cmd
+            .get_args()
+            .map(|a| crate::util::shell_quote(&a.to_string_lossy()))
+            .collect();
+        let dir = cmd
+            .get_current_dir()
+            .map(|d| format!(" (in {})", d.display()))
+            .unwrap_or_default();
+        let elapsed = start.elapsed().as_millis();
+        match &result {
+            Ok(out) if out.status.success() => {
+                tracing::debug!("+ git {}{} [{}ms]", args.join(" "), dir, elapsed)
+            }
+            Ok(out) => tracing::debug!(
+                "+ git {}{} [{}ms, {}]",
+                args.join(" "),
+                dir,
+                elapsed,
+                out.status
+            ),
+            Err(e) => tracing::debug!("+ git {}{}: {}", args.join(" "), dir, e),
+        }
+        if let Ok(out) = &result {
+            for (name, bytes) in [("stdout", &out.stdout), ("stderr", &out.stderr)] {
+                let text = String::from_utf8_lossy(bytes);
+                for line in text.lines() {
+                    tracing::trace!("  {}| {}", name, line);
+                }
+            }
+        }
+    }
+    result.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::TimedOut {
+            // A remote that stops answering; worth another try.
+            error::tag(error::ErrorKind::Network, e)
+        } else if e.kind() == std::io::ErrorKind::NotFound {
+            let only = if cfg!(feature = "libgit2") {
+                " (without it, wsp can only clone, fetch and create workspaces)"
+            } else {
+                ""
+            };
+            anyhow::Error::new(e).context(format!("git not found on PATH{}", only))
+        } else {
+            e.into()
+        }
+    })
+}
+
+/// `cmd.output()`, passing stderr to `on_stderr` line by line as it comes
+/// (a carriage return ends a line too, as in git's progress meters), and
+/// giving up after `timeout`: git is stopped and the result is a `TimedOut`
+/// error.
+fn output_streaming(
+    cmd: &mut Command,
+    timeout: Option<Duration>,
+    on_stderr: &mut dyn FnMut(&str),
+) -> std::io::Result<Output> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Read on their own threads so a chatty git never blocks on a full pipe,
+    // and left behind on timeout in case something git started holds them.
+    let stdout = drain(child.stdout.take());
+    let (tx, rx) = std::sync::mpsc::channel::<Vec<u8>>();
+    if let Some(mut pipe) = child.stderr.take() {
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 8192];
+            while let Ok(n @ 1..) = pipe.read(&mut buf) {
+                if tx.send(buf[..n].to_vec()).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    let deadline = timeout.map(|t| Instant::now() + t);
+    let mut stderr = Vec::new();
+    let mut line_start = 0;
+    let mut status = None;
+    loop {
+        if status.is_none() {
+            status = child.try_wait()?;
+        }
+        match rx.recv_timeout(Duration::from_millis(50)) {
+            Ok(chunk) => {
+                stderr.extend_from_slice(&chunk);
+                while let Some(end) = stderr[line_start..]
+                    .iter()
+                    .position(|&b| b == b'\n' || b == b'\r')
+                {
+                    let line = String::from_utf8_lossy(&stderr[line_start..line_start + end]);
+                    if !line.trim().is_empty() {
+                        on_stderr(&line);
+                    }
+                    line_start += end + 1;
+                }
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => match status {
+                Some(_) => break,
+                None => std::thread::sleep(Duration::from_millis(50)),
+            },
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+        }
+        if let (None, Some(deadline), Some(timeout)) = (status, deadline, timeout)
+            && Instant::now() >= deadline
+        {
+            stop(&mut child);
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("timed out after {}s", timeout.as_secs()),
+            ));
+        }
+    }
+    if line_start < stderr.len() {
+        on_stderr(&String::from_utf8_lossy(&stderr[line_start..]));
+    }
+    Ok(Output {
+        status: status.expect("loop ends once git exits"),
+        stdout: stdout.join().unwrap_or_default(),
+        stderr,
+    })
+}
+
+/// How long git gets to exit after being asked to stop before it's killed.
+const STOP_GRACE: Duration = Duration::from_secs(5);
+
+/// Asks git to stop (SIGTERM), so it removes the `*.lock` files it holds
+/// as it would on Ctrl-C, and kills it if it's still running after
+/// [`STOP_GRACE`]. A killed git can leave locks that fail every later
+/// fetch of that repo.
+fn stop(child: &mut std::process::Child) {
+    #[cfg(unix)]
+    {
+        use nix::sys::signal::{Signal, kill};
+        use nix::unistd::Pid;
+        if kill(Pid::from_raw(child.id() as i32), Signal::SIGTERM).is_ok() {
+            let deadline = Instant::now() + STOP_GRACE;
+            while Instant::now() < deadline {
+                if let Ok(Some(_)) = child.try_wait() {
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(50));
+            }
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+/// `stderr` without git's progress meters, which only bury the error. A
+/// meter redraws its line after each carriage return, so any line with
+/// one goes, whatever it counts; so do transfer lines printed only once.
+fn without_progress(stderr: &str) -> String {
+    stderr
+        .split('\n')
+        .map(|l| l.strip_suffix('\r').unwrap_or(l))
+        .filter(|l| !l.trim().is_empty() && !l.contains('\r') && !Transfer::default().update(l))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 /// Validate that a string is a valid git branch name.
 /// Uses `git check-ref-format` with the `--branch` flag so bare names
 /// (without `refs/heads/` prefix) are accepted.
 pub fn validate_branch_name(name: &str) -> Result<()> {
-    let output = Command::new("git")
-        .args(["check-ref-format", "--branch", name])
-        .output()?;
+    #[cfg(feature = "libgit2")]
+    if use_libgit2() {
+        if !crate::libgit2::validate_branch_name(name) {
+            bail!("{:?} is not a valid git branch name", name);
+        }
+        return Ok(());
+    }
+    let output = logged_output(Command::new("git").args(["check-ref-format", "--branch", name]))?;
     if !output.status.success() {
         bail!("{:?} is not a valid git branch name", name);
     }
@@ -33,6 +335,20 @@ pub fn run(dir: Option<&Path>, args: &[&str]) -> Result<String> {
 }
 
 pub fn run_with_env(dir: Option<&Path>, args: &[&str], env: &[(&str, &str)]) -> Result<String> {
+    Ok(run_untrimmed(dir, args, env, None, None)?
+        .trim()
+        .to_string())
+}
+
+/// Like [`run_with_env`], keeping leading whitespace that is part of the
+/// output, like the blank index column of `git status --short`.
+fn run_untrimmed(
+    dir: Option<&Path>,
+    args: &[&str],
+    env: &[(&str, &str)],
+    timeout: Option<Duration>,
+    on_stderr: Option<&mut dyn FnMut(&str)>,
+) -> Result<String> {
     let mut cmd = Command::new("git");
     cmd.args(args);
     if let Some(d) = dir {
@@ -42,34 +358,81 @@ pub fn run_with_env(dir: Option<&Path>, args: &[&str], env: &[(&str, &str)]) ->
         cmd.env(k, v);
     }
 
-    let output = cmd.output()?;
+    let output = logged_output_within(&mut cmd, timeout, on_stderr)?;
 
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let stderr = without_progress(&String::from_utf8_lossy(&output.stderr));
         let args_str = args.join(" ");
-        if let Some(d) = dir {
-            bail!(
+        let err = if let Some(d) = dir {
+            anyhow!(
                 "git {} (in {}): {}\n{}",
                 args_str,
                 d.display(),
                 output.status,
                 stderr
-            );
+            )
         } else {
-            bail!("git {}: {}\n{}", args_str, output.status, stderr);
-        }
+            anyhow!("git {}: {}\n{}", args_str, output.status, stderr)
+        };
+        return Err(error::tag(error::git_kind(&stderr), err));
     }
 
-    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
 }
 
+#[cfg(test)]
 pub fn clone_bare(url: &str, dest: &Path) -> Result<()> {
+    clone_bare_with(url, dest, None, None, &|_| {})
+}
+
+/// [`clone_bare`], passing on git's progress and giving up after
+/// `timeout`. With a `reference`, objects it already has are borrowed
+/// instead of downloaded again: they stay in `reference`, listed in the new
+/// repo's `objects/info/alternates`. libgit2 can neither borrow, report
+/// progress nor time out, so without a git binary this is a plain clone.
+pub fn clone_bare_with(
+    url: &str,
+    dest: &Path,
+    reference: Option<&Path>,
+    timeout: Option<Duration>,
+    on_progress: &dyn Fn(&Transfer),
+) -> Result<()> {
+    #[cfg(feature = "libgit2")]
+    if use_libgit2() {
+        return crate::libgit2::clone_bare(url, dest);
+    }
     let dest_str = path_str(dest)?;
-    run(None, &["clone", "--bare", url, dest_str])?;
+    let mut args = vec!["clone", "--bare", "--progress"];
+    if let Some(reference) = reference {
+        args.extend(["--reference-if-able", path_str(reference)?]);
+    }
+    args.extend([url, dest_str]);
+    let mut transfer = Transfer::default();
+    run_untrimmed(
+        None,
+        &args,
+        &[],
+        timeout,
+        Some(&mut |line| {
+            if transfer.update(line) {
+                on_progress(&transfer);
+            }
+        }),
+    )?;
     Ok(())
 }
 
 pub fn configure_fetch_refspec(dir: &Path) -> Result<()> {
+    #[cfg(feature = "libgit2")]
+    if use_libgit2() {
+        return crate::libgit2::set_fetch_refspecs(
+            dir,
+            &[
+                "+refs/heads/*:refs/heads/*",
+                "+refs/heads/*:refs/remotes/origin/*",
+            ],
+        );
+    }
     // Clear any existing refspecs first (ignore error if none exist)
     let _ = run(Some(dir), &["config", "--unset-all", "remote.origin.fetch"]);
     // Keep refs/heads/* in sync so git clone --local gets a current checkout
@@ -106,23 +469,130 @@ fn ensure_fetch_refspec(dir: &Path) -> Result<()> {
 }
 
 pub fn set_config(dir: &Path, key: &str, value: &str) -> Result<()> {
+    #[cfg(feature = "libgit2")]
+    if use_libgit2() {
+        return crate::libgit2::set_config(dir, key, value);
+    }
     run(Some(dir), &["config", "--local", key, value])?;
     Ok(())
 }
 
+/// Add `value` to a multi-valued local git config key, unless it's there.
+pub fn add_config(dir: &Path, key: &str, value: &str) -> Result<()> {
+    if get_all_config(dir, key).iter().any(|v| v == value) {
+        return Ok(());
+    }
+    run(Some(dir), &["config", "--local", "--add", key, value])?;
+    Ok(())
+}
+
+/// Every local value of a git config key; none if it's not set.
+pub fn get_all_config(dir: &Path, key: &str) -> Vec<String> {
+    run(Some(dir), &["config", "--local", "--get-all", key])
+        .map(|out| out.lines().map(String::from).collect())
+        .unwrap_or_default()
+}
+
 /// Read a local git config value. Returns Err if the key is not set.
 pub fn get_config(dir: &Path, key: &str) -> Result<String> {
+    #[cfg(feature = "libgit2")]
+    if use_libgit2() {
+        return crate::libgit2::get_config(dir, key);
+    }
     run(Some(dir), &["config", "--local", key])
 }
 
-pub fn fetch(dir: &Path, prune: bool) -> Result<()> {
+/// What a fetch has transferred, from git's progress output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Transfer {
+    pub objects: u64,
+    pub total_objects: u64,
+    /// As git shows it, so rounded to a few digits.
+    pub bytes: u64,
+}
+
+impl Transfer {
+    /// Takes in a line of `git fetch --progress` output, like `Receiving
+    /// objects:  45% (450/1000), 1.20 MiB | 2.40 MiB/s`. Returns whether it
+    /// was one.
+    fn update(&mut self, line: &str) -> bool {
+        let line = line.trim();
+        let Some(rest) = line
+            .strip_prefix("Receiving objects:")
+            .or_else(|| line.strip_prefix("Unpacking objects:"))
+        else {
+            return false;
+        };
+        let Some((counts, after)) = rest.split_once('(').and_then(|(_, r)| r.split_once(')'))
+        else {
+            return false;
+        };
+        let Some((Ok(objects), Ok(total))) = counts
+            .split_once('/')
+            .map(|(n, total)| (n.parse(), total.parse()))
+        else {
+            return false;
+        };
+        self.objects = objects;
+        self.total_objects = total;
+        // Small transfers have no size: `(3/3), done.`
+        if let Some(bytes) = after
+            .strip_prefix(", ")
+            .and_then(|s| s.split([',', '|']).next())
+            .and_then(|size| parse_size(size.trim()))
+        {
+            self.bytes = bytes;
+        }
+        true
+    }
+}
+
+/// Parses a size as git prints one: `232 bytes`, `1.20 MiB`.
+fn parse_size(s: &str) -> Option<u64> {
+    let (num, unit) = s.split_once(' ')?;
+    let num: f64 = num.parse().ok()?;
+    let scale: u64 = match unit {
+        "bytes" | "byte" => 1,
+        "KiB" => 1 << 10,
+        "MiB" => 1 << 20,
+        "GiB" => 1 << 30,
+        "TiB" => 1 << 40,
+        _ => return None,
+    };
+    Some((num * scale as f64) as u64)
+}
+
+/// Fetch all remotes, failing with a network error when it takes longer
+/// than `timeout`. `on_progress` hears how the transfer is going whenever
+/// git reports it. Returns what was transferred.
+pub fn fetch(
+    dir: &Path,
+    prune: bool,
+    timeout: Option<Duration>,
+    on_progress: &dyn Fn(&Transfer),
+) -> Result<Transfer> {
+    #[cfg(feature = "libgit2")]
+    if use_libgit2() {
+        return crate::libgit2::fetch(dir, prune, timeout, on_progress);
+    }
     ensure_fetch_refspec(dir)?;
-    let mut args = vec!["fetch", "--all"];
+    let mut args = vec!["fetch", "--all", "--progress"];
     if prune {
         args.push("--prune");
     }
-    run(Some(dir), &args)?;
-    Ok(())
+    let mut transfer = Transfer::default();
+    run_untrimmed(
+        Some(dir),
+        &args,
+        &[],
+        timeout,
+        Some(&mut |line| {
+            if transfer.update(line) {
+                on_progress(&transfer);
+            }
+        }),
+    )?;
+    Ok(transfer)
 }
 
 pub fn default_branch(dir: &Path) -> Result<String> {
@@ -142,6 +612,10 @@ pub fn default_branch(dir: &Path) -> Result<String> {
 
 /// Fetch from a local path with an explicit refspec, leaving no remote configured.
 pub fn fetch_from_path(dir: &Path, source_path: &Path, refspec: &str, prune: bool) -> Result<()> {
+    #[cfg(feature = "libgit2")]
+    if use_libgit2() {
+        return crate::libgit2::fetch_from_path(dir, source_path, refspec, prune);
+    }
     let src = path_str(source_path)?;
     let mut args = vec!["fetch"];
     if prune {
@@ -156,6 +630,10 @@ pub fn fetch_from_path(dir: &Path, source_path: &Path, refspec: &str, prune: boo
 
 /// Read the default branch from a bare mirror's refs/remotes/origin/HEAD.
 pub fn default_branch_from_mirror(mirror_dir: &Path) -> Result<String> {
+    #[cfg(feature = "libgit2")]
+    if use_libgit2() {
+        return crate::libgit2::default_branch_from_mirror(mirror_dir);
+    }
     let ref_str = run(
         Some(mirror_dir),
         &["symbolic-ref", "refs/remotes/origin/HEAD"],
@@ -172,9 +650,11 @@ pub fn has_remote(dir: &Path, name: &str) -> bool {
     run(Some(dir), &["remote", "get-url", name]).is_ok()
 }
 
-/// Return the URL configured for a named remote.
+/// Return the URL configured for a named remote, as written: without the
+/// `insteadOf` rewrites `git remote get-url` would apply.
 pub fn remote_get_url(dir: &Path, name: &str) -> Result<String> {
-    run(Some(dir), &["remote", "get-url", name])
+    let key = format!("remote.{}.url", name);
+    run(Some(dir), &["config", "--get", &key])
 }
 
 /// Remove a named remote. Errors if the remote does not exist.
@@ -185,11 +665,19 @@ pub fn remove_remote(dir: &Path, name: &str) -> Result<()> {
 
 /// Set the URL for an existing remote.
 pub fn remote_set_url(dir: &Path, remote: &str, url: &str) -> Result<()> {
+    #[cfg(feature = "libgit2")]
+    if use_libgit2() {
+        return crate::libgit2::remote_set_url(dir, remote, url);
+    }
     run(Some(dir), &["remote", "set-url", remote, url])?;
     Ok(())
 }
 
 pub fn clone_local(mirror_dir: &Path, dest: &Path) -> Result<()> {
+    #[cfg(feature = "libgit2")]
+    if use_libgit2() {
+        return crate::libgit2::clone_local(mirror_dir, dest);
+    }
     let src = path_str(mirror_dir)?;
     let dst = path_str(dest)?;
     run(None, &["clone", "--local", src, dst])?;
@@ -209,6 +697,10 @@ pub fn fetch_remote_prune(dir: &Path, remote: &str) -> Result<()> {
 }
 
 pub fn checkout_new_branch(dir: &Path, branch: &str, start_point: &str) -> Result<()> {
+    #[cfg(feature = "libgit2")]
+    if use_libgit2() {
+        return crate::libgit2::checkout_new_branch(dir, branch, start_point);
+    }
     run(
         Some(dir),
         &["checkout", "-b", branch, "--no-track", start_point],
@@ -217,6 +709,10 @@ pub fn checkout_new_branch(dir: &Path, branch: &str, start_point: &str) -> Resul
 }
 
 pub fn checkout_orphan(dir: &Path, branch: &str) -> Result<()> {
+    #[cfg(feature = "libgit2")]
+    if use_libgit2() {
+        return crate::libgit2::checkout_orphan(dir, branch);
+    }
     run(Some(dir), &["checkout", "--orphan", branch])?;
     Ok(())
 }
@@ -227,6 +723,10 @@ pub fn branch_rename(dir: &Path, old: &str, new: &str) -> Result<()> {
 }
 
 pub fn checkout(dir: &Path, ref_or_branch: &str) -> Result<()> {
+    #[cfg(feature = "libgit2")]
+    if use_libgit2() {
+        return crate::libgit2::checkout(dir, ref_or_branch);
+    }
     run(Some(dir), &["checkout", ref_or_branch])?;
     Ok(())
 }
@@ -248,6 +748,10 @@ pub fn default_branch_for_remote(dir: &Path, remote: &str) -> Result<String> {
 }
 
 pub fn remote_set_head(dir: &Path, remote: &str, branch: &str) -> Result<()> {
+    #[cfg(feature = "libgit2")]
+    if use_libgit2() {
+        return crate::libgit2::remote_set_head(dir, remote, branch);
+    }
     run(Some(dir), &["remote", "set-head", remote, branch])?;
     Ok(())
 }
@@ -256,7 +760,7 @@ pub fn branch_is_merged(dir: &Path, branch: &str, target: &str) -> Result<bool>
     let mut cmd = Command::new("git");
     cmd.args(["merge-base", "--is-ancestor", branch, target]);
     cmd.current_dir(dir);
-    let output = cmd.output()?;
+    let output = logged_output(&mut cmd)?;
     match output.status.code() {
         Some(0) => Ok(true),
         Some(1) => Ok(false),
@@ -308,7 +812,7 @@ pub fn is_content_merged(dir: &Path, branch: &str, target: &str) -> Result<bool>
         cmd.arg(f);
     }
     cmd.current_dir(dir);
-    let output = cmd.output()?;
+    let output = logged_output(&mut cmd)?;
     match output.status.code() {
         Some(0) => Ok(true),
         Some(1) => Ok(false),
@@ -348,20 +852,35 @@ pub fn branch_safety(dir: &Path, branch: &str, target: &str) -> BranchSafety {
 }
 
 pub fn branch_exists(dir: &Path, branch: &str) -> bool {
-    let ref_path = format!("refs/heads/{}", branch);
-    run(Some(dir), &["rev-parse", "--verify", &ref_path]).is_ok()
+    ref_exists(dir, &format!("refs/heads/{}", branch))
 }
 
 pub fn ref_exists(dir: &Path, git_ref: &str) -> bool {
+    #[cfg(feature = "gitoxide")]
+    if let Some(found) = crate::gitoxide::rev_exists(dir, git_ref) {
+        return found;
+    }
+    #[cfg(feature = "libgit2")]
+    if use_libgit2() {
+        return crate::libgit2::rev_exists(dir, git_ref);
+    }
     run(Some(dir), &["rev-parse", "--verify", git_ref]).is_ok()
 }
 
 pub fn update_ref(dir: &Path, refname: &str, target: &str) -> Result<()> {
+    #[cfg(feature = "libgit2")]
+    if use_libgit2() {
+        return crate::libgit2::update_ref(dir, refname, target);
+    }
     run(Some(dir), &["update-ref", "--no-deref", refname, target])?;
     Ok(())
 }
 
 pub fn is_ancestor(dir: &Path, ancestor: &str, descendant: &str) -> bool {
+    #[cfg(feature = "libgit2")]
+    if use_libgit2() {
+        return crate::libgit2::is_ancestor(dir, ancestor, descendant);
+    }
     run(
         Some(dir),
         &["merge-base", "--is-ancestor", ancestor, descendant],
@@ -370,9 +889,56 @@ pub fn is_ancestor(dir: &Path, ancestor: &str, descendant: &str) -> bool {
 }
 
 pub fn branch_current(dir: &Path) -> Result<String> {
+    #[cfg(feature = "gitoxide")]
+    if let Some(branch) = crate::gitoxide::branch_current(dir) {
+        return Ok(branch);
+    }
     run(Some(dir), &["rev-parse", "--abbrev-ref", "HEAD"])
 }
 
+/// The branch HEAD points at, even before its first commit. `None` when
+/// HEAD is detached (or `dir` isn't a repo).
+pub fn symbolic_head(dir: &Path) -> Option<String> {
+    run(Some(dir), &["symbolic-ref", "--quiet", "--short", "HEAD"]).ok()
+}
+
+/// Whether HEAD's commit is on some local or remote-tracking branch, i.e.
+/// leaving a detached HEAD would not strand commits.
+pub fn head_on_any_branch(dir: &Path) -> Result<bool> {
+    let out = run(
+        Some(dir),
+        &[
+            "for-each-ref",
+            "--count=1",
+            "--contains=HEAD",
+            "refs/heads",
+            "refs/remotes",
+        ],
+    )?;
+    Ok(!out.is_empty())
+}
+
+pub fn head_sha(dir: &Path) -> Result<String> {
+    #[cfg(feature = "gitoxide")]
+    if let Some(sha) = crate::gitoxide::head_sha(dir) {
+        return Ok(sha);
+    }
+    run(Some(dir), &["rev-parse", "HEAD"])
+}
+
+/// Name of the current branch's tracking branch, e.g. `origin/my-feature`.
+pub fn upstream_name(dir: &Path) -> Result<String> {
+    run(
+        Some(dir),
+        &[
+            "rev-parse",
+            "--abbrev-ref",
+            "--symbolic-full-name",
+            "@{upstream}",
+        ],
+    )
+}
+
 /// Resolved upstream reference for the current branch.
 pub enum UpstreamRef {
     /// @{upstream} tracking branch exists.
@@ -383,15 +949,14 @@ pub enum UpstreamRef {
     Head,
 }
 
-/// Probe once and return the best upstream reference.
+/// Probe once and return the best upstream reference: the tracking
+/// branch if it exists, else origin's default branch, else HEAD.
 pub fn resolve_upstream_ref(dir: &Path) -> UpstreamRef {
-    if run(Some(dir), &["rev-parse", "--verify", "@{upstream}"]).is_ok() {
-        return UpstreamRef::Tracking;
-    }
-    if let Ok(branch) = default_branch(dir) {
-        return UpstreamRef::DefaultBranch(branch);
+    #[cfg(feature = "gitoxide")]
+    if let Some(upstream) = crate::gitoxide::upstream_ref(dir) {
+        return upstream;
     }
-    UpstreamRef::Head
+    refs(dir).map_or(UpstreamRef::Head, |r| r.upstream())
 }
 
 pub fn merge_base(dir: &Path, a: &str, b: &str) -> Result<String> {
@@ -403,23 +968,241 @@ pub fn ahead_count(dir: &Path) -> Result<u32> {
 }
 
 pub fn ahead_count_from(dir: &Path, upstream: &UpstreamRef) -> Result<u32> {
-    let range = match upstream {
-        UpstreamRef::Tracking => "@{upstream}..HEAD".to_string(),
-        UpstreamRef::DefaultBranch(b) => format!("origin/{}..HEAD", b),
-        UpstreamRef::Head => return Ok(0),
-    };
-    let out = run(Some(dir), &["rev-list", "--count", &range])?;
-    Ok(out.parse::<u32>().unwrap_or(0))
+    match upstream_rev(upstream) {
+        Some(base) => commit_count(dir, &base, "HEAD"),
+        None => Ok(0),
+    }
 }
 
 pub fn behind_count_from(dir: &Path, upstream: &UpstreamRef) -> Result<u32> {
-    let range = match upstream {
-        UpstreamRef::Tracking => "HEAD..@{upstream}".to_string(),
-        UpstreamRef::DefaultBranch(b) => format!("HEAD..origin/{}", b),
-        UpstreamRef::Head => return Ok(0),
+    match upstream_rev(upstream) {
+        Some(base) => commit_count(dir, "HEAD", &base),
+        None => Ok(0),
+    }
+}
+
+fn upstream_rev(upstream: &UpstreamRef) -> Option<String> {
+    match upstream {
+        UpstreamRef::Tracking => Some("@{upstream}".to_string()),
+        UpstreamRef::DefaultBranch(b) => Some(format!("origin/{}", b)),
+        UpstreamRef::Head => None,
+    }
+}
+
+/// A clone's refs from one `git for-each-ref`: its branches and origin's,
+/// the current branch's upstream, and origin's default branch. Answers
+/// what [`branch_exists`], [`remote_branch_exists`],
+/// [`resolve_upstream_ref`] and [`default_branch`] would, without a git
+/// call each.
+#[derive(Debug, Default)]
+pub struct Refs {
+    /// The checked-out branch; `None` when HEAD is detached or unborn.
+    pub head: Option<String>,
+    /// Commits ahead of and behind the current branch's upstream, when it
+    /// has one and it exists.
+    tracking: Option<(u32, u32)>,
+    heads: BTreeSet<String>,
+    origin: BTreeSet<String>,
+    /// Where `refs/remotes/origin/HEAD` points.
+    origin_head: Option<String>,
+}
+
+impl Refs {
+    pub fn branch_exists(&self, branch: &str) -> bool {
+        self.heads.contains(branch)
+    }
+
+    pub fn remote_branch_exists(&self, branch: &str) -> bool {
+        self.origin.contains(branch)
+    }
+
+    /// [`default_branch`]: origin's, else the branch HEAD is on.
+    pub fn default_branch(&self) -> Option<&str> {
+        self.origin_head
+            .as_deref()
+            .or(self.head.as_deref())
+            // Same parsing as `default_branch`, which keeps the last part.
+            .and_then(|b| b.rsplit('/').next())
+    }
+
+    /// [`resolve_upstream_ref`].
+    pub fn upstream(&self) -> UpstreamRef {
+        if self.tracking.is_some() {
+            return UpstreamRef::Tracking;
+        }
+        match self.default_branch() {
+            Some(b) => UpstreamRef::DefaultBranch(b.to_string()),
+            None => UpstreamRef::Head,
+        }
+    }
+
+    fn parse(out: &str) -> Refs {
+        let mut refs = Refs::default();
+        for line in out.lines() {
+            let fields: Vec<&str> = line.split('\0').collect();
+            let [refname, symref, upstream, track, head] = fields[..] else {
+                continue;
+            };
+            if let Some(branch) = refname.strip_prefix("refs/heads/") {
+                refs.heads.insert(branch.to_string());
+                if head.trim() == "*" {
+                    refs.head = Some(branch.to_string());
+                    if !upstream.is_empty() && track != "[gone]" {
+                        refs.tracking = Some(parse_track(track));
+                    }
+                }
+            } else if refname == "refs/remotes/origin/HEAD" {
+                refs.origin_head = symref
+                    .strip_prefix("refs/remotes/origin/")
+                    .map(String::from);
+            } else if let Some(branch) = refname.strip_prefix("refs/remotes/origin/") {
+                refs.origin.insert(branch.to_string());
+            }
+        }
+        refs
+    }
+}
+
+/// `%(upstream:track)`, like `[ahead 2, behind 1]`, as (ahead, behind).
+fn parse_track(track: &str) -> (u32, u32) {
+    let mut counts = (0, 0);
+    for part in track.trim_matches(['[', ']']).split(", ") {
+        match part.split_once(' ') {
+            Some(("ahead", n)) => counts.0 = n.parse().unwrap_or(0),
+            Some(("behind", n)) => counts.1 = n.parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+    counts
+}
+
+pub fn refs(dir: &Path) -> Result<Refs> {
+    let out = run(
+        Some(dir),
+        &[
+            "for-each-ref",
+            "--format=%(refname)%00%(symref)%00%(upstream)%00%(upstream:track)%00%(HEAD)",
+            "refs/heads",
+            "refs/remotes/origin",
+        ],
+    )?;
+    Ok(Refs::parse(&out))
+}
+
+/// What `wsp st` and removal checks want to know about a clone, from
+/// [`refs`] and one `git status` (plus a `rev-list` when the branch has no
+/// upstream and is compared to origin's default branch instead).
+#[derive(Debug)]
+pub struct Snapshot {
+    pub refs: Refs,
+    /// [`branch_current`]: the branch, "HEAD" when detached, `None` when
+    /// unborn.
+    pub branch: Option<String>,
+    /// Commits on HEAD not on the upstream, as [`ahead_count`] counts them.
+    pub ahead: u32,
+    pub behind: u32,
+    /// Whether `ahead` and `behind` were measured. False when there is
+    /// nothing to compare HEAD with, or the comparison failed.
+    pub compared: bool,
+    /// [`changed_files`].
+    pub files: Vec<String>,
+    pub stashes: u32,
+}
+
+pub fn snapshot(dir: &Path) -> Result<Snapshot> {
+    let refs = refs(dir)?;
+    // In process, asking each question separately costs nothing.
+    #[cfg(feature = "gitoxide")]
+    if let (Some(files), Some(stashes)) = (
+        crate::gitoxide::status_short(dir),
+        crate::gitoxide::stash_count(dir),
+    ) {
+        let upstream = refs.upstream();
+        let counts = match upstream_rev(&upstream) {
+            Some(_) => ahead_count_from(dir, &upstream)
+                .and_then(|a| Ok((a, behind_count_from(dir, &upstream)?)))
+                .ok(),
+            None => None,
+        };
+        return Ok(Snapshot {
+            branch: branch_current(dir).ok(),
+            ahead: counts.map_or(0, |c| c.0),
+            behind: counts.map_or(0, |c| c.1),
+            compared: counts.is_some(),
+            files,
+            stashes,
+            refs,
+        });
+    }
+    let status = run_untrimmed(
+        Some(dir),
+        &["status", "--porcelain=v2", "--branch", "--show-stash"],
+        &[],
+        None,
+        None,
+    )?;
+    let mut branch = None;
+    let mut unborn = false;
+    let mut files = Vec::new();
+    let mut stashes = 0;
+    for line in status.lines() {
+        if let Some(head) = line.strip_prefix("# branch.head ") {
+            branch = Some(match head {
+                "(detached)" => "HEAD".to_string(),
+                b => b.to_string(),
+            });
+        } else if line == "# branch.oid (initial)" {
+            unborn = true;
+        } else if let Some(n) = line.strip_prefix("# stash ") {
+            stashes = n.parse().unwrap_or(0);
+        } else if let Some(file) = short_status_line(line) {
+            files.push(file);
+        }
+    }
+    let counts = match (refs.upstream(), unborn) {
+        (_, true) | (UpstreamRef::Head, _) => None,
+        (UpstreamRef::Tracking, _) => refs.tracking,
+        (UpstreamRef::DefaultBranch(b), _) if refs.remote_branch_exists(&b) => {
+            let range = format!("origin/{}...HEAD", b);
+            let out = run(Some(dir), &["rev-list", "--left-right", "--count", &range])?;
+            out.split_once('\t')
+                .and_then(|(behind, ahead)| Some((ahead.parse().ok()?, behind.parse().ok()?)))
+        }
+        (UpstreamRef::DefaultBranch(_), _) => None,
     };
-    let out = run(Some(dir), &["rev-list", "--count", &range])?;
-    Ok(out.parse::<u32>().unwrap_or(0))
+    Ok(Snapshot {
+        refs,
+        branch: branch.filter(|_| !unborn),
+        ahead: counts.map_or(0, |c| c.0),
+        behind: counts.map_or(0, |c| c.1),
+        compared: counts.is_some(),
+        files,
+        stashes,
+    })
+}
+
+/// A `git status --porcelain=v2` entry as `git status --short` prints it,
+/// e.g. `1 .M N... 100644 100644 100644 <sha> <sha> src/main.rs` as
+/// ` M src/main.rs`. `None` for headers and ignored files.
+fn short_status_line(line: &str) -> Option<String> {
+    let xy = |xy: &str| xy.replace('.', " ");
+    match line.split_at_checked(2)? {
+        ("? ", path) => Some(format!("?? {}", path)),
+        ("1 ", rest) => {
+            let f: Vec<&str> = rest.splitn(8, ' ').collect();
+            Some(format!("{} {}", xy(f.first()?), f.get(7)?))
+        }
+        ("2 ", rest) => {
+            let f: Vec<&str> = rest.splitn(9, ' ').collect();
+            let (path, orig) = f.get(8)?.split_once('\t')?;
+            Some(format!("{} {} -> {}", xy(f.first()?), orig, path))
+        }
+        ("u ", rest) => {
+            let f: Vec<&str> = rest.splitn(10, ' ').collect();
+            Some(format!("{} {}", xy(f.first()?), f.get(9)?))
+        }
+        _ => None,
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -431,6 +1214,10 @@ pub enum SyncAction {
 }
 
 pub fn commit_count(dir: &Path, from: &str, to: &str) -> Result<u32> {
+    #[cfg(feature = "gitoxide")]
+    if let Some(count) = crate::gitoxide::commit_count(dir, from, to) {
+        return Ok(count);
+    }
     let range = format!("{}..{}", from, to);
     let out = run(Some(dir), &["rev-list", "--count", &range])?;
     Ok(out.parse::<u32>().unwrap_or(0))
@@ -498,32 +1285,76 @@ pub fn merge_from(dir: &Path, target: &str) -> Result<SyncAction> {
     }
 }
 
-/// Detect an in-progress rebase or merge and return what kind, if any.
+/// A git operation left in progress in a clone, e.g. a rebase stopped on a
+/// conflict. Needs a human before wsp touches the repo.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum InProgressOp {
     Rebase,
     Merge,
+    CherryPick,
+    Revert,
+    Bisect,
+}
+
+impl InProgressOp {
+    pub fn name(self) -> &'static str {
+        match self {
+            InProgressOp::Rebase => "rebase",
+            InProgressOp::Merge => "merge",
+            InProgressOp::CherryPick => "cherry-pick",
+            InProgressOp::Revert => "revert",
+            InProgressOp::Bisect => "bisect",
+        }
+    }
+
+    /// How to finish or back out of the operation.
+    pub fn hint(self) -> &'static str {
+        match self {
+            InProgressOp::Rebase => "run `git rebase --continue` or `git rebase --abort`",
+            InProgressOp::Merge => "run `git merge --continue` or `git merge --abort`",
+            InProgressOp::CherryPick => {
+                "run `git cherry-pick --continue` or `git cherry-pick --abort`"
+            }
+            InProgressOp::Revert => "run `git revert --continue` or `git revert --abort`",
+            InProgressOp::Bisect => "run `git bisect reset` when done",
+        }
+    }
 }
 
+/// Detect an in-progress operation from the state files git keeps in `.git`.
 pub fn in_progress_op(dir: &Path) -> Option<InProgressOp> {
     let git_dir = dir.join(".git");
     if git_dir.join("rebase-merge").exists() || git_dir.join("rebase-apply").exists() {
         Some(InProgressOp::Rebase)
     } else if git_dir.join("MERGE_HEAD").exists() {
         Some(InProgressOp::Merge)
+    } else if git_dir.join("CHERRY_PICK_HEAD").exists() {
+        Some(InProgressOp::CherryPick)
+    } else if git_dir.join("REVERT_HEAD").exists() {
+        Some(InProgressOp::Revert)
+    } else if git_dir.join("BISECT_LOG").exists() {
+        Some(InProgressOp::Bisect)
     } else {
         None
     }
 }
 
-/// Abort an in-progress rebase or merge.
+/// Abort an in-progress operation, restoring the state from before it began.
 pub fn abort_in_progress(dir: &Path, op: &InProgressOp) -> Result<()> {
     match op {
         InProgressOp::Rebase => run(Some(dir), &["rebase", "--abort"]).map(|_| ()),
         InProgressOp::Merge => run(Some(dir), &["merge", "--abort"]).map(|_| ()),
+        InProgressOp::CherryPick => run(Some(dir), &["cherry-pick", "--abort"]).map(|_| ()),
+        InProgressOp::Revert => run(Some(dir), &["revert", "--abort"]).map(|_| ()),
+        InProgressOp::Bisect => run(Some(dir), &["bisect", "reset"]).map(|_| ()),
     }
 }
 
 pub fn set_upstream(dir: &Path, branch: &str, upstream: &str) -> Result<()> {
+    #[cfg(feature = "libgit2")]
+    if use_libgit2() {
+        return crate::libgit2::set_upstream(dir, branch, Some(upstream));
+    }
     run(
         Some(dir),
         &["branch", "--set-upstream-to", upstream, branch],
@@ -532,25 +1363,136 @@ pub fn set_upstream(dir: &Path, branch: &str, upstream: &str) -> Result<()> {
 }
 
 pub fn unset_upstream(dir: &Path, branch: &str) -> Result<()> {
+    #[cfg(feature = "libgit2")]
+    if use_libgit2() {
+        return crate::libgit2::set_upstream(dir, branch, None);
+    }
     run(Some(dir), &["branch", "--unset-upstream", branch])?;
     Ok(())
 }
 
 pub fn changed_file_count(dir: &Path) -> Result<u32> {
-    let out = run(Some(dir), &["status", "--short"])?;
-    if out.is_empty() {
-        Ok(0)
-    } else {
-        Ok(out.lines().count() as u32)
-    }
+    Ok(changed_files(dir)?.len() as u32)
 }
 
+/// `git status --short` lines, each with its two-letter status code.
 pub fn changed_files(dir: &Path) -> Result<Vec<String>> {
-    let out = run(Some(dir), &["status", "--short"])?;
-    if out.is_empty() {
-        Ok(vec![])
-    } else {
-        Ok(out.lines().map(|l| l.to_string()).collect())
+    #[cfg(feature = "gitoxide")]
+    if let Some(lines) = crate::gitoxide::status_short(dir) {
+        return Ok(lines);
+    }
+    let out = run_untrimmed(Some(dir), &["status", "--short"], &[], None, None)?;
+    Ok(out
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// One line of `git diff --numstat`. Binary files have no line counts.
+#[derive(Debug, PartialEq)]
+pub struct NumStat {
+    pub path: String,
+    pub added: Option<u32>,
+    pub deleted: Option<u32>,
+}
+
+/// Parse `git diff --numstat` output. Lines in any other format (e.g. from
+/// extra args that change the output) are skipped.
+pub fn parse_numstat(out: &str) -> Vec<NumStat> {
+    out.lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let (added, deleted, path) = (parts.next()?, parts.next()?, parts.next()?);
+            let count = |s: &str| -> Option<Option<u32>> {
+                match s {
+                    "-" => Some(None),
+                    n => n.parse().ok().map(Some),
+                }
+            };
+            Some(NumStat {
+                path: path.to_string(),
+                added: count(added)?,
+                deleted: count(deleted)?,
+            })
+        })
+        .collect()
+}
+
+/// Everything git ignores in `dir`'s worktree, relative to it. A directory
+/// that is ignored as a whole is listed once, with a trailing `/`, rather
+/// than file by file.
+pub fn ignored_entries(dir: &Path) -> Result<Vec<String>> {
+    let out = run_untrimmed(
+        Some(dir),
+        &[
+            "ls-files",
+            "--others",
+            "--ignored",
+            "--exclude-standard",
+            "--directory",
+            "-z",
+        ],
+        &[],
+        None,
+        None,
+    )?;
+    Ok(out
+        .split('\0')
+        .filter(|p| !p.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Returns the subset of `paths` (relative to `dir`) that git ignores.
+///
+/// Paths go to `git check-ignore` on stdin, NUL-separated, so a large batch
+/// of events can't overflow the argument list.
+pub fn ignored_paths(dir: &Path, paths: &[&str]) -> Result<Vec<String>> {
+    if paths.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut cmd = Command::new("git");
+    untranslated(&mut cmd);
+    let mut child = cmd
+        .args(["check-ignore", "--stdin", "-z"])
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("failed to run git check-ignore: {}", e))?;
+    let mut input = Vec::new();
+    for p in paths {
+        input.extend_from_slice(p.as_bytes());
+        input.push(0);
+    }
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    // Write from a separate thread: git answers as it reads, and a full
+    // stdout pipe would otherwise block both sides.
+    let (output, written) = std::thread::scope(|s| {
+        let writer = s.spawn(move || stdin.write_all(&input));
+        let output = child.wait_with_output();
+        (output, writer.join().expect("stdin writer panicked"))
+    });
+    let output = output?;
+    // Exit 1 means none of the paths are ignored
+    match output.status.code() {
+        Some(0) | Some(1) => written
+            .map(|()| {
+                output
+                    .stdout
+                    .split(|b| *b == 0)
+                    .filter(|p| !p.is_empty())
+                    .map(|p| String::from_utf8_lossy(p).into_owned())
+                    .collect()
+            })
+            .map_err(|e| anyhow::anyhow!("writing to git check-ignore: {}", e)),
+        _ => bail!(
+            "git check-ignore (in {}): {}",
+            dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
     }
 }
 
@@ -570,7 +1512,7 @@ pub fn show_file(git_dir: &Path, rev: &str, path: &str) -> Result<Vec<u8>> {
     let mut cmd = Command::new("git");
     cmd.args(["show", &spec]);
     cmd.current_dir(git_dir);
-    let output = cmd.output()?;
+    let output = logged_output(&mut cmd)?;
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
         bail!("git show {} (in {}): {}", spec, git_dir.display(), stderr);
@@ -614,7 +1556,7 @@ mod tests {
         let bare = bare_tmp.path().join("repo.git");
         clone_bare(source.to_str().unwrap(), &bare).unwrap();
         configure_fetch_refspec(&bare).unwrap();
-        fetch(&bare, true).unwrap();
+        fetch(&bare, true, None, &|_| {}).unwrap();
 
         // Set symbolic HEAD so default_branch works
         let out = StdCommand::new("git")
@@ -700,7 +1642,7 @@ mod tests {
         squash_merge(&source, "feature", "main");
 
         // Fetch into bare so it has the updated refs
-        fetch(&bare, true).unwrap();
+        fetch(&bare, true, None, &|_| {}).unwrap();
 
         let result = branch_is_squash_merged(&bare, "origin/feature", "origin/main").unwrap();
         assert!(result, "squash-merged branch should be detected");
@@ -713,7 +1655,7 @@ mod tests {
         // Create a feature branch with a commit but don't merge it
         commit_on_branch(&source, "unmerged", "unmerged.txt");
 
-        fetch(&bare, true).unwrap();
+        fetch(&bare, true, None, &|_| {}).unwrap();
 
         let result = branch_is_squash_merged(&bare, "origin/unmerged", "origin/main").unwrap();
         assert!(
@@ -726,7 +1668,7 @@ mod tests {
     fn test_remote_branch_exists() {
         let (bare, source, _bt, _st) = setup_bare_repo();
         commit_on_branch(&source, "exists-branch", "e.txt");
-        fetch(&bare, true).unwrap();
+        fetch(&bare, true, None, &|_| {}).unwrap();
 
         assert!(remote_branch_exists(&bare, "exists-branch"));
     }
@@ -771,7 +1713,7 @@ mod tests {
         assert!(out.status.success());
 
         // Fetch everything into bare — creates refs/remotes/origin/* for all branches
-        fetch(&bare, true).unwrap();
+        fetch(&bare, true, None, &|_| {}).unwrap();
 
         // Ensure local branches (refs/heads/*) mirror the remote tracking refs.
         // This simulates what workspace clones do: the workspace branch is a
@@ -833,7 +1775,7 @@ mod tests {
 
         commit_on_branch(&source, "feature", "feat.txt");
         squash_merge(&source, "feature", "main");
-        fetch(&bare, true).unwrap();
+        fetch(&bare, true, None, &|_| {}).unwrap();
 
         let result = is_content_merged(&bare, "origin/feature", "origin/main").unwrap();
         assert!(result, "squash-merged branch should be content-merged");
@@ -844,7 +1786,7 @@ mod tests {
         let (bare, source, _bt, _st) = setup_bare_repo();
 
         commit_on_branch(&source, "unmerged", "unmerged.txt");
-        fetch(&bare, true).unwrap();
+        fetch(&bare, true, None, &|_| {}).unwrap();
 
         let result = is_content_merged(&bare, "origin/unmerged", "origin/main").unwrap();
         assert!(!result, "unmerged branch should not be content-merged");
@@ -879,7 +1821,7 @@ mod tests {
 
         // Squash-merge feature into main
         squash_merge(&source, "feature", "main");
-        fetch(&bare, true).unwrap();
+        fetch(&bare, true, None, &|_| {}).unwrap();
 
         // cherry/patch-id may fail here, but content-based detection should work
         let result = is_content_merged(&bare, "origin/feature", "origin/main").unwrap();
@@ -931,6 +1873,41 @@ mod tests {
         assert_eq!(count, 0);
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_timeout_lets_the_command_clean_up() {
+        let tmp = tempfile::tempdir().unwrap();
+        let cleaned = tmp.path().join("cleaned");
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "trap 'touch cleaned; exit 1' TERM; sleep 10 & wait"])
+            .current_dir(tmp.path());
+        let err =
+            output_streaming(&mut cmd, Some(Duration::from_millis(200)), &mut |_| {}).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+        assert!(cleaned.exists());
+    }
+
+    #[test]
+    fn test_ignored_paths() {
+        let (clone, _source, _ct, _st) = setup_clone_repo();
+        std::fs::write(clone.join(".gitignore"), "target/\n").unwrap();
+        assert!(ignored_paths(&clone, &[]).unwrap().is_empty());
+
+        // More paths than fit on a command line.
+        let long = "x".repeat(200);
+        let many: Vec<String> = (0..20_000)
+            .map(|i| format!("target/{}/{}", long, i))
+            .chain(["src/lib.rs".to_string()])
+            .collect();
+        let refs: Vec<&str> = many.iter().map(|s| s.as_str()).collect();
+        let ignored = ignored_paths(&clone, &refs).unwrap();
+        assert_eq!(ignored.len(), 20_000);
+        assert!(!ignored.contains(&"src/lib.rs".to_string()));
+
+        let not_a_repo = tempfile::tempdir().unwrap();
+        assert!(ignored_paths(not_a_repo.path(), &["a"]).is_err());
+    }
+
     #[test]
     fn test_rebase_onto_up_to_date() {
         let (clone, _source, _ct, _st) = setup_clone_repo();
@@ -1056,6 +2033,125 @@ mod tests {
         assert_eq!(ahead_count_from(&clone, &upstream).unwrap(), 1);
     }
 
+    #[test]
+    fn test_snapshot_stashes() {
+        let (clone, _source, _ct, _st) = setup_clone_repo();
+        assert_eq!(snapshot(&clone).unwrap().stashes, 0);
+
+        for i in 0..2 {
+            local_commit(&clone, &format!("f{i}.txt"), "v1");
+            std::fs::write(clone.join(format!("f{i}.txt")), "v2").unwrap();
+            run(Some(&clone), &["stash"]).unwrap();
+        }
+        assert_eq!(snapshot(&clone).unwrap().stashes, 2);
+    }
+
+    #[test]
+    fn test_snapshot_matches_single_queries() {
+        let (clone, _source, _ct, _st) = setup_clone_repo();
+        run(Some(&clone), &["checkout", "-q", "main"]).unwrap();
+        let snap = snapshot(&clone).unwrap();
+        assert_eq!(snap.branch.as_deref(), Some("main"));
+        assert!(matches!(snap.refs.upstream(), UpstreamRef::Tracking));
+        assert_eq!((snap.ahead, snap.behind), (0, 0));
+        assert!(snap.compared);
+
+        local_commit(&clone, "a.txt", "v1");
+        local_commit(&clone, "b.txt", "v1");
+        run(Some(&clone), &["mv", "b.txt", "c.txt"]).unwrap();
+        std::fs::write(clone.join("a.txt"), "v2").unwrap();
+        std::fs::create_dir(clone.join("dir")).unwrap();
+        std::fs::write(clone.join("dir/new file.txt"), "new").unwrap();
+        run(Some(&clone), &["checkout", "-q", "-B", "feature"]).unwrap();
+
+        let snap = snapshot(&clone).unwrap();
+        assert_eq!(snap.files, changed_files(&clone).unwrap());
+        assert_eq!(snap.files.len(), 3);
+        assert_eq!(snap.branch.unwrap(), branch_current(&clone).unwrap());
+        assert!(snap.refs.branch_exists("main") && snap.refs.branch_exists("feature"));
+        assert!(snap.refs.remote_branch_exists("main"));
+        assert!(!snap.refs.remote_branch_exists("feature"));
+        assert_eq!(snap.refs.default_branch(), Some("main"));
+        // No upstream: compared to origin's default branch.
+        assert!(matches!(snap.refs.upstream(), UpstreamRef::DefaultBranch(b) if b == "main"));
+        assert_eq!(
+            snap.ahead,
+            commit_count(&clone, "origin/main", "HEAD").unwrap()
+        );
+        assert_eq!(snap.ahead, 2);
+        assert!(snap.compared);
+
+        // Nothing to compare with: origin's default branch is gone.
+        run(
+            Some(&clone),
+            &["update-ref", "-d", "refs/remotes/origin/main"],
+        )
+        .unwrap();
+        assert!(!snapshot(&clone).unwrap().compared);
+
+        run(Some(&clone), &["checkout", "-q", "--detach"]).unwrap();
+        let snap = snapshot(&clone).unwrap();
+        assert_eq!(snap.branch.as_deref(), Some("HEAD"));
+        assert_eq!(snap.refs.head, None);
+    }
+
+    #[test]
+    fn test_short_status_line() {
+        let sha = "0".repeat(40);
+        let cases = vec![
+            (
+                format!("1 .M N... 100644 100644 100644 {sha} {sha} src/a b.rs"),
+                Some(" M src/a b.rs"),
+            ),
+            (
+                format!("1 A. N... 000000 100644 100644 {sha} {sha} new.rs"),
+                Some("A  new.rs"),
+            ),
+            (
+                format!("2 R. N... 100644 100644 100644 {sha} {sha} R100 new.rs\told.rs"),
+                Some("R  old.rs -> new.rs"),
+            ),
+            (
+                format!("u UU N... 100644 100644 100644 100644 {sha} {sha} {sha} c.rs"),
+                Some("UU c.rs"),
+            ),
+            ("? dir/".to_string(), Some("?? dir/")),
+            ("! target/".to_string(), None),
+            ("# branch.head main".to_string(), None),
+        ];
+        for (line, want) in cases {
+            assert_eq!(short_status_line(&line).as_deref(), want, "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_parse_track() {
+        let cases = vec![
+            ("", (0, 0)),
+            ("[ahead 2]", (2, 0)),
+            ("[behind 3]", (0, 3)),
+            ("[ahead 2, behind 3]", (2, 3)),
+        ];
+        for (track, want) in cases {
+            assert_eq!(parse_track(track), want, "{}", track);
+        }
+    }
+
+    #[test]
+    fn test_changed_files() {
+        let (clone, _source, _ct, _st) = setup_clone_repo();
+        assert!(changed_files(&clone).unwrap().is_empty());
+
+        local_commit(&clone, "a.txt", "v1");
+        std::fs::write(clone.join("a.txt"), "v2").unwrap();
+        std::fs::write(clone.join("new.txt"), "new").unwrap();
+        assert_eq!(
+            changed_files(&clone).unwrap(),
+            vec![" M a.txt", "?? new.txt"]
+        );
+        assert_eq!(changed_file_count(&clone).unwrap(), 2);
+    }
+
     #[test]
     fn test_in_progress_op_none() {
         let (clone, _source, _ct, _st) = setup_clone_repo();
@@ -1111,6 +2207,34 @@ mod tests {
         assert!(in_progress_op(&clone).is_none());
     }
 
+    #[test]
+    fn test_in_progress_op_cherry_pick_and_bisect() {
+        let (clone, source, _ct, _st) = setup_clone_repo();
+
+        local_commit(&clone, "conflict.txt", "local version");
+        advance_origin(&source, &clone, "main", "conflict.txt", "upstream version");
+
+        let out = StdCommand::new("git")
+            .args(["cherry-pick", "origin/main"])
+            .current_dir(&clone)
+            .output()
+            .unwrap();
+        assert!(
+            !out.status.success(),
+            "cherry-pick should fail with conflict"
+        );
+        let op = in_progress_op(&clone);
+        assert_eq!(op, Some(InProgressOp::CherryPick));
+        abort_in_progress(&clone, &op.unwrap()).unwrap();
+        assert!(in_progress_op(&clone).is_none());
+
+        run(Some(&clone), &["bisect", "start"]).unwrap();
+        let op = in_progress_op(&clone);
+        assert_eq!(op, Some(InProgressOp::Bisect));
+        abort_in_progress(&clone, &op.unwrap()).unwrap();
+        assert!(in_progress_op(&clone).is_none());
+    }
+
     #[test]
     fn test_validate_branch_name() {
         let cases = vec![
@@ -1134,4 +2258,183 @@ mod tests {
             assert_eq!(result.is_ok(), want_ok, "{}: {:?}", label, result);
         }
     }
+
+    #[test]
+    fn test_parse_numstat() {
+        let out = "3\t1\tsrc/main.rs\n-\t-\tlogo.png\n0\t2\tsrc/{old => new}.rs\nnot numstat";
+        let got = parse_numstat(out);
+        let want = vec![
+            NumStat {
+                path: "src/main.rs".into(),
+                added: Some(3),
+                deleted: Some(1),
+            },
+            NumStat {
+                path: "logo.png".into(),
+                added: None,
+                deleted: None,
+            },
+            NumStat {
+                path: "src/{old => new}.rs".into(),
+                added: Some(0),
+                deleted: Some(2),
+            },
+        ];
+        assert_eq!(got, want);
+    }
+
+    #[test]
+    fn test_transfer_update() {
+        let cases = vec![
+            (
+                "Receiving objects:  45% (450/1000), 1.50 MiB | 2.40 MiB/s",
+                Some((450, 1000, 1_572_864)),
+            ),
+            (
+                "Unpacking objects: 100% (3/3), 232 bytes | 232.00 KiB/s, done.",
+                Some((3, 3, 232)),
+            ),
+            ("Receiving objects: 100% (3/3), done.", Some((3, 3, 0))),
+            ("remote: Counting objects: 100% (3/3), done.", None),
+            ("Resolving deltas: 100% (1/1), done.", None),
+            ("From /tmp/source", None),
+        ];
+        for (line, want) in cases {
+            let mut t = Transfer::default();
+            let got = t
+                .update(line)
+                .then_some((t.objects, t.total_objects, t.bytes));
+            assert_eq!(got, want, "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_without_progress() {
+        let stderr = "remote: Enumerating objects: 5, done.\n\
+                      remote: Counting objects:  50% (1/2)\rremote: Counting objects: 100% (2/2), done.\n\
+                      Resolving deltas:  0% (0/1)\rResolving deltas: 100% (1/1), done.\n\
+                      Receiving objects: 100% (3/3), done.\n\
+                      fatal: unable to access 'https://example.com/': Could not resolve host\r\n";
+        assert_eq!(
+            without_progress(stderr),
+            "remote: Enumerating objects: 5, done.\n\
+             fatal: unable to access 'https://example.com/': Could not resolve host"
+        );
+    }
+
+    #[test]
+    fn test_fetch_reports_transfer() {
+        let (_clone, source, _ct, _st) = setup_clone_repo();
+        let tmp = tempfile::tempdir().unwrap();
+        let mirror = tmp.path().join("m.git");
+        run(
+            None,
+            &[
+                "clone",
+                "--bare",
+                source.to_str().unwrap(),
+                mirror.to_str().unwrap(),
+            ],
+        )
+        .unwrap();
+        // Small fetches unpack loose objects, which only shows progress
+        // after a couple of seconds; index the pack as a big one would.
+        run(Some(&mirror), &["config", "fetch.unpackLimit", "1"]).unwrap();
+        crate::testutil::local_commit(&source, "a.txt", "hello\n");
+
+        let seen = std::cell::Cell::new(0);
+        let t = fetch(&mirror, true, None, &|_| seen.set(seen.get() + 1)).unwrap();
+        assert!(t.objects > 0, "{:?}", t);
+        assert_eq!(t.objects, t.total_objects);
+        assert!(seen.get() > 0);
+
+        let t = fetch(&mirror, true, None, &|_| {}).unwrap();
+        assert_eq!(t, Transfer::default(), "nothing new");
+    }
+
+    #[test]
+    fn test_git_messages_untranslated() {
+        let show = "alias.locale=!echo \"[$LC_ALL] $LC_MESSAGES $LC_CTYPE [$LANGUAGE]\"";
+        struct Case {
+            name: &'static str,
+            env: &'static [(&'static str, &'static str)],
+            want: &'static str,
+        }
+        let cases = [
+            Case {
+                name: "LC_ALL moves to LC_CTYPE",
+                env: &[("LC_ALL", "de_DE.UTF-8"), ("LANGUAGE", "de")],
+                want: "[] C de_DE.UTF-8 []",
+            },
+            Case {
+                name: "LC_CTYPE kept",
+                env: &[("LC_ALL", ""), ("LC_CTYPE", "ja_JP.UTF-8")],
+                want: "[] C ja_JP.UTF-8 []",
+            },
+        ];
+        for c in cases {
+            let out = run_with_env(None, &["-c", show, "locale"], c.env).unwrap();
+            assert_eq!(out, c.want, "{}", c.name);
+        }
+    }
+
+    #[test]
+    fn test_network_config_reaches_git() {
+        let (_clone, source, _ct, _st) = setup_clone_repo();
+        let entries = vec![
+            (
+                format!(
+                    "url.file://{}/.insteadOf",
+                    source.parent().unwrap().display()
+                ),
+                "https://example.invalid/acme/".to_string(),
+            ),
+            (
+                "http.https://ghe.acme.com.proxy".to_string(),
+                "http://proxy.acme.com:3128".to_string(),
+            ),
+        ];
+        let name = source.file_name().unwrap().to_str().unwrap();
+        let url = format!("https://example.invalid/acme/{}", name);
+
+        let mut cmd = Command::new("git");
+        cmd.args(["ls-remote", &url, "HEAD"]);
+        apply_network_config(&mut cmd, &entries);
+        let out = logged_output(&mut cmd).unwrap();
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        assert!(String::from_utf8_lossy(&out.stdout).contains("HEAD"));
+
+        let cases = vec![
+            (
+                "https://ghe.acme.com/acme/api.git",
+                "http://proxy.acme.com:3128",
+            ),
+            ("https://github.com/acme/api.git", ""),
+        ];
+        for (url, want) in cases {
+            let mut cmd = Command::new("git");
+            cmd.args(["config", "--get-urlmatch", "http.proxy", url]);
+            apply_network_config(&mut cmd, &entries);
+            let out = logged_output(&mut cmd).unwrap();
+            assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), want, "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_add_config_keeps_other_values() {
+        let (clone, _source, _ct, _st) = setup_clone_repo();
+        let key = "url.https://proxy.acme.com/.insteadOf";
+        add_config(&clone, key, "https://github.com/").unwrap();
+        add_config(&clone, key, "git@github.com:").unwrap();
+        add_config(&clone, key, "https://github.com/").unwrap();
+        assert_eq!(
+            get_all_config(&clone, key),
+            vec!["https://github.com/", "git@github.com:"]
+        );
+        assert!(get_all_config(&clone, "url.none.insteadOf").is_empty());
+    }
 }
@@ -0,0 +1,429 @@
+//! In-process answers to the read-only git queries `wsp st`, `wsp ls` and
+//! friends make for every repo — refs, HEAD, ahead/behind counts, short
+//! status, commit lists — using gitoxide instead of spawning git. Built
+//! with the `gitoxide` feature.
+//!
+//! Each function returns `None` when it can't answer exactly as the git
+//! CLI would (a revision syntax gix doesn't take, a submodule, a path git
+//! would quote), and the caller in `git` runs git instead.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use gix::bstr::ByteSlice;
+use gix::index::entry::Mode;
+use gix::status::index_worktree::Item as WorktreeItem;
+use gix::status::plumbing::index_as_worktree::{Change, Conflict, EntryStatus};
+
+use crate::git::UpstreamRef;
+
+fn open(dir: &Path) -> Option<gix::Repository> {
+    gix::open(dir).ok()
+}
+
+/// `git rev-parse --verify <spec>` succeeding.
+pub fn rev_exists(dir: &Path, spec: &str) -> Option<bool> {
+    let repo = open(dir)?;
+    if spec.starts_with("refs/") {
+        return repo.try_find_reference(spec).ok().map(|r| r.is_some());
+    }
+    // A failed parse may be syntax gix lacks; only trust successes.
+    repo.rev_parse_single(spec).ok().map(|_| true)
+}
+
+/// `git rev-parse HEAD`.
+pub fn head_sha(dir: &Path) -> Option<String> {
+    Some(open(dir)?.head_id().ok()?.to_string())
+}
+
+/// `git rev-parse --abbrev-ref HEAD`: the branch, or "HEAD" when detached.
+pub fn branch_current(dir: &Path) -> Option<String> {
+    let repo = open(dir)?;
+    match repo.head().ok()?.kind {
+        gix::head::Kind::Symbolic(r) => {
+            let short = r.name.shorten().to_str().ok()?.to_string();
+            // git prefixes names that are ambiguous with another ref.
+            let ambiguous = [
+                format!("refs/{}", short),
+                format!("refs/tags/{}", short),
+                format!("refs/remotes/{}", short),
+                format!("refs/remotes/{}/HEAD", short),
+            ]
+            .iter()
+            .any(|name| !matches!(repo.try_find_reference(name.as_str()), Ok(None)));
+            (!ambiguous).then_some(short)
+        }
+        gix::head::Kind::Detached { .. } => Some("HEAD".to_string()),
+        gix::head::Kind::Unborn(_) => None,
+    }
+}
+
+/// `git::resolve_upstream_ref`: the tracking branch if it exists, else
+/// origin's default branch, else HEAD's branch.
+pub fn upstream_ref(dir: &Path) -> Option<UpstreamRef> {
+    let repo = open(dir)?;
+    let head = repo.head().ok()?;
+    if let gix::head::Kind::Unborn(_) = head.kind {
+        return None;
+    }
+    if let Some(name) = repo.head_name().ok()? {
+        let tracking =
+            repo.branch_remote_tracking_ref_name(name.as_ref(), gix::remote::Direction::Fetch);
+        if let Some(tracking) = tracking.transpose().ok()?
+            && repo.try_find_reference(tracking.as_ref()).ok()?.is_some()
+        {
+            return Some(UpstreamRef::Tracking);
+        }
+    }
+    let target = |name: &str| -> Option<Option<String>> {
+        let Some(r) = repo.try_find_reference(name).ok()? else {
+            return Some(None);
+        };
+        Some(match r.target() {
+            gix::refs::TargetRef::Symbolic(t) => Some(t.as_bstr().to_str().ok()?.to_string()),
+            gix::refs::TargetRef::Object(_) => None,
+        })
+    };
+    let symbolic = match target("refs/remotes/origin/HEAD")? {
+        Some(t) => Some(t),
+        None => repo
+            .head_name()
+            .ok()?
+            .map(|n| n.as_bstr().to_str().map(String::from))
+            .transpose()
+            .ok()?,
+    };
+    // Same parsing as `git::default_branch`.
+    Some(match symbolic {
+        Some(full) if full.split('/').count() >= 3 => {
+            UpstreamRef::DefaultBranch(full.rsplit('/').next()?.to_string())
+        }
+        Some(_) => return None,
+        None => UpstreamRef::Head,
+    })
+}
+
+/// `git rev-list --count <from>..<to>`.
+pub fn commit_count(dir: &Path, from: &str, to: &str) -> Option<u32> {
+    let repo = open(dir)?;
+    let hidden = repo.rev_parse_single(from).ok()?.detach();
+    let tip = repo.rev_parse_single(to).ok()?.detach();
+    let walk = repo.rev_walk([tip]).with_hidden([hidden]).all().ok()?;
+    let mut count = 0;
+    for info in walk {
+        info.ok()?;
+        count += 1;
+    }
+    Some(count)
+}
+
+/// `git stash list`'s length.
+pub fn stash_count(dir: &Path) -> Option<u32> {
+    let repo = open(dir)?;
+    let Some(stash) = repo.try_find_reference("refs/stash").ok()? else {
+        return Some(0);
+    };
+    let mut log = stash.log_iter();
+    let mut count = 0;
+    for line in log.all().ok()?? {
+        line.ok()?;
+        count += 1;
+    }
+    Some(count)
+}
+
+/// A commit as `git log --format=%H%x00%ct%x00%s` prints it.
+pub struct LogEntry {
+    pub hash: String,
+    pub timestamp: i64,
+    pub subject: String,
+}
+
+/// `git log` for `<from>..<to>` or `-n <count> <tip>`, newest first.
+pub fn log(dir: &Path, revs: &[&str]) -> Option<Vec<LogEntry>> {
+    let (hidden, tip, limit) = match revs {
+        [range] => {
+            let (from, to) = range.split_once("..")?;
+            if to.starts_with('.') {
+                return None;
+            }
+            (Some(from), to, usize::MAX)
+        }
+        ["-n", n, tip] => (None, *tip, n.parse().ok()?),
+        _ => return None,
+    };
+    let repo = open(dir)?;
+    let tip = repo.rev_parse_single(tip).ok()?.detach();
+    let mut walk = repo
+        .rev_walk([tip])
+        .sorting(gix::revision::walk::Sorting::ByCommitTime(
+            Default::default(),
+        ));
+    if let Some(from) = hidden {
+        walk = walk.with_hidden([repo.rev_parse_single(from).ok()?.detach()]);
+    }
+    let mut entries = Vec::new();
+    for info in walk.all().ok()?.take(limit) {
+        let commit = info.ok()?.object().ok()?;
+        entries.push(LogEntry {
+            hash: commit.id.to_string(),
+            timestamp: commit.committer().ok()?.seconds(),
+            subject: commit.message().ok()?.summary().to_str().ok()?.to_string(),
+        });
+    }
+    Some(entries)
+}
+
+/// `git status --short`'s lines: tracked changes by path, then untracked
+/// paths, untracked directories collapsed to `dir/`.
+pub fn status_short(dir: &Path) -> Option<Vec<String>> {
+    let repo = open(dir)?;
+    let iter = repo
+        .status(gix::progress::Discard)
+        .ok()?
+        .untracked_files(gix::status::UntrackedFiles::Collapsed)
+        .index_worktree_rewrites(None)
+        .into_iter(None)
+        .ok()?;
+
+    // path → (index code, worktree code, rename source)
+    let mut tracked: BTreeMap<String, (char, char, Option<String>)> = BTreeMap::new();
+    let mut untracked = Vec::new();
+    for item in iter {
+        match item.ok()? {
+            gix::status::Item::TreeIndex(change) => {
+                use gix::diff::index::ChangeRef;
+                let (path, code, from, mode) = match &change {
+                    ChangeRef::Addition {
+                        location,
+                        entry_mode,
+                        ..
+                    } => (location, 'A', None, entry_mode),
+                    ChangeRef::Deletion {
+                        location,
+                        entry_mode,
+                        ..
+                    } => (location, 'D', None, entry_mode),
+                    ChangeRef::Modification {
+                        location,
+                        previous_entry_mode,
+                        entry_mode,
+                        ..
+                    } => {
+                        let code = if (*previous_entry_mode == Mode::SYMLINK)
+                            != (*entry_mode == Mode::SYMLINK)
+                        {
+                            'T'
+                        } else {
+                            'M'
+                        };
+                        (location, code, None, entry_mode)
+                    }
+                    ChangeRef::Rewrite {
+                        source_location,
+                        location,
+                        entry_mode,
+                        copy,
+                        ..
+                    } => (
+                        location,
+                        if *copy { 'C' } else { 'R' },
+                        Some(source_location),
+                        entry_mode,
+                    ),
+                };
+                if *mode == Mode::COMMIT {
+                    return None;
+                }
+                let from = match from {
+                    Some(p) => Some(plain_path(p.as_ref())?),
+                    None => None,
+                };
+                let entry = tracked
+                    .entry(plain_path(path.as_ref())?)
+                    .or_insert((' ', ' ', None));
+                entry.0 = code;
+                entry.2 = from;
+            }
+            gix::status::Item::IndexWorktree(item) => match item {
+                WorktreeItem::Modification {
+                    entry,
+                    rela_path,
+                    status,
+                    ..
+                } => {
+                    if entry.mode == Mode::COMMIT {
+                        return None;
+                    }
+                    let (x, y) = match status {
+                        EntryStatus::NeedsUpdate(_) => continue,
+                        EntryStatus::IntentToAdd => (' ', 'A'),
+                        EntryStatus::Change(Change::Removed) => (' ', 'D'),
+                        EntryStatus::Change(Change::Type { .. }) => (' ', 'T'),
+                        EntryStatus::Change(_) => (' ', 'M'),
+                        EntryStatus::Conflict { summary, .. } => match summary {
+                            Conflict::BothDeleted => ('D', 'D'),
+                            Conflict::AddedByUs => ('A', 'U'),
+                            Conflict::DeletedByThem => ('U', 'D'),
+                            Conflict::AddedByThem => ('U', 'A'),
+                            Conflict::DeletedByUs => ('D', 'U'),
+                            Conflict::BothAdded => ('A', 'A'),
+                            Conflict::BothModified => ('U', 'U'),
+                        },
+                    };
+                    let conflict = x != ' ';
+                    let entry = tracked
+                        .entry(plain_path(rela_path.as_ref())?)
+                        .or_insert((' ', ' ', None));
+                    if conflict {
+                        entry.0 = x;
+                    }
+                    entry.1 = y;
+                }
+                WorktreeItem::DirectoryContents { entry, .. } => {
+                    if entry.status != gix::dir::entry::Status::Untracked {
+                        continue;
+                    }
+                    let mut path = plain_path(entry.rela_path.as_ref())?;
+                    match entry.disk_kind {
+                        Some(gix::dir::entry::Kind::Directory) => path.push('/'),
+                        Some(gix::dir::entry::Kind::Repository) => return None,
+                        _ => {}
+                    }
+                    untracked.push(format!("?? {}", path));
+                }
+                WorktreeItem::Rewrite { .. } => return None,
+            },
+        }
+    }
+
+    // A conflicted path is listed once, by its conflict code.
+    let mut lines: Vec<String> = tracked
+        .into_iter()
+        .filter(|(_, (x, y, _))| (*x, *y) != (' ', ' '))
+        .map(|(path, (x, y, from))| match from {
+            Some(from) => format!("{}{} {} -> {}", x, y, from, path),
+            None => format!("{}{} {}", x, y, path),
+        })
+        .collect();
+    untracked.sort();
+    lines.extend(untracked);
+    Some(lines)
+}
+
+/// A path git prints as-is; it quotes ones with spaces, quotes,
+/// backslashes, control characters or (with `core.quotePath`) non-ASCII.
+fn plain_path(path: &gix::bstr::BStr) -> Option<String> {
+    let s = path.to_str().ok()?;
+    s.chars()
+        .all(|c| c.is_ascii_graphic() && c != '"' && c != '\\')
+        .then(|| s.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git;
+    use crate::testutil::{local_commit, setup_clone_repo};
+
+    fn git_run(dir: &Path, args: &[&str]) {
+        git::run(Some(dir), args).unwrap();
+    }
+
+    /// The gix answers match what git prints for the same repo.
+    fn assert_matches_git(dir: &Path) {
+        let out = std::process::Command::new("git")
+            .args(["status", "--short"])
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        let want: Vec<String> = String::from_utf8(out.stdout)
+            .unwrap()
+            .lines()
+            .filter(|l| !l.is_empty())
+            .map(String::from)
+            .collect();
+        assert_eq!(status_short(dir).unwrap(), want);
+        assert_eq!(
+            branch_current(dir),
+            git::run(Some(dir), &["rev-parse", "--abbrev-ref", "HEAD"]).ok()
+        );
+        assert_eq!(
+            head_sha(dir),
+            git::run(Some(dir), &["rev-parse", "HEAD"]).ok()
+        );
+    }
+
+    #[test]
+    fn test_status_short_matches_git() {
+        let (clone, _source, _clone_tmp, _source_tmp) = setup_clone_repo();
+        local_commit(&clone, "README.md", "hello\n");
+        assert_matches_git(&clone);
+
+        std::fs::write(clone.join("README.md"), "changed\n").unwrap();
+        std::fs::write(clone.join("new.txt"), "new\n").unwrap();
+        std::fs::create_dir_all(clone.join("scratch/deep")).unwrap();
+        std::fs::write(clone.join("scratch/deep/a.txt"), "a\n").unwrap();
+        assert_matches_git(&clone);
+
+        std::fs::write(clone.join("staged.txt"), "staged\n").unwrap();
+        git_run(&clone, &["add", "staged.txt", "README.md"]);
+        std::fs::write(clone.join("README.md"), "changed again\n").unwrap();
+        assert_matches_git(&clone);
+
+        git_run(&clone, &["commit", "-qm", "wip"]);
+        git_run(&clone, &["mv", "staged.txt", "moved.txt"]);
+        assert_matches_git(&clone);
+
+        git_run(&clone, &["rm", "-q", "--cached", "moved.txt"]);
+        assert_matches_git(&clone);
+
+        git_run(&clone, &["checkout", "-q", "--detach"]);
+        assert_matches_git(&clone);
+    }
+
+    #[test]
+    fn test_refs_and_counts_match_git() {
+        let (clone, _source, _clone_tmp, _source_tmp) = setup_clone_repo();
+        for i in 0..3 {
+            local_commit(&clone, &format!("f{}.txt", i), "x\n");
+        }
+
+        for spec in [
+            "refs/heads/feature",
+            "refs/heads/nope",
+            "refs/remotes/origin/main",
+            "origin/main",
+            "HEAD~1",
+        ] {
+            assert_eq!(
+                rev_exists(&clone, spec).unwrap_or(false),
+                git::run(Some(&clone), &["rev-parse", "--verify", spec]).is_ok(),
+                "{}",
+                spec
+            );
+        }
+        assert_eq!(commit_count(&clone, "origin/main", "HEAD"), Some(3));
+        assert_eq!(commit_count(&clone, "HEAD", "origin/main"), Some(0));
+        assert_eq!(stash_count(&clone), Some(0));
+
+        assert!(matches!(
+            upstream_ref(&clone),
+            Some(UpstreamRef::DefaultBranch(b)) if b == "main"
+        ));
+        git_run(&clone, &["branch", "-q", "--set-upstream-to=origin/main"]);
+        assert!(matches!(upstream_ref(&clone), Some(UpstreamRef::Tracking)));
+
+        let commits = log(&clone, &["origin/main..HEAD"]).unwrap();
+        let subjects: Vec<&str> = commits.iter().map(|c| c.subject.as_str()).collect();
+        let want = git::run(Some(&clone), &["log", "--format=%s", "origin/main..HEAD"]).unwrap();
+        assert_eq!(subjects, want.lines().collect::<Vec<_>>());
+        assert_eq!(subjects.len(), 3);
+        assert_eq!(log(&clone, &["-n", "1", "HEAD"]).unwrap().len(), 1);
+        assert!(log(&clone, &["--all"]).is_none());
+
+        std::fs::write(clone.join("f0.txt"), "stashed\n").unwrap();
+        git_run(&clone, &["stash", "-q"]);
+        assert_eq!(stash_count(&clone), Some(1));
+    }
+}
@@ -1,7 +1,10 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use anyhow::{Result, bail};
 
+use crate::error::{self, ErrorKind};
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Parsed {
     pub host: String,
@@ -116,71 +119,84 @@ fn parse_https(raw: &str) -> Result<Parsed> {
     Ok(parsed)
 }
 
-/// Computes the shortest unique suffix for each identity.
-pub fn shortnames(identities: &[String]) -> std::collections::HashMap<String, String> {
-    let mut result = std::collections::HashMap::new();
-
-    // Pre-split all identities once to avoid repeated allocations
-    let split: Vec<Vec<&str>> = identities
-        .iter()
-        .map(|id| id.split('/').collect())
-        .collect();
+/// Identities keyed by every `/`-separated suffix (`repo`, `user/repo`,
+/// `host/user/repo`), so resolving names and finding shortnames cost one
+/// lookup each instead of a pass over the whole registry. Build one when
+/// resolving many names against the same identities.
+pub struct Index<'a> {
+    identities: &'a [String],
+    by_suffix: HashMap<&'a str, Vec<usize>>,
+}
 
-    for (idx, parts) in split.iter().enumerate() {
-        let mut found = false;
-        // Try progressively longer suffixes starting from just the repo name
-        for depth in 1..=parts.len() {
-            let candidate = &parts[parts.len() - depth..];
-            let unique = split.iter().enumerate().all(|(j, other)| {
-                j == idx || other.len() < depth || other[other.len() - depth..] != *candidate
-            });
-            if unique {
-                result.insert(identities[idx].clone(), candidate.join("/"));
-                found = true;
-                break;
+impl<'a> Index<'a> {
+    pub fn new(identities: &'a [String]) -> Self {
+        let mut by_suffix: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, id) in identities.iter().enumerate() {
+            by_suffix.entry(id.as_str()).or_default().push(i);
+            for (pos, _) in id.match_indices('/') {
+                by_suffix.entry(&id[pos + 1..]).or_default().push(i);
             }
         }
-        if !found {
-            result.insert(identities[idx].clone(), identities[idx].clone());
+        Index {
+            identities,
+            by_suffix,
         }
     }
 
-    result
-}
-
-/// Resolves a shortname/partial name to a full identity.
-pub fn resolve(name: &str, identities: &[String]) -> Result<String> {
-    // Exact match first
-    for id in identities {
-        if id == name {
-            return Ok(id.clone());
+    /// Resolves a shortname/partial name to a full identity.
+    pub fn resolve(&self, name: &str) -> Result<String> {
+        let matches = self.by_suffix.get(name).map_or(&[][..], |m| m.as_slice());
+        // Exact match first
+        if let Some(&i) = matches.iter().find(|&&i| self.identities[i] == name) {
+            return Ok(self.identities[i].clone());
         }
-    }
-
-    // Suffix match
-    let mut matches = Vec::new();
-    for id in identities {
-        let parts: Vec<&str> = id.split('/').collect();
-        for i in (0..parts.len()).rev() {
-            let suffix = parts[i..].join("/");
-            if suffix == name {
-                matches.push(id.clone());
-                break;
-            }
+        match matches {
+            [] => Err(error::tag(
+                ErrorKind::NotFound,
+                anyhow::anyhow!("repo {:?} not found", name),
+            )),
+            [i] => Ok(self.identities[*i].clone()),
+            _ => bail!(
+                "repo {:?} is ambiguous, matches: {}",
+                name,
+                matches
+                    .iter()
+                    .map(|&i| self.identities[i].as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
         }
     }
 
-    match matches.len() {
-        0 => bail!("repo {:?} not found", name),
-        1 => Ok(matches.into_iter().next().unwrap()),
-        _ => bail!(
-            "repo {:?} is ambiguous, matches: {}",
-            name,
-            matches.join(", ")
-        ),
+    /// The shortest suffix of each identity that no other identity shares.
+    pub fn shortnames(&self) -> HashMap<String, String> {
+        self.identities
+            .iter()
+            .map(|id| {
+                let suffixes = id
+                    .rmatch_indices('/')
+                    .map(|(pos, _)| &id[pos + 1..])
+                    .chain([id.as_str()]);
+                let short = suffixes
+                    .into_iter()
+                    .find(|s| self.by_suffix.get(s).is_some_and(|m| m.len() == 1))
+                    .unwrap_or(id);
+                (id.clone(), short.to_string())
+            })
+            .collect()
     }
 }
 
+/// Computes the shortest unique suffix for each identity.
+pub fn shortnames(identities: &[String]) -> HashMap<String, String> {
+    Index::new(identities).shortnames()
+}
+
+/// Resolves a shortname/partial name to a full identity.
+pub fn resolve(name: &str, identities: &[String]) -> Result<String> {
+    Index::new(identities).resolve(name)
+}
+
 /// Strips any trailing `@ref` from a repo argument.
 /// The `@ref` syntax for context repos has been removed — if a ref is
 /// present it is silently ignored (the repo will be treated as active).
@@ -0,0 +1,356 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::workspace::Metadata;
+
+/// Lifecycle points where wsp runs a configured hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// After `wsp new` has cloned every repo.
+    PostCreate,
+    /// Before `wsp rm` removes the workspace. An aborting failure keeps it.
+    PreRemove,
+    /// After `wsp repo add` has cloned the new repos.
+    PostAdd,
+    /// After `wsp sync` has updated the repos.
+    PostSync,
+}
+
+pub const EVENTS: &[Event] = &[
+    Event::PostCreate,
+    Event::PreRemove,
+    Event::PostAdd,
+    Event::PostSync,
+];
+
+impl Event {
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::PostCreate => "post-create",
+            Event::PreRemove => "pre-remove",
+            Event::PostAdd => "post-add",
+            Event::PostSync => "post-sync",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Event> {
+        EVENTS.iter().copied().find(|e| e.name() == name)
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Default time a hook may run before it is killed.
+pub const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+/// What a failing hook (non-zero exit, timeout, or spawn error) does to the
+/// command that triggered it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnFailure {
+    /// Print a warning and carry on.
+    #[default]
+    Warn,
+    /// Fail the command. For pre-remove the workspace is kept.
+    Abort,
+}
+
+/// A shell command run at a lifecycle event, keyed by event name under
+/// `hooks` in config.yaml. Only there: templates and `.wsp.yaml` files get
+/// shared, and a hook runs without asking first.
+///
+/// ```yaml
+/// hooks:
+///   post-create: ./scripts/bootstrap-secrets.sh
+///   pre-remove:
+///     run: dev-dns unregister "$WSP_WORKSPACE"
+///     timeout: 30          # seconds, default 300
+///     on_failure: abort    # warn (default) or abort
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "HookDefRepr", into = "HookDefRepr")]
+pub struct HookDef {
+    pub run: String,
+    pub timeout: Option<u64>,
+    pub on_failure: OnFailure,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum HookDefRepr {
+    Short(String),
+    Full {
+        run: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timeout: Option<u64>,
+        #[serde(default, skip_serializing_if = "is_default")]
+        on_failure: OnFailure,
+    },
+}
+
+fn is_default(v: &OnFailure) -> bool {
+    *v == OnFailure::default()
+}
+
+impl From<HookDefRepr> for HookDef {
+    fn from(r: HookDefRepr) -> Self {
+        match r {
+            HookDefRepr::Short(run) => HookDef {
+                run,
+                ..Default::default()
+            },
+            HookDefRepr::Full {
+                run,
+                timeout,
+                on_failure,
+            } => HookDef {
+                run,
+                timeout,
+                on_failure,
+            },
+        }
+    }
+}
+
+impl From<HookDef> for HookDefRepr {
+    fn from(h: HookDef) -> Self {
+        if h.timeout.is_none() && h.on_failure == OnFailure::default() {
+            HookDefRepr::Short(h.run)
+        } else {
+            HookDefRepr::Full {
+                run: h.run,
+                timeout: h.timeout,
+                on_failure: h.on_failure,
+            }
+        }
+    }
+}
+
+/// Hooks keyed by event name.
+pub type Hooks = BTreeMap<String, HookDef>;
+
+/// Returns the event part of a `hook.<event>` key, or None if `key` is not a
+/// hook key. Expects a normalized key.
+pub fn key_event(key: &str) -> Option<&str> {
+    let (prefix, event) = key.split_once('.')?;
+    (prefix == "hook" || prefix == "hooks").then_some(event)
+}
+
+/// Validate the event name of a `hook.<event>` key.
+pub fn validate_event(name: &str) -> Result<()> {
+    if Event::from_name(name).is_none() {
+        let names: Vec<&str> = EVENTS.iter().map(|e| e.name()).collect();
+        bail!("unknown hook {:?}; valid hooks: {}", name, names.join(", "));
+    }
+    Ok(())
+}
+
+/// Run the hook configured for `event`, if any, in the workspace root.
+///
+/// The hook gets the `wsp shell` environment (`WSP_WORKSPACE`, `WSP_DIR`,
+/// `WSP_REPO_<NAME>`) plus `WSP_HOOK` (the event name) and `WSP_HOOK_REPOS`
+/// (space-separated identities the event is about: the added repos for
+/// post-add, every repo otherwise). Its stdout goes to stderr so `--json`
+/// output stays clean. Failures follow the hook's `on_failure` policy.
+pub fn run(
+    event: Event,
+    hooks: Option<&Hooks>,
+    ws_dir: &Path,
+    meta: &Metadata,
+    repos: &[String],
+) -> Result<()> {
+    let Some(hook) = hooks.and_then(|h| h.get(event.name())) else {
+        return Ok(());
+    };
+    if hook.run.trim().is_empty() {
+        return Ok(());
+    }
+
+    eprintln!("Running {} hook: {}", event, hook.run);
+    let timeout = Duration::from_secs(hook.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS));
+    let result = execute(event, hook, ws_dir, meta, repos, timeout);
+    match (result, hook.on_failure) {
+        (Ok(()), _) => Ok(()),
+        (Err(e), OnFailure::Warn) => {
+            tracing::warn!("{} hook failed: {}", event, e);
+            Ok(())
+        }
+        (Err(e), OnFailure::Abort) => bail!("{} hook failed: {}", event, e),
+    }
+}
+
+fn execute(
+    event: Event,
+    hook: &HookDef,
+    ws_dir: &Path,
+    meta: &Metadata,
+    repos: &[String],
+    timeout: Duration,
+) -> Result<()> {
+    let argv = crate::task::shell_argv(&hook.run);
+    let mut child = Command::new(&argv[0])
+        .args(&argv[1..])
+        .current_dir(ws_dir)
+        .envs(crate::cli::shell::workspace_env(ws_dir, meta)?)
+        .env("WSP_HOOK", event.name())
+        .env("WSP_HOOK_REPOS", repos.join(" "))
+        .stdin(Stdio::null())
+        .stdout(Stdio::from(std::io::stderr()))
+        .stderr(Stdio::inherit())
+        .spawn()?;
+
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            if status.success() {
+                return Ok(());
+            }
+            match status.code() {
+                Some(code) => bail!("exit status {}", code),
+                None => bail!("terminated by signal"),
+            }
+        }
+        if start.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("timed out after {}s", timeout.as_secs());
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta() -> Metadata {
+        let mut repos = BTreeMap::new();
+        repos.insert("github.com/acme/api".to_string(), None);
+        Metadata {
+            version: 0,
+            name: "fix".into(),
+            branch: "fix".into(),
+            repos,
+            created: chrono::Utc::now(),
+            description: None,
+            last_used: None,
+            created_from: None,
+            dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
+            config: None,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    fn hooks(event: Event, def: HookDef) -> Hooks {
+        BTreeMap::from([(event.name().to_string(), def)])
+    }
+
+    #[test]
+    fn test_hook_def_yaml_forms() {
+        let cases = vec![
+            ("short form", "./bootstrap.sh\n", None, OnFailure::Warn),
+            (
+                "full form",
+                "run: ./bootstrap.sh\ntimeout: 30\non_failure: abort\n",
+                Some(30),
+                OnFailure::Abort,
+            ),
+        ];
+        for (name, yaml, want_timeout, want_policy) in cases {
+            let def: HookDef = serde_yaml_ng::from_str(yaml).unwrap();
+            assert_eq!(def.run, "./bootstrap.sh", "{}", name);
+            assert_eq!(def.timeout, want_timeout, "{}", name);
+            assert_eq!(def.on_failure, want_policy, "{}", name);
+            // Round-trips to the same form
+            assert_eq!(serde_yaml_ng::to_string(&def).unwrap(), yaml, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_key_event() {
+        let cases = vec![
+            ("hook.post-create", Some("post-create")),
+            ("hooks.pre-remove", Some("pre-remove")),
+            ("task.test", None),
+            ("hook", None),
+        ];
+        for (key, want) in cases {
+            assert_eq!(key_event(key), want, "{}", key);
+        }
+        assert!(validate_event("post-sync").is_ok());
+        assert!(validate_event("post-push").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_env_and_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta = meta();
+        let repos = vec!["github.com/acme/api".to_string()];
+
+        let def = HookDef {
+            run: "echo \"$WSP_HOOK $WSP_WORKSPACE $WSP_HOOK_REPOS $WSP_REPO_API\" > out.txt".into(),
+            ..Default::default()
+        };
+        run(
+            Event::PostAdd,
+            Some(&hooks(Event::PostAdd, def)),
+            dir.path(),
+            &meta,
+            &repos,
+        )
+        .unwrap();
+        let out = std::fs::read_to_string(dir.path().join("out.txt")).unwrap();
+        assert_eq!(
+            out.trim(),
+            format!(
+                "post-add fix github.com/acme/api {}",
+                dir.path().join("api").display()
+            )
+        );
+
+        // Other events don't trigger it
+        run(Event::PostSync, None, dir.path(), &meta, &repos).unwrap();
+
+        let failing = |on_failure, timeout| HookDef {
+            run: "sleep 2; exit 3".into(),
+            timeout,
+            on_failure,
+        };
+        let cases = vec![
+            ("warn swallows failure", OnFailure::Warn, Some(0), true),
+            ("abort on timeout", OnFailure::Abort, Some(0), false),
+        ];
+        for (name, policy, timeout, want_ok) in cases {
+            let h = hooks(Event::PreRemove, failing(policy, timeout));
+            let got = run(Event::PreRemove, Some(&h), dir.path(), &meta, &repos);
+            assert_eq!(got.is_ok(), want_ok, "{}", name);
+        }
+
+        let h = hooks(
+            Event::PreRemove,
+            HookDef {
+                run: "exit 3".into(),
+                timeout: None,
+                on_failure: OnFailure::Abort,
+            },
+        );
+        let err = run(Event::PreRemove, Some(&h), dir.path(), &meta, &repos)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(err, "pre-remove hook failed: exit status 3");
+    }
+}
@@ -0,0 +1,392 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{Result, bail};
+
+use crate::forge::Forge;
+
+/// Workspace name template used when `issue.name-template` is unset.
+pub const DEFAULT_NAME_TEMPLATE: &str = "{key}-{title}";
+
+/// Longest workspace name generated from an issue; titles are cut to fit.
+const MAX_NAME_LEN: usize = 60;
+
+/// An issue named on the command line: a GitHub issue URL, or a Jira issue
+/// URL or key (`ACME-123`).
+#[derive(Debug, PartialEq)]
+pub enum IssueRef {
+    Github {
+        url: String,
+        host: String,
+        number: u64,
+    },
+    Jira {
+        /// Site URL, e.g. `https://acme.atlassian.net`; `None` for a bare key.
+        base: Option<String>,
+        key: String,
+    },
+}
+
+/// An issue as fetched from its tracker.
+pub struct Issue {
+    pub key: String,
+    pub title: String,
+    pub url: String,
+}
+
+pub fn parse(raw: &str) -> Result<IssueRef> {
+    let raw = raw.trim();
+    if is_jira_key(raw) {
+        return Ok(IssueRef::Jira {
+            base: None,
+            key: raw.to_string(),
+        });
+    }
+    let u: url::Url = raw.parse().map_err(|_| {
+        anyhow::anyhow!(
+            "unrecognized issue {:?}: expected a GitHub issue URL, a Jira issue URL, or a \
+             Jira key like ACME-123",
+            raw
+        )
+    })?;
+    let segments: Vec<&str> = u.path_segments().into_iter().flatten().collect();
+    if let Some(i) = segments.iter().position(|s| *s == "browse")
+        && let Some(key) = segments.get(i + 1).filter(|k| is_jira_key(k))
+    {
+        let mut base = u.clone();
+        base.set_path(&segments[..i].join("/"));
+        base.set_query(None);
+        base.set_fragment(None);
+        return Ok(IssueRef::Jira {
+            base: Some(base.to_string().trim_end_matches('/').to_string()),
+            key: key.to_string(),
+        });
+    }
+    if segments.len() >= 4
+        && segments[2] == "issues"
+        && let Ok(number) = segments[3].parse::<u64>()
+    {
+        return Ok(IssueRef::Github {
+            url: format!(
+                "{}://{}/{}/{}/issues/{}",
+                u.scheme(),
+                u.host_str().unwrap_or_default(),
+                segments[0],
+                segments[1],
+                number
+            ),
+            host: u.host_str().unwrap_or_default().to_string(),
+            number,
+        });
+    }
+    bail!(
+        "unrecognized issue URL {}: expected https://<host>/<owner>/<repo>/issues/<n> or \
+         https://<jira>/browse/<KEY-n>",
+        raw
+    );
+}
+
+impl IssueRef {
+    /// What `.wsp.yaml` records: the issue's URL, or the bare key when the
+    /// Jira site is unknown.
+    pub fn canonical(&self, jira_url: Option<&str>) -> String {
+        match self {
+            IssueRef::Github { url, .. } => url.clone(),
+            IssueRef::Jira { base, key } => {
+                match base
+                    .as_deref()
+                    .or(jira_url.map(|u| u.trim_end_matches('/')))
+                {
+                    Some(base) => format!("{}/browse/{}", base, key),
+                    None => key.clone(),
+                }
+            }
+        }
+    }
+}
+
+/// Look up the issue's title. GitHub goes through `gh`; Jira through its
+/// REST API with curl, authenticated by `JIRA_API_TOKEN` (with `JIRA_USER`
+/// for Jira Cloud's basic auth, else as a bearer token).
+pub fn fetch(issue: &IssueRef, jira_url: Option<&str>) -> Result<Issue> {
+    crate::offline::ensure_online("looking up the issue")?;
+    let url = issue.canonical(jira_url);
+    match issue {
+        IssueRef::Github { host, number, .. } => {
+            let forge = Forge::Github;
+            let output = forge
+                .command(host)
+                .args(["issue", "view", &url, "--json", "title", "--jq", ".title"])
+                .output()
+                .map_err(|e| anyhow::anyhow!("failed to run gh: {} (is gh installed?)", e))?;
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+                bail!("gh failed: {}", stderr);
+            }
+            Ok(Issue {
+                key: number.to_string(),
+                title: String::from_utf8_lossy(&output.stdout).trim().to_string(),
+                url,
+            })
+        }
+        IssueRef::Jira { base, key } => {
+            let Some(base) = base.as_deref().or(jira_url) else {
+                bail!(
+                    "jira.url is not set; set it to look up Jira keys \
+                     (wsp config set jira.url https://acme.atlassian.net)"
+                );
+            };
+            let api = format!(
+                "{}/rest/api/2/issue/{}?fields=summary",
+                base.trim_end_matches('/'),
+                key
+            );
+            let body = curl_json(&api)?;
+            let title = body["fields"]["summary"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Jira returned no summary for {}", key))?;
+            Ok(Issue {
+                key: key.clone(),
+                title: title.to_string(),
+                url,
+            })
+        }
+    }
+}
+
+/// GET `url` with curl. Credentials go through curl's config on stdin so
+/// they never appear in the process list.
+fn curl_json(url: &str) -> Result<serde_json::Value> {
+    let mut config = String::new();
+    if let Ok(token) = std::env::var("JIRA_API_TOKEN") {
+        match std::env::var("JIRA_USER") {
+            Ok(user) => config.push_str(&format!(
+                "user = \"{}\"\n",
+                curl_quote(&format!("{}:{}", user, token))
+            )),
+            Err(_) => config.push_str(&format!(
+                "header = \"{}\"\n",
+                curl_quote(&format!("Authorization: Bearer {}", token))
+            )),
+        }
+    }
+    let mut child = Command::new("curl")
+        .args([
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--location",
+            "--header",
+            "Accept: application/json",
+            "--config",
+            "-",
+            url,
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("failed to run curl: {} (is curl installed?)", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(config.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        bail!("fetching {}: {}", url, stderr);
+    }
+    serde_json::from_slice(&output.stdout)
+        .map_err(|e| anyhow::anyhow!("parsing response from {}: {}", url, e))
+}
+
+fn curl_quote(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// `ACME-123`: a project key (uppercase letter, then uppercase letters,
+/// digits, or underscores), a dash, and a number.
+fn is_jira_key(s: &str) -> bool {
+    let Some((project, number)) = s.rsplit_once('-') else {
+        return false;
+    };
+    let mut chars = project.chars();
+    chars.next().is_some_and(|c| c.is_ascii_uppercase())
+        && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+        && !number.is_empty()
+        && number.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Short label for a recorded issue: the Jira key, `owner/repo#n` for a
+/// GitHub issue, else the value as recorded.
+pub fn key(stored: &str) -> String {
+    match parse(stored) {
+        Ok(IssueRef::Jira { key, .. }) => key,
+        Ok(IssueRef::Github { url, number, .. }) => {
+            let path = url.splitn(4, '/').nth(3).unwrap_or_default();
+            let repo = path.split("/issues/").next().unwrap_or_default();
+            format!("{}#{}", repo, number)
+        }
+        Err(_) => stored.to_string(),
+    }
+}
+
+/// Render a workspace name from `template`, replacing `{key}` with the
+/// issue key and `{title}` with the title, lowercased and dashed.
+/// Characters not allowed in workspace names become dashes, and the result
+/// is cut to a readable length.
+pub fn workspace_name(template: &str, key: &str, title: &str) -> Result<String> {
+    let rendered = template
+        .replace("{key}", key)
+        .replace("{title}", &title.to_lowercase());
+    let mut name = String::new();
+    for c in rendered.chars() {
+        let c = if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+            c
+        } else {
+            '-'
+        };
+        if c == '-' && (name.is_empty() || name.ends_with('-')) {
+            continue;
+        }
+        name.push(c);
+    }
+    name.truncate(MAX_NAME_LEN);
+    let name = name.trim_matches(['-', '.']).to_string();
+    crate::workspace::validate_name(&name)
+        .map_err(|e| anyhow::anyhow!("issue.name-template {:?}: {}", template, e))?;
+    Ok(name)
+}
+
+/// A template must use at least one placeholder, or every issue would get
+/// the same workspace name.
+pub fn validate_template(template: &str) -> Result<()> {
+    if !template.contains("{key}") && !template.contains("{title}") {
+        bail!("issue.name-template must contain {{key}} or {{title}}");
+    }
+    workspace_name(template, "ACME-1", "example").map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let cases = vec![
+            (
+                "ACME-123",
+                IssueRef::Jira {
+                    base: None,
+                    key: "ACME-123".into(),
+                },
+            ),
+            (
+                "https://acme.atlassian.net/browse/OPS_2-7?focusedCommentId=1",
+                IssueRef::Jira {
+                    base: Some("https://acme.atlassian.net".into()),
+                    key: "OPS_2-7".into(),
+                },
+            ),
+            (
+                "https://jira.acme.com/jira/browse/WEB-9",
+                IssueRef::Jira {
+                    base: Some("https://jira.acme.com/jira".into()),
+                    key: "WEB-9".into(),
+                },
+            ),
+            (
+                "https://github.com/acme/api/issues/12#issuecomment-3",
+                IssueRef::Github {
+                    url: "https://github.com/acme/api/issues/12".into(),
+                    host: "github.com".into(),
+                    number: 12,
+                },
+            ),
+        ];
+        for (raw, want) in cases {
+            assert_eq!(parse(raw).unwrap(), want, "{}", raw);
+        }
+        for raw in [
+            "acme-123",
+            "ACME-",
+            "123",
+            "https://github.com/acme/api/pull/12",
+            "https://acme.atlassian.net/browse/lower-1",
+        ] {
+            assert!(parse(raw).is_err(), "{}", raw);
+        }
+    }
+
+    #[test]
+    fn test_canonical_and_key() {
+        let jira = Some("https://acme.atlassian.net/");
+        let cases = vec![
+            (
+                "ACME-123",
+                jira,
+                "https://acme.atlassian.net/browse/ACME-123",
+                "ACME-123",
+            ),
+            ("ACME-123", None, "ACME-123", "ACME-123"),
+            (
+                "https://jira.acme.com/browse/WEB-9",
+                jira,
+                "https://jira.acme.com/browse/WEB-9",
+                "WEB-9",
+            ),
+            (
+                "https://github.com/acme/api/issues/12",
+                jira,
+                "https://github.com/acme/api/issues/12",
+                "acme/api#12",
+            ),
+        ];
+        for (raw, jira_url, canonical, short) in cases {
+            let stored = parse(raw).unwrap().canonical(jira_url);
+            assert_eq!(stored, canonical, "{}", raw);
+            assert_eq!(key(&stored), short, "{}", raw);
+        }
+        assert_eq!(key("not an issue"), "not an issue");
+    }
+
+    #[test]
+    fn test_workspace_name() {
+        let cases = vec![
+            (
+                DEFAULT_NAME_TEMPLATE,
+                "ACME-123",
+                "Fix login timeout (SSO)",
+                "ACME-123-fix-login-timeout-sso",
+            ),
+            ("{key}", "42", "ignored", "42"),
+            (
+                "gh-{key}_{title}",
+                "42",
+                "Crash: `wsp ls` — ünicode!",
+                "gh-42_crash-wsp-ls-nicode",
+            ),
+            (
+                DEFAULT_NAME_TEMPLATE,
+                "ACME-1",
+                "a very long title that keeps going well past any reasonable length",
+                "ACME-1-a-very-long-title-that-keeps-going-well-past-any-reas",
+            ),
+        ];
+        for (template, key, title, want) in cases {
+            assert_eq!(
+                workspace_name(template, key, title).unwrap(),
+                want,
+                "{}",
+                title
+            );
+        }
+        assert!(workspace_name("{title}", "X-1", "!!!").is_err());
+    }
+
+    #[test]
+    fn test_validate_template() {
+        assert!(validate_template(DEFAULT_NAME_TEMPLATE).is_ok());
+        assert!(validate_template("review/{key}").is_ok());
+        assert!(validate_template("fixed-name").is_err());
+    }
+}
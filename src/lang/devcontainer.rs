@@ -0,0 +1,398 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde_json::{Map, Value, json};
+
+use crate::workspace::Metadata;
+
+use super::LanguageIntegration;
+
+pub(crate) const DEVCONTAINER_HEADER: &str = "// Generated by wsp. DO NOT EDIT.";
+
+/// Image used when no repo's devcontainer names one.
+const DEFAULT_IMAGE: &str = "mcr.microsoft.com/devcontainers/base:ubuntu";
+
+/// Where a repo keeps its devcontainer config, in order of preference.
+const REPO_CONFIGS: &[&str] = &[".devcontainer/devcontainer.json", ".devcontainer.json"];
+
+/// Generates `.devcontainer/devcontainer.json` at the workspace root so the
+/// whole workspace (every repo clone lives under it) opens in one container.
+/// Per-repo devcontainer configs contribute their `features`, `forwardPorts`,
+/// and VS Code extensions; the first repo (by directory name) wins on
+/// conflicting features and on the image.
+pub struct DevcontainerIntegration;
+
+impl LanguageIntegration for DevcontainerIntegration {
+    fn name(&self) -> &str {
+        "devcontainer"
+    }
+
+    fn detect(&self, ws_dir: &Path, metadata: &Metadata) -> bool {
+        !metadata.repos.is_empty() || is_generated(&config_path(ws_dir))
+    }
+
+    fn apply(&self, ws_dir: &Path, metadata: &Metadata) -> Result<()> {
+        let path = config_path(ws_dir);
+        if path.exists() && !is_generated(&path) {
+            bail!(
+                ".devcontainer/devcontainer.json exists and was not generated by wsp; leaving it alone"
+            );
+        }
+        if metadata.repos.is_empty() {
+            if path.exists() {
+                fs::remove_file(&path).with_context(|| format!("removing {}", path.display()))?;
+                let _ = fs::remove_dir(ws_dir.join(".devcontainer"));
+            }
+            return Ok(());
+        }
+
+        let mut repos = Vec::new();
+        for (dir_name, rel) in repo_configs(ws_dir, metadata) {
+            let text = fs::read_to_string(ws_dir.join(&rel))
+                .with_context(|| format!("reading {}", rel))?;
+            let value: Value = serde_json::from_str(&strip_jsonc(&text))
+                .with_context(|| format!("parsing {}", rel))?;
+            repos.push((dir_name, value));
+        }
+
+        let mut out = String::new();
+        out.push_str(DEVCONTAINER_HEADER);
+        out.push('\n');
+        out.push_str(&serde_json::to_string_pretty(&merge(
+            &metadata.name,
+            &repos,
+        ))?);
+        out.push('\n');
+
+        fs::create_dir_all(ws_dir.join(".devcontainer"))?;
+        let tmp_path = ws_dir.join(".devcontainer/.devcontainer.json.tmp");
+        fs::write(&tmp_path, out).with_context(|| format!("writing {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &path)
+            .with_context(|| format!("renaming {} to {}", tmp_path.display(), path.display()))?;
+        Ok(())
+    }
+}
+
+fn config_path(ws_dir: &Path) -> PathBuf {
+    ws_dir.join(".devcontainer").join("devcontainer.json")
+}
+
+pub(crate) fn is_generated(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|c| c.starts_with(DEVCONTAINER_HEADER))
+}
+
+/// (dir_name, workspace-relative config path) for each repo with a
+/// devcontainer config, sorted by directory.
+fn repo_configs(ws_dir: &Path, metadata: &Metadata) -> Vec<(String, String)> {
+    let mut dirs: Vec<String> = metadata
+        .repos
+        .keys()
+        .filter_map(|id| metadata.dir_name(id).ok())
+        .filter(|dn| !dn.contains("..") && !dn.starts_with('/'))
+        .collect();
+    dirs.sort();
+    dirs.into_iter()
+        .filter_map(|dn| {
+            let rel = REPO_CONFIGS
+                .iter()
+                .map(|f| format!("{}/{}", dn, f))
+                .find(|rel| ws_dir.join(rel).is_file())?;
+            Some((dn, rel))
+        })
+        .collect()
+}
+
+/// Build the workspace devcontainer from `(dir_name, config)` pairs.
+fn merge(workspace: &str, repos: &[(String, Value)]) -> Value {
+    let image = repos
+        .iter()
+        .find_map(|(_, v)| v.get("image").and_then(Value::as_str))
+        .unwrap_or(DEFAULT_IMAGE);
+
+    let mut features = Map::new();
+    let mut ports: Vec<Value> = Vec::new();
+    let mut extensions: Vec<Value> = Vec::new();
+    for (_, config) in repos {
+        if let Some(f) = config.get("features").and_then(Value::as_object) {
+            for (k, v) in f {
+                features.entry(k.clone()).or_insert_with(|| v.clone());
+            }
+        }
+        for port in config
+            .get("forwardPorts")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            if !ports.contains(port) {
+                ports.push(port.clone());
+            }
+        }
+        for ext in config
+            .pointer("/customizations/vscode/extensions")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            if !extensions.contains(ext) {
+                extensions.push(ext.clone());
+            }
+        }
+    }
+
+    let folder = format!("/workspaces/{}", workspace);
+    let mut out = json!({
+        "name": workspace,
+        "image": image,
+        "features": features,
+        "workspaceMount": format!(
+            "source=${{localWorkspaceFolder}},target={},type=bind",
+            folder
+        ),
+        "workspaceFolder": folder,
+    });
+    if !ports.is_empty() {
+        out["forwardPorts"] = Value::Array(ports);
+    }
+    if !extensions.is_empty() {
+        out["customizations"] = json!({ "vscode": { "extensions": extensions } });
+    }
+    out
+}
+
+/// Strip `//` and `/* */` comments and trailing commas from JSONC, the
+/// dialect devcontainer.json is written in.
+fn strip_jsonc(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => {
+                    if let Some(next) = chars.next() {
+                        out.push(next);
+                    }
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    remove_trailing_commas(&out)
+}
+
+fn remove_trailing_commas(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            if c == '\\' {
+                out.push(c);
+                i += 1;
+                if let Some(&next) = chars.get(i) {
+                    out.push(next);
+                }
+            } else {
+                if c == '"' {
+                    in_string = false;
+                }
+                out.push(c);
+            }
+        } else if c == '"' {
+            in_string = true;
+            out.push(c);
+        } else if c == ',' {
+            let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+            if !matches!(next, Some('}') | Some(']')) {
+                out.push(c);
+            }
+        } else {
+            out.push(c);
+        }
+        i += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use chrono::Utc;
+
+    fn make_metadata(repos: &[&str]) -> Metadata {
+        let mut map = BTreeMap::new();
+        for id in repos {
+            map.insert(id.to_string(), None);
+        }
+        Metadata {
+            version: 0,
+            name: "test".into(),
+            branch: "test".into(),
+            repos: map,
+            created: Utc::now(),
+            description: None,
+            last_used: None,
+            created_from: None,
+            dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
+            config: None,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_strip_jsonc() {
+        let cases = vec![
+            ("line comment", "{\"a\": 1 // one\n}", "{\"a\": 1 \n}"),
+            ("block comment", "{/* x */\"a\": 1}", "{\"a\": 1}"),
+            (
+                "trailing commas",
+                "{\"a\": [1, 2,],\n}",
+                "{\"a\": [1, 2]\n}",
+            ),
+            (
+                "comment markers in strings",
+                "{\"url\": \"http://x/*y*/\", \"s\": \"a,]\"}",
+                "{\"url\": \"http://x/*y*/\", \"s\": \"a,]\"}",
+            ),
+            (
+                "escaped quote",
+                "{\"q\": \"\\\"//\"}",
+                "{\"q\": \"\\\"//\"}",
+            ),
+        ];
+        for (name, input, want) in cases {
+            assert_eq!(strip_jsonc(input), want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_apply_merges_repo_configs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws_dir = tmp.path();
+        for (path, content) in [
+            (
+                "api/.devcontainer/devcontainer.json",
+                r#"{
+                    // API service
+                    "image": "mcr.microsoft.com/devcontainers/go:1",
+                    "features": {"ghcr.io/devcontainers/features/go:1": {"version": "1.22"}},
+                    "forwardPorts": [8080],
+                }"#,
+            ),
+            (
+                "web/.devcontainer.json",
+                r#"{
+                    "image": "node:20",
+                    "features": {
+                        "ghcr.io/devcontainers/features/go:1": {"version": "1.21"},
+                        "ghcr.io/devcontainers/features/node:1": {}
+                    },
+                    "forwardPorts": [3000, 8080],
+                    "customizations": {"vscode": {"extensions": ["dbaeumer.vscode-eslint"]}}
+                }"#,
+            ),
+        ] {
+            let p = ws_dir.join(path);
+            fs::create_dir_all(p.parent().unwrap()).unwrap();
+            fs::write(p, content).unwrap();
+        }
+        fs::create_dir_all(ws_dir.join("docs")).unwrap();
+        let meta = make_metadata(&[
+            "github.com/acme/api",
+            "github.com/acme/docs",
+            "github.com/acme/web",
+        ]);
+
+        let integration = DevcontainerIntegration;
+        assert!(integration.detect(ws_dir, &meta));
+        integration.apply(ws_dir, &meta).unwrap();
+
+        let text = fs::read_to_string(config_path(ws_dir)).unwrap();
+        assert!(text.starts_with(DEVCONTAINER_HEADER));
+        let got: Value = serde_json::from_str(&strip_jsonc(&text)).unwrap();
+        let want = json!({
+            "name": "test",
+            "image": "mcr.microsoft.com/devcontainers/go:1",
+            "features": {
+                "ghcr.io/devcontainers/features/go:1": {"version": "1.22"},
+                "ghcr.io/devcontainers/features/node:1": {}
+            },
+            "forwardPorts": [8080, 3000],
+            "customizations": {"vscode": {"extensions": ["dbaeumer.vscode-eslint"]}},
+            "workspaceMount": "source=${localWorkspaceFolder},target=/workspaces/test,type=bind",
+            "workspaceFolder": "/workspaces/test"
+        });
+        assert_eq!(got, want);
+    }
+
+    #[test]
+    fn test_apply_defaults_and_cleanup() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws_dir = tmp.path();
+        fs::create_dir_all(ws_dir.join("api")).unwrap();
+        let integration = DevcontainerIntegration;
+
+        integration
+            .apply(ws_dir, &make_metadata(&["github.com/acme/api"]))
+            .unwrap();
+        let text = fs::read_to_string(config_path(ws_dir)).unwrap();
+        let got: Value = serde_json::from_str(&strip_jsonc(&text)).unwrap();
+        assert_eq!(got["image"], DEFAULT_IMAGE);
+        assert_eq!(got["features"], json!({}));
+
+        // Last repo removed: the generated config goes with it
+        let empty = make_metadata(&[]);
+        assert!(integration.detect(ws_dir, &empty));
+        integration.apply(ws_dir, &empty).unwrap();
+        assert!(!ws_dir.join(".devcontainer").exists());
+    }
+
+    #[test]
+    fn test_apply_keeps_user_config() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws_dir = tmp.path();
+        fs::create_dir_all(ws_dir.join(".devcontainer")).unwrap();
+        fs::write(config_path(ws_dir), "{}").unwrap();
+
+        let meta = make_metadata(&["github.com/acme/api"]);
+        assert!(DevcontainerIntegration.apply(ws_dir, &meta).is_err());
+        assert_eq!(fs::read_to_string(config_path(ws_dir)).unwrap(), "{}");
+    }
+}
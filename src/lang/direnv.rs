@@ -0,0 +1,200 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+
+use crate::util::shell_quote;
+use crate::workspace::Metadata;
+
+use super::LanguageIntegration;
+
+pub(crate) const DIRENV_HEADER: &str = "# Generated by wsp. DO NOT EDIT.";
+
+/// Per-repo fragment sourced into the workspace `.envrc`, for settings a
+/// repo wants when it is developed alongside others.
+const FRAGMENT: &str = ".envrc.workspace";
+
+/// Generates a workspace-root `.envrc` for direnv exporting the same
+/// `WSP_*` variables as `wsp shell`, loading the dotenv integration's `.env`
+/// when there is one, and sourcing each repo's `.envrc.workspace`. direnv
+/// refuses a changed `.envrc` until it is allowed again, so a rewrite prints
+/// the `direnv allow` command.
+pub struct DirenvIntegration;
+
+impl LanguageIntegration for DirenvIntegration {
+    fn name(&self) -> &str {
+        "direnv"
+    }
+
+    fn detect(&self, ws_dir: &Path, metadata: &Metadata) -> bool {
+        !metadata.repos.is_empty() || is_generated(&ws_dir.join(".envrc"))
+    }
+
+    fn apply(&self, ws_dir: &Path, metadata: &Metadata) -> Result<()> {
+        let path = ws_dir.join(".envrc");
+        if path.exists() && !is_generated(&path) {
+            bail!(".envrc exists and was not generated by wsp; leaving it alone");
+        }
+        if metadata.repos.is_empty() {
+            if path.exists() {
+                fs::remove_file(&path).with_context(|| format!("removing {}", path.display()))?;
+            }
+            return Ok(());
+        }
+
+        let content = render(ws_dir, metadata)?;
+        if fs::read_to_string(&path).is_ok_and(|old| old == content) {
+            return Ok(());
+        }
+        let tmp_path = ws_dir.join(".envrc.tmp");
+        fs::write(&tmp_path, content).with_context(|| format!("writing {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &path)
+            .with_context(|| format!("renaming {} to {}", tmp_path.display(), path.display()))?;
+        eprintln!(
+            "wrote .envrc; run `direnv allow {}` to load it",
+            shell_quote(&ws_dir.to_string_lossy())
+        );
+        Ok(())
+    }
+}
+
+fn is_generated(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|c| c.starts_with(DIRENV_HEADER))
+}
+
+fn render(ws_dir: &Path, metadata: &Metadata) -> Result<String> {
+    let mut out = String::new();
+    out.push_str(DIRENV_HEADER);
+    out.push('\n');
+    out.push_str("# Regenerated when repos are added or removed; run `direnv allow` after.\n\n");
+    for (name, value) in crate::cli::shell::workspace_env(ws_dir, metadata)? {
+        out.push_str(&format!("export {}={}\n", name, shell_quote(&value)));
+    }
+
+    let env_file = ws_dir.join(".env");
+    if fs::read_to_string(&env_file).is_ok_and(|c| c.starts_with(super::DOTENV_HEADER)) {
+        out.push_str("\ndotenv_if_exists .env\n");
+    }
+
+    // Every repo is listed: source_env_if_exists skips missing fragments,
+    // so adding one later needs no regeneration.
+    let mut dirs: Vec<String> = metadata
+        .repos
+        .keys()
+        .filter_map(|id| metadata.dir_name(id).ok())
+        .filter(|dn| !dn.contains("..") && !dn.starts_with('/'))
+        .collect();
+    dirs.sort();
+    if !dirs.is_empty() {
+        out.push('\n');
+    }
+    for dn in dirs {
+        out.push_str(&format!(
+            "source_env_if_exists {}\n",
+            shell_quote(&format!("{}/{}", dn, FRAGMENT))
+        ));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use chrono::Utc;
+
+    fn make_metadata(repos: &[&str]) -> Metadata {
+        let mut map = BTreeMap::new();
+        for id in repos {
+            map.insert(id.to_string(), None);
+        }
+        Metadata {
+            version: 0,
+            name: "test".into(),
+            branch: "test".into(),
+            repos: map,
+            created: Utc::now(),
+            description: None,
+            last_used: None,
+            created_from: None,
+            dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
+            config: None,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws_dir = tmp.path().join("my ws");
+        fs::create_dir_all(ws_dir.join("web")).unwrap();
+        fs::write(ws_dir.join("web").join(FRAGMENT), "PATH_add bin\n").unwrap();
+        fs::create_dir_all(ws_dir.join("api")).unwrap();
+        let meta = make_metadata(&["github.com/acme/api", "github.com/acme/web"]);
+
+        let got = render(&ws_dir, &meta).unwrap();
+        let dir = ws_dir.display();
+        let want = format!(
+            "{DIRENV_HEADER}\n\
+             # Regenerated when repos are added or removed; run `direnv allow` after.\n\n\
+             export WSP_WORKSPACE=test\n\
+             export WSP_DIR='{dir}'\n\
+             export WSP_REPO_API='{dir}/api'\n\
+             export WSP_REPO_WEB='{dir}/web'\n\
+             \n\
+             source_env_if_exists api/.envrc.workspace\n\
+             source_env_if_exists web/.envrc.workspace\n"
+        );
+        assert_eq!(got, want);
+
+        // A generated .env is loaded too; a hand-written one is not.
+        fs::write(ws_dir.join(".env"), "A=1\n").unwrap();
+        assert!(!render(&ws_dir, &meta).unwrap().contains("dotenv_if_exists"));
+        fs::write(
+            ws_dir.join(".env"),
+            format!("{}\nA=1\n", super::super::DOTENV_HEADER),
+        )
+        .unwrap();
+        assert!(
+            render(&ws_dir, &meta)
+                .unwrap()
+                .contains("\ndotenv_if_exists .env\n")
+        );
+    }
+
+    #[test]
+    fn test_apply_and_cleanup() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws_dir = tmp.path();
+        fs::create_dir_all(ws_dir.join("api")).unwrap();
+        let integration = DirenvIntegration;
+
+        let meta = make_metadata(&["github.com/acme/api"]);
+        assert!(integration.detect(ws_dir, &meta));
+        integration.apply(ws_dir, &meta).unwrap();
+        assert!(is_generated(&ws_dir.join(".envrc")));
+
+        let empty = make_metadata(&[]);
+        assert!(integration.detect(ws_dir, &empty));
+        integration.apply(ws_dir, &empty).unwrap();
+        assert!(!ws_dir.join(".envrc").exists());
+    }
+
+    #[test]
+    fn test_apply_keeps_user_envrc() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws_dir = tmp.path();
+        fs::write(ws_dir.join(".envrc"), "use nix\n").unwrap();
+
+        let meta = make_metadata(&["github.com/acme/api"]);
+        assert!(DirenvIntegration.apply(ws_dir, &meta).is_err());
+        assert_eq!(
+            fs::read_to_string(ws_dir.join(".envrc")).unwrap(),
+            "use nix\n"
+        );
+    }
+}
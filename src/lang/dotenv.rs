@@ -0,0 +1,259 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+
+use crate::workspace::Metadata;
+
+use super::LanguageIntegration;
+
+pub(crate) const DOTENV_HEADER: &str = "# Generated by wsp. DO NOT EDIT.";
+
+/// Per-repo fragment files, in order of preference: a repo's
+/// `.env.workspace` (meant for multi-repo development) wins over its
+/// `.env.example`.
+const FRAGMENTS: &[&str] = &[".env.workspace", ".env.example"];
+
+/// Merges per-repo env fragments into a workspace-level `.env`, so one file
+/// can be loaded for every service. Variables keep the value from the first
+/// repo (by directory name) that sets them; later duplicates are kept as
+/// comments naming the repo they came from.
+pub struct DotenvIntegration;
+
+impl LanguageIntegration for DotenvIntegration {
+    fn name(&self) -> &str {
+        "dotenv"
+    }
+
+    fn detect(&self, ws_dir: &Path, metadata: &Metadata) -> bool {
+        // A generated .env is kept in scope so it is cleaned up once the last
+        // repo with a fragment is removed.
+        !fragments(ws_dir, metadata).is_empty() || is_generated(&ws_dir.join(".env"))
+    }
+
+    fn apply(&self, ws_dir: &Path, metadata: &Metadata) -> Result<()> {
+        let path = ws_dir.join(".env");
+        if path.exists() && !is_generated(&path) {
+            bail!(".env exists and was not generated by wsp; leaving it alone");
+        }
+
+        let fragments = fragments(ws_dir, metadata);
+        if fragments.is_empty() {
+            if path.exists() {
+                fs::remove_file(&path).with_context(|| format!("removing {}", path.display()))?;
+            }
+            return Ok(());
+        }
+
+        let mut sources = Vec::new();
+        for rel in fragments {
+            let content = fs::read_to_string(ws_dir.join(&rel))
+                .with_context(|| format!("reading {}", rel))?;
+            sources.push((rel, content));
+        }
+
+        let tmp_path = ws_dir.join(".env.tmp");
+        fs::write(&tmp_path, merge(&sources))
+            .with_context(|| format!("writing {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &path)
+            .with_context(|| format!("renaming {} to {}", tmp_path.display(), path.display()))?;
+        Ok(())
+    }
+}
+
+/// Workspace-relative fragment path for each repo that has one, sorted by
+/// repo directory.
+fn fragments(ws_dir: &Path, metadata: &Metadata) -> Vec<String> {
+    let mut dirs: Vec<String> = metadata
+        .repos
+        .keys()
+        .filter_map(|id| metadata.dir_name(id).ok())
+        .filter(|dn| !dn.contains("..") && !dn.starts_with('/'))
+        .collect();
+    dirs.sort();
+    dirs.into_iter()
+        .filter_map(|dn| {
+            FRAGMENTS
+                .iter()
+                .map(|f| format!("{}/{}", dn, f))
+                .find(|rel| ws_dir.join(rel).is_file())
+        })
+        .collect()
+}
+
+fn is_generated(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|c| c.starts_with(DOTENV_HEADER))
+}
+
+/// Build the merged file from `(source, content)` pairs.
+fn merge(sources: &[(String, String)]) -> String {
+    let mut out = String::new();
+    out.push_str(DOTENV_HEADER);
+    out.push('\n');
+    out.push_str("# Merged from each repo's .env.workspace (or .env.example). Regenerated\n");
+    out.push_str("# when repos are added or removed; the first repo to set a variable wins.\n");
+
+    let mut seen: BTreeMap<String, &str> = BTreeMap::new();
+    for (source, content) in sources {
+        let repo = source.split('/').next().unwrap_or(source);
+        out.push_str(&format!("\n# --- {} ---\n", source));
+        for line in content.lines() {
+            match var_name(line) {
+                Some(name) => {
+                    if let Some(first) = seen.get(name) {
+                        out.push_str(&format!("# {} (already set by {})\n", line, first));
+                    } else {
+                        seen.insert(name.to_string(), repo);
+                        out.push_str(line);
+                        out.push('\n');
+                    }
+                }
+                None => {
+                    out.push_str(line);
+                    out.push('\n');
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Variable name of an assignment line (`KEY=value` or `export KEY=value`).
+fn var_name(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    if line.starts_with('#') {
+        return None;
+    }
+    let line = line.strip_prefix("export ").unwrap_or(line);
+    let (name, _) = line.split_once('=')?;
+    let name = name.trim();
+    (!name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+        .then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::Utc;
+
+    fn make_metadata(repos: &[&str]) -> Metadata {
+        let mut map = BTreeMap::new();
+        for id in repos {
+            map.insert(id.to_string(), None);
+        }
+        Metadata {
+            version: 0,
+            name: "test".into(),
+            branch: "test".into(),
+            repos: map,
+            created: Utc::now(),
+            description: None,
+            last_used: None,
+            created_from: None,
+            dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
+            config: None,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_var_name() {
+        let cases = vec![
+            ("DATABASE_URL=postgres://", Some("DATABASE_URL")),
+            ("export PORT=8080", Some("PORT")),
+            ("  KEY = value", Some("KEY")),
+            ("# COMMENTED=1", None),
+            ("", None),
+            ("not an assignment", None),
+        ];
+        for (line, want) in cases {
+            assert_eq!(var_name(line), want, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn test_apply_merges_with_provenance() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws_dir = tmp.path();
+        for (dir, file, content) in [
+            ("api", ".env.example", "PORT=8080\nDB_URL=postgres://api\n"),
+            ("api", ".env.workspace", "PORT=8081\n"),
+            (
+                "web",
+                ".env.example",
+                "# web\nPORT=3000\nAPI_URL=http://localhost:8081\n",
+            ),
+            ("docs", "README.md", "no env"),
+        ] {
+            fs::create_dir_all(ws_dir.join(dir)).unwrap();
+            fs::write(ws_dir.join(dir).join(file), content).unwrap();
+        }
+        let meta = make_metadata(&[
+            "github.com/acme/web",
+            "github.com/acme/api",
+            "github.com/acme/docs",
+        ]);
+
+        let integration = DotenvIntegration;
+        assert!(integration.detect(ws_dir, &meta));
+        integration.apply(ws_dir, &meta).unwrap();
+
+        let content = fs::read_to_string(ws_dir.join(".env")).unwrap();
+        let body: Vec<&str> = content.lines().skip(3).collect();
+        assert_eq!(
+            body,
+            vec![
+                "",
+                "# --- api/.env.workspace ---",
+                "PORT=8081",
+                "",
+                "# --- web/.env.example ---",
+                "# web",
+                "# PORT=3000 (already set by api)",
+                "API_URL=http://localhost:8081",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_regenerates_and_removes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws_dir = tmp.path();
+        fs::create_dir_all(ws_dir.join("api")).unwrap();
+        fs::write(ws_dir.join("api/.env.example"), "PORT=8080\n").unwrap();
+        let integration = DotenvIntegration;
+
+        integration
+            .apply(ws_dir, &make_metadata(&["github.com/acme/api"]))
+            .unwrap();
+        assert!(ws_dir.join(".env").exists());
+
+        // Repo removed: the generated file goes with it
+        let empty = make_metadata(&[]);
+        assert!(integration.detect(ws_dir, &empty));
+        integration.apply(ws_dir, &empty).unwrap();
+        assert!(!ws_dir.join(".env").exists());
+        assert!(!integration.detect(ws_dir, &empty));
+    }
+
+    #[test]
+    fn test_apply_keeps_user_env() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws_dir = tmp.path();
+        fs::create_dir_all(ws_dir.join("api")).unwrap();
+        fs::write(ws_dir.join("api/.env.example"), "PORT=8080\n").unwrap();
+        fs::write(ws_dir.join(".env"), "SECRET=mine\n").unwrap();
+
+        let meta = make_metadata(&["github.com/acme/api"]);
+        assert!(DotenvIntegration.apply(ws_dir, &meta).is_err());
+        assert_eq!(
+            fs::read_to_string(ws_dir.join(".env")).unwrap(),
+            "SECRET=mine\n"
+        );
+    }
+}
@@ -412,7 +412,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: BTreeMap::new(),
         }
     }
 
@@ -444,7 +448,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: BTreeMap::new(),
         }
     }
 
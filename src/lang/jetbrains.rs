@@ -0,0 +1,331 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+
+use crate::util::xml_escape;
+use crate::workspace::Metadata;
+
+use super::LanguageIntegration;
+
+pub(crate) const JETBRAINS_HEADER: &str = "<!-- Generated by wsp. DO NOT EDIT. -->";
+
+/// Module files wsp writes end in this. The IDE rewrites the XML files
+/// (dropping the header comment) but keeps file names, so the suffix is
+/// what marks a module, and a `modules.xml` listing only such modules, as
+/// wsp's.
+const MODULE_SUFFIX: &str = ".wsp.iml";
+
+/// Generates a JetBrains project in `.idea/` at the workspace root: one
+/// module with every repo clone as a content root, and a Git mapping per
+/// repo so the IDE tracks each clone's branch separately. Opening the
+/// workspace root in IntelliJ, GoLand, etc. then shows all repos at once.
+/// Everything else the IDE writes to `.idea/` is left alone.
+pub struct JetbrainsIntegration;
+
+impl LanguageIntegration for JetbrainsIntegration {
+    fn name(&self) -> &str {
+        "jetbrains"
+    }
+
+    fn detect(&self, ws_dir: &Path, metadata: &Metadata) -> bool {
+        !metadata.repos.is_empty() || is_generated(&ws_dir.join(".idea"))
+    }
+
+    fn apply(&self, ws_dir: &Path, metadata: &Metadata) -> Result<()> {
+        let idea = ws_dir.join(".idea");
+        let modules = modules_path(ws_dir);
+        let vcs = idea.join("vcs.xml");
+        let ours = is_generated(&idea);
+        for path in [&modules, &vcs] {
+            if path.exists() && !ours && !has_header(path) {
+                bail!(
+                    ".idea/{} exists and was not generated by wsp; leaving the project alone",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                );
+            }
+        }
+
+        // Module files from before a rename, or all of them once the last
+        // repo is gone.
+        let iml_name = format!("{}{}", metadata.name, MODULE_SUFFIX);
+        for stale in generated_modules(&idea) {
+            if metadata.repos.is_empty() || stale.file_name() != Some(iml_name.as_ref()) {
+                fs::remove_file(&stale).with_context(|| format!("removing {}", stale.display()))?;
+            }
+        }
+        if metadata.repos.is_empty() {
+            for path in [&modules, &vcs] {
+                if path.exists() {
+                    fs::remove_file(path)
+                        .with_context(|| format!("removing {}", path.display()))?;
+                }
+            }
+            return Ok(());
+        }
+
+        let dirs = repo_dirs(metadata);
+        fs::create_dir_all(&idea)?;
+        write_file(&idea.join(&iml_name), &module_xml(&dirs))?;
+        write_file(&modules, &modules_xml(&iml_name))?;
+        write_file(&vcs, &vcs_xml(&dirs))?;
+        Ok(())
+    }
+}
+
+fn modules_path(ws_dir: &Path) -> PathBuf {
+    ws_dir.join(".idea").join("modules.xml")
+}
+
+/// Whether the project in `idea` is wsp's: its `modules.xml` registers only
+/// wsp modules, or still carries the header wsp wrote.
+pub(crate) fn is_generated(idea: &Path) -> bool {
+    let modules = idea.join("modules.xml");
+    if has_header(&modules) {
+        return true;
+    }
+    let Ok(content) = fs::read_to_string(&modules) else {
+        return false;
+    };
+    let mut filepaths = content
+        .split("filepath=\"")
+        .skip(1)
+        .filter_map(|rest| rest.split('"').next())
+        .peekable();
+    filepaths.peek().is_some() && filepaths.all(|p| p.ends_with(MODULE_SUFFIX))
+}
+
+fn has_header(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|c| c.starts_with(JETBRAINS_HEADER))
+}
+
+/// wsp's `.iml` files in `.idea/`, including ones from before the suffix
+/// that still carry the header.
+fn generated_modules(idea: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(idea) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.to_string_lossy().ends_with(MODULE_SUFFIX)
+                || (p.extension().is_some_and(|e| e == "iml") && has_header(p))
+        })
+        .collect()
+}
+
+/// Clone directory names, sorted, skipping any that would leave the
+/// workspace.
+fn repo_dirs(metadata: &Metadata) -> Vec<String> {
+    let mut dirs: Vec<String> = metadata
+        .repos
+        .keys()
+        .filter_map(|id| metadata.dir_name(id).ok())
+        .filter(|dn| !dn.contains("..") && !dn.starts_with('/'))
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+fn module_xml(dirs: &[String]) -> String {
+    let mut out = format!(
+        "{}\n<module type=\"WEB_MODULE\" version=\"4\">\n  <component name=\"NewModuleRootManager\" inherit-compiler-output=\"true\">\n",
+        JETBRAINS_HEADER
+    );
+    for dn in dirs {
+        out.push_str(&format!(
+            "    <content url=\"file://$MODULE_DIR$/../{}\" />\n",
+            xml_escape(dn)
+        ));
+    }
+    out.push_str("    <orderEntry type=\"inheritedJdk\" />\n");
+    out.push_str("    <orderEntry type=\"sourceFolder\" forTests=\"false\" />\n");
+    out.push_str("  </component>\n</module>\n");
+    out
+}
+
+fn modules_xml(iml_name: &str) -> String {
+    let path = format!("$PROJECT_DIR$/.idea/{}", xml_escape(iml_name));
+    format!(
+        "{}\n<project version=\"4\">\n  <component name=\"ProjectModuleManager\">\n    <modules>\n      <module fileurl=\"file://{path}\" filepath=\"{path}\" />\n    </modules>\n  </component>\n</project>\n",
+        JETBRAINS_HEADER
+    )
+}
+
+fn vcs_xml(dirs: &[String]) -> String {
+    let mut out = format!(
+        "{}\n<project version=\"4\">\n  <component name=\"VcsDirectoryMappings\">\n",
+        JETBRAINS_HEADER
+    );
+    for dn in dirs {
+        out.push_str(&format!(
+            "    <mapping directory=\"$PROJECT_DIR$/{}\" vcs=\"Git\" />\n",
+            xml_escape(dn)
+        ));
+    }
+    out.push_str("  </component>\n</project>\n");
+    out
+}
+
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    let tmp_path = path.with_extension("wsp-tmp");
+    fs::write(&tmp_path, content).with_context(|| format!("writing {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, path)
+        .with_context(|| format!("renaming {} to {}", tmp_path.display(), path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use chrono::Utc;
+
+    fn make_metadata(name: &str, repos: &[&str]) -> Metadata {
+        let mut map = BTreeMap::new();
+        for id in repos {
+            map.insert(id.to_string(), None);
+        }
+        Metadata {
+            version: 0,
+            name: name.into(),
+            branch: name.into(),
+            repos: map,
+            created: Utc::now(),
+            description: None,
+            last_used: None,
+            created_from: None,
+            dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
+            config: None,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_apply_writes_project() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws_dir = tmp.path();
+        let meta = make_metadata("feat", &["github.com/acme/web", "github.com/acme/api"]);
+
+        let integration = JetbrainsIntegration;
+        assert!(integration.detect(ws_dir, &meta));
+        integration.apply(ws_dir, &meta).unwrap();
+
+        let module = fs::read_to_string(ws_dir.join(".idea/feat.wsp.iml")).unwrap();
+        assert!(module.starts_with(JETBRAINS_HEADER));
+        let api = module.find("$MODULE_DIR$/../api").unwrap();
+        let web = module.find("$MODULE_DIR$/../web").unwrap();
+        assert!(api < web, "content roots sorted by directory");
+
+        let modules = fs::read_to_string(modules_path(ws_dir)).unwrap();
+        assert!(modules.contains("filepath=\"$PROJECT_DIR$/.idea/feat.wsp.iml\""));
+
+        let vcs = fs::read_to_string(ws_dir.join(".idea/vcs.xml")).unwrap();
+        assert!(vcs.contains("<mapping directory=\"$PROJECT_DIR$/api\" vcs=\"Git\" />"));
+        assert!(vcs.contains("<mapping directory=\"$PROJECT_DIR$/web\" vcs=\"Git\" />"));
+    }
+
+    #[test]
+    fn test_apply_rename_and_cleanup() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws_dir = tmp.path();
+        let integration = JetbrainsIntegration;
+        integration
+            .apply(ws_dir, &make_metadata("old", &["github.com/acme/api"]))
+            .unwrap();
+        // IDE state next to the generated files is kept throughout.
+        fs::write(ws_dir.join(".idea/workspace.xml"), "<project />").unwrap();
+
+        integration
+            .apply(ws_dir, &make_metadata("new", &["github.com/acme/api"]))
+            .unwrap();
+        assert!(!ws_dir.join(".idea/old.wsp.iml").exists());
+        assert!(ws_dir.join(".idea/new.wsp.iml").exists());
+
+        let empty = make_metadata("new", &[]);
+        assert!(integration.detect(ws_dir, &empty));
+        integration.apply(ws_dir, &empty).unwrap();
+        for name in ["new.wsp.iml", "modules.xml", "vcs.xml"] {
+            assert!(!ws_dir.join(".idea").join(name).exists(), "{}", name);
+        }
+        assert!(ws_dir.join(".idea/workspace.xml").exists());
+    }
+
+    #[test]
+    fn test_apply_keeps_user_project() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws_dir = tmp.path();
+        fs::create_dir_all(ws_dir.join(".idea")).unwrap();
+        fs::write(modules_path(ws_dir), "<project />").unwrap();
+
+        let meta = make_metadata("feat", &["github.com/acme/api"]);
+        assert!(JetbrainsIntegration.apply(ws_dir, &meta).is_err());
+        assert_eq!(
+            fs::read_to_string(modules_path(ws_dir)).unwrap(),
+            "<project />"
+        );
+        assert!(!ws_dir.join(".idea/feat.wsp.iml").exists());
+    }
+
+    #[test]
+    fn test_apply_after_ide_rewrite() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws_dir = tmp.path();
+        let integration = JetbrainsIntegration;
+        let meta = make_metadata("feat", &["github.com/acme/api"]);
+        integration.apply(ws_dir, &meta).unwrap();
+
+        // The IDE rewrites its XML without the header comment.
+        for name in ["modules.xml", "vcs.xml", "feat.wsp.iml"] {
+            let path = ws_dir.join(".idea").join(name);
+            let content = fs::read_to_string(&path).unwrap();
+            let stripped = content.strip_prefix(JETBRAINS_HEADER).unwrap();
+            fs::write(&path, format!("<?xml version=\"1.0\"?>{}", stripped)).unwrap();
+        }
+        assert!(is_generated(&ws_dir.join(".idea")));
+
+        let meta = make_metadata("feat", &["github.com/acme/api", "github.com/acme/web"]);
+        integration.apply(ws_dir, &meta).unwrap();
+        let vcs = fs::read_to_string(ws_dir.join(".idea/vcs.xml")).unwrap();
+        assert!(vcs.contains("$PROJECT_DIR$/web"));
+    }
+
+    #[test]
+    fn test_is_generated() {
+        let modules = |paths: &[&str]| {
+            let entries: String = paths
+                .iter()
+                .map(|p| format!("<module fileurl=\"file://{p}\" filepath=\"{p}\" />"))
+                .collect();
+            format!(
+                "<project><component name=\"ProjectModuleManager\"><modules>{entries}</modules></component></project>"
+            )
+        };
+        let wsp = "$PROJECT_DIR$/.idea/feat.wsp.iml";
+        let user = "$PROJECT_DIR$/.idea/feat.iml";
+        let cases = vec![
+            ("wsp module", Some(modules(&[wsp])), true),
+            ("user module", Some(modules(&[user])), false),
+            ("mixed", Some(modules(&[wsp, user])), false),
+            ("no modules", Some(modules(&[])), false),
+            (
+                "header",
+                Some(format!("{}\n<project />", JETBRAINS_HEADER)),
+                true,
+            ),
+            ("missing", None, false),
+        ];
+        for (name, content, want) in cases {
+            let tmp = tempfile::tempdir().unwrap();
+            if let Some(content) = content {
+                fs::write(tmp.path().join("modules.xml"), content).unwrap();
+            }
+            assert_eq!(is_generated(tmp.path()), want, "{}", name);
+        }
+    }
+}
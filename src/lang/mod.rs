@@ -1,4 +1,11 @@
+pub(crate) mod devcontainer;
+pub(crate) mod direnv;
+pub(crate) mod dotenv;
 pub(crate) mod go;
+pub(crate) mod jetbrains;
+pub(crate) mod nix;
+pub(crate) use direnv::DIRENV_HEADER;
+pub(crate) use dotenv::DOTENV_HEADER;
 pub(crate) use go::GO_WORK_HEADER;
 
 use std::path::Path;
@@ -15,7 +22,15 @@ pub trait LanguageIntegration {
 }
 
 fn all_integrations() -> Vec<Box<dyn LanguageIntegration>> {
-    vec![Box::new(go::GoIntegration)]
+    vec![
+        Box::new(devcontainer::DevcontainerIntegration),
+        Box::new(dotenv::DotenvIntegration),
+        // After dotenv: .envrc loads the .env it generates.
+        Box::new(direnv::DirenvIntegration),
+        Box::new(go::GoIntegration),
+        Box::new(jetbrains::JetbrainsIntegration),
+        Box::new(nix::NixIntegration),
+    ]
 }
 
 /// Returns the names of all known language integrations.
@@ -49,7 +64,7 @@ pub fn run_integrations(ws_dir: &Path, metadata: &Metadata, config: &Config) {
         }
 
         if let Err(e) = integration.apply(ws_dir, metadata) {
-            eprintln!("warning: {} integration failed: {}", name, e);
+            tracing::warn!("{} integration failed: {}", name, e);
         }
     }
 }
@@ -79,7 +94,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: BTreeMap::new(),
         }
     }
 
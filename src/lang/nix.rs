@@ -0,0 +1,363 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+
+use crate::workspace::Metadata;
+
+use super::LanguageIntegration;
+
+pub(crate) const NIX_HEADER: &str = "# Generated by wsp. DO NOT EDIT.";
+
+/// Files this integration owns at the workspace root.
+const GENERATED: &[&str] = &["flake.nix", "shell.nix"];
+
+/// How a repo declares its dev dependencies.
+#[derive(Debug, Clone, PartialEq)]
+enum Source {
+    /// `flake.nix` exposing `devShells.<system>.default`.
+    Flake,
+    /// `shell.nix` (no flake).
+    Shell,
+    /// Both: the flake is used by `nix develop`, shell.nix by `nix-shell`.
+    Both,
+}
+
+/// Generates `flake.nix` and `shell.nix` at the workspace root whose dev shell
+/// combines every repo's own dev shell (`inputsFrom`), so `nix develop` (or
+/// `nix-shell`) at the root has the toolchains for all repos.
+pub struct NixIntegration;
+
+impl LanguageIntegration for NixIntegration {
+    fn name(&self) -> &str {
+        "nix"
+    }
+
+    fn detect(&self, ws_dir: &Path, metadata: &Metadata) -> bool {
+        !repo_sources(ws_dir, metadata).is_empty()
+            || GENERATED.iter().any(|f| is_generated(&ws_dir.join(f)))
+    }
+
+    fn apply(&self, ws_dir: &Path, metadata: &Metadata) -> Result<()> {
+        for name in GENERATED {
+            let path = ws_dir.join(name);
+            if path.exists() && !is_generated(&path) {
+                bail!(
+                    "{} exists and was not generated by wsp; leaving it alone",
+                    name
+                );
+            }
+        }
+
+        let sources = repo_sources(ws_dir, metadata);
+        if sources.is_empty() {
+            for name in GENERATED {
+                let path = ws_dir.join(name);
+                if path.exists() {
+                    fs::remove_file(&path)
+                        .with_context(|| format!("removing {}", path.display()))?;
+                }
+            }
+            return Ok(());
+        }
+
+        write(ws_dir, "flake.nix", &flake_nix(&metadata.name, &sources))?;
+        write(ws_dir, "shell.nix", &shell_nix(&sources))
+    }
+}
+
+pub(crate) fn is_generated(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|c| c.starts_with(NIX_HEADER))
+}
+
+fn write(ws_dir: &Path, name: &str, content: &str) -> Result<()> {
+    let tmp_path = ws_dir.join(format!(".{}.tmp", name));
+    let final_path = ws_dir.join(name);
+    fs::write(&tmp_path, content).with_context(|| format!("writing {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, &final_path).with_context(|| {
+        format!(
+            "renaming {} to {}",
+            tmp_path.display(),
+            final_path.display()
+        )
+    })
+}
+
+/// (dir_name, source) for each repo that declares a dev shell, sorted by
+/// directory.
+fn repo_sources(ws_dir: &Path, metadata: &Metadata) -> Vec<(String, Source)> {
+    let mut dirs: Vec<String> = metadata
+        .repos
+        .keys()
+        .filter_map(|id| metadata.dir_name(id).ok())
+        .filter(|dn| !dn.contains("..") && !dn.starts_with('/'))
+        .collect();
+    dirs.sort();
+    dirs.into_iter()
+        .filter_map(|dn| {
+            let flake = ws_dir.join(&dn).join("flake.nix").is_file();
+            let shell = ws_dir.join(&dn).join("shell.nix").is_file();
+            let source = match (flake, shell) {
+                (true, true) => Source::Both,
+                (true, false) => Source::Flake,
+                (false, true) => Source::Shell,
+                (false, false) => return None,
+            };
+            Some((dn, source))
+        })
+        .collect()
+}
+
+/// Nix string literal (also valid as a quoted attribute name).
+fn nix_str(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '$' => out.push_str("\\$"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn flake_nix(workspace: &str, sources: &[(String, Source)]) -> String {
+    let mut out = String::new();
+    out.push_str(NIX_HEADER);
+    out.push('\n');
+    out.push_str("{\n");
+    out.push_str(&format!(
+        "  description = {};\n\n",
+        nix_str(&format!("wsp workspace {}", workspace))
+    ));
+    out.push_str("  inputs = {\n");
+    out.push_str("    nixpkgs.url = \"github:NixOS/nixpkgs/nixos-unstable\";\n");
+    for (dir, source) in sources {
+        if *source != Source::Shell {
+            let name = nix_str(dir);
+            out.push_str(&format!(
+                "    {}.url = {};\n",
+                name,
+                nix_str(&format!("path:./{}", dir))
+            ));
+            out.push_str(&format!(
+                "    {}.inputs.nixpkgs.follows = \"nixpkgs\";\n",
+                name
+            ));
+        }
+    }
+    out.push_str("  };\n\n");
+    out.push_str("  outputs = { nixpkgs, ... }@inputs:\n");
+    out.push_str("    let\n");
+    out.push_str(
+        "      systems = [ \"x86_64-linux\" \"aarch64-linux\" \"x86_64-darwin\" \"aarch64-darwin\" ];\n",
+    );
+    out.push_str("      forAllSystems = f: nixpkgs.lib.genAttrs systems (system: f system nixpkgs.legacyPackages.${system});\n");
+    out.push_str("    in\n");
+    out.push_str("    {\n");
+    out.push_str("      devShells = forAllSystems (system: pkgs: {\n");
+    out.push_str("        default = pkgs.mkShell {\n");
+    out.push_str("          inputsFrom = [\n");
+    for (dir, source) in sources {
+        match source {
+            Source::Flake | Source::Both => out.push_str(&format!(
+                "            inputs.{}.devShells.${{system}}.default\n",
+                nix_str(dir)
+            )),
+            Source::Shell => out.push_str(&format!(
+                "            (pkgs.callPackage {} {{ }})\n",
+                shell_path(dir)
+            )),
+        }
+    }
+    out.push_str("          ];\n");
+    out.push_str("        };\n");
+    out.push_str("      });\n");
+    out.push_str("    };\n");
+    out.push_str("}\n");
+    out
+}
+
+fn shell_nix(sources: &[(String, Source)]) -> String {
+    let mut out = String::new();
+    out.push_str(NIX_HEADER);
+    out.push('\n');
+    out.push_str("{ pkgs ? import <nixpkgs> { } }:\n\n");
+    out.push_str("pkgs.mkShell {\n");
+    out.push_str("  inputsFrom = [\n");
+    for (dir, source) in sources {
+        match source {
+            Source::Shell | Source::Both => out.push_str(&format!(
+                "    (pkgs.callPackage {} {{ }})\n",
+                shell_path(dir)
+            )),
+            Source::Flake => out.push_str(&format!(
+                "    # {}: flake.nix only, use `nix develop`\n",
+                dir
+            )),
+        }
+    }
+    out.push_str("  ];\n");
+    out.push_str("}\n");
+    out
+}
+
+/// Nix expression for the path of a repo's shell.nix. Path literals can't be
+/// quoted, so directories with unusual characters are appended as a string.
+fn shell_path(dir: &str) -> String {
+    if dir
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        format!("./{}/shell.nix", dir)
+    } else {
+        format!("(./. + {})", nix_str(&format!("/{}/shell.nix", dir)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use chrono::Utc;
+
+    fn make_metadata(repos: &[&str]) -> Metadata {
+        let mut map = BTreeMap::new();
+        for id in repos {
+            map.insert(id.to_string(), None);
+        }
+        Metadata {
+            version: 0,
+            name: "test".into(),
+            branch: "test".into(),
+            repos: map,
+            created: Utc::now(),
+            description: None,
+            last_used: None,
+            created_from: None,
+            dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
+            config: None,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    fn setup(ws_dir: &Path, files: &[&str]) {
+        for f in files {
+            let p = ws_dir.join(f);
+            fs::create_dir_all(p.parent().unwrap()).unwrap();
+            fs::write(p, "{ }").unwrap();
+        }
+    }
+
+    #[test]
+    fn test_nix_str() {
+        let cases = vec![
+            ("api", "\"api\""),
+            ("a\"b", "\"a\\\"b\""),
+            ("${x}", "\"\\${x}\""),
+        ];
+        for (input, want) in cases {
+            assert_eq!(nix_str(input), want, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_shell_path() {
+        assert_eq!(shell_path("web-app"), "./web-app/shell.nix");
+        assert_eq!(shell_path("we b"), "(./. + \"/we b/shell.nix\")");
+    }
+
+    #[test]
+    fn test_apply_combines_repo_shells() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws_dir = tmp.path();
+        setup(
+            ws_dir,
+            &[
+                "api/flake.nix",
+                "web/shell.nix",
+                "cli/flake.nix",
+                "cli/shell.nix",
+            ],
+        );
+        fs::create_dir_all(ws_dir.join("docs")).unwrap();
+        let meta = make_metadata(&[
+            "github.com/acme/api",
+            "github.com/acme/cli",
+            "github.com/acme/docs",
+            "github.com/acme/web",
+        ]);
+
+        let integration = NixIntegration;
+        assert!(integration.detect(ws_dir, &meta));
+        integration.apply(ws_dir, &meta).unwrap();
+
+        let flake = fs::read_to_string(ws_dir.join("flake.nix")).unwrap();
+        for want in [
+            "  description = \"wsp workspace test\";\n",
+            "    \"api\".url = \"path:./api\";\n",
+            "    \"cli\".inputs.nixpkgs.follows = \"nixpkgs\";\n",
+            "            inputs.\"api\".devShells.${system}.default\n",
+            "            inputs.\"cli\".devShells.${system}.default\n",
+            "            (pkgs.callPackage ./web/shell.nix { })\n",
+        ] {
+            assert!(
+                flake.contains(want),
+                "flake.nix missing {:?}:\n{}",
+                want,
+                flake
+            );
+        }
+        assert!(!flake.contains("docs"));
+
+        let shell = fs::read_to_string(ws_dir.join("shell.nix")).unwrap();
+        assert_eq!(
+            shell,
+            "# Generated by wsp. DO NOT EDIT.\n\
+             { pkgs ? import <nixpkgs> { } }:\n\n\
+             pkgs.mkShell {\n  inputsFrom = [\n    \
+             # api: flake.nix only, use `nix develop`\n    \
+             (pkgs.callPackage ./cli/shell.nix { })\n    \
+             (pkgs.callPackage ./web/shell.nix { })\n  ];\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_removes_when_no_repo_declares() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws_dir = tmp.path();
+        setup(ws_dir, &["api/shell.nix"]);
+        let integration = NixIntegration;
+
+        integration
+            .apply(ws_dir, &make_metadata(&["github.com/acme/api"]))
+            .unwrap();
+        assert!(ws_dir.join("flake.nix").exists());
+
+        let empty = make_metadata(&[]);
+        assert!(integration.detect(ws_dir, &empty));
+        integration.apply(ws_dir, &empty).unwrap();
+        assert!(!ws_dir.join("flake.nix").exists());
+        assert!(!ws_dir.join("shell.nix").exists());
+    }
+
+    #[test]
+    fn test_apply_keeps_user_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws_dir = tmp.path();
+        setup(ws_dir, &["api/shell.nix", "shell.nix"]);
+
+        let meta = make_metadata(&["github.com/acme/api"]);
+        assert!(NixIntegration.apply(ws_dir, &meta).is_err());
+        assert_eq!(fs::read_to_string(ws_dir.join("shell.nix")).unwrap(), "{ }");
+        assert!(!ws_dir.join("flake.nix").exists());
+    }
+}
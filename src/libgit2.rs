@@ -0,0 +1,454 @@
+//! The git operations a workspace needs to exist — cloning and fetching
+//! mirrors, cloning a repo into a workspace and checking out its branch —
+//! done with libgit2, for hosts with no `git` binary on PATH. Built with
+//! the `libgit2` feature; [`crate::git::backend`] decides when it's used.
+//!
+//! Everything else (status, sync, diff, log) still runs git. URL rewrites
+//! and host settings from config.yaml are git config passed to the git
+//! CLI, so they don't apply here; `http.proxy` and the proxy environment
+//! variables do.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow};
+use git2::{
+    AutotagOption, BranchType, Cred, CredentialType, FetchOptions, FetchPrune, ProxyOptions,
+    RemoteCallbacks, Repository,
+};
+
+use crate::error::{self, ErrorKind};
+use crate::git::Transfer;
+use crate::util::path_str;
+
+fn open(dir: &Path) -> Result<Repository> {
+    Repository::open(dir).map_err(|e| wrap(e, "open", Some(dir)))
+}
+
+/// Tags libgit2's error like [`crate::git::run`] tags git's, so network
+/// failures get the network exit code.
+fn wrap(e: git2::Error, op: &str, dir: Option<&Path>) -> anyhow::Error {
+    let kind = match e.class() {
+        git2::ErrorClass::Net | git2::ErrorClass::Http | git2::ErrorClass::Ssh => {
+            ErrorKind::Network
+        }
+        _ => error::git_kind(e.message()),
+    };
+    let err = match dir {
+        Some(d) => anyhow!("libgit2 {} (in {}): {}", op, d.display(), e.message()),
+        None => anyhow!("libgit2 {}: {}", op, e.message()),
+    };
+    error::tag(kind, err)
+}
+
+/// Credentials as git would find them: the SSH agent for SSH URLs, the
+/// configured credential helper for HTTPS. Each is tried once, so a
+/// rejected credential fails the fetch instead of looping.
+/// Options for a fetch. `on_transfer` hears how it's going and can cancel
+/// it by returning false.
+fn fetch_options<'a>(
+    config: git2::Config,
+    prune: bool,
+    on_transfer: impl FnMut(git2::Progress<'_>) -> bool + 'a,
+) -> FetchOptions<'a> {
+    let mut tried = CredentialType::empty();
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |url, username, allowed| {
+        if allowed.contains(CredentialType::SSH_KEY) && !tried.contains(CredentialType::SSH_KEY) {
+            tried |= CredentialType::SSH_KEY;
+            return Cred::ssh_key_from_agent(username.unwrap_or("git"));
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT)
+            && !tried.contains(CredentialType::USER_PASS_PLAINTEXT)
+        {
+            tried |= CredentialType::USER_PASS_PLAINTEXT;
+            return Cred::credential_helper(&config, url, username);
+        }
+        if allowed.contains(CredentialType::DEFAULT) && !tried.contains(CredentialType::DEFAULT) {
+            tried |= CredentialType::DEFAULT;
+            return Cred::default();
+        }
+        Err(git2::Error::from_str("no credentials accepted"))
+    });
+    callbacks.transfer_progress(on_transfer);
+    let mut proxy = ProxyOptions::new();
+    proxy.auto();
+    let mut opts = FetchOptions::new();
+    opts.remote_callbacks(callbacks)
+        .proxy_options(proxy)
+        .download_tags(AutotagOption::All)
+        .prune(if prune {
+            FetchPrune::On
+        } else {
+            FetchPrune::Off
+        });
+    opts
+}
+
+/// `git check-ref-format --branch`.
+pub fn validate_branch_name(name: &str) -> bool {
+    git2::Branch::name_is_valid(name).unwrap_or(false)
+}
+
+/// `git clone --bare`: every upstream branch as a local branch, HEAD on
+/// upstream's default branch.
+pub fn clone_bare(url: &str, dest: &Path) -> Result<()> {
+    let repo = Repository::init_bare(dest).map_err(|e| wrap(e, "init", Some(dest)))?;
+    let result = (|| {
+        let mut remote = repo
+            .remote_with_fetch("origin", url, "+refs/heads/*:refs/heads/*")
+            .map_err(|e| wrap(e, "remote add", Some(dest)))?;
+        let config = repo.config().map_err(|e| wrap(e, "config", Some(dest)))?;
+        let mut opts = fetch_options(config, false, |_| true);
+        remote
+            .fetch::<&str>(&[], Some(&mut opts), None)
+            .map_err(|e| wrap(e, &format!("clone {}", url), None))?;
+        if let Ok(head) = remote.default_branch()
+            && let Some(head) = head.as_str()
+        {
+            repo.set_head(head)
+                .map_err(|e| wrap(e, "set HEAD", Some(dest)))?;
+        }
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_dir_all(dest);
+    }
+    result
+}
+
+/// Replaces `remote.origin.fetch` with `refspecs`.
+pub fn set_fetch_refspecs(dir: &Path, refspecs: &[&str]) -> Result<()> {
+    let repo = open(dir)?;
+    let mut config = repo.config().map_err(|e| wrap(e, "config", Some(dir)))?;
+    let _ = config.remove_multivar("remote.origin.fetch", ".*");
+    for spec in refspecs {
+        repo.remote_add_fetch("origin", spec)
+            .map_err(|e| wrap(e, "config remote.origin.fetch", Some(dir)))?;
+    }
+    Ok(())
+}
+
+/// `git config --local <key> <value>`.
+pub fn set_config(dir: &Path, key: &str, value: &str) -> Result<()> {
+    let repo = open(dir)?;
+    repo.config()
+        .and_then(|c| c.open_level(git2::ConfigLevel::Local))
+        .and_then(|mut c| c.set_str(key, value))
+        .map_err(|e| wrap(e, &format!("config {}", key), Some(dir)))
+}
+
+/// `git config --local <key>`.
+pub fn get_config(dir: &Path, key: &str) -> Result<String> {
+    let repo = open(dir)?;
+    repo.config()
+        .and_then(|c| c.open_level(git2::ConfigLevel::Local))
+        .and_then(|c| c.get_string(key))
+        .map_err(|e| wrap(e, &format!("config {}", key), Some(dir)))
+}
+
+/// `git fetch --all [--prune]`. Also points `refs/remotes/origin/HEAD` at
+/// origin's default branch when it isn't set yet, as git does on fetch.
+pub fn fetch(
+    dir: &Path,
+    prune: bool,
+    timeout: Option<Duration>,
+    on_progress: &dyn Fn(&Transfer),
+) -> Result<Transfer> {
+    let deadline = timeout.map(|t| Instant::now() + t);
+    let mut total = Transfer::default();
+    let repo = open(dir)?;
+    let remotes = repo.remotes().map_err(|e| wrap(e, "remote", Some(dir)))?;
+    for name in remotes.iter().flatten() {
+        let mut remote = repo
+            .find_remote(name)
+            .map_err(|e| wrap(e, "remote", Some(dir)))?;
+        let config = repo.config().map_err(|e| wrap(e, "config", Some(dir)))?;
+        let base = total;
+        let mut opts = fetch_options(config, prune, |p| {
+            on_progress(&added(base, p));
+            deadline.is_none_or(|d| Instant::now() < d)
+        });
+        remote
+            .fetch::<&str>(&[], Some(&mut opts), None)
+            .map_err(|e| match (timeout, deadline) {
+                (Some(t), Some(d)) if Instant::now() >= d => error::tag(
+                    ErrorKind::Network,
+                    anyhow!(
+                        "fetch {} (in {}): timed out after {}s",
+                        name,
+                        dir.display(),
+                        t.as_secs()
+                    ),
+                ),
+                _ => wrap(e, &format!("fetch {}", name), Some(dir)),
+            })?;
+        total = added(base, remote.stats());
+        if name != "origin" || repo.find_reference("refs/remotes/origin/HEAD").is_ok() {
+            continue;
+        }
+        if let Ok(head) = remote.default_branch()
+            && let Some(branch) = head.as_str().and_then(|h| h.strip_prefix("refs/heads/"))
+        {
+            let target = format!("refs/remotes/origin/{}", branch);
+            if repo.find_reference(&target).is_ok() {
+                let _ = repo.reference_symbolic(
+                    "refs/remotes/origin/HEAD",
+                    &target,
+                    false,
+                    "fetch: set origin/HEAD",
+                );
+            }
+        }
+    }
+    Ok(total)
+}
+
+/// `base` plus one remote's transfer so far.
+fn added(base: Transfer, p: git2::Progress<'_>) -> Transfer {
+    Transfer {
+        objects: base.objects + p.received_objects() as u64,
+        total_objects: base.total_objects + p.total_objects() as u64,
+        bytes: base.bytes + p.received_bytes() as u64,
+    }
+}
+
+/// `git fetch [--prune] -- <source_path> <refspec>`.
+pub fn fetch_from_path(dir: &Path, source_path: &Path, refspec: &str, prune: bool) -> Result<()> {
+    let repo = open(dir)?;
+    let mut remote = repo
+        .remote_anonymous(path_str(source_path)?)
+        .map_err(|e| wrap(e, "remote", Some(dir)))?;
+    let config = repo.config().map_err(|e| wrap(e, "config", Some(dir)))?;
+    let mut opts = fetch_options(config, prune, |_| true);
+    opts.download_tags(AutotagOption::Auto);
+    remote
+        .fetch(&[refspec], Some(&mut opts), None)
+        .map_err(|e| wrap(e, &format!("fetch {}", source_path.display()), Some(dir)))
+}
+
+/// `git clone --local`: origin is the source path, its HEAD branch checked
+/// out and tracking origin.
+pub fn clone_local(mirror_dir: &Path, dest: &Path) -> Result<()> {
+    git2::build::RepoBuilder::new()
+        .clone(path_str(mirror_dir)?, dest)
+        .map_err(|e| wrap(e, &format!("clone {}", mirror_dir.display()), None))?;
+    Ok(())
+}
+
+/// The branch `refs/remotes/origin/HEAD` points at.
+pub fn default_branch_from_mirror(mirror_dir: &Path) -> Result<String> {
+    let repo = open(mirror_dir)?;
+    let head = repo
+        .find_reference("refs/remotes/origin/HEAD")
+        .map_err(|e| wrap(e, "symbolic-ref refs/remotes/origin/HEAD", Some(mirror_dir)))?;
+    let target = head
+        .symbolic_target()
+        .context("refs/remotes/origin/HEAD is not a symbolic ref")?;
+    let parts: Vec<&str> = target.split('/').collect();
+    if parts.len() < 3 {
+        anyhow::bail!("unexpected ref format: {}", target);
+    }
+    Ok(parts[parts.len() - 1].to_string())
+}
+
+pub fn remote_set_url(dir: &Path, remote: &str, url: &str) -> Result<()> {
+    open(dir)?
+        .remote_set_url(remote, url)
+        .map_err(|e| wrap(e, "remote set-url", Some(dir)))
+}
+
+/// `git remote set-head <remote> <branch>`.
+pub fn remote_set_head(dir: &Path, remote: &str, branch: &str) -> Result<()> {
+    let repo = open(dir)?;
+    let target = format!("refs/remotes/{}/{}", remote, branch);
+    repo.find_reference(&target)
+        .map_err(|e| wrap(e, "remote set-head", Some(dir)))?;
+    repo.reference_symbolic(
+        &format!("refs/remotes/{}/HEAD", remote),
+        &target,
+        true,
+        "remote set-head",
+    )
+    .map_err(|e| wrap(e, "remote set-head", Some(dir)))?;
+    Ok(())
+}
+
+/// `git rev-parse --verify <spec>` succeeding.
+pub fn rev_exists(dir: &Path, spec: &str) -> bool {
+    open(dir).is_ok_and(|repo| repo.revparse_single(spec).is_ok())
+}
+
+/// `git update-ref --no-deref <refname> <target>`.
+pub fn update_ref(dir: &Path, refname: &str, target: &str) -> Result<()> {
+    let repo = open(dir)?;
+    let oid = repo
+        .revparse_single(target)
+        .map_err(|e| wrap(e, "update-ref", Some(dir)))?
+        .id();
+    repo.reference(refname, oid, true, "update-ref")
+        .map_err(|e| wrap(e, "update-ref", Some(dir)))?;
+    Ok(())
+}
+
+/// `git merge-base --is-ancestor <ancestor> <descendant>`.
+pub fn is_ancestor(dir: &Path, ancestor: &str, descendant: &str) -> bool {
+    let Ok(repo) = open(dir) else {
+        return false;
+    };
+    let commit = |spec: &str| repo.revparse_single(spec).and_then(|o| o.peel_to_commit());
+    match (commit(ancestor), commit(descendant)) {
+        (Ok(a), Ok(d)) => {
+            a.id() == d.id() || repo.graph_descendant_of(d.id(), a.id()).unwrap_or(false)
+        }
+        _ => false,
+    }
+}
+
+/// `git branch --set-upstream-to <upstream> <branch>`, or
+/// `--unset-upstream` with `None`.
+pub fn set_upstream(dir: &Path, branch: &str, upstream: Option<&str>) -> Result<()> {
+    let repo = open(dir)?;
+    repo.find_branch(branch, BranchType::Local)
+        .and_then(|mut b| b.set_upstream(upstream))
+        .map_err(|e| wrap(e, "branch", Some(dir)))
+}
+
+/// `git checkout <ref_or_branch>`: a local branch, else a new branch
+/// tracking `origin/<name>`, else a detached HEAD.
+pub fn checkout(dir: &Path, ref_or_branch: &str) -> Result<()> {
+    let repo = open(dir)?;
+    let local = format!("refs/heads/{}", ref_or_branch);
+    let remote = format!("refs/remotes/origin/{}", ref_or_branch);
+    let wrap = |e| wrap(e, &format!("checkout {}", ref_or_branch), Some(dir));
+    if repo.find_reference(&local).is_ok() {
+        let commit = repo
+            .revparse_single(&local)
+            .and_then(|o| o.peel_to_commit())
+            .map_err(wrap)?;
+        return switch_to(&repo, &commit, &local).map_err(wrap);
+    }
+    if let Ok(commit) = repo
+        .revparse_single(&remote)
+        .and_then(|o| o.peel_to_commit())
+    {
+        let mut created = repo.branch(ref_or_branch, &commit, false).map_err(wrap)?;
+        created
+            .set_upstream(Some(&format!("origin/{}", ref_or_branch)))
+            .map_err(wrap)?;
+        return switch_to(&repo, &commit, &local).map_err(wrap);
+    }
+    let commit = repo
+        .revparse_single(ref_or_branch)
+        .and_then(|o| o.peel_to_commit())
+        .map_err(wrap)?;
+    repo.checkout_tree(
+        commit.as_object(),
+        Some(git2::build::CheckoutBuilder::new().safe()),
+    )
+    .and_then(|_| repo.set_head_detached(commit.id()))
+    .map_err(wrap)
+}
+
+/// `git checkout -b <branch> --no-track <start_point>`.
+pub fn checkout_new_branch(dir: &Path, branch: &str, start_point: &str) -> Result<()> {
+    let repo = open(dir)?;
+    let wrap = |e| wrap(e, &format!("checkout -b {}", branch), Some(dir));
+    let commit = repo
+        .revparse_single(start_point)
+        .and_then(|o| o.peel_to_commit())
+        .map_err(wrap)?;
+    repo.branch(branch, &commit, false).map_err(wrap)?;
+    switch_to(&repo, &commit, &format!("refs/heads/{}", branch)).map_err(wrap)
+}
+
+/// `git checkout --orphan <branch>`.
+pub fn checkout_orphan(dir: &Path, branch: &str) -> Result<()> {
+    open(dir)?
+        .set_head(&format!("refs/heads/{}", branch))
+        .map_err(|e| wrap(e, "checkout --orphan", Some(dir)))
+}
+
+fn switch_to(repo: &Repository, commit: &git2::Commit, refname: &str) -> Result<(), git2::Error> {
+    repo.checkout_tree(
+        commit.as_object(),
+        Some(git2::build::CheckoutBuilder::new().safe()),
+    )?;
+    repo.set_head(refname)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git;
+    use crate::testutil::{local_commit, setup_clone_repo};
+
+    #[test]
+    fn test_mirror_and_workspace_clone() {
+        let (_clone, source, _clone_tmp, _source_tmp) = setup_clone_repo();
+        git::run(Some(&source), &["branch", "feature"]).unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+
+        let mirror = tmp.path().join("mirror.git");
+        clone_bare(path_str(&source).unwrap(), &mirror).unwrap();
+        assert!(git::ref_exists(&mirror, "refs/heads/feature"));
+        assert_eq!(git::symbolic_head(&mirror).as_deref(), Some("main"));
+
+        set_fetch_refspecs(
+            &mirror,
+            &[
+                "+refs/heads/*:refs/heads/*",
+                "+refs/heads/*:refs/remotes/origin/*",
+            ],
+        )
+        .unwrap();
+        let refspecs = git::run(
+            Some(&mirror),
+            &["config", "--get-all", "remote.origin.fetch"],
+        )
+        .unwrap();
+        assert_eq!(refspecs.lines().count(), 2);
+        set_config(&mirror, "wsp.test", "yes").unwrap();
+        assert_eq!(get_config(&mirror, "wsp.test").unwrap(), "yes");
+        assert!(get_config(&mirror, "wsp.unset").is_err());
+
+        local_commit(&source, "upstream.txt", "new\n");
+        fetch(&mirror, true, None, &|_| {}).unwrap();
+        assert!(rev_exists(&mirror, "refs/remotes/origin/main"));
+        assert_eq!(default_branch_from_mirror(&mirror).unwrap(), "main");
+        assert_eq!(
+            git::run(Some(&mirror), &["rev-parse", "main"]).unwrap(),
+            git::run(Some(&source), &["rev-parse", "main"]).unwrap()
+        );
+
+        let ws = tmp.path().join("repo");
+        clone_local(&mirror, &ws).unwrap();
+        remote_set_url(&ws, "origin", "git@test.local:user/repo.git").unwrap();
+        assert_eq!(
+            git::remote_get_url(&ws, "origin").unwrap(),
+            "git@test.local:user/repo.git"
+        );
+        remote_set_head(&ws, "origin", "main").unwrap();
+        assert!(is_ancestor(&ws, "origin/feature", "origin/main"));
+        assert!(!is_ancestor(&ws, "origin/main", "origin/feature"));
+
+        checkout_new_branch(&ws, "ws-branch", "origin/main").unwrap();
+        assert_eq!(git::branch_current(&ws).unwrap(), "ws-branch");
+        assert!(git::upstream_name(&ws).is_err());
+
+        checkout(&ws, "feature").unwrap();
+        assert_eq!(git::branch_current(&ws).unwrap(), "feature");
+        assert_eq!(git::upstream_name(&ws).unwrap(), "origin/feature");
+        set_upstream(&ws, "feature", None).unwrap();
+        assert!(git::upstream_name(&ws).is_err());
+
+        update_ref(&ws, "refs/heads/ws-branch", "origin/feature").unwrap();
+        assert_eq!(
+            git::run(Some(&ws), &["rev-parse", "ws-branch"]).unwrap(),
+            git::run(Some(&ws), &["rev-parse", "origin/feature"]).unwrap()
+        );
+        assert!(!rev_exists(&ws, "refs/heads/nope"));
+        assert!(validate_branch_name("feature/x"));
+        assert!(!validate_branch_name("bad..name"));
+    }
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::format::{self, FormatEvent, FormatFields};
+use tracing_subscriber::fmt::{FmtContext, FormattedFields};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Max log level for the global `-v`/`-q` flags: warnings by default, errors
+/// only with `--quiet`, git commands at `-v`, and their output at `-vv`.
+pub fn level(verbose: u8, quiet: bool) -> Level {
+    match (quiet, verbose) {
+        (true, _) => Level::ERROR,
+        (false, 0) => Level::WARN,
+        (false, 1) => Level::DEBUG,
+        (false, _) => Level::TRACE,
+    }
+}
+
+/// Install the stderr logger. Events are printed as bare messages (no
+/// timestamps or targets) so they read like the rest of wsp's stderr
+/// output; warnings get their `warning: ` prefix here, so messages don't
+/// carry one.
+pub fn init(verbose: u8, quiet: bool) {
+    let _ = tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_max_level(level(verbose, quiet))
+        .with_ansi(false)
+        .event_format(Plain)
+        .try_init();
+}
+
+struct Plain;
+
+impl<S, N> FormatEvent<S, N> for Plain
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: format::Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut message = FormattedFields::<N>::new(String::new());
+        ctx.format_fields(message.as_writer(), event)?;
+        writeln!(writer, "{}", decorate(*event.metadata().level(), &message))
+    }
+}
+
+/// Prefix a warning with `warning: `, after any indentation so it still
+/// lines up under the progress line it belongs to.
+fn decorate(level: Level, message: &str) -> String {
+    if level != Level::WARN {
+        return message.to_string();
+    }
+    let text = message.trim_start();
+    let indent = &message[..message.len() - text.len()];
+    format!("{}warning: {}", indent, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level() {
+        let cases = vec![
+            (0, false, Level::WARN),
+            (1, false, Level::DEBUG),
+            (2, false, Level::TRACE),
+            (3, false, Level::TRACE),
+            (0, true, Level::ERROR),
+            (2, true, Level::ERROR),
+        ];
+        for (verbose, quiet, want) in cases {
+            assert_eq!(
+                level(verbose, quiet),
+                want,
+                "-v x{} quiet={}",
+                verbose,
+                quiet
+            );
+        }
+    }
+
+    #[test]
+    fn test_decorate() {
+        let cases = vec![
+            (Level::WARN, "gc failed", "warning: gc failed"),
+            (Level::WARN, "  fetch failed", "  warning: fetch failed"),
+            (Level::DEBUG, "+ git status", "+ git status"),
+            (Level::TRACE, "  stdout| x", "  stdout| x"),
+        ];
+        for (level, message, want) in cases {
+            assert_eq!(decorate(level, message), want, "{}", message);
+        }
+    }
+}
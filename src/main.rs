@@ -1,17 +1,45 @@
 #![deny(unsafe_code)]
 
 mod agentmd;
+mod auth;
+mod backup;
+mod bootstrap;
 mod cli;
 mod config;
+mod configcheck;
+mod confirm;
+mod dag;
+mod deps;
 mod discovery;
+mod du;
+mod error;
 mod filelock;
+mod files;
+mod forge;
 mod gc;
 mod git;
+#[cfg(feature = "gitoxide")]
+mod gitoxide;
 mod giturl;
+mod hooks;
+mod issue;
 mod lang;
+#[cfg(feature = "libgit2")]
+mod libgit2;
+mod logging;
+mod managedhook;
+mod migrate;
 mod mirror;
+mod offline;
 mod output;
+mod picker;
+mod pushguard;
+mod statuscache;
+mod task;
 mod template;
+mod timings;
+mod tmux;
+mod trailer;
 mod util;
 mod workspace;
 
@@ -35,60 +63,97 @@ fn main() {
 
     let mut app = cli::build_cli();
     let matches = app.get_matches_mut();
-    let json = matches.get_flag("json");
+    let format = output::Format::from_matches(&matches);
+    logging::init(matches.get_count("verbose"), matches.get_flag("quiet"));
+    let started = std::time::Instant::now();
+    if matches.get_flag("timings") {
+        timings::enable();
+    }
+    if matches.get_flag("offline")
+        || std::env::var(offline::ENV).is_ok_and(|v| offline::env_enables(&v))
+    {
+        offline::enable();
+    }
 
     // Handle `wsp help [topic]` before general dispatch — it needs
     // the Command definition to print subcommand help.
     if let Some(("help", m)) = matches.subcommand() {
-        match cli::help::run(m, &mut app, json) {
+        match cli::help::run(m, &mut app, format) {
             Ok(_) => process::exit(0),
             Err(err) => {
-                render_error(err, json);
-                process::exit(1);
+                let code = render_error(err, format);
+                process::exit(code);
             }
         }
     }
 
-    let paths = match config::Paths::resolve() {
+    let profile = matches.get_one::<String>("profile").map(|s| s.as_str());
+    let paths = match config::Paths::resolve_profile(
+        profile,
+        cli::checks_config(&matches),
+        cli::changes_config(&matches),
+    ) {
         Ok(p) => p,
         Err(err) => {
-            render_error(err, json);
-            process::exit(1);
+            let code = render_error(err, format);
+            process::exit(code);
         }
     };
+    if let Ok(cfg) = config::Config::load_from(&paths.config_path) {
+        git::set_network_config(cfg.network_git_config());
+    }
 
     match cli::dispatch(&matches, &paths) {
         Ok(out) => {
             let code = output::exit_code(&out);
-            if let Err(err) = output::render(out, json) {
-                render_error(err, json);
-                process::exit(1);
+            if let Err(err) = output::render(out, format) {
+                // `wsp ... | head` closing the pipe early isn't a failure.
+                if output::is_broken_pipe(&err) {
+                    process::exit(code);
+                }
+                let code = render_error(err, format);
+                process::exit(code);
+            }
+            if timings::enabled() {
+                output::render_timings(&timings::report(started.elapsed()), format);
+            }
+            // Opportunistic gc — runs at most once per hour. Not from
+            // `wsp prompt`, which runs on every prompt and must stay fast.
+            if matches.subcommand_name() != Some("prompt") {
+                let retention = config::Config::load_from(&paths.config_path)
+                    .ok()
+                    .and_then(|c| c.gc_retention_days);
+                gc::maybe_run(&paths, retention);
             }
-            // Opportunistic gc — runs at most once per hour
-            let retention = config::Config::load_from(&paths.config_path)
-                .ok()
-                .and_then(|c| c.gc_retention_days);
-            gc::maybe_run(&paths, retention);
             if code != 0 {
                 process::exit(code);
             }
         }
         Err(err) => {
-            render_error(err, json);
-            process::exit(1);
+            let code = render_error(err, format);
+            if timings::enabled() {
+                output::render_timings(&timings::report(started.elapsed()), format);
+            }
+            process::exit(code);
         }
     }
 }
 
-fn render_error(err: anyhow::Error, json: bool) {
-    if json {
-        match serde_json::to_string_pretty(&output::ErrorOutput {
-            error: err.to_string(),
-        }) {
-            Ok(s) => println!("{}", s),
-            Err(_) => eprintln!("Error: {}", err),
+/// Print `err` and return the process exit code for its kind.
+fn render_error(err: anyhow::Error, format: output::Format) -> i32 {
+    let kind = error::classify(&err);
+    match format {
+        output::Format::Json | output::Format::Yaml => {
+            let out = output::ErrorOutput {
+                error: err.to_string(),
+                code: kind.code().to_string(),
+            };
+            if output::print_structured(&out, format, None).is_err() {
+                eprintln!("Error: {}", err);
+            }
         }
-    } else {
-        eprintln!("Error: {}", err);
+        // CSV consumers expect rows on stdout; errors go to stderr as text.
+        output::Format::Table | output::Format::Csv => eprintln!("Error: {}", err),
     }
+    kind.exit_code()
 }
@@ -0,0 +1,119 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+
+use crate::workspace::Metadata;
+
+/// A git hook wsp installs in clones, told apart from the user's own hooks
+/// by a marker line in its script.
+pub struct ManagedHook {
+    /// The git hook it's installed as, e.g. `pre-push`.
+    pub name: &'static str,
+    /// Marks the hook as wsp's, so it can be updated or removed without
+    /// touching hooks the user wrote. The script must contain it.
+    pub marker: &'static str,
+    pub script: &'static str,
+    /// What messages call it, e.g. "push guard hook".
+    pub label: &'static str,
+}
+
+impl ManagedHook {
+    pub fn path(&self, clone_dir: &Path) -> PathBuf {
+        clone_dir.join(".git").join("hooks").join(self.name)
+    }
+
+    /// Whether the clone has this hook.
+    pub fn is_installed(&self, clone_dir: &Path) -> bool {
+        fs::read_to_string(self.path(clone_dir)).is_ok_and(|s| s.contains(self.marker))
+    }
+
+    /// Whether the clone has a hook of the same name that isn't wsp's.
+    pub fn has_other_hook(&self, clone_dir: &Path) -> bool {
+        self.path(clone_dir).exists() && !self.is_installed(clone_dir)
+    }
+
+    /// Installs the hook in a clone. A hook of the user's own is left in
+    /// place and reported as an error.
+    pub fn install(&self, clone_dir: &Path) -> Result<()> {
+        if self.has_other_hook(clone_dir) {
+            bail!("has its own {} hook; not replacing it", self.name);
+        }
+        let path = self.path(clone_dir);
+        let dir = path.parent().context("hook path has no parent")?;
+        fs::create_dir_all(dir)?;
+        fs::write(&path, self.script).with_context(|| format!("writing {}", path.display()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+        }
+        Ok(())
+    }
+
+    /// Removes the hook from a clone, if it's wsp's.
+    pub fn uninstall(&self, clone_dir: &Path) -> Result<()> {
+        if self.is_installed(clone_dir) {
+            fs::remove_file(self.path(clone_dir))?;
+        }
+        Ok(())
+    }
+
+    /// Installs the hook in each clone, warning about the ones that fail.
+    /// If `only` is Some, only the listed identities get it.
+    pub fn install_all(&self, ws_dir: &Path, meta: &Metadata, only: Option<&[String]>) {
+        for info in meta.repo_infos(ws_dir) {
+            if only.is_some_and(|filter| !filter.contains(&info.identity)) {
+                continue;
+            }
+            if info.error.is_some() || !info.clone_dir.join(".git").is_dir() {
+                continue;
+            }
+            if let Err(e) = self.install(&info.clone_dir) {
+                eprintln!(
+                    "  warning: {} not installed in {}: {}",
+                    self.label, info.dir_name, e
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOOK: ManagedHook = ManagedHook {
+        name: "post-checkout",
+        marker: "# wsp: test hook",
+        script: "#!/bin/sh\n# wsp: test hook\nexit 0\n",
+        label: "test hook",
+    };
+
+    #[test]
+    fn test_install_and_uninstall() {
+        let (clone, _, _clone_tmp, _source_tmp) = crate::testutil::setup_clone_repo();
+        assert!(!HOOK.is_installed(&clone));
+        HOOK.install(&clone).unwrap();
+        assert!(HOOK.is_installed(&clone));
+        assert!(!HOOK.has_other_hook(&clone));
+        // Installing again rewrites wsp's own hook.
+        HOOK.install(&clone).unwrap();
+
+        HOOK.uninstall(&clone).unwrap();
+        assert!(!HOOK.path(&clone).exists());
+    }
+
+    #[test]
+    fn test_install_keeps_user_hook() {
+        let (clone, _, _clone_tmp, _source_tmp) = crate::testutil::setup_clone_repo();
+        let path = HOOK.path(&clone);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "#!/bin/sh\nexit 0\n").unwrap();
+
+        assert!(HOOK.has_other_hook(&clone));
+        assert!(HOOK.install(&clone).is_err());
+        HOOK.uninstall(&clone).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "#!/bin/sh\nexit 0\n");
+    }
+}
@@ -0,0 +1,241 @@
+//! Upgrades for on-disk formats (`.wsp.yaml`, `config.yaml`) whose layout
+//! changed between versions.
+//!
+//! Each file carries a top-level `version` (absent means 0). A [`Migration`]
+//! rewrites the raw YAML of one version into the next; [`load`] runs the
+//! ones a file needs in memory, so read-only commands never write. The
+//! upgraded layout reaches disk the next time a command saves the file under
+//! its lock, and [`back_up_older`] keeps the original as `<file>.v<N>.bak`
+//! first. Files from a newer wsp are refused rather than half-read.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde_yaml_ng::{Mapping, Value};
+
+/// Rewrites a file of version `from` into the layout of `from + 1`.
+pub struct Migration {
+    pub from: u32,
+    pub apply: fn(&mut Mapping) -> Result<()>,
+}
+
+/// Migrations for `.wsp.yaml`, oldest first.
+pub const METADATA: &[Migration] = &[];
+
+/// Migrations for `config.yaml`, oldest first.
+pub const CONFIG: &[Migration] = &[];
+
+/// Reads a versioned YAML file, upgraded to `current` if it's older. The
+/// file itself is left alone. `what` names the file in messages.
+pub fn load(path: &Path, current: u32, migrations: &[Migration], what: &str) -> Result<String> {
+    let data = crate::util::read_yaml_file(path)?;
+    match upgrade(&data, current, migrations, what)? {
+        Some((_, upgraded)) => Ok(upgraded),
+        None => Ok(data),
+    }
+}
+
+/// Copies `path` to its `.v<N>.bak` if it's older than `current`, before a
+/// save replaces it with the current layout. Callers hold the file's lock.
+pub fn back_up_older(path: &Path, current: u32, what: &str) -> Result<()> {
+    let Ok(data) = fs::read_to_string(path) else {
+        return Ok(());
+    };
+    let from = match version(&data) {
+        Some(v) if v < current => v,
+        _ => return Ok(()),
+    };
+    let backup = backup_path(path, from);
+    fs::copy(path, &backup).with_context(|| format!("backing up {}", path.display()))?;
+    eprintln!(
+        "Upgraded {} from version {} to {} (previous version saved as {})",
+        what,
+        from,
+        current,
+        backup.display()
+    );
+    Ok(())
+}
+
+/// The `version` of a YAML document, 0 if absent; None if unreadable.
+fn version(data: &str) -> Option<u32> {
+    let doc = serde_yaml_ng::from_str::<Value>(data).ok()?;
+    match doc.as_mapping()?.get("version") {
+        None => Some(0),
+        Some(v) => v.as_u64().and_then(|v| u32::try_from(v).ok()),
+    }
+}
+
+/// Where [`load`] keeps the version-`from` original of `path`.
+pub fn backup_path(path: &Path, from: u32) -> PathBuf {
+    let mut p = path.as_os_str().to_os_string();
+    p.push(format!(".v{}.bak", from));
+    PathBuf::from(p)
+}
+
+/// Applies the migrations `data` needs to reach `current`. Returns the
+/// version it started at and the upgraded YAML, or None if it's current.
+fn upgrade(
+    data: &str,
+    current: u32,
+    migrations: &[Migration],
+    what: &str,
+) -> Result<Option<(u32, String)>> {
+    // Malformed files are left for deserialization to report.
+    let Ok(mut doc) = serde_yaml_ng::from_str::<Value>(data) else {
+        return Ok(None);
+    };
+    let Some(map) = doc.as_mapping_mut() else {
+        return Ok(None);
+    };
+    let from = match map.get("version") {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .with_context(|| format!("{} has an invalid version {:?}", what, v))?,
+    };
+    if from > current {
+        bail!(
+            "{} has version {}, but this wsp only understands up to version {}; upgrade wsp to use it",
+            what,
+            from,
+            current
+        );
+    }
+    if from == current {
+        return Ok(None);
+    }
+
+    for version in from..current {
+        let Some(m) = migrations.iter().find(|m| m.from == version) else {
+            bail!(
+                "no upgrade for {} from version {} to {}",
+                what,
+                version,
+                version + 1
+            );
+        };
+        (m.apply)(map).with_context(|| {
+            format!(
+                "upgrading {} from version {} to {}",
+                what,
+                version,
+                version + 1
+            )
+        })?;
+    }
+    map.insert("version".into(), current.into());
+    Ok(Some((from, serde_yaml_ng::to_string(&doc)?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rename_title(map: &mut Mapping) -> Result<()> {
+        if let Some(v) = map.remove("title") {
+            map.insert("description".into(), v);
+        }
+        Ok(())
+    }
+
+    fn split_repos(map: &mut Mapping) -> Result<()> {
+        let Some(Value::String(list)) = map.remove("repos") else {
+            bail!("repos must be a string");
+        };
+        let repos: Vec<Value> = list.split(',').map(|s| s.trim().into()).collect();
+        map.insert("repos".into(), Value::Sequence(repos));
+        Ok(())
+    }
+
+    const MIGRATIONS: &[Migration] = &[
+        Migration {
+            from: 0,
+            apply: rename_title,
+        },
+        Migration {
+            from: 1,
+            apply: split_repos,
+        },
+    ];
+
+    #[test]
+    fn test_upgrade() {
+        let cases = vec![
+            (
+                "title: x\nrepos: a, b\n",
+                Some((0, "description: x\nrepos:\n- a\n- b\nversion: 2\n")),
+            ),
+            (
+                "version: 1\ndescription: x\nrepos: a\n",
+                Some((1, "version: 2\ndescription: x\nrepos:\n- a\n")),
+            ),
+            ("version: 2\nrepos: [a]\n", None),
+        ];
+        for (data, want) in cases {
+            let got = upgrade(data, 2, MIGRATIONS, "test.yaml").unwrap();
+            let got = got.as_ref().map(|(v, s)| (*v, s.as_str()));
+            assert_eq!(got, want, "{}", data);
+        }
+    }
+
+    #[test]
+    fn test_upgrade_errors() {
+        let cases = vec![
+            ("version: 3\n", "upgrade wsp"),
+            ("version: -1\n", "invalid version"),
+            ("version: 1\nrepos: [a]\n", "from version 1 to 2"),
+        ];
+        for (data, want) in cases {
+            let err = format!(
+                "{:#}",
+                upgrade(data, 2, MIGRATIONS, "test.yaml").unwrap_err()
+            );
+            assert!(err.contains(want), "{}: {}", data, err);
+        }
+        let err = upgrade("version: 0\n", 1, &[], "test.yaml").unwrap_err();
+        assert!(err.to_string().contains("no upgrade"), "{}", err);
+    }
+
+    #[test]
+    fn test_load_leaves_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("test.yaml");
+        fs::write(&path, "title: x\nrepos: a\n").unwrap();
+
+        let data = load(&path, 2, MIGRATIONS, "test.yaml").unwrap();
+        assert!(data.contains("version: 2"), "{}", data);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "title: x\nrepos: a\n");
+        assert!(!backup_path(&path, 0).exists());
+    }
+
+    #[test]
+    fn test_back_up_older() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("test.yaml");
+        let cases = vec![
+            ("title: x\n", Some(0)),
+            ("version: 1\ntitle: x\n", Some(1)),
+            ("version: 2\ntitle: x\n", None),
+            ("version: 3\ntitle: x\n", None),
+        ];
+        for (data, want) in cases {
+            fs::write(&path, data).unwrap();
+            back_up_older(&path, 2, "test.yaml").unwrap();
+            for v in 0..4 {
+                let backup = backup_path(&path, v);
+                if want == Some(v) {
+                    assert_eq!(fs::read_to_string(&backup).unwrap(), data);
+                    fs::remove_file(&backup).unwrap();
+                } else {
+                    assert!(!backup.exists(), "{}: v{}", data, v);
+                }
+            }
+        }
+
+        // Nothing to back up before the first save.
+        back_up_older(&tmp.path().join("missing.yaml"), 2, "test.yaml").unwrap();
+    }
+}
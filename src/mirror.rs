@@ -1,8 +1,13 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 
+use crate::filelock;
 use crate::git;
 use crate::giturl::Parsed;
 
@@ -11,22 +16,140 @@ pub fn dir(mirrors_dir: &Path, parsed: &Parsed) -> PathBuf {
 }
 
 pub fn clone(mirrors_dir: &Path, parsed: &Parsed, url: &str) -> Result<()> {
+    clone_within(mirrors_dir, parsed, url, None, &|_| {})
+}
+
+/// [`clone`], giving up on git after `timeout` and passing on its progress.
+pub fn clone_within(
+    mirrors_dir: &Path,
+    parsed: &Parsed,
+    url: &str,
+    timeout: Option<Duration>,
+    on_progress: &dyn Fn(&git::Transfer),
+) -> Result<()> {
+    crate::offline::ensure_online(&format!("cloning {}", url))?;
     let dest = dir(mirrors_dir, parsed);
     if let Some(parent) = dest.parent() {
         fs::create_dir_all(parent)?;
     }
-    git::clone_bare(url, &dest)?;
+    let _lock = filelock::lock_mirror(&dest)?;
+    let reference = reference_for(mirrors_dir, parsed);
+    git::clone_bare_with(url, &dest, reference.as_deref(), timeout, on_progress)?;
+    if let Some(reference) = reference
+        && !borrows_from(&dest).is_empty()
+    {
+        if !shares_history(&dest, &reference) {
+            // Only named like a fork: with no commits in common git had
+            // nothing to reuse, so the link is unused.
+            fs::remove_file(alternates_file(&dest))?;
+        } else if lend(&reference).is_err() {
+            // A fork that can't be protected from pruning isn't safe to
+            // borrow from.
+            dissociate(&dest)?;
+        }
+    }
     git::configure_fetch_refspec(&dest)
 }
 
 /// Fetch a mirror with pruning enabled.
 pub fn fetch(mirrors_dir: &Path, parsed: &Parsed) -> Result<()> {
-    let d = dir(mirrors_dir, parsed);
-    git::fetch(&d, true)
+    fetch_dir(&dir(mirrors_dir, parsed), true)
+}
+
+/// Fetch the mirror at `mirror_dir`, holding its lock so a concurrent wsp
+/// command waits rather than racing on the same refs.
+pub fn fetch_dir(mirror_dir: &Path, prune: bool) -> Result<()> {
+    fetch_dir_within(mirror_dir, prune, None, &|_| {}).map(|_| ())
 }
 
+/// Records when the last successful fetch of a mirror started, and whether
+/// it pruned, so fetches queued behind it can tell if it covers them and
+/// `--max-age` can tell how fresh the mirror is.
+const FETCHED_FILE: &str = "wsp-fetched";
+
+/// A mutex per mirror, so threads of one process wanting the same mirror
+/// queue up here instead of polling its lock file.
+static IN_PROCESS: Mutex<BTreeMap<PathBuf, Arc<Mutex<()>>>> = Mutex::new(BTreeMap::new());
+
+/// [`fetch_dir`], giving up on git after `timeout` (time spent waiting for
+/// the lock doesn't count) and passing on git's progress.
+///
+/// Besides the cross-process lock, threads of this process queue on a
+/// mutex per mirror. A fetch that started after this one was asked for,
+/// and finished while it waited, already brought in everything this one
+/// would: it is skipped. Returns what git transferred, `None` when it
+/// didn't run.
+pub fn fetch_dir_within(
+    mirror_dir: &Path,
+    prune: bool,
+    timeout: Option<Duration>,
+    on_progress: &dyn Fn(&git::Transfer),
+) -> Result<Option<git::Transfer>> {
+    crate::offline::ensure_online("fetching")?;
+    let requested = SystemTime::now();
+    let mutex = IN_PROCESS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry(mirror_dir.to_path_buf())
+        .or_default()
+        .clone();
+    let _guard = mutex.lock().unwrap_or_else(|e| e.into_inner());
+    let _lock = filelock::lock_mirror(mirror_dir)?;
+
+    if let Some((started, pruned)) = last_fetch_started(mirror_dir)
+        && started >= requested
+        && (pruned || !prune)
+    {
+        tracing::debug!("{}: fetched while waiting, skipping", mirror_dir.display());
+        return Ok(None);
+    }
+    let started = SystemTime::now();
+    let transfer = git::fetch(mirror_dir, prune, timeout, on_progress)?;
+    // Losing it only costs a fetch the next time one is asked for.
+    let _ = mark_fetched(mirror_dir, started, prune);
+    Ok(Some(transfer))
+}
+
+/// Writes the [`FETCHED_FILE`] stamp for a fetch that started at `started`.
+pub(crate) fn mark_fetched(mirror_dir: &Path, started: SystemTime, prune: bool) -> Result<()> {
+    let nanos = started
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    let stamp = if prune {
+        format!("{} prune\n", nanos)
+    } else {
+        format!("{}\n", nanos)
+    };
+    fs::write(mirror_dir.join(FETCHED_FILE), stamp)?;
+    Ok(())
+}
+
+fn last_fetch_started(mirror_dir: &Path) -> Option<(SystemTime, bool)> {
+    let text = fs::read_to_string(mirror_dir.join(FETCHED_FILE)).ok()?;
+    let mut fields = text.split_whitespace();
+    let nanos: u64 = fields.next()?.parse().ok()?;
+    let pruned = fields.next() == Some("prune");
+    Some((SystemTime::UNIX_EPOCH + Duration::from_nanos(nanos), pruned))
+}
+
+/// When the last successful fetch of the mirror from upstream started, by
+/// whichever command. Not `FETCH_HEAD`'s time: git rewrites it even when a
+/// fetch fails. `None` for a mirror that has only been cloned.
+pub fn last_fetched(mirrors_dir: &Path, parsed: &Parsed) -> Option<DateTime<Utc>> {
+    let (started, _) = last_fetch_started(&dir(mirrors_dir, parsed))?;
+    Some(started.into())
+}
+
+/// Removes the mirror, first giving the forks that borrow objects from it
+/// their own copies.
 pub fn remove(mirrors_dir: &Path, parsed: &Parsed) -> Result<()> {
     let d = dir(mirrors_dir, parsed);
+    for fork in borrowers(&d, forks(mirrors_dir, parsed)) {
+        let _lock = filelock::lock_mirror(&fork)?;
+        dissociate(&fork)
+            .map_err(|e| anyhow::anyhow!("copying objects into {}: {}", fork.display(), e))?;
+    }
+    let _lock = filelock::lock_mirror(&d)?;
     match fs::remove_dir_all(d) {
         Ok(()) => Ok(()),
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
@@ -38,6 +161,137 @@ pub fn exists(mirrors_dir: &Path, parsed: &Parsed) -> bool {
     dir(mirrors_dir, parsed).exists()
 }
 
+/// The other mirrors of repos named `parsed.repo` on the same host, which
+/// are most likely forks of one another. Sorted.
+pub fn forks(mirrors_dir: &Path, parsed: &Parsed) -> Vec<PathBuf> {
+    let own = dir(mirrors_dir, parsed);
+    let name = format!("{}.git", parsed.repo);
+    let mut found = Vec::new();
+    // Owners can nest (GitLab subgroups), so walk down to the mirrors.
+    let mut pending = vec![mirrors_dir.join(&parsed.host)];
+    while let Some(d) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&d) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+                continue;
+            }
+            let path = entry.path();
+            if !entry.file_name().to_string_lossy().ends_with(".git") {
+                pending.push(path);
+            } else if entry.file_name() == name.as_str() && path != own {
+                found.push(path);
+            }
+        }
+    }
+    found.sort();
+    found
+}
+
+/// A fork a new mirror of `parsed` can borrow objects from instead of
+/// downloading them again. Only mirrors that borrow nothing themselves
+/// qualify, so no mirror is more than one link from its objects.
+pub fn reference_for(mirrors_dir: &Path, parsed: &Parsed) -> Option<PathBuf> {
+    forks(mirrors_dir, parsed)
+        .into_iter()
+        .find(|d| borrows_from(d).is_empty())
+}
+
+/// A repo's object store: `objects` in a mirror, `.git/objects` in a clone.
+fn objects_dir(repo_dir: &Path) -> PathBuf {
+    let dot_git = repo_dir.join(".git");
+    if dot_git.is_dir() {
+        dot_git.join("objects")
+    } else {
+        repo_dir.join("objects")
+    }
+}
+
+fn alternates_file(repo_dir: &Path) -> PathBuf {
+    objects_dir(repo_dir).join("info").join("alternates")
+}
+
+/// The object stores a mirror or clone borrows from, per its
+/// `objects/info/alternates`.
+pub fn borrows_from(repo_dir: &Path) -> Vec<PathBuf> {
+    let Ok(text) = fs::read_to_string(alternates_file(repo_dir)) else {
+        return Vec::new();
+    };
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        // Relative entries are relative to the object store.
+        .map(|l| objects_dir(repo_dir).join(l))
+        .collect()
+}
+
+/// Those of `candidates` (mirrors or clones) that borrow objects from the
+/// mirror at `mirror_dir`.
+pub fn borrowers(mirror_dir: &Path, candidates: impl IntoIterator<Item = PathBuf>) -> Vec<PathBuf> {
+    let objects = objects_dir(mirror_dir);
+    let Ok(objects) = objects.canonicalize() else {
+        return Vec::new();
+    };
+    candidates
+        .into_iter()
+        .filter(|c| {
+            borrows_from(c)
+                .iter()
+                .any(|b| b.canonicalize().is_ok_and(|b| b == objects))
+        })
+        .collect()
+}
+
+/// Keeps `git gc` in a mirror that forks borrow from from pruning objects
+/// that became unreachable there: a fork may still reach them.
+fn lend(mirror_dir: &Path) -> Result<()> {
+    git::set_config(mirror_dir, "gc.pruneExpire", "never")
+}
+
+/// Switches an existing mirror to borrowing from `reference` the objects
+/// the two share, dropping its own copies. Returns the bytes freed.
+pub fn borrow(mirror_dir: &Path, reference: &Path) -> Result<u64> {
+    let _lock = filelock::lock_mirror(mirror_dir)?;
+    lend(reference)?;
+    let before = crate::du::dir_size(&objects_dir(mirror_dir));
+    let reference = objects_dir(reference).canonicalize()?;
+    let alternates = alternates_file(mirror_dir);
+    fs::write(&alternates, format!("{}\n", reference.display()))?;
+    // -l leaves out what the reference has. Until -d swaps the packs the
+    // old ones are intact, so a failure only needs the link undone.
+    if let Err(e) = git::run(Some(mirror_dir), &["repack", "-a", "-d", "-l", "-q"]) {
+        let _ = fs::remove_file(&alternates);
+        return Err(e);
+    }
+    Ok(before.saturating_sub(crate::du::dir_size(&objects_dir(mirror_dir))))
+}
+
+/// Stops a mirror or clone borrowing objects: copies in everything it
+/// borrows, then drops its alternates.
+pub fn dissociate(repo_dir: &Path) -> Result<()> {
+    git::run(Some(repo_dir), &["repack", "-a", "-d", "-q"])?;
+    match fs::remove_file(alternates_file(repo_dir)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Whether two mirrors' default branches start from a common root commit,
+/// as a fork and its upstream do.
+pub fn shares_history(a: &Path, b: &Path) -> bool {
+    let roots = |d: &Path| {
+        git::run(Some(d), &["rev-list", "--max-parents=0", "HEAD"])
+            .map(|out| {
+                out.lines()
+                    .map(str::to_string)
+                    .collect::<std::collections::BTreeSet<_>>()
+            })
+            .unwrap_or_default()
+    };
+    !roots(a).is_disjoint(&roots(b))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -88,6 +342,21 @@ mod tests {
         let d = dir(&mirrors_dir, &parsed);
         assert!(d.exists());
 
+        // A failed fetch doesn't count.
+        let fetched = last_fetched(&mirrors_dir, &parsed);
+        git::run(
+            Some(&d),
+            &["remote", "set-url", "origin", "/nonexistent/repo"],
+        )
+        .unwrap();
+        assert!(fetch(&mirrors_dir, &parsed).is_err());
+        assert_eq!(last_fetched(&mirrors_dir, &parsed), fetched);
+        git::run(
+            Some(&d),
+            &["remote", "set-url", "origin", repo.path().to_str().unwrap()],
+        )
+        .unwrap();
+
         let refspecs = git::run(Some(&d), &["config", "--get-all", "remote.origin.fetch"]).unwrap();
         assert!(
             refspecs.contains("+refs/heads/*:refs/heads/*"),
@@ -112,6 +381,7 @@ mod tests {
         };
 
         clone(&mirrors_dir, &parsed, repo.path().to_str().unwrap()).unwrap();
+        assert!(last_fetched(&mirrors_dir, &parsed).is_none());
 
         // Remove refspec to simulate a pre-fix bare clone
         let d = dir(&mirrors_dir, &parsed);
@@ -119,7 +389,9 @@ mod tests {
         assert!(git::run(Some(&d), &["config", "--get", "remote.origin.fetch"]).is_err());
 
         // Fetch should auto-configure the missing refspec
+        let before = Utc::now() - chrono::Duration::seconds(5);
         fetch(&mirrors_dir, &parsed).unwrap();
+        assert!(last_fetched(&mirrors_dir, &parsed).is_some_and(|t| t > before));
 
         let refspecs = git::run(Some(&d), &["config", "--get-all", "remote.origin.fetch"]).unwrap();
         assert!(
@@ -132,6 +404,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_concurrent_fetches_coalesce() {
+        let tmp_data = tempfile::tempdir().unwrap();
+        let mirrors_dir = tmp_data.path().join("mirrors");
+
+        let repo = create_test_repo();
+        let parsed = Parsed {
+            host: "test.local".into(),
+            owner: "user".into(),
+            repo: "test-repo".into(),
+        };
+        clone(&mirrors_dir, &parsed, repo.path().to_str().unwrap()).unwrap();
+        let d = dir(&mirrors_dir, &parsed);
+
+        // Asked for one after another, both fetch.
+        assert!(fetch_dir_within(&d, true, None, &|_| {}).unwrap().is_some());
+        assert!(
+            fetch_dir_within(&d, false, None, &|_| {})
+                .unwrap()
+                .is_some()
+        );
+
+        // Asked for while another command holds the mirror: the first to
+        // get it fetches for both.
+        let held = filelock::lock_mirror(&d).unwrap();
+        let ran: Vec<bool> = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..2)
+                .map(|_| s.spawn(|| fetch_dir_within(&d, true, None, &|_| {}).unwrap().is_some()))
+                .collect();
+            std::thread::sleep(Duration::from_millis(200));
+            drop(held);
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(ran.iter().filter(|&&r| r).count(), 1, "{:?}", ran);
+
+        // A fetch without prune doesn't cover one that wants it.
+        let held = filelock::lock_mirror(&d).unwrap();
+        let ran: Vec<bool> = std::thread::scope(|s| {
+            let plain = s.spawn(|| {
+                fetch_dir_within(&d, false, None, &|_| {})
+                    .unwrap()
+                    .is_some()
+            });
+            std::thread::sleep(Duration::from_millis(100));
+            let pruning = s.spawn(|| fetch_dir_within(&d, true, None, &|_| {}).unwrap().is_some());
+            std::thread::sleep(Duration::from_millis(100));
+            drop(held);
+            vec![plain.join().unwrap(), pruning.join().unwrap()]
+        });
+        assert_eq!(ran, vec![true, true]);
+    }
+
     #[test]
     fn test_remove() {
         let tmp_data = tempfile::tempdir().unwrap();
@@ -151,6 +475,89 @@ mod tests {
         assert!(!exists(&mirrors_dir, &parsed));
     }
 
+    #[test]
+    fn test_fork_borrows_from_mirror() {
+        let tmp_data = tempfile::tempdir().unwrap();
+        let mirrors_dir = tmp_data.path().join("mirrors");
+        let repo = create_test_repo();
+        let url = repo.path().to_str().unwrap();
+        let upstream = Parsed::from_identity("test.local/acme/test-repo").unwrap();
+        let fork = Parsed::from_identity("test.local/alice/test-repo").unwrap();
+        let other = Parsed::from_identity("test.local/acme/other-repo").unwrap();
+
+        clone(&mirrors_dir, &upstream, url).unwrap();
+        assert_eq!(reference_for(&mirrors_dir, &other), None);
+        assert_eq!(
+            reference_for(&mirrors_dir, &fork),
+            Some(dir(&mirrors_dir, &upstream))
+        );
+
+        // Same name, unrelated history: nothing is borrowed or lent.
+        let namesake = Parsed::from_identity("test.local/bob/test-repo").unwrap();
+        let unrelated = create_test_repo();
+        git::run(
+            Some(unrelated.path()),
+            &["commit", "--amend", "--allow-empty", "-m", "another root"],
+        )
+        .unwrap();
+        clone(&mirrors_dir, &namesake, unrelated.path().to_str().unwrap()).unwrap();
+        assert!(borrows_from(&dir(&mirrors_dir, &namesake)).is_empty());
+        assert!(git::get_config(&dir(&mirrors_dir, &upstream), "gc.pruneExpire").is_err());
+        remove(&mirrors_dir, &namesake).unwrap();
+
+        clone(&mirrors_dir, &fork, url).unwrap();
+        let fork_dir = dir(&mirrors_dir, &fork);
+        let upstream_dir = dir(&mirrors_dir, &upstream);
+        assert_eq!(
+            borrowers(&upstream_dir, forks(&mirrors_dir, &upstream)),
+            vec![fork_dir.clone()]
+        );
+        assert_eq!(
+            git::get_config(&upstream_dir, "gc.pruneExpire").unwrap(),
+            "never"
+        );
+
+        // Removing the lender leaves the fork whole.
+        remove(&mirrors_dir, &upstream).unwrap();
+        assert!(borrows_from(&fork_dir).is_empty());
+        git::run(Some(&fork_dir), &["fsck", "--connectivity-only"]).unwrap();
+    }
+
+    #[test]
+    fn test_borrow() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = create_test_repo();
+        let url = repo.path().to_str().unwrap();
+        let upstream = tmp.path().join("upstream.git");
+        let fork = tmp.path().join("fork.git");
+        let unrelated = tmp.path().join("unrelated.git");
+        git::clone_bare(url, &upstream).unwrap();
+        git::clone_bare(url, &fork).unwrap();
+        let other = create_test_repo();
+        git::run(
+            Some(other.path()),
+            &["commit", "--amend", "--allow-empty", "-m", "another root"],
+        )
+        .unwrap();
+        git::clone_bare(other.path().to_str().unwrap(), &unrelated).unwrap();
+
+        assert!(shares_history(&fork, &upstream));
+        assert!(!shares_history(&unrelated, &upstream));
+
+        // Mirrors keep what they clone and fetch in packs.
+        for d in [&upstream, &fork] {
+            git::run(Some(d), &["repack", "-a", "-d", "-q"]).unwrap();
+        }
+        let freed = borrow(&fork, &upstream).unwrap();
+        assert!(freed > 0);
+        assert_eq!(borrowers(&upstream, [fork.clone()]), vec![fork.clone()]);
+        git::run(Some(&fork), &["fsck", "--connectivity-only"]).unwrap();
+
+        dissociate(&fork).unwrap();
+        assert!(borrows_from(&fork).is_empty());
+        git::run(Some(&fork), &["fsck", "--connectivity-only"]).unwrap();
+    }
+
     #[test]
     fn test_dir() {
         let mirrors_dir = Path::new("/data/ws/mirrors");
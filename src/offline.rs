@@ -0,0 +1,127 @@
+//! Offline mode (`--offline` or `WSP_OFFLINE`): commands skip fetching and
+//! work from what the mirrors last fetched, and operations that can only be
+//! done over the network fail up front instead of timing out. A command's
+//! own `--no-fetch` skips just its fetch; [`FetchPolicy`] combines the two.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{Result, bail};
+
+/// Environment variable that turns offline mode on when `--offline` isn't
+/// given.
+pub const ENV: &str = "WSP_OFFLINE";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Turn offline mode on for the rest of the process.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Whether a `WSP_OFFLINE` value turns offline mode on. Empty, `0` and
+/// `false` leave it off, so the variable can be overridden per command.
+pub fn env_enables(value: &str) -> bool {
+    !matches!(value.trim().to_lowercase().as_str(), "" | "0" | "false")
+}
+
+/// Fails when offline. `what` is the operation, e.g. "pushing branches".
+pub fn ensure_online(what: &str) -> Result<()> {
+    if enabled() {
+        bail!(
+            "{} needs the network; not available offline (--offline / {})",
+            what,
+            ENV
+        );
+    }
+    Ok(())
+}
+
+/// Whether a command fetches from upstream before it works.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FetchPolicy {
+    Fetch,
+    /// The command's `--no-fetch`.
+    NoFetch,
+    /// Offline mode, whatever the command's flags.
+    Offline,
+}
+
+impl FetchPolicy {
+    /// The policy of a command given its `--no-fetch` flag.
+    pub fn from_flag(no_fetch: bool) -> FetchPolicy {
+        if enabled() {
+            FetchPolicy::Offline
+        } else if no_fetch {
+            FetchPolicy::NoFetch
+        } else {
+            FetchPolicy::Fetch
+        }
+    }
+
+    pub fn fetches(self) -> bool {
+        self == FetchPolicy::Fetch
+    }
+
+    /// Why nothing was fetched, for messages and JSON: `offline` or
+    /// `no-fetch`. None when fetching.
+    pub fn skip_reason(self) -> Option<&'static str> {
+        match self {
+            FetchPolicy::Fetch => None,
+            FetchPolicy::NoFetch => Some("no-fetch"),
+            FetchPolicy::Offline => Some("offline"),
+        }
+    }
+
+    /// Tells the user that fetching was skipped, so what follows reflects
+    /// the mirrors as of their last fetch.
+    pub fn note_skipped(self) {
+        match self {
+            FetchPolicy::Fetch => {}
+            FetchPolicy::NoFetch => {
+                eprintln!("Not fetching (--no-fetch); using refs from the last fetch")
+            }
+            FetchPolicy::Offline => {
+                eprintln!("Offline: not fetching; using refs from the last fetch (may be stale)")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fetch_policy() {
+        // Offline mode is off in tests, so only the flag decides.
+        let cases = vec![
+            (false, FetchPolicy::Fetch, None),
+            (true, FetchPolicy::NoFetch, Some("no-fetch")),
+        ];
+        for (no_fetch, want, reason) in cases {
+            let got = FetchPolicy::from_flag(no_fetch);
+            assert_eq!(got, want, "{}", no_fetch);
+            assert_eq!(got.fetches(), !no_fetch);
+            assert_eq!(got.skip_reason(), reason);
+        }
+    }
+
+    #[test]
+    fn test_env_enables() {
+        let cases = vec![
+            ("1", true),
+            ("true", true),
+            ("yes", true),
+            ("", false),
+            ("0", false),
+            ("FALSE", false),
+        ];
+        for (value, want) in cases {
+            assert_eq!(env_enables(value), want, "{:?}", value);
+        }
+    }
+}
@@ -1,8 +1,10 @@
-use std::io::Write;
+use std::collections::BTreeMap;
+use std::io::{IsTerminal, Write};
 use std::path::PathBuf;
 
 use anyhow::{Result, bail};
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::Serialize;
 use tabwriter::TabWriter;
 
@@ -48,6 +50,60 @@ impl Table {
     }
 }
 
+/// A table printed a row at a time, for output that streams: columns are
+/// padded to widths fixed up front rather than measured over every row.
+/// A cell wider than its column pushes the rest of its row over.
+pub struct RowStream {
+    widths: Vec<usize>,
+}
+
+impl RowStream {
+    /// Prints the header. `widths` leave out the two spaces between
+    /// columns; the last column isn't padded.
+    pub fn new(headers: &[String], widths: Vec<usize>) -> std::io::Result<Self> {
+        let stream = RowStream { widths };
+        let upper: Vec<String> = headers.iter().map(|h| h.to_uppercase()).collect();
+        stream.row(&upper)?;
+        Ok(stream)
+    }
+
+    pub fn row(&self, cells: &[String]) -> std::io::Result<()> {
+        let mut line = String::new();
+        for (i, cell) in cells.iter().enumerate() {
+            line.push_str(cell);
+            if i + 1 < cells.len() {
+                let width = self.widths.get(i).copied().unwrap_or(0);
+                let pad = width.saturating_sub(cell.chars().count()) + 2;
+                line.extend(std::iter::repeat_n(' ', pad));
+            }
+        }
+        writeln!(std::io::stdout().lock(), "{}", line)
+    }
+}
+
+/// Whether streamed output should go on after a write: a closed pipe
+/// (`wsp st | head -1`) ends it quietly, and any other failure ends it
+/// with a warning.
+pub fn keep_streaming(written: std::io::Result<()>) -> std::ops::ControlFlow<()> {
+    match written {
+        Ok(()) => std::ops::ControlFlow::Continue(()),
+        Err(e) => {
+            if e.kind() != std::io::ErrorKind::BrokenPipe {
+                tracing::warn!("writing output: {}", e);
+            }
+            std::ops::ControlFlow::Break(())
+        }
+    }
+}
+
+/// Whether `err` is a write to a closed pipe, which ends output quietly.
+pub fn is_broken_pipe(err: &anyhow::Error) -> bool {
+    err.chain().any(|e| {
+        e.downcast_ref::<std::io::Error>()
+            .is_some_and(|e| e.kind() == std::io::ErrorKind::BrokenPipe)
+    })
+}
+
 fn render_buf(headers: &[String], rows: &[Vec<String>]) -> Result<Vec<u8>> {
     let mut tw = TabWriter::new(Vec::new()).minwidth(0).padding(2);
 
@@ -92,6 +148,44 @@ pub fn format_repo_status(
     parts.join(", ")
 }
 
+/// The status column of `wsp st`: [`format_repo_status`] with modified and
+/// untracked files counted separately, plus unfinished git operations,
+/// stashes, and unpushed branches.
+fn repo_status_text(rs: &RepoStatusEntry) -> String {
+    // `git rev-parse --abbrev-ref HEAD` says "HEAD" when detached.
+    let detached = rs.branch == "HEAD";
+    let base = format_repo_status(
+        rs.ahead,
+        rs.behind,
+        rs.changed.saturating_sub(rs.untracked),
+        rs.has_upstream,
+        if detached { &None } else { &rs.expected_branch },
+    );
+    let mut parts = Vec::new();
+    if let Some(op) = &rs.in_progress {
+        parts.push(format!("{} in progress", op.to_uppercase()));
+    }
+    if detached && let Some(expected) = &rs.expected_branch {
+        parts.push(format!("detached HEAD (workspace branch: {})", expected));
+    }
+    if base != "clean" {
+        parts.push(base);
+    }
+    if rs.untracked > 0 {
+        parts.push(format!("{} untracked", rs.untracked));
+    }
+    if rs.stashes > 0 {
+        parts.push(format!("{} stashed", rs.stashes));
+    }
+    if !rs.pushed && rs.ahead > 0 {
+        parts.push("not pushed".to_string());
+    }
+    if parts.is_empty() {
+        return "clean".to_string();
+    }
+    parts.join(", ")
+}
+
 pub fn format_error(err: &dyn std::fmt::Display) -> String {
     format!("ERROR: {}", err)
 }
@@ -100,49 +194,58 @@ pub fn format_error(err: &dyn std::fmt::Display) -> String {
 // JSON-serializable output types
 // ---------------------------------------------------------------------------
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct RepoListOutput {
     pub repos: Vec<RepoListEntry>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct RepoListEntry {
     pub identity: String,
     pub shortname: String,
     pub url: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct TemplateListOutput {
     pub templates: Vec<TemplateListEntry>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct TemplateListEntry {
     pub name: String,
     pub repo_count: usize,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct TemplateShowOutput {
     pub name: String,
     pub repos: Vec<TemplateShowRepo>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct TemplateShowRepo {
     pub url: String,
     pub identity: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct WorkspaceListOutput {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub hint: Option<String>,
     pub workspaces: Vec<WorkspaceListEntry>,
+    /// Sum of `disk_bytes`, present with `wsp ls --du`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_disk_bytes: Option<u64>,
+    /// Workspaces on all pages, present with `wsp ls --limit`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<usize>,
+    /// Rows were already printed while the workspaces were checked.
+    #[serde(skip)]
+    pub streamed: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct WorkspaceListEntry {
     pub name: String,
     pub branch: String,
@@ -150,14 +253,55 @@ pub struct WorkspaceListEntry {
     pub path: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// Linked issue URL or key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issue: Option<String>,
     pub created: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_used: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_from: Option<String>,
+    /// Present with `wsp ls --status`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<WorkspaceStatusSummary>,
+    /// Size on disk, present with `wsp ls --du`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk_bytes: Option<u64>,
 }
 
-#[derive(Serialize)]
+/// Unfinished work across a workspace's repos.
+#[derive(Serialize, JsonSchema, Default, Debug, PartialEq)]
+pub struct WorkspaceStatusSummary {
+    /// Repos with uncommitted changes.
+    pub dirty: usize,
+    /// Commits ahead of upstream, summed over repos.
+    pub ahead: u32,
+    /// Repos that could not be checked (e.g. missing clone).
+    pub errors: usize,
+}
+
+impl WorkspaceStatusSummary {
+    /// "2 dirty, 5 ahead", or "clean" when there is nothing to report.
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if self.dirty > 0 {
+            parts.push(format!("{} dirty", self.dirty));
+        }
+        if self.ahead > 0 {
+            parts.push(format!("{} ahead", self.ahead));
+        }
+        if self.errors > 0 {
+            parts.push(format!("{} unchecked", self.errors));
+        }
+        if parts.is_empty() {
+            "clean".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
 pub struct StatusOutput {
     pub workspace: String,
     pub branch: String,
@@ -165,14 +309,28 @@ pub struct StatusOutput {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     pub created: DateTime<Utc>,
+    /// Oldest upstream fetch among the repos' mirrors; absent when none has
+    /// been fetched since it was cloned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fetched: Option<DateTime<Utc>>,
+    /// Why the counts come from the last fetch rather than one made for this
+    /// status: `no-fetch` (no `--fetch`) or `offline`. Absent after `--fetch`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fetch_skipped: Option<String>,
     pub repos: Vec<RepoStatusEntry>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub root: Vec<String>,
     #[serde(skip)]
     pub verbose: bool,
+    #[serde(skip)]
+    pub porcelain: bool,
+    /// The header and repo lines (porcelain or table) were already
+    /// printed while the repos were checked.
+    #[serde(skip)]
+    pub streamed: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct RepoStatusEntry {
     pub identity: String,
     pub shortname: String,
@@ -180,8 +338,17 @@ pub struct RepoStatusEntry {
     pub branch: String,
     pub ahead: u32,
     pub behind: u32,
+    /// Whether `ahead` and `behind` were measured; porcelain prints `-` for
+    /// them when not.
+    #[serde(skip)]
+    pub compared: bool,
     pub changed: u32,
+    /// Files counted in `changed` that git doesn't track yet.
+    pub untracked: u32,
+    pub stashes: u32,
     pub has_upstream: bool,
+    /// The branch exists on origin, i.e. it has been pushed at least once.
+    pub pushed: bool,
     pub role: String,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub files: Vec<String>,
@@ -190,9 +357,64 @@ pub struct RepoStatusEntry {
     /// Set when an active repo's HEAD is on a different branch than the workspace branch.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub expected_branch: Option<String>,
+    /// An unfinished git operation: rebase, merge, cherry-pick, revert, or bisect.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub in_progress: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
+pub struct InfoOutput {
+    pub workspace: String,
+    pub branch: String,
+    pub workspace_dir: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Linked issue URL or key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issue: Option<String>,
+    pub created: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_from: Option<String>,
+    pub repos: Vec<InfoRepoEntry>,
+    /// Repos that failed to clone and are waiting for `wsp repair`, by
+    /// identity, with the error.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub pending: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct InfoRepoEntry {
+    pub identity: String,
+    pub dir_name: String,
+    pub path: String,
+    pub role: String,
+    /// Ref recorded in `.wsp.yaml`, if any.
+    #[serde(rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub r#ref: Option<String>,
+    /// Checked-out branch (`HEAD` when detached).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha: Option<String>,
+    /// What ahead/behind are counted against: the tracking branch, or
+    /// `origin/<default>` when there is none (`has_upstream` false).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+    pub has_upstream: bool,
+    pub ahead: u32,
+    pub behind: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mirror: Option<String>,
+    pub mirror_exists: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
 pub struct DiffOutput {
     pub workspace: String,
     pub branch: String,
@@ -200,17 +422,60 @@ pub struct DiffOutput {
     pub repos: Vec<RepoDiffEntry>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct RepoDiffEntry {
     pub identity: String,
     pub shortname: String,
     pub path: String,
-    pub diff: String,
+    /// Unified diff text; omitted with `--no-patch`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+    /// Per-file line counts for the same diff (always present in JSON).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stat: Option<DiffStat>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
+pub struct DiffStat {
+    pub files_changed: u32,
+    pub insertions: u32,
+    pub deletions: u32,
+    pub files: Vec<DiffFileStat>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DiffFileStat {
+    pub path: String,
+    pub insertions: u32,
+    pub deletions: u32,
+    /// Binary files have no line counts.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub binary: bool,
+}
+
+impl DiffStat {
+    pub fn from_numstat(stats: Vec<crate::git::NumStat>) -> Self {
+        let files: Vec<DiffFileStat> = stats
+            .into_iter()
+            .map(|s| DiffFileStat {
+                binary: s.added.is_none(),
+                insertions: s.added.unwrap_or(0),
+                deletions: s.deleted.unwrap_or(0),
+                path: s.path,
+            })
+            .collect();
+        DiffStat {
+            files_changed: files.len() as u32,
+            insertions: files.iter().map(|f| f.insertions).sum(),
+            deletions: files.iter().map(|f| f.deletions).sum(),
+            files,
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
 pub struct LogOutput {
     pub workspace: String,
     pub branch: String,
@@ -220,7 +485,7 @@ pub struct LogOutput {
     pub repos: Vec<RepoLogEntry>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct RepoLogEntry {
     pub identity: String,
     pub shortname: String,
@@ -232,7 +497,7 @@ pub struct RepoLogEntry {
     pub error: Option<String>,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, JsonSchema, Clone)]
 pub struct LogCommit {
     pub hash: String,
     pub authored_at: String,
@@ -242,13 +507,52 @@ pub struct LogCommit {
     pub subject: String,
 }
 
-#[derive(Serialize)]
+/// `wsp context`: a briefing on the workspace for pasting into an LLM
+/// prompt. Rendered as Markdown by default.
+#[derive(Serialize, JsonSchema)]
+pub struct ContextOutput {
+    pub workspace: String,
+    pub branch: String,
+    pub workspace_dir: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issue: Option<String>,
+    pub repos: Vec<ContextRepoEntry>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ContextRepoEntry {
+    pub identity: String,
+    pub dir_name: String,
+    pub path: String,
+    pub role: String,
+    /// Ref recorded in `.wsp.yaml`, if any.
+    #[serde(rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub r#ref: Option<String>,
+    /// Checked-out branch (`HEAD` when detached).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// What ahead/behind are counted against.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    /// Uncommitted changes as `git status --short` lines.
+    pub changed_files: Vec<String>,
+    /// Most recent commits on HEAD, newest first.
+    pub recent_commits: Vec<LogCommit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
 pub struct ConfigListOutput {
     #[serde(rename = "settings")]
     pub entries: Vec<ConfigListEntry>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ConfigListEntry {
     pub key: String,
     pub value: String,
@@ -258,13 +562,13 @@ pub struct ConfigListEntry {
     pub experimental: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ConfigGetOutput {
     pub key: String,
     pub value: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct WorkspaceRepoListOutput {
     pub workspace: String,
     pub branch: String,
@@ -272,20 +576,20 @@ pub struct WorkspaceRepoListOutput {
     pub repos: Vec<WorkspaceRepoListEntry>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct WorkspaceRepoListEntry {
     pub identity: String,
     pub shortname: String,
     pub dir_name: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ExecOutput {
     pub workspace: String,
     pub repos: Vec<ExecRepoResult>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ExecRepoResult {
     pub identity: String,
     pub shortname: String,
@@ -301,22 +605,96 @@ pub struct ExecRepoResult {
     pub error: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
+pub struct RunOutput {
+    pub workspace: String,
+    pub task: String,
+    pub repos: Vec<RunRepoResult>,
+}
+
+/// One node of a task run: a task in one repo. `status` is `ok`, `failed`, or
+/// `skipped` (a dependency failed, so the command never ran).
+#[derive(Serialize, JsonSchema)]
+pub struct RunRepoResult {
+    pub task: String,
+    pub status: String,
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    #[serde(flatten)]
+    pub result: ExecRepoResult,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DepsGraphOutput {
+    /// Workspace whose config and repos were applied, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+    pub repos: Vec<DepsGraphRepo>,
+    /// Text syntax: `dot` or `mermaid`.
+    #[serde(skip)]
+    pub format: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DepsGraphRepo {
+    pub identity: String,
+    pub shortname: String,
+    pub in_workspace: bool,
+    pub depends_on: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct LayoutOutput {
+    pub workspace: String,
+    pub path: String,
+    pub panes: Vec<LayoutPane>,
+    /// Text syntax: `tmux` (tmuxinator YAML) or `zellij` (KDL).
+    #[serde(skip)]
+    pub kind: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct LayoutPane {
+    pub identity: String,
+    pub directory: String,
+    pub path: String,
+}
+
+#[derive(Serialize, JsonSchema)]
 pub struct FetchOutput {
     pub workspace: String,
     pub repos: Vec<FetchRepoResult>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct FetchRepoResult {
     pub identity: String,
     pub shortname: String,
     pub ok: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// Attempts repeated after a network error.
+    pub retries: u32,
+    /// Objects received from upstream; absent when git didn't report any
+    /// or another command's fetch covered this one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub objects: Option<u64>,
+    /// Bytes received, as git reported them (rounded).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+    /// Time spent on the repo, retries included.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Fetched recently enough (`--max-age`) to be left alone.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skipped: bool,
+    /// The mirror was missing and was cloned first.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cloned: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct MutationOutput {
     pub ok: bool,
     pub message: String,
@@ -330,6 +708,24 @@ pub struct MutationOutput {
     pub path: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub branch: Option<String>,
+    /// Why the command worked from the last fetch instead of fetching
+    /// first: `offline` or `no-fetch`, as in `wsp sync --json`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetch_skipped: Option<String>,
+    /// Repos that failed to clone and are waiting for `wsp repair`. Any
+    /// make the exit code 1.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pending: Vec<PendingRepo>,
+    /// Print only `path` on stdout and the message on stderr, for callers
+    /// that capture the path (`wsp new --print-dir`).
+    #[serde(skip)]
+    pub print_dir: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct PendingRepo {
+    pub identity: String,
+    pub error: String,
 }
 
 impl MutationOutput {
@@ -342,6 +738,9 @@ impl MutationOutput {
             workspace: None,
             path: None,
             branch: None,
+            fetch_skipped: None,
+            pending: Vec::new(),
+            print_dir: false,
         }
     }
 
@@ -366,32 +765,97 @@ impl MutationOutput {
         self.branch = Some(branch.into());
         self
     }
+
+    pub fn with_fetch_skipped(mut self, fetch: crate::offline::FetchPolicy) -> Self {
+        self.fetch_skipped = fetch.skip_reason().map(String::from);
+        self
+    }
+
+    pub fn with_print_dir(mut self, print_dir: bool) -> Self {
+        self.print_dir = print_dir;
+        self
+    }
+    pub fn with_pending<'a>(
+        mut self,
+        pending: impl IntoIterator<Item = (&'a String, &'a String)>,
+    ) -> Self {
+        self.pending = pending
+            .into_iter()
+            .map(|(identity, error)| PendingRepo {
+                identity: identity.clone(),
+                error: error.clone(),
+            })
+            .collect();
+        self
+    }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct PathOutput {
     pub path: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
+pub struct ShellCheckOutput {
+    pub inside: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
 pub struct RecoverListOutput {
     #[serde(rename = "workspaces")]
     pub entries: Vec<crate::gc::GcListEntry>,
     pub retention_days: u32,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct RecoverShowOutput {
     pub entry: crate::gc::GcShowEntry,
     pub retention_days: u32,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
+pub struct TimingsOutput {
+    pub total_ms: u64,
+    /// One entry per (repo, git operation), slowest first.
+    pub entries: Vec<TimingEntry>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct TimingEntry {
+    pub repo: String,
+    pub op: String,
+    pub calls: u32,
+    pub ms: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
 pub struct ErrorOutput {
     pub error: String,
+    /// Stable category: error, config, git, network, conflict, not_found.
+    pub code: String,
 }
 
+#[derive(Serialize, JsonSchema)]
+pub struct SchemaListOutput {
+    pub schemas: Vec<SchemaListEntry>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct SchemaListEntry {
+    pub name: String,
+    pub command: String,
+}
+
+/// A JSON Schema document, printed as-is.
 #[derive(Serialize)]
+#[serde(transparent)]
+pub struct SchemaOutput(pub serde_json::Value);
+
+#[derive(Serialize, JsonSchema)]
 pub struct ImportOutput {
     pub registered: Vec<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
@@ -400,21 +864,25 @@ pub struct ImportOutput {
     pub failed: Vec<ImportFailure>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ImportFailure {
     pub name: String,
     pub error: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct SyncOutput {
     pub workspace: String,
     pub branch: String,
     pub dry_run: bool,
+    /// Why it synced against the refs of the last fetch instead of
+    /// fetching: `offline` or `no-fetch`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetch_skipped: Option<String>,
     pub repos: Vec<SyncRepoResult>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct SyncRepoResult {
     pub identity: String,
     pub shortname: String,
@@ -436,13 +904,13 @@ pub struct SyncRepoResult {
     pub strategy: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct SyncAbortOutput {
     pub workspace: String,
     pub repos: Vec<SyncAbortRepoResult>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct SyncAbortRepoResult {
     pub identity: String,
     pub shortname: String,
@@ -453,43 +921,200 @@ pub struct SyncAbortRepoResult {
     pub error: Option<String>,
 }
 
-// ---------------------------------------------------------------------------
-// Sample constructors for SKILL.md generation (codegen only)
-// ---------------------------------------------------------------------------
+#[derive(Serialize, JsonSchema)]
+pub struct FixOutput {
+    pub workspace: String,
+    pub branch: String,
+    /// Only repos that had drifted off the workspace branch.
+    pub repos: Vec<FixRepoResult>,
+}
 
-#[cfg(feature = "codegen")]
-impl RepoListOutput {
-    pub fn sample() -> Self {
-        Self {
-            repos: vec![RepoListEntry {
-                identity: "github.com/acme/api-gateway".into(),
-                shortname: "api-gateway".into(),
-                url: "git@github.com:acme/api-gateway.git".into(),
-            }],
-        }
-    }
+#[derive(Serialize, JsonSchema)]
+pub struct FixRepoResult {
+    pub identity: String,
+    pub shortname: String,
+    /// Branch the repo was on, or "HEAD" when detached.
+    pub from: String,
+    /// The repo's workspace branch.
+    pub to: String,
+    /// "switched" or "skipped".
+    pub action: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
-#[cfg(feature = "codegen")]
-impl TemplateListOutput {
-    pub fn sample() -> Self {
-        Self {
-            templates: vec![TemplateListEntry {
-                name: "backend".into(),
-                repo_count: 3,
-            }],
-        }
-    }
+/// `wsp pr create`: what happened in each repo.
+#[derive(Serialize, JsonSchema)]
+pub struct PrOutput {
+    pub workspace: String,
+    pub branch: String,
+    pub dry_run: bool,
+    pub repos: Vec<PrRepoResult>,
 }
 
-#[cfg(feature = "codegen")]
-impl TemplateShowOutput {
-    pub fn sample() -> Self {
-        Self {
-            name: "backend".into(),
-            repos: vec![
-                TemplateShowRepo {
-                    url: "git@github.com:acme/api-gateway.git".into(),
+#[derive(Serialize, JsonSchema)]
+pub struct PrRepoResult {
+    pub identity: String,
+    pub shortname: String,
+    /// "created", "pushed" (PR already open), "skipped", or with `--dry-run`
+    /// "would create" / "would push".
+    pub action: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// `wsp pr status`: each repo's PR and whether the whole change can merge.
+#[derive(Serialize, JsonSchema)]
+pub struct PrStatusOutput {
+    pub workspace: String,
+    pub branch: String,
+    /// Every PR is merged or mergeable now, and at least one exists.
+    pub ready: bool,
+    pub repos: Vec<PrStatusEntry>,
+}
+
+#[derive(Default, Serialize, JsonSchema)]
+pub struct PrStatusEntry {
+    pub identity: String,
+    pub shortname: String,
+    /// "github" or "gitlab"; on GitLab the PR is a merge request and
+    /// `number` its project-scoped iid.
+    pub forge: String,
+    /// Absent when the repo has no PR.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number: Option<u64>,
+    /// "open", "merged", or "closed"; empty without a PR.
+    pub state: String,
+    pub draft: bool,
+    /// "approved", "changes_requested", "review_required", or empty when no
+    /// review is required.
+    pub review: String,
+    /// "mergeable", "conflicting", or "unknown" while the forge computes it.
+    pub mergeable: String,
+    pub checks_passed: u32,
+    pub checks_failed: u32,
+    pub checks_pending: u32,
+    /// What keeps this PR from merging, e.g. "draft", "checks failing".
+    pub blockers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// `wsp prompt`: the workspace containing the current directory.
+#[derive(Serialize, JsonSchema)]
+pub struct PromptOutput {
+    pub workspace: String,
+    /// Present with `--status`; repos not checked within the time budget
+    /// count as errors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<WorkspaceStatusSummary>,
+    /// `--starship`: text without a trailing newline. Starship escapes
+    /// command output for the shell itself, so the text is otherwise as-is.
+    #[serde(skip)]
+    pub starship: bool,
+}
+
+/// `wsp setup auth status`: where each known forge host's API token comes
+/// from.
+#[derive(Serialize, JsonSchema)]
+pub struct AuthStatusOutput {
+    pub hosts: Vec<AuthHostStatus>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct AuthHostStatus {
+    pub host: String,
+    /// "github" or "gitlab".
+    pub forge: String,
+    /// The CLI wsp runs for the host: "gh" or "glab".
+    pub cli: String,
+    /// "env", "keyring", or "none" (the CLI's own login is used).
+    pub source: String,
+    /// The environment variable holding the token, when `source` is "env".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variable: Option<String>,
+}
+
+/// `wsp setup schedule status`: the scheduled mirror fetch, if installed.
+#[derive(Serialize, JsonSchema)]
+pub struct ScheduleStatusOutput {
+    /// "systemd" or "launchd".
+    pub scheduler: String,
+    pub installed: bool,
+    /// The timer unit or plist that defines the schedule.
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval_minutes: Option<u64>,
+    /// Whether the scheduler reports the job as loaded; absent when not
+    /// installed or the scheduler could not be asked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active: Option<bool>,
+}
+
+impl PromptOutput {
+    /// "my-feature *2 ↑5", with "?" when some repos weren't checked.
+    pub fn text(&self) -> String {
+        let mut out = self.workspace.clone();
+        if let Some(s) = &self.status {
+            if s.dirty > 0 {
+                out.push_str(&format!(" *{}", s.dirty));
+            }
+            if s.ahead > 0 {
+                out.push_str(&format!(" ↑{}", s.ahead));
+            }
+            if s.errors > 0 {
+                out.push_str(" ?");
+            }
+        }
+        out
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Sample constructors for SKILL.md generation (codegen only)
+// ---------------------------------------------------------------------------
+
+#[cfg(feature = "codegen")]
+impl RepoListOutput {
+    pub fn sample() -> Self {
+        Self {
+            repos: vec![RepoListEntry {
+                identity: "github.com/acme/api-gateway".into(),
+                shortname: "api-gateway".into(),
+                url: "git@github.com:acme/api-gateway.git".into(),
+            }],
+        }
+    }
+}
+
+#[cfg(feature = "codegen")]
+impl TemplateListOutput {
+    pub fn sample() -> Self {
+        Self {
+            templates: vec![TemplateListEntry {
+                name: "backend".into(),
+                repo_count: 3,
+            }],
+        }
+    }
+}
+
+#[cfg(feature = "codegen")]
+impl TemplateShowOutput {
+    pub fn sample() -> Self {
+        Self {
+            name: "backend".into(),
+            repos: vec![
+                TemplateShowRepo {
+                    url: "git@github.com:acme/api-gateway.git".into(),
                     identity: "github.com/acme/api-gateway".into(),
                 },
                 TemplateShowRepo {
@@ -512,10 +1137,20 @@ impl WorkspaceListOutput {
                 repo_count: 2,
                 path: "/home/user/dev/workspaces/my-feature".into(),
                 description: Some("migrating billing to stripe v3".into()),
+                issue: Some("https://acme.atlassian.net/browse/BILL-142".into()),
                 created: "2026-03-01T10:00:00+00:00".into(),
                 last_used: Some("2026-03-06T15:30:00+00:00".into()),
                 created_from: Some("backend".into()),
+                status: Some(WorkspaceStatusSummary {
+                    dirty: 1,
+                    ahead: 3,
+                    errors: 0,
+                }),
+                disk_bytes: Some(52_428_800),
             }],
+            total_disk_bytes: Some(52_428_800),
+            total: None,
+            streamed: false,
         }
     }
 }
@@ -529,6 +1164,8 @@ impl StatusOutput {
             description: Some("migrating billing to stripe v3".into()),
             workspace_dir: PathBuf::from("/home/user/dev/workspaces/my-feature"),
             created: "2026-01-15T10:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+            fetched: Some("2026-01-16T09:55:00Z".parse::<DateTime<Utc>>().unwrap()),
+            fetch_skipped: Some("no-fetch".into()),
             repos: vec![RepoStatusEntry {
                 identity: "github.com/acme/api-gateway".into(),
                 shortname: "api-gateway".into(),
@@ -536,15 +1173,90 @@ impl StatusOutput {
                 branch: "my-feature".into(),
                 ahead: 2,
                 behind: 0,
+                compared: true,
                 changed: 1,
+                untracked: 0,
+                stashes: 0,
                 has_upstream: true,
+                pushed: true,
                 role: "active".into(),
                 files: vec![],
                 error: None,
                 expected_branch: None,
+                in_progress: None,
             }],
             root: vec![],
             verbose: false,
+            porcelain: false,
+            streamed: false,
+        }
+    }
+}
+
+#[cfg(feature = "codegen")]
+impl InfoOutput {
+    pub fn sample() -> Self {
+        Self {
+            workspace: "my-feature".into(),
+            branch: "my-feature".into(),
+            workspace_dir: PathBuf::from("/home/user/dev/workspaces/my-feature"),
+            description: Some("migrating billing to stripe v3".into()),
+            issue: Some("https://acme.atlassian.net/browse/BILL-142".into()),
+            created: "2026-01-15T10:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+            last_used: Some("2026-01-16T09:30:00Z".parse::<DateTime<Utc>>().unwrap()),
+            created_from: Some("template:backend".into()),
+            repos: vec![InfoRepoEntry {
+                identity: "github.com/acme/api-gateway".into(),
+                dir_name: "api-gateway".into(),
+                path: "/home/user/dev/workspaces/my-feature/api-gateway".into(),
+                role: "active".into(),
+                r#ref: None,
+                branch: Some("my-feature".into()),
+                sha: Some("4f2c9a1e8b7d6c5f4e3d2c1b0a9f8e7d6c5b4a39".into()),
+                upstream: Some("origin/my-feature".into()),
+                has_upstream: true,
+                ahead: 2,
+                behind: 0,
+                origin_url: Some("git@github.com:acme/api-gateway.git".into()),
+                mirror: Some(
+                    "/home/user/.local/share/wsp/mirrors/github.com/acme/api-gateway.git".into(),
+                ),
+                mirror_exists: true,
+                error: None,
+            }],
+            pending: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(feature = "codegen")]
+impl ContextOutput {
+    pub fn sample() -> Self {
+        Self {
+            workspace: "my-feature".into(),
+            branch: "my-feature".into(),
+            workspace_dir: PathBuf::from("/home/user/dev/workspaces/my-feature"),
+            description: Some("migrating billing to stripe v3".into()),
+            issue: Some("https://acme.atlassian.net/browse/BILL-142".into()),
+            repos: vec![ContextRepoEntry {
+                identity: "github.com/acme/api-gateway".into(),
+                dir_name: "api-gateway".into(),
+                path: "/home/user/dev/workspaces/my-feature/api-gateway".into(),
+                role: "active".into(),
+                r#ref: None,
+                branch: Some("my-feature".into()),
+                upstream: Some("origin/main".into()),
+                ahead: 1,
+                behind: 0,
+                changed_files: vec!["M src/billing.rs".into()],
+                recent_commits: vec![LogCommit {
+                    hash: "4f2c9a1e8b7d6c5f4e3d2c1b0a9f8e7d6c5b4a39".into(),
+                    authored_at: "2026-01-16T09:30:00+00:00".into(),
+                    timestamp: 0,
+                    subject: "Add stripe v3 client".into(),
+                }],
+                error: None,
+            }],
         }
     }
 }
@@ -560,8 +1272,21 @@ impl DiffOutput {
                 identity: "github.com/acme/api-gateway".into(),
                 shortname: "api-gateway".into(),
                 path: "/home/user/dev/workspaces/my-feature/api-gateway".into(),
-                diff: "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1,3 +1,4 @@\n+use std::io;\n ..."
-                    .into(),
+                diff: Some(
+                    "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1,3 +1,4 @@\n+use std::io;\n ..."
+                        .into(),
+                ),
+                stat: Some(DiffStat {
+                    files_changed: 1,
+                    insertions: 1,
+                    deletions: 0,
+                    files: vec![DiffFileStat {
+                        path: "src/main.rs".into(),
+                        insertions: 1,
+                        deletions: 0,
+                        binary: false,
+                    }],
+                }),
                 error: None,
             }],
         }
@@ -600,6 +1325,7 @@ impl SyncOutput {
             workspace: "my-feature".into(),
             branch: "my-feature".into(),
             dry_run: false,
+            fetch_skipped: None,
             repos: vec![SyncRepoResult {
                 identity: "github.com/acme/api-gateway".into(),
                 shortname: "api-gateway".into(),
@@ -616,6 +1342,164 @@ impl SyncOutput {
     }
 }
 
+#[cfg(feature = "codegen")]
+impl PrOutput {
+    pub fn sample() -> Self {
+        Self {
+            workspace: "my-feature".into(),
+            branch: "my-feature".into(),
+            dry_run: false,
+            repos: vec![
+                PrRepoResult {
+                    identity: "github.com/acme/api-gateway".into(),
+                    shortname: "api-gateway".into(),
+                    action: "created".into(),
+                    ok: true,
+                    url: Some("https://github.com/acme/api-gateway/pull/42".into()),
+                    detail: Some("against main".into()),
+                    error: None,
+                },
+                PrRepoResult {
+                    identity: "github.com/acme/user-service".into(),
+                    shortname: "user-service".into(),
+                    action: "skipped".into(),
+                    ok: true,
+                    url: None,
+                    detail: Some("no commits ahead of origin/main".into()),
+                    error: None,
+                },
+            ],
+        }
+    }
+}
+
+#[cfg(feature = "codegen")]
+impl PrStatusOutput {
+    pub fn sample() -> Self {
+        Self {
+            workspace: "my-feature".into(),
+            branch: "my-feature".into(),
+            ready: false,
+            repos: vec![
+                PrStatusEntry {
+                    identity: "github.com/acme/api-gateway".into(),
+                    shortname: "api-gateway".into(),
+                    forge: "github".into(),
+                    url: Some("https://github.com/acme/api-gateway/pull/42".into()),
+                    number: Some(42),
+                    state: "open".into(),
+                    draft: false,
+                    review: "approved".into(),
+                    mergeable: "mergeable".into(),
+                    checks_passed: 5,
+                    checks_failed: 0,
+                    checks_pending: 0,
+                    blockers: vec![],
+                    error: None,
+                },
+                PrStatusEntry {
+                    identity: "github.com/acme/user-service".into(),
+                    shortname: "user-service".into(),
+                    forge: "github".into(),
+                    url: Some("https://github.com/acme/user-service/pull/17".into()),
+                    number: Some(17),
+                    state: "open".into(),
+                    draft: false,
+                    review: "review_required".into(),
+                    mergeable: "mergeable".into(),
+                    checks_passed: 3,
+                    checks_failed: 1,
+                    checks_pending: 0,
+                    blockers: vec!["review required".into(), "checks failing".into()],
+                    error: None,
+                },
+            ],
+        }
+    }
+}
+
+#[cfg(feature = "codegen")]
+impl PromptOutput {
+    pub fn sample() -> Self {
+        Self {
+            workspace: "my-feature".into(),
+            status: Some(WorkspaceStatusSummary {
+                dirty: 1,
+                ahead: 3,
+                errors: 0,
+            }),
+            starship: false,
+        }
+    }
+}
+
+#[cfg(feature = "codegen")]
+impl AuthStatusOutput {
+    pub fn sample() -> Self {
+        Self {
+            hosts: vec![
+                AuthHostStatus {
+                    host: "github.com".into(),
+                    forge: "github".into(),
+                    cli: "gh".into(),
+                    source: "keyring".into(),
+                    variable: None,
+                },
+                AuthHostStatus {
+                    host: "gitlab.com".into(),
+                    forge: "gitlab".into(),
+                    cli: "glab".into(),
+                    source: "env".into(),
+                    variable: Some("GITLAB_TOKEN".into()),
+                },
+            ],
+        }
+    }
+}
+
+#[cfg(feature = "codegen")]
+impl ScheduleStatusOutput {
+    pub fn sample() -> Self {
+        Self {
+            scheduler: "systemd".into(),
+            installed: true,
+            path: "/home/user/.config/systemd/user/wsp-fetch.timer".into(),
+            interval_minutes: Some(30),
+            active: Some(true),
+        }
+    }
+}
+
+#[cfg(feature = "codegen")]
+impl FixOutput {
+    pub fn sample() -> Self {
+        Self {
+            workspace: "my-feature".into(),
+            branch: "my-feature".into(),
+            repos: vec![
+                FixRepoResult {
+                    identity: "github.com/acme/api-gateway".into(),
+                    shortname: "api-gateway".into(),
+                    from: "main".into(),
+                    to: "my-feature".into(),
+                    action: "switched".into(),
+                    ok: true,
+                    error: None,
+                },
+                FixRepoResult {
+                    identity: "github.com/acme/user-service".into(),
+                    shortname: "user-service".into(),
+                    from: "HEAD".into(),
+                    to: "my-feature".into(),
+                    action: "skipped".into(),
+                    ok: false,
+                    error: Some("uncommitted changes; commit or stash them first".into()),
+                },
+            ],
+        }
+    }
+}
+
 #[cfg(feature = "codegen")]
 impl SyncAbortOutput {
     pub fn sample() -> Self {
@@ -724,6 +1608,67 @@ impl ExecOutput {
     }
 }
 
+#[cfg(feature = "codegen")]
+impl RunOutput {
+    pub fn sample() -> Self {
+        Self {
+            workspace: "my-feature".into(),
+            task: "test".into(),
+            repos: ExecOutput::sample()
+                .repos
+                .into_iter()
+                .map(|result| RunRepoResult {
+                    task: "test".into(),
+                    status: "ok".into(),
+                    duration_ms: 1250,
+                    depends_on: vec!["proto:gen".into()],
+                    result,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(feature = "codegen")]
+impl DepsGraphOutput {
+    pub fn sample() -> Self {
+        Self {
+            workspace: Some("my-feature".into()),
+            repos: vec![
+                DepsGraphRepo {
+                    identity: "github.com/acme/api-gateway".into(),
+                    shortname: "api-gateway".into(),
+                    in_workspace: true,
+                    depends_on: vec!["github.com/acme/proto".into()],
+                },
+                DepsGraphRepo {
+                    identity: "github.com/acme/proto".into(),
+                    shortname: "proto".into(),
+                    in_workspace: false,
+                    depends_on: vec![],
+                },
+            ],
+            format: "dot".into(),
+        }
+    }
+}
+
+#[cfg(feature = "codegen")]
+impl LayoutOutput {
+    pub fn sample() -> Self {
+        Self {
+            workspace: "my-feature".into(),
+            path: "/home/user/dev/workspaces/my-feature".into(),
+            panes: vec![LayoutPane {
+                identity: "github.com/acme/api-gateway".into(),
+                directory: "api-gateway".into(),
+                path: "/home/user/dev/workspaces/my-feature/api-gateway".into(),
+            }],
+            kind: "tmux".into(),
+        }
+    }
+}
+
 #[cfg(feature = "codegen")]
 impl FetchOutput {
     pub fn sample() -> Self {
@@ -734,6 +1679,12 @@ impl FetchOutput {
                 shortname: "api-gateway".into(),
                 ok: true,
                 error: None,
+                retries: 0,
+                objects: Some(1204),
+                bytes: Some(2_726_297),
+                duration_ms: Some(3412),
+                skipped: false,
+                cloned: false,
             }],
         }
     }
@@ -750,6 +1701,9 @@ impl MutationOutput {
             workspace: None,
             path: None,
             branch: None,
+            fetch_skipped: None,
+            pending: Vec::new(),
+            print_dir: false,
         }
     }
 }
@@ -759,6 +1713,7 @@ impl ErrorOutput {
     pub fn sample() -> Self {
         Self {
             error: "repo \"foo\" not found".into(),
+            code: "not_found".into(),
         }
     }
 }
@@ -828,27 +1783,70 @@ impl RecoverShowOutput {
 // Output enum — returned by all command handlers
 // ---------------------------------------------------------------------------
 
-pub enum Output {
-    RepoList(RepoListOutput),
-    TemplateList(TemplateListOutput),
-    TemplateShow(TemplateShowOutput),
-    WorkspaceList(WorkspaceListOutput),
-    WorkspaceRepoList(WorkspaceRepoListOutput),
-    Status(StatusOutput),
-    Diff(DiffOutput),
-    Log(LogOutput),
-    Exec(ExecOutput),
-    Fetch(FetchOutput),
-    Sync(SyncOutput),
-    SyncAbort(SyncAbortOutput),
-    ConfigList(ConfigListOutput),
-    ConfigGet(ConfigGetOutput),
+#[cfg(feature = "codegen")]
+impl SchemaListOutput {
+    pub fn sample() -> Self {
+        Self {
+            schemas: vec![
+                SchemaListEntry {
+                    name: "ls".into(),
+                    command: "wsp ls".into(),
+                },
+                SchemaListEntry {
+                    name: "st".into(),
+                    command: "wsp st".into(),
+                },
+            ],
+        }
+    }
+}
+
+#[cfg(feature = "codegen")]
+impl ShellCheckOutput {
+    pub fn sample() -> Self {
+        Self {
+            inside: true,
+            workspace: Some("my-feature".into()),
+            path: Some("/home/user/dev/workspaces/my-feature".into()),
+        }
+    }
+}
+
+pub enum Output {
+    RepoList(RepoListOutput),
+    TemplateList(TemplateListOutput),
+    TemplateShow(TemplateShowOutput),
+    WorkspaceList(WorkspaceListOutput),
+    WorkspaceRepoList(WorkspaceRepoListOutput),
+    Status(StatusOutput),
+    Info(InfoOutput),
+    Context(ContextOutput),
+    Diff(DiffOutput),
+    Log(LogOutput),
+    Exec(ExecOutput),
+    Run(RunOutput),
+    DepsGraph(DepsGraphOutput),
+    Layout(LayoutOutput),
+    Fetch(FetchOutput),
+    Sync(SyncOutput),
+    SyncAbort(SyncAbortOutput),
+    Fix(FixOutput),
+    Pr(PrOutput),
+    PrStatus(PrStatusOutput),
+    ConfigList(ConfigListOutput),
+    ConfigGet(ConfigGetOutput),
     Mutation(MutationOutput),
     Import(ImportOutput),
     RecoverList(RecoverListOutput),
     RecoverShow(RecoverShowOutput),
     Path(PathOutput),
+    Prompt(PromptOutput),
+    ScheduleStatus(ScheduleStatusOutput),
+    AuthStatus(AuthStatusOutput),
+    ShellCheck(ShellCheckOutput),
     Doctor(crate::cli::doctor::DoctorOutput),
+    SchemaList(SchemaListOutput),
+    Schema(SchemaOutput),
     None,
 }
 
@@ -856,30 +1854,136 @@ pub enum Output {
 // Central render function
 // ---------------------------------------------------------------------------
 
-pub fn render(output: Output, json: bool) -> Result<()> {
-    if json {
+/// Output format selected by `--format` (or `--json`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Table,
+    Json,
+    Yaml,
+    Csv,
+}
+
+pub const FORMAT_NAMES: &[&str] = &["table", "json", "yaml", "csv"];
+
+impl Format {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "table" => Ok(Format::Table),
+            "json" => Ok(Format::Json),
+            "yaml" => Ok(Format::Yaml),
+            "csv" => Ok(Format::Csv),
+            _ => bail!(
+                "unknown format {:?} (expected one of: {})",
+                s,
+                FORMAT_NAMES.join(", ")
+            ),
+        }
+    }
+
+    /// Resolve the global `--json` / `--format` flags from the root matches or
+    /// any subcommand's. The deepest explicit `--format` wins; values that are
+    /// not output formats (`deps graph --format mermaid`) are skipped.
+    pub fn from_matches(matches: &clap::ArgMatches) -> Self {
+        let mut format = Format::Table;
+        let mut cur = Some(matches);
+        while let Some(m) = cur {
+            if m.get_flag("json") {
+                return Format::Json;
+            }
+            if m.value_source("format") == Some(clap::parser::ValueSource::CommandLine)
+                && let Some(f) = m
+                    .get_one::<String>("format")
+                    .and_then(|s| Format::parse(s).ok())
+            {
+                format = f;
+            }
+            cur = m.subcommand().map(|(_, sub)| sub);
+        }
+        format
+    }
+
+    /// True for machine-readable formats. Commands that stream text while
+    /// they run (exec, run, diff colors) switch to capture mode for these.
+    pub fn is_structured(self) -> bool {
+        self != Format::Table
+    }
+}
+
+pub const COLOR_NAMES: &[&str] = &["auto", "always", "never"];
+
+/// Whether to emit ANSI color on stdout, from the global `--color` flag.
+/// `auto` (the default) colors only a terminal and honors `NO_COLOR`.
+pub fn use_color(matches: &clap::ArgMatches) -> bool {
+    let when = matches
+        .try_get_one::<String>("color")
+        .ok()
+        .flatten()
+        .map(String::as_str)
+        .unwrap_or("auto");
+    resolve_color(
+        when,
+        std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()),
+        std::io::stdout().is_terminal(),
+    )
+}
+
+fn resolve_color(when: &str, no_color: bool, is_terminal: bool) -> bool {
+    match when {
+        "always" => true,
+        "never" => false,
+        _ => !no_color && is_terminal,
+    }
+}
+
+/// The `--color=` argument for git commands whose output wsp passes through,
+/// so git's own `color.ui` setting can't override wsp's choice.
+pub fn git_color_arg(color: bool) -> &'static str {
+    if color {
+        "--color=always"
+    } else {
+        "--color=never"
+    }
+}
+
+pub fn render(output: Output, format: Format) -> Result<()> {
+    if format.is_structured() {
         return match output {
             Output::None => Ok(()),
-            Output::RepoList(v) => print_json(&v),
-            Output::TemplateList(v) => print_json(&v),
-            Output::TemplateShow(v) => print_json(&v),
-            Output::WorkspaceList(v) => print_json(&v),
-            Output::WorkspaceRepoList(v) => print_json(&v),
-            Output::Status(v) => print_json(&v),
-            Output::Diff(v) => print_json(&v),
-            Output::Log(v) => print_json(&v),
-            Output::Exec(v) => print_json(&v),
-            Output::Fetch(v) => print_json(&v),
-            Output::Sync(v) => print_json(&v),
-            Output::SyncAbort(v) => print_json(&v),
-            Output::ConfigList(v) => print_json(&v),
-            Output::ConfigGet(v) => print_json(&v),
-            Output::Mutation(v) => print_json(&v),
-            Output::Import(v) => print_json(&v),
-            Output::RecoverList(v) => print_json(&v),
-            Output::RecoverShow(v) => print_json(&v),
-            Output::Path(v) => print_json(&v),
-            Output::Doctor(v) => print_json(&v),
+            Output::RepoList(v) => print_structured(&v, format, Some("repos")),
+            Output::TemplateList(v) => print_structured(&v, format, Some("templates")),
+            Output::TemplateShow(v) => print_structured(&v, format, Some("repos")),
+            Output::WorkspaceList(v) => print_structured(&v, format, Some("workspaces")),
+            Output::WorkspaceRepoList(v) => print_structured(&v, format, Some("repos")),
+            Output::Status(v) => print_structured(&v, format, Some("repos")),
+            Output::Info(v) => print_structured(&v, format, Some("repos")),
+            Output::Context(v) => print_structured(&v, format, Some("repos")),
+            Output::Diff(v) => print_structured(&v, format, Some("repos")),
+            Output::Log(v) => print_structured(&v, format, Some("repos")),
+            Output::Exec(v) => print_structured(&v, format, Some("repos")),
+            Output::Run(v) => print_structured(&v, format, Some("repos")),
+            Output::DepsGraph(v) => print_structured(&v, format, Some("repos")),
+            Output::Layout(v) => print_structured(&v, format, Some("panes")),
+            Output::Fetch(v) => print_structured(&v, format, Some("repos")),
+            Output::Sync(v) => print_structured(&v, format, Some("repos")),
+            Output::SyncAbort(v) => print_structured(&v, format, Some("repos")),
+            Output::Fix(v) => print_structured(&v, format, Some("repos")),
+            Output::Pr(v) => print_structured(&v, format, Some("repos")),
+            Output::PrStatus(v) => print_structured(&v, format, Some("repos")),
+            Output::ConfigList(v) => print_structured(&v, format, Some("settings")),
+            Output::ConfigGet(v) => print_structured(&v, format, None),
+            Output::Mutation(v) => print_structured(&v, format, None),
+            Output::Import(v) => print_structured(&v, format, None),
+            Output::RecoverList(v) => print_structured(&v, format, Some("workspaces")),
+            Output::RecoverShow(v) => print_structured(&v, format, None),
+            Output::Path(v) => print_structured(&v, format, None),
+            Output::Prompt(v) => print_structured(&v, format, None),
+            Output::ScheduleStatus(v) => print_structured(&v, format, None),
+            Output::AuthStatus(v) => print_structured(&v, format, None),
+            Output::ShellCheck(v) => print_structured(&v, format, None),
+            Output::Doctor(v) => print_structured(&v, format, Some("checks")),
+            Output::SchemaList(v) => print_structured(&v, format, Some("schemas")),
+            Output::Schema(v) if format == Format::Yaml => print_structured(&v, format, None),
+            Output::Schema(v) => render_schema_text(v),
         };
     }
     match output {
@@ -889,13 +1993,40 @@ pub fn render(output: Output, json: bool) -> Result<()> {
         Output::TemplateShow(v) => render_template_show_text(v),
         Output::WorkspaceList(v) => render_workspace_list_table(v),
         Output::WorkspaceRepoList(v) => render_workspace_repo_list_table(v),
+        Output::Status(v) if v.porcelain => render_status_porcelain(v),
         Output::Status(v) => render_status_table(v),
+        Output::Info(v) => {
+            print!("{}", info_text(&v));
+            Ok(())
+        }
+        Output::Context(v) => {
+            print!("{}", context_markdown(&v));
+            Ok(())
+        }
         Output::Diff(v) => render_diff_text(v),
         Output::Log(v) => render_log_text(v),
         Output::Exec(_) => Ok(()), // text output handled inline during execution
+        Output::Run(v) => render_run_summary(v),
+        Output::DepsGraph(v) => {
+            match v.format.as_str() {
+                "mermaid" => print!("{}", deps_graph_mermaid(&v)),
+                _ => print!("{}", deps_graph_dot(&v)),
+            }
+            Ok(())
+        }
+        Output::Layout(v) => {
+            match v.kind.as_str() {
+                "zellij" => print!("{}", layout_zellij(&v)),
+                _ => print!("{}", layout_tmuxinator(&v)),
+            }
+            Ok(())
+        }
         Output::Fetch(v) => render_fetch_text(v),
         Output::Sync(v) => render_sync_text(v),
         Output::SyncAbort(v) => render_sync_abort_text(v),
+        Output::Fix(v) => render_fix_text(v),
+        Output::Pr(v) => render_pr_text(v),
+        Output::PrStatus(v) => render_pr_status_text(v),
         Output::ConfigList(v) => render_config_list_text(v),
         Output::ConfigGet(v) => render_config_get_text(v),
         Output::Mutation(v) => render_mutation_text(v),
@@ -903,7 +2034,70 @@ pub fn render(output: Output, json: bool) -> Result<()> {
         Output::RecoverList(v) => render_recover_list_text(v),
         Output::RecoverShow(v) => render_recover_show_text(v),
         Output::Path(v) => render_path_text(v),
+        Output::Prompt(v) => {
+            if v.starship {
+                print!("{}", v.text());
+            } else {
+                println!("{}", v.text());
+            }
+            Ok(())
+        }
+        Output::ScheduleStatus(v) => render_schedule_status_text(v),
+        Output::AuthStatus(v) => render_auth_status_table(v),
+        Output::ShellCheck(v) => render_shell_check_text(v),
         Output::Doctor(_) => Ok(()), // text output handled inline during run
+        Output::SchemaList(v) => render_schema_list_table(v),
+        Output::Schema(v) => render_schema_text(v),
+    }
+}
+
+/// Print `--timings` on stderr, so stdout stays parseable. Structured formats
+/// get a JSON document; otherwise a table.
+pub fn render_timings(v: &TimingsOutput, format: Format) {
+    if format.is_structured() {
+        #[derive(Serialize)]
+        struct Doc<'a> {
+            timings: &'a TimingsOutput,
+        }
+        if let Ok(s) = serde_json::to_string_pretty(&Doc { timings: v }) {
+            eprintln!("{}", s);
+        }
+        return;
+    }
+    let git_ms: u64 = v.entries.iter().map(|e| e.ms).sum();
+    eprintln!(
+        "\nTimings: {} total, {} in git",
+        format_ms(v.total_ms),
+        format_ms(git_ms)
+    );
+    if v.entries.is_empty() {
+        return;
+    }
+    let mut table = Table::new(
+        Box::new(std::io::stderr()),
+        vec![
+            "Repo".to_string(),
+            "Operation".to_string(),
+            "Calls".to_string(),
+            "Time".to_string(),
+        ],
+    );
+    for e in &v.entries {
+        let _ = table.add_row(vec![
+            e.repo.clone(),
+            e.op.clone(),
+            e.calls.to_string(),
+            format_ms(e.ms),
+        ]);
+    }
+    let _ = table.render();
+}
+
+fn format_ms(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{}ms", ms)
+    } else {
+        format!("{:.2}s", ms as f64 / 1000.0)
     }
 }
 
@@ -911,20 +2105,107 @@ pub fn render(output: Output, json: bool) -> Result<()> {
 pub fn exit_code(output: &Output) -> i32 {
     match output {
         Output::Exec(v) if v.repos.iter().any(|r| !r.ok) => 1,
+        Output::Run(v) if v.repos.iter().any(|r| r.status != "ok") => 1,
         Output::Fetch(v) if v.repos.iter().any(|r| !r.ok) => 1,
         Output::Sync(v) if v.repos.iter().any(|r| !r.ok) => 1,
         Output::SyncAbort(v) if v.repos.iter().any(|r| !r.ok) => 1,
+        Output::Fix(v) if v.repos.iter().any(|r| !r.ok) => 1,
+        Output::Pr(v) if v.repos.iter().any(|r| !r.ok) => 1,
         Output::Import(v) if !v.failed.is_empty() => 1,
+        Output::Mutation(v) if !v.pending.is_empty() => 1,
+        Output::ShellCheck(v) if !v.inside => 1,
         Output::Doctor(v) => crate::cli::doctor::exit_code(v),
         _ => 0,
     }
 }
 
+/// Print `value` in a machine-readable format. For CSV, `rows` names the
+/// top-level array whose elements become rows; without it the value itself
+/// is a single row.
+pub fn print_structured(value: &impl Serialize, format: Format, rows: Option<&str>) -> Result<()> {
+    match format {
+        Format::Json => print_json(value),
+        Format::Yaml => {
+            print!("{}", serde_yaml_ng::to_string(value)?);
+            Ok(())
+        }
+        Format::Csv => {
+            print!("{}", to_csv(&serde_json::to_value(value)?, rows));
+            Ok(())
+        }
+        Format::Table => bail!("table output is not available for this value"),
+    }
+}
+
 fn print_json(value: &impl Serialize) -> Result<()> {
     println!("{}", serde_json::to_string_pretty(value)?);
     Ok(())
 }
 
+/// Flatten a JSON value into CSV. Columns are the union of row keys (sorted);
+/// nested arrays of scalars are joined with `;`, other nested values are
+/// written as compact JSON.
+fn to_csv(value: &serde_json::Value, rows: Option<&str>) -> String {
+    use serde_json::Value;
+
+    let rows: Vec<&Value> = match rows {
+        Some(field) => value
+            .get(field)
+            .and_then(Value::as_array)
+            .map(|a| a.iter().collect())
+            .unwrap_or_default(),
+        None => vec![value],
+    };
+
+    let mut columns: Vec<&str> = Vec::new();
+    for row in &rows {
+        if let Some(obj) = row.as_object() {
+            for k in obj.keys() {
+                if !columns.contains(&k.as_str()) {
+                    columns.push(k);
+                }
+            }
+        }
+    }
+    columns.sort();
+
+    let mut out = String::new();
+    let header: Vec<String> = columns.iter().map(|c| csv_field(c)).collect();
+    out.push_str(&header.join(","));
+    out.push('\n');
+    for row in rows {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|c| csv_field(&csv_cell(row.get(*c).unwrap_or(&Value::Null))))
+            .collect();
+        out.push_str(&cells.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+fn csv_cell(value: &serde_json::Value) -> String {
+    use serde_json::Value;
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Bool(_) | Value::Number(_) => value.to_string(),
+        Value::Array(items) if items.iter().all(|v| !v.is_array() && !v.is_object()) => {
+            items.iter().map(csv_cell).collect::<Vec<_>>().join(";")
+        }
+        _ => value.to_string(),
+    }
+}
+
+/// Quote a CSV field when it contains a delimiter, quote, or newline.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Text/table renderers
 // ---------------------------------------------------------------------------
@@ -972,38 +2253,88 @@ fn render_template_show_text(v: TemplateShowOutput) -> Result<()> {
 }
 
 fn render_workspace_list_table(v: WorkspaceListOutput) -> Result<()> {
+    let mut out = std::io::stdout().lock();
     if let Some(hint) = &v.hint {
-        println!("{}\n", hint);
+        writeln!(out, "{}\n", hint)?;
     }
     if v.workspaces.is_empty() {
-        println!("No workspaces.");
+        writeln!(out, "No workspaces.")?;
         return Ok(());
     }
-    let now = chrono::Utc::now().timestamp();
-    let mut table = Table::new(
-        Box::new(std::io::stdout()),
-        vec![
-            "Name".to_string(),
-            "Branch".to_string(),
-            "Repos".to_string(),
-            "Created".to_string(),
-            "Description".to_string(),
-        ],
-    );
-    for ws in &v.workspaces {
-        let created = chrono::DateTime::parse_from_rfc3339(&ws.created)
-            .map(|t| format_relative_time(t.timestamp(), now))
-            .unwrap_or_default();
-        let desc = ws.description.as_deref().unwrap_or("").to_string();
-        table.add_row(vec![
-            ws.name.clone(),
-            ws.branch.clone(),
-            ws.repo_count.to_string(),
-            created,
-            desc,
-        ])?;
+    if !v.streamed {
+        let now = chrono::Utc::now().timestamp();
+        let with_status = v.workspaces.iter().any(|ws| ws.status.is_some());
+        let with_du = v.total_disk_bytes.is_some();
+        let mut table = Table::new(
+            Box::new(std::io::stdout()),
+            workspace_list_headers(with_status, with_du),
+        );
+        for ws in &v.workspaces {
+            table.add_row(workspace_list_row(ws, with_status, with_du, now))?;
+        }
+        table.render()?;
     }
-    table.render()
+    if let Some(total) = v.total
+        && total > v.workspaces.len()
+    {
+        writeln!(
+            out,
+            "\n{} of {} workspaces; see --page for more",
+            v.workspaces.len(),
+            total
+        )?;
+    }
+    if let Some(total) = v.total_disk_bytes {
+        writeln!(out, "\nTotal: {}", format_bytes(total))?;
+    }
+    Ok(())
+}
+
+pub fn workspace_list_headers(with_status: bool, with_du: bool) -> Vec<String> {
+    let mut headers = vec![
+        "Name".to_string(),
+        "Branch".to_string(),
+        "Repos".to_string(),
+    ];
+    if with_status {
+        headers.push("Status".to_string());
+    }
+    if with_du {
+        headers.push("Size".to_string());
+    }
+    headers.extend(["Created".to_string(), "Description".to_string()]);
+    headers
+}
+
+/// A `wsp ls` row under [`workspace_list_headers`]. `now` is a Unix
+/// timestamp, for the relative creation time.
+pub fn workspace_list_row(
+    ws: &WorkspaceListEntry,
+    with_status: bool,
+    with_du: bool,
+    now: i64,
+) -> Vec<String> {
+    let created = chrono::DateTime::parse_from_rfc3339(&ws.created)
+        .map(|t| format_relative_time(t.timestamp(), now))
+        .unwrap_or_default();
+    let desc = match (&ws.issue, &ws.description) {
+        (Some(issue), Some(d)) => format!("[{}] {}", crate::issue::key(issue), d),
+        (Some(issue), None) => format!("[{}]", crate::issue::key(issue)),
+        (None, d) => d.clone().unwrap_or_default(),
+    };
+    let mut row = vec![
+        ws.name.clone(),
+        ws.branch.clone(),
+        ws.repo_count.to_string(),
+    ];
+    if with_status {
+        row.push(ws.status.as_ref().map(|s| s.summary()).unwrap_or_default());
+    }
+    if with_du {
+        row.push(ws.disk_bytes.map(format_bytes).unwrap_or_default());
+    }
+    row.extend([created, desc]);
+    row
 }
 
 fn render_workspace_repo_list_table(v: WorkspaceRepoListOutput) -> Result<()> {
@@ -1029,48 +2360,302 @@ fn render_workspace_repo_list_table(v: WorkspaceRepoListOutput) -> Result<()> {
     table.render()
 }
 
-fn render_status_table(v: StatusOutput) -> Result<()> {
-    let now = chrono::Utc::now().timestamp();
-    let created_age = format_relative_time(v.created.timestamp(), now);
+/// Changed files listed per repo in the Markdown briefing; the rest are
+/// counted.
+const CONTEXT_MAX_FILES: usize = 20;
 
-    let mut header = format!("Workspace: {}  Branch: {}", v.workspace, v.branch);
-    if let Some(ref desc) = v.description {
-        header.push_str(&format!("  ({})", desc));
+fn context_markdown(v: &ContextOutput) -> String {
+    let mut out = format!("# Workspace `{}`\n\n", v.workspace);
+    if let Some(ref d) = v.description {
+        out.push_str(&format!("{}\n\n", d));
     }
-    println!("{}", header);
-    println!(
-        "Created: {} ({})\n",
-        v.created.format("%Y-%m-%d %H:%M"),
-        created_age
-    );
+    if let Some(ref issue) = v.issue {
+        out.push_str(&format!("Issue: {}\n\n", issue));
+    }
+    out.push_str(&format!(
+        "Branch `{}` at `{}`. Multi-repo workspace: each directory below is its own git \
+         repository; the root is not.\n",
+        v.branch,
+        v.workspace_dir.display()
+    ));
 
-    let mut table = Table::new(
-        Box::new(std::io::stdout()),
-        vec![
-            "Repository".to_string(),
-            "Branch".to_string(),
-            "Status".to_string(),
-        ],
+    for r in &v.repos {
+        out.push_str(&format!("\n## {}\n\n", r.dir_name));
+        out.push_str(&format!("- Repo: `{}` ({})\n", r.identity, r.role));
+        if !r.path.is_empty() {
+            out.push_str(&format!("- Path: `{}`\n", r.path));
+        }
+        if let Some(ref rf) = r.r#ref {
+            out.push_str(&format!("- Ref: `{}`\n", rf));
+        }
+        if let Some(ref b) = r.branch {
+            let against = match r.upstream {
+                Some(ref u) => format!(" ({} ahead, {} behind `{}`)", r.ahead, r.behind, u),
+                None => String::new(),
+            };
+            out.push_str(&format!("- Branch: `{}`{}\n", b, against));
+        }
+        if let Some(ref e) = r.error {
+            out.push_str(&format!("- Error: {}\n", e));
+            continue;
+        }
+        if r.changed_files.is_empty() {
+            out.push_str("- Working tree: clean\n");
+        } else {
+            out.push_str(&format!(
+                "- Uncommitted changes ({}):\n",
+                r.changed_files.len()
+            ));
+            for f in r.changed_files.iter().take(CONTEXT_MAX_FILES) {
+                out.push_str(&format!("  - `{}`\n", f));
+            }
+            if r.changed_files.len() > CONTEXT_MAX_FILES {
+                out.push_str(&format!(
+                    "  - … and {} more\n",
+                    r.changed_files.len() - CONTEXT_MAX_FILES
+                ));
+            }
+        }
+        if !r.recent_commits.is_empty() {
+            out.push_str("- Recent commits:\n");
+            for c in &r.recent_commits {
+                out.push_str(&format!(
+                    "  - `{}` {}\n",
+                    &c.hash[..c.hash.len().min(12)],
+                    c.subject
+                ));
+            }
+        }
+    }
+    out
+}
+
+fn info_text(v: &InfoOutput) -> String {
+    fn field(out: &mut String, indent: &str, key: &str, value: &str) {
+        out.push_str(&format!("{}{:11}{}\n", indent, format!("{}:", key), value));
+    }
+    let mut out = String::new();
+    field(&mut out, "", "Workspace", &v.workspace);
+    field(&mut out, "", "Branch", &v.branch);
+    field(&mut out, "", "Path", &v.workspace_dir.display().to_string());
+    if let Some(ref d) = v.description {
+        field(&mut out, "", "Purpose", d);
+    }
+    if let Some(ref issue) = v.issue {
+        field(&mut out, "", "Issue", issue);
+    }
+    field(
+        &mut out,
+        "",
+        "Created",
+        &v.created.format("%Y-%m-%d %H:%M").to_string(),
     );
+    if let Some(ref t) = v.last_used {
+        field(
+            &mut out,
+            "",
+            "Last used",
+            &t.format("%Y-%m-%d %H:%M").to_string(),
+        );
+    }
+    if let Some(ref from) = v.created_from {
+        field(&mut out, "", "From", from);
+    }
+
+    for r in &v.repos {
+        out.push_str(&format!("\n==> [{}]\n", r.dir_name));
+        let ind = "  ";
+        field(&mut out, ind, "Identity", &r.identity);
+        if !r.path.is_empty() {
+            field(&mut out, ind, "Dir", &r.path);
+        }
+        field(&mut out, ind, "Role", &r.role);
+        if let Some(ref rf) = r.r#ref {
+            field(&mut out, ind, "Ref", rf);
+        }
+        if let Some(ref b) = r.branch {
+            let sha = r.sha.as_deref().map(|s| &s[..s.len().min(12)]);
+            let head = match sha {
+                Some(sha) => format!("{} @ {}", b, sha),
+                None => b.clone(),
+            };
+            field(&mut out, ind, "HEAD", &head);
+        }
+        if let Some(ref u) = r.upstream {
+            let note = if r.has_upstream { "" } else { ", not tracking" };
+            field(
+                &mut out,
+                ind,
+                "Upstream",
+                &format!("{} ({} ahead, {} behind{})", u, r.ahead, r.behind, note),
+            );
+        }
+        if let Some(ref url) = r.origin_url {
+            field(&mut out, ind, "Origin", url);
+        }
+        if let Some(ref m) = r.mirror {
+            let missing = if r.mirror_exists { "" } else { " (missing)" };
+            field(&mut out, ind, "Mirror", &format!("{}{}", m, missing));
+        }
+        if let Some(ref e) = r.error {
+            field(&mut out, ind, "Error", e);
+        }
+    }
+    for (identity, e) in &v.pending {
+        out.push_str(&format!(
+            "\n==> {} (pending, retry with `wsp repair`)\n",
+            identity
+        ));
+        field(&mut out, "  ", "Error", e);
+    }
+    out
+}
+
+/// Version of the `wsp st --porcelain` format. Bump when fields change;
+/// existing fields never change meaning within a version.
+pub const STATUS_PORCELAIN_VERSION: u32 = 1;
+
+fn render_status_porcelain(v: StatusOutput) -> Result<()> {
+    if v.streamed {
+        return Ok(());
+    }
     for rs in &v.repos {
-        let status = if let Some(ref e) = rs.error {
-            format_error(e)
+        if let Some(ref e) = rs.error {
+            eprintln!("[{}] error: {}", rs.shortname, e);
+        }
+    }
+    write!(std::io::stdout().lock(), "{}", status_porcelain(&v))?;
+    Ok(())
+}
+
+/// One header line (`# wsp-status v<N>`), then one tab-separated line per
+/// repo: repo, branch, ahead, behind, modified, untracked. Unknown values
+/// (repos that failed to load) are `-`.
+fn status_porcelain(v: &StatusOutput) -> String {
+    let mut out = status_porcelain_header();
+    for rs in &v.repos {
+        out.push_str(&status_porcelain_line(rs));
+    }
+    out
+}
+
+pub fn status_porcelain_header() -> String {
+    format!("# wsp-status v{}\n", STATUS_PORCELAIN_VERSION)
+}
+
+/// One repo's porcelain line, newline included.
+pub fn status_porcelain_line(rs: &RepoStatusEntry) -> String {
+    if rs.error.is_some() {
+        return format!("{}\t-\t-\t-\t-\t-\n", rs.shortname);
+    }
+    let branch = match rs.branch.as_str() {
+        "" | "?" => "-",
+        b => b,
+    };
+    let untracked = rs.files.iter().filter(|f| f.starts_with("??")).count();
+    let modified = rs.files.len() - untracked;
+    let count = |n: u32| {
+        if rs.compared {
+            n.to_string()
         } else {
-            format_repo_status(
-                rs.ahead,
-                rs.behind,
-                rs.changed,
-                rs.has_upstream,
-                &rs.expected_branch,
-            )
-        };
-        table.add_row(vec![rs.shortname.clone(), rs.branch.clone(), status])?;
+            "-".to_string()
+        }
+    };
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}\n",
+        rs.shortname,
+        branch,
+        count(rs.ahead),
+        count(rs.behind),
+        modified,
+        untracked
+    )
+}
+
+/// The "Workspace:" and "Created:" lines above the `wsp st` table, and
+/// the blank line after them.
+pub fn status_preamble(
+    workspace: &str,
+    branch: &str,
+    description: Option<&str>,
+    created: DateTime<Utc>,
+    fetched: Option<DateTime<Utc>>,
+) -> String {
+    let now = chrono::Utc::now().timestamp();
+    let mut out = format!("Workspace: {}  Branch: {}", workspace, branch);
+    if let Some(desc) = description {
+        out.push_str(&format!("  ({})", desc));
     }
-    if !v.root.is_empty() {
-        let root_status = format!("{} untracked", v.root.len());
-        table.add_row(vec!["(workspace root)".into(), "-".into(), root_status])?;
+    out.push('\n');
+    let mut line = format!(
+        "Created: {} ({})",
+        created.format("%Y-%m-%d %H:%M"),
+        format_relative_time(created.timestamp(), now)
+    );
+    if let Some(fetched) = fetched {
+        line.push_str(&format!(
+            "  Fetched: {}",
+            format_relative_time(fetched.timestamp(), now)
+        ));
+    }
+    out.push_str(&line);
+    out.push_str("\n\n");
+    out
+}
+
+pub fn status_table_headers() -> Vec<String> {
+    vec![
+        "Repository".to_string(),
+        "Branch".to_string(),
+        "Status".to_string(),
+    ]
+}
+
+/// One repo's row in the `wsp st` table.
+pub fn status_table_row(rs: &RepoStatusEntry) -> Vec<String> {
+    let status = if let Some(ref e) = rs.error {
+        format_error(e)
+    } else {
+        repo_status_text(rs)
+    };
+    vec![rs.shortname.clone(), rs.branch.clone(), status]
+}
+
+/// Name of the `wsp st` row for untracked files at the workspace root.
+pub const STATUS_ROOT_NAME: &str = "(workspace root)";
+
+/// The `wsp st` row for untracked files at the workspace root.
+pub fn status_root_row(root: &[String]) -> Vec<String> {
+    vec![
+        STATUS_ROOT_NAME.into(),
+        "-".into(),
+        format!("{} untracked", root.len()),
+    ]
+}
+
+fn render_status_table(v: StatusOutput) -> Result<()> {
+    let mut out = std::io::stdout().lock();
+    if !v.streamed {
+        write!(
+            out,
+            "{}",
+            status_preamble(
+                &v.workspace,
+                &v.branch,
+                v.description.as_deref(),
+                v.created,
+                v.fetched,
+            )
+        )?;
+        let mut table = Table::new(Box::new(std::io::stdout()), status_table_headers());
+        for rs in &v.repos {
+            table.add_row(status_table_row(rs))?;
+        }
+        if !v.root.is_empty() {
+            table.add_row(status_root_row(&v.root))?;
+        }
+        table.render()?;
     }
-    table.render()?;
 
     let has_detail = v.repos.iter().any(|r| !r.files.is_empty()) || !v.root.is_empty();
 
@@ -1079,24 +2664,40 @@ fn render_status_table(v: StatusOutput) -> Result<()> {
             if rs.error.is_some() || rs.files.is_empty() {
                 continue;
             }
-            println!("\n==> [{}]", rs.shortname);
+            writeln!(out, "\n==> [{}]", rs.shortname)?;
             for f in &rs.files {
-                println!("  {}", f);
+                writeln!(out, "  {}", f)?;
             }
         }
         if !v.root.is_empty() {
-            println!("\n==> [workspace root]");
+            writeln!(out, "\n==> [workspace root]")?;
             for item in &v.root {
-                println!("  {}", item);
+                writeln!(out, "  {}", item)?;
             }
         }
     } else if has_detail {
-        println!("\nUse `wsp st -v` to see file details.");
+        writeln!(out, "\nUse `wsp st --files` to see file details.")?;
     }
 
     if !v.root.is_empty() {
         eprintln!("\nhint: suppress with wspignore (see `wsp help wspignore`)");
     }
+    let drifted: Vec<&str> = v
+        .repos
+        .iter()
+        .filter_map(|r| r.expected_branch.as_deref())
+        .collect();
+    if !drifted.is_empty() {
+        let target = if drifted.iter().all(|b| *b == v.branch) {
+            v.branch.as_str()
+        } else {
+            "their workspace branches"
+        };
+        eprintln!(
+            "\nhint: run `wsp fix` to switch drifted repos back to {}",
+            target
+        );
+    }
 
     Ok(())
 }
@@ -1108,38 +2709,349 @@ fn render_diff_text(v: DiffOutput) -> Result<()> {
             eprintln!("[{}] error: {}", entry.shortname, e);
             continue;
         }
-        if entry.diff.is_empty() {
-            continue;
+        match (&entry.diff, &entry.stat) {
+            (Some(diff), _) if !diff.is_empty() => {
+                if !first {
+                    println!();
+                }
+                println!("==> [{}]", entry.shortname);
+                println!("{}", diff);
+            }
+            (None, Some(stat)) if stat.files_changed > 0 => {
+                if !first {
+                    println!();
+                }
+                println!(
+                    "==> [{}] {} file{} changed, +{} -{}",
+                    entry.shortname,
+                    stat.files_changed,
+                    if stat.files_changed == 1 { "" } else { "s" },
+                    stat.insertions,
+                    stat.deletions
+                );
+                for f in &stat.files {
+                    if f.binary {
+                        println!("  {}  (binary)", f.path);
+                    } else {
+                        println!("  {}  +{} -{}", f.path, f.insertions, f.deletions);
+                    }
+                }
+            }
+            _ => continue,
         }
-        if !first {
-            println!();
+        first = false;
+    }
+    Ok(())
+}
+
+fn render_run_summary(v: RunOutput) -> Result<()> {
+    if v.repos.is_empty() {
+        println!("Task {:?} has no commands for this workspace.", v.task);
+        return Ok(());
+    }
+    let mut table = Table::new(
+        Box::new(std::io::stdout()),
+        vec![
+            "Task".to_string(),
+            "Repo".to_string(),
+            "Status".to_string(),
+            "Time".to_string(),
+        ],
+    );
+    for r in &v.repos {
+        table.add_row(vec![
+            r.task.clone(),
+            r.result.shortname.clone(),
+            r.status.clone(),
+            format!("{:.1}s", r.duration_ms as f64 / 1000.0),
+        ])?;
+    }
+    table.render()
+}
+
+/// Render a dependency graph in Graphviz DOT. Edges point from a repo to the
+/// repo it depends on; workspace repos are filled.
+fn deps_graph_dot(v: &DepsGraphOutput) -> String {
+    let mut out = String::from("digraph repos {\n  rankdir=LR;\n  node [shape=box];\n");
+    for r in &v.repos {
+        let style = if r.in_workspace {
+            ", style=filled, fillcolor=lightblue"
+        } else {
+            ""
+        };
+        out.push_str(&format!(
+            "  {:?} [label={:?}{}];\n",
+            r.identity, r.shortname, style
+        ));
+    }
+    for r in &v.repos {
+        for dep in &r.depends_on {
+            out.push_str(&format!("  {:?} -> {:?};\n", r.identity, dep));
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Render a workspace layout as a tmuxinator project: one tiled window with a
+/// pane per repo. Strings are JSON-quoted, which YAML accepts.
+fn layout_tmuxinator(v: &LayoutOutput) -> String {
+    let q = |s: &str| serde_json::to_string(s).unwrap_or_default();
+    let mut out = format!(
+        "name: {}\nroot: {}\nwindows:\n  - {}:\n      layout: tiled\n      panes:\n",
+        q(&crate::tmux::session_name(&v.workspace)),
+        q(&v.path),
+        q(&v.workspace),
+    );
+    for p in &v.panes {
+        let cd = format!("cd {}", crate::util::shell_quote(&p.directory));
+        out.push_str(&format!("        - {}\n", q(&cd)));
+    }
+    out
+}
+
+/// Render a workspace layout as a zellij KDL layout: one tab with a pane per
+/// repo, keeping zellij's default tab and status bars.
+fn layout_zellij(v: &LayoutOutput) -> String {
+    let mut out = format!("layout {{\n    cwd {}\n", kdl_quote(&v.path));
+    out.push_str(
+        "    default_tab_template {\n        \
+         pane size=1 borderless=true {\n            \
+         plugin location=\"zellij:tab-bar\"\n        }\n        \
+         children\n        \
+         pane size=2 borderless=true {\n            \
+         plugin location=\"zellij:status-bar\"\n        }\n    }\n",
+    );
+    out.push_str(&format!(
+        "    tab name={} focus=true {{\n",
+        kdl_quote(&v.workspace)
+    ));
+    for p in &v.panes {
+        let dir = kdl_quote(&p.directory);
+        out.push_str(&format!("        pane name={} cwd={}\n", dir, dir));
+    }
+    out.push_str("    }\n}\n");
+    out
+}
+
+/// Quote a KDL string: backslash, quote, and the escapes KDL defines, with
+/// other control characters as `\u{...}`.
+fn kdl_quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            c if c.is_control() => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Render a dependency graph as a Mermaid flowchart. Identities aren't valid
+/// Mermaid ids, so nodes are numbered and labeled with their shortname.
+fn deps_graph_mermaid(v: &DepsGraphOutput) -> String {
+    let ids: std::collections::HashMap<&str, String> = v
+        .repos
+        .iter()
+        .enumerate()
+        .map(|(i, r)| (r.identity.as_str(), format!("n{}", i)))
+        .collect();
+    let mut out = String::from("graph LR\n");
+    for r in &v.repos {
+        out.push_str(&format!(
+            "  {}[\"{}\"]\n",
+            ids[r.identity.as_str()],
+            r.shortname
+        ));
+    }
+    for r in &v.repos {
+        for dep in &r.depends_on {
+            if let Some(to) = ids.get(dep.as_str()) {
+                out.push_str(&format!("  {} --> {}\n", ids[r.identity.as_str()], to));
+            }
+        }
+    }
+    let in_ws: Vec<&str> = v
+        .repos
+        .iter()
+        .filter(|r| r.in_workspace)
+        .map(|r| ids[r.identity.as_str()].as_str())
+        .collect();
+    if !in_ws.is_empty() {
+        out.push_str("  classDef workspace fill:#cde4ff,stroke:#4a90d9\n");
+        out.push_str(&format!("  class {} workspace\n", in_ws.join(",")));
+    }
+    out
+}
+
+fn render_fetch_text(v: FetchOutput) -> Result<()> {
+    let skipped = v.repos.iter().filter(|r| r.skipped).count();
+    let total = v.repos.len() - skipped;
+    let failed = v.repos.iter().filter(|r| !r.ok).count();
+    let retried = v.repos.iter().filter(|r| r.retries > 0).count();
+    let retried = if retried > 0 {
+        format!(" ({} after retrying)", retried)
+    } else {
+        String::new()
+    };
+    let skipped = if skipped > 0 {
+        format!(", {} skipped (fetched recently)", skipped)
+    } else {
+        String::new()
+    };
+    if failed == 0 {
+        println!("Fetched {} repo(s){}{}", total, retried, skipped);
+    } else {
+        println!(
+            "Fetched {} repo(s), {} failed{}{}",
+            total - failed,
+            failed,
+            retried,
+            skipped
+        );
+    }
+    Ok(())
+}
+
+fn render_pr_text(v: PrOutput) -> Result<()> {
+    if v.dry_run {
+        println!(
+            "Workspace: {}  Branch: {}  (dry run)\n",
+            v.workspace, v.branch
+        );
+    } else {
+        println!("Workspace: {}  Branch: {}\n", v.workspace, v.branch);
+    }
+
+    let mut table = Table::new(
+        Box::new(std::io::stdout()),
+        vec![
+            "Repository".to_string(),
+            "Action".to_string(),
+            "Result".to_string(),
+        ],
+    );
+    for r in &v.repos {
+        let result = if let Some(ref e) = r.error {
+            format!("ERROR — {}", e)
+        } else {
+            [r.url.as_deref(), r.detail.as_deref()]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join("  ")
+        };
+        table.add_row(vec![r.shortname.clone(), r.action.clone(), result])?;
+    }
+    table.render()
+}
+
+fn render_pr_status_text(v: PrStatusOutput) -> Result<()> {
+    println!("Workspace: {}  Branch: {}\n", v.workspace, v.branch);
+    let mut table = Table::new(
+        Box::new(std::io::stdout()),
+        vec![
+            "Repository".to_string(),
+            "PR".to_string(),
+            "State".to_string(),
+            "Review".to_string(),
+            "Checks".to_string(),
+            "Blockers".to_string(),
+        ],
+    );
+    for r in &v.repos {
+        if let Some(ref e) = r.error {
+            table.add_row(vec![
+                r.shortname.clone(),
+                "-".into(),
+                "-".into(),
+                "-".into(),
+                "-".into(),
+                format!("ERROR — {}", e),
+            ])?;
+            continue;
         }
-        println!("==> [{}]", entry.shortname);
-        println!("{}", entry.diff);
-        first = false;
+        let Some(number) = r.number else {
+            table.add_row(vec![
+                r.shortname.clone(),
+                "-".into(),
+                "no PR".into(),
+                "-".into(),
+                "-".into(),
+                String::new(),
+            ])?;
+            continue;
+        };
+        let state = if r.draft && r.state == "open" {
+            "draft".to_string()
+        } else {
+            r.state.clone()
+        };
+        let total = r.checks_passed + r.checks_failed + r.checks_pending;
+        let checks = if total == 0 {
+            "-".to_string()
+        } else if r.checks_failed > 0 {
+            format!("{}/{} failing", r.checks_failed, total)
+        } else if r.checks_pending > 0 {
+            format!("{}/{} pending", r.checks_pending, total)
+        } else {
+            format!("{}/{} passed", r.checks_passed, total)
+        };
+        let review = if r.review.is_empty() {
+            "-".to_string()
+        } else {
+            r.review.replace('_', " ")
+        };
+        table.add_row(vec![
+            r.shortname.clone(),
+            if r.forge == "gitlab" {
+                format!("!{}", number)
+            } else {
+                format!("#{}", number)
+            },
+            state,
+            review,
+            checks,
+            r.blockers.join(", "),
+        ])?;
     }
-    Ok(())
-}
-
-fn render_fetch_text(v: FetchOutput) -> Result<()> {
-    let total = v.repos.len();
-    let failed = v.repos.iter().filter(|r| !r.ok).count();
-    if failed == 0 {
-        println!("Fetched {} repo(s)", total);
-    } else {
-        println!("Fetched {} repo(s), {} failed", total - failed, failed);
+    table.render()?;
+    if v.ready {
+        println!("\nReady to merge.");
+    } else if v.repos.iter().any(|r| r.url.is_some()) {
+        println!("\nNot ready to merge.");
     }
     Ok(())
 }
 
 fn render_sync_text(v: SyncOutput) -> Result<()> {
+    let mut notes = Vec::new();
     if v.dry_run {
+        notes.push("dry run");
+    }
+    let skipped;
+    if let Some(ref why) = v.fetch_skipped {
+        skipped = format!("{}, against last-fetched refs", why);
+        notes.push(&skipped);
+    }
+    if notes.is_empty() {
+        println!("Workspace: {}  Branch: {}\n", v.workspace, v.branch);
+    } else {
         println!(
-            "Workspace: {}  Branch: {}  (dry run)\n",
-            v.workspace, v.branch
+            "Workspace: {}  Branch: {}  ({})\n",
+            v.workspace,
+            v.branch,
+            notes.join("; ")
         );
-    } else {
-        println!("Workspace: {}  Branch: {}\n", v.workspace, v.branch);
     }
 
     let mut table = Table::new(
@@ -1204,6 +3116,33 @@ fn render_sync_abort_text(v: SyncAbortOutput) -> Result<()> {
     Ok(())
 }
 
+fn render_fix_text(v: FixOutput) -> Result<()> {
+    if v.repos.is_empty() {
+        println!("All repos are on {}.", v.branch);
+        return Ok(());
+    }
+    let mut table = Table::new(
+        Box::new(std::io::stdout()),
+        vec![
+            "Repository".to_string(),
+            "Was on".to_string(),
+            "Result".to_string(),
+        ],
+    );
+    for r in &v.repos {
+        let from = match r.from.as_str() {
+            "HEAD" => "(detached)".to_string(),
+            b => b.to_string(),
+        };
+        let result = match &r.error {
+            Some(e) => format!("skipped — {}", e),
+            None => format!("switched to {}", r.to),
+        };
+        table.add_row(vec![r.shortname.clone(), from, result])?;
+    }
+    table.render()
+}
+
 fn render_config_list_text(v: ConfigListOutput) -> Result<()> {
     if v.entries.is_empty() {
         println!("No config values set.");
@@ -1254,13 +3193,69 @@ fn render_config_get_text(v: ConfigGetOutput) -> Result<()> {
     Ok(())
 }
 
-fn render_mutation_text(v: MutationOutput) -> Result<()> {
-    match v.duration_ms {
-        Some(ms) => println!("{} ({:.1}s)", v.message, ms as f64 / 1000.0),
-        None => println!("{}", v.message),
+fn render_shell_check_text(v: ShellCheckOutput) -> Result<()> {
+    match (&v.workspace, &v.path) {
+        (Some(ws), Some(path)) => println!("Inside wsp shell for workspace {:?} ({})", ws, path),
+        (None, Some(path)) => println!("Inside wsp shell ({})", path),
+        _ => println!("Not inside a wsp shell."),
+    }
+    Ok(())
+}
+
+fn render_schedule_status_text(v: ScheduleStatusOutput) -> Result<()> {
+    if !v.installed {
+        println!("No scheduled fetch installed.");
+        println!("  install one with `wsp setup schedule install`");
+        return Ok(());
+    }
+    let every = match v.interval_minutes {
+        Some(m) => format!("every {} min", m),
+        None => "custom schedule".into(),
+    };
+    let state = match v.active {
+        Some(true) => "active",
+        Some(false) => "inactive",
+        None => "unknown",
+    };
+    println!("Scheduled fetch ({}): {}, {}", v.scheduler, every, state);
+    println!("  {}", v.path);
+    Ok(())
+}
+
+fn render_auth_status_table(v: AuthStatusOutput) -> Result<()> {
+    if v.hosts.is_empty() {
+        println!("No forge hosts: register a repo or set forge.<host> first.");
+        return Ok(());
+    }
+    let mut table = Table::new(
+        Box::new(std::io::stdout()),
+        vec!["Host".to_string(), "CLI".to_string(), "Token".to_string()],
+    );
+    for h in &v.hosts {
+        let token = match (h.source.as_str(), &h.variable) {
+            ("env", Some(var)) => format!("${}", var),
+            ("keyring", _) => "keyring".to_string(),
+            _ => format!("{}'s own login", h.cli),
+        };
+        table.add_row(vec![h.host.clone(), h.cli.clone(), token])?;
     }
+    table.render()
+}
+
+fn render_mutation_text(v: MutationOutput) -> Result<()> {
+    let mut text = match v.duration_ms {
+        Some(ms) => format!("{} ({:.1}s)\n", v.message, ms as f64 / 1000.0),
+        None => format!("{}\n", v.message),
+    };
     if let Some(hint) = &v.hint {
-        println!("  {}", hint);
+        text.push_str(&format!("  {}\n", hint));
+    }
+    match v.path.as_deref().filter(|_| v.print_dir) {
+        Some(path) => {
+            eprint!("{}", text);
+            println!("{}", path);
+        }
+        None => print!("{}", text),
     }
     Ok(())
 }
@@ -1290,13 +3285,30 @@ fn render_import_text(v: ImportOutput) -> Result<()> {
     Ok(())
 }
 
+fn render_schema_list_table(v: SchemaListOutput) -> Result<()> {
+    let mut table = Table::new(
+        Box::new(std::io::stdout()),
+        vec!["Name".to_string(), "Describes".to_string()],
+    );
+    for s in &v.schemas {
+        table.add_row(vec![s.name.clone(), s.command.clone()])?;
+    }
+    table.render()
+}
+
+/// Schemas are JSON documents, so table (and CSV) output prints them as JSON.
+fn render_schema_text(v: SchemaOutput) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(&v.0)?);
+    Ok(())
+}
+
 fn render_path_text(v: PathOutput) -> Result<()> {
     println!("{}", v.path);
     Ok(())
 }
 
-fn format_age(trashed_at: &chrono::DateTime<chrono::Utc>) -> String {
-    let age = chrono::Utc::now() - trashed_at;
+pub fn format_age(at: &chrono::DateTime<chrono::Utc>) -> String {
+    let age = chrono::Utc::now() - at;
     if age.num_seconds() < 0 {
         return "just now".into();
     }
@@ -1309,7 +3321,7 @@ fn format_age(trashed_at: &chrono::DateTime<chrono::Utc>) -> String {
     }
 }
 
-fn format_bytes(bytes: u64) -> String {
+pub fn format_bytes(bytes: u64) -> String {
     if bytes >= 1_073_741_824 {
         format!("{:.1} GB", bytes as f64 / 1_073_741_824.0)
     } else if bytes >= 1_048_576 {
@@ -1532,9 +3544,144 @@ mod tests {
         String::from_utf8(buf).unwrap()
     }
 
+    fn sample_deps_graph() -> DepsGraphOutput {
+        let repo = |name: &str, in_workspace: bool, deps: &[&str]| DepsGraphRepo {
+            identity: format!("github.com/acme/{}", name),
+            shortname: name.into(),
+            in_workspace,
+            depends_on: deps
+                .iter()
+                .map(|d| format!("github.com/acme/{}", d))
+                .collect(),
+        };
+        DepsGraphOutput {
+            workspace: Some("ws".into()),
+            repos: vec![
+                repo("api", true, &["proto"]),
+                repo("proto", false, &[]),
+                repo("web", true, &["api", "proto"]),
+            ],
+            format: "dot".into(),
+        }
+    }
+
+    #[test]
+    fn test_deps_graph_render() {
+        let v = sample_deps_graph();
+        let cases = vec![
+            (
+                "dot",
+                deps_graph_dot(&v),
+                "digraph repos {
+  rankdir=LR;
+  node [shape=box];
+  \"github.com/acme/api\" [label=\"api\", style=filled, fillcolor=lightblue];
+  \"github.com/acme/proto\" [label=\"proto\"];
+  \"github.com/acme/web\" [label=\"web\", style=filled, fillcolor=lightblue];
+  \"github.com/acme/api\" -> \"github.com/acme/proto\";
+  \"github.com/acme/web\" -> \"github.com/acme/api\";
+  \"github.com/acme/web\" -> \"github.com/acme/proto\";
+}
+",
+            ),
+            (
+                "mermaid",
+                deps_graph_mermaid(&v),
+                "graph LR
+  n0[\"api\"]
+  n1[\"proto\"]
+  n2[\"web\"]
+  n0 --> n1
+  n2 --> n0
+  n2 --> n1
+  classDef workspace fill:#cde4ff,stroke:#4a90d9
+  class n0,n2 workspace
+",
+            ),
+        ];
+        for (name, got, want) in cases {
+            assert_eq!(got, want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_layout_render() {
+        let v = LayoutOutput {
+            workspace: "fix.auth".into(),
+            path: "/ws/fix.auth".into(),
+            panes: vec![
+                LayoutPane {
+                    identity: "github.com/acme/api".into(),
+                    directory: "api".into(),
+                    path: "/ws/fix.auth/api".into(),
+                },
+                LayoutPane {
+                    identity: "github.com/other/api".into(),
+                    directory: "other-api".into(),
+                    path: "/ws/fix.auth/other-api".into(),
+                },
+            ],
+            kind: "tmux".into(),
+        };
+        let cases = vec![
+            (
+                "tmuxinator",
+                layout_tmuxinator(&v),
+                "name: \"fix-auth\"
+root: \"/ws/fix.auth\"
+windows:
+  - \"fix.auth\":
+      layout: tiled
+      panes:
+        - \"cd api\"
+        - \"cd other-api\"
+",
+            ),
+            (
+                "zellij",
+                layout_zellij(&v),
+                "layout {
+    cwd \"/ws/fix.auth\"
+    default_tab_template {
+        pane size=1 borderless=true {
+            plugin location=\"zellij:tab-bar\"
+        }
+        children
+        pane size=2 borderless=true {
+            plugin location=\"zellij:status-bar\"
+        }
+    }
+    tab name=\"fix.auth\" focus=true {
+        pane name=\"api\" cwd=\"api\"
+        pane name=\"other-api\" cwd=\"other-api\"
+    }
+}
+",
+            ),
+        ];
+        for (name, got, want) in cases {
+            assert_eq!(got, want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_kdl_quote() {
+        let cases = vec![
+            ("plain", "api", r#""api""#),
+            ("quote and backslash", r#"a"b\c"#, r#""a\"b\\c""#),
+            ("named escapes", "a\nb\tc\u{8}", r#""a\nb\tc\b""#),
+            ("other control", "a\0b", r#""a\u{0}b""#),
+            ("unicode kept", "café", r#""café""#),
+        ];
+        for (name, input, want) in cases {
+            assert_eq!(kdl_quote(input), want, "{}", name);
+        }
+    }
+
     #[test]
     fn test_table() {
-        let cases: Vec<(&str, Vec<&str>, Vec<Vec<&str>>, &str)> = vec![
+        type Case<'a> = (&'a str, Vec<&'a str>, Vec<Vec<&'a str>>, &'a str);
+        let cases: Vec<Case> = vec![
             (
                 "single column",
                 vec!["Name"],
@@ -1608,7 +3755,8 @@ mod tests {
     fn test_format_repo_status() {
         let none: Option<String> = None;
         //                  (name, ahead, behind, modified, has_upstream, expected_branch, want)
-        let cases: Vec<(&str, u32, u32, u32, bool, &Option<String>, &str)> = vec![
+        type Case<'a> = (&'a str, u32, u32, u32, bool, &'a Option<String>, &'a str);
+        let cases: Vec<Case> = vec![
             ("clean", 0, 0, 0, true, &none, "clean"),
             ("clean no upstream", 0, 0, 0, false, &none, "clean"),
             ("modified only", 0, 0, 5, true, &none, "5 modified"),
@@ -1684,6 +3832,83 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_prompt_text() {
+        let prompt = |status| PromptOutput {
+            workspace: "feat".into(),
+            status,
+            starship: false,
+        };
+        let summary = |dirty, ahead, errors| {
+            Some(WorkspaceStatusSummary {
+                dirty,
+                ahead,
+                errors,
+            })
+        };
+        let cases = vec![
+            (prompt(None), "feat"),
+            (prompt(summary(0, 0, 0)), "feat"),
+            (prompt(summary(2, 0, 0)), "feat *2"),
+            (prompt(summary(1, 5, 0)), "feat *1 ↑5"),
+            (prompt(summary(0, 3, 1)), "feat ↑3 ?"),
+        ];
+        for (p, want) in cases {
+            assert_eq!(p.text(), want);
+        }
+    }
+
+    #[test]
+    fn test_repo_status_text() {
+        let entry = |ahead, changed, untracked, stashes, pushed| RepoStatusEntry {
+            identity: "github.com/user/api".into(),
+            shortname: "api".into(),
+            path: String::new(),
+            branch: "my-ws".into(),
+            ahead,
+            behind: 0,
+            compared: true,
+            changed,
+            untracked,
+            stashes,
+            has_upstream: pushed,
+            pushed,
+            role: "active".into(),
+            files: vec![],
+            error: None,
+            expected_branch: None,
+            in_progress: None,
+        };
+        let cases = vec![
+            ("clean", entry(0, 0, 0, 0, true), "clean"),
+            (
+                "unpushed branch, nothing ahead",
+                entry(0, 0, 0, 0, false),
+                "clean",
+            ),
+            (
+                "modified and untracked",
+                entry(0, 3, 1, 0, true),
+                "2 modified, 1 untracked",
+            ),
+            ("only untracked", entry(0, 2, 2, 0, true), "2 untracked"),
+            ("stash alone", entry(0, 0, 0, 2, true), "2 stashed"),
+            (
+                "never pushed",
+                entry(1, 0, 0, 0, false),
+                "1 ahead (no upstream), not pushed",
+            ),
+            (
+                "pushed and ahead",
+                entry(1, 0, 0, 1, true),
+                "1 ahead, 1 stashed",
+            ),
+        ];
+        for (name, rs, want) in cases {
+            assert_eq!(repo_status_text(&rs), want, "{}", name);
+        }
+    }
+
     #[test]
     fn test_format_error() {
         assert_eq!(format_error(&"something broke"), "ERROR: something broke");
@@ -1722,12 +3947,19 @@ mod tests {
                 repo_count: 2,
                 path: "/home/user/dev/workspaces/my-ws".into(),
                 description: Some("test workspace".into()),
+                issue: None,
                 created: "2026-03-01T10:00:00+00:00".into(),
                 last_used: None,
                 created_from: None,
+                status: None,
+                disk_bytes: None,
             }],
+            total_disk_bytes: None,
+            total: None,
+            streamed: false,
         };
         let val = serde_json::to_value(&output).unwrap();
+        assert!(val.get("total").is_none());
         assert_eq!(val["workspaces"][0]["name"], "my-ws");
         assert_eq!(val["workspaces"][0]["repo_count"], 2);
         assert_eq!(val["workspaces"][0]["description"], "test workspace");
@@ -1800,6 +4032,8 @@ mod tests {
             workspace_dir: PathBuf::from("/tmp/workspaces/my-ws"),
             description: None,
             created: "2026-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+            fetched: None,
+            fetch_skipped: None,
             repos: vec![
                 RepoStatusEntry {
                     identity: "github.com/user/repo-a".into(),
@@ -1808,12 +4042,17 @@ mod tests {
                     branch: "my-ws".into(),
                     ahead: 1,
                     behind: 3,
+                    compared: true,
                     changed: 2,
+                    untracked: 1,
+                    stashes: 1,
                     has_upstream: true,
+                    pushed: true,
                     role: "active".into(),
                     files: vec![" M src/main.rs".into(), "?? new.txt".into()],
                     error: None,
                     expected_branch: None,
+                    in_progress: None,
                 },
                 RepoStatusEntry {
                     identity: "github.com/user/repo-b".into(),
@@ -1822,16 +4061,23 @@ mod tests {
                     branch: String::new(),
                     ahead: 0,
                     behind: 0,
+                    compared: true,
                     changed: 0,
+                    untracked: 0,
+                    stashes: 0,
                     has_upstream: false,
+                    pushed: false,
                     role: "active".into(),
                     files: vec![],
                     error: Some("parse error".into()),
                     expected_branch: None,
+                    in_progress: None,
                 },
             ],
             root: vec![],
             verbose: false,
+            porcelain: false,
+            streamed: false,
         };
         let val = serde_json::to_value(&output).unwrap();
         assert_eq!(val["workspace"], "my-ws");
@@ -1839,7 +4085,10 @@ mod tests {
         assert_eq!(val["repos"][0]["ahead"], 1);
         assert_eq!(val["repos"][0]["behind"], 3);
         assert_eq!(val["repos"][0]["changed"], 2);
+        assert_eq!(val["repos"][0]["untracked"], 1);
+        assert_eq!(val["repos"][0]["stashes"], 1);
         assert_eq!(val["repos"][0]["has_upstream"], true);
+        assert_eq!(val["repos"][0]["pushed"], true);
         assert_eq!(val["repos"][0]["role"], "active");
         assert_eq!(val["repos"][0]["files"][0], " M src/main.rs");
         assert_eq!(val["repos"][0]["files"][1], "?? new.txt");
@@ -1854,6 +4103,208 @@ mod tests {
         assert!(val.get("root").is_none());
     }
 
+    #[test]
+    fn test_info_text() {
+        let entry = |name: &str| InfoRepoEntry {
+            identity: format!("github.com/acme/{}", name),
+            dir_name: name.into(),
+            path: format!("/ws/demo/{}", name),
+            role: "active".into(),
+            r#ref: None,
+            branch: Some("demo".into()),
+            sha: Some("0123456789abcdef0123".into()),
+            upstream: Some("origin/main".into()),
+            has_upstream: false,
+            ahead: 1,
+            behind: 0,
+            origin_url: Some(format!("/mirrors/{}.git", name)),
+            mirror: Some(format!("/mirrors/{}.git", name)),
+            mirror_exists: true,
+            error: None,
+        };
+        let output = InfoOutput {
+            workspace: "demo".into(),
+            branch: "demo".into(),
+            workspace_dir: PathBuf::from("/ws/demo"),
+            description: None,
+            issue: None,
+            created: "2026-01-01T08:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+            last_used: None,
+            created_from: None,
+            repos: vec![
+                entry("api"),
+                InfoRepoEntry {
+                    r#ref: Some("v2".into()),
+                    mirror_exists: false,
+                    error: Some("clone directory is missing".into()),
+                    branch: None,
+                    sha: None,
+                    upstream: None,
+                    origin_url: None,
+                    ..entry("web")
+                },
+            ],
+            pending: BTreeMap::from([(
+                "github.com/acme/docs".into(),
+                "cloning mirror: connection refused".into(),
+            )]),
+        };
+        assert_eq!(
+            info_text(&output),
+            "Workspace: demo\n\
+             Branch:    demo\n\
+             Path:      /ws/demo\n\
+             Created:   2026-01-01 08:00\n\
+             \n==> [api]\n  \
+             Identity:  github.com/acme/api\n  \
+             Dir:       /ws/demo/api\n  \
+             Role:      active\n  \
+             HEAD:      demo @ 0123456789ab\n  \
+             Upstream:  origin/main (1 ahead, 0 behind, not tracking)\n  \
+             Origin:    /mirrors/api.git\n  \
+             Mirror:    /mirrors/api.git\n\
+             \n==> [web]\n  \
+             Identity:  github.com/acme/web\n  \
+             Dir:       /ws/demo/web\n  \
+             Role:      active\n  \
+             Ref:       v2\n  \
+             Mirror:    /mirrors/web.git (missing)\n  \
+             Error:     clone directory is missing\n\
+             \n==> github.com/acme/docs (pending, retry with `wsp repair`)\n  \
+             Error:     cloning mirror: connection refused\n"
+        );
+    }
+
+    #[test]
+    fn test_context_markdown() {
+        let entry = |name: &str| ContextRepoEntry {
+            identity: format!("github.com/acme/{}", name),
+            dir_name: name.into(),
+            path: format!("/ws/demo/{}", name),
+            role: "active".into(),
+            r#ref: None,
+            branch: Some("demo".into()),
+            upstream: Some("origin/main".into()),
+            ahead: 1,
+            behind: 0,
+            changed_files: vec![],
+            recent_commits: vec![LogCommit {
+                hash: "0123456789abcdef0123".into(),
+                authored_at: String::new(),
+                timestamp: 0,
+                subject: "Add login".into(),
+            }],
+            error: None,
+        };
+        let output = ContextOutput {
+            workspace: "demo".into(),
+            branch: "demo".into(),
+            workspace_dir: PathBuf::from("/ws/demo"),
+            description: Some("login flow".into()),
+            issue: Some("https://github.com/acme/api/issues/12".into()),
+            repos: vec![
+                ContextRepoEntry {
+                    changed_files: (0..22).map(|i| format!("M f{}", i)).collect(),
+                    ..entry("api")
+                },
+                ContextRepoEntry {
+                    r#ref: Some("v2".into()),
+                    branch: None,
+                    upstream: None,
+                    recent_commits: vec![],
+                    error: Some("clone directory is missing".into()),
+                    ..entry("web")
+                },
+            ],
+        };
+        let got = context_markdown(&output);
+        assert!(got.starts_with(
+            "# Workspace `demo`\n\n\
+             login flow\n\n\
+             Issue: https://github.com/acme/api/issues/12\n\n\
+             Branch `demo` at `/ws/demo`. "
+        ));
+        assert!(got.contains(
+            "\n## api\n\n\
+             - Repo: `github.com/acme/api` (active)\n\
+             - Path: `/ws/demo/api`\n\
+             - Branch: `demo` (1 ahead, 0 behind `origin/main`)\n\
+             - Uncommitted changes (22):\n  \
+             - `M f0`\n"
+        ));
+        assert!(got.contains(
+            "  - `M f19`\n  - … and 2 more\n- Recent commits:\n  - `0123456789ab` Add login\n"
+        ));
+        assert!(got.ends_with(
+            "\n## web\n\n\
+             - Repo: `github.com/acme/web` (active)\n\
+             - Path: `/ws/demo/web`\n\
+             - Ref: `v2`\n\
+             - Error: clone directory is missing\n"
+        ));
+    }
+
+    #[test]
+    fn test_status_porcelain() {
+        let entry = |name: &str, files: Vec<String>, error: Option<String>| RepoStatusEntry {
+            identity: format!("github.com/user/{}", name),
+            shortname: name.into(),
+            path: String::new(),
+            branch: "my-ws".into(),
+            ahead: 1,
+            behind: 3,
+            compared: true,
+            changed: files.len() as u32,
+            untracked: 0,
+            stashes: 0,
+            has_upstream: true,
+            pushed: false,
+            role: "active".into(),
+            files,
+            error,
+            expected_branch: None,
+            in_progress: None,
+        };
+        let output = StatusOutput {
+            workspace: "my-ws".into(),
+            branch: "my-ws".into(),
+            workspace_dir: PathBuf::from("/tmp/workspaces/my-ws"),
+            description: None,
+            created: "2026-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+            fetched: None,
+            fetch_skipped: None,
+            repos: vec![
+                entry(
+                    "repo-a",
+                    vec![
+                        " M src/main.rs".into(),
+                        "A  lib.rs".into(),
+                        "?? new.txt".into(),
+                    ],
+                    None,
+                ),
+                entry("repo-b", vec![], Some("parse error".into())),
+                RepoStatusEntry {
+                    compared: false,
+                    ahead: 0,
+                    behind: 0,
+                    ..entry("repo-c", vec!["?? new.txt".into()], None)
+                },
+            ],
+            root: vec![],
+            verbose: false,
+            porcelain: true,
+            streamed: false,
+        };
+        assert_eq!(
+            status_porcelain(&output),
+            "# wsp-status v1\n\
+             repo-a\tmy-ws\t1\t3\t2\t1\n\
+             repo-b\t-\t-\t-\t-\t-\n\
+             repo-c\tmy-ws\t-\t-\t0\t1\n"
+        );
+    }
+
     #[test]
     fn test_json_status_with_root() {
         let output = StatusOutput {
@@ -1862,9 +4313,13 @@ mod tests {
             workspace_dir: PathBuf::from("/tmp/workspaces/my-ws"),
             description: None,
             created: "2026-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+            fetched: None,
+            fetch_skipped: None,
             repos: vec![],
             root: vec!["?? notes.md".into(), "?? my-stuff/".into()],
             verbose: true,
+            porcelain: false,
+            streamed: false,
         };
         let val = serde_json::to_value(&output).unwrap();
         assert_eq!(val["root"][0], "?? notes.md");
@@ -1872,6 +4327,95 @@ mod tests {
         assert_eq!(val["root"].as_array().unwrap().len(), 2);
         // verbose is #[serde(skip)] → not serialized
         assert!(val.get("verbose").is_none());
+        assert!(val.get("fetch_skipped").is_none());
+    }
+
+    #[test]
+    fn test_format_from_matches() {
+        let cases: Vec<(&[&str], Format)> = vec![
+            (&["wsp", "ls"], Format::Table),
+            (&["wsp", "ls", "--json"], Format::Json),
+            (&["wsp", "--format", "yaml", "ls"], Format::Yaml),
+            (&["wsp", "ls", "--format", "csv"], Format::Csv),
+            (&["wsp", "ls", "--format", "table"], Format::Table),
+            (
+                &["wsp", "deps", "graph", "--format", "mermaid"],
+                Format::Table,
+            ),
+            (&["wsp", "deps", "graph", "--format", "yaml"], Format::Yaml),
+            (&["wsp", "--format", "csv", "deps", "graph"], Format::Csv),
+        ];
+        for (args, want) in cases {
+            let m = crate::cli::build_cli().try_get_matches_from(args).unwrap();
+            assert_eq!(Format::from_matches(&m), want, "{:?}", args);
+        }
+
+        for args in [
+            &["wsp", "ls", "--format", "xml"][..],
+            &["wsp", "ls", "--json", "--format", "yaml"][..],
+        ] {
+            assert!(
+                crate::cli::build_cli().try_get_matches_from(args).is_err(),
+                "{:?}",
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_color() {
+        let cases = vec![
+            ("auto", false, true, true),
+            ("auto", false, false, false),
+            ("auto", true, true, false),
+            ("always", true, false, true),
+            ("never", false, true, false),
+        ];
+        for (when, no_color, tty, want) in cases {
+            assert_eq!(
+                resolve_color(when, no_color, tty),
+                want,
+                "{} NO_COLOR={} tty={}",
+                when,
+                no_color,
+                tty
+            );
+        }
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let output = ConfigListOutput {
+            entries: vec![
+                ConfigListEntry {
+                    key: "branch-prefix".into(),
+                    value: "jg, \"x\"".into(),
+                    source: None,
+                    experimental: false,
+                },
+                ConfigListEntry {
+                    key: "sync-strategy".into(),
+                    value: "rebase".into(),
+                    source: Some("global".into()),
+                    experimental: false,
+                },
+            ],
+        };
+        let val = serde_json::to_value(&output).unwrap();
+        assert_eq!(
+            to_csv(&val, Some("settings")),
+            "key,source,value\n\
+             branch-prefix,,\"jg, \"\"x\"\"\"\n\
+             sync-strategy,global,rebase\n"
+        );
+
+        // Without a rows field the value itself is one row; scalar arrays
+        // are joined and nested objects kept as JSON.
+        let val = serde_json::json!({"name": "a", "tags": ["x", "y"], "meta": {"k": 1}});
+        assert_eq!(
+            to_csv(&val, None),
+            "meta,name,tags\n\"{\"\"k\"\":1}\",a,x;y\n"
+        );
     }
 
     #[test]
@@ -1885,14 +4429,18 @@ mod tests {
                     identity: "github.com/user/repo-a".into(),
                     shortname: "repo-a".into(),
                     path: "/tmp/ws/repo-a".into(),
-                    diff: "--- a/file\n+++ b/file".into(),
+                    diff: Some("--- a/file\n+++ b/file".into()),
+                    stat: Some(DiffStat::from_numstat(crate::git::parse_numstat(
+                        "3\t1\tfile\n-\t-\tlogo.png",
+                    ))),
                     error: None,
                 },
                 RepoDiffEntry {
                     identity: "github.com/user/repo-b".into(),
                     shortname: "repo-b".into(),
                     path: String::new(),
-                    diff: String::new(),
+                    diff: None,
+                    stat: None,
                     error: Some("not found".into()),
                 },
             ],
@@ -1900,7 +4448,15 @@ mod tests {
         let val = serde_json::to_value(&output).unwrap();
         assert_eq!(val["repos"][0]["diff"], "--- a/file\n+++ b/file");
         assert!(val["repos"][0].get("error").is_none());
+        let stat = &val["repos"][0]["stat"];
+        assert_eq!(stat["files_changed"], 2);
+        assert_eq!(stat["insertions"], 3);
+        assert_eq!(stat["deletions"], 1);
+        assert_eq!(stat["files"][0]["path"], "file");
+        assert!(stat["files"][0].get("binary").is_none());
+        assert_eq!(stat["files"][1]["binary"], true);
         assert_eq!(val["repos"][1]["error"], "not found");
+        assert!(val["repos"][1].get("diff").is_none());
     }
 
     #[test]
@@ -1934,6 +4490,22 @@ mod tests {
         assert_eq!(val["ok"], true);
         assert_eq!(val["message"], "Registered repo");
         assert!(val.get("hint").is_none()); // omitted when None
+        assert!(val.get("fetch_skipped").is_none());
+    }
+
+    #[test]
+    fn test_json_mutation_fetch_skipped() {
+        use crate::offline::FetchPolicy;
+        let cases = vec![
+            (FetchPolicy::Fetch, None),
+            (FetchPolicy::NoFetch, Some("no-fetch")),
+            (FetchPolicy::Offline, Some("offline")),
+        ];
+        for (fetch, want) in cases {
+            let output = MutationOutput::new("Done.").with_fetch_skipped(fetch);
+            let val = serde_json::to_value(&output).unwrap();
+            assert_eq!(val.get("fetch_skipped").and_then(|v| v.as_str()), want);
+        }
     }
 
     #[test]
@@ -1949,9 +4521,11 @@ mod tests {
     fn test_json_error() {
         let output = ErrorOutput {
             error: "something went wrong".into(),
+            code: "git".into(),
         };
         let val = serde_json::to_value(&output).unwrap();
         assert_eq!(val["error"], "something went wrong");
+        assert_eq!(val["code"], "git");
     }
 
     #[test]
@@ -2079,6 +4653,7 @@ mod tests {
                     workspace: "my-ws".into(),
                     branch: "my-ws".into(),
                     dry_run: false,
+                    fetch_skipped: None,
                     repos: vec![SyncRepoResult {
                         identity: "github.com/acme/api-gateway".into(),
                         shortname: "api-gateway".into(),
@@ -2112,6 +4687,7 @@ mod tests {
                     workspace: "my-ws".into(),
                     branch: "my-ws".into(),
                     dry_run: true,
+                    fetch_skipped: None,
                     repos: vec![SyncRepoResult {
                         identity: "github.com/acme/api-gateway".into(),
                         shortname: "api-gateway".into(),
@@ -2145,6 +4721,7 @@ mod tests {
                     workspace: "my-ws".into(),
                     branch: "my-ws".into(),
                     dry_run: false,
+                    fetch_skipped: None,
                     repos: vec![SyncRepoResult {
                         identity: "github.com/acme/shared-lib".into(),
                         shortname: "shared-lib".into(),
@@ -2399,6 +4976,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_exit_code_mutation_pending() {
+        let error = "cloning mirror: connection refused".to_string();
+        let pending = BTreeMap::from([("github.com/acme/proto".to_string(), error)]);
+        let cases = vec![
+            ("none pending", MutationOutput::new("created"), 0),
+            (
+                "pending",
+                MutationOutput::new("created").with_pending(&pending),
+                1,
+            ),
+        ];
+        for (name, output, want) in cases {
+            assert_eq!(exit_code(&Output::Mutation(output)), want, "{}", name);
+        }
+    }
+
     #[test]
     fn test_format_relative_time() {
         let now = 1700000000i64;
@@ -2460,4 +5054,19 @@ mod tests {
         assert_eq!(val["entry"]["disk_bytes"], 1024);
         assert_eq!(val["entry"]["gc_path"], "/tmp/gc/my-ws__123");
     }
+
+    #[test]
+    fn closed_pipe_stops_streaming_quietly() {
+        let (reader, mut writer) = std::io::pipe().unwrap();
+        drop(reader);
+        let written = writeln!(writer, "row");
+        let err = written.as_ref().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+        assert!(is_broken_pipe(&anyhow::Error::new(std::io::Error::from(
+            err.kind()
+        ))));
+        assert!(keep_streaming(written).is_break());
+        assert!(keep_streaming(Ok(())).is_continue());
+        assert!(!is_broken_pipe(&anyhow::anyhow!("broken pipe")));
+    }
 }
@@ -0,0 +1,302 @@
+use std::io::IsTerminal;
+
+use anyhow::{Result, bail};
+
+use crate::util::read_stdin_line;
+
+/// Line-based fuzzy multi-select on stderr/stdin.
+///
+/// Each round lists the items matching the current filter with stable
+/// numbers, then reads a line of space-separated tokens:
+///
+/// - `3`, `2-5`  toggle items by number
+/// - `*`         select every listed item
+/// - `/`         clear the filter
+/// - other text  filter by fuzzy match; a unique match is toggled
+///
+/// An empty line finishes, Ctrl-D cancels. In single mode, picking an item
+/// finishes immediately and an empty line takes the first listed item.
+struct Picker {
+    items: Vec<String>,
+    single: bool,
+    selected: Vec<bool>,
+    filter: String,
+    message: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Step {
+    Continue,
+    Done,
+}
+
+impl Picker {
+    fn new(items: Vec<String>, single: bool) -> Self {
+        let selected = vec![false; items.len()];
+        Picker {
+            items,
+            single,
+            selected,
+            filter: String::new(),
+            message: None,
+        }
+    }
+
+    fn visible(&self) -> Vec<usize> {
+        (0..self.items.len())
+            .filter(|&i| fuzzy_match(&self.filter, &self.items[i]))
+            .collect()
+    }
+
+    fn apply(&mut self, line: &str) -> Step {
+        self.message = None;
+        if line.trim().is_empty() {
+            if self.single
+                && let Some(&first) = self.visible().first()
+            {
+                self.selected[first] = true;
+            }
+            return Step::Done;
+        }
+        for token in line.split_whitespace() {
+            if token == "*" {
+                if !self.single {
+                    for i in self.visible() {
+                        self.selected[i] = true;
+                    }
+                }
+            } else if token == "/" {
+                self.filter.clear();
+            } else if let Some((lo, hi)) = parse_range(token) {
+                if lo == 0 || hi > self.items.len() || lo > hi {
+                    self.message = Some(format!("no item {}", token));
+                    continue;
+                }
+                for i in lo - 1..hi {
+                    self.toggle(i);
+                }
+            } else {
+                self.filter = token.to_string();
+                let visible = self.visible();
+                match visible.len() {
+                    0 => self.message = Some(format!("nothing matches {:?}", token)),
+                    1 => {
+                        self.toggle(visible[0]);
+                        self.filter.clear();
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if self.single && self.selected.contains(&true) {
+            return Step::Done;
+        }
+        Step::Continue
+    }
+
+    fn toggle(&mut self, i: usize) {
+        if self.single {
+            self.selected.fill(false);
+        }
+        self.selected[i] = !self.selected[i];
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let width = self.items.len().to_string().len();
+        for i in self.visible() {
+            let mark = match (self.single, self.selected[i]) {
+                (true, _) => "",
+                (false, true) => "[x] ",
+                (false, false) => "[ ] ",
+            };
+            out.push_str(&format!(
+                "  {}{:>width$}  {}\n",
+                mark,
+                i + 1,
+                self.items[i],
+                width = width
+            ));
+        }
+        if !self.filter.is_empty() {
+            out.push_str(&format!("  (filter: {})\n", self.filter));
+        }
+        if let Some(msg) = &self.message {
+            out.push_str(&format!("  {}\n", msg));
+        }
+        if !self.single {
+            let count = self.selected.iter().filter(|s| **s).count();
+            out.push_str(&format!("  {} selected\n", count));
+        }
+        out
+    }
+
+    fn chosen(&self) -> Vec<usize> {
+        (0..self.items.len())
+            .filter(|&i| self.selected[i])
+            .collect()
+    }
+}
+
+/// `3` or `2-5` as an inclusive 1-based range.
+fn parse_range(token: &str) -> Option<(usize, usize)> {
+    match token.split_once('-') {
+        Some((a, b)) => Some((a.parse().ok()?, b.parse().ok()?)),
+        None => token.parse().ok().map(|n| (n, n)),
+    }
+}
+
+/// Case-insensitive subsequence match: `gwa` matches `gateway-api`.
+pub fn fuzzy_match(query: &str, candidate: &str) -> bool {
+    let candidate = candidate.to_lowercase();
+    let mut chars = candidate.chars();
+    query
+        .to_lowercase()
+        .chars()
+        .all(|q| chars.by_ref().any(|c| c == q))
+}
+
+/// Let the user pick any number of `items`. Returns the chosen indices in
+/// list order. Fails when stdin is not a terminal or the user cancels.
+pub fn pick_many(title: &str, items: Vec<String>) -> Result<Vec<usize>> {
+    eprintln!("{}", title);
+    eprintln!("  (numbers or ranges toggle, text filters, * selects all, enter when done)");
+    run(Picker::new(items, false))
+}
+
+/// Let the user pick one of `items`, listed in order of preference.
+pub fn pick_one(title: &str, items: Vec<String>) -> Result<usize> {
+    eprintln!("{}", title);
+    eprintln!("  (type to filter, a number to choose, enter for the first)");
+    match run(Picker::new(items, true))?.first() {
+        Some(&i) => Ok(i),
+        None => bail!("nothing selected"),
+    }
+}
+
+fn run(mut picker: Picker) -> Result<Vec<usize>> {
+    if !std::io::stdin().is_terminal() {
+        bail!("interactive selection needs a terminal");
+    }
+    loop {
+        eprint!("{}> ", picker.render());
+        let line = read_stdin_line();
+        if line.is_empty() {
+            eprintln!();
+            bail!("selection cancelled");
+        }
+        if picker.apply(&line) == Step::Done {
+            return Ok(picker.chosen());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match() {
+        let cases = vec![
+            ("", "anything", true),
+            ("api", "github.com/acme/api-gateway", true),
+            ("gwa", "gateway-api", true),
+            ("API", "api", true),
+            ("ipa", "api", false),
+            ("web", "github.com/acme/api", false),
+        ];
+        for (query, candidate, want) in cases {
+            assert_eq!(
+                fuzzy_match(query, candidate),
+                want,
+                "{:?} ~ {:?}",
+                query,
+                candidate
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_range() {
+        let cases = vec![
+            ("3", Some((3, 3))),
+            ("2-5", Some((2, 5))),
+            ("x", None),
+            ("2-", None),
+        ];
+        for (token, want) in cases {
+            assert_eq!(parse_range(token), want, "{}", token);
+        }
+    }
+
+    #[test]
+    fn test_picker_apply() {
+        let items = || {
+            vec![
+                "api-gateway".to_string(),
+                "user-service".to_string(),
+                "web-app".to_string(),
+                "proto".to_string(),
+            ]
+        };
+        let cases: Vec<(&str, Vec<&str>, Vec<usize>)> = vec![
+            ("numbers", vec!["1 3"], vec![0, 2]),
+            ("range", vec!["2-4"], vec![1, 2, 3]),
+            ("toggle off", vec!["1 2", "1"], vec![1]),
+            ("unique filter selects", vec!["web"], vec![2]),
+            ("ambiguous filter narrows", vec!["e", "*"], vec![0, 1, 2]),
+            ("select filtered", vec!["a", "*"], vec![0, 2]),
+            ("clear filter", vec!["a", "/", "4"], vec![3]),
+            ("out of range ignored", vec!["9 1"], vec![0]),
+        ];
+        for (name, lines, want) in cases {
+            let mut p = Picker::new(items(), false);
+            for line in lines {
+                assert_eq!(p.apply(line), Step::Continue, "{}", name);
+            }
+            assert_eq!(p.apply("\n"), Step::Done, "{}", name);
+            assert_eq!(p.chosen(), want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_picker_single() {
+        let items = || {
+            vec![
+                "api".to_string(),
+                "web".to_string(),
+                "docs".to_string(),
+                "websocket".to_string(),
+            ]
+        };
+        let cases: Vec<(&str, Vec<&str>, Vec<usize>)> = vec![
+            ("number picks", vec!["2"], vec![1]),
+            ("unique filter picks", vec!["doc"], vec![2]),
+            ("enter takes first", vec![""], vec![0]),
+            ("enter takes first filtered", vec!["we", ""], vec![1]),
+            ("last number wins", vec!["1 3"], vec![2]),
+            ("nothing listed", vec!["zzz", ""], vec![]),
+        ];
+        for (name, lines, want) in cases {
+            let mut p = Picker::new(items(), true);
+            let (last, rest) = lines.split_last().unwrap();
+            for line in rest {
+                assert_eq!(p.apply(line), Step::Continue, "{}", name);
+            }
+            assert_eq!(p.apply(last), Step::Done, "{}", name);
+            assert_eq!(p.chosen(), want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_picker_render() {
+        let mut p = Picker::new(vec!["api".into(), "web".into()], false);
+        p.apply("1");
+        assert_eq!(p.render(), "  [x] 1  api\n  [ ] 2  web\n  1 selected\n");
+        p.apply("zzz");
+        assert_eq!(
+            p.render(),
+            "  (filter: zzz)\n  nothing matches \"zzz\"\n  1 selected\n"
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use crate::managedhook::ManagedHook;
+
+pub const HOOK: ManagedHook = ManagedHook {
+    name: "pre-push",
+    marker: "# wsp: push guard",
+    script: SCRIPT,
+    label: "push guard hook",
+};
+
+/// Rejects pushes that would update the repo's default branch (per
+/// origin/HEAD) on any remote. Repos without origin/HEAD aren't guarded.
+const SCRIPT: &str = r#"#!/bin/sh
+# wsp: push guard
+head=$(git symbolic-ref --quiet refs/remotes/origin/HEAD) || exit 0
+default=${head#refs/remotes/origin/}
+status=0
+while read -r local_ref local_sha remote_ref remote_sha; do
+    if [ "$remote_ref" = "refs/heads/$default" ]; then
+        echo "wsp: refusing to push to $default on $1, the default branch" >&2
+        status=1
+    fi
+done
+if [ "$status" -ne 0 ]; then
+    echo "wsp: push the workspace branch instead, or bypass with git push --no-verify" >&2
+fi
+exit $status
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hook_blocks_default_branch() {
+        let (clone, source, _clone_tmp, _source_tmp) = crate::testutil::setup_clone_repo();
+        crate::git::run(
+            Some(&source),
+            &["config", "receive.denyCurrentBranch", "ignore"],
+        )
+        .unwrap();
+        crate::testutil::local_commit(&clone, "a.txt", "a");
+        HOOK.install(&clone).unwrap();
+        assert!(HOOK.is_installed(&clone));
+
+        let cases = vec![
+            ("feature", true),
+            ("feature:main", false),
+            ("HEAD:refs/heads/main", false),
+            ("feature:other", true),
+        ];
+        for (refspec, ok) in cases {
+            let result = crate::git::run(Some(&clone), &["push", "-q", "origin", refspec]);
+            assert_eq!(result.is_ok(), ok, "{}: {:?}", refspec, result);
+        }
+
+        crate::git::run(
+            Some(&clone),
+            &["push", "-q", "--no-verify", "origin", "feature:main"],
+        )
+        .unwrap();
+
+        HOOK.uninstall(&clone).unwrap();
+        assert!(!HOOK.path(&clone).exists());
+    }
+}
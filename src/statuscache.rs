@@ -0,0 +1,339 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::git;
+
+const CACHE_FILE: &str = "status-cache.yaml";
+
+/// Most files and directories [`key`] looks at in a worktree (and in its
+/// refs) before giving up on caching the clone.
+const WALK_LIMIT: usize = 20_000;
+
+/// Each clone's last `(changed files, commits ahead)` for `wsp ls --status`
+/// and `wsp prompt --status`, with what the clone looked like when they
+/// were counted. Stored in `data_dir`; any problem reading or writing it
+/// just means running git again.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cache {
+    #[serde(default)]
+    repos: BTreeMap<PathBuf, Entry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    #[serde(flatten)]
+    key: Key,
+    changed: u32,
+    ahead: u32,
+    /// What git ignored when the counts were taken, left out of the
+    /// worktree time: build output changing doesn't change the counts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ignored: Vec<String>,
+}
+
+/// What the counts depend on. The worktree is the newest modification
+/// time of anything in it that git doesn't ignore: editing, adding or
+/// removing a file moves it, and so does editing a `.gitignore`.
+/// Refs and config cover commits, fetches and upstream changes; the index
+/// covers staging.
+///
+/// The index alone won't do: editing a tracked file or adding an untracked
+/// one leaves it alone, and only `git status` itself can tell without
+/// looking at the files. So every lookup, hits included, stats each file
+/// git doesn't ignore. That's the stat `git status` does per tracked file
+/// anyway, minus three git processes (ignored files, status, ahead count)
+/// and reading the index, with build output and dependencies skipped. For
+/// a worktree past [`WALK_LIMIT`] entries that saving stops being worth a
+/// walk, so the walk stops there and the clone isn't cached: git runs every
+/// time, as it would without the cache.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Key {
+    head: String,
+    index: u64,
+    refs: u64,
+    worktree: u64,
+}
+
+pub fn load(data_dir: &Path) -> Cache {
+    fs::read_to_string(data_dir.join(CACHE_FILE))
+        .ok()
+        .and_then(|s| serde_yaml_ng::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+impl Cache {
+    pub fn entry(&self, clone_dir: &Path) -> Option<&Entry> {
+        self.repos.get(clone_dir)
+    }
+}
+
+/// `(changed files, commits ahead)` for a clone: `cached` when the clone
+/// hasn't changed since it was stored, else counted with git. The second
+/// value is a new entry to [`save`] when git ran and the clone held still
+/// meanwhile.
+pub fn counts(clone_dir: &Path, cached: Option<&Entry>) -> Option<((u32, u32), Option<Entry>)> {
+    if let Some(entry) = cached
+        && key(clone_dir, &entry.ignored).is_some_and(|k| k == entry.key)
+    {
+        return Some(((entry.changed, entry.ahead), None));
+    }
+    let ignored = git::ignored_entries(clone_dir).unwrap_or_default();
+    let before = key(clone_dir, &ignored);
+    let changed = git::changed_file_count(clone_dir).ok()?;
+    let ahead = git::ahead_count(clone_dir).ok()?;
+    // `git status` may rewrite the index as it refreshes it; anything else
+    // moving meanwhile means the counts may already be stale.
+    let entry = before
+        .and_then(|before| {
+            let after = key(clone_dir, &ignored)?;
+            let same = Key {
+                index: 0,
+                ..after.clone()
+            } == Key { index: 0, ..before };
+            same.then_some(after)
+        })
+        .map(|key| Entry {
+            key,
+            changed,
+            ahead,
+            ignored,
+        });
+    Some(((changed, ahead), entry))
+}
+
+/// Stores `updates` into `cache` and writes it, dropping clones that no
+/// longer exist.
+pub fn save(data_dir: &Path, mut cache: Cache, updates: Vec<(PathBuf, Entry)>) {
+    if updates.is_empty() {
+        return;
+    }
+    cache.repos.extend(updates);
+    cache.repos.retain(|dir, _| dir.join(".git").exists());
+    let Ok(text) = serde_yaml_ng::to_string(&cache) else {
+        return;
+    };
+    let _ = fs::create_dir_all(data_dir);
+    // Written whole and renamed, so a concurrent prompt never reads half.
+    if let Ok(mut tmp) = tempfile::NamedTempFile::new_in(data_dir) {
+        use std::io::Write;
+        if tmp.write_all(text.as_bytes()).is_ok() {
+            let _ = tmp.persist(data_dir.join(CACHE_FILE));
+        }
+    }
+}
+
+/// `None` when the clone's HEAD can't be read or it has more than
+/// [`WALK_LIMIT`] files or refs to look at; the counts then come from git
+/// every time.
+fn key(clone_dir: &Path, ignored: &[String]) -> Option<Key> {
+    let git_dir = clone_dir.join(".git");
+    let head = head_sha(&git_dir)?;
+    let mut refs = mtime(&git_dir.join("config"))
+        .max(mtime(&git_dir.join("packed-refs")))
+        .max(mtime(&git_dir.join("HEAD")));
+    let mut budget = WALK_LIMIT;
+    if !newest(
+        &git_dir.join("refs"),
+        &HashSet::new(),
+        &mut refs,
+        &mut budget,
+    ) {
+        return None;
+    }
+    let mut skip: HashSet<PathBuf> = ignored
+        .iter()
+        .map(|p| clone_dir.join(p.trim_end_matches('/')))
+        .collect();
+    skip.insert(git_dir.clone());
+    let mut worktree = 0;
+    let mut budget = WALK_LIMIT;
+    if !newest(clone_dir, &skip, &mut worktree, &mut budget) {
+        return None;
+    }
+    Some(Key {
+        head,
+        index: mtime(&git_dir.join("index")),
+        refs,
+        worktree,
+    })
+}
+
+/// HEAD's commit, read from the ref files; the ref name for a branch with
+/// no commits yet.
+fn head_sha(git_dir: &Path) -> Option<String> {
+    let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+    let Some(name) = head.strip_prefix("ref: ") else {
+        return Some(head.to_string());
+    };
+    if let Ok(sha) = fs::read_to_string(git_dir.join(name)) {
+        return Some(sha.trim().to_string());
+    }
+    let packed = fs::read_to_string(git_dir.join("packed-refs")).unwrap_or_default();
+    Some(
+        packed
+            .lines()
+            .find_map(|l| l.strip_suffix(name)?.strip_suffix(' ').map(String::from))
+            .unwrap_or_else(|| name.to_string()),
+    )
+}
+
+/// Modification time in nanoseconds since the epoch, 0 when missing.
+fn mtime(path: &Path) -> u64 {
+    fs::symlink_metadata(path)
+        .ok()
+        .map_or(0, |m| nanos(m.modified().ok()))
+}
+
+fn nanos(t: Option<SystemTime>) -> u64 {
+    t.and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos() as u64)
+}
+
+/// Raises `max` to the newest modification time under `dir`, the
+/// directory itself included, skipping the paths in `skip`. Symlinks
+/// aren't followed. Each entry looked at uses up one of `budget`; false
+/// if it runs out before the walk is done.
+fn newest(dir: &Path, skip: &HashSet<PathBuf>, max: &mut u64, budget: &mut usize) -> bool {
+    *max = (*max).max(mtime(dir));
+    let Ok(entries) = fs::read_dir(dir) else {
+        return true;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if skip.contains(&path) {
+            continue;
+        }
+        let Some(left) = budget.checked_sub(1) else {
+            return false;
+        };
+        *budget = left;
+        match entry.file_type() {
+            Ok(ft) if ft.is_dir() => {
+                if !newest(&path, skip, max, budget) {
+                    return false;
+                }
+            }
+            _ => *max = (*max).max(nanos(entry.metadata().ok().and_then(|m| m.modified().ok()))),
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{local_commit, setup_clone_repo};
+
+    #[test]
+    fn test_counts_cached_until_clone_changes() {
+        let (clone, _source, _ct, _st) = setup_clone_repo();
+        let data = tempfile::tempdir().unwrap();
+
+        let run = |clone: &Path| {
+            let cache = load(data.path());
+            let (counts, entry) = counts(clone, cache.entry(clone)).unwrap();
+            let hit = entry.is_none();
+            save(
+                data.path(),
+                cache,
+                entry
+                    .map(|e| (clone.to_path_buf(), e))
+                    .into_iter()
+                    .collect(),
+            );
+            (counts, hit)
+        };
+
+        assert_eq!(run(&clone), ((0, 0), false));
+        assert_eq!(run(&clone), ((0, 0), true));
+
+        std::fs::write(clone.join("new.txt"), "x").unwrap();
+        assert_eq!(run(&clone), ((1, 0), false));
+        assert_eq!(run(&clone), ((1, 0), true));
+
+        local_commit(&clone, "new.txt", "x");
+        assert_eq!(run(&clone), ((0, 1), false));
+
+        std::fs::write(clone.join("new.txt"), "changed").unwrap();
+        assert_eq!(run(&clone).0, (1, 1));
+    }
+
+    #[test]
+    fn test_ignored_files_keep_cache() {
+        let (clone, _source, _ct, _st) = setup_clone_repo();
+        std::fs::write(clone.join(".gitignore"), "target/\n*.log\n").unwrap();
+        local_commit(&clone, ".gitignore", "target/\n*.log\n");
+        std::fs::create_dir(clone.join("target")).unwrap();
+        std::fs::write(clone.join("target/out"), "1").unwrap();
+
+        let (first, entry) = counts(&clone, None).unwrap();
+        assert_eq!(first, (0, 1));
+        let entry = entry.unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        std::fs::write(clone.join("target/out"), "2").unwrap();
+        std::fs::write(clone.join("target/new"), "x").unwrap();
+        assert!(counts(&clone, Some(&entry)).unwrap().1.is_none());
+
+        std::fs::write(clone.join("build.log"), "x").unwrap();
+        assert!(counts(&clone, Some(&entry)).unwrap().1.is_some());
+    }
+
+    #[test]
+    fn test_newest_stops_at_budget() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::create_dir(tmp.path().join("src")).unwrap();
+        for name in ["a", "b", "src/c"] {
+            std::fs::write(tmp.path().join(name), "x").unwrap();
+        }
+        // Four entries: a, b, src and src/c.
+        let cases = vec![
+            ("enough", 4, None, true),
+            ("one short", 3, None, false),
+            ("skipped entries are free", 3, Some("b"), true),
+            ("short even skipping", 2, Some("b"), false),
+        ];
+        for (name, budget, skip, want) in cases {
+            let skip: HashSet<PathBuf> = skip.map(|s| tmp.path().join(s)).into_iter().collect();
+            let mut max = 0;
+            let mut budget = budget;
+            assert_eq!(
+                newest(tmp.path(), &skip, &mut max, &mut budget),
+                want,
+                "{}",
+                name
+            );
+            assert!(max > 0, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_counts_without_key_run_git() {
+        let (clone, _source, _ct, _st) = setup_clone_repo();
+        let linked = tempfile::tempdir().unwrap();
+        let dir = linked.path().join("wt");
+        git::run(
+            Some(&clone),
+            &[
+                "worktree",
+                "add",
+                "-q",
+                "-b",
+                "other",
+                dir.to_str().unwrap(),
+            ],
+        )
+        .unwrap();
+        std::fs::write(dir.join("new.txt"), "x").unwrap();
+
+        // `.git` is a file here, so there is no key to cache under.
+        let (first, entry) = counts(&dir, None).unwrap();
+        assert_eq!(first.0, 1);
+        assert!(entry.is_none());
+    }
+}
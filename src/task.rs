@@ -0,0 +1,457 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::dag::Dag;
+use crate::deps::{self, DependsOn};
+use crate::giturl;
+
+/// A named command preset run by `wsp run <task>`.
+///
+/// Written in YAML either as a plain command string (`test: make test`) or as a
+/// map with per-repo overrides:
+///
+/// ```yaml
+/// tasks:
+///   test:
+///     run: make test
+///     repos:
+///       web: npm test      # shortname or identity
+///       docs: ""           # empty command skips the repo
+///     depends_on:
+///       api: [proto]       # api's test waits for proto's test
+///       web: [proto:gen]   # web's test waits for proto's gen task
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "TaskDefRepr", into = "TaskDefRepr")]
+pub struct TaskDef {
+    pub run: String,
+    pub repos: BTreeMap<String, String>,
+    /// Per-repo dependencies: repo name → `<repo>` (same task) or `<repo>:<task>`.
+    pub depends_on: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum TaskDefRepr {
+    Short(String),
+    Full {
+        #[serde(default)]
+        run: String,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        repos: BTreeMap<String, String>,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        depends_on: BTreeMap<String, Vec<String>>,
+    },
+}
+
+impl From<TaskDefRepr> for TaskDef {
+    fn from(r: TaskDefRepr) -> Self {
+        match r {
+            TaskDefRepr::Short(run) => TaskDef {
+                run,
+                ..Default::default()
+            },
+            TaskDefRepr::Full {
+                run,
+                repos,
+                depends_on,
+            } => TaskDef {
+                run,
+                repos,
+                depends_on,
+            },
+        }
+    }
+}
+
+impl From<TaskDef> for TaskDefRepr {
+    fn from(t: TaskDef) -> Self {
+        if t.repos.is_empty() && t.depends_on.is_empty() {
+            TaskDefRepr::Short(t.run)
+        } else {
+            TaskDefRepr::Full {
+                run: t.run,
+                repos: t.repos,
+                depends_on: t.depends_on,
+            }
+        }
+    }
+}
+
+impl TaskDef {
+    /// Returns the command to run for each identity, keyed by identity.
+    /// Per-repo overrides are matched by shortname or identity; overrides naming
+    /// repos outside this workspace are ignored. Repos whose effective command is
+    /// empty are omitted.
+    pub fn commands(&self, identities: &[String]) -> BTreeMap<String, String> {
+        identities
+            .iter()
+            .filter_map(|id| {
+                self.command_for(id, identities)
+                    .map(|cmd| (id.clone(), cmd.to_string()))
+            })
+            .collect()
+    }
+
+    /// Returns the command for one identity, or None if it is empty (skipped).
+    pub fn command_for(&self, identity: &str, identities: &[String]) -> Option<&str> {
+        let cmd = self
+            .repos
+            .iter()
+            .find(|(name, _)| giturl::resolve(name, identities).is_ok_and(|id| id == identity))
+            .map(|(_, cmd)| cmd.as_str())
+            .unwrap_or(&self.run);
+        (!cmd.trim().is_empty()).then_some(cmd)
+    }
+
+    /// Returns the `(identity, task)` pairs that `identity`'s run of `task_name`
+    /// waits on: the task's own `depends_on`, plus the same task in every repo
+    /// `identity` depends on at the repo level. Repos outside the workspace are
+    /// ignored.
+    fn deps_for(
+        &self,
+        task_name: &str,
+        identity: &str,
+        identities: &[String],
+        repo_deps: &Dag<String>,
+    ) -> Vec<(String, String)> {
+        let mut out: Vec<(String, String)> = repo_deps
+            .deps(&identity.to_string())
+            .map(|dep| (dep.clone(), task_name.to_string()))
+            .collect();
+        for (name, specs) in &self.depends_on {
+            if giturl::resolve(name, identities).ok().as_deref() != Some(identity) {
+                continue;
+            }
+            for spec in specs {
+                let (repo, task) = spec.split_once(':').unwrap_or((spec, task_name));
+                if let Ok(dep_id) = giturl::resolve(repo, identities) {
+                    out.push((dep_id, task.to_string()));
+                }
+            }
+        }
+        out
+    }
+}
+
+/// One unit of work in a task plan: `task` run in the repo `identity`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TaskNode {
+    pub identity: String,
+    pub task: String,
+}
+
+impl fmt::Display for TaskNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let repo = self.identity.rsplit('/').next().unwrap_or(&self.identity);
+        write!(f, "{}:{}", repo, self.task)
+    }
+}
+
+/// The nodes to run for `wsp run <task>` with their commands, the dependency
+/// graph between them, and an execution order that respects it.
+pub struct Plan {
+    pub commands: BTreeMap<TaskNode, String>,
+    pub dag: Dag<TaskNode>,
+    pub order: Vec<TaskNode>,
+}
+
+/// Build the execution plan for `root` across `identities`, pulling in the
+/// tasks it depends on. Repo-level `depends_on` adds an edge to the same task
+/// in each depended-on repo. Dependencies on repos where the depended-on task
+/// has no command are dropped. Errors on unknown tasks and dependency cycles.
+pub fn plan(
+    tasks: &BTreeMap<String, TaskDef>,
+    root: &str,
+    identities: &[String],
+    repo_deps: Option<&DependsOn>,
+) -> Result<Plan> {
+    let repo_deps = deps::graph(repo_deps, identities);
+    let Some(root_def) = tasks.get(root) else {
+        bail!("unknown task {:?}", root);
+    };
+
+    let mut commands = BTreeMap::new();
+    let mut dag = Dag::new();
+    let mut queue = VecDeque::new();
+    for (identity, cmd) in root_def.commands(identities) {
+        let node = TaskNode {
+            identity,
+            task: root.to_string(),
+        };
+        dag.add_node(node.clone());
+        commands.insert(node.clone(), cmd);
+        queue.push_back(node);
+    }
+
+    while let Some(node) = queue.pop_front() {
+        let def = &tasks[&node.task];
+        for (dep_id, dep_task) in def.deps_for(&node.task, &node.identity, identities, &repo_deps) {
+            let Some(dep_def) = tasks.get(&dep_task) else {
+                bail!(
+                    "task {:?} depends on unknown task {:?}",
+                    node.task,
+                    dep_task
+                );
+            };
+            let Some(cmd) = dep_def.command_for(&dep_id, identities) else {
+                continue;
+            };
+            let dep = TaskNode {
+                identity: dep_id,
+                task: dep_task,
+            };
+            if !dag.contains(&dep) {
+                commands.insert(dep.clone(), cmd.to_string());
+                queue.push_back(dep.clone());
+            }
+            dag.add_edge(node.clone(), dep);
+        }
+    }
+
+    let order = dag.toposort().map_err(|e| anyhow::anyhow!("task {}", e))?;
+    Ok(Plan {
+        commands,
+        dag,
+        order,
+    })
+}
+
+/// Returns the task name from a `task.<name>` config key. The name is taken
+/// verbatim (not key-normalized) since task names may contain underscores.
+pub fn key_name(key: &str) -> Option<&str> {
+    let (prefix, name) = key.split_once('.')?;
+    (prefix == "task" || prefix == "tasks").then_some(name)
+}
+
+/// Validate a task name for use as a config key component (`task.<name>`).
+pub fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        bail!("task name cannot be empty");
+    }
+    if name.starts_with('-') {
+        bail!("task name {:?} cannot start with a dash", name);
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        bail!(
+            "task name {:?} contains invalid characters (allowed: a-z, A-Z, 0-9, dash, underscore)",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Build the argv that runs a task command string through the platform shell.
+#[cfg(unix)]
+pub fn shell_argv(command: &str) -> Vec<String> {
+    vec!["sh".into(), "-c".into(), command.into()]
+}
+
+#[cfg(windows)]
+pub fn shell_argv(command: &str) -> Vec<String> {
+    vec!["cmd".into(), "/C".into(), command.into()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_def_yaml_forms() {
+        let cases = vec![
+            ("short form", "make test\n", "make test", vec![]),
+            (
+                "full form",
+                "run: make test\nrepos:\n  web: npm test\n",
+                "make test",
+                vec![("web", "npm test")],
+            ),
+            (
+                "overrides only",
+                "repos:\n  web: npm test\n",
+                "",
+                vec![("web", "npm test")],
+            ),
+        ];
+        for (name, yaml, want_run, want_repos) in cases {
+            let def: TaskDef = serde_yaml_ng::from_str(yaml).unwrap();
+            assert_eq!(def.run, want_run, "{}", name);
+            let want_repos: BTreeMap<String, String> = want_repos
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            assert_eq!(def.repos, want_repos, "{}", name);
+
+            // Round-trip preserves the value
+            let out = serde_yaml_ng::to_string(&def).unwrap();
+            let back: TaskDef = serde_yaml_ng::from_str(&out).unwrap();
+            assert_eq!(back, def, "{}: round-trip", name);
+        }
+    }
+
+    #[test]
+    fn test_task_def_serializes_short_form_without_overrides() {
+        let def = TaskDef {
+            run: "cargo fmt".into(),
+            ..Default::default()
+        };
+        assert_eq!(serde_yaml_ng::to_string(&def).unwrap(), "cargo fmt\n");
+    }
+
+    #[test]
+    fn test_commands() {
+        let identities = vec![
+            "github.com/acme/api".to_string(),
+            "github.com/acme/web".to_string(),
+            "github.com/acme/docs".to_string(),
+        ];
+        let def = TaskDef {
+            run: "make test".into(),
+            repos: [
+                ("web", "npm test"),
+                ("github.com/acme/docs", ""),
+                ("not-in-workspace", "true"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+            ..Default::default()
+        };
+        let got = def.commands(&identities);
+        let want: BTreeMap<String, String> = [
+            ("github.com/acme/api", "make test"),
+            ("github.com/acme/web", "npm test"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        assert_eq!(got, want);
+    }
+
+    fn tasks_from_yaml(yaml: &str) -> BTreeMap<String, TaskDef> {
+        serde_yaml_ng::from_str(yaml).unwrap()
+    }
+
+    fn ids() -> Vec<String> {
+        ["proto", "api", "web"]
+            .iter()
+            .map(|r| format!("github.com/acme/{}", r))
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_order() {
+        // (name, tasks, task run, want)
+        let cases = vec![
+            (
+                "no deps",
+                "build: make\n",
+                "build",
+                vec!["api:build", "proto:build", "web:build"],
+            ),
+            (
+                "same task in another repo",
+                "build:\n  run: make\n  depends_on:\n    api: [proto]\n    web: [api]\n",
+                "build",
+                vec!["proto:build", "api:build", "web:build"],
+            ),
+            (
+                "other task pulled in",
+                "gen: buf generate\ntest:\n  run: make test\n  repos:\n    proto: ''\n  depends_on:\n    api: ['proto:gen']\n",
+                "test",
+                vec!["proto:gen", "api:test", "web:test"],
+            ),
+            (
+                "dep on skipped repo dropped",
+                "build:\n  run: make\n  repos:\n    proto: ''\n  depends_on:\n    api: [proto]\n",
+                "build",
+                vec!["api:build", "web:build"],
+            ),
+            (
+                "dep outside workspace ignored",
+                "build:\n  run: make\n  depends_on:\n    api: [billing]\n",
+                "build",
+                vec!["api:build", "proto:build", "web:build"],
+            ),
+        ];
+        for (name, yaml, root, want) in cases {
+            let p = plan(&tasks_from_yaml(yaml), root, &ids(), None).unwrap();
+            let got: Vec<String> = p.order.iter().map(|n| n.to_string()).collect();
+            assert_eq!(got, want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_plan_repo_deps() {
+        let tasks = tasks_from_yaml(
+            "build:\n  run: make\n  repos:\n    web: ''\n  depends_on:\n    proto: ['web:lint']\nlint: eslint\n",
+        );
+        let repo_deps: DependsOn = [
+            ("github.com/acme/web".to_string(), vec!["api".to_string()]),
+            ("api".to_string(), vec!["proto".to_string()]),
+        ]
+        .into_iter()
+        .collect();
+        let p = plan(&tasks, "build", &ids(), Some(&repo_deps)).unwrap();
+        let got: Vec<String> = p.order.iter().map(|n| n.to_string()).collect();
+        // proto:build pulls in web:lint, which waits on api:lint and, through
+        // it, proto:lint via repo-level deps. web has no build command.
+        assert_eq!(
+            got,
+            vec![
+                "proto:lint",
+                "api:lint",
+                "web:lint",
+                "proto:build",
+                "api:build"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_errors() {
+        let cases = vec![
+            ("unknown root", "build: make\n", "nope", "unknown task"),
+            (
+                "unknown dep task",
+                "build:\n  run: make\n  depends_on:\n    api: ['proto:gen']\n",
+                "build",
+                "unknown task \"gen\"",
+            ),
+            (
+                "cycle",
+                "build:\n  run: make\n  depends_on:\n    api: [web]\n    web: [api]\n",
+                "build",
+                "cycle",
+            ),
+        ];
+        for (name, yaml, root, want) in cases {
+            let err = match plan(&tasks_from_yaml(yaml), root, &ids(), None) {
+                Ok(_) => panic!("{}: expected error", name),
+                Err(e) => e.to_string(),
+            };
+            assert!(err.contains(want), "{}: got {:?}", name, err);
+        }
+    }
+
+    #[test]
+    fn test_validate_name() {
+        let cases = vec![
+            ("simple", "test", false),
+            ("dash and underscore", "lint-all_fast", false),
+            ("empty", "", true),
+            ("leading dash", "-x", true),
+            ("dot", "a.b", true),
+            ("space", "a b", true),
+        ];
+        for (name, input, want_err) in cases {
+            assert_eq!(validate_name(input).is_err(), want_err, "{}", name);
+        }
+    }
+}
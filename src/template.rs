@@ -7,6 +7,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::config::{self, Paths, RepoEntry};
+use crate::error::{self, ErrorKind};
 use crate::filelock;
 use crate::giturl;
 use crate::mirror;
@@ -45,6 +46,26 @@ pub struct TemplateConfig {
         alias = "git_config"
     )]
     pub git_config: Option<std::collections::BTreeMap<String, String>>,
+    /// Per-repo working subdirectory for `wsp exec`, keyed by repo identity.
+    /// Set in a workspace via `wsp config set exec-dir.<repo> <subdir>`; wins
+    /// over the repo's global `exec-dir`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exec_dirs: Option<std::collections::BTreeMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tasks: Option<std::collections::BTreeMap<String, crate::task::TaskDef>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depends_on: Option<crate::deps::DependsOn>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub files: Option<crate::files::Files>,
+    /// Workspace-scoped only: the `branch-prefix` for repos added later.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch_prefix: Option<String>,
+    /// Workspace-scoped only: whether wsp maintains AGENTS.md.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_md: Option<bool>,
+    /// Workspace-scoped only: the default `--jobs` for `wsp run`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_jobs: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -77,6 +98,28 @@ impl Template {
                     target.insert(k.clone(), v.clone());
                 }
             }
+            if let Some(ref tasks) = settings.tasks {
+                let target = effective
+                    .tasks
+                    .get_or_insert_with(std::collections::BTreeMap::new);
+                for (k, v) in tasks {
+                    target.insert(k.clone(), v.clone());
+                }
+            }
+            if let Some(ref deps) = settings.depends_on {
+                let target = effective
+                    .depends_on
+                    .get_or_insert_with(std::collections::BTreeMap::new);
+                crate::deps::merge(target, deps, &self.identities().unwrap_or_default());
+            }
+            if let Some(ref files) = settings.files {
+                let target = effective
+                    .files
+                    .get_or_insert_with(std::collections::BTreeMap::new);
+                for (k, v) in files {
+                    target.insert(k.clone(), v.clone());
+                }
+            }
         }
         effective
     }
@@ -114,6 +157,21 @@ impl Template {
                     eprintln!("  git.{}: {}", key, value);
                 }
             }
+            if let Some(ref tasks) = settings.tasks {
+                for (name, def) in tasks {
+                    eprintln!("  task.{}: {}", name, def.run);
+                }
+            }
+            if let Some(ref deps) = settings.depends_on {
+                for (repo, on) in deps {
+                    eprintln!("  depends-on.{}: {}", repo, crate::deps::format_value(on));
+                }
+            }
+            if let Some(ref files) = settings.files {
+                for (dest, source) in files {
+                    eprintln!("  file.{}: {}", dest, source);
+                }
+            }
         }
 
         if let Some(ref content) = self.agent_md {
@@ -179,6 +237,9 @@ pub fn validate_name(name: &str) -> Result<()> {
             name
         );
     }
+    if crate::util::is_windows_reserved_name(name) {
+        bail!("template name {:?} is reserved on Windows", name);
+    }
     Ok(())
 }
 
@@ -208,7 +269,10 @@ pub fn load(templates_dir: &Path, name: &str) -> Result<Template> {
     validate_name(name)?;
     let path = template_path(templates_dir, name);
     if !path.exists() {
-        bail!("template {:?} not found", name);
+        return Err(error::tag(
+            ErrorKind::NotFound,
+            anyhow::anyhow!("template {:?} not found", name),
+        ));
     }
     load_from_file(&path)
 }
@@ -223,9 +287,10 @@ pub fn delete(templates_dir: &Path, name: &str) -> Result<()> {
             let _ = delete_source(templates_dir, name);
             Ok(())
         }
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-            bail!("template {:?} not found", name)
-        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(error::tag(
+            ErrorKind::NotFound,
+            anyhow::anyhow!("template {:?} not found", name),
+        )),
         Err(e) => Err(e).with_context(|| format!("removing template {:?}", name)),
     }
 }
@@ -238,13 +303,19 @@ pub fn rename(templates_dir: &Path, old_name: &str, new_name: &str, force: bool)
     let new_path = template_path(templates_dir, new_name);
 
     if !old_path.exists() {
-        bail!("template {:?} not found", old_name);
+        return Err(error::tag(
+            ErrorKind::NotFound,
+            anyhow::anyhow!("template {:?} not found", old_name),
+        ));
     }
     if new_path.exists() && !force {
-        bail!(
-            "template {:?} already exists (use --force to overwrite)",
-            new_name
-        );
+        return Err(error::tag(
+            ErrorKind::Conflict,
+            anyhow::anyhow!(
+                "template {:?} already exists (use --force to overwrite)",
+                new_name
+            ),
+        ));
     }
 
     // If forcing over an existing template, clean up its sidecar first
@@ -507,13 +578,25 @@ pub fn from_workspace(paths: &Paths, ws_name: &str) -> Result<Template> {
 /// Auto-register any repos from a template that aren't already in the registry.
 /// Clones mirrors and adds entries to config.
 pub fn auto_register(tmpl: &Template, cfg: &mut config::Config, paths: &Paths) -> Result<()> {
+    let urls: Vec<&str> = tmpl.repos.iter().map(|r| r.url.as_str()).collect();
+    register_urls(&urls, "from template", cfg, paths)
+}
+
+/// Register any of `urls` not already in the registry, cloning their
+/// mirrors. `source` says where they came from in the progress message.
+pub fn register_urls(
+    urls: &[&str],
+    source: &str,
+    cfg: &mut config::Config,
+    paths: &Paths,
+) -> Result<()> {
     let mut to_register = Vec::new();
 
-    for repo in &tmpl.repos {
-        let parsed = giturl::parse(&repo.url)?;
+    for url in urls {
+        let parsed = giturl::parse(url)?;
         let identity = parsed.identity();
         if !cfg.repos.contains_key(&identity) {
-            to_register.push((identity, parsed, repo.url.clone()));
+            to_register.push((identity, parsed, url.to_string()));
         }
     }
 
@@ -521,10 +604,7 @@ pub fn auto_register(tmpl: &Template, cfg: &mut config::Config, paths: &Paths) -
         return Ok(());
     }
 
-    eprintln!(
-        "Auto-registering {} repos from template...",
-        to_register.len()
-    );
+    eprintln!("Auto-registering {} repos {}...", to_register.len(), source);
 
     for (identity, parsed, url) in &to_register {
         if !mirror::exists(&paths.mirrors_dir, parsed) {
@@ -543,6 +623,8 @@ pub fn auto_register(tmpl: &Template, cfg: &mut config::Config, paths: &Paths) -
                     RepoEntry {
                         url: url.clone(),
                         added: Utc::now(),
+                        setup: None,
+                        exec_dir: None,
                     },
                 );
             }
@@ -557,6 +639,8 @@ pub fn auto_register(tmpl: &Template, cfg: &mut config::Config, paths: &Paths) -
             RepoEntry {
                 url,
                 added: Utc::now(),
+                setup: None,
+                exec_dir: None,
             },
         );
     }
@@ -570,7 +654,14 @@ pub fn auto_register(tmpl: &Template, cfg: &mut config::Config, paths: &Paths) -
 
 /// Valid key prefixes for template config. Global-only keys like `branch-prefix`,
 /// `workspaces-dir`, `gc.retention-days`, and `agent-md` are not valid here.
-const VALID_TEMPLATE_CONFIG_PREFIXES: &[&str] = &["lang.", "sync-strategy", "git."];
+const VALID_TEMPLATE_CONFIG_PREFIXES: &[&str] = &[
+    "lang.",
+    "sync-strategy",
+    "git.",
+    "task.",
+    "depends-on.",
+    "file.",
+];
 
 ///// Normalize a config key: convert underscores to hyphens and map old prefixes to new.
 pub(crate) fn normalize_key(key: &str) -> String {
@@ -602,7 +693,7 @@ pub(crate) fn validate_template_config_key(key: &str) -> Result<()> {
         }
     }
     bail!(
-        "invalid template config key {:?}; valid key patterns: lang.<name>, sync-strategy, git.<key>",
+        "invalid template config key {:?}; valid key patterns: lang.<name>, sync-strategy, git.<key>, task.<name>, depends-on.<repo>, file.<path>",
         key
     );
 }
@@ -674,6 +765,7 @@ pub fn remove_repos(template: &mut Template, urls_or_identities: Vec<String>) ->
 pub fn set_config(template: &mut Template, key: &str, value: &str) -> Result<()> {
     let normalized = normalize_key(key);
     validate_template_config_key(key)?;
+    let identities = template.identities()?;
 
     let config = template.config.get_or_insert_with(TemplateConfig::default);
 
@@ -699,6 +791,24 @@ pub fn set_config(template: &mut Template, key: &str, value: &str) -> Result<()>
             .git_config
             .get_or_insert_with(std::collections::BTreeMap::new);
         gc.insert(git_key.to_string(), value.to_string());
+    } else if let Some(name) = crate::task::key_name(key) {
+        crate::task::validate_name(name)?;
+        let tasks = config
+            .tasks
+            .get_or_insert_with(std::collections::BTreeMap::new);
+        tasks.entry(name.to_string()).or_default().run = value.to_string();
+    } else if let Some(repo) = crate::deps::key_repo(key) {
+        let (identity, deps) = crate::deps::resolve_entry(repo, value, &identities)?;
+        config
+            .depends_on
+            .get_or_insert_with(std::collections::BTreeMap::new)
+            .insert(identity, deps);
+    } else if let Some(dest) = crate::files::key_dest(key) {
+        crate::files::validate_dest(dest)?;
+        let files = config
+            .files
+            .get_or_insert_with(std::collections::BTreeMap::new);
+        files.insert(dest.to_string(), value.to_string());
     }
 
     Ok(())
@@ -728,6 +838,21 @@ pub fn get_config(template: &Template, key: &str) -> Result<Option<String>> {
             .as_ref()
             .and_then(|m| m.get(git_key))
             .cloned())
+    } else if let Some(name) = crate::task::key_name(key) {
+        Ok(config
+            .tasks
+            .as_ref()
+            .and_then(|m| m.get(name))
+            .map(|t| t.run.clone()))
+    } else if let Some(repo) = crate::deps::key_repo(key) {
+        let identity = giturl::resolve(repo, &template.identities()?)?;
+        Ok(config
+            .depends_on
+            .as_ref()
+            .and_then(|m| m.get(&identity))
+            .map(|d| crate::deps::format_value(d)))
+    } else if let Some(dest) = crate::files::key_dest(key) {
+        Ok(config.files.as_ref().and_then(|m| m.get(dest)).cloned())
     } else {
         Ok(None)
     }
@@ -737,6 +862,7 @@ pub fn get_config(template: &Template, key: &str) -> Result<Option<String>> {
 pub fn unset_config(template: &mut Template, key: &str) -> Result<()> {
     let normalized = normalize_key(key);
     validate_template_config_key(key)?;
+    let identities = template.identities()?;
 
     let config = match &mut template.config {
         Some(c) => c,
@@ -759,6 +885,28 @@ pub fn unset_config(template: &mut Template, key: &str) -> Result<()> {
         if m.is_empty() {
             config.git_config = None;
         }
+    } else if let Some(name) = crate::task::key_name(key)
+        && let Some(ref mut m) = config.tasks
+    {
+        m.remove(name);
+        if m.is_empty() {
+            config.tasks = None;
+        }
+    } else if let Some(repo) = crate::deps::key_repo(key)
+        && let Some(ref mut m) = config.depends_on
+    {
+        let identity = giturl::resolve(repo, &identities)?;
+        m.remove(&identity);
+        if m.is_empty() {
+            config.depends_on = None;
+        }
+    } else if let Some(dest) = crate::files::key_dest(key)
+        && let Some(ref mut m) = config.files
+    {
+        m.remove(dest);
+        if m.is_empty() {
+            config.files = None;
+        }
     }
 
     // Clean up empty config
@@ -1011,6 +1159,11 @@ mod tests {
                 input: "source-code",
                 want_err: false,
             },
+            Case {
+                name: "windows device",
+                input: "prn",
+                want_err: true,
+            },
         ];
 
         for tc in cases {
@@ -1089,8 +1242,10 @@ created: 2026-03-07T10:00:00Z
     fn apply_config_overrides_config() {
         use std::collections::BTreeMap;
 
-        let mut cfg = config::Config::default();
-        cfg.sync_strategy = Some("rebase".into());
+        let mut cfg = config::Config {
+            sync_strategy: Some("rebase".into()),
+            ..Default::default()
+        };
         let mut li = BTreeMap::new();
         li.insert("go".into(), false);
         cfg.language_integrations = Some(li);
@@ -1104,24 +1259,30 @@ created: 2026-03-07T10:00:00Z
                 language_integrations: Some(BTreeMap::from([("go".into(), true)])),
                 sync_strategy: Some("merge".into()),
                 git_config: None,
+                exec_dirs: None,
+                tasks: None,
+                depends_on: None,
+                files: None,
+                branch_prefix: None,
+                agent_md: None,
+                run_jobs: None,
             }),
             agent_md: None,
         };
 
         let effective = tmpl.apply_config(&cfg);
         assert_eq!(effective.sync_strategy.as_deref(), Some("merge"));
-        assert_eq!(
-            effective.language_integrations.as_ref().unwrap()["go"],
-            true
-        );
+        assert!(effective.language_integrations.as_ref().unwrap()["go"]);
     }
 
     #[test]
     fn apply_config_preserves_config_when_absent() {
         use std::collections::BTreeMap;
 
-        let mut cfg = config::Config::default();
-        cfg.sync_strategy = Some("rebase".into());
+        let mut cfg = config::Config {
+            sync_strategy: Some("rebase".into()),
+            ..Default::default()
+        };
         let mut li = BTreeMap::new();
         li.insert("go".into(), true);
         cfg.language_integrations = Some(li);
@@ -1137,10 +1298,16 @@ created: 2026-03-07T10:00:00Z
 
         let effective = tmpl.apply_config(&cfg);
         assert_eq!(effective.sync_strategy.as_deref(), Some("rebase"));
-        assert_eq!(
-            effective.language_integrations.as_ref().unwrap()["go"],
-            true
-        );
+        assert!(effective.language_integrations.as_ref().unwrap()["go"]);
+    }
+
+    #[test]
+    fn apply_config_ignores_template_hooks() {
+        let yaml = "repos: []\nconfig:\n  hooks:\n    post-create: touch PWNED\n";
+        let tmpl: Template = serde_yaml_ng::from_str(yaml).unwrap();
+        let effective = tmpl.apply_config(&config::Config::default());
+        assert!(effective.hooks.is_none());
+        assert!(!tmpl.has_customizations());
     }
 
     #[test]
@@ -1158,6 +1325,13 @@ created: 2026-03-07T10:00:00Z
                 language_integrations: Some(BTreeMap::from([("go".into(), true)])),
                 sync_strategy: Some("merge".into()),
                 git_config: None,
+                exec_dirs: None,
+                tasks: None,
+                depends_on: None,
+                files: None,
+                branch_prefix: None,
+                agent_md: None,
+                run_jobs: None,
             }),
             agent_md: None,
         };
@@ -1167,7 +1341,7 @@ created: 2026-03-07T10:00:00Z
 
         let s = parsed.config.unwrap();
         assert_eq!(s.sync_strategy.as_deref(), Some("merge"));
-        assert_eq!(s.language_integrations.as_ref().unwrap()["go"], true);
+        assert!(s.language_integrations.as_ref().unwrap()["go"]);
     }
 
     #[test]
@@ -1333,6 +1507,13 @@ created: 2026-03-07T10:00:00Z
                     "push.autoSetupRemote".into(),
                     "false".into(),
                 )])),
+                exec_dirs: None,
+                tasks: None,
+                depends_on: None,
+                files: None,
+                branch_prefix: None,
+                agent_md: None,
+                run_jobs: None,
             }),
             agent_md: None,
         };
@@ -1362,6 +1543,13 @@ created: 2026-03-07T10:00:00Z
                     ("push.default".into(), "simple".into()),
                     ("rerere.enabled".into(), "false".into()),
                 ])),
+                exec_dirs: None,
+                tasks: None,
+                depends_on: None,
+                files: None,
+                branch_prefix: None,
+                agent_md: None,
+                run_jobs: None,
             }),
             agent_md: None,
         };
@@ -1415,6 +1603,13 @@ created: 2026-03-07T10:00:00Z
                             "push.default".into(),
                             "simple".into(),
                         )])),
+                        exec_dirs: None,
+                        tasks: None,
+                        depends_on: None,
+                        files: None,
+                        branch_prefix: None,
+                        agent_md: None,
+                        run_jobs: None,
                     }),
                     agent_md: None,
                 },
@@ -1431,6 +1626,13 @@ created: 2026-03-07T10:00:00Z
                         language_integrations: None,
                         sync_strategy: Some("merge".into()),
                         git_config: None,
+                        exec_dirs: None,
+                        tasks: None,
+                        depends_on: None,
+                        files: None,
+                        branch_prefix: None,
+                        agent_md: None,
+                        run_jobs: None,
                     }),
                     agent_md: None,
                 },
@@ -1583,14 +1785,13 @@ created: 2026-03-07T10:00:00Z
     fn set_config_language_integration() {
         let mut tmpl = sample_template();
         set_config(&mut tmpl, "lang.go", "true").unwrap();
-        assert_eq!(
+        assert!(
             tmpl.config
                 .as_ref()
                 .unwrap()
                 .language_integrations
                 .as_ref()
-                .unwrap()["go"],
-            true
+                .unwrap()["go"]
         );
     }
 
@@ -1622,6 +1823,25 @@ created: 2026-03-07T10:00:00Z
         );
     }
 
+    #[test]
+    fn set_config_files_round_trip() {
+        let mut tmpl = sample_template();
+        // Destinations keep their spelling: no underscore/hyphen folding
+        set_config(
+            &mut tmpl,
+            "file.docker-compose.override_dev.yml",
+            "files/dc.yml",
+        )
+        .unwrap();
+        assert_eq!(
+            get_config(&tmpl, "file.docker-compose.override_dev.yml").unwrap(),
+            Some("files/dc.yml".into())
+        );
+        assert!(set_config(&mut tmpl, "file.../escape", "x").is_err());
+        unset_config(&mut tmpl, "file.docker-compose.override_dev.yml").unwrap();
+        assert!(tmpl.config.is_none());
+    }
+
     #[test]
     fn set_config_invalid_key() {
         let mut tmpl = sample_template();
@@ -1685,6 +1905,13 @@ created: 2026-03-07T10:00:00Z
                 language_integrations: Some(BTreeMap::from([("go".into(), true)])),
                 sync_strategy: None,
                 git_config: None,
+                exec_dirs: None,
+                tasks: None,
+                depends_on: None,
+                files: None,
+                branch_prefix: None,
+                agent_md: None,
+                run_jobs: None,
             }),
             agent_md: None,
         };
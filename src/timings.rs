@@ -0,0 +1,135 @@
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::output::{TimingEntry, TimingsOutput};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static RECORDS: Mutex<Vec<Record>> = Mutex::new(Vec::new());
+
+struct Record {
+    repo: String,
+    op: String,
+    elapsed: Duration,
+}
+
+/// Start collecting timings (the global `--timings` flag).
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Record one git subprocess. `dir` is where it ran; commands that run
+/// outside a repo (clones) are attributed to their last argument, the
+/// destination.
+pub fn record_git(dir: Option<&Path>, args: &[String], elapsed: Duration) {
+    if !enabled() {
+        return;
+    }
+    let op = args
+        .iter()
+        .find(|a| !a.starts_with('-'))
+        .cloned()
+        .unwrap_or_else(|| "git".into());
+    let path = dir.or_else(|| args.last().map(Path::new));
+    let repo = path.map(repo_label).unwrap_or_else(|| "-".into());
+    if let Ok(mut records) = RECORDS.lock() {
+        records.push(Record { repo, op, elapsed });
+    }
+}
+
+/// Short name for the repo a path belongs to: its last component, without a
+/// bare repo's `.git` suffix.
+fn repo_label(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string());
+    name.strip_suffix(".git").unwrap_or(&name).to_string()
+}
+
+/// Aggregate what was recorded into one entry per (repo, operation), slowest
+/// first.
+pub fn report(total: Duration) -> TimingsOutput {
+    let records = RECORDS.lock().map(|r| aggregate(&r)).unwrap_or_default();
+    TimingsOutput {
+        total_ms: total.as_millis() as u64,
+        entries: records,
+    }
+}
+
+fn aggregate(records: &[Record]) -> Vec<TimingEntry> {
+    let mut entries: Vec<TimingEntry> = Vec::new();
+    for r in records {
+        let ms = r.elapsed.as_millis() as u64;
+        match entries
+            .iter_mut()
+            .find(|e| e.repo == r.repo && e.op == r.op)
+        {
+            Some(e) => {
+                e.calls += 1;
+                e.ms += ms;
+            }
+            None => entries.push(TimingEntry {
+                repo: r.repo.clone(),
+                op: r.op.clone(),
+                calls: 1,
+                ms,
+            }),
+        }
+    }
+    entries.sort_by(|a, b| {
+        b.ms.cmp(&a.ms)
+            .then_with(|| a.repo.cmp(&b.repo))
+            .then_with(|| a.op.cmp(&b.op))
+    });
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repo_label() {
+        let cases = vec![
+            ("/ws/demo/api", "api"),
+            ("/data/mirrors/github.com/acme/api.git", "api"),
+            ("web", "web"),
+        ];
+        for (path, want) in cases {
+            assert_eq!(repo_label(Path::new(path)), want, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let rec = |repo: &str, op: &str, ms: u64| Record {
+            repo: repo.into(),
+            op: op.into(),
+            elapsed: Duration::from_millis(ms),
+        };
+        let got = aggregate(&[
+            rec("api", "status", 5),
+            rec("web", "fetch", 800),
+            rec("api", "status", 7),
+            rec("api", "fetch", 300),
+        ]);
+        let got: Vec<(&str, &str, u32, u64)> = got
+            .iter()
+            .map(|e| (e.repo.as_str(), e.op.as_str(), e.calls, e.ms))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                ("web", "fetch", 1, 800),
+                ("api", "fetch", 1, 300),
+                ("api", "status", 2, 12),
+            ]
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::{Result, bail};
+
+use crate::util::path_str;
+
+/// One pane to open: a label for the pane title, the directory it starts in,
+/// and the shell command it runs.
+pub struct Pane {
+    pub label: String,
+    pub dir: PathBuf,
+    pub command: String,
+}
+
+/// tmux session name for a workspace. tmux reserves `.` and `:` in target
+/// names, so they are replaced with `-`.
+pub fn session_name(workspace: &str) -> String {
+    workspace.replace(['.', ':'], "-")
+}
+
+pub fn is_available() -> bool {
+    Command::new("tmux")
+        .arg("-V")
+        .output()
+        .is_ok_and(|o| o.status.success())
+}
+
+/// True when running inside a tmux client.
+pub fn is_inside() -> bool {
+    std::env::var_os("TMUX").is_some_and(|v| !v.is_empty())
+}
+
+pub fn has_session(session: &str) -> bool {
+    Command::new("tmux")
+        .args(["has-session", "-t", &format!("={}", session)])
+        .output()
+        .is_ok_and(|o| o.status.success())
+}
+
+/// Open `panes` side by side in a new window named `window` of `session`,
+/// creating the session (detached) if needed. Panes stay open after their
+/// command exits so output can be read.
+pub fn launch(session: &str, window: &str, panes: &[Pane]) -> Result<()> {
+    let Some((first, rest)) = panes.split_first() else {
+        return Ok(());
+    };
+    let dir = path_str(&first.dir)?;
+    // The window starts with the default shell so options can be set before
+    // the command runs; a command that exits immediately would otherwise take
+    // the window with it.
+    let window_id = if has_session(session) {
+        run(&[
+            "new-window",
+            "-d",
+            "-P",
+            "-F",
+            "#{window_id}",
+            "-t",
+            &format!("={}:", session),
+            "-n",
+            window,
+            "-c",
+            dir,
+        ])?
+    } else {
+        run(&[
+            "new-session",
+            "-d",
+            "-P",
+            "-F",
+            "#{window_id}",
+            "-s",
+            session,
+            "-n",
+            window,
+            "-c",
+            dir,
+        ])?
+    };
+    run(&["set-option", "-w", "-t", &window_id, "remain-on-exit", "on"])?;
+    run(&[
+        "set-option",
+        "-w",
+        "-t",
+        &window_id,
+        "pane-border-status",
+        "top",
+    ])?;
+    run(&["select-pane", "-t", &window_id, "-T", &first.label])?;
+    run(&[
+        "respawn-pane",
+        "-k",
+        "-t",
+        &window_id,
+        "-c",
+        dir,
+        &first.command,
+    ])?;
+
+    for pane in rest {
+        let pane_id = run(&[
+            "split-window",
+            "-d",
+            "-P",
+            "-F",
+            "#{pane_id}",
+            "-t",
+            &window_id,
+            "-c",
+            path_str(&pane.dir)?,
+            &pane.command,
+        ])?;
+        run(&["select-pane", "-t", &pane_id, "-T", &pane.label])?;
+        // Re-tile after each split so later splits have room
+        run(&["select-layout", "-t", &window_id, "tiled"])?;
+    }
+    Ok(())
+}
+
+/// Command that brings the user to `session`: switch-client inside tmux,
+/// attach outside it.
+pub fn attach_hint(session: &str) -> String {
+    if is_inside() {
+        format!(
+            "tmux switch-client -t {}",
+            crate::util::shell_quote(session)
+        )
+    } else {
+        format!("tmux attach -t {}", crate::util::shell_quote(session))
+    }
+}
+
+fn run(args: &[&str]) -> Result<String> {
+    let output = Command::new("tmux").args(args).output()?;
+    if !output.status.success() {
+        bail!(
+            "tmux {}: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_name() {
+        let cases = vec![
+            ("plain", "my-feature", "my-feature"),
+            ("dots", "v1.2-fix", "v1-2-fix"),
+            ("colon", "a:b", "a-b"),
+        ];
+        for (name, input, want) in cases {
+            assert_eq!(session_name(input), want, "{}", name);
+        }
+    }
+}
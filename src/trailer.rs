@@ -0,0 +1,63 @@
+use crate::managedhook::ManagedHook;
+
+pub const HOOK: ManagedHook = ManagedHook {
+    name: "prepare-commit-msg",
+    marker: "# wsp: workspace trailer",
+    script: SCRIPT,
+    label: "commit trailer hook",
+};
+
+/// Adds a `Workspace: <name>` trailer to each commit message. The name is
+/// read from the workspace's metadata at commit time, so it survives
+/// `wsp rename`. Messages that already carry the same trailer (amends) are
+/// left alone.
+const SCRIPT: &str = r#"#!/bin/sh
+# wsp: workspace trailer
+case "$2" in merge|squash) exit 0 ;; esac
+dir=$(git rev-parse --show-toplevel)
+while [ "$dir" != / ] && [ ! -f "$dir/.wsp.yaml" ]; do dir=$(dirname "$dir"); done
+[ -f "$dir/.wsp.yaml" ] || exit 0
+name=$(sed -n 's/^name: *//p' "$dir/.wsp.yaml" | head -n 1)
+[ -n "$name" ] || exit 0
+exec git interpret-trailers --in-place --if-exists addIfDifferent \
+    --trailer "Workspace: $name" "$1"
+"#;
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use super::*;
+
+    fn commit_message(dir: &Path, message: &str, amend: bool) -> String {
+        let mut args = vec!["commit", "--allow-empty", "-q", "-m", message];
+        if amend {
+            args.push("--amend");
+        }
+        crate::git::run(Some(dir), &args).unwrap();
+        crate::git::run(Some(dir), &["log", "-1", "--format=%B"]).unwrap()
+    }
+
+    #[test]
+    fn test_hook_adds_trailer() {
+        let (clone, _, clone_tmp, _source_tmp) = crate::testutil::setup_clone_repo();
+        fs::write(
+            clone_tmp.path().join(".wsp.yaml"),
+            "name: add-billing\nbranch: x\n",
+        )
+        .unwrap();
+
+        HOOK.install(&clone).unwrap();
+        assert!(HOOK.is_installed(&clone));
+
+        let msg = commit_message(&clone, "Add billing", false);
+        assert!(msg.contains("\n\nWorkspace: add-billing"), "{:?}", msg);
+
+        let msg = commit_message(&clone, "Add billing", true);
+        assert_eq!(msg.matches("Workspace:").count(), 1, "{:?}", msg);
+
+        HOOK.uninstall(&clone).unwrap();
+        assert!(!HOOK.path(&clone).exists());
+    }
+}
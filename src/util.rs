@@ -1,5 +1,8 @@
 use std::io::{BufRead, Read};
+use std::ops::ControlFlow;
 use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use anyhow::{Context, Result, bail};
 
@@ -27,10 +30,289 @@ pub(crate) fn read_yaml_file(path: &Path) -> Result<String> {
     Ok(buf)
 }
 
+/// Flushes a directory's entries (e.g. a rename into it) to disk. Best
+/// effort: not every platform can open a directory to sync it.
+pub(crate) fn sync_dir(dir: &Path) {
+    if let Ok(d) = std::fs::File::open(dir) {
+        let _ = d.sync_all();
+    }
+}
+
+/// Parses an age like `30s`, `10m`, `2h` or `7d`; a bare number is seconds.
+pub(crate) fn parse_duration(s: &str) -> Result<std::time::Duration> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+    let Ok(n) = num.parse::<u64>() else {
+        bail!("invalid duration {:?} (expected e.g. 30s, 10m, 2h, 7d)", s);
+    };
+    let scale = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => bail!("invalid duration {:?} (expected e.g. 30s, 10m, 2h, 7d)", s),
+    };
+    Ok(std::time::Duration::from_secs(n.saturating_mul(scale)))
+}
+
+pub(crate) fn read_stdin_line() -> String {
+    let stdin = std::io::stdin();
+    let mut line = String::new();
+    if let Err(e) = stdin.lock().read_line(&mut line) {
+        tracing::warn!("failed to read stdin: {}", e);
+    }
+    line
+}
+
+/// Quote `s` for a POSIX shell. Words made only of safe characters are left
+/// as-is; anything else is single-quoted.
+pub(crate) fn shell_quote(s: &str) -> String {
+    let safe = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if safe {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
+
+/// `p` as a `&str`, for command-line arguments and APIs that need UTF-8.
+pub(crate) fn path_str(p: &Path) -> Result<&str> {
+    p.to_str()
+        .with_context(|| format!("path {} is not valid UTF-8", p.display()))
+}
+
+/// Escape `s` for XML text and attribute values.
+pub(crate) fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Creates a symlink at `link` pointing to `original`, which is relative to
+/// `link`'s directory as on unix. Windows needs to know whether the target
+/// is a directory, and may refuse without Developer Mode; callers that can
+/// do without a link fall back to a copy.
+pub(crate) fn symlink(original: &Path, link: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(original, link)
+    }
+    #[cfg(windows)]
+    {
+        let target = link.parent().unwrap_or(Path::new("")).join(original);
+        if target.is_dir() {
+            std::os::windows::fs::symlink_dir(original, link)
+        } else {
+            std::os::windows::fs::symlink_file(original, link)
+        }
+    }
+}
+
+/// Windows device names, which can't be used as a file name with or
+/// without an extension.
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Whether Windows can't create a file or directory called `name`: a device
+/// name (`con`, `nul.txt`, ...) or one ending in a dot or space. Names that
+/// become directories are checked on every platform, so workspaces and
+/// backups carry over between them.
+pub(crate) fn is_windows_reserved_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name);
+    WINDOWS_RESERVED_NAMES
+        .iter()
+        .any(|r| r.eq_ignore_ascii_case(stem.trim_end()))
+        || name.ends_with(['.', ' '])
+}
+
+/// Matches `name` against `pattern`, where each `*` stands for any run of
+/// characters.
+pub(crate) fn glob_match(pattern: &str, name: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == name;
+    }
+    let mut pos = 0;
+    for (i, part) in parts.iter().enumerate() {
+        if part.is_empty() {
+            continue;
+        }
+        match name[pos..].find(part) {
+            Some(idx) => {
+                if i == 0 && idx != 0 {
+                    return false;
+                }
+                pos += idx + part.len();
+            }
+            None => return false,
+        }
+    }
+    if !pattern.ends_with('*') {
+        pos == name.len()
+    } else {
+        true
+    }
+}
+
+/// Receives [`run_bounded`] results in order; breaking stops the run.
+pub(crate) type Emit<'a, R> = &'a mut (dyn FnMut(&R) -> ControlFlow<()> + Send);
+
+/// Run `work` on each item with at most `jobs` in flight. Results come
+/// back in the order of `items`; `emit` gets them in that order too, each
+/// as soon as it and all earlier ones are done. When `emit` breaks, no
+/// new items start and only the results finished so far come back.
+pub(crate) fn run_bounded<T: Sync, R: Send>(
+    items: &[T],
+    jobs: usize,
+    work: impl Fn(&T) -> R + Sync,
+    emit: Option<Emit<'_, R>>,
+) -> Vec<R> {
+    struct Done<'a, R> {
+        slots: Vec<Option<R>>,
+        emitted: usize,
+        emit: Option<Emit<'a, R>>,
+    }
+    let next = Mutex::new(0usize);
+    let stopped = AtomicBool::new(false);
+    let done = Mutex::new(Done {
+        slots: (0..items.len()).map(|_| None).collect(),
+        emitted: 0,
+        emit,
+    });
+    std::thread::scope(|s| {
+        for _ in 0..jobs.min(items.len()) {
+            s.spawn(|| {
+                loop {
+                    if stopped.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let i = {
+                        let mut next = next.lock().unwrap_or_else(|e| e.into_inner());
+                        let i = *next;
+                        *next += 1;
+                        i
+                    };
+                    let Some(item) = items.get(i) else {
+                        break;
+                    };
+                    let result = work(item);
+                    let mut done = done.lock().unwrap_or_else(|e| e.into_inner());
+                    let done = &mut *done;
+                    done.slots[i] = Some(result);
+                    while let Some(Some(ready)) = done.slots.get(done.emitted) {
+                        if let Some(f) = done.emit.as_mut()
+                            && f(ready).is_break()
+                        {
+                            done.emit = None;
+                            stopped.store(true, Ordering::Relaxed);
+                        }
+                        done.emitted += 1;
+                    }
+                }
+            });
+        }
+    });
+    done.into_inner()
+        .unwrap_or_else(|e| e.into_inner())
+        .slots
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_glob_match() {
+        let cases = vec![
+            ("prefix wildcard", "api-*", "api-gateway", true),
+            ("prefix wildcard 2", "api-*", "api-v2", true),
+            ("prefix no match", "api-*", "user-api", false),
+            ("suffix wildcard", "*-service", "user-service", true),
+            ("suffix no match", "*-service", "service-mesh", false),
+            ("contains wildcard", "*core*", "core", true),
+            ("contains wildcard 2", "*core*", "core-lib", true),
+            ("contains wildcard 3", "*core*", "my-core-service", true),
+            ("exact match", "exact", "exact", true),
+            ("exact no match", "exact", "exactly", false),
+            ("match all", "*", "anything", true),
+            ("match all empty", "*", "", true),
+            ("empty pattern", "", "", true),
+            ("empty pattern no match", "", "x", false),
+            ("multi wildcard", "a*b*c", "aXbYc", true),
+            ("multi wildcard no match", "a*b*c", "aXYc", false),
+            ("anchored start", "api-*", "xapi-foo", false),
+            ("anchored end", "*-api", "api-x", false),
+        ];
+        for (name, pattern, input, want) in cases {
+            assert_eq!(
+                glob_match(pattern, input),
+                want,
+                "{}: glob_match({:?}, {:?})",
+                name,
+                pattern,
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_windows_reserved_name() {
+        let cases = vec![
+            ("con", true),
+            ("NUL", true),
+            ("aux.txt", true),
+            ("com1.tar.gz", true),
+            ("lpt9", true),
+            ("trailing.", true),
+            ("trailing ", true),
+            ("console", false),
+            ("com10", false),
+            ("my-con", false),
+            ("add-billing", false),
+            ("v1.2", false),
+        ];
+        for (name, want) in cases {
+            assert_eq!(is_windows_reserved_name(name), want, "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        let cases = vec![
+            ("plain", "make", "make"),
+            ("path", "./bin/run-tests", "./bin/run-tests"),
+            ("space", "npm run dev", "'npm run dev'"),
+            ("empty", "", "''"),
+            ("single quote", "it's", "'it'\\''s'"),
+            ("dollar", "$HOME", "'$HOME'"),
+        ];
+        for (name, input, want) in cases {
+            assert_eq!(shell_quote(input), want, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_xml_escape() {
+        let cases = vec![
+            ("api", "api"),
+            ("a&b", "a&amp;b"),
+            ("<x>", "&lt;x&gt;"),
+            ("say \"hi\"", "say &quot;hi&quot;"),
+        ];
+        for (input, want) in cases {
+            assert_eq!(xml_escape(input), want, "{}", input);
+        }
+    }
+
     #[test]
     fn test_read_yaml_file_ok() {
         let tmp = tempfile::NamedTempFile::new().unwrap();
@@ -57,18 +339,83 @@ mod tests {
         assert_eq!(content.len(), MAX_YAML_BYTES as usize);
     }
 
+    #[test]
+    fn test_parse_duration() {
+        let cases = vec![
+            ("90", Some(90)),
+            ("30s", Some(30)),
+            ("10m", Some(600)),
+            ("2h", Some(7200)),
+            ("7d", Some(604_800)),
+            ("0m", Some(0)),
+            ("", None),
+            ("m", None),
+            ("10x", None),
+            ("1.5h", None),
+            ("-1m", None),
+        ];
+        for (input, want) in cases {
+            let got = parse_duration(input).ok().map(|d| d.as_secs());
+            assert_eq!(got, want, "{:?}", input);
+        }
+    }
+
     #[test]
     fn test_read_yaml_file_missing() {
         let result = read_yaml_file(Path::new("/nonexistent/file.yaml"));
         assert!(result.is_err());
     }
-}
 
-pub(crate) fn read_stdin_line() -> String {
-    let stdin = std::io::stdin();
-    let mut line = String::new();
-    if let Err(e) = stdin.lock().read_line(&mut line) {
-        eprintln!("warning: failed to read stdin: {}", e);
+    #[test]
+    fn test_run_bounded_keeps_order() {
+        let items: Vec<u64> = (0..20).collect();
+        for jobs in [1, 4, 64] {
+            let mut emitted = Vec::new();
+            let mut emit = |n: &u64| {
+                emitted.push(*n);
+                ControlFlow::Continue(())
+            };
+            let results = run_bounded(
+                &items,
+                jobs,
+                |n| {
+                    // Later items finish first.
+                    std::thread::sleep(std::time::Duration::from_millis(20 - n));
+                    *n
+                },
+                Some(&mut emit),
+            );
+            assert_eq!(results, items, "jobs={}", jobs);
+            assert_eq!(emitted, items, "jobs={}", jobs);
+        }
+        assert!(run_bounded(&[] as &[u64], 4, |n| *n, None).is_empty());
+    }
+
+    #[test]
+    fn test_run_bounded_stops_when_emit_breaks() {
+        let items: Vec<u64> = (0..100).collect();
+        let started = std::sync::atomic::AtomicUsize::new(0);
+        let mut emitted = Vec::new();
+        let mut emit = |n: &u64| {
+            emitted.push(*n);
+            if *n == 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        };
+        let results = run_bounded(
+            &items,
+            2,
+            |n| {
+                started.fetch_add(1, Ordering::Relaxed);
+                *n
+            },
+            Some(&mut emit),
+        );
+        assert_eq!(emitted, vec![0, 1, 2]);
+        // Each worker finishes at most the item it had in hand.
+        assert!(results.len() <= 5, "{} results", results.len());
+        assert_eq!(started.load(Ordering::Relaxed), results.len());
     }
-    line
 }
@@ -7,11 +7,13 @@ use anyhow::{Context, Result, bail};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::config::Paths;
+use crate::config::{Config, Paths};
+use crate::error::{self, ErrorKind};
 use crate::filelock;
 use crate::git;
 use crate::giturl;
 use crate::mirror;
+use crate::offline::FetchPolicy;
 use crate::util::read_stdin_line;
 
 pub const CURRENT_METADATA_VERSION: u32 = 0;
@@ -54,11 +56,44 @@ pub struct Metadata {
     pub created_from: Option<String>,
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     pub dirs: BTreeMap<String, String>,
+    /// Branch per repo identity where it differs from `branch`, from
+    /// per-host/owner branch prefixes.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub branches: BTreeMap<String, String>,
+    /// Pull request URL per repo identity, recorded by `wsp pr create`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub prs: BTreeMap<String, String>,
+    /// Issue the workspace is for: a GitHub issue URL, or a Jira issue URL or
+    /// key.
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue: Option<String>,
+    /// Workspace-scoped settings, layered over the global config by
+    /// [`Metadata::apply_workspace_config`].
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "settings")]
     pub config: Option<crate::template::TemplateConfig>,
+    /// Repos `wsp new --continue-on-error` couldn't clone, by identity, with
+    /// the error. They aren't in `repos` until `wsp repair` clones them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pending: BTreeMap<String, String>,
 }
 
 impl Metadata {
+    /// Returns the workspace branch for a repo: its entry in `branches`,
+    /// else `branch`.
+    pub fn branch_for(&self, identity: &str) -> &str {
+        self.branches.get(identity).unwrap_or(&self.branch)
+    }
+
+    /// Returns the identities that hold a directory name: the repos, then
+    /// the pending repos, which keep theirs for `wsp repair`.
+    pub fn dir_identities(&self) -> Vec<&str> {
+        self.repos
+            .keys()
+            .chain(self.pending.keys())
+            .map(|s| s.as_str())
+            .collect()
+    }
+
     /// Returns the clone directory name for an identity.
     /// Uses the dirs map if an override exists, otherwise falls back to parsed.repo.
     pub fn dir_name(&self, identity: &str) -> Result<String> {
@@ -69,6 +104,31 @@ impl Metadata {
         Ok(parsed.repo)
     }
 
+    /// Resolve a repo argument to one of this workspace's identities: a
+    /// clone directory name (including custom ones) first, then an identity
+    /// or unique shortname.
+    pub fn resolve_repo(&self, name: &str) -> Result<String> {
+        for identity in self.repos.keys() {
+            if self.dir_name(identity).is_ok_and(|dn| dn == name) {
+                return Ok(identity.clone());
+            }
+        }
+        let identities: Vec<String> = self.repos.keys().cloned().collect();
+        giturl::resolve(name, &identities)
+            .map_err(|e| error::prefixed(e, &format!("workspace {:?}", self.name)))
+    }
+
+    /// Returns the configured `exec-dir` subdirectory for an identity, if any:
+    /// the workspace's own, else the global one.
+    pub fn exec_dir<'a>(&'a self, cfg: &'a Config, identity: &str) -> Option<&'a str> {
+        self.config
+            .as_ref()
+            .and_then(|c| c.exec_dirs.as_ref())
+            .and_then(|m| m.get(identity))
+            .or_else(|| cfg.repos.get(identity)?.exec_dir.as_ref())
+            .map(|s| s.as_str())
+    }
+
     /// Apply workspace config onto global config, returning a modified copy.
     /// Workspace config overrides global config; absent fields leave config unchanged.
     /// Same pattern as `Template::apply_config`.
@@ -86,6 +146,15 @@ impl Metadata {
             if let Some(ref strategy) = settings.sync_strategy {
                 effective.sync_strategy = Some(strategy.clone());
             }
+            if let Some(ref prefix) = settings.branch_prefix {
+                effective.branch_prefix = Some(prefix.clone());
+            }
+            if let Some(enabled) = settings.agent_md {
+                effective.agent_md = Some(enabled);
+            }
+            if let Some(jobs) = settings.run_jobs {
+                effective.run_jobs = Some(jobs);
+            }
             if let Some(ref gc) = settings.git_config {
                 let target = effective
                     .git_config
@@ -94,6 +163,29 @@ impl Metadata {
                     target.insert(k.clone(), v.clone());
                 }
             }
+            if let Some(ref tasks) = settings.tasks {
+                let target = effective
+                    .tasks
+                    .get_or_insert_with(std::collections::BTreeMap::new);
+                for (k, v) in tasks {
+                    target.insert(k.clone(), v.clone());
+                }
+            }
+            if let Some(ref deps) = settings.depends_on {
+                let target = effective
+                    .depends_on
+                    .get_or_insert_with(std::collections::BTreeMap::new);
+                let identities: Vec<String> = self.repos.keys().cloned().collect();
+                crate::deps::merge(target, deps, &identities);
+            }
+            if let Some(ref files) = settings.files {
+                let target = effective
+                    .files
+                    .get_or_insert_with(std::collections::BTreeMap::new);
+                for (k, v) in files {
+                    target.insert(k.clone(), v.clone());
+                }
+            }
         }
         effective
     }
@@ -125,6 +217,9 @@ pub(crate) fn compute_dir_names(identities: &[&str]) -> Result<BTreeMap<String,
 }
 
 pub const METADATA_FILE: &str = ".wsp.yaml";
+/// The previous `.wsp.yaml`, kept by [`save_metadata`] to recover from by
+/// hand.
+pub const METADATA_BACKUP_FILE: &str = ".wsp.yaml.bak";
 
 pub fn dir(workspaces_dir: &Path, name: &str) -> PathBuf {
     workspaces_dir.join(name)
@@ -151,22 +246,40 @@ pub fn validate_name(name: &str) -> Result<()> {
             name
         );
     }
+    if crate::util::is_windows_reserved_name(name) {
+        bail!("workspace name {:?} is reserved on Windows", name);
+    }
     Ok(())
 }
 
 pub fn load_metadata(ws_dir: &Path) -> Result<Metadata> {
-    let data = crate::util::read_yaml_file(&ws_dir.join(METADATA_FILE))?;
-    let m: Metadata = serde_yaml_ng::from_str(&data)?;
-    if m.version > CURRENT_METADATA_VERSION {
-        eprintln!(
-            "warning: .wsp.yaml has version {}, but this wsp only supports version {}. Some fields may be ignored.",
-            m.version, CURRENT_METADATA_VERSION
-        );
-    }
+    let data = crate::migrate::load(
+        &ws_dir.join(METADATA_FILE),
+        CURRENT_METADATA_VERSION,
+        crate::migrate::METADATA,
+        METADATA_FILE,
+    )?;
+    let m: Metadata = serde_yaml_ng::from_str(&data).map_err(|e| {
+        if ws_dir.join(METADATA_BACKUP_FILE).exists() {
+            anyhow::anyhow!(
+                "{} (the previous version is saved in {})",
+                e,
+                METADATA_BACKUP_FILE
+            )
+        } else {
+            e.into()
+        }
+    })?;
     for (identity, dir_name) in &m.dirs {
         validate_dir_name(dir_name)
             .map_err(|e| anyhow::anyhow!("invalid dir override for {}: {}", identity, e))?;
     }
+    if let Some(exec_dirs) = m.config.as_ref().and_then(|c| c.exec_dirs.as_ref()) {
+        for (identity, subdir) in exec_dirs {
+            validate_exec_dir(subdir)
+                .map_err(|e| anyhow::anyhow!("invalid exec-dir for {}: {}", identity, e))?;
+        }
+    }
     Ok(m)
 }
 
@@ -183,17 +296,55 @@ fn validate_dir_name(name: &str) -> Result<()> {
     if name == "." || name == ".." || name.contains("..") {
         bail!("directory name {:?} contains path traversal", name);
     }
+    if crate::util::is_windows_reserved_name(name) {
+        bail!("directory name {:?} is reserved on Windows", name);
+    }
     Ok(())
 }
 
+/// Validate an `exec-dir` subdirectory: a relative path that stays inside the clone.
+pub(crate) fn validate_exec_dir(subdir: &str) -> Result<()> {
+    if subdir.is_empty() {
+        bail!("subdirectory cannot be empty");
+    }
+    if subdir.contains('\0') {
+        bail!("subdirectory {:?} contains null bytes", subdir);
+    }
+    let path = Path::new(subdir);
+    if path.is_absolute() {
+        bail!("subdirectory {:?} must be relative to the repo", subdir);
+    }
+    if path.components().any(|c| {
+        !matches!(
+            c,
+            std::path::Component::Normal(_) | std::path::Component::CurDir
+        )
+    }) {
+        bail!("subdirectory {:?} contains path traversal", subdir);
+    }
+    Ok(())
+}
+
+/// Writes the metadata to a synced temp file and renames it over
+/// `.wsp.yaml`, so a crash leaves either the old or the new version, never a
+/// truncated one. The old version is copied to `.wsp.yaml.bak` first.
 pub fn save_metadata(ws_dir: &Path, m: &Metadata) -> Result<()> {
     let data = serde_yaml_ng::to_string(m)?;
+    let path = ws_dir.join(METADATA_FILE);
     let mut tmp =
         tempfile::NamedTempFile::new_in(ws_dir).context("creating temp file for atomic save")?;
     tmp.write_all(data.as_bytes())
         .context("writing metadata to temp file")?;
-    tmp.persist(ws_dir.join(METADATA_FILE))
+    tmp.as_file()
+        .sync_all()
+        .context("syncing metadata temp file")?;
+    if path.exists() {
+        fs::copy(&path, ws_dir.join(METADATA_BACKUP_FILE)).context("backing up metadata")?;
+    }
+    crate::migrate::back_up_older(&path, CURRENT_METADATA_VERSION, METADATA_FILE)?;
+    tmp.persist(&path)
         .context("renaming temp file to metadata")?;
+    crate::util::sync_dir(ws_dir);
     Ok(())
 }
 
@@ -207,30 +358,166 @@ pub fn detect(start_dir: &Path) -> Result<PathBuf> {
             Some(parent) if parent != dir => {
                 dir = parent.to_path_buf();
             }
-            _ => bail!("not in a workspace (no {} found)", METADATA_FILE),
+            _ => {
+                return Err(error::tag(
+                    ErrorKind::NotFound,
+                    anyhow::anyhow!("not in a workspace (no {} found)", METADATA_FILE),
+                ));
+            }
         }
     }
 }
 
 /// Update `last_used` timestamp in workspace metadata.
 /// Best-effort: errors are logged to stderr but not propagated.
+pub fn touch_last_used(ws_dir: &Path) {
+    if let Err(e) = filelock::with_metadata(ws_dir, |meta| {
+        meta.last_used = Some(Utc::now());
+        Ok(())
+    }) {
+        tracing::warn!("updating last used time: {}", e);
+    }
+}
+
+/// Branch template used when `branch-template` is unset. An empty
+/// `{prefix}` drops out together with its slash.
+pub const DEFAULT_BRANCH_TEMPLATE: &str = "{prefix}/{workspace}";
+
+/// Returns the branch for workspace `name`: `template` (default
+/// [`DEFAULT_BRANCH_TEMPLATE`]) with `{prefix}` (branch-prefix), `{user}`
+/// ($USER), `{workspace}`, and `{date}` (today, YYYY-MM-DD) filled in.
+pub fn branch_name(template: Option<&str>, prefix: Option<&str>, name: &str) -> Result<String> {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+        .filter(|u| !u.is_empty());
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    render_branch(
+        template.unwrap_or(DEFAULT_BRANCH_TEMPLATE),
+        prefix.unwrap_or(""),
+        user.as_deref(),
+        name,
+        &date,
+    )
+}
+
+/// Checks a `branch-template` value by rendering it with sample values.
+pub fn validate_branch_template(template: &str) -> Result<()> {
+    if !template.contains("{workspace}") {
+        bail!(
+            "branch-template {:?} must contain {{workspace}} so each workspace gets its own branch",
+            template
+        );
+    }
+    render_branch(template, "prefix", Some("user"), "workspace", "2000-01-01").map(|_| ())
+}
+
+fn render_branch(
+    template: &str,
+    prefix: &str,
+    user: Option<&str>,
+    workspace: &str,
+    date: &str,
+) -> Result<String> {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let Some(len) = rest[open..].find('}') else {
+            bail!("unclosed {{ in branch-template {:?}", template);
+        };
+        match &rest[open + 1..open + len] {
+            "prefix" => out.push_str(prefix),
+            "user" => out.push_str(user.ok_or_else(|| {
+                anyhow::anyhow!("branch-template uses {{user}} but $USER is not set")
+            })?),
+            "workspace" => out.push_str(workspace),
+            "date" => out.push_str(date),
+            other => bail!(
+                "unknown placeholder {{{}}} in branch-template {:?} (available: {{prefix}}, {{user}}, {{workspace}}, {{date}})",
+                other,
+                template
+            ),
+        }
+        rest = &rest[open + len + 1..];
+    }
+    out.push_str(rest);
+
+    // Empty placeholders leave empty path segments behind; drop them.
+    let branch = out
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("/");
+    git::validate_branch_name(&branch)?;
+    Ok(branch)
+}
+
+/// Returns the pattern part of a `branch-prefix.<pattern>` key.
+pub fn key_prefix_pattern(key: &str) -> Option<&str> {
+    key.strip_prefix("branch-prefix.")
+}
+
+/// The branches a workspace's repos are created on.
+#[derive(Debug, Clone)]
+pub struct Branches {
+    /// The workspace branch, for every repo without its own.
+    pub default: String,
+    /// Repos whose `branch-prefix.<pattern>` rule gives them another branch.
+    pub per_repo: BTreeMap<String, String>,
+}
+
+impl Branches {
+    /// Works out the branches for workspace `name` from config: the default
+    /// from `branch-prefix`, and one per repo whose identity matches a
+    /// `branch-prefix.<pattern>` rule giving a different branch.
+    pub fn plan<'a>(
+        cfg: &Config,
+        name: &str,
+        identities: impl IntoIterator<Item = &'a String>,
+    ) -> Result<Branches> {
+        let template = cfg.branch_template.as_deref();
+        let default = branch_name(template, cfg.branch_prefix.as_deref(), name)?;
+        let mut per_repo = BTreeMap::new();
+        for identity in identities {
+            if let Some(prefix) = cfg.branch_prefix_rule(identity) {
+                let branch = branch_name(template, Some(prefix), name)?;
+                if branch != default {
+                    per_repo.insert(identity.clone(), branch);
+                }
+            }
+        }
+        Ok(Branches { default, per_repo })
+    }
+}
+
+/// Options for [`create`] beyond the repos and their branches.
+#[derive(Default)]
+pub struct CreateOpts<'a> {
+    pub description: Option<&'a str>,
+    pub created_from: Option<&'a str>,
+    /// Record repos that fail to clone as pending instead of failing the
+    /// workspace.
+    pub continue_on_error: bool,
+}
+
+/// Creates workspace `name` with a clone of each of `repo_refs`. With
+/// `continue_on_error`, repos that fail to clone don't fail the workspace:
+/// they are recorded as pending and returned with their errors, unless all
+/// of them fail.
 pub fn create(
     paths: &Paths,
     name: &str,
     repo_refs: &BTreeMap<String, String>,
-    branch_prefix: Option<&str>,
+    branches: &Branches,
     upstream_urls: &BTreeMap<String, String>,
-    description: Option<&str>,
-    created_from: Option<&str>,
-) -> Result<()> {
+    opts: &CreateOpts,
+) -> Result<BTreeMap<String, String>> {
     validate_name(name)?;
-
-    let branch = match branch_prefix.filter(|p| !p.is_empty()) {
-        Some(prefix) => format!("{}/{}", prefix, name),
-        None => name.to_string(),
-    };
-
-    git::validate_branch_name(&branch)?;
+    git::validate_branch_name(&branches.default)?;
+    for branch in branches.per_repo.values() {
+        git::validate_branch_name(branch)?;
+    }
 
     let ws_dir = dir(&paths.workspaces_dir, name);
     if ws_dir.exists() {
@@ -238,7 +525,10 @@ pub fn create(
         // A previous `wsp new` may have crashed mid-clone, leaving a partial dir.
         let meta_path = ws_dir.join(METADATA_FILE);
         if meta_path.exists() {
-            bail!("workspace {:?} already exists", name);
+            return Err(error::tag(
+                ErrorKind::Conflict,
+                anyhow::anyhow!("workspace {:?} already exists", name),
+            ));
         }
         eprintln!("Resuming partial workspace creation for {:?}...", name);
     } else {
@@ -247,15 +537,16 @@ pub fn create(
 
     match create_inner(&CreateInnerOpts {
         mirrors_dir: &paths.mirrors_dir,
-        branch: &branch,
+        branches,
         ws_dir: &ws_dir,
         name,
         repo_refs,
         upstream_urls,
-        description,
-        created_from,
+        description: opts.description,
+        created_from: opts.created_from,
+        continue_on_error: opts.continue_on_error,
     }) {
-        Ok(()) => Ok(()),
+        Ok(pending) => Ok(pending),
         Err(e) => {
             // Clean up workspace dir on failure (best-effort), but only if
             // the metadata was never written (partial state). If metadata
@@ -271,16 +562,17 @@ pub fn create(
 
 struct CreateInnerOpts<'a> {
     mirrors_dir: &'a Path,
-    branch: &'a str,
+    branches: &'a Branches,
     ws_dir: &'a Path,
     name: &'a str,
     repo_refs: &'a BTreeMap<String, String>,
     upstream_urls: &'a BTreeMap<String, String>,
     description: Option<&'a str>,
     created_from: Option<&'a str>,
+    continue_on_error: bool,
 }
 
-fn create_inner(opts: &CreateInnerOpts) -> Result<()> {
+fn create_inner(opts: &CreateInnerOpts) -> Result<BTreeMap<String, String>> {
     let mut repos: BTreeMap<String, Option<WorkspaceRepoRef>> = BTreeMap::new();
     for identity in opts.repo_refs.keys() {
         let url = opts.upstream_urls.get(identity).cloned();
@@ -296,21 +588,32 @@ fn create_inner(opts: &CreateInnerOpts) -> Result<()> {
     let identities: Vec<&str> = opts.repo_refs.keys().map(|s| s.as_str()).collect();
     let dirs = compute_dir_names(&identities)?;
 
-    let meta = Metadata {
+    let mut meta = Metadata {
         version: CURRENT_METADATA_VERSION,
         name: opts.name.to_string(),
-        branch: opts.branch.to_string(),
+        branch: opts.branches.default.clone(),
         repos,
         created: Utc::now(),
         description: opts.description.map(|s| s.to_string()),
         last_used: None,
         created_from: opts.created_from.map(|s| s.to_string()),
         dirs: dirs.clone(),
+        branches: opts
+            .branches
+            .per_repo
+            .iter()
+            .filter(|(id, _)| opts.repo_refs.contains_key(*id))
+            .map(|(id, b)| (id.clone(), b.clone()))
+            .collect(),
+        prs: BTreeMap::new(),
+        issue: None,
         config: None,
+        pending: BTreeMap::new(),
     };
 
     for identity in opts.repo_refs.keys() {
         let dn = meta.dir_name(identity)?;
+        let branch = meta.branch_for(identity);
         let dest = opts.ws_dir.join(&dn);
         let upstream = opts
             .upstream_urls
@@ -327,23 +630,45 @@ fn create_inner(opts: &CreateInnerOpts) -> Result<()> {
             if !upstream.is_empty() {
                 prompt_origin_url_for_adopt(&dest, upstream)?;
             }
-            prompt_branch_for_adopt(&dest, opts.branch)?;
+            prompt_branch_for_adopt(&dest, branch)?;
             eprintln!("  adopted existing directory {}/", dn);
-        } else {
-            clone_from_mirror(
-                opts.mirrors_dir,
-                opts.ws_dir,
-                identity,
-                &dn,
-                opts.branch,
-                upstream,
-            )
-            .map_err(|e| anyhow::anyhow!("cloning repo {}: {}", identity, e))?;
+            continue;
         }
+        let result = clone_from_mirror(
+            opts.mirrors_dir,
+            opts.ws_dir,
+            identity,
+            &dn,
+            branch,
+            upstream,
+        );
+        match result {
+            Ok(()) => {}
+            Err(e) if opts.continue_on_error => {
+                eprintln!("  FAIL  {} ({}), left pending", identity, e);
+                // Whatever got as far as disk is half a clone.
+                let _ = fs::remove_dir_all(&dest);
+                meta.pending.insert(identity.clone(), e.to_string());
+            }
+            Err(e) => bail!("cloning repo {}: {}", identity, e),
+        }
+    }
+
+    if !meta.pending.is_empty() {
+        if meta.pending.len() == meta.repos.len() {
+            let mut list = String::new();
+            for (identity, e) in &meta.pending {
+                list.push_str(&format!("\n  - {}: {}", identity, e));
+            }
+            bail!("no repo could be cloned:{}", list);
+        }
+        // Pending repos get their place back when `wsp repair` adds them.
+        // They keep their directory name meanwhile.
+        meta.repos.retain(|id, _| !meta.pending.contains_key(id));
     }
 
     save_metadata(opts.ws_dir, &meta)?;
-    Ok(())
+    Ok(meta.pending)
 }
 
 /// Validate that an existing directory can be adopted as a managed repo.
@@ -539,15 +864,17 @@ fn propagate_mirror_refs(mirrors_dir: &Path, dest: &Path, identity: &str) -> Res
     Ok(())
 }
 
+/// Clones `repo_refs` into an existing workspace, each on its entry in
+/// `branches` or else the workspace branch.
 pub fn add_repos(
     mirrors_dir: &Path,
     ws_dir: &Path,
     repo_refs: &BTreeMap<String, String>,
     upstream_urls: &BTreeMap<String, String>,
+    branches: &BTreeMap<String, String>,
 ) -> Result<()> {
     // Phase 1: snapshot metadata to determine branch and dir layout (fast lock)
     let snapshot = filelock::read_metadata(ws_dir)?;
-    let branch = snapshot.branch.clone();
 
     // Phase 2: clone repos from mirrors outside the lock (slow I/O).
     // Pre-compute directory names for the union of existing + new repos using
@@ -568,17 +895,38 @@ pub fn add_repos(
         })
         .collect();
 
-    // Compute dir names for existing + new repos together to detect all collisions
-    let all_identities: Vec<&str> = snapshot
-        .repos
-        .keys()
-        .map(|s| s.as_str())
-        .chain(new_identities.iter().map(|s| s.as_str()))
-        .collect();
+    // Compute dir names for existing + pending + new repos together to detect
+    // all collisions
+    let mut all_identities = snapshot.dir_identities();
+    for id in &new_identities {
+        if !snapshot.pending.contains_key(id.as_str()) {
+            all_identities.push(id.as_str());
+        }
+    }
     let all_dirs = compute_dir_names(&all_identities)?;
 
+    // Directory of each new repo: a pending repo keeps the one recorded for
+    // it (it may have been renamed by hand, e.g. in a restored backup), the
+    // others use the disambiguated name from all_dirs if present, otherwise
+    // the default.
+    let mut new_dirs: BTreeMap<&str, String> = BTreeMap::new();
+    for identity in &new_identities {
+        let dn = match (
+            snapshot.pending.contains_key(identity.as_str()),
+            snapshot.dirs.get(identity.as_str()),
+        ) {
+            (true, Some(d)) => d.clone(),
+            _ => match all_dirs.get(identity.as_str()) {
+                Some(d) => d.clone(),
+                None => parse_identity(identity)?.repo,
+            },
+        };
+        new_dirs.insert(identity.as_str(), dn);
+    }
+
     // Determine which existing repos need renaming (they now appear in all_dirs
-    // but weren't in snapshot.dirs, or their dir name changed)
+    // but weren't in snapshot.dirs, or their dir name changed). A repo with a
+    // recorded dir keeps it unless a new repo takes that name.
     struct RenameInfo {
         existing_id: String,
         old_dir: String,
@@ -588,6 +936,10 @@ pub fn add_repos(
     for existing_id in snapshot.repos.keys() {
         if let Some(new_dir) = all_dirs.get(existing_id) {
             let old_dir = snapshot.dir_name(existing_id)?;
+            if snapshot.dirs.contains_key(existing_id) && !new_dirs.values().any(|d| *d == old_dir)
+            {
+                continue;
+            }
             if *new_dir != old_dir {
                 renames.push(RenameInfo {
                     existing_id: existing_id.clone(),
@@ -601,6 +953,7 @@ pub fn add_repos(
     struct CloneInfo {
         identity: String,
         dir_name: String,
+        branch: String,
     }
     let mut clones: Vec<CloneInfo> = Vec::new();
 
@@ -609,15 +962,12 @@ pub fn add_repos(
             .get(identity.as_str())
             .map(|s| s.as_str())
             .unwrap_or("");
+        let branch = branches
+            .get(identity.as_str())
+            .unwrap_or(&snapshot.branch)
+            .clone();
 
-        // Use disambiguated name from all_dirs if present, otherwise default
-        let dn = match all_dirs.get(identity.as_str()) {
-            Some(d) => d.clone(),
-            None => {
-                let parsed = parse_identity(identity)?;
-                parsed.repo
-            }
-        };
+        let dn = new_dirs[identity.as_str()].clone();
 
         let dest = ws_dir.join(&dn);
         if dest.exists() {
@@ -637,6 +987,7 @@ pub fn add_repos(
         clones.push(CloneInfo {
             identity: identity.to_string(),
             dir_name: dn,
+            branch,
         });
     }
 
@@ -658,11 +1009,16 @@ pub fn add_repos(
 
         // Register new repos
         for ci in &clones {
-            if all_dirs.contains_key(&ci.identity) {
+            if all_dirs.contains_key(&ci.identity) || meta.dirs.contains_key(&ci.identity) {
                 meta.dirs.insert(ci.identity.clone(), ci.dir_name.clone());
             }
 
+            if ci.branch != meta.branch {
+                meta.branches.insert(ci.identity.clone(), ci.branch.clone());
+            }
+
             meta.repos.insert(ci.identity.clone(), None);
+            meta.pending.remove(&ci.identity);
         }
         Ok(())
     })?;
@@ -677,21 +1033,51 @@ fn remove_legacy_wsp_mirror(clone_dir: &Path) {
     }
 }
 
-/// Fetch a mirror from upstream and propagate refs to a clone (best-effort).
-fn fetch_and_propagate(mirrors_dir: &Path, clone_dir: &Path, identity: &str) -> Result<()> {
+/// Fetch a mirror from upstream (unless `fetch` says not to) and propagate
+/// refs to a clone (best-effort).
+fn fetch_and_propagate(
+    mirrors_dir: &Path,
+    clone_dir: &Path,
+    identity: &str,
+    fetch: FetchPolicy,
+) -> Result<()> {
     let parsed = parse_identity(identity)?;
     let mirror_path = mirror::dir(mirrors_dir, &parsed);
     remove_legacy_wsp_mirror(clone_dir);
-    git::fetch(&mirror_path, true)?;
+    if fetch.fetches() {
+        mirror::fetch_dir(&mirror_path, true)?;
+    }
     git::fetch_from_path(clone_dir, &mirror_path, MIRROR_PROPAGATE_REFSPEC, true)?;
     Ok(())
 }
 
+/// Updates a clone's origin refs before checking whether its branch is
+/// merged. Returns why they may be stale: not fetched (offline or
+/// --no-fetch), or the fetch failed.
+fn refresh_for_merge_check(
+    mirrors_dir: &Path,
+    clone_dir: &Path,
+    identity: &str,
+    fetch: FetchPolicy,
+) -> Option<&'static str> {
+    let result = fetch_and_propagate(mirrors_dir, clone_dir, identity, fetch);
+    if let Some(why) = fetch.skip_reason() {
+        return Some(why);
+    }
+    if result.is_err() {
+        tracing::warn!("  fetch failed for {}, using local data", identity);
+        return Some("fetch failed");
+    }
+    None
+}
+
 pub fn remove_repos(
     mirrors_dir: &Path,
     ws_dir: &Path,
     identities_to_remove: &[String],
     force: bool,
+    fetch: FetchPolicy,
+    jobs: usize,
 ) -> Result<()> {
     // Phase 1: snapshot metadata for safety checks (fast lock)
     let snapshot = filelock::read_metadata(ws_dir)?;
@@ -699,71 +1085,29 @@ pub fn remove_repos(
     // Validate all identities exist in the workspace
     for identity in identities_to_remove {
         if !snapshot.repos.contains_key(identity) {
-            bail!("repo {} is not in this workspace", identity);
+            return Err(error::tag(
+                ErrorKind::NotFound,
+                anyhow::anyhow!("repo {} is not in this workspace", identity),
+            ));
         }
     }
 
     // Phase 2: safety checks including network fetch (slow, no lock held)
     if !force {
-        let mut problems: Vec<String> = Vec::new();
-        for identity in identities_to_remove {
-            let dn = snapshot.dir_name(identity)?;
-            let clone_dir = ws_dir.join(&dn);
-
-            let changed = git::changed_file_count(&clone_dir).unwrap_or(0);
-            let ahead = git::ahead_count(&clone_dir).unwrap_or(0);
-            if changed > 0 || ahead > 0 {
-                problems.push(format!("{} (pending changes)", identity));
-                continue;
-            }
-
-            let fetch_failed = fetch_and_propagate(mirrors_dir, &clone_dir, identity).is_err();
-            if fetch_failed {
-                eprintln!("  warning: fetch failed for {}, using local data", identity);
-            }
-
-            if git::branch_exists(&clone_dir, &snapshot.branch) {
-                let default_branch = git::default_branch_for_remote(&clone_dir, "origin")
-                    .or_else(|_| git::default_branch(&clone_dir))
-                    .unwrap_or_default();
-                if !default_branch.is_empty() {
-                    let merge_target = format!("origin/{}", default_branch);
-                    let target = if git::ref_exists(&clone_dir, &merge_target) {
-                        merge_target
-                    } else {
-                        default_branch
-                    };
-                    match git::branch_safety(&clone_dir, &snapshot.branch, &target) {
-                        git::BranchSafety::Merged | git::BranchSafety::SquashMerged => {}
-                        git::BranchSafety::PushedToRemote => {
-                            let mut msg =
-                                format!("{} (unmerged branch, but pushed to remote)", identity);
-                            if fetch_failed {
-                                msg.push_str(" (fetch failed, local data may be stale)");
-                            }
-                            problems.push(msg);
-                        }
-                        git::BranchSafety::Unmerged => {
-                            let mut msg = format!("{} (unmerged branch)", identity);
-                            if fetch_failed {
-                                msg.push_str(" (fetch failed, local data may be stale)");
-                            }
-                            problems.push(msg);
-                        }
-                    }
-                }
-            }
-        }
-
+        let ids: Vec<&String> = identities_to_remove.iter().collect();
+        let problems = unsaved_work_all(mirrors_dir, ws_dir, &snapshot, &ids, fetch, jobs)?;
         if !problems.is_empty() {
             let mut list = String::new();
             for p in &problems {
                 list.push_str(&format!("\n  - {}", p));
             }
-            bail!(
-                "cannot remove repos:{}\n\nUse --force to remove anyway",
-                list
-            );
+            return Err(error::tag(
+                ErrorKind::Conflict,
+                anyhow::anyhow!(
+                    "cannot remove repos:{}\n\nUse --force to remove anyway",
+                    list
+                ),
+            ));
         }
     }
 
@@ -774,16 +1118,25 @@ pub fn remove_repos(
             let clone_path = ws_dir.join(&dn);
 
             if let Err(e) = fs::remove_dir_all(&clone_path) {
-                eprintln!("  warning: removing clone for {}: {}", identity, e);
+                tracing::warn!("  removing clone for {}: {}", identity, e);
             }
 
             meta.repos.remove(identity);
             meta.dirs.remove(identity);
+            meta.branches.remove(identity);
+            meta.prs.remove(identity);
+            if let Some(config) = meta.config.as_mut()
+                && let Some(exec_dirs) = config.exec_dirs.as_mut()
+            {
+                exec_dirs.remove(identity);
+                if exec_dirs.is_empty() {
+                    config.exec_dirs = None;
+                }
+            }
         }
 
         // Recalculate dir names for remaining repos
-        let remaining_ids: Vec<&str> = meta.repos.keys().map(|s| s.as_str()).collect();
-        let new_dirs = compute_dir_names(&remaining_ids)?;
+        let new_dirs = compute_dir_names(&meta.dir_identities())?;
 
         // Check if any collision disambiguations can be undone
         for (identity, new_dir) in &new_dirs {
@@ -791,7 +1144,7 @@ pub fn remove_repos(
                 && old_dir != new_dir
                 && let Err(e) = fs::rename(ws_dir.join(old_dir), ws_dir.join(new_dir))
             {
-                eprintln!("  warning: renaming directory for {}: {}", identity, e);
+                tracing::warn!("  renaming directory for {}: {}", identity, e);
             }
         }
 
@@ -803,7 +1156,7 @@ pub fn remove_repos(
                 let parsed = parse_identity(identity)?;
                 let short_name = parsed.repo.clone();
                 if let Err(e) = fs::rename(ws_dir.join(&old_dir), ws_dir.join(&short_name)) {
-                    eprintln!("  warning: renaming directory for {}: {}", identity, e);
+                    tracing::warn!("  renaming directory for {}: {}", identity, e);
                 }
             }
         }
@@ -886,7 +1239,7 @@ pub fn propagate_mirror_to_clones(mirrors_dir: &Path, ws_dir: &Path, meta: &Meta
                         MIRROR_PROPAGATE_REFSPEC,
                         prune,
                     ) {
-                        eprintln!("  warning: propagate mirror for {}: {}", id, e);
+                        tracing::warn!("  propagate mirror for {}: {}", id, e);
                     }
                 })
             })
@@ -1003,13 +1356,30 @@ pub(crate) fn is_ignored(path: &str, patterns: &[IgnorePattern]) -> bool {
 
 /// Load wspignore patterns from both global and per-workspace files.
 /// Creates the global wspignore with defaults on first use.
-pub(crate) fn load_wspignore(data_dir: &Path, ws_dir: &Path) -> Vec<IgnorePattern> {
-    let _ = ensure_global_wspignore(data_dir);
-    let mut patterns = load_wspignore_file(&data_dir.join("wspignore"));
+pub(crate) fn load_wspignore(
+    config_dir: &Path,
+    data_dir: &Path,
+    ws_dir: &Path,
+) -> Vec<IgnorePattern> {
+    let _ = ensure_global_wspignore(config_dir, data_dir);
+    let mut patterns = load_wspignore_file(&global_wspignore_path(config_dir, data_dir));
     patterns.extend(load_wspignore_file(&ws_dir.join(".wspignore")));
     patterns
 }
 
+/// The global wspignore: in `config_dir`, or in `data_dir` where versions
+/// before the config split kept it, until it's moved. Reads only;
+/// [`ensure_global_wspignore`] does the move.
+pub(crate) fn global_wspignore_path(config_dir: &Path, data_dir: &Path) -> PathBuf {
+    let path = config_dir.join("wspignore");
+    let legacy = data_dir.join("wspignore");
+    if !path.exists() && legacy.exists() {
+        legacy
+    } else {
+        path
+    }
+}
+
 /// Filter out ignored problems from a list of root problems.
 pub(crate) fn filter_ignored(
     problems: Vec<RootProblem>,
@@ -1021,12 +1391,22 @@ pub(crate) fn filter_ignored(
         .collect()
 }
 
-/// Create the default global wspignore if it doesn't exist.
+/// Create the default global wspignore in `config_dir` if it doesn't
+/// exist, or move it there from `data_dir`.
 /// Uses O_CREAT|O_EXCL (create_new) for atomic creation — no TOCTOU race.
-pub(crate) fn ensure_global_wspignore(data_dir: &Path) -> Result<()> {
-    let path = data_dir.join("wspignore");
-    // Ensure the data dir exists (may not on first ever use)
-    fs::create_dir_all(data_dir).context("creating data directory")?;
+pub(crate) fn ensure_global_wspignore(config_dir: &Path, data_dir: &Path) -> Result<()> {
+    let path = config_dir.join("wspignore");
+    // Ensure the config dir exists (may not on first ever use)
+    fs::create_dir_all(config_dir).context("creating config directory")?;
+    let legacy = global_wspignore_path(config_dir, data_dir);
+    if legacy != path {
+        if fs::rename(&legacy, &path).is_err() {
+            // Across filesystems: copy, then drop the old one.
+            fs::copy(&legacy, &path).context("moving wspignore to the config directory")?;
+            fs::remove_file(&legacy).context("removing the old wspignore")?;
+        }
+        return Ok(());
+    }
     match fs::OpenOptions::new()
         .create_new(true)
         .write(true)
@@ -1056,14 +1436,19 @@ pub(crate) fn check_root_content(ws_dir: &Path, metadata: &Metadata) -> Result<V
     }
 
     let go_work_is_wsp = ws_dir.join("go.work").exists() && check_go_work(ws_dir).is_none();
+    let flake_is_wsp = crate::lang::nix::is_generated(&ws_dir.join("flake.nix"));
 
     for entry in fs::read_dir(ws_dir).context("reading workspace root directory")? {
         let entry = entry?;
         let name = entry.file_name();
         let name_str = name.to_string_lossy();
 
-        // Skip .wsp.yaml and its lock file
-        if name_str == METADATA_FILE || name_str == ".wsp.yaml.lock" {
+        // Skip .wsp.yaml, its backup and the lock files
+        if name_str == METADATA_FILE
+            || name_str == METADATA_BACKUP_FILE
+            || name_str == ".wsp.yaml.lock"
+            || name_str == ".wsp.lock"
+        {
             continue;
         }
 
@@ -1107,6 +1492,60 @@ pub(crate) fn check_root_content(ws_dir: &Path, metadata: &Metadata) -> Result<V
             continue;
         }
 
+        // .env — generated by the dotenv integration
+        if name_str == ".env" {
+            if let Some(problem) = check_dotenv(ws_dir) {
+                problems.push(problem);
+            }
+            continue;
+        }
+
+        // .envrc — generated by the direnv integration
+        if name_str == ".envrc" {
+            if !fs::read_to_string(entry.path())
+                .is_ok_and(|c| c.starts_with(crate::lang::DIRENV_HEADER))
+            {
+                problems.push(RootProblem {
+                    path: ".envrc".into(),
+                    kind: RootProblemKind::Untracked,
+                });
+            }
+            continue;
+        }
+
+        // .devcontainer/ — generated by the devcontainer integration
+        if name_str == ".devcontainer" {
+            problems.extend(check_devcontainer(ws_dir));
+            continue;
+        }
+
+        // .idea/ — generated by the jetbrains integration; the rest of it
+        // is IDE state
+        if name_str == ".idea" {
+            if !crate::lang::jetbrains::is_generated(&entry.path()) {
+                problems.push(RootProblem {
+                    path: ".idea/".into(),
+                    kind: RootProblemKind::Untracked,
+                });
+            }
+            continue;
+        }
+
+        // flake.nix / shell.nix — generated by the nix integration; flake.lock
+        // is safe alongside a generated flake.nix
+        if name_str == "flake.nix" || name_str == "shell.nix" {
+            if !crate::lang::nix::is_generated(&entry.path()) {
+                problems.push(RootProblem {
+                    path: name_str.to_string(),
+                    kind: RootProblemKind::Untracked,
+                });
+            }
+            continue;
+        }
+        if name_str == "flake.lock" && flake_is_wsp {
+            continue;
+        }
+
         // go.work.sum — safe when go.work is wsp-generated
         if name_str == "go.work.sum" && go_work_is_wsp {
             continue;
@@ -1201,9 +1640,13 @@ fn check_agents_md(ws_dir: &Path) -> Option<RootProblem> {
     None
 }
 
-/// Check CLAUDE.md — symlink to AGENTS.md is fine, anything else is flagged.
+/// Check CLAUDE.md — symlink to AGENTS.md (or wsp's copy of it) is fine,
+/// anything else is flagged.
 fn check_claude_md(ws_dir: &Path) -> Option<RootProblem> {
     let path = ws_dir.join("CLAUDE.md");
+    if crate::agentmd::is_claude_md_managed(ws_dir) {
+        return None;
+    }
     match fs::symlink_metadata(&path) {
         Ok(meta) => {
             if meta.file_type().is_symlink() {
@@ -1309,6 +1752,49 @@ fn check_claude_dir(ws_dir: &Path) -> Vec<RootProblem> {
     problems
 }
 
+/// Check .env — wsp-generated header means it's managed.
+pub(crate) fn check_dotenv(ws_dir: &Path) -> Option<RootProblem> {
+    let path = ws_dir.join(".env");
+    match fs::read_to_string(&path) {
+        Ok(content) if content.starts_with(crate::lang::DOTENV_HEADER) => None,
+        Ok(_) => Some(RootProblem {
+            path: ".env".into(),
+            kind: RootProblemKind::Untracked,
+        }),
+        Err(_) => Some(RootProblem {
+            path: ".env".into(),
+            kind: RootProblemKind::Modified {
+                detail: "unreadable".into(),
+            },
+        }),
+    }
+}
+
+/// Check .devcontainer/ — managed when it holds only a wsp-generated
+/// devcontainer.json.
+pub(crate) fn check_devcontainer(ws_dir: &Path) -> Vec<RootProblem> {
+    let dir = ws_dir.join(".devcontainer");
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return vec![RootProblem {
+            path: ".devcontainer/".into(),
+            kind: RootProblemKind::Untracked,
+        }];
+    };
+    let mut problems = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name == "devcontainer.json" && crate::lang::devcontainer::is_generated(&entry.path()) {
+            continue;
+        }
+        problems.push(RootProblem {
+            path: format!(".devcontainer/{}", name),
+            kind: RootProblemKind::Untracked,
+        });
+    }
+    problems.sort_by(|a, b| a.path.cmp(&b.path));
+    problems
+}
+
 /// Check go.work — wsp-generated header means it's managed.
 pub(crate) fn check_go_work(ws_dir: &Path) -> Option<RootProblem> {
     let path = ws_dir.join("go.work");
@@ -1330,104 +1816,239 @@ pub(crate) fn check_go_work(ws_dir: &Path) -> Option<RootProblem> {
     }
 }
 
-pub fn remove(paths: &Paths, name: &str, force: bool, permanent: bool) -> Result<()> {
-    let ws_dir = dir(&paths.workspaces_dir, name);
-    let meta =
-        load_metadata(&ws_dir).map_err(|e| anyhow::anyhow!("reading workspace metadata: {}", e))?;
+/// The ref a clone's workspace branch merges into: `origin/<default>` when
+/// the remote-tracking ref exists, else the local default branch.
+fn merge_target(refs: &git::Refs) -> Result<String> {
+    let Some(default_branch) = refs.default_branch() else {
+        bail!("cannot detect default branch");
+    };
+    Ok(if refs.remote_branch_exists(default_branch) {
+        format!("origin/{}", default_branch)
+    } else {
+        default_branch.to_string()
+    })
+}
 
-    if !force {
-        let mut problems: Vec<String> = Vec::new();
+/// Cheap, local-only summary of whether a workspace looks safe to remove,
+/// for annotating lists. `remove` still runs the full checks (with a fetch).
+#[derive(Debug, Default, PartialEq)]
+pub struct RemovalState {
+    /// Some repo has uncommitted changes or unpushed commits.
+    pub dirty: bool,
+    /// Every repo with the workspace branch has it merged into its default
+    /// branch (as of the last fetch).
+    pub merged: bool,
+}
 
-        for identity in meta.repos.keys() {
-            let dn = meta.dir_name(identity)?;
-            let clone_dir = ws_dir.join(&dn);
+/// What removing one clone would throw away, from local data only.
+#[derive(Debug, PartialEq)]
+pub struct RepoRemovalState {
+    pub identity: String,
+    /// Uncommitted changes (including untracked files).
+    pub changed: u32,
+    /// Commits on HEAD not on the upstream.
+    pub ahead: u32,
+    /// Whether the workspace branch is merged; `None` when the clone has no
+    /// such branch.
+    pub merged: Option<bool>,
+}
 
-            // Check for pending local changes on HEAD
-            let changed = git::changed_file_count(&clone_dir).unwrap_or(0);
-            let ahead = git::ahead_count(&clone_dir).unwrap_or(0);
-            if changed > 0 || ahead > 0 {
-                problems.push(format!("{} (pending changes)", identity));
-                continue;
-            }
+impl RepoRemovalState {
+    /// Human-readable list of what would be lost, or `None` if nothing.
+    pub fn at_risk(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.changed > 0 {
+            parts.push(format!(
+                "{} uncommitted change{}",
+                self.changed,
+                if self.changed == 1 { "" } else { "s" }
+            ));
+        }
+        if self.ahead > 0 {
+            parts.push(format!(
+                "{} unpushed commit{}",
+                self.ahead,
+                if self.ahead == 1 { "" } else { "s" }
+            ));
+        }
+        if self.merged == Some(false) {
+            parts.push("unmerged branch".into());
+        }
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
 
-            // Check if HEAD is on the wrong branch — the workspace branch may
-            // have unpushed commits that the HEAD-relative checks above missed.
-            let current = git::branch_current(&clone_dir).unwrap_or_default();
-            if current != meta.branch && git::branch_exists(&clone_dir, &meta.branch) {
-                let ws_ahead =
-                    git::commit_count(&clone_dir, &format!("origin/{}", meta.branch), &meta.branch)
-                        .or_else(|_| {
-                            // No remote tracking branch — count all commits vs default branch
-                            let default = git::default_branch(&clone_dir).unwrap_or("main".into());
-                            git::commit_count(
-                                &clone_dir,
-                                &format!("origin/{}", default),
-                                &meta.branch,
-                            )
-                        })
-                        .unwrap_or(0);
-                if ws_ahead > 0 {
-                    problems.push(format!(
-                        "{} (not on workspace branch; {} has {} unpushed commit{})",
-                        identity,
-                        meta.branch,
-                        ws_ahead,
-                        if ws_ahead == 1 { "" } else { "s" }
-                    ));
-                    continue;
-                }
-            }
+pub fn repo_removal_states(
+    ws_dir: &Path,
+    meta: &Metadata,
+    identities: &[String],
+) -> Vec<RepoRemovalState> {
+    identities
+        .iter()
+        .filter_map(|identity| {
+            let clone_dir = ws_dir.join(meta.dir_name(identity).ok()?);
+            let branch = meta.branch_for(identity);
+            let snap = git::snapshot(&clone_dir).ok();
+            let refs = snap.as_ref().map(|s| &s.refs);
+            let merged = refs.is_some_and(|r| r.branch_exists(branch)).then(|| {
+                refs.and_then(|r| merge_target(r).ok())
+                    .is_some_and(|target| {
+                        matches!(
+                            git::branch_safety(&clone_dir, branch, &target),
+                            git::BranchSafety::Merged | git::BranchSafety::SquashMerged
+                        )
+                    })
+            });
+            Some(RepoRemovalState {
+                identity: identity.clone(),
+                changed: snap.as_ref().map_or(0, |s| s.files.len() as u32),
+                ahead: snap.as_ref().map_or(0, |s| s.ahead),
+                merged,
+            })
+        })
+        .collect()
+}
 
-            let fetch_failed =
-                fetch_and_propagate(&paths.mirrors_dir, &clone_dir, identity).is_err();
-            if fetch_failed {
-                eprintln!("  warning: fetch failed for {}, using local data", identity);
-            }
+pub fn removal_state(ws_dir: &Path, meta: &Metadata) -> RemovalState {
+    let identities: Vec<String> = meta.repos.keys().cloned().collect();
+    let repos = repo_removal_states(ws_dir, meta, &identities);
+    let with_branch: Vec<bool> = repos.iter().filter_map(|r| r.merged).collect();
+    RemovalState {
+        dirty: repos.iter().any(|r| r.changed > 0 || r.ahead > 0),
+        merged: !with_branch.is_empty() && with_branch.iter().all(|m| *m),
+    }
+}
 
-            if !git::branch_exists(&clone_dir, &meta.branch) {
-                continue;
-            }
-            let default_branch = match git::default_branch_for_remote(&clone_dir, "origin") {
-                Ok(b) => b,
-                Err(_) => match git::default_branch(&clone_dir) {
-                    Ok(b) => b,
-                    Err(e) => {
-                        eprintln!(
-                            "  warning: cannot detect default branch for {}: {}",
-                            identity, e
-                        );
-                        continue;
-                    }
-                },
-            };
-            let merge_target = format!("origin/{}", default_branch);
-            let target = if git::ref_exists(&clone_dir, &merge_target) {
-                merge_target
-            } else {
-                default_branch
-            };
-            match git::branch_safety(&clone_dir, &meta.branch, &target) {
-                git::BranchSafety::Merged | git::BranchSafety::SquashMerged => {}
-                git::BranchSafety::PushedToRemote => {
-                    let mut msg = format!("{} (unmerged branch, but pushed to remote)", identity);
-                    if fetch_failed {
-                        msg.push_str(" (fetch failed, local data may be stale)");
-                    }
-                    problems.push(msg);
-                }
-                git::BranchSafety::Unmerged => {
-                    let mut msg = format!("{} (unmerged branch)", identity);
-                    if fetch_failed {
-                        msg.push_str(" (fetch failed, local data may be stale)");
-                    }
-                    problems.push(msg);
-                }
+/// Why removing `identity`'s clone would lose work, if it would: pending
+/// changes, unpushed commits on a workspace branch that isn't checked out,
+/// or a branch that isn't merged.
+fn unsaved_work(
+    mirrors_dir: &Path,
+    meta: &Metadata,
+    identity: &str,
+    clone_dir: &Path,
+    fetch: FetchPolicy,
+) -> Option<String> {
+    // Check for pending local changes on HEAD
+    let snap = git::snapshot(clone_dir).ok();
+    if snap
+        .as_ref()
+        .is_some_and(|s| !s.files.is_empty() || s.ahead > 0)
+    {
+        return Some(format!("{} (pending changes)", identity));
+    }
+
+    // Check if HEAD is on the wrong branch — the workspace branch may
+    // have unpushed commits that the HEAD-relative checks above missed.
+    let branch = meta.branch_for(identity);
+    if let Some(snap) = &snap
+        && snap.branch.as_deref() != Some(branch)
+        && snap.refs.branch_exists(branch)
+    {
+        // Without a remote tracking branch, count commits vs the
+        // default branch.
+        let base = if snap.refs.remote_branch_exists(branch) {
+            branch
+        } else {
+            snap.refs.default_branch().unwrap_or("main")
+        };
+        let ws_ahead =
+            git::commit_count(clone_dir, &format!("origin/{}", base), branch).unwrap_or(0);
+        if ws_ahead > 0 {
+            return Some(format!(
+                "{} (not on workspace branch; {} has {} unpushed commit{})",
+                identity,
+                branch,
+                ws_ahead,
+                if ws_ahead == 1 { "" } else { "s" }
+            ));
+        }
+    }
+
+    let stale = refresh_for_merge_check(mirrors_dir, clone_dir, identity, fetch);
+
+    // The fetch may have moved refs; look again.
+    let refs = git::refs(clone_dir).unwrap_or_default();
+    if !refs.branch_exists(branch) {
+        return None;
+    }
+    let target = match merge_target(&refs) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!(
+                "  warning: cannot detect default branch for {}: {}",
+                identity, e
+            );
+            return None;
+        }
+    };
+    match git::branch_safety(clone_dir, branch, &target) {
+        git::BranchSafety::Merged | git::BranchSafety::SquashMerged => None,
+        git::BranchSafety::PushedToRemote => {
+            let mut msg = format!("{} (unmerged branch, but pushed to remote)", identity);
+            if let Some(why) = stale {
+                msg.push_str(&format!(" ({}, local data may be stale)", why));
+            }
+            Some(msg)
+        }
+        git::BranchSafety::Unmerged => {
+            let mut msg = format!("{} (unmerged branch)", identity);
+            if let Some(why) = stale {
+                msg.push_str(&format!(" ({}, local data may be stale)", why));
             }
+            Some(msg)
         }
+    }
+}
+
+/// [`unsaved_work`] for each of `identities`, `jobs` at a time since each
+/// check may fetch. The problems found come back in the order of
+/// `identities`.
+fn unsaved_work_all(
+    mirrors_dir: &Path,
+    ws_dir: &Path,
+    meta: &Metadata,
+    identities: &[&String],
+    fetch: FetchPolicy,
+    jobs: usize,
+) -> Result<Vec<String>> {
+    // Directory names first, in order, so a bad one fails before any
+    // fetching starts.
+    let clones = identities
+        .iter()
+        .map(|id| Ok((*id, ws_dir.join(meta.dir_name(id)?))))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(crate::util::run_bounded(
+        &clones,
+        jobs,
+        |(identity, clone_dir)| unsaved_work(mirrors_dir, meta, identity, clone_dir, fetch),
+        None,
+    )
+    .into_iter()
+    .flatten()
+    .collect())
+}
+
+pub fn remove(
+    paths: &Paths,
+    name: &str,
+    force: bool,
+    permanent: bool,
+    fetch: FetchPolicy,
+) -> Result<()> {
+    let ws_dir = paths.workspace_dir(name);
+    let _lock = filelock::lock_workspace(&ws_dir)?;
+    let meta =
+        load_metadata(&ws_dir).map_err(|e| error::prefixed(e, "reading workspace metadata"))?;
+
+    let cfg = Config::load_from(&paths.config_path)?;
+    if !force {
+        let ids: Vec<&String> = meta.repos.keys().collect();
+        let jobs = crate::cli::fetch::Limits::from_config(&cfg).jobs;
+        let mut problems = unsaved_work_all(&paths.mirrors_dir, &ws_dir, &meta, &ids, fetch, jobs)?;
 
         // Check workspace root for user content
-        let ignore_patterns =
-            load_wspignore(paths.config_path.parent().unwrap_or(Path::new("")), &ws_dir);
+        let ignore_patterns = load_wspignore(paths.config_dir(), paths.data_dir(), &ws_dir);
         match check_root_content(&ws_dir, &meta) {
             Ok(raw_problems) => {
                 let root_problems = filter_ignored(raw_problems.clone(), &ignore_patterns);
@@ -1453,7 +2074,7 @@ pub fn remove(paths: &Paths, name: &str, force: bool, permanent: bool) -> Result
                 }
             }
             Err(e) => {
-                eprintln!("  warning: root content check failed: {}", e);
+                tracing::warn!("  root content check failed: {}", e);
             }
         }
 
@@ -1464,16 +2085,38 @@ pub fn remove(paths: &Paths, name: &str, force: bool, permanent: bool) -> Result
             for p in &sorted {
                 list.push_str(&format!("\n  - {}", p));
             }
-            bail!(
-                "workspace {:?} has unsaved work ({}):{}\n\nUse --force to remove anyway",
-                name,
-                meta.branch,
-                list
-            );
+            return Err(error::tag(
+                ErrorKind::Conflict,
+                anyhow::anyhow!(
+                    "workspace {:?} has unsaved work ({}):{}\n\nUse --force to remove anyway",
+                    name,
+                    meta.branch,
+                    list
+                ),
+            ));
         }
     }
 
+    // The hook runs once removal is known to be safe, under the same lock,
+    // so it sees exactly the workspace that is about to go.
+    let identities: Vec<String> = meta.repos.keys().cloned().collect();
+    crate::hooks::run(
+        crate::hooks::Event::PreRemove,
+        cfg.hooks.as_ref(),
+        &ws_dir,
+        &meta,
+        &identities,
+    )?;
+
     if permanent {
+        // Clones are most of the tree, so they go side by side first;
+        // whatever fails is left for the final pass to report.
+        std::thread::scope(|s| {
+            for dn in meta.repos.keys().filter_map(|id| meta.dir_name(id).ok()) {
+                let clone_dir = ws_dir.join(dn);
+                s.spawn(move || fs::remove_dir_all(clone_dir));
+            }
+        });
         fs::remove_dir_all(&ws_dir)?;
     } else {
         crate::gc::move_to_gc(paths, name, &meta.branch)?;
@@ -1491,49 +2134,72 @@ pub struct RenameRepoResult {
     pub error: Option<String>,
 }
 
+/// Derives a repo's branch after a workspace rename by replacing old_name
+/// with new_name. The default template gives "<prefix>/<name>" or just
+/// "<name>"; templates like "{workspace}-{date}" put the name first.
+fn renamed_branch(branch: &str, old_name: &str, new_name: &str) -> String {
+    if let Some(prefix) = branch.strip_suffix(old_name) {
+        // prefix includes the trailing "/" if present
+        format!("{}{}", prefix, new_name)
+    } else if let Some(suffix) = branch.strip_prefix(old_name) {
+        format!("{}{}", new_name, suffix)
+    } else {
+        // Branch was manually set or doesn't match the name pattern — just use new_name
+        new_name.to_string()
+    }
+}
+
 /// Rename a workspace: directory, metadata, and git branches in active repos.
 pub fn rename(paths: &Paths, old_name: &str, new_name: &str) -> Result<Vec<RenameRepoResult>> {
     validate_name(old_name)?;
     validate_name(new_name)?;
 
-    let old_dir = dir(&paths.workspaces_dir, old_name);
+    let old_dir = paths.workspace_dir(old_name);
     if !old_dir.exists() {
-        bail!("workspace {:?} does not exist", old_name);
+        return Err(error::tag(
+            ErrorKind::NotFound,
+            anyhow::anyhow!("workspace {:?} does not exist", old_name),
+        ));
     }
-    let new_dir = dir(&paths.workspaces_dir, new_name);
-    if new_dir.exists() {
-        bail!("workspace {:?} already exists", new_name);
+    // Renamed in place, so it stays in its workspaces dir.
+    let new_dir = old_dir.with_file_name(new_name);
+    if new_dir.exists() || paths.workspace_dir(new_name).exists() {
+        return Err(error::tag(
+            ErrorKind::Conflict,
+            anyhow::anyhow!("workspace {:?} already exists", new_name),
+        ));
     }
+    let _lock = filelock::lock_workspace(&old_dir)?;
 
-    let meta = load_metadata(&old_dir)
-        .map_err(|e| anyhow::anyhow!("reading workspace metadata: {}", e))?;
-
-    // Derive the new branch name by replacing old_name with new_name in the branch.
-    // Branch format is either "<prefix>/<name>" or just "<name>".
-    let new_branch = if let Some(prefix) = meta.branch.strip_suffix(old_name) {
-        // prefix includes the trailing "/" if present
-        format!("{}{}", prefix, new_name)
-    } else {
-        // Branch was manually set or doesn't match the name pattern — just use new_name
-        new_name.to_string()
-    };
+    let meta =
+        load_metadata(&old_dir).map_err(|e| error::prefixed(e, "reading workspace metadata"))?;
 
+    let new_branch = renamed_branch(&meta.branch, old_name, new_name);
     git::validate_branch_name(&new_branch)?;
+    let new_branches: BTreeMap<String, String> = meta
+        .branches
+        .iter()
+        .map(|(id, b)| (id.clone(), renamed_branch(b, old_name, new_name)))
+        .collect();
+    for branch in new_branches.values() {
+        git::validate_branch_name(branch)?;
+    }
 
-    let old_branch = meta.branch.clone();
     let mut results = Vec::new();
 
     // Rename branches in all repos
     for identity in meta.repos.keys() {
         let dn = meta.dir_name(identity)?;
         let clone_dir = old_dir.join(&dn);
+        let old_branch = meta.branch_for(identity).to_string();
+        let new_branch = new_branches.get(identity).unwrap_or(&new_branch).clone();
 
         match git::branch_rename(&clone_dir, &old_branch, &new_branch) {
             Ok(()) => {
                 results.push(RenameRepoResult {
                     name: dn,
-                    old_branch: old_branch.clone(),
-                    new_branch: new_branch.clone(),
+                    old_branch,
+                    new_branch,
                     ok: true,
 
                     error: None,
@@ -1542,8 +2208,8 @@ pub fn rename(paths: &Paths, old_name: &str, new_name: &str) -> Result<Vec<Renam
             Err(e) => {
                 results.push(RenameRepoResult {
                     name: dn,
-                    old_branch: old_branch.clone(),
-                    new_branch: new_branch.clone(),
+                    old_branch,
+                    new_branch,
                     ok: false,
 
                     error: Some(e.to_string()),
@@ -1557,8 +2223,8 @@ pub fn rename(paths: &Paths, old_name: &str, new_name: &str) -> Result<Vec<Renam
     if !failures.is_empty() {
         for r in results.iter().filter(|r| r.ok) {
             let clone_dir = old_dir.join(&r.name);
-            if let Err(e) = git::branch_rename(&clone_dir, &new_branch, &old_branch) {
-                eprintln!("  warning: rollback failed for {}: {}", r.name, e);
+            if let Err(e) = git::branch_rename(&clone_dir, &r.new_branch, &r.old_branch) {
+                tracing::warn!("  rollback failed for {}: {}", r.name, e);
             }
         }
         let msgs: Vec<String> = failures
@@ -1580,10 +2246,10 @@ pub fn rename(paths: &Paths, old_name: &str, new_name: &str) -> Result<Vec<Renam
 
     // Update metadata under lock to prevent concurrent mutation data loss
     let new_name_owned = new_name.to_string();
-    let new_branch_clone = new_branch.clone();
     let meta = crate::filelock::with_metadata(&old_dir, |meta| {
         meta.name = new_name_owned;
-        meta.branch = new_branch_clone;
+        meta.branch = new_branch;
+        meta.branches = new_branches;
         Ok(())
     })?;
 
@@ -1592,7 +2258,7 @@ pub fn rename(paths: &Paths, old_name: &str, new_name: &str) -> Result<Vec<Renam
 
     // Regenerate AGENTS.md with updated metadata
     if let Err(e) = crate::agentmd::update(&new_dir, &meta) {
-        eprintln!("  warning: failed to update AGENTS.md: {}", e);
+        tracing::warn!("  failed to update AGENTS.md: {}", e);
     }
 
     // Re-run language integrations (go.work, etc.)
@@ -1602,7 +2268,18 @@ pub fn rename(paths: &Paths, old_name: &str, new_name: &str) -> Result<Vec<Renam
     Ok(results)
 }
 
-pub fn list_all(workspaces_dir: &Path) -> Result<Vec<String>> {
+/// Names of the workspaces in every workspaces dir, sorted.
+pub fn list_all(paths: &Paths) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for root in paths.workspace_roots() {
+        names.extend(list_dir(root)?);
+    }
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+fn list_dir(workspaces_dir: &Path) -> Result<Vec<String>> {
     if !workspaces_dir.exists() {
         return Ok(Vec::new());
     }
@@ -1620,7 +2297,6 @@ pub fn list_all(workspaces_dir: &Path) -> Result<Vec<String>> {
             names.push(name.to_string());
         }
     }
-    names.sort();
     Ok(names)
 }
 
@@ -1746,6 +2422,59 @@ pub fn apply_git_config(
     }
 }
 
+/// Add multi-valued git config entries, such as
+/// [URL rewrites](Config::url_rewrite_git_config), to each clone, keeping
+/// the values already there. If `only` is Some, only apply to the listed
+/// identities.
+pub fn add_git_config(
+    ws_dir: &Path,
+    meta: &Metadata,
+    entries: &[(String, String)],
+    only: Option<&[String]>,
+) {
+    if entries.is_empty() {
+        return;
+    }
+    for identity in meta.repos.keys() {
+        if only.is_some_and(|filter| !filter.contains(identity)) {
+            continue;
+        }
+        let Ok(dir_name) = meta.dir_name(identity) else {
+            continue;
+        };
+        let repo_dir = ws_dir.join(&dir_name);
+        if !repo_dir.join(".git").exists() {
+            continue;
+        }
+        for (key, value) in entries {
+            if let Err(e) = git::add_config(&repo_dir, key, value) {
+                eprintln!(
+                    "  warning: git config --add {} {} failed for {}: {}",
+                    key, value, dir_name, e
+                );
+            }
+        }
+    }
+}
+
+/// Set each clone's commit author from the `committer` entry matching its
+/// repo. If `only` is Some, only apply to the listed identities.
+pub fn apply_committers(ws_dir: &Path, meta: &Metadata, cfg: &Config, only: Option<&[String]>) {
+    for identity in meta.repos.keys() {
+        if only.is_some_and(|filter| !filter.contains(identity)) {
+            continue;
+        }
+        if let Some(committer) = cfg.committer_for(identity) {
+            apply_git_config(
+                ws_dir,
+                meta,
+                &committer.git_config(),
+                Some(std::slice::from_ref(identity)),
+            );
+        }
+    }
+}
+
 fn parse_identity(identity: &str) -> Result<giturl::Parsed> {
     giturl::Parsed::from_identity(identity)
 }
@@ -1755,6 +2484,36 @@ mod tests {
     use super::*;
     use std::process::Command;
 
+    /// [`super::create`] on the default branch for `branch_prefix`, as
+    /// `wsp new` does without a branch-template.
+    fn create(
+        paths: &Paths,
+        name: &str,
+        repo_refs: &BTreeMap<String, String>,
+        branch_prefix: Option<&str>,
+        upstream_urls: &BTreeMap<String, String>,
+        description: Option<&str>,
+        created_from: Option<&str>,
+    ) -> Result<()> {
+        let branches = Branches {
+            default: branch_name(None, branch_prefix, name)?,
+            per_repo: BTreeMap::new(),
+        };
+        super::create(
+            paths,
+            name,
+            repo_refs,
+            &branches,
+            upstream_urls,
+            &CreateOpts {
+                description,
+                created_from,
+                ..Default::default()
+            },
+        )
+        .map(|_| ())
+    }
+
     /// Sets up a test environment using tempdirs.
     /// Returns Paths, TempDirs (keep alive!), identity, and upstream URL map.
     fn setup_test_env() -> (
@@ -1876,6 +2635,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_create_branches_from_default_even_if_on_origin() {
+        let (paths, _d, repo_dir, identity, upstream_urls) = setup_test_env();
+        git::run(Some(repo_dir.path()), &["branch", "taken"]).unwrap();
+        git::run(
+            Some(repo_dir.path()),
+            &["commit", "--allow-empty", "-m", "only on main"],
+        )
+        .unwrap();
+        let parsed = parse_identity(&identity).unwrap();
+        mirror::fetch(&paths.mirrors_dir, &parsed).unwrap();
+
+        let refs = BTreeMap::from([(identity, String::new())]);
+        create(&paths, "taken", &refs, None, &upstream_urls, None, None).unwrap();
+
+        // Only `wsp setup restore` picks up a branch from origin.
+        let clone_dir = dir(&paths.workspaces_dir, "taken").join("test-repo");
+        assert!(
+            git::run(
+                Some(&clone_dir),
+                &["rev-parse", "--abbrev-ref", "@{upstream}"]
+            )
+            .is_err()
+        );
+        let log = git::run(Some(&clone_dir), &["log", "--format=%s"]).unwrap();
+        assert!(log.contains("only on main"), "{}", log);
+    }
+
     #[test]
     fn test_default_branch_tracks_origin_not_mirror() {
         let (paths, _d, _r, identity, upstream_urls) = setup_test_env();
@@ -2116,7 +2903,7 @@ mod tests {
         assert!(ws_dir.exists());
 
         // Branch was created from main with no extra commits, so it's merged
-        remove(&paths, "rm-merged", false, true).unwrap();
+        remove(&paths, "rm-merged", false, true, FetchPolicy::Fetch).unwrap();
         assert!(!ws_dir.exists());
     }
 
@@ -2150,7 +2937,7 @@ mod tests {
         }
 
         // Fetch to update mirror
-        git::fetch(&mirror_dir, true).unwrap();
+        git::fetch(&mirror_dir, true, None, &|_| {}).unwrap();
 
         // Create workspace
         let refs = BTreeMap::from([(identity.clone(), String::new())]);
@@ -2169,7 +2956,7 @@ mod tests {
         assert!(ws_dir.exists());
 
         // Remove should succeed — the workspace branch has no extra commits
-        remove(&paths, "rm-origin-ahead", false, true).unwrap();
+        remove(&paths, "rm-origin-ahead", false, true, FetchPolicy::Fetch).unwrap();
         assert!(!ws_dir.exists());
     }
 
@@ -2213,9 +3000,11 @@ mod tests {
             );
         }
 
-        let result = remove(&paths, "rm-unmerged", false, true);
+        let result = remove(&paths, "rm-unmerged", false, true, FetchPolicy::Fetch);
         assert!(result.is_err());
-        let err = result.unwrap_err().to_string();
+        let err = result.unwrap_err();
+        assert_eq!(error::classify(&err), ErrorKind::Conflict);
+        let err = err.to_string();
         assert!(
             err.contains("unsaved work"),
             "expected 'unsaved work' in error: {}",
@@ -2226,6 +3015,33 @@ mod tests {
         assert!(ws_dir.exists());
     }
 
+    #[test]
+    fn test_removal_state() {
+        let (paths, _d, _r, identity, upstream_urls) = setup_test_env();
+
+        let refs = BTreeMap::from([(identity.clone(), String::new())]);
+        create(&paths, "rm-state", &refs, None, &upstream_urls, None, None).unwrap();
+        let ws_dir = dir(&paths.workspaces_dir, "rm-state");
+        let meta = load_metadata(&ws_dir).unwrap();
+
+        assert_eq!(
+            removal_state(&ws_dir, &meta),
+            RemovalState {
+                dirty: false,
+                merged: true
+            }
+        );
+
+        fs::write(ws_dir.join("test-repo").join("scratch.txt"), "wip").unwrap();
+        assert_eq!(
+            removal_state(&ws_dir, &meta),
+            RemovalState {
+                dirty: true,
+                merged: true
+            }
+        );
+    }
+
     #[test]
     fn test_remove_force_deletes_unmerged() {
         let (paths, _d, _r, identity, upstream_urls) = setup_test_env();
@@ -2258,7 +3074,7 @@ mod tests {
         }
 
         // Force remove should succeed despite unmerged branch
-        remove(&paths, "rm-force", true, true).unwrap();
+        remove(&paths, "rm-force", true, true, FetchPolicy::Fetch).unwrap();
         assert!(!ws_dir.exists());
     }
 
@@ -2273,7 +3089,7 @@ mod tests {
         let repo_dir = ws_dir.join("test-repo");
         fs::write(repo_dir.join("dirty.txt"), "x").unwrap();
 
-        let result = remove(&paths, "rm-dirty", false, true);
+        let result = remove(&paths, "rm-dirty", false, true, FetchPolicy::Fetch);
         assert!(result.is_err());
         let err = result.unwrap_err().to_string();
         assert!(
@@ -2284,19 +3100,91 @@ mod tests {
         assert!(ws_dir.exists());
     }
 
+    #[test]
+    fn test_remove_runs_pre_remove_hook_after_safety_check() {
+        let (paths, _d, _r, identity, upstream_urls) = setup_test_env();
+        let marker_dir = tempfile::tempdir().unwrap();
+        let marker = marker_dir.path().join("ran");
+        let cfg = Config {
+            hooks: Some(BTreeMap::from([(
+                "pre-remove".to_string(),
+                crate::hooks::HookDef {
+                    run: format!(
+                        "touch {}",
+                        crate::util::shell_quote(&marker.to_string_lossy())
+                    ),
+                    ..Default::default()
+                },
+            )])),
+            ..Default::default()
+        };
+        cfg.save_to(&paths.config_path).unwrap();
+
+        let refs = BTreeMap::from([(identity, String::new())]);
+        create(&paths, "rm-hook", &refs, None, &upstream_urls, None, None).unwrap();
+        let ws_dir = dir(&paths.workspaces_dir, "rm-hook");
+        let dirty = ws_dir.join("test-repo").join("dirty.txt");
+        fs::write(&dirty, "x").unwrap();
+
+        assert!(remove(&paths, "rm-hook", false, true, FetchPolicy::Fetch).is_err());
+        assert!(!marker.exists(), "hook ran although removal was blocked");
+
+        fs::remove_file(&dirty).unwrap();
+        remove(&paths, "rm-hook", false, true, FetchPolicy::Fetch).unwrap();
+        assert!(marker.exists(), "hook didn't run");
+        assert!(!ws_dir.exists());
+    }
+
+    #[test]
+    fn test_remove_checks_every_repo() {
+        let (paths, _d, source_repo, identity1, mut upstream_urls) = setup_test_env();
+        let (identity2, urls2) = add_mirror_with_owner(
+            &paths,
+            source_repo.path(),
+            "test.local",
+            "other",
+            "test-repo",
+        );
+        upstream_urls.extend(urls2);
+        let refs = BTreeMap::from([
+            (identity1.clone(), String::new()),
+            (identity2.clone(), String::new()),
+        ]);
+        create(&paths, "rm-many", &refs, None, &upstream_urls, None, None).unwrap();
+        let ws_dir = dir(&paths.workspaces_dir, "rm-many");
+        for dn in ["user-test-repo", "other-test-repo"] {
+            fs::write(ws_dir.join(dn).join("dirty.txt"), "x").unwrap();
+        }
+
+        let err = remove(&paths, "rm-many", false, true, FetchPolicy::Fetch)
+            .unwrap_err()
+            .to_string();
+        let want = format!(
+            "\n  - {} (pending changes)\n  - {} (pending changes)",
+            identity2, identity1
+        );
+        assert!(err.contains(&want), "{}", err);
+
+        for dn in ["user-test-repo", "other-test-repo"] {
+            fs::remove_file(ws_dir.join(dn).join("dirty.txt")).unwrap();
+        }
+        remove(&paths, "rm-many", false, true, FetchPolicy::Fetch).unwrap();
+        assert!(!ws_dir.exists());
+    }
+
     #[test]
     fn test_list_all() {
         let (paths, _d, _r, identity, upstream_urls) = setup_test_env();
 
         // Initially empty
-        let names = list_all(&paths.workspaces_dir).unwrap();
+        let names = list_all(&paths).unwrap();
         assert!(names.is_empty());
 
         // Create a workspace
         let refs = BTreeMap::from([(identity, String::new())]);
         create(&paths, "ws-1-list", &refs, None, &upstream_urls, None, None).unwrap();
 
-        let names = list_all(&paths.workspaces_dir).unwrap();
+        let names = list_all(&paths).unwrap();
         assert_eq!(names, vec!["ws-1-list"]);
     }
 
@@ -2316,7 +3204,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: BTreeMap::new(),
         };
 
         save_metadata(tmp.path(), &meta).unwrap();
@@ -2330,6 +3222,74 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_metadata_settings_override_config() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(
+            tmp.path().join(METADATA_FILE),
+            "name: hotfix\nbranch: hotfix\nrepos: {}\ncreated: 2026-01-01T00:00:00Z\n\
+             settings:\n  branch_prefix: hotfix\n  agent_md: false\n  run_jobs: 1\n",
+        )
+        .unwrap();
+        let meta = load_metadata(tmp.path()).unwrap();
+        let cfg = crate::config::Config {
+            branch_prefix: Some("jg".into()),
+            sync_strategy: Some("merge".into()),
+            run_jobs: Some(8),
+            ..Default::default()
+        };
+
+        let effective = meta.apply_workspace_config(&cfg);
+        assert_eq!(effective.branch_prefix.as_deref(), Some("hotfix"));
+        assert_eq!(effective.agent_md, Some(false));
+        assert_eq!(effective.run_jobs, Some(1));
+        assert_eq!(effective.sync_strategy.as_deref(), Some("merge"));
+    }
+
+    #[test]
+    fn test_save_metadata_keeps_backup() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut meta = Metadata {
+            version: CURRENT_METADATA_VERSION,
+            name: "my-ws".into(),
+            branch: "my-ws".into(),
+            repos: BTreeMap::new(),
+            created: Utc::now(),
+            description: None,
+            last_used: None,
+            created_from: None,
+            dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
+            config: None,
+            pending: BTreeMap::new(),
+        };
+
+        save_metadata(tmp.path(), &meta).unwrap();
+        assert!(!tmp.path().join(METADATA_BACKUP_FILE).exists());
+
+        meta.description = Some("second".into());
+        save_metadata(tmp.path(), &meta).unwrap();
+        let backup = fs::read_to_string(tmp.path().join(METADATA_BACKUP_FILE)).unwrap();
+        assert!(!backup.contains("second"), "{}", backup);
+        assert_eq!(
+            load_metadata(tmp.path()).unwrap().description.as_deref(),
+            Some("second")
+        );
+
+        // No temp files are left behind.
+        let names: Vec<String> = fs::read_dir(tmp.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names.len(), 2, "{:?}", names);
+
+        fs::write(tmp.path().join(METADATA_FILE), "name: [trunc").unwrap();
+        let err = load_metadata(tmp.path()).unwrap_err().to_string();
+        assert!(err.contains(METADATA_BACKUP_FILE), "{}", err);
+    }
+
     #[test]
     fn test_save_and_load_metadata_round_trip_with_refs() {
         let tmp = tempfile::tempdir().unwrap();
@@ -2359,7 +3319,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: BTreeMap::new(),
         };
 
         save_metadata(tmp.path(), &meta).unwrap();
@@ -2397,7 +3361,11 @@ mod tests {
             last_used: None,
             created_from: Some("backend".into()),
             dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: BTreeMap::new(),
         };
 
         save_metadata(tmp.path(), &meta).unwrap();
@@ -2432,6 +3400,10 @@ mod tests {
             ("semicolon", "a;b", true),
             ("pipe", "a|b", true),
             ("ampersand", "a&b", true),
+            ("windows device", "con", true),
+            ("windows device with extension", "NUL.txt", true),
+            ("trailing dot", "fix.", true),
+            ("device prefix", "console", false),
         ];
         for (name, input, want_err) in cases {
             let result = validate_name(input);
@@ -2443,6 +3415,120 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_render_branch() {
+        let cases = vec![
+            (
+                "default",
+                "{prefix}/{workspace}",
+                "jg",
+                "my-ws",
+                Some("jg/my-ws"),
+            ),
+            (
+                "default no prefix",
+                "{prefix}/{workspace}",
+                "",
+                "my-ws",
+                Some("my-ws"),
+            ),
+            (
+                "user",
+                "{prefix}/{user}/{workspace}",
+                "feat",
+                "my-ws",
+                Some("feat/alice/my-ws"),
+            ),
+            (
+                "date",
+                "{workspace}-{date}",
+                "",
+                "my-ws",
+                Some("my-ws-2026-03-04"),
+            ),
+            (
+                "literal",
+                "wip/{workspace}",
+                "jg",
+                "my-ws",
+                Some("wip/my-ws"),
+            ),
+            (
+                "prefix with slash",
+                "{prefix}/{workspace}",
+                "feat/",
+                "x",
+                Some("feat/x"),
+            ),
+            ("unknown placeholder", "{team}/{workspace}", "", "x", None),
+            ("unclosed", "{prefix/{workspace}", "", "x", None),
+            ("invalid ref", "{workspace}..{date}", "", "x", None),
+            (
+                "invalid ref from prefix",
+                "{prefix}/{workspace}",
+                "a b",
+                "x",
+                None,
+            ),
+        ];
+        for (name, template, prefix, ws, want) in cases {
+            let got = render_branch(template, prefix, Some("alice"), ws, "2026-03-04");
+            match want {
+                Some(want) => assert_eq!(got.unwrap(), want, "{}", name),
+                None => assert!(got.is_err(), "{}: expected error, got {:?}", name, got),
+            }
+        }
+
+        let err = render_branch("{user}/{workspace}", "", None, "x", "2026-03-04").unwrap_err();
+        assert!(err.to_string().contains("$USER"), "{}", err);
+    }
+
+    #[test]
+    fn test_branches_plan() {
+        let cfg = Config {
+            branch_prefix: Some("jg".into()),
+            branch_prefixes: Some(BTreeMap::from([
+                ("github.com/oss-org/*".to_string(), "feature".to_string()),
+                ("github.com/acme/*".to_string(), "jg".to_string()),
+            ])),
+            ..Default::default()
+        };
+        let identities: Vec<String> = vec![
+            "github.com/acme/api".into(),
+            "github.com/oss-org/lib".into(),
+            "gitlab.com/team/web".into(),
+        ];
+        let branches = Branches::plan(&cfg, "my-ws", &identities).unwrap();
+        assert_eq!(branches.default, "jg/my-ws");
+        assert_eq!(
+            branches.per_repo,
+            BTreeMap::from([(
+                "github.com/oss-org/lib".to_string(),
+                "feature/my-ws".to_string()
+            )])
+        );
+    }
+
+    #[test]
+    fn test_validate_branch_template() {
+        let cases = vec![
+            ("{prefix}/{workspace}", true),
+            ("{prefix}/{user}/{workspace}", true),
+            ("{workspace}-{date}", true),
+            ("{prefix}/{date}", false),
+            ("{workspace}/{branch}", false),
+            ("{workspace}.lock", false),
+        ];
+        for (template, want_ok) in cases {
+            assert_eq!(
+                validate_branch_template(template).is_ok(),
+                want_ok,
+                "{}",
+                template
+            );
+        }
+    }
+
     #[test]
     fn test_validate_dir_name() {
         let cases = vec![
@@ -2457,6 +3543,7 @@ mod tests {
             ("contains dotdot", "foo..bar", true),
             ("path traversal prefix", "../etc", true),
             ("absolute path", "/etc/passwd", true),
+            ("windows device", "aux", true),
         ];
         for (name, input, want_err) in cases {
             let result = validate_dir_name(input);
@@ -2468,6 +3555,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_validate_exec_dir() {
+        let cases = vec![
+            ("single component", "web", false),
+            ("nested", "services/api", false),
+            ("leading dot component", "./services/api", false),
+            ("empty", "", true),
+            ("null byte", "bad\0dir", true),
+            ("absolute path", "/etc", true),
+            ("parent traversal", "../other-repo", true),
+            ("embedded traversal", "services/../../x", true),
+        ];
+        for (name, input, want_err) in cases {
+            let result = validate_exec_dir(input);
+            if want_err {
+                assert!(result.is_err(), "{}: expected error", name);
+            } else {
+                assert!(result.is_ok(), "{}: unexpected error: {:?}", name, result);
+            }
+        }
+    }
+
     #[test]
     fn test_load_metadata_rejects_traversal_in_dirs() {
         let cases = vec![
@@ -2531,7 +3640,14 @@ mod tests {
         let ws_dir = dir(&paths.workspaces_dir, "add-ws");
 
         // Try adding the same repo again — should skip
-        add_repos(&paths.mirrors_dir, &ws_dir, &refs, &upstream_urls).unwrap();
+        add_repos(
+            &paths.mirrors_dir,
+            &ws_dir,
+            &refs,
+            &upstream_urls,
+            &BTreeMap::new(),
+        )
+        .unwrap();
 
         let meta = load_metadata(&ws_dir).unwrap();
         assert_eq!(meta.repos.len(), 1);
@@ -2566,7 +3682,14 @@ mod tests {
         upstream_urls.extend(urls2);
 
         let add_refs = BTreeMap::from([(identity2, String::new())]);
-        add_repos(&paths.mirrors_dir, &ws_dir, &add_refs, &upstream_urls).unwrap();
+        add_repos(
+            &paths.mirrors_dir,
+            &ws_dir,
+            &add_refs,
+            &upstream_urls,
+            &BTreeMap::new(),
+        )
+        .unwrap();
 
         let clone_dir = ws_dir.join("added-repo");
         let result = git::run(Some(&clone_dir), &["rev-parse", "--verify", "@{upstream}"]);
@@ -2650,7 +3773,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: BTreeMap::from([("github.com/acme/utils".into(), "acme-utils".into())]),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: BTreeMap::new(),
         };
         assert_eq!(
             meta.dir_name("github.com/acme/utils").unwrap(),
@@ -2658,6 +3785,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_resolve_repo() {
+        let meta = Metadata {
+            version: CURRENT_METADATA_VERSION,
+            name: "test".into(),
+            branch: "test".into(),
+            repos: BTreeMap::from([
+                ("github.com/acme/utils".into(), None),
+                ("github.com/other/utils".into(), None),
+                ("github.com/acme/api-gateway".into(), None),
+            ]),
+            created: Utc::now(),
+            description: None,
+            last_used: None,
+            created_from: None,
+            dirs: BTreeMap::from([
+                ("github.com/acme/utils".into(), "acme-utils".into()),
+                ("github.com/other/utils".into(), "other-utils".into()),
+            ]),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
+            config: None,
+            pending: BTreeMap::new(),
+        };
+        let cases = vec![
+            ("api-gateway", Some("github.com/acme/api-gateway")),
+            ("acme-utils", Some("github.com/acme/utils")),
+            ("other/utils", Some("github.com/other/utils")),
+            ("github.com/acme/utils", Some("github.com/acme/utils")),
+            ("utils", None),
+            ("web", None),
+        ];
+        for (name, want) in cases {
+            assert_eq!(meta.resolve_repo(name).ok().as_deref(), want, "{}", name);
+        }
+    }
+
     #[test]
     fn test_dir_name_without_override() {
         let meta = Metadata {
@@ -2670,7 +3835,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: BTreeMap::new(),
         };
         assert_eq!(meta.dir_name("github.com/acme/utils").unwrap(), "utils");
     }
@@ -2751,7 +3920,14 @@ mod tests {
             "test-repo",
         );
         let new_refs = BTreeMap::from([(identity2.clone(), String::new())]);
-        add_repos(&paths.mirrors_dir, &ws_dir, &new_refs, &urls2).unwrap();
+        add_repos(
+            &paths.mirrors_dir,
+            &ws_dir,
+            &new_refs,
+            &urls2,
+            &BTreeMap::new(),
+        )
+        .unwrap();
 
         let meta = load_metadata(&ws_dir).unwrap();
         assert_eq!(meta.dir_name(&identity1).unwrap(), "user-test-repo");
@@ -2795,7 +3971,14 @@ mod tests {
         ]);
         let mut all_urls = upstream_urls.clone();
         all_urls.extend(urls2);
-        add_repos(&paths.mirrors_dir, &ws_dir, &new_refs, &all_urls).unwrap();
+        add_repos(
+            &paths.mirrors_dir,
+            &ws_dir,
+            &new_refs,
+            &all_urls,
+            &BTreeMap::new(),
+        )
+        .unwrap();
 
         let meta = load_metadata(&ws_dir).unwrap();
         assert_eq!(meta.dir_name(&identity1).unwrap(), "user-test-repo");
@@ -2837,13 +4020,30 @@ mod tests {
         let ws_dir = dir(&paths.workspaces_dir, "rm-repo-ws");
         assert!(ws_dir.join("test-repo").exists());
         assert!(ws_dir.join("other-repo").exists());
+        filelock::with_metadata(&ws_dir, |meta| {
+            meta.config = Some(crate::template::TemplateConfig {
+                exec_dirs: Some(BTreeMap::from([(identity2.clone(), "src".into())])),
+                ..Default::default()
+            });
+            Ok(())
+        })
+        .unwrap();
 
-        remove_repos(&paths.mirrors_dir, &ws_dir, &[identity2.clone()], false).unwrap();
+        remove_repos(
+            &paths.mirrors_dir,
+            &ws_dir,
+            std::slice::from_ref(&identity2),
+            false,
+            FetchPolicy::Fetch,
+            4,
+        )
+        .unwrap();
 
         let meta = load_metadata(&ws_dir).unwrap();
         assert_eq!(meta.repos.len(), 1);
         assert!(meta.repos.contains_key(&identity1));
         assert!(!meta.repos.contains_key(&identity2));
+        assert_eq!(meta.config.and_then(|c| c.exec_dirs), None);
         assert!(ws_dir.join("test-repo").exists());
         assert!(!ws_dir.join("other-repo").exists());
     }
@@ -2870,6 +4070,8 @@ mod tests {
             &ws_dir,
             &["test.local/nobody/fake".to_string()],
             false,
+            FetchPolicy::Fetch,
+            4,
         );
         assert!(result.is_err());
         assert!(
@@ -2900,11 +4102,70 @@ mod tests {
         let repo_dir = ws_dir.join("test-repo");
         fs::write(repo_dir.join("dirty.txt"), "x").unwrap();
 
-        let result = remove_repos(&paths.mirrors_dir, &ws_dir, &[identity.clone()], false);
+        let result = remove_repos(
+            &paths.mirrors_dir,
+            &ws_dir,
+            std::slice::from_ref(&identity),
+            false,
+            FetchPolicy::Fetch,
+            4,
+        );
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("pending changes"));
     }
 
+    #[test]
+    fn test_remove_repos_blocks_work_off_the_checked_out_branch() {
+        let (paths, _d, _r, identity, upstream_urls) = setup_test_env();
+
+        let refs = BTreeMap::from([(identity.clone(), String::new())]);
+        create(
+            &paths,
+            "rm-repo-away",
+            &refs,
+            None,
+            &upstream_urls,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let ws_dir = dir(&paths.workspaces_dir, "rm-repo-away");
+        let repo_dir = ws_dir.join("test-repo");
+        for (key, value) in [
+            ("user.email", "test@test.com"),
+            ("user.name", "Test"),
+            ("commit.gpgsign", "false"),
+        ] {
+            git::run(Some(&repo_dir), &["config", key, value]).unwrap();
+        }
+        git::run(
+            Some(&repo_dir),
+            &["commit", "--allow-empty", "-q", "-m", "work"],
+        )
+        .unwrap();
+        git::run(
+            Some(&repo_dir),
+            &["checkout", "-q", "-b", "elsewhere", "origin/main"],
+        )
+        .unwrap();
+
+        let err = remove_repos(
+            &paths.mirrors_dir,
+            &ws_dir,
+            std::slice::from_ref(&identity),
+            false,
+            FetchPolicy::NoFetch,
+            4,
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("not on workspace branch"),
+            "{}",
+            err
+        );
+    }
+
     #[test]
     fn test_remove_repos_force_with_pending_changes() {
         let (paths, _d, _r, identity, upstream_urls) = setup_test_env();
@@ -2925,7 +4186,15 @@ mod tests {
         let repo_dir = ws_dir.join("test-repo");
         fs::write(repo_dir.join("dirty.txt"), "x").unwrap();
 
-        remove_repos(&paths.mirrors_dir, &ws_dir, &[identity.clone()], true).unwrap();
+        remove_repos(
+            &paths.mirrors_dir,
+            &ws_dir,
+            std::slice::from_ref(&identity),
+            true,
+            FetchPolicy::Fetch,
+            4,
+        )
+        .unwrap();
 
         let meta = load_metadata(&ws_dir).unwrap();
         assert!(meta.repos.is_empty());
@@ -2964,7 +4233,15 @@ mod tests {
         assert!(ws_dir.join("user-test-repo").exists());
         assert!(ws_dir.join("other-test-repo").exists());
 
-        remove_repos(&paths.mirrors_dir, &ws_dir, &[identity2.clone()], false).unwrap();
+        remove_repos(
+            &paths.mirrors_dir,
+            &ws_dir,
+            std::slice::from_ref(&identity2),
+            false,
+            FetchPolicy::Fetch,
+            4,
+        )
+        .unwrap();
 
         let meta = load_metadata(&ws_dir).unwrap();
         assert_eq!(meta.repos.len(), 1);
@@ -3072,7 +4349,7 @@ mod tests {
         squash_merge_branch(source_repo.path(), "rm-squash", "main");
 
         // Remove should succeed without --force since branch is squash-merged
-        remove(&paths, "rm-squash", false, true).unwrap();
+        remove(&paths, "rm-squash", false, true, FetchPolicy::Fetch).unwrap();
         assert!(!ws_dir.exists());
     }
 
@@ -3088,7 +4365,7 @@ mod tests {
 
         commit_push_and_track(&repo_dir, "rm-pushed", "wip.txt", "wip");
 
-        let result = remove(&paths, "rm-pushed", false, true);
+        let result = remove(&paths, "rm-pushed", false, true, FetchPolicy::Fetch);
         assert!(result.is_err());
         let err = result.unwrap_err().to_string();
         assert!(
@@ -3121,7 +4398,15 @@ mod tests {
         commit_push_and_track(&repo_dir, "rmr-squash", "feat.txt", "feature");
         squash_merge_branch(source_repo.path(), "rmr-squash", "main");
 
-        remove_repos(&paths.mirrors_dir, &ws_dir, &[identity.clone()], false).unwrap();
+        remove_repos(
+            &paths.mirrors_dir,
+            &ws_dir,
+            std::slice::from_ref(&identity),
+            false,
+            FetchPolicy::Fetch,
+            4,
+        )
+        .unwrap();
         let meta = load_metadata(&ws_dir).unwrap();
         assert!(meta.repos.is_empty());
     }
@@ -3147,7 +4432,14 @@ mod tests {
 
         commit_push_and_track(&repo_dir, "rmr-pushed", "wip.txt", "wip");
 
-        let result = remove_repos(&paths.mirrors_dir, &ws_dir, &[identity.clone()], false);
+        let result = remove_repos(
+            &paths.mirrors_dir,
+            &ws_dir,
+            std::slice::from_ref(&identity),
+            false,
+            FetchPolicy::Fetch,
+            4,
+        );
         assert!(result.is_err());
         let err = result.unwrap_err().to_string();
         assert!(
@@ -3209,7 +4501,7 @@ mod tests {
         let parsed = parse_identity(&identity).unwrap();
         let mirror_dir = mirror::dir(&paths.mirrors_dir, &parsed);
 
-        remove(&paths, "rm-no-mirror", false, true).unwrap();
+        remove(&paths, "rm-no-mirror", false, true, FetchPolicy::Fetch).unwrap();
 
         // Mirror should still exist and be intact
         assert!(mirror_dir.exists());
@@ -3248,7 +4540,7 @@ mod tests {
         // Fetch mirror to pick up the new commit
         let parsed = parse_identity(&identity).unwrap();
         let mirror_dir = mirror::dir(&paths.mirrors_dir, &parsed);
-        git::fetch(&mirror_dir, true).unwrap();
+        git::fetch(&mirror_dir, true, None, &|_| {}).unwrap();
 
         // Get the new commit sha from mirror
         let mirror_sha = git::run(Some(&mirror_dir), &["rev-parse", "origin/main"]).unwrap();
@@ -3344,7 +4636,7 @@ mod tests {
             .unwrap();
         assert!(output.status.success());
 
-        git::fetch(&mirror_dir, true).unwrap();
+        git::fetch(&mirror_dir, true, None, &|_| {}).unwrap();
         let meta = load_metadata(&ws_dir).unwrap();
         propagate_mirror_to_clones(&paths.mirrors_dir, &ws_dir, &meta, false);
 
@@ -3368,7 +4660,7 @@ mod tests {
             .unwrap();
         assert!(output.status.success());
 
-        git::fetch(&mirror_dir, true).unwrap();
+        git::fetch(&mirror_dir, true, None, &|_| {}).unwrap();
 
         // Propagate with prune=true — should remove stale origin/feature-x
         propagate_mirror_to_clones(&paths.mirrors_dir, &ws_dir, &meta, true);
@@ -3471,7 +4763,7 @@ mod tests {
         assert!(out.status.success());
 
         // Remove should succeed without --force
-        remove(&paths, "rm-div-squash", false, true).unwrap();
+        remove(&paths, "rm-div-squash", false, true, FetchPolicy::Fetch).unwrap();
         assert!(!ws_dir.exists());
     }
 
@@ -3488,7 +4780,11 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: BTreeMap::new(),
         };
 
         save_metadata(tmp.path(), &meta).unwrap();
@@ -3515,14 +4811,19 @@ mod tests {
     }
 
     #[test]
-    fn test_metadata_unknown_version_loads() {
+    fn test_metadata_future_version_refused() {
         let tmp = tempfile::tempdir().unwrap();
         let yaml = "version: 99\nname: future-ws\nbranch: future-ws\nrepos:\n  github.com/acme/api:\ncreated: '2024-01-01T00:00:00Z'\n";
         fs::write(tmp.path().join(METADATA_FILE), yaml).unwrap();
 
-        let meta = load_metadata(tmp.path()).unwrap();
-        assert_eq!(meta.version, 99);
-        assert_eq!(meta.name, "future-ws");
+        let err = load_metadata(tmp.path()).unwrap_err().to_string();
+        assert!(err.contains("version 99"), "{}", err);
+        assert!(err.contains("upgrade wsp"), "{}", err);
+        // The file is left for the newer wsp.
+        assert_eq!(
+            fs::read_to_string(tmp.path().join(METADATA_FILE)).unwrap(),
+            yaml
+        );
     }
 
     // --- Root content detection tests ---
@@ -3542,10 +4843,15 @@ mod tests {
             last_used: None,
             created_from: None,
             dirs: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            prs: BTreeMap::new(),
+            issue: None,
             config: None,
+            pending: BTreeMap::new(),
         }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_check_root_content() {
         use std::os::unix::fs::symlink;
@@ -3710,6 +5016,135 @@ mod tests {
                 want_clean: false,
                 want_contains: vec!["?? go.work"],
             },
+            Case {
+                name: ".devcontainer with wsp-generated config",
+                setup: Box::new(|ws| {
+                    fs::write(ws.join(METADATA_FILE), "").unwrap();
+                    fs::create_dir_all(ws.join(".devcontainer")).unwrap();
+                    fs::write(
+                        ws.join(".devcontainer/devcontainer.json"),
+                        "// Generated by wsp. DO NOT EDIT.\n{}\n",
+                    )
+                    .unwrap();
+                }),
+                repos: vec![],
+                want_clean: true,
+                want_contains: vec![],
+            },
+            Case {
+                name: ".idea with wsp-generated project and IDE state",
+                setup: Box::new(|ws| {
+                    fs::write(ws.join(METADATA_FILE), "").unwrap();
+                    fs::create_dir_all(ws.join(".idea")).unwrap();
+                    fs::write(
+                        ws.join(".idea/modules.xml"),
+                        "<!-- Generated by wsp. DO NOT EDIT. -->\n<project />\n",
+                    )
+                    .unwrap();
+                    fs::write(ws.join(".idea/workspace.xml"), "<project />").unwrap();
+                }),
+                repos: vec![],
+                want_clean: true,
+                want_contains: vec![],
+            },
+            Case {
+                name: ".envrc with wsp header",
+                setup: Box::new(|ws| {
+                    fs::write(ws.join(METADATA_FILE), "").unwrap();
+                    fs::write(
+                        ws.join(".envrc"),
+                        "# Generated by wsp. DO NOT EDIT.\nexport WSP_WORKSPACE=x\n",
+                    )
+                    .unwrap();
+                }),
+                repos: vec![],
+                want_clean: true,
+                want_contains: vec![],
+            },
+            Case {
+                name: ".envrc without wsp header",
+                setup: Box::new(|ws| {
+                    fs::write(ws.join(METADATA_FILE), "").unwrap();
+                    fs::write(ws.join(".envrc"), "use nix\n").unwrap();
+                }),
+                repos: vec![],
+                want_clean: false,
+                want_contains: vec!["?? .envrc"],
+            },
+            Case {
+                name: ".idea from the IDE",
+                setup: Box::new(|ws| {
+                    fs::write(ws.join(METADATA_FILE), "").unwrap();
+                    fs::create_dir_all(ws.join(".idea")).unwrap();
+                    fs::write(ws.join(".idea/modules.xml"), "<project />").unwrap();
+                }),
+                repos: vec![],
+                want_clean: false,
+                want_contains: vec!["?? .idea/"],
+            },
+            Case {
+                name: ".devcontainer with user files",
+                setup: Box::new(|ws| {
+                    fs::write(ws.join(METADATA_FILE), "").unwrap();
+                    fs::create_dir_all(ws.join(".devcontainer")).unwrap();
+                    fs::write(ws.join(".devcontainer/devcontainer.json"), "{}").unwrap();
+                    fs::write(ws.join(".devcontainer/Dockerfile"), "FROM x").unwrap();
+                }),
+                repos: vec![],
+                want_clean: false,
+                want_contains: vec![
+                    "?? .devcontainer/Dockerfile",
+                    "?? .devcontainer/devcontainer.json",
+                ],
+            },
+            Case {
+                name: "nix files with wsp header and flake.lock",
+                setup: Box::new(|ws| {
+                    fs::write(ws.join(METADATA_FILE), "").unwrap();
+                    for f in ["flake.nix", "shell.nix"] {
+                        fs::write(ws.join(f), "# Generated by wsp. DO NOT EDIT.\n{ }\n").unwrap();
+                    }
+                    fs::write(ws.join("flake.lock"), "{}").unwrap();
+                }),
+                repos: vec![],
+                want_clean: true,
+                want_contains: vec![],
+            },
+            Case {
+                name: "user shell.nix and flake.lock without generated flake",
+                setup: Box::new(|ws| {
+                    fs::write(ws.join(METADATA_FILE), "").unwrap();
+                    fs::write(ws.join("shell.nix"), "{ }").unwrap();
+                    fs::write(ws.join("flake.lock"), "{}").unwrap();
+                }),
+                repos: vec![],
+                want_clean: false,
+                want_contains: vec!["?? shell.nix", "?? flake.lock"],
+            },
+            Case {
+                name: ".env with wsp header",
+                setup: Box::new(|ws| {
+                    fs::write(ws.join(METADATA_FILE), "").unwrap();
+                    fs::write(
+                        ws.join(".env"),
+                        "# Generated by wsp. DO NOT EDIT.\nPORT=8080\n",
+                    )
+                    .unwrap();
+                }),
+                repos: vec![],
+                want_clean: true,
+                want_contains: vec![],
+            },
+            Case {
+                name: ".env without wsp header",
+                setup: Box::new(|ws| {
+                    fs::write(ws.join(METADATA_FILE), "").unwrap();
+                    fs::write(ws.join(".env"), "SECRET=mine\n").unwrap();
+                }),
+                repos: vec![],
+                want_clean: false,
+                want_contains: vec!["?? .env"],
+            },
             Case {
                 name: "go.work.sum alongside wsp go.work",
                 setup: Box::new(|ws| {
@@ -3740,6 +5175,8 @@ mod tests {
                 setup: Box::new(|ws| {
                     fs::write(ws.join(METADATA_FILE), "").unwrap();
                     fs::write(ws.join(".wsp.yaml.lock"), "12345").unwrap();
+                    fs::write(ws.join(".wsp.lock"), "12345").unwrap();
+                    fs::write(ws.join(METADATA_BACKUP_FILE), "").unwrap();
                 }),
                 repos: vec![],
                 want_clean: true,
@@ -4053,7 +5490,7 @@ mod tests {
         let data_dir = tmp.path();
 
         // First call creates the file
-        ensure_global_wspignore(data_dir).unwrap();
+        ensure_global_wspignore(data_dir, data_dir).unwrap();
         let path = data_dir.join("wspignore");
         assert!(path.exists());
         let content = fs::read_to_string(&path).unwrap();
@@ -4062,7 +5499,7 @@ mod tests {
 
         // Second call doesn't overwrite
         fs::write(&path, "custom content").unwrap();
-        ensure_global_wspignore(data_dir).unwrap();
+        ensure_global_wspignore(data_dir, data_dir).unwrap();
         let content2 = fs::read_to_string(&path).unwrap();
         assert_eq!(content2, "custom content");
     }
@@ -4104,7 +5541,7 @@ mod tests {
 
         let meta = make_simple_metadata(&[]);
         let problems = check_root_content(ws_dir, &meta).unwrap();
-        let ignore = load_wspignore(&data_dir, ws_dir);
+        let ignore = load_wspignore(data_dir, data_dir, ws_dir);
         let filtered = filter_ignored(problems, &ignore);
 
         // .claude/settings.json should be filtered out, notes.md should remain
@@ -4129,7 +5566,7 @@ mod tests {
         fs::write(data_tmp.path().join("wspignore"), "# global\n.DS_Store\n").unwrap();
         fs::write(ws_tmp.path().join(".wspignore"), "# local\nnotes.md\n").unwrap();
 
-        let patterns = load_wspignore(data_tmp.path(), ws_tmp.path());
+        let patterns = load_wspignore(data_tmp.path(), data_tmp.path(), ws_tmp.path());
         assert_eq!(patterns.len(), 2);
         assert_eq!(patterns[0], IgnorePattern::Exact(".DS_Store".into()));
         assert_eq!(patterns[1], IgnorePattern::Exact("notes.md".into()));
@@ -4142,10 +5579,38 @@ mod tests {
         // data_dir doesn't exist yet
         assert!(!nested.exists());
 
-        ensure_global_wspignore(&nested).unwrap();
+        ensure_global_wspignore(&nested, tmp.path()).unwrap();
         assert!(nested.join("wspignore").exists());
     }
 
+    #[test]
+    fn test_global_wspignore_moves_to_config_dir() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = tmp.path().join("config");
+        let data = tmp.path().join("data");
+        let ws_tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(&data).unwrap();
+        fs::write(data.join("wspignore"), "notes.md\n").unwrap();
+
+        // Found where older versions kept it until it's moved.
+        assert_eq!(
+            global_wspignore_path(&config, &data),
+            data.join("wspignore")
+        );
+
+        let patterns = load_wspignore(&config, &data, ws_tmp.path());
+        assert_eq!(patterns, vec![IgnorePattern::Exact("notes.md".into())]);
+        assert_eq!(
+            fs::read_to_string(config.join("wspignore")).unwrap(),
+            "notes.md\n"
+        );
+        assert!(!data.join("wspignore").exists());
+        assert_eq!(
+            global_wspignore_path(&config, &data),
+            config.join("wspignore")
+        );
+    }
+
     /// Create a git repo in the given directory with one commit and an origin remote.
     fn create_local_repo(dir: &Path, origin_url: &str) {
         fs::create_dir_all(dir).unwrap();
@@ -4322,7 +5787,14 @@ mod tests {
             identity2.clone(),
             repo2_dir.path().to_str().unwrap().to_string(),
         )]);
-        add_repos(&paths.mirrors_dir, &ws_dir, &refs2, &upstream_urls2).unwrap();
+        add_repos(
+            &paths.mirrors_dir,
+            &ws_dir,
+            &refs2,
+            &upstream_urls2,
+            &BTreeMap::new(),
+        )
+        .unwrap();
 
         // Verify it was registered in metadata
         let meta = load_metadata(&ws_dir).unwrap();
@@ -4394,7 +5866,13 @@ mod tests {
             wrong_upstream.path().to_str().unwrap().to_string(),
         )]);
 
-        let result = add_repos(&paths.mirrors_dir, &ws_dir, &refs2, &upstream_urls2);
+        let result = add_repos(
+            &paths.mirrors_dir,
+            &ws_dir,
+            &refs2,
+            &upstream_urls2,
+            &BTreeMap::new(),
+        );
         assert!(result.is_err(), "should reject identity mismatch");
         let err = result.unwrap_err().to_string();
         assert!(
@@ -4509,6 +5987,137 @@ mod tests {
         assert_eq!(branch, "jganoff/your-feature");
     }
 
+    #[test]
+    fn test_rename_with_branch_template() {
+        let (paths, _d, _r, identity, upstream_urls) = setup_test_env();
+        let refs = BTreeMap::from([(identity.clone(), String::new())]);
+        super::create(
+            &paths,
+            "my-feature",
+            &refs,
+            &Branches {
+                default: "my-feature-2026-03-04".into(),
+                per_repo: BTreeMap::new(),
+            },
+            &upstream_urls,
+            &CreateOpts::default(),
+        )
+        .unwrap();
+
+        let results = rename(&paths, "my-feature", "your-feature").unwrap();
+        assert!(results[0].ok);
+        assert_eq!(results[0].new_branch, "your-feature-2026-03-04");
+
+        let meta = load_metadata(&dir(&paths.workspaces_dir, "your-feature")).unwrap();
+        assert_eq!(meta.branch, "your-feature-2026-03-04");
+    }
+
+    #[test]
+    fn test_create_continue_on_error() {
+        let (paths, _d, _r, identity, upstream_urls) = setup_test_env();
+        // Shares its name with the clone, so both get owner-prefixed dirs.
+        let missing = "test.local/acme/test-repo".to_string();
+        let branches = Branches {
+            default: "my-feature".into(),
+            per_repo: BTreeMap::new(),
+        };
+
+        let only_missing = BTreeMap::from([(missing.clone(), String::new())]);
+        let both = BTreeMap::from([
+            (identity.clone(), String::new()),
+            (missing.clone(), String::new()),
+        ]);
+        assert!(
+            super::create(
+                &paths,
+                "strict",
+                &both,
+                &branches,
+                &upstream_urls,
+                &CreateOpts::default(),
+            )
+            .is_err()
+        );
+        assert!(
+            super::create(
+                &paths,
+                "none",
+                &only_missing,
+                &branches,
+                &upstream_urls,
+                &CreateOpts {
+                    continue_on_error: true,
+                    ..Default::default()
+                },
+            )
+            .is_err()
+        );
+
+        let pending = super::create(
+            &paths,
+            "my-feature",
+            &both,
+            &branches,
+            &upstream_urls,
+            &CreateOpts {
+                continue_on_error: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(pending.keys().collect::<Vec<_>>(), vec![&missing]);
+
+        let ws_dir = dir(&paths.workspaces_dir, "my-feature");
+        let meta = load_metadata(&ws_dir).unwrap();
+        assert_eq!(meta.repos.keys().collect::<Vec<_>>(), vec![&identity]);
+        assert_eq!(meta.pending, pending);
+        assert!(ws_dir.join("user-test-repo").is_dir());
+        assert!(!ws_dir.join("acme-test-repo").exists());
+        assert_eq!(meta.dir_name(&missing).unwrap(), "acme-test-repo");
+    }
+
+    #[test]
+    fn test_create_and_rename_with_repo_branch() {
+        let (paths, _d, _r, identity, upstream_urls) = setup_test_env();
+        let refs = BTreeMap::from([(identity.clone(), String::new())]);
+        let branches = Branches {
+            default: "jg/my-feature".into(),
+            per_repo: BTreeMap::from([(identity.clone(), "feature/my-feature".into())]),
+        };
+        super::create(
+            &paths,
+            "my-feature",
+            &refs,
+            &branches,
+            &upstream_urls,
+            &CreateOpts::default(),
+        )
+        .unwrap();
+
+        let ws_dir = dir(&paths.workspaces_dir, "my-feature");
+        let meta = load_metadata(&ws_dir).unwrap();
+        assert_eq!(meta.branch, "jg/my-feature");
+        assert_eq!(meta.branch_for(&identity), "feature/my-feature");
+        let clone_dir = ws_dir.join("test-repo");
+        assert_eq!(
+            git::branch_current(&clone_dir).unwrap(),
+            "feature/my-feature"
+        );
+
+        let results = rename(&paths, "my-feature", "your-feature").unwrap();
+        assert_eq!(results[0].old_branch, "feature/my-feature");
+        assert_eq!(results[0].new_branch, "feature/your-feature");
+
+        let ws_dir = dir(&paths.workspaces_dir, "your-feature");
+        let meta = load_metadata(&ws_dir).unwrap();
+        assert_eq!(meta.branch, "jg/your-feature");
+        assert_eq!(meta.branch_for(&identity), "feature/your-feature");
+        assert_eq!(
+            git::branch_current(&ws_dir.join("test-repo")).unwrap(),
+            "feature/your-feature"
+        );
+    }
+
     #[test]
     fn test_rename_target_exists() {
         let (paths, _d, _r, identity, upstream_urls) = setup_test_env();
@@ -4518,6 +6127,7 @@ mod tests {
 
         let err = rename(&paths, "ws-a", "ws-b").unwrap_err();
         assert!(err.to_string().contains("already exists"));
+        assert_eq!(error::classify(&err), ErrorKind::Conflict);
     }
 
     #[test]
@@ -4525,5 +6135,6 @@ mod tests {
         let (paths, _d, _r, _identity, _upstream_urls) = setup_test_env();
         let err = rename(&paths, "nonexistent", "new-name").unwrap_err();
         assert!(err.to_string().contains("does not exist"));
+        assert_eq!(error::classify(&err), ErrorKind::NotFound);
     }
 }